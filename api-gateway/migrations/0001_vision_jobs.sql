@@ -0,0 +1,9 @@
+CREATE TABLE vision_jobs (
+    id UUID PRIMARY KEY,
+    user_id UUID,
+    crop_type TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'queued',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX idx_vision_jobs_created_at ON vision_jobs (created_at DESC, id DESC);
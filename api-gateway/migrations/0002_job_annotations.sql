@@ -0,0 +1,8 @@
+CREATE TABLE job_annotations (
+    job_id UUID NOT NULL,
+    version INT NOT NULL,
+    boxes JSONB NOT NULL,
+    reviewer_id UUID NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (job_id, version)
+);
@@ -0,0 +1,6 @@
+CREATE TABLE user_preferences (
+    user_id UUID PRIMARY KEY,
+    schema_version INT NOT NULL,
+    doc JSONB NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
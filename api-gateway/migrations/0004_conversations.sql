@@ -0,0 +1,21 @@
+-- Durable chat history. Redis previously held conversations in lists, which
+-- a gateway restart (or Redis eviction) silently emptied.
+CREATE TABLE conversations (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX idx_conversations_user ON conversations (user_id, created_at DESC);
+
+CREATE TABLE messages (
+    id UUID PRIMARY KEY,
+    conversation_id UUID NOT NULL REFERENCES conversations (id) ON DELETE CASCADE,
+    role TEXT NOT NULL,
+    content TEXT NOT NULL,
+    image_url TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+-- Keyset pagination walks (created_at, id) descending.
+CREATE INDEX idx_messages_conversation ON messages (conversation_id, created_at DESC, id DESC);
@@ -0,0 +1,20 @@
+-- User-scoped case tags for extension officers ("follow-up needed",
+-- "severe-outbreak", free text). Taggings attach a tag to a conversation or
+-- a vision job; the composite key makes attach naturally idempotent.
+CREATE TABLE tags (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL,
+    name TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    UNIQUE (user_id, name)
+);
+
+CREATE TABLE taggings (
+    tag_id UUID NOT NULL REFERENCES tags (id) ON DELETE CASCADE,
+    target_type TEXT NOT NULL CHECK (target_type IN ('conversation', 'job')),
+    target_id UUID NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (tag_id, target_type, target_id)
+);
+
+CREATE INDEX idx_taggings_target ON taggings (target_type, target_id);
@@ -0,0 +1,4 @@
+-- Soft delete for vision jobs: bulk delete from the history view hides jobs
+-- but keeps the rows so an undo within the client's toast window (and audit
+-- questions later) can restore them.
+ALTER TABLE vision_jobs ADD COLUMN deleted_at TIMESTAMPTZ;
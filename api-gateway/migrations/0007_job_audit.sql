@@ -0,0 +1,11 @@
+-- Audit trail for destructive bulk job actions: who soft-deleted or
+-- restored which job, and when.
+CREATE TABLE job_audit (
+    id UUID PRIMARY KEY,
+    job_id UUID NOT NULL,
+    user_id UUID NOT NULL,
+    action TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX idx_job_audit_job ON job_audit (job_id, created_at);
@@ -0,0 +1,9 @@
+-- Accounts for password login. Roles are plain text labels checked by the
+-- gateway ('farmer' by default; 'officer' and 'admin' assigned manually).
+CREATE TABLE users (
+    id UUID PRIMARY KEY,
+    email TEXT NOT NULL UNIQUE,
+    password_hash TEXT NOT NULL,
+    roles TEXT[] NOT NULL DEFAULT '{farmer}',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
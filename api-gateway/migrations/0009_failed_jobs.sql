@@ -0,0 +1,11 @@
+-- Jobs that exhausted their retry budget, drained off the parking queue by
+-- the gateway's DLQ consumer. Persisting them makes failed jobs queryable
+-- and keeps them across broker restarts.
+CREATE TABLE failed_jobs (
+    job_id UUID PRIMARY KEY,
+    payload JSONB NOT NULL,
+    retry_count INT NOT NULL DEFAULT 0,
+    parked_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX idx_failed_jobs_parked_at ON failed_jobs (parked_at DESC);
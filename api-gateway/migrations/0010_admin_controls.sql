@@ -0,0 +1,14 @@
+-- Admin API support: accounts can now be disabled without being deleted,
+-- and every admin action is written to its own audit table.
+ALTER TABLE users ADD COLUMN disabled BOOLEAN NOT NULL DEFAULT false;
+
+CREATE TABLE admin_audit_log (
+    id UUID PRIMARY KEY,
+    admin_id UUID NOT NULL,
+    action TEXT NOT NULL,
+    target TEXT,
+    detail JSONB,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX idx_admin_audit_log_created ON admin_audit_log (created_at DESC);
@@ -0,0 +1,13 @@
+-- Webhook subscriptions: callbacks POSTed by the dispatcher when a vision
+-- job reaches a terminal state. The secret signs each delivery so the
+-- receiver can verify the payload came from us.
+CREATE TABLE webhooks (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL,
+    url TEXT NOT NULL,
+    events TEXT[] NOT NULL,
+    secret TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX idx_webhooks_user ON webhooks (user_id);
@@ -0,0 +1,22 @@
+-- Searchable summaries of finished diagnoses: one row per completed job,
+-- combining the detections with the generated advice so farmers can
+-- full-text search their history ("all rice blast cases from last month").
+-- The 'simple' configuration is deliberate: disease names are English but
+-- advice is Thai, and language-specific stemmers would mangle one or the
+-- other.
+CREATE TABLE diagnoses (
+    id UUID PRIMARY KEY,
+    job_id UUID NOT NULL UNIQUE,
+    user_id UUID NOT NULL,
+    crop_type TEXT NOT NULL,
+    disease_name TEXT NOT NULL,
+    advice TEXT NOT NULL,
+    severity_score REAL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    search_vector TSVECTOR GENERATED ALWAYS AS (
+        to_tsvector('simple', disease_name || ' ' || advice || ' ' || crop_type)
+    ) STORED
+);
+
+CREATE INDEX idx_diagnoses_search ON diagnoses USING GIN (search_vector);
+CREATE INDEX idx_diagnoses_user ON diagnoses (user_id, created_at DESC);
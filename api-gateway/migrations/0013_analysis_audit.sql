@@ -0,0 +1,22 @@
+-- Structured audit of every issued diagnosis, written by the gateway when
+-- a vision job completes. Extension officers review what was told to which
+-- farmer; the farmer-facing `diagnoses` table serves search and stays
+-- scoped to its owner, so the officer view gets its own table with the
+-- request id for cross-referencing logs.
+CREATE TABLE analysis_audit (
+    id UUID PRIMARY KEY,
+    job_id UUID NOT NULL UNIQUE,
+    user_id UUID,
+    request_id TEXT,
+    crop_type TEXT NOT NULL,
+    disease_name TEXT NOT NULL,
+    confidence REAL,
+    severity_score REAL,
+    requested_at TIMESTAMPTZ,
+    completed_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+-- Farmers read their own history newest-first; officers slice by crop and
+-- time window across all users.
+CREATE INDEX idx_analysis_audit_user ON analysis_audit (user_id, completed_at DESC);
+CREATE INDEX idx_analysis_audit_crop ON analysis_audit (crop_type, completed_at DESC);
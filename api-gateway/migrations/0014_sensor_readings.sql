@@ -0,0 +1,17 @@
+-- Field sensor readings (soil moisture, temperature, humidity) so disease
+-- outbreaks can be correlated with growing conditions. Devices buffer
+-- offline and upload in bursts; recorded_at is the sensor's clock,
+-- received_at is ours.
+CREATE TABLE sensor_readings (
+    id UUID PRIMARY KEY,
+    device_id TEXT NOT NULL,
+    crop_type TEXT NOT NULL,
+    temperature_c REAL NOT NULL,
+    soil_moisture_pct REAL NOT NULL,
+    humidity_pct REAL NOT NULL,
+    recorded_at TIMESTAMPTZ NOT NULL,
+    received_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+-- Every query is "this device, this time window, newest first".
+CREATE INDEX idx_sensor_readings_device ON sensor_readings (device_id, recorded_at DESC);
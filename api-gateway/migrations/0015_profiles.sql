@@ -0,0 +1,13 @@
+-- Farmer profiles: who the user is and what they farm, used to pre-fill
+-- forms and (eventually) give advice generation real context. One row per
+-- user; PUT /api/v1/profile upserts.
+CREATE TABLE profiles (
+    user_id UUID PRIMARY KEY,
+    name TEXT NOT NULL,
+    province TEXT,
+    farm_size_rai DOUBLE PRECISION,
+    primary_crops TEXT[] NOT NULL DEFAULT '{}',
+    phone_number TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
@@ -0,0 +1,335 @@
+//! Application assembly: router, state, and background tasks.
+//!
+//! Lives in the library (rather than `main.rs`) so the end-to-end suite can
+//! boot the same wiring in-process against containerized backends. `main`
+//! stays responsible for process-level concerns only: config, logging,
+//! signals, and the listener.
+
+use std::sync::Arc;
+
+use axum::{
+    routing::{get, post},
+    Router,
+};
+use metrics_exporter_prometheus::PrometheusHandle;
+use sqlx::postgres::PgPoolOptions;
+use tower_http::cors::CorsLayer;
+
+use crate::services::{
+    file_storage::FileStorageService, rabbitmq::RabbitMQService, registry::ServiceRegistry,
+    scheduler::BackgroundScheduler,
+};
+use crate::{config::AppConfig, handlers, logging::LogBroadcaster, state::AppState};
+
+/// Connect everything the handlers need and run migrations. The metrics
+/// recorder and the log broadcaster are passed in because both are
+/// process-global: tests install one recorder and share it across states.
+pub async fn build_state(
+    config: Arc<AppConfig>,
+    log_broadcaster: LogBroadcaster,
+    metrics: PrometheusHandle,
+) -> anyhow::Result<AppState> {
+    let db = PgPoolOptions::new()
+        .max_connections(config.database.max_connections)
+        .connect(&config.database.url)
+        .await?;
+    sqlx::migrate!("./migrations").run(&db).await?;
+
+    let redis_client = redis::Client::open(config.redis.url.as_str())?;
+    let rabbitmq = Arc::new(RabbitMQService::new(config.rabbitmq.clone()).await?);
+    let feature_flags = Arc::new(crate::services::feature_flags::FeatureFlagService::new(
+        redis_client.clone(),
+    ));
+    let quotas = Arc::new(crate::services::quota::QuotaService::new(
+        redis_client.clone(),
+        config.quotas.clone(),
+    ));
+    let services = Arc::new(ServiceRegistry::new(
+        &config.external_apis,
+        feature_flags.clone(),
+    )?);
+    let file_storage = Arc::new(FileStorageService::new(config.file_storage.clone()));
+    let alerts = Arc::new(crate::services::alerts::AlertSink::new(config.alerts.clone()));
+    let cache = Arc::new(crate::services::cache::CacheService::new(
+        redis_client.clone(),
+        &config.cache,
+    ));
+    let conversations = Arc::new(crate::services::conversations::ConversationRepository::new(
+        db.clone(),
+    ));
+
+    Ok(AppState {
+        config: config.clone(),
+        db,
+        redis_client,
+        rabbitmq,
+        services,
+        file_storage,
+        versions: Arc::new(std::sync::RwLock::new(config.version.clone())),
+        client_version_counts: Arc::new(std::sync::Mutex::new(Default::default())),
+        counters: Arc::new(std::sync::Mutex::new(Default::default())),
+        log_broadcaster,
+        alerts,
+        metrics,
+        last_correlation_id: Arc::new(std::sync::Mutex::new(None)),
+        shadow: Arc::new(crate::middleware::shadow::ShadowMirror::new(
+            config.shadow.routes.clone(),
+        )),
+        shutting_down: Arc::new(std::sync::atomic::AtomicBool::new(false)),
+        conversations,
+        cache,
+        feature_flags,
+        quotas,
+    })
+}
+
+/// How often the stale-cache sweep runs. Staleness only appears on a model
+/// upgrade, so hourly is plenty and keeps the SCAN load negligible.
+const CACHE_PRUNE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+
+/// Response-cache TTLs for the polled GET routes. Job status transitions
+/// in the queue worker, outside this process, so its TTL is the staleness
+/// bound and must stay at a couple of seconds; chat history only changes
+/// through this gateway, which invalidates eagerly, so its TTL is just a
+/// safety net.
+const JOB_STATUS_CACHE_TTL: std::time::Duration = std::time::Duration::from_secs(2);
+const CHAT_HISTORY_CACHE_TTL: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Start the long-running loops that ride alongside the HTTP server:
+/// cleanup sweeps, cache pruning, advice generation, webhook dispatch,
+/// gauge polling, and the DLQ consumer. The returned scheduler owns the
+/// periodic sweeps; dropping it (or calling `shutdown`) stops them, so
+/// the caller must keep it for the life of the process.
+pub fn spawn_background_tasks(state: &AppState) -> BackgroundScheduler {
+    let scheduler = BackgroundScheduler::new();
+    let cleanup_state = state.clone();
+    scheduler.start(
+        "file_cleanup",
+        state.config.file_storage.cleanup_interval,
+        move || {
+            let state = cleanup_state.clone();
+            async move { crate::services::cleanup::run_once(&state).await }
+        },
+    );
+    let prune_state = state.clone();
+    scheduler.start("cache_prune", CACHE_PRUNE_INTERVAL, move || {
+        let state = prune_state.clone();
+        async move { crate::services::cleanup::prune_stale_cache(&state).await }
+    });
+    crate::services::advice::spawn(state.clone());
+    crate::services::webhooks::spawn(state.clone());
+    crate::metrics::spawn_gauge_poller(state.clone());
+    state.rabbitmq.clone().start_dlq_consumer(state.db.clone());
+    scheduler
+}
+
+pub fn create_router(state: AppState, cors: CorsLayer) -> Router {
+    // Image-carrying routes get the larger `max_upload_size` body limit;
+    // everything else is capped at `max_body_size` below.
+    let upload_routes = Router::new()
+        .route("/api/v1/vision/analyze", post(handlers::vision::queue_vision_analysis))
+        .route(
+            "/api/v1/vision/analyze/upload",
+            post(handlers::vision::queue_vision_upload),
+        )
+        .route(
+            "/api/v1/vision/analyze/batch",
+            post(handlers::vision::queue_batch_analysis),
+        )
+        .route("/api/v1/vision/upload/init", post(handlers::uploads::init_upload))
+        .route(
+            "/api/v1/vision/upload/:upload_id/chunk",
+            post(handlers::uploads::upload_chunk),
+        )
+        .route(
+            "/api/v1/vision/upload/:upload_id/complete",
+            post(handlers::uploads::complete_upload),
+        )
+        .layer(crate::middleware::limits::upload_body_limit(&state.config.server));
+
+    let router = Router::new()
+        .route("/health", get(handlers::health_check))
+        .route("/health/ready", get(handlers::readiness_check))
+        .route("/health/metrics", get(handlers::metrics))
+        .route("/health/metrics.json", get(handlers::health::metrics_json))
+        .route("/health/queues", get(handlers::health::queue_metrics))
+        .route("/api/v1/version", get(handlers::version::get_version))
+        .route("/api/v1/usage", get(handlers::usage::get_usage))
+        .route("/api/v1/openapi.json", get(crate::docs::openapi_json))
+        .route("/api/v1/docs", get(crate::docs::docs_redirect))
+        .route("/api/v1/admin/logs/stream", get(handlers::admin_logs::stream_logs))
+        .route(
+            "/api/v1/admin/shadow/summary",
+            get(handlers::admin_shadow::shadow_summary),
+        )
+        .route("/api/v1/auth/register", post(handlers::auth::register))
+        .route("/api/v1/auth/login", post(handlers::auth::login))
+        .route("/api/v1/auth/refresh", post(handlers::auth::refresh))
+        .route("/api/v1/webhooks/line", post(handlers::line_webhook::line_webhook))
+        .route("/api/v1/webhooks", post(handlers::webhooks::register_webhook))
+        .route(
+            "/api/v1/webhooks/:webhook_id",
+            axum::routing::delete(handlers::webhooks::delete_webhook),
+        )
+        .route("/api/v1/sensors/readings", post(handlers::sensors::submit_reading))
+        .route(
+            "/api/v1/sensors/:device_id/readings",
+            get(handlers::sensors::get_readings),
+        )
+        .route("/api/v1/dashboard/trends", get(handlers::dashboard::get_trends))
+        .route("/api/v1/diagnoses/search", get(handlers::search::search))
+        .route(
+            "/api/v1/profile",
+            get(handlers::profile::get_profile)
+                .put(handlers::profile::put_profile),
+        )
+        .route(
+            "/api/v1/profile/preferences",
+            get(handlers::preferences::get_preferences)
+                .patch(handlers::preferences::patch_preferences),
+        )
+        .route("/api/v1/chat", post(handlers::chat::send_message))
+        .route("/api/v1/chat/ws", get(handlers::chat::chat_ws))
+        .route("/api/v1/chat/stream", post(handlers::chat::stream_message))
+        .route(
+            "/api/v1/chat/history",
+            // Conditional GET: history rarely changes between page loads.
+            // The response cache sits inside the ETag layer so cached
+            // bodies still revalidate to 304s.
+            get(handlers::chat::get_conversation)
+                .layer(axum::middleware::from_fn_with_state(
+                    crate::middleware::response_cache::RouteCache {
+                        state: state.clone(),
+                        ttl: CHAT_HISTORY_CACHE_TTL,
+                    },
+                    crate::middleware::response_cache::response_cache_middleware,
+                ))
+                .layer(axum::middleware::from_fn(crate::middleware::etag::etag_middleware)),
+        )
+        .route(
+            "/api/v1/chat/:conversation_id",
+            axum::routing::delete(handlers::chat::delete_conversation),
+        )
+        .route(
+            "/api/v1/chat/:conversation_id/context",
+            axum::routing::put(handlers::chat::set_crop_context),
+        )
+        .route(
+            "/api/v1/tags",
+            get(handlers::tags::list_tags).post(handlers::tags::create_tag),
+        )
+        .route(
+            "/api/v1/tags/:tag_id/:target_type/:target_id",
+            axum::routing::put(handlers::tags::attach_tag)
+                .delete(handlers::tags::detach_tag),
+        )
+        .route(
+            "/api/v1/vision/batches/:batch_id",
+            get(handlers::vision::get_batch_status),
+        )
+        .route(
+            "/api/v1/vision/files/stats",
+            get(handlers::vision::get_file_stats),
+        )
+        .route("/api/v1/vision/history", get(handlers::history::get_history))
+        .route("/api/v1/vision/jobs", get(handlers::vision::list_jobs))
+        .route(
+            "/api/v1/vision/jobs/batch/tag",
+            post(handlers::vision::batch_tag_jobs),
+        )
+        .route(
+            "/api/v1/vision/jobs/batch/delete",
+            post(handlers::vision::batch_delete_jobs),
+        )
+        .route(
+            "/api/v1/vision/jobs/batch/restore",
+            post(handlers::vision::batch_restore_jobs),
+        )
+        .route(
+            "/api/v1/vision/jobs/failed",
+            get(handlers::vision::list_failed_jobs),
+        )
+        .route(
+            "/api/v1/vision/jobs/failed/:job_id/retry",
+            post(handlers::vision::retry_failed_job),
+        )
+        .route(
+            "/api/v1/vision/jobs/:job_id",
+            get(handlers::vision::get_job_status)
+                .layer(axum::middleware::from_fn_with_state(
+                    crate::middleware::response_cache::RouteCache {
+                        state: state.clone(),
+                        ttl: JOB_STATUS_CACHE_TTL,
+                    },
+                    crate::middleware::response_cache::response_cache_middleware,
+                ))
+                .layer(axum::middleware::from_fn(crate::middleware::etag::etag_middleware)),
+        )
+        .route(
+            "/api/v1/vision/jobs/:job_id/stream",
+            get(handlers::vision::stream_job_status),
+        )
+        .route(
+            "/api/v1/vision/jobs/:job_id/ws",
+            get(handlers::vision::job_status_ws),
+        )
+        .route(
+            "/api/v1/vision/jobs/:job_id/advice/retry",
+            post(handlers::vision::retry_job_advice),
+        )
+        .route(
+            "/api/v1/vision/jobs/:job_id/cancel",
+            post(handlers::vision::cancel_job),
+        )
+        .route(
+            "/api/v1/vision/jobs/:job_id/annotations",
+            get(handlers::annotations::get_annotations).put(handlers::annotations::save_annotations),
+        );
+
+    // The admin surface only exists when explicitly enabled; disabled means
+    // 404, not 403, so probes can't even confirm the routes are there.
+    let router = if state.config.admin.enabled {
+        router.nest("/api/admin/v1", handlers::admin::router())
+    } else {
+        router
+    };
+
+    router
+        .layer(crate::middleware::limits::default_body_limit(&state.config.server))
+        .merge(upload_routes)
+        .merge(utoipa_swagger_ui::SwaggerUi::new("/docs").url(
+            "/openapi.json",
+            <crate::docs::ApiDoc as utoipa::OpenApi>::openapi(),
+        ))
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            crate::middleware::shadow::shadow_middleware,
+        ))
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            crate::middleware::rate_limit::rate_limit_middleware,
+        ))
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            crate::middleware::auth::auth_middleware,
+        ))
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            crate::middleware::client_version::enforce_client_version,
+        ))
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            crate::middleware::correlation::correlation_id_middleware,
+        ))
+        .layer(axum::middleware::from_fn(crate::metrics::track_http_metrics))
+        .layer(axum::middleware::from_fn(crate::telemetry::trace_http))
+        // Timeout sits outside the handlers; the envelope rewriter sits
+        // outside the timeout so its 408 (and the body limits' 413) leave
+        // in the standard error shape.
+        .layer(crate::middleware::limits::timeout(&state.config.server))
+        .layer(axum::middleware::from_fn(
+            crate::middleware::limits::envelope_rejections,
+        ))
+        .layer(cors)
+        .with_state(state)
+}
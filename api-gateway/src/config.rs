@@ -0,0 +1,688 @@
+//! Environment-driven configuration.
+
+use std::time::Duration;
+
+use serde::Deserialize;
+
+#[derive(Debug, thiserror::Error)]
+#[error("configuration error: {0}")]
+pub struct ConfigError(pub String);
+
+/// Top-level gateway configuration, loaded once at startup.
+#[derive(Debug, Clone, Deserialize)]
+pub struct AppConfig {
+    pub server: ServerConfig,
+    pub database: DatabaseConfig,
+    pub redis: RedisConfig,
+    pub rabbitmq: RabbitMQConfig,
+    pub file_storage: FileStorageConfig,
+    pub external_apis: ExternalApisConfig,
+    #[serde(default)]
+    pub version: VersionConfig,
+    #[serde(default)]
+    pub rate_limit: RateLimitConfig,
+    #[serde(default)]
+    pub quotas: QuotaConfig,
+    #[serde(default)]
+    pub alerts: AlertsConfig,
+    #[serde(default)]
+    pub line_webhook: LineWebhookConfig,
+    #[serde(default)]
+    pub vision_cache: VisionCacheConfig,
+    #[serde(default)]
+    pub diagnosis: DiagnosisConfig,
+    #[serde(default)]
+    pub shadow: ShadowConfig,
+    #[serde(default)]
+    pub cache: CacheConfig,
+    #[serde(default)]
+    pub cors: CorsConfig,
+    #[serde(default)]
+    pub admin: AdminConfig,
+    #[serde(default)]
+    pub log: LoggingConfig,
+    #[serde(default)]
+    pub tracing: TracingConfig,
+    pub jwt_secret: String,
+}
+
+/// Distributed tracing export (see `telemetry::init`). Off by default so a
+/// laptop run doesn't spend its startup retrying a collector that isn't
+/// there; deployments point `otlp_endpoint` at their Jaeger/Tempo agent.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TracingConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// OTLP/gRPC collector endpoint.
+    #[serde(default = "default_otlp_endpoint")]
+    pub otlp_endpoint: String,
+    /// Head-sampling ratio in `0.0..=1.0`; out-of-range values are clamped.
+    #[serde(default = "default_sample_rate")]
+    pub sample_rate: f64,
+}
+
+impl Default for TracingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            otlp_endpoint: default_otlp_endpoint(),
+            sample_rate: default_sample_rate(),
+        }
+    }
+}
+
+fn default_otlp_endpoint() -> String {
+    "http://localhost:4317".to_string()
+}
+
+fn default_sample_rate() -> f64 {
+    1.0
+}
+
+/// Log pipeline shape (see `logging::init`). The default — human-readable
+/// text on stdout — is what you want on a laptop; deployments behind
+/// Loki/Elasticsearch switch `format` to `json` and optionally `output` to
+/// a daily-rolling file.
+#[derive(Debug, Clone, Deserialize)]
+pub struct LoggingConfig {
+    #[serde(default)]
+    pub format: LogFormat,
+    #[serde(default)]
+    pub output: LogOutput,
+    /// Per-module level overrides applied on top of `RUST_LOG`, e.g.
+    /// `{"sqlx": "warn", "api_gateway::handlers": "debug"}`.
+    #[serde(default)]
+    pub filters: std::collections::BTreeMap<String, String>,
+    /// Directory for rolling log files when `output` is `file`.
+    #[serde(default = "default_log_directory")]
+    pub directory: String,
+    /// File name prefix; files roll daily as `<prefix>.YYYY-MM-DD`.
+    #[serde(default = "default_log_file_prefix")]
+    pub file_prefix: String,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            format: LogFormat::default(),
+            output: LogOutput::default(),
+            filters: Default::default(),
+            directory: default_log_directory(),
+            file_prefix: default_log_file_prefix(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogOutput {
+    #[default]
+    Stdout,
+    File,
+}
+
+fn default_log_directory() -> String {
+    "./logs".to_string()
+}
+
+fn default_log_file_prefix() -> String {
+    "api-gateway.log".to_string()
+}
+
+/// Gate for the `/api/admin/v1` sub-router (see `handlers::admin`). Off by
+/// default: most deployments never need destructive controls over HTTP, and
+/// the routes simply don't exist unless someone opts in.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AdminConfig {
+    #[serde(default)]
+    pub enabled: bool,
+}
+
+/// Browser cross-origin policy (see `middleware::cors`). The default is the
+/// hackathon-era wildcard; production deployments list their frontend
+/// origins and usually enable credentials.
+#[derive(Debug, Clone, Deserialize)]
+pub struct CorsConfig {
+    /// Exact origins allowed to call the API from a browser; a literal `*`
+    /// allows any origin but is rejected when credentials are enabled.
+    #[serde(default = "default_cors_origins")]
+    pub allowed_origins: Vec<String>,
+    #[serde(default = "default_cors_methods")]
+    pub allowed_methods: Vec<String>,
+    #[serde(default)]
+    pub allow_credentials: bool,
+    /// How long browsers may cache a preflight response.
+    #[serde(with = "humantime_serde", default = "default_cors_max_age")]
+    pub max_age: Duration,
+}
+
+impl Default for CorsConfig {
+    fn default() -> Self {
+        Self {
+            allowed_origins: default_cors_origins(),
+            allowed_methods: default_cors_methods(),
+            allow_credentials: false,
+            max_age: default_cors_max_age(),
+        }
+    }
+}
+
+fn default_cors_origins() -> Vec<String> {
+    vec!["*".into()]
+}
+
+fn default_cors_methods() -> Vec<String> {
+    ["GET", "POST", "PUT", "PATCH", "DELETE", "OPTIONS"]
+        .iter()
+        .map(|m| m.to_string())
+        .collect()
+}
+
+fn default_cors_max_age() -> Duration {
+    Duration::from_secs(3600)
+}
+
+/// Redis-backed query cache for read-heavy endpoints (see `services::cache`).
+#[derive(Debug, Clone, Deserialize)]
+pub struct CacheConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default = "default_cache_ttl_secs")]
+    pub default_ttl_secs: u64,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_true(),
+            default_ttl_secs: default_cache_ttl_secs(),
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_cache_ttl_secs() -> u64 {
+    60
+}
+
+/// Request shadowing: mirror a sample of production traffic to a staging
+/// deployment and record how its responses compare. Off unless routes are
+/// configured.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ShadowConfig {
+    #[serde(default)]
+    pub routes: Vec<ShadowRoute>,
+}
+
+/// One shadowed route. Matched by path prefix against the request path.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ShadowRoute {
+    /// Prefix of the production path to mirror, e.g. `/api/v1/vision/analyze`.
+    pub path_prefix: String,
+    /// Base URL of the staging deployment the copy is sent to.
+    pub target_base_url: String,
+    /// Percentage of matching requests to mirror, 0–100.
+    pub sample_percent: f64,
+    /// Forward the request body too. Off by default: shadowed metadata is
+    /// enough for contract comparison, and bodies carry farmer images.
+    #[serde(default)]
+    pub include_body: bool,
+}
+
+/// Content-addressed caching of completed vision results, keyed by image
+/// hash and crop type.
+#[derive(Debug, Clone, Deserialize)]
+pub struct VisionCacheConfig {
+    #[serde(with = "humantime_serde", default = "default_vision_cache_ttl")]
+    pub ttl: Duration,
+}
+
+impl Default for VisionCacheConfig {
+    fn default() -> Self {
+        Self { ttl: default_vision_cache_ttl() }
+    }
+}
+
+fn default_vision_cache_ttl() -> Duration {
+    Duration::from_secs(24 * 60 * 60)
+}
+
+/// How vision results are presented to the caller.
+#[derive(Debug, Clone, Deserialize)]
+pub struct DiagnosisConfig {
+    /// Top-detection confidence below which a result is presented as
+    /// uncertain — candidate diseases plus retake guidance instead of a
+    /// firm diagnosis — and the treatment-advice LLM call is skipped.
+    #[serde(default = "default_confidence_threshold")]
+    pub confidence_threshold: f32,
+}
+
+impl Default for DiagnosisConfig {
+    fn default() -> Self {
+        Self { confidence_threshold: default_confidence_threshold() }
+    }
+}
+
+/// Aligned with the shared `Low` confidence band so the gateway's cutoff
+/// and the banding copy shown next to it can't drift apart.
+fn default_confidence_threshold() -> f32 {
+    shared::confidence::LOW_BELOW
+}
+
+/// Ops webhook alerts (see `services::alerts`). Disabled unless a webhook
+/// URL is configured.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AlertsConfig {
+    pub webhook_url: Option<String>,
+    #[serde(default)]
+    pub style: crate::services::alerts::AlertStyle,
+    #[serde(default)]
+    pub min_severity: crate::services::alerts::Severity,
+    /// Window inside which repeated alerts of the same event type are
+    /// dropped.
+    #[serde(default = "default_suppress_seconds")]
+    pub suppress_seconds: u64,
+}
+
+fn default_suppress_seconds() -> u64 {
+    300
+}
+
+/// Client-version negotiation, served by `GET /api/v1/version` and enforced
+/// by the client-version middleware. Reloadable at runtime via
+/// `AppState::versions`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct VersionConfig {
+    #[serde(default = "default_api_version")]
+    pub api_version: String,
+    /// Bundles below this get the reload banner.
+    #[serde(default = "default_zero_version")]
+    pub min_frontend_version: String,
+    #[serde(default = "default_zero_version")]
+    pub latest_frontend_version: String,
+    /// Bundles below this are rejected on mutating endpoints with 426.
+    #[serde(default = "default_zero_version")]
+    pub incompatible_below: String,
+}
+
+impl Default for VersionConfig {
+    fn default() -> Self {
+        Self {
+            api_version: default_api_version(),
+            min_frontend_version: default_zero_version(),
+            latest_frontend_version: default_zero_version(),
+            incompatible_below: default_zero_version(),
+        }
+    }
+}
+
+/// Sliding-window rate limiting (see `middleware::rate_limit`), with
+/// separate budgets per route group: vision analyses are far more expensive
+/// than reads, so they get a much tighter limit.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RateLimitConfig {
+    #[serde(default = "default_limit")]
+    pub default: WindowLimit,
+    #[serde(default = "default_vision_limit")]
+    pub vision: WindowLimit,
+    #[serde(default = "default_chat_limit")]
+    pub chat: WindowLimit,
+    /// Batched job mutations; each call can touch up to 100 rows.
+    #[serde(default = "default_bulk_limit")]
+    pub bulk: WindowLimit,
+}
+
+/// One sliding-window budget: `requests_per_window` requests per
+/// `window_seconds`-second window.
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct WindowLimit {
+    pub requests_per_window: u64,
+    pub window_seconds: u64,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            default: default_limit(),
+            vision: default_vision_limit(),
+            chat: default_chat_limit(),
+            bulk: default_bulk_limit(),
+        }
+    }
+}
+
+fn default_limit() -> WindowLimit {
+    WindowLimit { requests_per_window: 60, window_seconds: 60 }
+}
+
+fn default_vision_limit() -> WindowLimit {
+    WindowLimit { requests_per_window: 10, window_seconds: 60 }
+}
+
+fn default_chat_limit() -> WindowLimit {
+    WindowLimit { requests_per_window: 60, window_seconds: 60 }
+}
+
+fn default_bulk_limit() -> WindowLimit {
+    WindowLimit { requests_per_window: 6, window_seconds: 60 }
+}
+
+/// Monthly usage quotas (see `services::quota`). Distinct from rate
+/// limiting: windows are calendar months and the budgets are a pricing
+/// decision, not a protection one. Off by default; the pilot config lists
+/// the free-tier roles. Roles not listed are unlimited, and a user with
+/// several roles is capped by the most generous listed one.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct QuotaConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Role name → monthly allowance.
+    #[serde(default)]
+    pub monthly: std::collections::HashMap<String, MonthlyQuota>,
+}
+
+/// One role's allowance per calendar month.
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct MonthlyQuota {
+    pub chat_messages: u64,
+    pub vision_analyses: u64,
+}
+
+fn default_api_version() -> String {
+    "v1".to_string()
+}
+
+fn default_zero_version() -> String {
+    "0.0.0".to_string()
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ServerConfig {
+    pub host: String,
+    pub port: u16,
+    /// Overall per-request timeout.
+    #[serde(with = "humantime_serde", default = "default_request_timeout")]
+    pub request_timeout: Duration,
+    /// How long shutdown waits for in-flight requests before dropping the
+    /// remaining connections. `shutdown_timeout` is accepted as an alias
+    /// since some deployment templates use that name.
+    #[serde(
+        with = "humantime_serde",
+        default = "default_drain_timeout",
+        alias = "shutdown_timeout"
+    )]
+    pub drain_timeout: Duration,
+    /// Maximum accepted request body size in bytes on the vision upload
+    /// routes (image payloads).
+    #[serde(default = "default_max_upload_size")]
+    pub max_upload_size: usize,
+    /// Maximum accepted request body size in bytes everywhere else; JSON
+    /// requests never legitimately approach this.
+    #[serde(default = "default_max_body_size")]
+    pub max_body_size: usize,
+    /// Bearer token required by `GET /health/metrics`; unset leaves the
+    /// endpoint open (fine behind a private scrape network, not otherwise).
+    #[serde(default)]
+    pub metrics_auth_token: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DatabaseConfig {
+    pub url: String,
+    #[serde(default = "default_pool_size")]
+    pub max_connections: u32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RedisConfig {
+    pub url: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RabbitMQConfig {
+    pub url: String,
+    #[serde(default = "default_vision_queue")]
+    pub vision_queue: String,
+    /// Holding queue for failed jobs awaiting a retry cycle; messages TTL
+    /// back onto the main queue after `retry_delay`.
+    #[serde(default = "default_retry_queue")]
+    pub retry_queue: String,
+    /// Parking queue for jobs that exhausted their retries.
+    #[serde(default = "default_dlq")]
+    pub dlq: String,
+    /// Queue carrying terminal-job events for the webhook dispatcher.
+    #[serde(default = "default_webhook_queue")]
+    pub webhook_queue: String,
+    #[serde(with = "humantime_serde", default = "default_retry_delay")]
+    pub retry_delay: Duration,
+    /// Retry budget honored by the DLQ consumer: dead-lettered messages
+    /// with fewer recorded retries are requeued instead of persisted.
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+    /// Base URL of the RabbitMQ management API, used only for the queue
+    /// depth gauge; unset disables that poll.
+    #[serde(default)]
+    pub management_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct FileStorageConfig {
+    pub temp_dir: String,
+    /// Storage backend selector: `"local"` or `"s3"`. Left unset, the
+    /// backend is inferred from `s3_bucket` (set means S3) so existing
+    /// deployments keep working; an explicit value wins over inference and
+    /// `"s3"` without a bucket is a startup error rather than a silent
+    /// fallback to the temp dir.
+    #[serde(default)]
+    pub backend: Option<String>,
+    /// Setting a bucket switches storage to an S3-compatible backend; all
+    /// other S3 fields are ignored without it.
+    #[serde(default)]
+    pub s3_bucket: Option<String>,
+    #[serde(default)]
+    pub s3_region: Option<String>,
+    /// Custom endpoint for MinIO or other S3-compatible stores.
+    #[serde(default)]
+    pub s3_endpoint: Option<String>,
+    #[serde(default)]
+    pub s3_access_key: Option<String>,
+    #[serde(default)]
+    pub s3_secret_key: Option<String>,
+    /// Maximum accepted image size in bytes (10 MB default).
+    #[serde(default = "default_max_file_size")]
+    pub max_file_size: usize,
+    /// Ceiling for reassembled chunked uploads, which exist precisely for
+    /// camera files above `max_file_size` (30 MB default).
+    #[serde(default = "default_max_chunked_file_size")]
+    pub max_chunked_file_size: usize,
+    /// Chunk size clients must use for chunked uploads (2 MB default).
+    #[serde(default = "default_chunk_size")]
+    pub chunk_size: usize,
+    #[serde(default = "default_supported_formats")]
+    pub supported_formats: Vec<String>,
+    #[serde(with = "humantime_serde", default = "default_file_ttl")]
+    pub file_ttl: Duration,
+    #[serde(with = "humantime_serde", default = "default_cleanup_interval")]
+    pub cleanup_interval: Duration,
+    /// Images are resized to fit within this many pixels on the longest
+    /// side before queueing; the vision model never needs more.
+    #[serde(default = "default_max_dimension")]
+    pub max_dimension: u32,
+    /// JPEG re-encode quality for compressed uploads.
+    #[serde(default = "default_jpeg_quality")]
+    pub jpeg_quality: u8,
+}
+
+/// LINE webhook receiver settings (see `handlers::line_webhook`).
+#[derive(Debug, Clone, Deserialize)]
+pub struct LineWebhookConfig {
+    /// Events older than this are treated as replays / clock problems and
+    /// dropped.
+    #[serde(with = "humantime_serde", default = "default_line_skew_window")]
+    pub skew_window: Duration,
+    /// LINE channel secret for verifying `X-Line-Signature` on deliveries.
+    /// Empty (the default) rejects every delivery — set it before pointing
+    /// LINE at the gateway.
+    #[serde(default)]
+    pub channel_secret: String,
+}
+
+impl Default for LineWebhookConfig {
+    fn default() -> Self {
+        Self {
+            skew_window: default_line_skew_window(),
+            channel_secret: String::new(),
+        }
+    }
+}
+
+fn default_line_skew_window() -> Duration {
+    Duration::from_secs(5 * 60)
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExternalApisConfig {
+    pub vision_service_url: String,
+    pub llm_service_url: String,
+    /// One or more AI4Thai API keys, comma-separated. Multiple keys rotate
+    /// round-robin, and a key that answers quota-exceeded is demoted for a
+    /// cooldown (see `services::ai4thai`).
+    pub ai4thai_api_key: String,
+    #[serde(with = "humantime_serde", default = "default_upstream_timeout")]
+    pub timeout: Duration,
+    /// Attempts per upstream call for transient failures (1 = no retries).
+    #[serde(default = "default_retry_max_attempts")]
+    pub retry_max_attempts: u32,
+    /// Backoff before the first retry; doubles per attempt, with jitter.
+    #[serde(with = "humantime_serde", default = "default_retry_base_delay")]
+    pub retry_base_delay: Duration,
+    /// System prompt prepended to completions when the user writes Thai.
+    #[serde(default = "default_thai_prompt_template")]
+    pub thai_prompt_template: String,
+    /// System prompt prepended to completions when the user writes English.
+    #[serde(default = "default_english_prompt_template")]
+    pub english_prompt_template: String,
+}
+
+impl AppConfig {
+    /// Load from environment variables with the `APP__` prefix
+    /// (e.g. `APP__SERVER__PORT=8080`).
+    pub fn from_env() -> Result<Self, ConfigError> {
+        config::Config::builder()
+            .add_source(config::Environment::with_prefix("APP").separator("__"))
+            .build()
+            .and_then(|c| c.try_deserialize())
+            .map_err(|e| ConfigError(e.to_string()))
+    }
+}
+
+fn default_drain_timeout() -> Duration {
+    Duration::from_secs(20)
+}
+
+fn default_request_timeout() -> Duration {
+    Duration::from_secs(60)
+}
+
+fn default_max_upload_size() -> usize {
+    10 * 1024 * 1024
+}
+
+fn default_max_body_size() -> usize {
+    1024 * 1024
+}
+
+fn default_pool_size() -> u32 {
+    10
+}
+
+fn default_vision_queue() -> String {
+    "vision_analysis_queue".to_string()
+}
+
+fn default_retry_queue() -> String {
+    "vision_analysis_retry_queue".to_string()
+}
+
+fn default_dlq() -> String {
+    "vision_analysis_dlq".to_string()
+}
+
+fn default_webhook_queue() -> String {
+    "webhook_events".to_string()
+}
+
+fn default_retry_delay() -> Duration {
+    Duration::from_secs(10)
+}
+
+fn default_max_retries() -> u32 {
+    3
+}
+
+fn default_max_file_size() -> usize {
+    10 * 1024 * 1024
+}
+
+fn default_max_chunked_file_size() -> usize {
+    30 * 1024 * 1024
+}
+
+fn default_chunk_size() -> usize {
+    2 * 1024 * 1024
+}
+
+fn default_max_dimension() -> u32 {
+    1024
+}
+
+fn default_jpeg_quality() -> u8 {
+    85
+}
+
+fn default_supported_formats() -> Vec<String> {
+    vec!["jpeg".into(), "jpg".into(), "png".into(), "webp".into()]
+}
+
+fn default_file_ttl() -> Duration {
+    Duration::from_secs(24 * 60 * 60)
+}
+
+fn default_cleanup_interval() -> Duration {
+    Duration::from_secs(60 * 60)
+}
+
+fn default_upstream_timeout() -> Duration {
+    Duration::from_secs(30)
+}
+
+fn default_retry_max_attempts() -> u32 {
+    3
+}
+
+fn default_retry_base_delay() -> Duration {
+    Duration::from_millis(200)
+}
+
+fn default_thai_prompt_template() -> String {
+    "คุณคือผู้ช่วยด้านโรคพืชสำหรับเกษตรกรไทย ตอบเป็นภาษาไทยที่เข้าใจง่าย \
+     และให้คำแนะนำที่ทำได้จริง"
+        .to_string()
+}
+
+fn default_english_prompt_template() -> String {
+    "You are a plant disease assistant for farmers. Answer in clear, \
+     practical English with actionable advice."
+        .to_string()
+}
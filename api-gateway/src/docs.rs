@@ -0,0 +1,206 @@
+//! OpenAPI document for the gateway, assembled from the `#[utoipa::path]`
+//! annotations on each handler. Served as Swagger UI at `/docs` and raw
+//! JSON at `/openapi.json`; both are public so integrators can read the
+//! contract before they have a token. The mobile team's tooling expects
+//! versioned paths, so `/api/v1/openapi.json` serves the same document and
+//! `/api/v1/docs` redirects to the UI.
+
+use axum::{response::Redirect, Json};
+use serde::Serialize;
+use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::{Modify, OpenApi, ToSchema};
+
+/// Documentation-only mirror of the error body produced by
+/// [`crate::errors::AppError`]: the standard envelope with `success: false`
+/// plus the optional machine-readable `code` and the correlation id stamped
+/// by the correlation middleware. It exists only so error responses have a
+/// concrete schema to point at.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ErrorBody {
+    #[schema(example = false)]
+    pub success: bool,
+    /// Human-readable description of what went wrong.
+    pub error: String,
+    /// Stable machine-readable code (see [`shared::errors::ErrorCode`]),
+    /// present on every error so clients branch on codes, not prose.
+    #[schema(example = "VALIDATION_FAILED")]
+    pub code: String,
+    /// Correlation id of the failed request, for support tickets and log
+    /// lookup.
+    pub correlation_id: Option<String>,
+}
+
+/// Registers the JWT bearer scheme referenced by the per-route
+/// `security(("bearer_jwt" = []))` entries.
+struct SecurityAddon;
+
+impl Modify for SecurityAddon {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer_jwt",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .bearer_format("JWT")
+                    .build(),
+            ),
+        );
+    }
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Plant Disease AI Gateway",
+        description = "Image analysis, chat, and job management API for the \
+                       plant disease assistant. Unless marked otherwise, \
+                       endpoints require a bearer JWT from `/api/v1/auth`."
+    ),
+    paths(
+        crate::handlers::health::health_check,
+        crate::handlers::health::readiness_check,
+        crate::handlers::health::metrics,
+        crate::handlers::health::metrics_json,
+        crate::handlers::health::queue_metrics,
+        crate::handlers::chat::send_message,
+        crate::handlers::chat::chat_ws,
+        crate::handlers::chat::stream_message,
+        crate::handlers::chat::set_crop_context,
+        crate::handlers::chat::delete_conversation,
+        crate::handlers::chat::get_conversation,
+        crate::handlers::vision::queue_vision_analysis,
+        crate::handlers::vision::queue_vision_upload,
+        crate::handlers::vision::queue_batch_analysis,
+        crate::handlers::vision::get_batch_status,
+        crate::handlers::vision::get_job_status,
+        crate::handlers::vision::stream_job_status,
+        crate::handlers::vision::job_status_ws,
+        crate::handlers::vision::list_jobs,
+        crate::handlers::vision::list_failed_jobs,
+        crate::handlers::vision::retry_failed_job,
+        crate::handlers::vision::retry_job_advice,
+        crate::handlers::vision::cancel_job,
+        crate::handlers::vision::batch_tag_jobs,
+        crate::handlers::vision::batch_delete_jobs,
+        crate::handlers::vision::batch_restore_jobs,
+        crate::handlers::vision::get_file_stats,
+        crate::handlers::uploads::init_upload,
+        crate::handlers::uploads::upload_chunk,
+        crate::handlers::uploads::complete_upload,
+    ),
+    components(schemas(
+        ErrorBody,
+        shared::models::CropType,
+        shared::models::Language,
+        shared::models::JobStatus,
+        shared::models::DiseaseDetection,
+        shared::models::VisionResponse,
+        shared::models::LLMResponse,
+        shared::models::MessageRole,
+        shared::models::ChatMessage,
+    )),
+    modifiers(&SecurityAddon),
+    tags(
+        (name = "health", description = "Liveness, readiness, and metrics"),
+        (name = "chat", description = "LLM conversations and history"),
+        (name = "vision", description = "Image analysis jobs and their lifecycle"),
+    )
+)]
+pub struct ApiDoc;
+
+/// `GET /api/v1/openapi.json` — the spec under the versioned prefix, for
+/// generators that are pointed at the API base URL.
+pub async fn openapi_json() -> Json<utoipa::openapi::OpenApi> {
+    Json(ApiDoc::openapi())
+}
+
+/// `GET /api/v1/docs` — one Swagger UI bundle is enough; the versioned
+/// path just points at it.
+pub async fn docs_redirect() -> Redirect {
+    Redirect::permanent("/docs")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spec_is_openapi_3_and_covers_the_routes_clients_depend_on() {
+        let spec = serde_json::to_value(ApiDoc::openapi()).expect("spec serializes");
+        assert!(
+            spec["openapi"].as_str().unwrap_or_default().starts_with("3."),
+            "expected an OpenAPI 3 document, got {}",
+            spec["openapi"]
+        );
+        let paths = spec["paths"].as_object().expect("paths object");
+        for path in [
+            "/health",
+            "/health/ready",
+            "/health/metrics",
+            "/health/metrics.json",
+            "/api/v1/chat",
+            "/api/v1/chat/stream",
+            "/api/v1/chat/history",
+            "/api/v1/chat/{conversation_id}",
+            "/api/v1/chat/{conversation_id}/context",
+            "/api/v1/vision/analyze",
+            "/api/v1/vision/analyze/upload",
+            "/api/v1/vision/analyze/batch",
+            "/api/v1/vision/batches/{batch_id}",
+            "/api/v1/vision/files/stats",
+            "/api/v1/vision/jobs",
+            "/api/v1/vision/jobs/{job_id}",
+            "/api/v1/vision/jobs/{job_id}/stream",
+            "/api/v1/vision/jobs/{job_id}/advice/retry",
+            "/api/v1/vision/jobs/{job_id}/cancel",
+            "/api/v1/vision/jobs/batch/tag",
+            "/api/v1/vision/jobs/batch/delete",
+            "/api/v1/vision/jobs/batch/restore",
+            "/api/v1/vision/jobs/failed",
+            "/api/v1/vision/jobs/failed/{job_id}/retry",
+            "/api/v1/vision/upload/init",
+            "/api/v1/vision/upload/{upload_id}/chunk",
+            "/api/v1/vision/upload/{upload_id}/complete",
+        ] {
+            assert!(paths.contains_key(path), "spec is missing {path}");
+        }
+        // The bearer scheme the per-route security entries reference must
+        // actually be registered.
+        assert!(spec["components"]["securitySchemes"]["bearer_jwt"].is_object());
+    }
+
+    #[test]
+    fn error_responses_reference_the_error_body_schema() {
+        let spec = serde_json::to_value(ApiDoc::openapi()).expect("spec serializes");
+        assert!(spec["components"]["schemas"]["ErrorBody"].is_object());
+        // Spot-check that a guarded route documents its error shape with
+        // the shared schema rather than an ad-hoc inline object.
+        let analyze = &spec["paths"]["/api/v1/vision/analyze"]["post"]["responses"];
+        let has_error_ref = analyze
+            .as_object()
+            .expect("responses object")
+            .iter()
+            .filter(|(status, _)| status.starts_with('4') || status.starts_with('5'))
+            .any(|(_, response)| {
+                response["content"]["application/json"]["schema"]["$ref"]
+                    .as_str()
+                    .is_some_and(|r| r.ends_with("ErrorBody"))
+            });
+        assert!(has_error_ref, "analyze route lacks an ErrorBody error response");
+    }
+
+    #[test]
+    fn operation_ids_are_unique() {
+        let spec = serde_json::to_value(ApiDoc::openapi()).expect("spec serializes");
+        let mut seen = std::collections::HashSet::new();
+        for operations in spec["paths"].as_object().expect("paths object").values() {
+            for operation in operations.as_object().expect("operations object").values() {
+                if let Some(id) = operation["operationId"].as_str() {
+                    assert!(seen.insert(id.to_string()), "duplicate operationId {id}");
+                }
+            }
+        }
+        assert!(!seen.is_empty());
+    }
+}
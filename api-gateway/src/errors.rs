@@ -0,0 +1,179 @@
+//! Gateway error type and HTTP mapping.
+
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use shared::errors::ErrorCode;
+use shared::types::ApiResponse;
+
+pub type AppResult<T> = Result<T, AppError>;
+
+/// Every failure path in the gateway funnels through this enum so handlers
+/// can use `?` and the HTTP mapping lives in one place.
+#[derive(Debug, thiserror::Error)]
+pub enum AppError {
+    #[error("authentication failed: {0}")]
+    Auth(String),
+    #[error("forbidden: {0}")]
+    Forbidden(String),
+    #[error("validation failed: {0}")]
+    Validation(String),
+    #[error("image too large: {0}")]
+    ImageTooLarge(String),
+    #[error("invalid encoding: {0}")]
+    InvalidEncoding(String),
+    #[error("not found: {0}")]
+    NotFound(String),
+    #[error("conflict: {0}")]
+    Conflict(String),
+    /// The request is well-formed but contradicts earlier state, e.g. an
+    /// idempotency key reused with a different body.
+    #[error("unprocessable: {0}")]
+    Unprocessable(String),
+    #[error("rate limit exceeded")]
+    RateLimit,
+    /// Monthly usage quota exhausted; carries when the window resets so
+    /// clients can tell the farmer when to come back.
+    #[error("monthly quota exceeded, resets at {resets_at}")]
+    QuotaExceeded { resets_at: chrono::DateTime<chrono::Utc> },
+    #[error("client version too old, reload the app")]
+    ClientOutdated,
+    #[error("upstream service unavailable: {0}")]
+    ServiceUnavailable(String),
+    #[error("external API error: {0}")]
+    ExternalApi(String),
+    #[error("database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("internal error: {0}")]
+    Internal(String),
+}
+
+impl AppError {
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            AppError::Auth(_) => StatusCode::UNAUTHORIZED,
+            AppError::Forbidden(_) => StatusCode::FORBIDDEN,
+            AppError::Validation(_) => StatusCode::BAD_REQUEST,
+            AppError::ImageTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            AppError::InvalidEncoding(_) => StatusCode::BAD_REQUEST,
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::Conflict(_) => StatusCode::CONFLICT,
+            AppError::Unprocessable(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            AppError::RateLimit => StatusCode::TOO_MANY_REQUESTS,
+            AppError::QuotaExceeded { .. } => StatusCode::TOO_MANY_REQUESTS,
+            AppError::ClientOutdated => StatusCode::UPGRADE_REQUIRED,
+            AppError::ServiceUnavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+            AppError::ExternalApi(_) => StatusCode::BAD_GATEWAY,
+            AppError::Database(_) | AppError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl AppError {
+    /// Machine-readable code for clients to branch on, carried in every
+    /// error body. Deliberately no wildcard arm: a new variant without a
+    /// code fails to compile here.
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            AppError::Auth(_) => ErrorCode::AuthFailed,
+            AppError::Forbidden(_) => ErrorCode::Forbidden,
+            AppError::Validation(_) => ErrorCode::ValidationFailed,
+            AppError::ImageTooLarge(_) => ErrorCode::ImageTooLarge,
+            AppError::InvalidEncoding(_) => ErrorCode::InvalidEncoding,
+            AppError::NotFound(_) => ErrorCode::NotFound,
+            AppError::Conflict(_) => ErrorCode::Conflict,
+            AppError::Unprocessable(_) => ErrorCode::Unprocessable,
+            AppError::RateLimit => ErrorCode::RateLimited,
+            AppError::QuotaExceeded { .. } => ErrorCode::QuotaExceeded,
+            AppError::ClientOutdated => ErrorCode::ClientOutdated,
+            AppError::ServiceUnavailable(_) => ErrorCode::ServiceUnavailable,
+            AppError::ExternalApi(_) => ErrorCode::ExternalApi,
+            AppError::Database(_) | AppError::Internal(_) => ErrorCode::Internal,
+        }
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        let status = self.status_code();
+        // Internal details stay in the logs, not the response body.
+        let message = match &self {
+            AppError::Database(err) => {
+                tracing::error!(error = %err, "database error");
+                "internal server error".to_string()
+            }
+            AppError::Internal(err) => {
+                tracing::error!(error = %err, "internal error");
+                "internal server error".to_string()
+            }
+            other => other.to_string(),
+        };
+        let mut body = serde_json::to_value(ApiResponse::<()>::error(message)).unwrap_or_default();
+        if let Some(map) = body.as_object_mut() {
+            map.insert("code".into(), self.code().as_str().into());
+        }
+        // Quota rejections carry the reset instant as a field, not just
+        // prose, so clients can render a countdown.
+        if let (AppError::QuotaExceeded { resets_at }, Some(map)) = (&self, body.as_object_mut()) {
+            map.insert("resets_at".into(), resets_at.to_rfc3339().into());
+        }
+        // Stamp the request's correlation id so a support ticket quoting the
+        // error body leads straight to the right log lines.
+        if let (Some(id), Some(map)) = (
+            crate::middleware::correlation::current_correlation_id(),
+            body.as_object_mut(),
+        ) {
+            map.insert("correlation_id".into(), id.into());
+        }
+        (status, Json(body)).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::middleware::correlation::scope_correlation_id;
+
+    #[tokio::test]
+    async fn error_bodies_echo_the_correlation_id() {
+        let response = scope_correlation_id("req-echo-1", async {
+            AppError::Validation("bad input".into()).into_response()
+        })
+        .await;
+        let bytes = axum::body::to_bytes(response.into_body(), 64 * 1024)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["correlation_id"], "req-echo-1");
+    }
+
+    #[tokio::test]
+    async fn every_error_body_carries_a_machine_readable_code() {
+        let cases = [
+            (AppError::Auth("bad token".into()), "AUTH_FAILED"),
+            (AppError::Validation("bad input".into()), "VALIDATION_FAILED"),
+            (AppError::RateLimit, "RATE_LIMITED"),
+            (AppError::Internal("boom".into()), "INTERNAL"),
+        ];
+        for (error, expected) in cases {
+            let response = error.into_response();
+            let bytes = axum::body::to_bytes(response.into_body(), 64 * 1024)
+                .await
+                .unwrap();
+            let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+            assert_eq!(body["code"], expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn errors_outside_a_request_scope_omit_the_id() {
+        let response = AppError::Validation("bad input".into()).into_response();
+        let bytes = axum::body::to_bytes(response.into_body(), 64 * 1024)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert!(body.get("correlation_id").is_none());
+    }
+}
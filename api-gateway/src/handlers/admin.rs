@@ -0,0 +1,393 @@
+//! Admin control surface, mounted under `/api/admin/v1` only when
+//! `admin.enabled` is set.
+//!
+//! Everything here is the kind of action that previously needed a psql or
+//! rabbitmqadmin session: listing and editing accounts, purging the vision
+//! queue, flushing Redis, and filtering jobs across all users. Every
+//! mutating action lands in the `admin_audit_log` table before the
+//! response goes out — an admin API without an audit trail is how "who
+//! deleted that account?" becomes unanswerable.
+
+use axum::{
+    extract::{Path, Query, State},
+    routing::{get, patch, post},
+    Json, Router,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use shared::types::ApiResponse;
+use sqlx::QueryBuilder;
+use uuid::Uuid;
+
+use crate::{
+    errors::{AppError, AppResult},
+    middleware::{auth::require_role, request_context::RequestContext},
+    shared::pagination::{OffsetParams, Page},
+    state::AppState,
+    AuthUser,
+};
+
+/// Roles an admin may assign; anything else is a typo that would silently
+/// grant nothing.
+const ASSIGNABLE_ROLES: [&str; 4] = ["farmer", "officer", "reviewer", "admin"];
+
+/// Job statuses the jobs filter accepts.
+const JOB_STATUSES: [&str; 4] = ["queued", "processing", "completed", "failed"];
+
+/// Admin lists default to bigger pages than the farmer-facing endpoints;
+/// the cap is still `pagination::MAX_LIMIT`.
+const DEFAULT_PAGE_SIZE: i64 = 50;
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/users", get(list_users))
+        .route("/users/:user_id", patch(update_user).delete(delete_user))
+        .route("/queues/flush", post(flush_queue))
+        .route("/cache/flush", post(flush_cache))
+        .route("/jobs", get(list_all_jobs))
+        .route("/flags", get(list_flags))
+        .route("/flags/:name", axum::routing::put(set_flag))
+}
+
+/// Record one admin action. Runs before the action's response is returned;
+/// an unauditable action is treated as a failed action.
+async fn audit(
+    state: &AppState,
+    admin_id: Uuid,
+    action: &str,
+    target: Option<String>,
+    detail: serde_json::Value,
+) -> AppResult<()> {
+    sqlx::query(
+        "INSERT INTO admin_audit_log (id, admin_id, action, target, detail) \
+         VALUES ($1, $2, $3, $4, $5)",
+    )
+    .bind(Uuid::new_v4())
+    .bind(admin_id)
+    .bind(action)
+    .bind(target)
+    .bind(detail)
+    .execute(&state.db)
+    .await?;
+    Ok(())
+}
+
+fn require_admin(ctx: &RequestContext) -> AppResult<&AuthUser> {
+    let user = ctx.require_user()?;
+    require_role(user, "admin")?;
+    Ok(user)
+}
+
+/// Validate a roles update: non-empty, and every entry a role we know.
+pub fn validate_roles(roles: &[String]) -> AppResult<()> {
+    if roles.is_empty() {
+        return Err(AppError::Validation(
+            "a user must keep at least one role".into(),
+        ));
+    }
+    for role in roles {
+        if !ASSIGNABLE_ROLES.contains(&role.as_str()) {
+            return Err(AppError::Validation(format!(
+                "unknown role '{role}', assignable: {}",
+                ASSIGNABLE_ROLES.join(", ")
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Admins cannot edit or delete their own account through this API — losing
+/// your own admin role (or disabling yourself) mid-session locks everyone
+/// out with no audit-visible second actor.
+pub fn reject_self_target(admin_id: Uuid, target_id: Uuid) -> AppResult<()> {
+    if admin_id == target_id {
+        Err(AppError::Validation(
+            "admins cannot modify their own account; use a second admin".into(),
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct UserSummary {
+    pub id: Uuid,
+    pub email: String,
+    pub roles: Vec<String>,
+    pub disabled: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+/// `GET /api/admin/v1/users?page&limit` — paginated account list, newest
+/// first.
+pub async fn list_users(
+    State(state): State<AppState>,
+    ctx: RequestContext,
+    Query(query): Query<OffsetParams>,
+) -> AppResult<Json<ApiResponse<Page<UserSummary>>>> {
+    require_admin(&ctx)?;
+    let (_, limit, offset) = query.window_or(DEFAULT_PAGE_SIZE);
+    let items: Vec<UserSummary> = sqlx::query_as(
+        "SELECT id, email, roles, disabled, created_at FROM users \
+         ORDER BY created_at DESC LIMIT $1 OFFSET $2",
+    )
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(&state.db)
+    .await?;
+    let (total,): (i64,) = sqlx::query_as("SELECT count(*) FROM users")
+        .fetch_one(&state.db)
+        .await?;
+    Ok(Json(ApiResponse::ok(Page {
+        items,
+        next_cursor: None,
+        total: Some(total as u64),
+    })))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateUserRequest {
+    pub roles: Option<Vec<String>>,
+    pub disabled: Option<bool>,
+}
+
+/// `PATCH /api/admin/v1/users/:id` — update roles and/or disable an
+/// account. Omitted fields are left as they are.
+pub async fn update_user(
+    State(state): State<AppState>,
+    ctx: RequestContext,
+    Path(user_id): Path<Uuid>,
+    Json(request): Json<UpdateUserRequest>,
+) -> AppResult<Json<ApiResponse<UserSummary>>> {
+    let admin_id = require_admin(&ctx)?.user_id;
+    reject_self_target(admin_id, user_id)?;
+    if let Some(roles) = &request.roles {
+        validate_roles(roles)?;
+    }
+    if request.roles.is_none() && request.disabled.is_none() {
+        return Err(AppError::Validation("nothing to update".into()));
+    }
+
+    let updated: Option<UserSummary> = sqlx::query_as(
+        "UPDATE users SET roles = COALESCE($2, roles), disabled = COALESCE($3, disabled) \
+         WHERE id = $1 RETURNING id, email, roles, disabled, created_at",
+    )
+    .bind(user_id)
+    .bind(&request.roles)
+    .bind(request.disabled)
+    .fetch_optional(&state.db)
+    .await?;
+    let updated = updated.ok_or_else(|| AppError::NotFound(format!("user {user_id}")))?;
+
+    audit(
+        &state,
+        admin_id,
+        "user.update",
+        Some(user_id.to_string()),
+        serde_json::json!({ "roles": request.roles, "disabled": request.disabled }),
+    )
+    .await?;
+    Ok(Json(ApiResponse::ok(updated)))
+}
+
+/// `DELETE /api/admin/v1/users/:id` — remove an account outright.
+pub async fn delete_user(
+    State(state): State<AppState>,
+    ctx: RequestContext,
+    Path(user_id): Path<Uuid>,
+) -> AppResult<Json<ApiResponse<()>>> {
+    let admin_id = require_admin(&ctx)?.user_id;
+    reject_self_target(admin_id, user_id)?;
+
+    let result = sqlx::query("DELETE FROM users WHERE id = $1")
+        .bind(user_id)
+        .execute(&state.db)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound(format!("user {user_id}")));
+    }
+    audit(
+        &state,
+        admin_id,
+        "user.delete",
+        Some(user_id.to_string()),
+        serde_json::Value::Null,
+    )
+    .await?;
+    Ok(Json(ApiResponse::ok(())))
+}
+
+/// `POST /api/admin/v1/queues/flush` — purge the vision queue through the
+/// RabbitMQ management API (AMQP has no purge that also covers unacked
+/// routing states the way the management call does for our setup).
+pub async fn flush_queue(
+    State(state): State<AppState>,
+    ctx: RequestContext,
+) -> AppResult<Json<ApiResponse<()>>> {
+    let admin_id = require_admin(&ctx)?.user_id;
+    let base = state
+        .config
+        .rabbitmq
+        .management_url
+        .as_deref()
+        .ok_or_else(|| {
+            AppError::ServiceUnavailable("rabbitmq.management_url is not configured".into())
+        })?;
+    let queue = &state.config.rabbitmq.vision_queue;
+    let url = format!("{}/api/queues/%2f/{queue}/contents", base.trim_end_matches('/'));
+    let response = reqwest::Client::new()
+        .delete(&url)
+        .send()
+        .await
+        .map_err(|e| AppError::ServiceUnavailable(format!("rabbitmq management: {e}")))?;
+    if !response.status().is_success() {
+        return Err(AppError::ServiceUnavailable(format!(
+            "queue purge failed: {}",
+            response.status()
+        )));
+    }
+    audit(
+        &state,
+        admin_id,
+        "queue.flush",
+        Some(queue.clone()),
+        serde_json::Value::Null,
+    )
+    .await?;
+    Ok(Json(ApiResponse::ok(())))
+}
+
+/// `POST /api/admin/v1/cache/flush` — `FLUSHDB` on the cache database.
+/// Also wipes job status keys and crop-context pins, which rebuild from
+/// Postgres and the clients; the audit row records who decided that was
+/// worth it.
+pub async fn flush_cache(
+    State(state): State<AppState>,
+    ctx: RequestContext,
+) -> AppResult<Json<ApiResponse<()>>> {
+    let admin_id = require_admin(&ctx)?.user_id;
+    let mut redis = state.get_redis().await?;
+    let _: () = redis::cmd("FLUSHDB")
+        .query_async(&mut redis)
+        .await
+        .map_err(|e| AppError::ServiceUnavailable(format!("redis: {e}")))?;
+    audit(&state, admin_id, "cache.flush", None, serde_json::Value::Null).await?;
+    Ok(Json(ApiResponse::ok(())))
+}
+
+/// `GET /api/admin/v1/flags` — every feature flag currently defined.
+pub async fn list_flags(
+    State(state): State<AppState>,
+    ctx: RequestContext,
+) -> AppResult<Json<ApiResponse<std::collections::BTreeMap<String, crate::services::feature_flags::FlagDefinition>>>>
+{
+    require_admin(&ctx)?;
+    Ok(Json(ApiResponse::ok(state.feature_flags.all().await?)))
+}
+
+/// `PUT /api/admin/v1/flags/:name` — create or replace one feature flag at
+/// runtime; no restart, applies to every gateway instance on the next
+/// lookup.
+pub async fn set_flag(
+    State(state): State<AppState>,
+    ctx: RequestContext,
+    Path(name): Path<String>,
+    Json(definition): Json<crate::services::feature_flags::FlagDefinition>,
+) -> AppResult<Json<ApiResponse<crate::services::feature_flags::FlagDefinition>>> {
+    let admin_id = require_admin(&ctx)?.user_id;
+    state.feature_flags.set(&name, &definition).await?;
+    audit(
+        &state,
+        admin_id,
+        "flag.set",
+        Some(name),
+        serde_json::to_value(&definition).unwrap_or_default(),
+    )
+    .await?;
+    Ok(Json(ApiResponse::ok(definition)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AdminJobsQuery {
+    pub status: Option<String>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    #[serde(flatten)]
+    pub page: OffsetParams,
+}
+
+/// `GET /api/admin/v1/jobs?status&from&to&page&limit` — jobs across all
+/// users, filtered by status and creation window.
+pub async fn list_all_jobs(
+    State(state): State<AppState>,
+    ctx: RequestContext,
+    Query(query): Query<AdminJobsQuery>,
+) -> AppResult<Json<ApiResponse<Page<super::vision::JobSummary>>>> {
+    require_admin(&ctx)?;
+    if let Some(status) = &query.status {
+        if !JOB_STATUSES.contains(&status.as_str()) {
+            return Err(AppError::Validation(format!(
+                "unknown status '{status}', expected one of: {}",
+                JOB_STATUSES.join(", ")
+            )));
+        }
+    }
+    let (_, limit, offset) = query.page.window_or(DEFAULT_PAGE_SIZE);
+
+    let mut qb = QueryBuilder::new(
+        "SELECT id, crop_type, status, created_at FROM vision_jobs WHERE deleted_at IS NULL",
+    );
+    if let Some(status) = &query.status {
+        qb.push(" AND status = ").push_bind(status);
+    }
+    if let Some(from) = query.from {
+        qb.push(" AND created_at >= ").push_bind(from);
+    }
+    if let Some(to) = query.to {
+        qb.push(" AND created_at < ").push_bind(to);
+    }
+    qb.push(" ORDER BY created_at DESC LIMIT ").push_bind(limit);
+    qb.push(" OFFSET ").push_bind(offset);
+
+    let items: Vec<super::vision::JobSummary> =
+        qb.build_query_as().fetch_all(&state.db).await?;
+    Ok(Json(ApiResponse::ok(Page {
+        items,
+        next_cursor: None,
+        total: None,
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user(roles: &[&str]) -> AuthUser {
+        AuthUser {
+            user_id: Uuid::new_v4(),
+            email: "someone@example.com".into(),
+            roles: roles.iter().map(|r| r.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn non_admins_get_a_403_from_the_guard() {
+        let error = require_role(&user(&["farmer", "officer"]), "admin").unwrap_err();
+        assert!(matches!(error, AppError::Forbidden(_)));
+        assert_eq!(error.status_code(), axum::http::StatusCode::FORBIDDEN);
+    }
+
+    #[test]
+    fn role_updates_are_checked_against_the_known_set() {
+        assert!(validate_roles(&["farmer".into(), "admin".into()]).is_ok());
+        assert!(validate_roles(&[]).is_err());
+        assert!(validate_roles(&["superuser".into()]).is_err());
+    }
+
+    #[test]
+    fn admins_cannot_target_themselves() {
+        let id = Uuid::new_v4();
+        assert!(reject_self_target(id, id).is_err());
+        assert!(reject_self_target(id, Uuid::new_v4()).is_ok());
+    }
+}
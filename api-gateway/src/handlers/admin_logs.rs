@@ -0,0 +1,54 @@
+//! Admin-only live log tailing over SSE, so demos don't need SSH access.
+
+use std::{convert::Infallible, time::Duration};
+
+use axum::{
+    extract::{Query, State},
+    response::sse::{Event, KeepAlive, Sse},
+};
+use futures_util::stream::Stream;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::{
+    errors::AppResult,
+    logging::LogFilter,
+    middleware::auth::require_role,
+    middleware::request_context::RequestContext,
+    state::AppState,
+};
+
+/// Streams with no matching events for this long are closed; the browser's
+/// EventSource reconnects if the admin still cares.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// `GET /api/v1/admin/logs/stream` — tail gateway logs as SSE. Supports
+/// `?level=warn` (minimum level) and `?module=api_gateway::handlers`
+/// (target prefix) filters.
+pub async fn stream_logs(
+    State(state): State<AppState>,
+    ctx: RequestContext,
+    Query(filter): Query<LogFilter>,
+) -> AppResult<Sse<impl Stream<Item = Result<Event, Infallible>>>> {
+    require_role(ctx.require_user()?, "admin")?;
+
+    let receiver = state.log_broadcaster.subscribe();
+    let stream = futures_util::stream::unfold(receiver, move |mut receiver| {
+        let filter = filter.clone();
+        async move {
+            loop {
+                match tokio::time::timeout(IDLE_TIMEOUT, receiver.recv()).await {
+                    Ok(Ok(event)) if filter.matches(&event) => {
+                        let data = serde_json::to_string(&event).unwrap_or_default();
+                        return Some((Ok(Event::default().data(data)), receiver));
+                    }
+                    // Filtered out, or we lagged and skipped events the
+                    // bounded buffer dropped: keep waiting.
+                    Ok(Ok(_)) | Ok(Err(RecvError::Lagged(_))) => continue,
+                    Ok(Err(RecvError::Closed)) => return None,
+                    Err(_) => return None, // idle: close the stream
+                }
+            }
+        }
+    });
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
@@ -0,0 +1,101 @@
+//! Admin summary of request-shadowing comparisons.
+
+use axum::{extract::State, Json};
+use serde::Serialize;
+use shared::types::ApiResponse;
+
+use crate::{
+    errors::AppResult,
+    middleware::auth::require_role,
+    middleware::request_context::RequestContext,
+    middleware::shadow::ShadowComparison,
+    state::AppState,
+};
+
+/// Mismatching rows echoed back verbatim; beyond this the aggregate counts
+/// tell the story better than a longer list would.
+const MAX_MISMATCH_ROWS: usize = 50;
+
+#[derive(Debug, Serialize)]
+pub struct ShadowSummary {
+    pub enabled: bool,
+    pub total: usize,
+    pub mismatches: usize,
+    /// Mean of `latency_delta_ms` over all rows; positive means staging is
+    /// slower on average.
+    pub mean_latency_delta_ms: Option<i64>,
+    /// The most recent mismatching comparisons, newest first.
+    pub recent_mismatches: Vec<ShadowComparison>,
+}
+
+/// Fold the comparison log into the summary the admin endpoint returns.
+/// Split from the handler so it can be fed fixed rows in tests.
+pub fn summarize(enabled: bool, rows: Vec<ShadowComparison>) -> ShadowSummary {
+    let total = rows.len();
+    let mean_latency_delta_ms = if total == 0 {
+        None
+    } else {
+        Some(rows.iter().map(|r| r.latency_delta_ms).sum::<i64>() / total as i64)
+    };
+    let mut mismatching: Vec<ShadowComparison> =
+        rows.into_iter().filter(|r| r.is_mismatch()).collect();
+    let mismatches = mismatching.len();
+    mismatching.reverse();
+    mismatching.truncate(MAX_MISMATCH_ROWS);
+    ShadowSummary {
+        enabled,
+        total,
+        mismatches,
+        mean_latency_delta_ms,
+        recent_mismatches: mismatching,
+    }
+}
+
+/// `GET /api/v1/admin/shadow/summary` — how staging's responses compare to
+/// production's for the shadowed sample.
+pub async fn shadow_summary(
+    State(state): State<AppState>,
+    ctx: RequestContext,
+) -> AppResult<Json<ApiResponse<ShadowSummary>>> {
+    require_role(ctx.require_user()?, "admin")?;
+    let summary = summarize(state.shadow.enabled(), state.shadow.snapshot());
+    Ok(Json(ApiResponse::ok(summary)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn row(prod: u16, shadow: Option<u16>, delta: i64) -> ShadowComparison {
+        ShadowComparison {
+            path: "/api/v1/vision/analyze".into(),
+            prod_status: prod,
+            shadow_status: shadow,
+            latency_delta_ms: delta,
+            recorded_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn summary_counts_mismatches_and_averages_latency() {
+        let summary = summarize(
+            true,
+            vec![row(200, Some(200), 10), row(200, Some(500), 30), row(200, None, 20)],
+        );
+        assert_eq!(summary.total, 3);
+        assert_eq!(summary.mismatches, 2);
+        assert_eq!(summary.mean_latency_delta_ms, Some(20));
+        // Newest mismatch first: the failed shadow call, then the 500.
+        assert_eq!(summary.recent_mismatches[0].shadow_status, None);
+        assert_eq!(summary.recent_mismatches[1].shadow_status, Some(500));
+    }
+
+    #[test]
+    fn empty_log_has_no_average() {
+        let summary = summarize(false, Vec::new());
+        assert_eq!(summary.total, 0);
+        assert_eq!(summary.mean_latency_delta_ms, None);
+        assert!(summary.recent_mismatches.is_empty());
+    }
+}
@@ -0,0 +1,217 @@
+//! Expert annotation endpoints for the reviewer workflow.
+//!
+//! Agronomists with the `reviewer` role can correct the model's bounding
+//! boxes; every save creates a new version so the model team can diff expert
+//! output against the model's.
+
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use shared::{models::DiseaseDetection, types::ApiResponse};
+use uuid::Uuid;
+
+use crate::{
+    errors::{AppError, AppResult},
+    middleware::request_context::RequestContext,
+    state::AppState,
+    AuthUser,
+};
+
+pub const REVIEWER_ROLE: &str = "reviewer";
+
+/// One expert-drawn box.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AnnotationBox {
+    pub label: String,
+    /// Normalized [x, y, width, height] in 0..1 image coordinates.
+    pub bounding_box: [f32; 4],
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SaveAnnotationsRequest {
+    pub boxes: Vec<AnnotationBox>,
+    /// Version the client based its edit on; a mismatch with the stored
+    /// head version means a concurrent save happened.
+    pub base_version: Option<i32>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AnnotationsResponse {
+    pub job_id: Uuid,
+    pub version: i32,
+    pub boxes: Vec<AnnotationBox>,
+    pub reviewer_id: Uuid,
+    pub created_at: DateTime<Utc>,
+}
+
+fn require_reviewer(user: &AuthUser) -> AppResult<()> {
+    if user.has_role(REVIEWER_ROLE) {
+        Ok(())
+    } else {
+        Err(AppError::Auth(format!(
+            "the '{REVIEWER_ROLE}' role is required for annotations"
+        )))
+    }
+}
+
+/// `GET /api/v1/vision/jobs/:id/annotations` — latest annotation version.
+pub async fn get_annotations(
+    State(state): State<AppState>,
+    ctx: RequestContext,
+    Path(job_id): Path<Uuid>,
+) -> AppResult<Json<ApiResponse<AnnotationsResponse>>> {
+    require_reviewer(ctx.require_user()?)?;
+
+    let row: Option<(i32, serde_json::Value, Uuid, DateTime<Utc>)> = sqlx::query_as(
+        "SELECT version, boxes, reviewer_id, created_at FROM job_annotations \
+         WHERE job_id = $1 ORDER BY version DESC LIMIT 1",
+    )
+    .bind(job_id)
+    .fetch_optional(&state.db)
+    .await?;
+
+    let (version, boxes, reviewer_id, created_at) =
+        row.ok_or_else(|| AppError::NotFound(format!("annotations for job {job_id}")))?;
+    let boxes: Vec<AnnotationBox> = serde_json::from_value(boxes)
+        .map_err(|e| AppError::Internal(format!("stored annotations: {e}")))?;
+
+    Ok(Json(ApiResponse::ok(AnnotationsResponse {
+        job_id,
+        version,
+        boxes,
+        reviewer_id,
+        created_at,
+    })))
+}
+
+/// `PUT /api/v1/vision/jobs/:id/annotations` — append a new version.
+pub async fn save_annotations(
+    State(state): State<AppState>,
+    ctx: RequestContext,
+    Path(job_id): Path<Uuid>,
+    Json(request): Json<SaveAnnotationsRequest>,
+) -> AppResult<Json<ApiResponse<AnnotationsResponse>>> {
+    let user = ctx.require_user()?.clone();
+    require_reviewer(&user)?;
+
+    for b in &request.boxes {
+        if b.label.trim().is_empty() {
+            return Err(AppError::Validation("annotation label must not be empty".into()));
+        }
+        if b.bounding_box.iter().any(|v| !(0.0..=1.0).contains(v)) {
+            return Err(AppError::Validation(
+                "bounding box coordinates must be normalized to 0..1".into(),
+            ));
+        }
+    }
+
+    let mut tx = state.db.begin().await?;
+    let head: Option<i32> =
+        sqlx::query_scalar("SELECT MAX(version) FROM job_annotations WHERE job_id = $1")
+            .bind(job_id)
+            .fetch_one(&mut *tx)
+            .await?;
+    let head = head.unwrap_or(0);
+    if let Some(base) = request.base_version {
+        if base != head {
+            return Err(AppError::Validation(format!(
+                "annotation version conflict: head is {head}, edit was based on {base}"
+            )));
+        }
+    }
+    let version = head + 1;
+    let created_at = Utc::now();
+    sqlx::query(
+        "INSERT INTO job_annotations (job_id, version, boxes, reviewer_id, created_at) \
+         VALUES ($1, $2, $3, $4, $5)",
+    )
+    .bind(job_id)
+    .bind(version)
+    .bind(serde_json::to_value(&request.boxes).expect("boxes serialize"))
+    .bind(user.user_id)
+    .bind(created_at)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    Ok(Json(ApiResponse::ok(AnnotationsResponse {
+        job_id,
+        version,
+        boxes: request.boxes,
+        reviewer_id: user.user_id,
+        created_at,
+    })))
+}
+
+/// Boxes to use when exporting a job for the model team: expert annotations
+/// win over the model's own detections when any exist.
+pub fn boxes_for_export(
+    expert: Option<&[AnnotationBox]>,
+    model: &[DiseaseDetection],
+) -> Vec<AnnotationBox> {
+    match expert {
+        Some(boxes) if !boxes.is_empty() => boxes.to_vec(),
+        _ => model
+            .iter()
+            .filter_map(|d| {
+                d.bounding_box.map(|bounding_box| AnnotationBox {
+                    label: d.disease_name.clone(),
+                    bounding_box,
+                })
+            })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user(roles: &[&str]) -> AuthUser {
+        AuthUser {
+            user_id: Uuid::new_v4(),
+            email: "a@example.com".into(),
+            roles: roles.iter().map(|r| r.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn reviewer_role_is_enforced() {
+        assert!(require_reviewer(&user(&["reviewer"])).is_ok());
+        assert!(matches!(
+            require_reviewer(&user(&["farmer"])),
+            Err(AppError::Auth(_))
+        ));
+    }
+
+    #[test]
+    fn export_prefers_expert_annotations() {
+        let model = vec![DiseaseDetection {
+            disease_name: "rice_blast".into(),
+            confidence: 0.8,
+            bounding_box: Some([0.1, 0.1, 0.2, 0.2]),
+        }];
+        let expert = vec![AnnotationBox {
+            label: "brown_spot".into(),
+            bounding_box: [0.3, 0.3, 0.1, 0.1],
+        }];
+        let boxes = boxes_for_export(Some(&expert), &model);
+        assert_eq!(boxes, expert);
+    }
+
+    #[test]
+    fn export_falls_back_to_model_detections() {
+        let model = vec![DiseaseDetection {
+            disease_name: "rice_blast".into(),
+            confidence: 0.8,
+            bounding_box: Some([0.1, 0.1, 0.2, 0.2]),
+        }];
+        let boxes = boxes_for_export(Some(&[]), &model);
+        assert_eq!(boxes[0].label, "rice_blast");
+        let boxes = boxes_for_export(None, &model);
+        assert_eq!(boxes.len(), 1);
+    }
+}
@@ -0,0 +1,294 @@
+//! Registration, login, and token refresh.
+//!
+//! Passwords are bcrypt-hashed (cost 12); access tokens are the JWTs the
+//! auth middleware validates, and refresh tokens are opaque ids stored in
+//! Redis with a TTL so they can be revoked server-side. Refresh rotates
+//! the token: a stolen refresh token stops working the first time the
+//! legitimate client uses its own.
+
+use axum::{extract::State, Json};
+use chrono::{DateTime, Duration, Utc};
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+use shared::types::ApiResponse;
+use uuid::Uuid;
+
+use crate::{
+    errors::{AppError, AppResult},
+    middleware::auth::Claims,
+    state::AppState,
+};
+
+/// bcrypt cost factor; 12 keeps a verify around ~250 ms on our hardware,
+/// slow enough to blunt offline cracking without hurting login UX.
+const BCRYPT_COST: u32 = 12;
+/// Access token lifetime.
+const ACCESS_TTL: Duration = Duration::hours(24);
+/// Refresh token lifetime in Redis.
+const REFRESH_TTL_SECS: u64 = 30 * 24 * 60 * 60;
+
+/// Light-touch email shape check: one `@` with a dotted domain. Real
+/// validation happens when mail bounces; this only catches typos early.
+pub fn validate_email(email: &str) -> AppResult<()> {
+    let email = email.trim();
+    let valid = email.split_once('@').is_some_and(|(local, domain)| {
+        !local.is_empty()
+            && domain.contains('.')
+            && !domain.starts_with('.')
+            && !domain.ends_with('.')
+            && !domain.contains('@')
+    });
+    if valid {
+        Ok(())
+    } else {
+        Err(AppError::Validation("invalid email address".into()))
+    }
+}
+
+pub fn validate_password(password: &str) -> AppResult<()> {
+    if password.chars().count() < 8 {
+        return Err(AppError::Validation(
+            "password must be at least 8 characters".into(),
+        ));
+    }
+    Ok(())
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct UserRow {
+    id: Uuid,
+    email: String,
+    password_hash: String,
+    roles: Vec<String>,
+    disabled: bool,
+    created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UserResponse {
+    pub id: Uuid,
+    pub email: String,
+    pub roles: Vec<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<UserRow> for UserResponse {
+    fn from(row: UserRow) -> Self {
+        Self {
+            id: row.id,
+            email: row.email,
+            roles: row.roles,
+            created_at: row.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RegisterRequest {
+    pub email: String,
+    pub password: String,
+}
+
+/// `POST /api/v1/auth/register`
+pub async fn register(
+    State(state): State<AppState>,
+    Json(request): Json<RegisterRequest>,
+) -> AppResult<Json<ApiResponse<UserResponse>>> {
+    validate_email(&request.email)?;
+    validate_password(&request.password)?;
+    let email = request.email.trim().to_ascii_lowercase();
+
+    // bcrypt at cost 12 takes long enough to matter on the async workers.
+    let hash = tokio::task::spawn_blocking(move || bcrypt::hash(request.password, BCRYPT_COST))
+        .await
+        .map_err(|e| AppError::Internal(format!("hash task: {e}")))?
+        .map_err(|e| AppError::Internal(format!("bcrypt: {e}")))?;
+
+    let row: Result<UserRow, sqlx::Error> = sqlx::query_as(
+        "INSERT INTO users (id, email, password_hash) VALUES ($1, $2, $3) \
+         RETURNING id, email, password_hash, roles, disabled, created_at",
+    )
+    .bind(Uuid::new_v4())
+    .bind(&email)
+    .bind(&hash)
+    .fetch_one(&state.db)
+    .await;
+    match row {
+        Ok(row) => Ok(Json(ApiResponse::ok(row.into()))),
+        Err(sqlx::Error::Database(e)) if e.code().as_deref() == Some("23505") => {
+            Err(AppError::Validation("email already registered".into()))
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LoginRequest {
+    pub email: String,
+    pub password: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TokenResponse {
+    pub token: String,
+    pub expires_at: DateTime<Utc>,
+    pub refresh_token: String,
+}
+
+/// Mint an access token for the user; `now` is injected for tests.
+fn mint_token(
+    user_id: Uuid,
+    email: &str,
+    roles: &[String],
+    secret: &[u8],
+    now: DateTime<Utc>,
+) -> AppResult<(String, DateTime<Utc>)> {
+    let expires_at = now + ACCESS_TTL;
+    let claims = Claims {
+        sub: user_id,
+        email: email.to_string(),
+        roles: roles.to_vec(),
+        exp: expires_at.timestamp(),
+    };
+    let token = jsonwebtoken::encode(
+        &jsonwebtoken::Header::default(),
+        &claims,
+        &jsonwebtoken::EncodingKey::from_secret(secret),
+    )
+    .map_err(|e| AppError::Internal(format!("sign token: {e}")))?;
+    Ok((token, expires_at))
+}
+
+fn refresh_key(token: &str) -> String {
+    format!("refresh:{token}")
+}
+
+async fn issue_tokens(state: &AppState, user: &UserRow) -> AppResult<TokenResponse> {
+    let (token, expires_at) = mint_token(
+        user.id,
+        &user.email,
+        &user.roles,
+        state.config.jwt_secret.as_bytes(),
+        Utc::now(),
+    )?;
+    let refresh_token = Uuid::new_v4().to_string();
+    let mut redis = state.get_redis().await?;
+    let _: () = redis
+        .set_ex(refresh_key(&refresh_token), user.id.to_string(), REFRESH_TTL_SECS)
+        .await
+        .map_err(|e| AppError::ServiceUnavailable(format!("redis: {e}")))?;
+    Ok(TokenResponse { token, expires_at, refresh_token })
+}
+
+/// `POST /api/v1/auth/login`
+pub async fn login(
+    State(state): State<AppState>,
+    Json(request): Json<LoginRequest>,
+) -> AppResult<Json<ApiResponse<TokenResponse>>> {
+    let email = request.email.trim().to_ascii_lowercase();
+    let user: Option<UserRow> = sqlx::query_as(
+        "SELECT id, email, password_hash, roles, disabled, created_at FROM users WHERE email = $1",
+    )
+    .bind(&email)
+    .fetch_optional(&state.db)
+    .await?;
+    // Same error for unknown email and wrong password: don't leak which
+    // addresses have accounts.
+    let user = user.ok_or_else(|| AppError::Auth("invalid email or password".into()))?;
+
+    let hash = user.password_hash.clone();
+    let password = request.password;
+    let verified = tokio::task::spawn_blocking(move || bcrypt::verify(password, &hash))
+        .await
+        .map_err(|e| AppError::Internal(format!("verify task: {e}")))?
+        .unwrap_or(false);
+    if !verified {
+        return Err(AppError::Auth("invalid email or password".into()));
+    }
+    // Checked after password verification so a disabled account still
+    // doesn't reveal whether a guessed password was right.
+    if user.disabled {
+        return Err(AppError::Forbidden("account disabled".into()));
+    }
+
+    Ok(Json(ApiResponse::ok(issue_tokens(&state, &user).await?)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RefreshRequest {
+    pub refresh_token: String,
+}
+
+/// `POST /api/v1/auth/refresh` — exchange a live refresh token for a fresh
+/// access token and a rotated refresh token.
+pub async fn refresh(
+    State(state): State<AppState>,
+    Json(request): Json<RefreshRequest>,
+) -> AppResult<Json<ApiResponse<TokenResponse>>> {
+    let mut redis = state.get_redis().await?;
+    let user_id: Option<String> = redis
+        .get_del(refresh_key(&request.refresh_token))
+        .await
+        .map_err(|e| AppError::ServiceUnavailable(format!("redis: {e}")))?;
+    let user_id: Uuid = user_id
+        .as_deref()
+        .and_then(|id| id.parse().ok())
+        .ok_or_else(|| AppError::Auth("invalid or expired refresh token".into()))?;
+
+    let user: Option<UserRow> = sqlx::query_as(
+        "SELECT id, email, password_hash, roles, disabled, created_at FROM users WHERE id = $1",
+    )
+    .bind(user_id)
+    .fetch_optional(&state.db)
+    .await?;
+    let user = user.ok_or_else(|| AppError::Auth("account no longer exists".into()))?;
+    if user.disabled {
+        return Err(AppError::Forbidden("account disabled".into()));
+    }
+
+    Ok(Json(ApiResponse::ok(issue_tokens(&state, &user).await?)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::middleware::auth::validate_token;
+
+    #[test]
+    fn email_shapes_are_checked() {
+        assert!(validate_email("farmer@example.com").is_ok());
+        assert!(validate_email(" farmer@example.co.th ").is_ok());
+        assert!(validate_email("no-at-sign").is_err());
+        assert!(validate_email("@example.com").is_err());
+        assert!(validate_email("farmer@nodot").is_err());
+        assert!(validate_email("farmer@.com").is_err());
+        assert!(validate_email("a@b@c.com").is_err());
+    }
+
+    #[test]
+    fn short_passwords_are_rejected() {
+        assert!(validate_password("1234567").is_err());
+        assert!(validate_password("12345678").is_ok());
+        assert!(validate_password("รหัสผ่านไทย").is_ok()); // chars, not bytes
+    }
+
+    #[test]
+    fn minted_tokens_round_trip_through_the_middleware() {
+        let user_id = Uuid::new_v4();
+        let now = Utc::now();
+        let (token, expires_at) = mint_token(
+            user_id,
+            "farmer@example.com",
+            &["farmer".to_string()],
+            b"secret",
+            now,
+        )
+        .unwrap();
+        assert_eq!(expires_at, now + ACCESS_TTL);
+
+        let user = validate_token(&token, b"secret").unwrap();
+        assert_eq!(user.user_id, user_id);
+        assert_eq!(user.email, "farmer@example.com");
+        assert!(user.has_role("farmer"));
+    }
+}
@@ -0,0 +1,736 @@
+//! Chat endpoints: send a message to the LLM and read back history.
+//!
+//! History lives in Postgres (`conversations` / `messages`); Redis only
+//! holds the per-conversation crop-context pin, which is cheap to lose.
+
+use std::convert::Infallible;
+
+use axum::{
+    extract::{
+        ws::{Message as WsMessage, WebSocket, WebSocketUpgrade},
+        Query, State,
+    },
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        Response,
+    },
+    Json,
+};
+use chrono::Utc;
+use futures_util::{Stream, StreamExt};
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+use shared::{
+    models::{ChatMessage, CropType, Language, LLMResponse, MessageRole},
+    types::ApiResponse,
+};
+use uuid::Uuid;
+
+use crate::{
+    errors::{AppError, AppResult},
+    middleware::request_context::RequestContext,
+    shared::pagination::{Cursor, Page, SortDirection},
+    state::AppState,
+    AuthUser,
+};
+
+fn crop_context_key(conversation_id: Uuid) -> String {
+    format!("conversation:{conversation_id}:crop_context")
+}
+
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+pub struct SendMessageRequest {
+    pub conversation_id: Option<Uuid>,
+    pub message: String,
+    #[serde(default)]
+    pub language: Language,
+    /// Crop pinned to this conversation so follow-ups ("it", "the leaves")
+    /// keep their referent. Sent by the client and persisted server-side.
+    pub crop_context: Option<CropType>,
+}
+
+/// Prefix the user's message with the pinned crop so the LLM answers about
+/// the right plant even for pronoun-only follow-ups.
+fn build_prompt(message: &str, crop_context: Option<CropType>) -> String {
+    match crop_context {
+        Some(crop) => format!(
+            "[context: the farmer is asking about their {} crop]\n{message}",
+            crop.as_str()
+        ),
+        None => message.to_string(),
+    }
+}
+
+/// Request-supplied pin wins and is persisted; otherwise fall back to the pin
+/// stored on the conversation.
+async fn resolve_crop_context(
+    redis: &mut redis::aio::MultiplexedConnection,
+    conversation_id: Uuid,
+    requested: Option<CropType>,
+) -> AppResult<Option<CropType>> {
+    match requested {
+        Some(crop) => {
+            let _: () = redis
+                .set(crop_context_key(conversation_id), crop.as_str())
+                .await
+                .map_err(|e| AppError::ServiceUnavailable(format!("redis: {e}")))?;
+            Ok(Some(crop))
+        }
+        None => {
+            let stored: Option<String> = redis
+                .get(crop_context_key(conversation_id))
+                .await
+                .map_err(|e| AppError::ServiceUnavailable(format!("redis: {e}")))?;
+            Ok(stored.and_then(|s| serde_json::from_value(serde_json::Value::String(s)).ok()))
+        }
+    }
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct SendMessageResponse {
+    pub conversation_id: Uuid,
+    pub reply: ChatMessage,
+}
+
+/// `POST /api/v1/chat` — forward the user's message to the LLM service and
+/// append both sides to the conversation history.
+#[utoipa::path(
+    post,
+    path = "/api/v1/chat",
+    operation_id = "sendChatMessage",
+    tag = "chat",
+    request_body = SendMessageRequest,
+    responses(
+        (status = 200, body = ApiResponse<SendMessageResponse>),
+        (status = 400, description = "empty message", body = crate::docs::ErrorBody),
+        (status = 401, body = crate::docs::ErrorBody)
+    ),
+    security(("bearer_jwt" = []))
+)]
+pub async fn send_message(
+    State(state): State<AppState>,
+    ctx: RequestContext,
+    Json(request): Json<SendMessageRequest>,
+) -> AppResult<Json<ApiResponse<SendMessageResponse>>> {
+    let user = ctx.require_user()?.clone();
+    if request.message.trim().is_empty() {
+        return Err(AppError::Validation("message must not be empty".into()));
+    }
+    state
+        .quotas
+        .admit(Some(&user), crate::services::quota::UsageKind::ChatMessage)
+        .await?;
+    let conversation_id = request.conversation_id.unwrap_or_else(Uuid::new_v4);
+
+    let mut redis = state.get_redis().await?;
+    let crop_context =
+        resolve_crop_context(&mut redis, conversation_id, request.crop_context).await?;
+
+    let prompt = build_prompt(&request.message, crop_context);
+    let llm_response = state
+        .services
+        .llm
+        .completion(&prompt, request.language)
+        .await?;
+
+    let user_message = ChatMessage {
+        id: None,
+        conversation_id: None,
+        role: MessageRole::User,
+        content: request.message,
+        image_url: None,
+        created_at: Utc::now(),
+    };
+    let reply = ChatMessage {
+        id: None,
+        conversation_id: None,
+        role: MessageRole::Assistant,
+        content: llm_response.advice,
+        image_url: None,
+        created_at: Utc::now(),
+    };
+
+    let mut messages = [user_message, reply];
+    state
+        .conversations
+        .add_exchange(conversation_id, user.user_id, &mut messages)
+        .await?;
+    invalidate_history_cache(&state, conversation_id, user.user_id).await;
+    let [_, reply] = messages;
+
+    Ok(Json(ApiResponse::ok(SendMessageResponse {
+        conversation_id,
+        reply,
+    })))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WsAuthParams {
+    pub token: String,
+}
+
+/// Final frame sent once the LLM stream completes, so clients know the
+/// message is finished rather than stalled.
+fn done_frame(conversation_id: Uuid) -> String {
+    serde_json::json!({ "done": true, "conversation_id": conversation_id }).to_string()
+}
+
+/// `GET /api/v1/chat/ws` — WebSocket upgrade for streaming chat. Browsers
+/// cannot set headers on upgrade requests, so the bearer token travels as a
+/// `token` query parameter and is validated before the handshake completes.
+#[utoipa::path(
+    get,
+    path = "/api/v1/chat/ws",
+    operation_id = "chatWebSocket",
+    tag = "chat",
+    params(("token" = String, Query, description = "JWT, since browsers cannot set WS headers")),
+    responses((status = 101, description = "switching protocols to the chat socket"))
+)]
+pub async fn chat_ws(
+    State(state): State<AppState>,
+    Query(params): Query<WsAuthParams>,
+    ws: WebSocketUpgrade,
+) -> AppResult<Response> {
+    let user =
+        crate::middleware::auth::validate_token(&params.token, state.config.jwt_secret.as_bytes())?;
+    Ok(ws.on_upgrade(move |socket| handle_chat_socket(socket, state, user)))
+}
+
+async fn handle_chat_socket(mut socket: WebSocket, state: AppState, user: crate::AuthUser) {
+    tracing::debug!(user_id = %user.user_id, "chat websocket connected");
+
+    // The first text frame carries the same payload as `POST /api/v1/chat`.
+    let request: SendMessageRequest = loop {
+        match socket.recv().await {
+            Some(Ok(WsMessage::Text(text))) => match serde_json::from_str(&text) {
+                Ok(request) => break request,
+                Err(e) => {
+                    let frame = serde_json::json!({ "error": format!("invalid request: {e}") });
+                    let _ = socket.send(WsMessage::Text(frame.to_string())).await;
+                    return;
+                }
+            },
+            // Pings are answered by axum; ignore anything else non-text.
+            Some(Ok(_)) => continue,
+            _ => return, // disconnected before sending a message
+        }
+    };
+
+    if let Err(e) = stream_reply(&mut socket, &state, &user, request).await {
+        tracing::warn!(error = %e, "chat stream failed");
+        let frame = serde_json::json!({ "error": e.to_string() });
+        let _ = socket.send(WsMessage::Text(frame.to_string())).await;
+    }
+}
+
+/// Forward LLM tokens to the client as they arrive, then persist the full
+/// exchange. Returning early on a failed send drops the upstream stream,
+/// which cancels the in-flight LLM request.
+async fn stream_reply(
+    socket: &mut WebSocket,
+    state: &AppState,
+    user: &AuthUser,
+    request: SendMessageRequest,
+) -> AppResult<()> {
+    if request.message.trim().is_empty() {
+        return Err(AppError::Validation("message must not be empty".into()));
+    }
+    // Same monthly quota as the HTTP chat endpoints — the socket is not a
+    // side door around it.
+    state
+        .quotas
+        .admit(Some(user), crate::services::quota::UsageKind::ChatMessage)
+        .await?;
+    let conversation_id = request.conversation_id.unwrap_or_else(Uuid::new_v4);
+
+    let mut redis = state.get_redis().await?;
+    let crop_context =
+        resolve_crop_context(&mut redis, conversation_id, request.crop_context).await?;
+    let prompt = build_prompt(&request.message, crop_context);
+
+    let stream = state
+        .services
+        .llm
+        .stream_completion(&prompt, request.language)
+        .await?;
+    let mut stream = std::pin::pin!(stream);
+
+    let mut full_reply = String::new();
+    while let Some(chunk) = stream.next().await {
+        full_reply.push_str(&chunk);
+        if socket.send(WsMessage::Text(chunk)).await.is_err() {
+            tracing::debug!("chat websocket client disconnected mid-stream");
+            return Ok(());
+        }
+    }
+    let _ = socket.send(WsMessage::Text(done_frame(conversation_id))).await;
+
+    let user_message = ChatMessage {
+        id: None,
+        conversation_id: None,
+        role: MessageRole::User,
+        content: request.message,
+        image_url: None,
+        created_at: Utc::now(),
+    };
+    let reply = ChatMessage {
+        id: None,
+        conversation_id: None,
+        role: MessageRole::Assistant,
+        content: full_reply,
+        image_url: None,
+        created_at: Utc::now(),
+    };
+    let mut messages = [user_message, reply];
+    state
+        .conversations
+        .add_exchange(conversation_id, user.user_id, &mut messages)
+        .await?;
+    invalidate_history_cache(&state, conversation_id, user.user_id).await;
+    Ok(())
+}
+
+/// Payload of the SSE `done` event: the complete response plus the
+/// conversation id so first-message clients learn which conversation to
+/// continue.
+fn done_payload(conversation_id: Uuid, response: &LLMResponse) -> String {
+    serde_json::json!({ "conversation_id": conversation_id, "response": response }).to_string()
+}
+
+/// `POST /api/v1/chat/stream` — same contract as `POST /api/v1/chat`, but
+/// the reply streams back as SSE: `token` events as chunks arrive, then one
+/// `done` event carrying the complete [`LLMResponse`]. A stream that ends
+/// without yielding anything becomes an `error` event instead of a silent
+/// close, so clients can distinguish "no advice" from a dropped upstream.
+#[utoipa::path(
+    post,
+    path = "/api/v1/chat/stream",
+    operation_id = "streamChatMessage",
+    tag = "chat",
+    request_body = SendMessageRequest,
+    responses(
+        (status = 200, description = "SSE stream of token events, then one done event", content_type = "text/event-stream"),
+        (status = 400, body = crate::docs::ErrorBody),
+        (status = 401, body = crate::docs::ErrorBody)
+    ),
+    security(("bearer_jwt" = []))
+)]
+pub async fn stream_message(
+    State(state): State<AppState>,
+    ctx: RequestContext,
+    Json(request): Json<SendMessageRequest>,
+) -> AppResult<Sse<impl Stream<Item = Result<Event, Infallible>>>> {
+    let user = ctx.require_user()?.clone();
+    if request.message.trim().is_empty() {
+        return Err(AppError::Validation("message must not be empty".into()));
+    }
+    state
+        .quotas
+        .admit(Some(&user), crate::services::quota::UsageKind::ChatMessage)
+        .await?;
+    let conversation_id = request.conversation_id.unwrap_or_else(Uuid::new_v4);
+
+    let mut redis = state.get_redis().await?;
+    let crop_context =
+        resolve_crop_context(&mut redis, conversation_id, request.crop_context).await?;
+    let prompt = build_prompt(&request.message, crop_context);
+    let language = request.language;
+
+    let upstream = state
+        .services
+        .llm
+        .stream_completion(&prompt, language)
+        .await?;
+
+    let ctx = (state, user, request.message, language);
+    let stream = futures_util::stream::unfold(
+        (Box::pin(upstream), String::new(), Some(ctx)),
+        move |(mut upstream, mut buffer, ctx)| async move {
+            let ctx = ctx?; // final event already emitted
+            match upstream.next().await {
+                Some(chunk) => {
+                    buffer.push_str(&chunk);
+                    let event = Event::default().event("token").data(chunk);
+                    Some((Ok(event), (upstream, buffer, Some(ctx))))
+                }
+                None if buffer.is_empty() => {
+                    // The upstream died before producing anything.
+                    let event = Event::default()
+                        .event("error")
+                        .data("llm stream ended without output");
+                    Some((Ok(event), (upstream, buffer, None)))
+                }
+                None => {
+                    let (state, user, message, language) = ctx;
+                    let response = LLMResponse {
+                        advice: buffer.clone(),
+                        language,
+                        model: "ai4thai-llm".into(),
+                        generated_at: Utc::now(),
+                    };
+                    let event = Event::default()
+                        .event("done")
+                        .data(done_payload(conversation_id, &response));
+
+                    // Persist in the background; the client already has the
+                    // full text on screen.
+                    let mut messages = [
+                        ChatMessage {
+                            id: None,
+                            conversation_id: None,
+                            role: MessageRole::User,
+                            content: message,
+                            image_url: None,
+                            created_at: Utc::now(),
+                        },
+                        ChatMessage {
+                            id: None,
+                            conversation_id: None,
+                            role: MessageRole::Assistant,
+                            content: response.advice,
+                            image_url: None,
+                            created_at: Utc::now(),
+                        },
+                    ];
+                    tokio::spawn(async move {
+                        if let Err(e) = state
+                            .conversations
+                            .add_exchange(conversation_id, user.user_id, &mut messages)
+                            .await
+                        {
+                            tracing::warn!(error = %e, "persist streamed exchange failed");
+                        }
+                        invalidate_history_cache(&state, conversation_id, user.user_id).await;
+                    });
+                    Some((Ok(event), (upstream, buffer, None)))
+                }
+            }
+        },
+    );
+
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+pub struct SetCropContextRequest {
+    pub crop_context: CropType,
+}
+
+/// `PUT /api/v1/chat/:conversation_id/context` — pin a crop to the
+/// conversation and note the switch as a system message so both sides of a
+/// later reading know which answers refer to which crop.
+#[utoipa::path(
+    put,
+    path = "/api/v1/chat/{conversation_id}/context",
+    operation_id = "setCropContext",
+    tag = "chat",
+    params(("conversation_id" = Uuid, Path)),
+    request_body = SetCropContextRequest,
+    responses(
+        (status = 200, body = ApiResponse<()>),
+        (status = 401, body = crate::docs::ErrorBody)
+    ),
+    security(("bearer_jwt" = []))
+)]
+pub async fn set_crop_context(
+    State(state): State<AppState>,
+    ctx: RequestContext,
+    axum::extract::Path(conversation_id): axum::extract::Path<Uuid>,
+    Json(request): Json<SetCropContextRequest>,
+) -> AppResult<Json<ApiResponse<()>>> {
+    let user = ctx.require_user()?.clone();
+    let mut redis = state.get_redis().await?;
+    let _: () = redis
+        .set(crop_context_key(conversation_id), request.crop_context.as_str())
+        .await
+        .map_err(|e| AppError::ServiceUnavailable(format!("redis: {e}")))?;
+
+    let note = ChatMessage {
+        id: None,
+        conversation_id: None,
+        role: MessageRole::System,
+        content: format!("crop context switched to {}", request.crop_context.as_str()),
+        image_url: None,
+        created_at: Utc::now(),
+    };
+    let mut messages = [note];
+    state
+        .conversations
+        .add_exchange(conversation_id, user.user_id, &mut messages)
+        .await?;
+    invalidate_history_cache(&state, conversation_id, user.user_id).await;
+
+    Ok(Json(ApiResponse::ok(())))
+}
+
+/// `DELETE /api/v1/chat/:conversation_id` — remove a conversation and its
+/// messages. 404 covers both unknown ids and other users' conversations, so
+/// the response doesn't leak which ids exist.
+#[utoipa::path(
+    delete,
+    path = "/api/v1/chat/{conversation_id}",
+    operation_id = "deleteConversation",
+    tag = "chat",
+    params(("conversation_id" = Uuid, Path)),
+    responses(
+        (status = 200, body = ApiResponse<()>),
+        (status = 404, description = "unknown or foreign conversation", body = crate::docs::ErrorBody)
+    ),
+    security(("bearer_jwt" = []))
+)]
+pub async fn delete_conversation(
+    State(state): State<AppState>,
+    ctx: RequestContext,
+    axum::extract::Path(conversation_id): axum::extract::Path<Uuid>,
+) -> AppResult<Json<ApiResponse<()>>> {
+    let user = ctx.require_user()?.clone();
+    let deleted = state
+        .conversations
+        .delete_conversation(conversation_id, user.user_id)
+        .await?;
+    if !deleted {
+        return Err(AppError::NotFound(format!("conversation {conversation_id}")));
+    }
+    Ok(Json(ApiResponse::ok(())))
+}
+
+#[derive(Debug, Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct HistoryParams {
+    pub conversation_id: Uuid,
+    pub limit: Option<i64>,
+    /// Opaque keyset cursor from a previous page's `next_cursor`.
+    pub cursor: Option<String>,
+    /// `asc` (oldest first) or `desc` (default, newest first).
+    pub order: Option<String>,
+    /// Restrict to one role: `user`, `assistant`, or `system`.
+    pub message_type: Option<MessageRole>,
+    /// Message id to page backwards from (exclusive). Predates the opaque
+    /// cursor; kept for clients that stored raw ids.
+    pub before: Option<Uuid>,
+}
+
+const DEFAULT_HISTORY_LIMIT: i64 = 50;
+
+fn clamp_limit(limit: Option<i64>) -> i64 {
+    limit.unwrap_or(DEFAULT_HISTORY_LIMIT).clamp(1, 100)
+}
+
+fn parse_order(raw: Option<&str>) -> AppResult<SortDirection> {
+    match raw {
+        None | Some("desc") => Ok(SortDirection::Desc),
+        Some("asc") => Ok(SortDirection::Asc),
+        Some(other) => Err(AppError::Validation(format!(
+            "order must be 'asc' or 'desc', not '{other}'"
+        ))),
+    }
+}
+
+/// Cache key for a user's newest history page. Scoped to the user as well
+/// as the conversation: the ownership check lives in the SQL join, so a key
+/// shared between users would let a non-owner's empty result shadow the
+/// owner's messages.
+fn history_cache_key(conversation_id: Uuid, user_id: Uuid) -> String {
+    format!("conv:{conversation_id}:u:{user_id}:messages:first")
+}
+
+/// Drop the cached newest page after a write. Deeper keyset pages are never
+/// cached — in an append-only table only the first page changes on insert.
+async fn invalidate_history_cache(state: &AppState, conversation_id: Uuid, user_id: Uuid) {
+    state
+        .cache
+        .invalidate(&history_cache_key(conversation_id, user_id))
+        .await;
+    // Also the whole-response cache: a freshly posted message must show up
+    // on the very next history poll.
+    if let Ok(mut redis) = state.get_redis().await {
+        crate::middleware::response_cache::invalidate(
+            &mut redis,
+            Some(user_id),
+            "/api/v1/chat/history",
+        )
+        .await;
+    }
+}
+
+/// `GET /api/v1/chat/history?conversation_id=&limit=&cursor=&order=&message_type=`
+/// — page through a conversation's messages, keyset-paginated on
+/// `(created_at, id)` so inserts during paging never shift the window.
+/// `next_cursor` is opaque (see `shared::pagination::Cursor`); clients hand
+/// it back verbatim instead of fabricating offsets. `order=asc` reads the
+/// conversation forwards; `message_type` filters to one role.
+#[utoipa::path(
+    get,
+    path = "/api/v1/chat/history",
+    operation_id = "getConversationHistory",
+    tag = "chat",
+    params(HistoryParams),
+    responses(
+        (status = 200, body = ApiResponse<Page<ChatMessage>>),
+        (status = 400, description = "bad cursor or order", body = crate::docs::ErrorBody),
+        (status = 401, body = crate::docs::ErrorBody)
+    ),
+    security(("bearer_jwt" = []))
+)]
+pub async fn get_conversation(
+    State(state): State<AppState>,
+    ctx: RequestContext,
+    Query(params): Query<HistoryParams>,
+) -> AppResult<Json<ApiResponse<Page<ChatMessage>>>> {
+    let user = ctx.require_user()?.clone();
+    let limit = clamp_limit(params.limit);
+    let order = parse_order(params.order.as_deref())?;
+    // Legacy `before` ids resolve to the same cursor shape, so both
+    // parameters share one query path.
+    let cursor = match (&params.cursor, params.before) {
+        (Some(raw), _) => Some(Cursor::decode(raw)?),
+        (None, Some(before)) => Some(state.conversations.message_cursor(before).await?),
+        (None, None) => None,
+    };
+
+    // Only the default first page is cached: it's what the frontend polls,
+    // and it's the only page an insert changes.
+    let is_default_page = cursor.is_none()
+        && params.message_type.is_none()
+        && order == SortDirection::Desc
+        && limit == DEFAULT_HISTORY_LIMIT;
+    let items = if is_default_page {
+        let conversations = state.conversations.clone();
+        let conversation_id = params.conversation_id;
+        let user_id = user.user_id;
+        state
+            .cache
+            .get_or_set(
+                &history_cache_key(conversation_id, user_id),
+                state.cache.default_ttl,
+                async move {
+                    conversations
+                        .get_messages_page(
+                            conversation_id,
+                            user_id,
+                            limit,
+                            None,
+                            SortDirection::Desc,
+                            None,
+                        )
+                        .await
+                },
+            )
+            .await?
+    } else {
+        state
+            .conversations
+            .get_messages_page(
+                params.conversation_id,
+                user.user_id,
+                limit,
+                cursor.as_ref(),
+                order,
+                params.message_type,
+            )
+            .await?
+    };
+
+    Ok(Json(ApiResponse::ok(Page::from_rows(
+        items,
+        limit as u32,
+        |message| Cursor {
+            created_at: message.created_at,
+            id: message.id.expect("persisted messages carry ids"),
+        },
+    ))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prompt_injects_pinned_crop() {
+        let prompt = build_prompt("ใบมันเหลือง ทำไงดี", Some(CropType::Durian));
+        assert!(prompt.starts_with("[context: the farmer is asking about their durian crop]"));
+        assert!(prompt.ends_with("ใบมันเหลือง ทำไงดี"));
+    }
+
+    #[test]
+    fn prompt_without_pin_is_untouched() {
+        assert_eq!(build_prompt("hello", None), "hello");
+    }
+
+    #[test]
+    fn history_limit_is_clamped() {
+        assert_eq!(clamp_limit(None), 50);
+        assert_eq!(clamp_limit(Some(0)), 1);
+        assert_eq!(clamp_limit(Some(1000)), 100);
+        assert_eq!(clamp_limit(Some(20)), 20);
+    }
+
+    #[test]
+    fn order_defaults_to_desc_and_rejects_garbage() {
+        assert_eq!(parse_order(None).unwrap(), SortDirection::Desc);
+        assert_eq!(parse_order(Some("desc")).unwrap(), SortDirection::Desc);
+        assert_eq!(parse_order(Some("asc")).unwrap(), SortDirection::Asc);
+        assert!(matches!(
+            parse_order(Some("sideways")),
+            Err(AppError::Validation(_))
+        ));
+    }
+
+    #[test]
+    fn cursors_stay_stable_when_messages_arrive_between_page_fetches() {
+        let message = |secs: i64| ChatMessage {
+            id: Some(Uuid::new_v4()),
+            conversation_id: None,
+            role: MessageRole::User,
+            content: format!("m{secs}"),
+            image_url: None,
+            created_at: chrono::DateTime::from_timestamp(secs, 0).unwrap(),
+        };
+        // Conversation at fetch time, newest first.
+        let history = vec![message(40), message(30), message(20), message(10)];
+
+        // Page 1: limit 2, so the repository hands back 3 rows.
+        let page = Page::from_rows(history[..3].to_vec(), 2, |m| Cursor {
+            created_at: m.created_at,
+            id: m.id.unwrap(),
+        });
+        let cursor = Cursor::decode(page.next_cursor.as_deref().unwrap()).unwrap();
+
+        // A new message lands before page 2 is fetched. The keyset
+        // predicate `(created_at, id) < cursor` — what
+        // `get_messages_page` pushes into SQL — must still select exactly
+        // the rows after page 1.
+        let mut grown = vec![message(50)];
+        grown.extend(history.iter().cloned());
+        let page2: Vec<&ChatMessage> = grown
+            .iter()
+            .filter(|m| (m.created_at, m.id.unwrap()) < (cursor.created_at, cursor.id))
+            .collect();
+        assert_eq!(page2.len(), 2);
+        assert_eq!(page2[0].content, "m20");
+        assert_eq!(page2[1].content, "m10");
+    }
+
+    #[test]
+    fn sse_done_payload_carries_response_and_conversation() {
+        let id = Uuid::new_v4();
+        let response = LLMResponse {
+            advice: "ใช้เชื้อราไตรโคเดอร์มา".into(),
+            language: Language::Thai,
+            model: "ai4thai-llm".into(),
+            generated_at: Utc::now(),
+        };
+        let payload: serde_json::Value =
+            serde_json::from_str(&done_payload(id, &response)).unwrap();
+        assert_eq!(payload["conversation_id"], id.to_string());
+        assert_eq!(payload["response"]["advice"], "ใช้เชื้อราไตรโคเดอร์มา");
+    }
+
+    #[test]
+    fn done_frame_carries_conversation_id() {
+        let id = Uuid::new_v4();
+        let frame: serde_json::Value = serde_json::from_str(&done_frame(id)).unwrap();
+        assert_eq!(frame["done"], true);
+        assert_eq!(frame["conversation_id"], id.to_string());
+    }
+}
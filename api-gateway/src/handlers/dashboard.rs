@@ -0,0 +1,297 @@
+//! Dashboard trend aggregation, the backend of the frontend trend charts.
+//!
+//! `GET /api/v1/dashboard/trends` buckets the caller's completed diagnoses
+//! into ISO weeks computed in Asia/Bangkok — a job uploaded Sunday 23:30
+//! Bangkok time belongs to that week, not to UTC's next one. Each bucket
+//! runs Monday 00:00 Bangkok (inclusive) to the following Monday 00:00
+//! (exclusive); weeks with no diagnoses are omitted. Responses are cached
+//! per user for five minutes under a generation counter that the advice
+//! stage bumps when a job completes, so a fresh diagnosis shows up on the
+//! next read instead of after the TTL.
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use chrono::{DateTime, Datelike, FixedOffset, NaiveDate, TimeZone, Utc};
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+use shared::{models::CropType, types::ApiResponse};
+use uuid::Uuid;
+
+use crate::{
+    errors::AppResult,
+    middleware::request_context::RequestContext,
+    state::AppState,
+};
+
+/// Asia/Bangkok is UTC+7 year-round with no DST, so a fixed offset is
+/// exact and we avoid a timezone-database dependency.
+const BANGKOK_UTC_OFFSET_SECS: i32 = 7 * 3600;
+
+const DEFAULT_WEEKS: u32 = 12;
+const MAX_WEEKS: u32 = 52;
+const TRENDS_TTL: Duration = Duration::from_secs(300);
+
+fn bangkok() -> FixedOffset {
+    FixedOffset::east_opt(BANGKOK_UTC_OFFSET_SECS).expect("UTC+7 is a valid offset")
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TrendsQuery {
+    pub crop_type: CropType,
+    pub weeks: Option<u32>,
+}
+
+/// One non-empty week. `avg_severity` is null when no diagnosis in the
+/// week carried a severity score.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrendWeek {
+    /// ISO date of the bucket's Monday, in Bangkok local time.
+    pub week_start: NaiveDate,
+    pub avg_severity: Option<f64>,
+    pub count: u64,
+    /// Most frequent disease in the week; ties break alphabetically.
+    pub top_disease: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrendsData {
+    pub crop_type: String,
+    pub weeks: Vec<TrendWeek>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+pub(crate) struct TrendRow {
+    pub created_at: DateTime<Utc>,
+    pub severity_score: Option<f32>,
+    pub disease_name: String,
+}
+
+/// The Monday (Bangkok local date) of the ISO week containing `ts`.
+pub(crate) fn week_start_bangkok(ts: DateTime<Utc>) -> NaiveDate {
+    let local = ts.with_timezone(&bangkok());
+    let date = local.date_naive();
+    date - chrono::Duration::days(i64::from(local.weekday().num_days_from_monday()))
+}
+
+/// UTC instant where the `weeks`-week window begins: Monday 00:00 Bangkok
+/// of the earliest week that can still appear in the response.
+pub(crate) fn window_start_utc(now: DateTime<Utc>, weeks: u32) -> DateTime<Utc> {
+    let first_monday = week_start_bangkok(now) - chrono::Duration::weeks(i64::from(weeks) - 1);
+    bangkok()
+        .from_local_datetime(&first_monday.and_hms_opt(0, 0, 0).expect("midnight exists"))
+        .single()
+        .expect("fixed offsets have no DST gaps")
+        .with_timezone(&Utc)
+}
+
+/// Group rows into ascending weekly buckets. Averages skip rows without a
+/// severity score; the top disease counts each name of a multi-detection
+/// diagnosis (stored comma-joined) separately.
+pub(crate) fn bucket_weeks(rows: &[TrendRow]) -> Vec<TrendWeek> {
+    #[derive(Default)]
+    struct Bucket {
+        severities: Vec<f64>,
+        count: u64,
+        diseases: BTreeMap<String, u32>,
+    }
+
+    let mut buckets: BTreeMap<NaiveDate, Bucket> = BTreeMap::new();
+    for row in rows {
+        let bucket = buckets.entry(week_start_bangkok(row.created_at)).or_default();
+        bucket.count += 1;
+        if let Some(severity) = row.severity_score {
+            bucket.severities.push(f64::from(severity));
+        }
+        for disease in row.disease_name.split(", ").filter(|d| !d.is_empty()) {
+            *bucket.diseases.entry(disease.to_string()).or_default() += 1;
+        }
+    }
+
+    buckets
+        .into_iter()
+        .map(|(week_start, bucket)| {
+            let avg_severity = (!bucket.severities.is_empty()).then(|| {
+                bucket.severities.iter().sum::<f64>() / bucket.severities.len() as f64
+            });
+            // `max_by` keeps the *last* maximum, so break count ties by
+            // reversed name to land on the alphabetically first disease.
+            let top_disease = bucket
+                .diseases
+                .iter()
+                .max_by(|a, b| a.1.cmp(b.1).then_with(|| b.0.cmp(a.0)))
+                .map(|(name, _)| name.clone());
+            TrendWeek {
+                week_start,
+                avg_severity,
+                count: bucket.count,
+                top_disease,
+            }
+        })
+        .collect()
+}
+
+fn generation_key(user_id: Uuid) -> String {
+    format!("dashboard:trends:{user_id}:gen")
+}
+
+pub(crate) fn cache_key(user_id: Uuid, generation: u64, crop_type: CropType, weeks: u32) -> String {
+    format!(
+        "dashboard:trends:{user_id}:{generation}:{}:{weeks}",
+        crop_type.as_str()
+    )
+}
+
+async fn current_generation(state: &AppState, user_id: Uuid) -> u64 {
+    let Ok(mut redis) = state.get_redis().await else {
+        return 0;
+    };
+    redis
+        .get::<_, Option<u64>>(generation_key(user_id))
+        .await
+        .ok()
+        .flatten()
+        .unwrap_or(0)
+}
+
+/// Bump the user's trends generation so the next read recomputes; called
+/// by the advice stage when a job completes. Best effort — stale entries
+/// age out via their TTL anyway.
+pub async fn invalidate_trends(state: &AppState, user_id: Uuid) {
+    if let Ok(mut redis) = state.get_redis().await {
+        let _: Result<u64, _> = redis.incr(generation_key(user_id), 1).await;
+    }
+}
+
+/// `GET /api/v1/dashboard/trends?crop_type&weeks` — weekly severity and
+/// volume buckets for the caller's own diagnoses, newest window last.
+pub async fn get_trends(
+    State(state): State<AppState>,
+    ctx: RequestContext,
+    Query(query): Query<TrendsQuery>,
+) -> AppResult<Json<ApiResponse<TrendsData>>> {
+    let user_id = ctx.require_user()?.user_id;
+    let weeks = query.weeks.unwrap_or(DEFAULT_WEEKS).clamp(1, MAX_WEEKS);
+    let generation = current_generation(&state, user_id).await;
+    let key = cache_key(user_id, generation, query.crop_type, weeks);
+
+    let data = state
+        .cache
+        .get_or_set(&key, TRENDS_TTL, async {
+            let cutoff = window_start_utc(Utc::now(), weeks);
+            let rows: Vec<TrendRow> = sqlx::query_as(
+                "SELECT created_at, severity_score, disease_name FROM diagnoses \
+                 WHERE user_id = $1 AND crop_type = $2 AND created_at >= $3",
+            )
+            .bind(user_id)
+            .bind(query.crop_type.as_str())
+            .bind(cutoff)
+            .fetch_all(&state.db)
+            .await?;
+            Ok(TrendsData {
+                crop_type: query.crop_type.as_str().to_string(),
+                weeks: bucket_weeks(&rows),
+            })
+        })
+        .await?;
+    Ok(Json(ApiResponse::ok(data)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(s: &str) -> DateTime<Utc> {
+        s.parse().unwrap()
+    }
+
+    fn row(created_at: &str, severity: Option<f32>, disease: &str) -> TrendRow {
+        TrendRow {
+            created_at: utc(created_at),
+            severity_score: severity,
+            disease_name: disease.into(),
+        }
+    }
+
+    #[test]
+    fn bangkok_week_boundary_is_sunday_midnight_utc_plus_seven() {
+        // 2026-06-07 is a Sunday. 16:59:59 UTC is 23:59:59 Bangkok — still
+        // the old week; one second later it's Monday 00:00 Bangkok.
+        assert_eq!(
+            week_start_bangkok(utc("2026-06-07T16:59:59Z")),
+            "2026-06-01".parse::<NaiveDate>().unwrap()
+        );
+        assert_eq!(
+            week_start_bangkok(utc("2026-06-07T17:00:00Z")),
+            "2026-06-08".parse::<NaiveDate>().unwrap()
+        );
+    }
+
+    #[test]
+    fn jobs_straddling_the_boundary_land_in_different_buckets() {
+        let rows = vec![
+            row("2026-06-07T16:30:00Z", Some(0.4), "rice_blast"),
+            row("2026-06-07T17:30:00Z", Some(0.8), "rice_blast"),
+        ];
+        let weeks = bucket_weeks(&rows);
+        assert_eq!(weeks.len(), 2);
+        assert_eq!(weeks[0].week_start.to_string(), "2026-06-01");
+        assert_eq!(weeks[1].week_start.to_string(), "2026-06-08");
+        assert_eq!(weeks[0].count, 1);
+        assert_eq!(weeks[1].avg_severity, Some(f64::from(0.8f32)));
+    }
+
+    #[test]
+    fn averages_skip_missing_severities_and_empty_weeks_are_omitted() {
+        let rows = vec![
+            row("2026-06-01T03:00:00Z", Some(0.2), "rice_blast"),
+            row("2026-06-02T03:00:00Z", None, "healthy"),
+            row("2026-06-03T03:00:00Z", Some(0.6), "brown_spot, rice_blast"),
+            // Two weeks later: the empty week in between must not appear.
+            row("2026-06-16T03:00:00Z", None, "healthy"),
+        ];
+        let weeks = bucket_weeks(&rows);
+        assert_eq!(weeks.len(), 2);
+        assert_eq!(weeks[0].count, 3);
+        assert_eq!(
+            weeks[0].avg_severity,
+            Some((f64::from(0.2f32) + f64::from(0.6f32)) / 2.0)
+        );
+        // rice_blast appears twice (once via the comma-joined pair).
+        assert_eq!(weeks[0].top_disease.as_deref(), Some("rice_blast"));
+        assert_eq!(weeks[1].avg_severity, None);
+    }
+
+    #[test]
+    fn top_disease_ties_break_alphabetically() {
+        let rows = vec![
+            row("2026-06-01T03:00:00Z", None, "rice_blast"),
+            row("2026-06-02T03:00:00Z", None, "brown_spot"),
+        ];
+        let weeks = bucket_weeks(&rows);
+        assert_eq!(weeks[0].top_disease.as_deref(), Some("brown_spot"));
+    }
+
+    #[test]
+    fn window_start_is_monday_midnight_bangkok() {
+        // From a mid-week instant, a 2-week window opens at the previous
+        // week's Monday 00:00 Bangkok = Sunday 17:00 UTC.
+        let start = window_start_utc(utc("2026-06-10T12:00:00Z"), 2);
+        assert_eq!(start, utc("2026-05-31T17:00:00Z"));
+    }
+
+    #[test]
+    fn completing_a_job_changes_the_cache_key() {
+        let user = Uuid::new_v4();
+        let before = cache_key(user, 0, CropType::Rice, 12);
+        let after = cache_key(user, 1, CropType::Rice, 12);
+        assert_ne!(before, after);
+        // Other users and other windows never share entries.
+        assert_ne!(before, cache_key(Uuid::new_v4(), 0, CropType::Rice, 12));
+        assert_ne!(before, cache_key(user, 0, CropType::Rice, 4));
+    }
+}
@@ -0,0 +1,287 @@
+//! Liveness, readiness, and metrics endpoints.
+
+use std::time::Duration;
+
+use axum::{extract::State, http::StatusCode, Json};
+use serde_json::{json, Value};
+
+use crate::{
+    errors::{AppError, AppResult},
+    state::AppState,
+};
+
+/// Individual dependency probes must answer within this budget so one hung
+/// dependency can't stall the whole readiness response.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Liveness: the process is up and serving.
+#[utoipa::path(
+    get,
+    path = "/health",
+    operation_id = "healthCheck",
+    tag = "health",
+    responses((status = 200, description = "process is up"))
+)]
+pub async fn health_check() -> Json<Value> {
+    Json(json!({ "status": "ok" }))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ProbeStatus {
+    Ok,
+    Degraded,
+}
+
+impl ProbeStatus {
+    fn as_str(&self) -> &'static str {
+        match self {
+            ProbeStatus::Ok => "ok",
+            ProbeStatus::Degraded => "degraded",
+        }
+    }
+}
+
+async fn probe<F>(future: F) -> ProbeStatus
+where
+    F: std::future::Future<Output = bool>,
+{
+    match tokio::time::timeout(PROBE_TIMEOUT, future).await {
+        Ok(true) => ProbeStatus::Ok,
+        _ => ProbeStatus::Degraded,
+    }
+}
+
+/// Readiness for Kubernetes: actually pings Postgres, Redis, and RabbitMQ,
+/// returning a per-dependency status map with 200 only when all are healthy.
+#[utoipa::path(
+    get,
+    path = "/health/ready",
+    operation_id = "readinessCheck",
+    tag = "health",
+    responses(
+        (status = 200, description = "all dependencies healthy"),
+        (status = 503, description = "one or more dependencies degraded")
+    )
+)]
+pub async fn readiness_check(State(state): State<AppState>) -> (StatusCode, Json<Value>) {
+    // Once shutdown begins, tell the load balancer to stop sending traffic
+    // immediately — the dependencies may all still be healthy, but this
+    // instance is draining.
+    if state.is_shutting_down() {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(json!({ "status": "shutting_down" })),
+        );
+    }
+    let postgres = probe(async {
+        sqlx::query("SELECT 1").execute(&state.db).await.is_ok()
+    })
+    .await;
+
+    let redis = probe(async {
+        match state.get_redis().await {
+            Ok(mut conn) => redis::cmd("PING")
+                .query_async::<_, String>(&mut conn)
+                .await
+                .is_ok(),
+            Err(_) => false,
+        }
+    })
+    .await;
+
+    let rabbitmq = probe(async { state.rabbitmq.is_open() }).await;
+
+    // Which flags are defined, so an operator checking readiness also sees
+    // what runtime behavior is switched in. Names only — rollout details
+    // live behind the admin API.
+    let feature_flags = match state.feature_flags.all().await {
+        Ok(flags) => json!(flags.keys().cloned().collect::<Vec<_>>()),
+        Err(_) => json!("unavailable"),
+    };
+
+    let all_ok = [postgres, redis, rabbitmq]
+        .iter()
+        .all(|s| *s == ProbeStatus::Ok);
+    let status = if all_ok {
+        StatusCode::OK
+    } else {
+        let alerts = state.alerts.clone();
+        let message = format!(
+            "readiness degraded: postgres={} redis={} rabbitmq={}",
+            postgres.as_str(),
+            redis.as_str(),
+            rabbitmq.as_str()
+        );
+        tokio::spawn(async move {
+            alerts
+                .send(crate::services::alerts::Alert {
+                    severity: crate::services::alerts::Severity::Critical,
+                    event_type: "readiness_failed",
+                    message,
+                    link: Some("/health/ready".into()),
+                })
+                .await;
+        });
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (
+        status,
+        Json(json!({
+            "postgres": postgres.as_str(),
+            "redis": redis.as_str(),
+            "rabbitmq": rabbitmq.as_str(),
+            "vision_breaker": state.services.vision_breaker.state_name(),
+            "llm_breaker": state.services.llm_breaker.state_name(),
+            "featureFlags": feature_flags,
+        })),
+    )
+}
+
+/// How long one queue-stats reading is served before the broker is asked
+/// again; dashboards poll this endpoint every second or two.
+const QUEUE_STATS_CACHE_TTL: Duration = Duration::from_secs(5);
+
+/// Pending-set entries older than this are presumed dead (lost outside a
+/// gateway-side terminal transition) and pruned rather than reported as
+/// an ever-growing oldest age.
+const PENDING_JOB_MAX_AGE_SECS: i64 = 24 * 60 * 60;
+
+/// In-process cache for [`queue_metrics`]; one reading per TTL window no
+/// matter how many dashboards poll.
+static QUEUE_STATS_CACHE: std::sync::OnceLock<
+    tokio::sync::Mutex<Option<(std::time::Instant, Value)>>,
+> = std::sync::OnceLock::new();
+
+/// Age in seconds of the oldest job still in the pending sorted set (see
+/// `metrics::PENDING_JOBS_KEY`), pruning presumed-dead entries first.
+async fn oldest_pending_age_secs(state: &AppState) -> Option<i64> {
+    use redis::AsyncCommands;
+
+    let mut redis = state.get_redis().await.ok()?;
+    let now = chrono::Utc::now().timestamp();
+    let _: Result<(), _> = redis
+        .zrembyscore(
+            crate::metrics::PENDING_JOBS_KEY,
+            i64::MIN,
+            now - PENDING_JOB_MAX_AGE_SECS,
+        )
+        .await;
+    let oldest: Vec<(String, i64)> = redis
+        .zrange_withscores(crate::metrics::PENDING_JOBS_KEY, 0, 0)
+        .await
+        .ok()?;
+    oldest.first().map(|(_, queued)| (now - queued).max(0))
+}
+
+/// `GET /health/queues` — RabbitMQ backlog visibility without the broker
+/// console: message and consumer counts for the vision queue and DLQ,
+/// plus the age of the oldest pending job. Readings are cached briefly,
+/// and an unreachable broker degrades to partial data with a `warning`
+/// field instead of a failed endpoint.
+#[utoipa::path(
+    get,
+    path = "/health/queues",
+    operation_id = "queueMetrics",
+    tag = "health",
+    responses((status = 200, description = "queue depth and consumer counts"))
+)]
+pub async fn queue_metrics(State(state): State<AppState>) -> Json<Value> {
+    let cache = QUEUE_STATS_CACHE.get_or_init(|| tokio::sync::Mutex::new(None));
+    let mut cached = cache.lock().await;
+    if let Some((read_at, body)) = cached.as_ref() {
+        if read_at.elapsed() < QUEUE_STATS_CACHE_TTL {
+            return Json(body.clone());
+        }
+    }
+
+    let mut warnings = Vec::new();
+    let mut queue_json = |name: &str, stats: AppResult<crate::services::rabbitmq::QueueStats>| {
+        match stats {
+            Ok(stats) => json!({
+                "name": name,
+                "messages": stats.messages,
+                "consumers": stats.consumers,
+            }),
+            Err(e) => {
+                warnings.push(format!("{name}: {e}"));
+                json!({ "name": name })
+            }
+        }
+    };
+    let vision_name = state.rabbitmq.vision_queue().to_string();
+    let dlq_name = state.rabbitmq.dlq().to_string();
+    let vision = queue_json(&vision_name, state.rabbitmq.queue_stats(&vision_name).await);
+    let dlq = queue_json(&dlq_name, state.rabbitmq.queue_stats(&dlq_name).await);
+
+    let mut body = json!({
+        "vision_queue": vision,
+        "dlq": dlq,
+        "oldest_pending_job_seconds": oldest_pending_age_secs(&state).await,
+    });
+    if !warnings.is_empty() {
+        body["warning"] = json!(warnings.join("; "));
+    }
+    *cached = Some((std::time::Instant::now(), body.clone()));
+    Json(body)
+}
+
+/// `GET /health/metrics` — Prometheus exposition text. When
+/// `server.metrics_auth_token` is configured, scrapes must present it as a
+/// Bearer token so queue depths and traffic shapes aren't world-readable.
+#[utoipa::path(
+    get,
+    path = "/health/metrics",
+    operation_id = "prometheusMetrics",
+    tag = "health",
+    responses(
+        (status = 200, description = "Prometheus exposition text", content_type = "text/plain"),
+        (status = 401, description = "missing or wrong metrics token", body = crate::docs::ErrorBody)
+    ),
+    security(("bearer_jwt" = []))
+)]
+pub async fn metrics(
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+) -> AppResult<String> {
+    if let Some(expected) = &state.config.server.metrics_auth_token {
+        let provided = headers
+            .get(axum::http::header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "));
+        if provided != Some(expected.as_str()) {
+            return Err(AppError::Auth("metrics token required".into()));
+        }
+    }
+    Ok(state.metrics.render())
+}
+
+/// `GET /health/metrics.json` — the ad-hoc JSON metrics shape that predates
+/// the Prometheus exporter. The ops dashboard still polls it, so it stays
+/// until that dashboard reads the exposition format instead.
+#[utoipa::path(
+    get,
+    path = "/health/metrics.json",
+    operation_id = "jsonMetrics",
+    tag = "health",
+    responses((status = 200, description = "legacy JSON counters"))
+)]
+pub async fn metrics_json(State(state): State<AppState>) -> Json<Value> {
+    let client_versions = state
+        .client_version_counts
+        .lock()
+        .expect("version counter lock poisoned")
+        .clone();
+    let counters = state.counters.lock().expect("counter lock poisoned").clone();
+    let last_correlation_id = state
+        .last_correlation_id
+        .lock()
+        .expect("correlation lock poisoned")
+        .clone();
+    Json(json!({
+        "metrics": {
+            "requests_by_client_version": client_versions,
+            "counters": counters,
+            "last_correlation_id": last_correlation_id,
+        }
+    }))
+}
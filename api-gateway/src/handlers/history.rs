@@ -0,0 +1,210 @@
+//! Analysis history over the `analysis_audit` table.
+//!
+//! Every completed vision job leaves an audit row (written by the advice
+//! stage alongside the `diagnoses` summary). Farmers see their own rows;
+//! extension officers — who review what diagnoses were issued to which
+//! farmers — see everyone's and can narrow to one user.
+
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use shared::{models::CropType, types::ApiResponse};
+use sqlx::{Postgres, QueryBuilder};
+use uuid::Uuid;
+
+use crate::{
+    errors::{AppError, AppResult},
+    middleware::request_context::RequestContext,
+    shared::pagination::{OffsetParams, PaginatedResponse},
+    state::AppState,
+};
+
+#[derive(Debug, Default, Deserialize)]
+pub struct HistoryParams {
+    pub crop_type: Option<CropType>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    /// Keep only rows whose top detection reached this confidence (0–1).
+    pub min_confidence: Option<f32>,
+    /// Narrow to one farmer's records; only honored for officers, everyone
+    /// else is pinned to their own scope regardless.
+    pub user_id: Option<Uuid>,
+    #[serde(flatten)]
+    pub page: OffsetParams,
+}
+
+/// Whose rows a caller may see: farmers only their own, officers (and
+/// admins) everyone's unless they narrow to one user.
+pub fn history_scope(user: &crate::AuthUser, params: &HistoryParams) -> Option<Uuid> {
+    if user.has_role("officer") || user.has_role("admin") {
+        params.user_id
+    } else {
+        Some(user.user_id)
+    }
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct AuditRow {
+    pub job_id: Uuid,
+    pub user_id: Option<Uuid>,
+    pub request_id: Option<String>,
+    pub crop_type: String,
+    pub disease_name: String,
+    pub confidence: Option<f32>,
+    pub severity_score: Option<f32>,
+    pub requested_at: Option<DateTime<Utc>>,
+    pub completed_at: DateTime<Utc>,
+}
+
+/// Shared WHERE clause for the count and page queries: the visibility
+/// scope plus the optional crop, date-range, and confidence filters.
+fn push_filters(
+    qb: &mut QueryBuilder<'_, Postgres>,
+    scope_user: Option<Uuid>,
+    params: &HistoryParams,
+) {
+    qb.push(" WHERE TRUE");
+    if let Some(user_id) = scope_user {
+        qb.push(" AND user_id = ").push_bind(user_id);
+    }
+    if let Some(crop_type) = params.crop_type {
+        qb.push(" AND crop_type = ").push_bind(crop_type.as_str());
+    }
+    if let Some(from) = params.from {
+        qb.push(" AND completed_at >= ").push_bind(from);
+    }
+    if let Some(to) = params.to {
+        qb.push(" AND completed_at < ").push_bind(to);
+    }
+    if let Some(min_confidence) = params.min_confidence {
+        qb.push(" AND confidence >= ").push_bind(min_confidence);
+    }
+}
+
+/// Repository half of the endpoint: one page of audit rows, newest first,
+/// and the total match count.
+pub async fn list_history(
+    db: &sqlx::PgPool,
+    scope_user: Option<Uuid>,
+    params: &HistoryParams,
+) -> AppResult<(Vec<AuditRow>, u64)> {
+    let (_, limit, offset) = params.page.window();
+
+    let mut count_qb = QueryBuilder::new("SELECT count(*) FROM analysis_audit");
+    push_filters(&mut count_qb, scope_user, params);
+    let (total,): (i64,) = count_qb.build_query_as().fetch_one(db).await?;
+
+    let mut qb = QueryBuilder::new(
+        "SELECT job_id, user_id, request_id, crop_type, disease_name, confidence, \
+         severity_score, requested_at, completed_at FROM analysis_audit",
+    );
+    push_filters(&mut qb, scope_user, params);
+    qb.push(" ORDER BY completed_at DESC");
+    qb.push(" LIMIT ").push_bind(limit);
+    qb.push(" OFFSET ").push_bind(offset);
+    let items: Vec<AuditRow> = qb.build_query_as().fetch_all(db).await?;
+
+    Ok((items, total as u64))
+}
+
+/// `GET /api/v1/vision/history?crop_type&from&to&min_confidence&user_id&page&limit`
+/// — paginated audit of issued diagnoses, scoped by role.
+pub async fn get_history(
+    State(state): State<AppState>,
+    ctx: RequestContext,
+    Query(params): Query<HistoryParams>,
+) -> AppResult<Json<ApiResponse<PaginatedResponse<AuditRow>>>> {
+    let user = ctx.require_user()?;
+    if params
+        .min_confidence
+        .is_some_and(|c| !(0.0..=1.0).contains(&c))
+    {
+        return Err(AppError::Validation(
+            "min_confidence must be between 0 and 1".into(),
+        ));
+    }
+    let scope = history_scope(user, &params);
+    let (page, limit, _) = params.page.window();
+    let (items, total) = list_history(&state.db, scope, &params).await?;
+    Ok(Json(ApiResponse::ok(PaginatedResponse::new(
+        items,
+        total,
+        page as u32,
+        limit as u32,
+    ))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user(roles: &[&str]) -> crate::AuthUser {
+        crate::AuthUser {
+            user_id: Uuid::new_v4(),
+            email: "someone@example.com".into(),
+            roles: roles.iter().map(|r| r.to_string()).collect(),
+        }
+    }
+
+    fn built_sql(scope: Option<Uuid>, params: &HistoryParams) -> String {
+        let mut qb = QueryBuilder::new("SELECT count(*) FROM analysis_audit");
+        push_filters(&mut qb, scope, params);
+        qb.sql().to_string()
+    }
+
+    #[test]
+    fn filters_only_appear_when_their_parameter_is_set() {
+        let bare = built_sql(None, &HistoryParams::default());
+        assert!(!bare.contains("user_id"));
+        assert!(!bare.contains("crop_type"));
+        assert!(!bare.contains("completed_at"));
+        assert!(!bare.contains("confidence"));
+
+        let full = built_sql(
+            Some(Uuid::new_v4()),
+            &HistoryParams {
+                crop_type: Some(CropType::Rice),
+                from: Some(Utc::now()),
+                to: Some(Utc::now()),
+                min_confidence: Some(0.8),
+                ..HistoryParams::default()
+            },
+        );
+        assert!(full.contains("user_id = "));
+        assert!(full.contains("crop_type = "));
+        assert!(full.contains("completed_at >= "));
+        assert!(full.contains("completed_at < "));
+        assert!(full.contains("confidence >= "));
+    }
+
+    #[test]
+    fn farmers_are_pinned_to_their_own_scope() {
+        let farmer = user(&["farmer"]);
+        // Asking for someone else's records doesn't widen the scope.
+        let params = HistoryParams {
+            user_id: Some(Uuid::new_v4()),
+            ..HistoryParams::default()
+        };
+        assert_eq!(history_scope(&farmer, &params), Some(farmer.user_id));
+        assert_eq!(
+            history_scope(&farmer, &HistoryParams::default()),
+            Some(farmer.user_id)
+        );
+    }
+
+    #[test]
+    fn officers_see_everyone_and_can_narrow_to_one_user() {
+        let officer = user(&["officer"]);
+        assert_eq!(history_scope(&officer, &HistoryParams::default()), None);
+
+        let target = Uuid::new_v4();
+        let params = HistoryParams {
+            user_id: Some(target),
+            ..HistoryParams::default()
+        };
+        assert_eq!(history_scope(&officer, &params), Some(target));
+    }
+}
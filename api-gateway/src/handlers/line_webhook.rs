@@ -0,0 +1,264 @@
+//! LINE webhook receiver with signature checking and replay protection.
+//!
+//! The route is public, so the channel-secret signature is the only thing
+//! standing between LINE and anyone with the URL: deliveries are verified
+//! over the raw body before any parsing. LINE redelivers events when we
+//! respond slowly, so the handler answers 200 immediately and processes
+//! asynchronously. Events are deduplicated on the LINE event id (Redis
+//! `SET NX` with TTL) and events older than the configured skew window are
+//! dropped — both cases counted for metrics.
+
+use async_trait::async_trait;
+use axum::{
+    body::Bytes,
+    extract::State,
+    http::{HeaderMap, StatusCode},
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::Utc;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::{
+    errors::{AppError, AppResult},
+    state::AppState,
+};
+
+/// Header carrying LINE's base64 HMAC-SHA256 over the raw request body,
+/// keyed by the channel secret.
+pub const SIGNATURE_HEADER: &str = "X-Line-Signature";
+
+/// How long a claimed event id blocks redeliveries.
+const DEDUPE_TTL_SECS: u64 = 10 * 60;
+
+#[derive(Debug, Deserialize)]
+pub struct LineWebhookBody {
+    #[serde(default)]
+    pub events: Vec<LineEvent>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct LineEvent {
+    /// LINE's webhook event id, unique per event across redeliveries.
+    #[serde(rename = "webhookEventId")]
+    pub webhook_event_id: String,
+    /// Event time in epoch milliseconds.
+    pub timestamp: i64,
+    #[serde(rename = "type")]
+    pub event_type: String,
+    #[serde(default)]
+    pub message: Option<Value>,
+}
+
+/// What to do with an incoming event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Disposition {
+    Process,
+    Stale,
+    Duplicate,
+}
+
+/// Claims event ids once; Redis in production, a set in tests.
+#[async_trait]
+pub trait DedupeStore: Send + Sync {
+    /// Returns true exactly once per id within the TTL.
+    async fn try_claim(&self, event_id: &str) -> bool;
+}
+
+pub struct RedisDedupe {
+    pub client: redis::Client,
+}
+
+#[async_trait]
+impl DedupeStore for RedisDedupe {
+    async fn try_claim(&self, event_id: &str) -> bool {
+        let Ok(mut conn) = self.client.get_multiplexed_async_connection().await else {
+            // Redis down: process rather than drop farmer messages; worst
+            // case is a duplicate analysis.
+            return true;
+        };
+        let result: Result<Value, _> = redis::cmd("SET")
+            .arg(format!("line:event:{event_id}"))
+            .arg(1)
+            .arg("NX")
+            .arg("EX")
+            .arg(DEDUPE_TTL_SECS)
+            .query_async(&mut conn)
+            .await;
+        matches!(result, Ok(Value::String(_)))
+    }
+}
+
+/// Classify an event against the skew window, then the dedupe store. Stale
+/// events are rejected before spending a Redis round-trip.
+pub async fn classify_event(
+    event: &LineEvent,
+    now_ms: i64,
+    skew_window_ms: i64,
+    dedupe: &dyn DedupeStore,
+) -> Disposition {
+    if now_ms - event.timestamp > skew_window_ms {
+        return Disposition::Stale;
+    }
+    if dedupe.try_claim(&event.webhook_event_id).await {
+        Disposition::Process
+    } else {
+        Disposition::Duplicate
+    }
+}
+
+/// True when `signature` is the base64 HMAC-SHA256 of `body` under the
+/// channel secret. Compared without short-circuiting so timing doesn't
+/// leak how much of a guess matched.
+pub fn signature_matches(channel_secret: &str, body: &[u8], signature: &str) -> bool {
+    let mac = crate::services::webhooks::hmac_sha256(channel_secret.as_bytes(), body);
+    let expected = STANDARD.encode(mac);
+    signature.len() == expected.len()
+        && signature
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
+
+/// `POST /api/v1/webhooks/line` — verify the signature, acknowledge
+/// immediately, process async.
+pub async fn line_webhook(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    raw_body: Bytes,
+) -> AppResult<StatusCode> {
+    let channel_secret = &state.config.line_webhook.channel_secret;
+    let signature = headers
+        .get(SIGNATURE_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .ok_or_else(|| AppError::Auth("missing LINE signature".into()))?;
+    if channel_secret.is_empty() || !signature_matches(channel_secret, &raw_body, signature) {
+        state.bump_counter("line_webhook_bad_signature");
+        return Err(AppError::Auth("invalid LINE signature".into()));
+    }
+    let body: LineWebhookBody = serde_json::from_slice(&raw_body)
+        .map_err(|e| AppError::Validation(format!("LINE webhook body: {e}")))?;
+
+    let now_ms = Utc::now().timestamp_millis();
+    let skew_window_ms = state.config.line_webhook.skew_window.as_millis() as i64;
+    let dedupe = RedisDedupe {
+        client: state.redis_client.clone(),
+    };
+
+    for event in body.events {
+        match classify_event(&event, now_ms, skew_window_ms, &dedupe).await {
+            Disposition::Stale => {
+                state.bump_counter("line_webhook_stale_events");
+                tracing::warn!(event_id = %event.webhook_event_id, "dropping stale LINE event");
+            }
+            Disposition::Duplicate => {
+                state.bump_counter("line_webhook_duplicate_events");
+                tracing::debug!(event_id = %event.webhook_event_id, "dropping duplicate LINE event");
+            }
+            Disposition::Process => {
+                // Hand off before LINE's response deadline; the queue retries
+                // internally so losing the HTTP context here is fine.
+                let state = state.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = process_event(&state, &event).await {
+                        tracing::error!(event_id = %event.webhook_event_id, error = %e, "LINE event processing failed");
+                    }
+                });
+            }
+        }
+    }
+
+    // Always 200 so LINE stops retrying; our own dedupe handles the rest.
+    Ok(StatusCode::OK)
+}
+
+async fn process_event(state: &AppState, event: &LineEvent) -> AppResult<()> {
+    if event.event_type == "message" {
+        // LINE relay events predate the typed job contract and stay untyped
+        // until the LINE ingestion pipeline builds real vision jobs.
+        state
+            .rabbitmq
+            .publish_raw(
+                &json!({
+                    "source": "line",
+                    "event_id": event.webhook_event_id,
+                    "message": event.message,
+                }),
+                shared::queue::JobPriority::default(),
+            )
+            .await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    #[derive(Default)]
+    struct MemoryDedupe {
+        seen: Mutex<std::collections::HashSet<String>>,
+        claims: Mutex<u32>,
+    }
+
+    #[async_trait]
+    impl DedupeStore for MemoryDedupe {
+        async fn try_claim(&self, event_id: &str) -> bool {
+            *self.claims.lock().unwrap() += 1;
+            self.seen.lock().unwrap().insert(event_id.to_string())
+        }
+    }
+
+    /// Mirrors the `line_webhook.skew_window` config default.
+    const SKEW_WINDOW_MS: i64 = 5 * 60 * 1000;
+
+    fn event(id: &str, timestamp: i64) -> LineEvent {
+        LineEvent {
+            webhook_event_id: id.into(),
+            timestamp,
+            event_type: "message".into(),
+            message: None,
+        }
+    }
+
+    #[test]
+    fn signatures_verify_over_the_raw_body() {
+        let body = br#"{"events":[]}"#;
+        let good = STANDARD.encode(crate::services::webhooks::hmac_sha256(b"channel-secret", body));
+        assert!(signature_matches("channel-secret", body, &good));
+        // A tampered body, a different secret, or garbage all fail.
+        assert!(!signature_matches("channel-secret", br#"{"events":[{}]}"#, &good));
+        assert!(!signature_matches("other-secret", body, &good));
+        assert!(!signature_matches("channel-secret", body, "not even base64"));
+    }
+
+    #[tokio::test]
+    async fn replayed_event_is_processed_once() {
+        let dedupe = MemoryDedupe::default();
+        let now = 1_000_000;
+        let e = event("evt-1", now - 10);
+        assert_eq!(
+            classify_event(&e, now, SKEW_WINDOW_MS, &dedupe).await,
+            Disposition::Process
+        );
+        assert_eq!(
+            classify_event(&e, now, SKEW_WINDOW_MS, &dedupe).await,
+            Disposition::Duplicate
+        );
+    }
+
+    #[tokio::test]
+    async fn stale_event_is_rejected_without_touching_dedupe() {
+        let dedupe = MemoryDedupe::default();
+        let now = 10_000_000;
+        let e = event("evt-old", now - SKEW_WINDOW_MS - 1);
+        assert_eq!(
+            classify_event(&e, now, SKEW_WINDOW_MS, &dedupe).await,
+            Disposition::Stale
+        );
+        assert_eq!(*dedupe.claims.lock().unwrap(), 0);
+    }
+}
@@ -0,0 +1,22 @@
+pub mod admin;
+pub mod admin_logs;
+pub mod admin_shadow;
+pub mod annotations;
+pub mod auth;
+pub mod chat;
+pub mod dashboard;
+pub mod health;
+pub mod history;
+pub mod line_webhook;
+pub mod preferences;
+pub mod profile;
+pub mod search;
+pub mod sensors;
+pub mod tags;
+pub mod uploads;
+pub mod usage;
+pub mod version;
+pub mod vision;
+pub mod webhooks;
+
+pub use health::{health_check, metrics, readiness_check};
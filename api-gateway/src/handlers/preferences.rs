@@ -0,0 +1,90 @@
+//! Per-user UI preference sync.
+//!
+//! `GET`/`PATCH /api/v1/profile/preferences` stores one JSON document per
+//! user, validated against the versioned schema in `shared::preferences`.
+//! The response always carries the merged document plus `updated_at` so
+//! devices can do last-write-wins.
+
+use axum::{extract::State, Json};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use shared::{
+    preferences::{merge_patch, validate_document, PREFERENCES_SCHEMA_VERSION},
+    types::ApiResponse,
+};
+
+use crate::{errors::AppError, errors::AppResult, state::AppState, AuthUser};
+
+#[derive(Debug, Serialize)]
+pub struct PreferencesResponse {
+    pub schema_version: u32,
+    pub preferences: Value,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+/// `GET /api/v1/profile/preferences`
+pub async fn get_preferences(
+    State(state): State<AppState>,
+    user: AuthUser,
+) -> AppResult<Json<ApiResponse<PreferencesResponse>>> {
+    let row: Option<(Value, DateTime<Utc>)> =
+        sqlx::query_as("SELECT doc, updated_at FROM user_preferences WHERE user_id = $1")
+            .bind(user.user_id)
+            .fetch_optional(&state.db)
+            .await?;
+
+    let (preferences, updated_at) = match row {
+        Some((doc, at)) => (doc, Some(at)),
+        None => (Value::Object(Default::default()), None),
+    };
+    Ok(Json(ApiResponse::ok(PreferencesResponse {
+        schema_version: PREFERENCES_SCHEMA_VERSION,
+        preferences,
+        updated_at,
+    })))
+}
+
+/// `PATCH /api/v1/profile/preferences` — validate, merge, persist, echo the
+/// merged document.
+pub async fn patch_preferences(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Json(patch): Json<Value>,
+) -> AppResult<Json<ApiResponse<PreferencesResponse>>> {
+    validate_document(&patch).map_err(AppError::Validation)?;
+
+    let mut tx = state.db.begin().await?;
+    let stored: Option<(Value,)> =
+        sqlx::query_as("SELECT doc FROM user_preferences WHERE user_id = $1 FOR UPDATE")
+            .bind(user.user_id)
+            .fetch_optional(&mut *tx)
+            .await?;
+    let merged = merge_patch(
+        &stored.map(|(doc,)| doc).unwrap_or(Value::Null),
+        &patch,
+    );
+    // Re-validate the merged result so repeated patches can't creep past the
+    // size cap.
+    validate_document(&merged).map_err(AppError::Validation)?;
+
+    let updated_at = Utc::now();
+    sqlx::query(
+        "INSERT INTO user_preferences (user_id, schema_version, doc, updated_at) \
+         VALUES ($1, $2, $3, $4) \
+         ON CONFLICT (user_id) DO UPDATE SET doc = $3, schema_version = $2, updated_at = $4",
+    )
+    .bind(user.user_id)
+    .bind(PREFERENCES_SCHEMA_VERSION as i32)
+    .bind(&merged)
+    .bind(updated_at)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    Ok(Json(ApiResponse::ok(PreferencesResponse {
+        schema_version: PREFERENCES_SCHEMA_VERSION,
+        preferences: merged,
+        updated_at: Some(updated_at),
+    })))
+}
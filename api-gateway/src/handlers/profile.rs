@@ -0,0 +1,234 @@
+//! Farmer profile storage.
+//!
+//! `GET`/`PUT /api/v1/profile` keeps one profile row per user (see
+//! `migrations/0015_profiles.sql`): name, province, farm size, primary
+//! crops, phone number. PUT replaces the whole profile — the form always
+//! submits every field, so merge semantics would only hide bugs.
+
+use axum::{extract::State, Json};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use shared::{
+    models::{CropType, FarmerProfile},
+    types::ApiResponse,
+};
+use uuid::Uuid;
+
+use crate::{errors::AppError, errors::AppResult, state::AppState, AuthUser};
+
+/// Longest accepted name/province; anything past this is a paste accident,
+/// not a name.
+const MAX_TEXT_FIELD_CHARS: usize = 100;
+
+/// Upper bound on a plausible farm, in rai. The largest farms in the
+/// country are a few thousand rai; six digits is a typo.
+const MAX_FARM_SIZE_RAI: f64 = 100_000.0;
+
+/// The writable profile fields, as `PUT /api/v1/profile` accepts them.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProfileUpdateRequest {
+    pub name: String,
+    #[serde(default)]
+    pub province: Option<String>,
+    #[serde(default)]
+    pub farm_size_rai: Option<f64>,
+    #[serde(default)]
+    pub primary_crops: Vec<CropType>,
+    #[serde(default)]
+    pub phone_number: Option<String>,
+}
+
+/// Field-level validation, kept apart from the handler so the rules are
+/// testable without a database.
+pub fn validate_profile(request: &ProfileUpdateRequest) -> Result<(), String> {
+    let name = request.name.trim();
+    if name.is_empty() {
+        return Err("name must not be empty".into());
+    }
+    if name.chars().count() > MAX_TEXT_FIELD_CHARS {
+        return Err(format!("name exceeds {MAX_TEXT_FIELD_CHARS} characters"));
+    }
+    if let Some(province) = &request.province {
+        if province.chars().count() > MAX_TEXT_FIELD_CHARS {
+            return Err(format!("province exceeds {MAX_TEXT_FIELD_CHARS} characters"));
+        }
+    }
+    if let Some(size) = request.farm_size_rai {
+        if !size.is_finite() || size <= 0.0 || size > MAX_FARM_SIZE_RAI {
+            return Err(format!("farm_size_rai must be between 0 and {MAX_FARM_SIZE_RAI}"));
+        }
+    }
+    if let Some(phone) = &request.phone_number {
+        let digits = phone.chars().filter(char::is_ascii_digit).count();
+        let valid_chars = phone
+            .chars()
+            .all(|c| c.is_ascii_digit() || matches!(c, '+' | '-' | ' '));
+        if !valid_chars || !(8..=15).contains(&digits) {
+            return Err("phone_number must be 8-15 digits".into());
+        }
+    }
+    Ok(())
+}
+
+/// One `profiles` row as sqlx returns it; crops come back as their
+/// snake_case wire strings.
+type ProfileRow = (
+    String,
+    Option<String>,
+    Option<f64>,
+    Vec<String>,
+    Option<String>,
+    DateTime<Utc>,
+);
+
+fn row_to_profile(user_id: Uuid, row: ProfileRow) -> FarmerProfile {
+    let (name, province, farm_size_rai, crops, phone_number, created_at) = row;
+    FarmerProfile {
+        user_id,
+        name,
+        province,
+        farm_size_rai,
+        // Unknown crop strings (a removed variant) are dropped rather than
+        // failing the whole profile.
+        primary_crops: crops
+            .into_iter()
+            .filter_map(|c| CropType::ALL.iter().copied().find(|k| k.as_str() == c))
+            .collect(),
+        phone_number,
+        created_at,
+    }
+}
+
+/// `GET /api/v1/profile` — 404 until the user has saved a profile.
+pub async fn get_profile(
+    State(state): State<AppState>,
+    user: AuthUser,
+) -> AppResult<Json<ApiResponse<FarmerProfile>>> {
+    let row: Option<ProfileRow> = sqlx::query_as(
+        "SELECT name, province, farm_size_rai, primary_crops, phone_number, created_at \
+         FROM profiles WHERE user_id = $1",
+    )
+    .bind(user.user_id)
+    .fetch_optional(&state.db)
+    .await?;
+
+    match row {
+        Some(row) => Ok(Json(ApiResponse::ok(row_to_profile(user.user_id, row)))),
+        None => Err(AppError::NotFound("no profile saved yet".into())),
+    }
+}
+
+/// `PUT /api/v1/profile` — validate and upsert, echoing the stored
+/// profile. `created_at` survives updates; only the first save sets it.
+pub async fn put_profile(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Json(request): Json<ProfileUpdateRequest>,
+) -> AppResult<Json<ApiResponse<FarmerProfile>>> {
+    validate_profile(&request).map_err(AppError::Validation)?;
+
+    let crops: Vec<String> = request
+        .primary_crops
+        .iter()
+        .map(|c| c.as_str().to_string())
+        .collect();
+    let (created_at,): (DateTime<Utc>,) = sqlx::query_as(
+        "INSERT INTO profiles \
+         (user_id, name, province, farm_size_rai, primary_crops, phone_number) \
+         VALUES ($1, $2, $3, $4, $5, $6) \
+         ON CONFLICT (user_id) DO UPDATE SET \
+             name = $2, province = $3, farm_size_rai = $4, \
+             primary_crops = $5, phone_number = $6, updated_at = now() \
+         RETURNING created_at",
+    )
+    .bind(user.user_id)
+    .bind(request.name.trim())
+    .bind(&request.province)
+    .bind(request.farm_size_rai)
+    .bind(&crops)
+    .bind(&request.phone_number)
+    .fetch_one(&state.db)
+    .await?;
+
+    Ok(Json(ApiResponse::ok(FarmerProfile {
+        user_id: user.user_id,
+        name: request.name.trim().to_string(),
+        province: request.province,
+        farm_size_rai: request.farm_size_rai,
+        primary_crops: request.primary_crops,
+        phone_number: request.phone_number,
+        created_at,
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request() -> ProfileUpdateRequest {
+        ProfileUpdateRequest {
+            name: "สมชาย".into(),
+            province: Some("Khon Kaen".into()),
+            farm_size_rai: Some(25.0),
+            primary_crops: vec![CropType::Rice],
+            phone_number: Some("081-234-5678".into()),
+        }
+    }
+
+    #[test]
+    fn a_complete_profile_validates() {
+        assert_eq!(validate_profile(&request()), Ok(()));
+    }
+
+    #[test]
+    fn blank_or_marathon_names_are_rejected() {
+        let mut r = request();
+        r.name = "   ".into();
+        assert!(validate_profile(&r).is_err());
+        r.name = "ก".repeat(MAX_TEXT_FIELD_CHARS + 1);
+        assert!(validate_profile(&r).is_err());
+    }
+
+    #[test]
+    fn farm_size_must_be_positive_finite_and_plausible() {
+        for size in [0.0, -1.0, f64::NAN, f64::INFINITY, MAX_FARM_SIZE_RAI + 1.0] {
+            let mut r = request();
+            r.farm_size_rai = Some(size);
+            assert!(validate_profile(&r).is_err(), "{size} should be rejected");
+        }
+        let mut r = request();
+        r.farm_size_rai = None;
+        assert_eq!(validate_profile(&r), Ok(()));
+    }
+
+    #[test]
+    fn phone_numbers_need_8_to_15_digits_and_no_letters() {
+        let cases = [
+            ("0812345678", true),
+            ("+66 81 234 5678", true),
+            ("1234567", false),
+            ("call me maybe", false),
+        ];
+        for (phone, expected) in cases {
+            let mut r = request();
+            r.phone_number = Some(phone.into());
+            assert_eq!(validate_profile(&r).is_ok(), expected, "{phone}");
+        }
+    }
+
+    #[test]
+    fn unknown_stored_crops_are_dropped_not_fatal() {
+        let profile = row_to_profile(
+            Uuid::nil(),
+            (
+                "a".into(),
+                None,
+                None,
+                vec!["rice".into(), "kale".into(), "mango".into()],
+                None,
+                Utc::now(),
+            ),
+        );
+        assert_eq!(profile.primary_crops, vec![CropType::Rice, CropType::Mango]);
+    }
+}
@@ -0,0 +1,122 @@
+//! Full-text search over a farmer's past diagnoses.
+//!
+//! Every completed job leaves a summarised row in `diagnoses` (written by
+//! the advice stage) whose `search_vector` combines disease name, advice
+//! text, and crop type — so "blast" and the Thai advice wording both
+//! match. Results are ranked with `ts_rank`, which is why this endpoint
+//! uses page-numbered pagination instead of the keyset cursors the list
+//! endpoints use: rank is not a stable sort key.
+
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use shared::{models::CropType, types::ApiResponse};
+use sqlx::{Postgres, QueryBuilder};
+use uuid::Uuid;
+
+use crate::{
+    errors::AppResult,
+    middleware::request_context::RequestContext,
+    shared::pagination::{OffsetParams, PaginatedResponse},
+    state::AppState,
+};
+
+#[derive(Debug, Default, Deserialize)]
+pub struct SearchParams {
+    pub q: String,
+    pub crop_type: Option<CropType>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    #[serde(flatten)]
+    pub page: OffsetParams,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct DiagnosisSummary {
+    pub id: Uuid,
+    pub job_id: Uuid,
+    pub crop_type: String,
+    pub disease_name: String,
+    pub advice: String,
+    pub severity_score: Option<f32>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Shared WHERE clause for the count and page queries: the user's own
+/// rows, the tsquery match (skipped in browse mode), and the optional
+/// crop/date filters.
+fn push_filters(qb: &mut QueryBuilder<'_, Postgres>, user_id: Uuid, params: &SearchParams) {
+    qb.push(" WHERE user_id = ").push_bind(user_id);
+    if !params.q.trim().is_empty() {
+        qb.push(" AND search_vector @@ plainto_tsquery('simple', ")
+            .push_bind(params.q.clone())
+            .push(")");
+    }
+    if let Some(crop_type) = params.crop_type {
+        qb.push(" AND crop_type = ").push_bind(crop_type.as_str());
+    }
+    if let Some(from) = params.from {
+        qb.push(" AND created_at >= ").push_bind(from);
+    }
+    if let Some(to) = params.to {
+        qb.push(" AND created_at < ").push_bind(to);
+    }
+}
+
+/// Repository half of the endpoint: run the ranked search over one user's
+/// history, most relevant first, ties broken newest first. Returns the
+/// page of rows and the total match count.
+pub async fn search_diagnoses(
+    db: &sqlx::PgPool,
+    user_id: Uuid,
+    params: &SearchParams,
+) -> AppResult<(Vec<DiagnosisSummary>, u64)> {
+    let (_, limit, offset) = params.page.window();
+
+    let mut count_qb = QueryBuilder::new("SELECT count(*) FROM diagnoses");
+    push_filters(&mut count_qb, user_id, params);
+    let (total,): (i64,) = count_qb.build_query_as().fetch_one(db).await?;
+
+    let mut qb = QueryBuilder::new(
+        "SELECT id, job_id, crop_type, disease_name, advice, severity_score, created_at \
+         FROM diagnoses",
+    );
+    push_filters(&mut qb, user_id, params);
+    if params.q.trim().is_empty() {
+        // Browse mode has no rank to sort by; newest first is the
+        // contract the history page scrolls through.
+        qb.push(" ORDER BY created_at DESC");
+    } else {
+        qb.push(" ORDER BY ts_rank(search_vector, plainto_tsquery('simple', ")
+            .push_bind(params.q.clone())
+            .push(")) DESC, created_at DESC");
+    }
+    qb.push(" LIMIT ").push_bind(limit);
+    qb.push(" OFFSET ").push_bind(offset);
+    let items: Vec<DiagnosisSummary> = qb.build_query_as().fetch_all(db).await?;
+
+    Ok((items, total as u64))
+}
+
+/// `GET /api/v1/diagnoses/search?q&crop_type&from&to&page&limit` — ranked
+/// full-text search over the caller's own diagnosis history. An empty `q`
+/// is browse mode: every diagnosis, newest first, which is what the
+/// frontend history page scrolls through.
+pub async fn search(
+    State(state): State<AppState>,
+    ctx: RequestContext,
+    Query(params): Query<SearchParams>,
+) -> AppResult<Json<ApiResponse<PaginatedResponse<DiagnosisSummary>>>> {
+    let user_id = ctx.require_user()?.user_id;
+    let (page, limit, _) = params.page.window();
+    let (items, total) = search_diagnoses(&state.db, user_id, &params).await?;
+    Ok(Json(ApiResponse::ok(PaginatedResponse::new(
+        items,
+        total,
+        page as u32,
+        limit as u32,
+    ))))
+}
@@ -0,0 +1,325 @@
+//! Field sensor ingestion and aggregation.
+//!
+//! Cheap soil sensors report through the farmer's phone; the readings land
+//! in `sensor_readings` and come back out aggregated per hour or per day
+//! so a chart over a month is a few dozen points instead of thousands of
+//! rows. Aggregation happens in-process over the windowed rows — the
+//! window is bounded by the query and sensors report every few minutes,
+//! so even a month of one device fits comfortably.
+
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use chrono::{DateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use shared::{models::SensorReading, types::ApiResponse};
+use uuid::Uuid;
+
+use crate::{
+    errors::{AppError, AppResult},
+    state::AppState,
+};
+
+/// Plausible bounds for a field sensor in Thailand; anything outside is a
+/// broken sensor, and storing it would poison the aggregates.
+const TEMPERATURE_RANGE_C: std::ops::RangeInclusive<f32> = -10.0..=60.0;
+
+/// Reject obviously wrong values before they reach the table.
+pub fn validate_reading(reading: &SensorReading) -> AppResult<()> {
+    if reading.device_id.trim().is_empty() {
+        return Err(AppError::Validation("device_id must not be empty".into()));
+    }
+    if !TEMPERATURE_RANGE_C.contains(&reading.temperature_c) {
+        return Err(AppError::Validation(format!(
+            "temperature_c {} outside plausible range",
+            reading.temperature_c
+        )));
+    }
+    for (name, value) in [
+        ("soil_moisture_pct", reading.soil_moisture_pct),
+        ("humidity_pct", reading.humidity_pct),
+    ] {
+        if !(0.0..=100.0).contains(&value) {
+            return Err(AppError::Validation(format!(
+                "{name} must be between 0 and 100, got {value}"
+            )));
+        }
+    }
+    // A clock-skewed sensor "from the future" would sort above every real
+    // reading forever; a small allowance covers honest drift.
+    if reading.recorded_at > Utc::now() + chrono::Duration::hours(1) {
+        return Err(AppError::Validation(
+            "recorded_at is in the future".into(),
+        ));
+    }
+    Ok(())
+}
+
+/// `POST /api/v1/sensors/readings` — store one reading.
+pub async fn submit_reading(
+    State(state): State<AppState>,
+    Json(reading): Json<SensorReading>,
+) -> AppResult<Json<ApiResponse<()>>> {
+    validate_reading(&reading)?;
+    sqlx::query(
+        "INSERT INTO sensor_readings \
+         (id, device_id, crop_type, temperature_c, soil_moisture_pct, humidity_pct, recorded_at) \
+         VALUES ($1, $2, $3, $4, $5, $6, $7)",
+    )
+    .bind(Uuid::new_v4())
+    .bind(&reading.device_id)
+    .bind(reading.crop_type.as_str())
+    .bind(reading.temperature_c)
+    .bind(reading.soil_moisture_pct)
+    .bind(reading.humidity_pct)
+    .bind(reading.recorded_at)
+    .execute(&state.db)
+    .await?;
+    Ok(Json(ApiResponse::ok(())))
+}
+
+/// One raw reading as pulled for aggregation (and for the advice stage's
+/// environmental context).
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct ReadingSample {
+    pub temperature_c: f32,
+    pub soil_moisture_pct: f32,
+    pub humidity_pct: f32,
+    pub recorded_at: DateTime<Utc>,
+}
+
+/// Most recent reading from one device, for injecting growing conditions
+/// into the advice prompt. Best effort: a sensor problem must never block
+/// advice.
+pub async fn latest_for_device(db: &sqlx::PgPool, device_id: &str) -> Option<ReadingSample> {
+    sqlx::query_as(
+        "SELECT temperature_c, soil_moisture_pct, humidity_pct, recorded_at \
+         FROM sensor_readings WHERE device_id = $1 ORDER BY recorded_at DESC LIMIT 1",
+    )
+    .bind(device_id)
+    .fetch_optional(db)
+    .await
+    .unwrap_or_else(|error| {
+        tracing::warn!(%device_id, %error, "latest sensor reading lookup failed");
+        None
+    })
+}
+
+/// Aggregation bucket width.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Interval {
+    Hourly,
+    Daily,
+}
+
+impl Interval {
+    fn seconds(self) -> i64 {
+        match self {
+            Interval::Hourly => 3600,
+            Interval::Daily => 86400,
+        }
+    }
+}
+
+/// UTC start of the bucket containing `at`.
+fn bucket_start(at: DateTime<Utc>, interval: Interval) -> DateTime<Utc> {
+    let width = interval.seconds();
+    let secs = at.timestamp().div_euclid(width) * width;
+    Utc.timestamp_opt(secs, 0).single().unwrap_or(at)
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Aggregate {
+    pub avg: f32,
+    pub min: f32,
+    pub max: f32,
+}
+
+impl Aggregate {
+    fn over(values: impl Iterator<Item = f32> + Clone, count: usize) -> Aggregate {
+        Aggregate {
+            avg: values.clone().sum::<f32>() / count as f32,
+            min: values.clone().fold(f32::INFINITY, f32::min),
+            max: values.fold(f32::NEG_INFINITY, f32::max),
+        }
+    }
+}
+
+/// AVG/MIN/MAX of every metric over one bucket.
+#[derive(Debug, Serialize)]
+pub struct AggregatedReading {
+    /// Bucket start, UTC.
+    pub bucket: DateTime<Utc>,
+    pub samples: u64,
+    pub temperature_c: Aggregate,
+    pub soil_moisture_pct: Aggregate,
+    pub humidity_pct: Aggregate,
+}
+
+/// Fold raw samples into per-bucket aggregates, oldest bucket first. Pure
+/// so a synthetic load is enough to test it.
+pub fn aggregate_readings(samples: &[ReadingSample], interval: Interval) -> Vec<AggregatedReading> {
+    let mut buckets: std::collections::BTreeMap<i64, Vec<&ReadingSample>> = Default::default();
+    for sample in samples {
+        buckets
+            .entry(bucket_start(sample.recorded_at, interval).timestamp())
+            .or_default()
+            .push(sample);
+    }
+    buckets
+        .into_iter()
+        .map(|(start, group)| {
+            let count = group.len();
+            AggregatedReading {
+                bucket: Utc.timestamp_opt(start, 0).single().expect("bucket start in range"),
+                samples: count as u64,
+                temperature_c: Aggregate::over(group.iter().map(|s| s.temperature_c), count),
+                soil_moisture_pct: Aggregate::over(
+                    group.iter().map(|s| s.soil_moisture_pct),
+                    count,
+                ),
+                humidity_pct: Aggregate::over(group.iter().map(|s| s.humidity_pct), count),
+            }
+        })
+        .collect()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReadingsQuery {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub interval: Option<Interval>,
+}
+
+/// `GET /api/v1/sensors/:device_id/readings?from&to&interval=hourly|daily`
+/// — aggregated readings for one device. Defaults to hourly buckets over
+/// the last 7 days.
+pub async fn get_readings(
+    State(state): State<AppState>,
+    Path(device_id): Path<String>,
+    Query(query): Query<ReadingsQuery>,
+) -> AppResult<Json<ApiResponse<Vec<AggregatedReading>>>> {
+    let to = query.to.unwrap_or_else(Utc::now);
+    let from = query.from.unwrap_or(to - chrono::Duration::days(7));
+    if from >= to {
+        return Err(AppError::Validation("from must be before to".into()));
+    }
+    let interval = query.interval.unwrap_or(Interval::Hourly);
+
+    let samples: Vec<ReadingSample> = sqlx::query_as(
+        "SELECT temperature_c, soil_moisture_pct, humidity_pct, recorded_at \
+         FROM sensor_readings \
+         WHERE device_id = $1 AND recorded_at >= $2 AND recorded_at < $3 \
+         ORDER BY recorded_at",
+    )
+    .bind(&device_id)
+    .bind(from)
+    .bind(to)
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(Json(ApiResponse::ok(aggregate_readings(&samples, interval))))
+}
+
+#[cfg(test)]
+mod tests {
+    use shared::models::CropType;
+
+    use super::*;
+
+    fn reading() -> SensorReading {
+        SensorReading {
+            device_id: "soil-007".into(),
+            crop_type: CropType::Rice,
+            temperature_c: 31.5,
+            soil_moisture_pct: 48.0,
+            humidity_pct: 80.0,
+            recorded_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn validation_rejects_broken_sensor_values() {
+        assert!(validate_reading(&reading()).is_ok());
+
+        let cases: Vec<(&str, Box<dyn Fn(&mut SensorReading)>)> = vec![
+            ("empty device", Box::new(|r| r.device_id = "  ".into())),
+            ("frozen sensor", Box::new(|r| r.temperature_c = -40.0)),
+            ("moisture over 100", Box::new(|r| r.soil_moisture_pct = 101.0)),
+            ("negative humidity", Box::new(|r| r.humidity_pct = -1.0)),
+            (
+                "future clock",
+                Box::new(|r| r.recorded_at = Utc::now() + chrono::Duration::days(2)),
+            ),
+        ];
+        for (label, mutate) in cases {
+            let mut bad = reading();
+            mutate(&mut bad);
+            assert!(
+                matches!(validate_reading(&bad), Err(AppError::Validation(_))),
+                "{label} should be rejected"
+            );
+        }
+    }
+
+    /// 1000 synthetic readings: one every 5 minutes from a fixed epoch, so
+    /// each hour holds 12 and the run spans a bit under 3.5 days. Values
+    /// encode the sample index, which makes expected aggregates exact.
+    fn synthetic_readings() -> Vec<ReadingSample> {
+        let start = Utc.timestamp_opt(1_700_000_000 / 3600 * 3600, 0).unwrap();
+        (0..1000)
+            .map(|i| ReadingSample {
+                temperature_c: 25.0 + (i % 12) as f32,
+                soil_moisture_pct: (i % 100) as f32,
+                humidity_pct: 70.0,
+                recorded_at: start + chrono::Duration::minutes(5 * i),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn hourly_aggregation_buckets_a_thousand_readings() {
+        let samples = synthetic_readings();
+        let hourly = aggregate_readings(&samples, Interval::Hourly);
+
+        // 1000 readings at 12 per hour: 83 full hours plus a remainder.
+        assert_eq!(hourly.len(), 84);
+        assert_eq!(hourly.iter().map(|b| b.samples).sum::<u64>(), 1000);
+        assert_eq!(hourly.last().unwrap().samples, 1000 % 12);
+
+        // Buckets come back oldest first and aligned to the hour.
+        for window in hourly.windows(2) {
+            assert!(window[0].bucket < window[1].bucket);
+        }
+        assert!(hourly.iter().all(|b| b.bucket.timestamp() % 3600 == 0));
+
+        // Within any full hour the temperature cycles 25..=36 exactly once.
+        let first = &hourly[0];
+        assert_eq!(first.temperature_c.min, 25.0);
+        assert_eq!(first.temperature_c.max, 36.0);
+        assert!((first.temperature_c.avg - 30.5).abs() < 1e-4);
+        // A constant metric aggregates to itself.
+        assert_eq!(first.humidity_pct, Aggregate { avg: 70.0, min: 70.0, max: 70.0 });
+    }
+
+    #[test]
+    fn daily_buckets_are_coarser_views_of_the_same_data() {
+        let samples = synthetic_readings();
+        let daily = aggregate_readings(&samples, Interval::Daily);
+
+        assert!(daily.len() < aggregate_readings(&samples, Interval::Hourly).len());
+        assert_eq!(daily.iter().map(|b| b.samples).sum::<u64>(), 1000);
+        assert!(daily.iter().all(|b| b.bucket.timestamp() % 86400 == 0));
+        // The global extremes survive any bucketing.
+        let min = daily.iter().map(|b| b.soil_moisture_pct.min).fold(f32::INFINITY, f32::min);
+        let max = daily.iter().map(|b| b.soil_moisture_pct.max).fold(f32::NEG_INFINITY, f32::max);
+        assert_eq!((min, max), (0.0, 99.0));
+    }
+
+    #[test]
+    fn empty_windows_aggregate_to_nothing() {
+        assert!(aggregate_readings(&[], Interval::Hourly).is_empty());
+    }
+}
@@ -0,0 +1,189 @@
+//! User-scoped tags on conversations and vision jobs.
+//!
+//! Extension officers juggling many farmer cases tag them ("follow-up
+//! needed", "severe-outbreak") and filter listings by tag. Tag names are
+//! unique per user; attach and detach are idempotent so retried requests
+//! can't fail.
+
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use shared::types::ApiResponse;
+use uuid::Uuid;
+
+use crate::{
+    errors::{AppError, AppResult},
+    state::AppState,
+    AuthUser,
+};
+
+pub const MAX_TAG_LENGTH: usize = 30;
+
+/// Trim and validate a tag name: non-empty, at most 30 chars, no control
+/// characters.
+pub fn validate_tag_name(name: &str) -> AppResult<String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(AppError::Validation("tag name must not be empty".into()));
+    }
+    if name.chars().count() > MAX_TAG_LENGTH {
+        return Err(AppError::Validation(format!(
+            "tag name exceeds {MAX_TAG_LENGTH} characters"
+        )));
+    }
+    if name.chars().any(char::is_control) {
+        return Err(AppError::Validation("tag name contains control characters".into()));
+    }
+    Ok(name.to_string())
+}
+
+/// What a tagging can point at; doubles as the path segment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TargetType {
+    Conversation,
+    Job,
+}
+
+impl TargetType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TargetType::Conversation => "conversation",
+            TargetType::Job => "job",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateTagRequest {
+    pub name: String,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct Tag {
+    pub id: Uuid,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    /// How many conversations/jobs currently carry this tag.
+    pub usage_count: i64,
+}
+
+/// `POST /api/v1/tags` — create a tag; creating an existing name returns
+/// the existing tag, keeping the endpoint idempotent.
+pub async fn create_tag(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Json(request): Json<CreateTagRequest>,
+) -> AppResult<Json<ApiResponse<Tag>>> {
+    let name = validate_tag_name(&request.name)?;
+    let tag: Tag = sqlx::query_as(
+        "INSERT INTO tags (id, user_id, name) VALUES ($1, $2, $3) \
+         ON CONFLICT (user_id, name) DO UPDATE SET name = EXCLUDED.name \
+         RETURNING id, name, created_at, \
+           (SELECT count(*) FROM taggings WHERE tag_id = tags.id) AS usage_count",
+    )
+    .bind(Uuid::new_v4())
+    .bind(user.user_id)
+    .bind(&name)
+    .fetch_one(&state.db)
+    .await?;
+    Ok(Json(ApiResponse::ok(tag)))
+}
+
+/// `GET /api/v1/tags` — the caller's tags with usage counts, most used
+/// first.
+pub async fn list_tags(
+    State(state): State<AppState>,
+    user: AuthUser,
+) -> AppResult<Json<ApiResponse<Vec<Tag>>>> {
+    let tags: Vec<Tag> = sqlx::query_as(
+        "SELECT t.id, t.name, t.created_at, count(tg.tag_id) AS usage_count \
+         FROM tags t LEFT JOIN taggings tg ON tg.tag_id = t.id \
+         WHERE t.user_id = $1 \
+         GROUP BY t.id ORDER BY usage_count DESC, t.name",
+    )
+    .bind(user.user_id)
+    .fetch_all(&state.db)
+    .await?;
+    Ok(Json(ApiResponse::ok(tags)))
+}
+
+pub(crate) async fn owned_tag_id(
+    state: &AppState,
+    user: &AuthUser,
+    tag_id: Uuid,
+) -> AppResult<Uuid> {
+    let exists: Option<(Uuid,)> = sqlx::query_as("SELECT id FROM tags WHERE id = $1 AND user_id = $2")
+        .bind(tag_id)
+        .bind(user.user_id)
+        .fetch_optional(&state.db)
+        .await?;
+    exists
+        .map(|(id,)| id)
+        .ok_or_else(|| AppError::NotFound(format!("tag {tag_id}")))
+}
+
+/// `PUT /api/v1/tags/:tag_id/:target_type/:target_id` — attach; attaching
+/// twice is a no-op.
+pub async fn attach_tag(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path((tag_id, target_type, target_id)): Path<(Uuid, TargetType, Uuid)>,
+) -> AppResult<Json<ApiResponse<()>>> {
+    let tag_id = owned_tag_id(&state, &user, tag_id).await?;
+    sqlx::query(
+        "INSERT INTO taggings (tag_id, target_type, target_id) VALUES ($1, $2, $3) \
+         ON CONFLICT DO NOTHING",
+    )
+    .bind(tag_id)
+    .bind(target_type.as_str())
+    .bind(target_id)
+    .execute(&state.db)
+    .await?;
+    Ok(Json(ApiResponse::ok(())))
+}
+
+/// `DELETE /api/v1/tags/:tag_id/:target_type/:target_id` — detach;
+/// detaching an absent tagging succeeds, so retries are safe.
+pub async fn detach_tag(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path((tag_id, target_type, target_id)): Path<(Uuid, TargetType, Uuid)>,
+) -> AppResult<Json<ApiResponse<()>>> {
+    let tag_id = owned_tag_id(&state, &user, tag_id).await?;
+    sqlx::query("DELETE FROM taggings WHERE tag_id = $1 AND target_type = $2 AND target_id = $3")
+        .bind(tag_id)
+        .bind(target_type.as_str())
+        .bind(target_id)
+        .execute(&state.db)
+        .await?;
+    Ok(Json(ApiResponse::ok(())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tag_names_are_trimmed_and_bounded() {
+        assert_eq!(validate_tag_name("  follow-up needed ").unwrap(), "follow-up needed");
+        assert!(validate_tag_name("").is_err());
+        assert!(validate_tag_name("   ").is_err());
+        assert!(validate_tag_name(&"x".repeat(31)).is_err());
+        assert!(validate_tag_name(&"ก".repeat(30)).is_ok()); // chars, not bytes
+        assert!(validate_tag_name("bad\ntag").is_err());
+    }
+
+    #[test]
+    fn target_types_parse_from_path_segments() {
+        let t: TargetType = serde_json::from_value(serde_json::json!("conversation")).unwrap();
+        assert_eq!(t, TargetType::Conversation);
+        assert_eq!(t.as_str(), "conversation");
+        let t: TargetType = serde_json::from_value(serde_json::json!("job")).unwrap();
+        assert_eq!(t.as_str(), "job");
+        assert!(serde_json::from_value::<TargetType>(serde_json::json!("basket")).is_err());
+    }
+}
@@ -0,0 +1,388 @@
+//! Chunked uploads for images above the per-request body limit.
+//!
+//! Mobile cameras produce 15–25 MB files while a single request body is
+//! capped at `max_file_size`. The client initializes an upload, sends
+//! numbered 2 MB (configurable) chunks with `Content-Range` headers, and
+//! completes it; chunks park in Redis with a one-hour TTL so an abandoned
+//! upload cleans itself up. Completion reassembles the chunks, validates
+//! the whole file, and hands it to the normal analyze pipeline.
+
+use axum::{
+    body::Bytes,
+    extract::{Path, State},
+    http::HeaderMap,
+    Json,
+};
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+use shared::{models::CropType, types::ApiResponse};
+use uuid::Uuid;
+
+use crate::{
+    errors::{AppError, AppResult},
+    middleware::request_context::RequestContext,
+    state::AppState,
+};
+
+/// How long parked chunks and upload metadata live without progress.
+const UPLOAD_TTL_SECS: u64 = 3600;
+
+fn meta_key(upload_id: Uuid) -> String {
+    format!("upload:{upload_id}:meta")
+}
+
+fn chunk_key(upload_id: Uuid, index: u64) -> String {
+    format!("upload:{upload_id}:chunk:{index}")
+}
+
+/// Upload metadata fixed at init time; chunk validation checks every
+/// request against it.
+#[derive(Debug, Serialize, Deserialize)]
+struct UploadMeta {
+    extension: String,
+    total_size: u64,
+    chunk_size: u64,
+}
+
+/// A parsed `Content-Range: bytes <start>-<end>/<total>` header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContentRange {
+    pub start: u64,
+    pub end: u64,
+    pub total: u64,
+}
+
+/// Parse the `bytes start-end/total` form; wildcard totals are rejected
+/// because chunk math needs the real size.
+pub fn parse_content_range(raw: &str) -> Option<ContentRange> {
+    let rest = raw.trim().strip_prefix("bytes ")?;
+    let (range, total) = rest.split_once('/')?;
+    let (start, end) = range.split_once('-')?;
+    let range = ContentRange {
+        start: start.trim().parse().ok()?,
+        end: end.trim().parse().ok()?,
+        total: total.trim().parse().ok()?,
+    };
+    (range.start <= range.end && range.end < range.total).then_some(range)
+}
+
+/// Check one chunk's range against the upload's geometry and return its
+/// index. Chunks must start on a chunk boundary and span exactly one chunk,
+/// except the last, which may be short.
+pub fn validate_chunk(
+    range: ContentRange,
+    chunk_size: u64,
+    total_size: u64,
+    body_len: u64,
+) -> AppResult<u64> {
+    if range.total != total_size {
+        return Err(AppError::Validation(format!(
+            "Content-Range total {} does not match the declared upload size {total_size}",
+            range.total
+        )));
+    }
+    if range.start % chunk_size != 0 {
+        return Err(AppError::Validation(format!(
+            "chunk must start on a {chunk_size}-byte boundary, got {}",
+            range.start
+        )));
+    }
+    let span = range.end - range.start + 1;
+    if body_len != span {
+        return Err(AppError::Validation(format!(
+            "body is {body_len} bytes but Content-Range spans {span}"
+        )));
+    }
+    let expected_end = (range.start + chunk_size).min(total_size) - 1;
+    if range.end != expected_end {
+        return Err(AppError::Validation(format!(
+            "chunk ending at {} should end at {expected_end}",
+            range.end
+        )));
+    }
+    Ok(range.start / chunk_size)
+}
+
+/// How many chunks an upload of `total_size` has.
+pub fn chunk_count(total_size: u64, chunk_size: u64) -> u64 {
+    total_size.div_ceil(chunk_size)
+}
+
+/// Stitch the ordered chunks back together, checking the byte count.
+pub fn reassemble(parts: &[Vec<u8>], total_size: u64) -> AppResult<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(total_size as usize);
+    for part in parts {
+        bytes.extend_from_slice(part);
+    }
+    if bytes.len() as u64 != total_size {
+        return Err(AppError::Validation(format!(
+            "reassembled {} bytes, expected {total_size}",
+            bytes.len()
+        )));
+    }
+    Ok(bytes)
+}
+
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+pub struct InitUploadRequest {
+    /// File extension, checked against `supported_formats` up front so a
+    /// doomed upload fails before any chunks travel.
+    pub extension: String,
+    pub total_size: u64,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct InitUploadResponse {
+    pub upload_id: Uuid,
+    /// Chunk size every non-final chunk must use.
+    pub chunk_size: u64,
+    pub total_chunks: u64,
+}
+
+/// `POST /api/v1/vision/upload/init` — start a chunked upload.
+#[utoipa::path(
+    post,
+    path = "/api/v1/vision/upload/init",
+    operation_id = "initChunkedUpload",
+    tag = "vision",
+    request_body = InitUploadRequest,
+    responses(
+        (status = 200, body = ApiResponse<InitUploadResponse>),
+        (status = 400, body = crate::docs::ErrorBody),
+        (status = 401, body = crate::docs::ErrorBody)
+    ),
+    security(("bearer_jwt" = []))
+)]
+pub async fn init_upload(
+    State(state): State<AppState>,
+    ctx: RequestContext,
+    Json(request): Json<InitUploadRequest>,
+) -> AppResult<Json<ApiResponse<InitUploadResponse>>> {
+    ctx.require_user()?;
+    state.file_storage.validate_format(&request.extension)?;
+    let max = state.file_storage.config().max_chunked_file_size as u64;
+    if request.total_size == 0 || request.total_size > max {
+        return Err(AppError::Validation(format!(
+            "total_size must be between 1 and {max} bytes"
+        )));
+    }
+
+    let upload_id = Uuid::new_v4();
+    let chunk_size = state.file_storage.config().chunk_size as u64;
+    let meta = UploadMeta {
+        extension: request.extension.to_ascii_lowercase(),
+        total_size: request.total_size,
+        chunk_size,
+    };
+    let mut redis = state.get_redis().await?;
+    let _: () = redis
+        .set_ex(
+            meta_key(upload_id),
+            serde_json::to_string(&meta)
+                .map_err(|e| AppError::Internal(format!("serialize upload meta: {e}")))?,
+            UPLOAD_TTL_SECS,
+        )
+        .await
+        .map_err(|e| AppError::ServiceUnavailable(format!("redis: {e}")))?;
+
+    Ok(Json(ApiResponse::ok(InitUploadResponse {
+        upload_id,
+        chunk_size,
+        total_chunks: chunk_count(request.total_size, chunk_size),
+    })))
+}
+
+async fn load_meta(
+    redis: &mut redis::aio::MultiplexedConnection,
+    upload_id: Uuid,
+) -> AppResult<UploadMeta> {
+    let raw: Option<String> = redis
+        .get(meta_key(upload_id))
+        .await
+        .map_err(|e| AppError::ServiceUnavailable(format!("redis: {e}")))?;
+    let raw = raw.ok_or_else(|| {
+        AppError::NotFound(format!("upload {upload_id} does not exist or has expired"))
+    })?;
+    serde_json::from_str(&raw).map_err(|e| AppError::Internal(format!("upload meta: {e}")))
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct ChunkReceipt {
+    pub index: u64,
+}
+
+/// `POST /api/v1/vision/upload/:upload_id/chunk` — one numbered binary
+/// chunk, positioned by its `Content-Range` header.
+#[utoipa::path(
+    post,
+    path = "/api/v1/vision/upload/{upload_id}/chunk",
+    operation_id = "uploadChunk",
+    tag = "vision",
+    params(("upload_id" = Uuid, Path)),
+    request_body(content_type = "application/octet-stream", description = "raw chunk bytes; position comes from `Content-Range: bytes start-end/total`"),
+    responses(
+        (status = 200, body = ApiResponse<ChunkReceipt>),
+        (status = 400, body = crate::docs::ErrorBody),
+        (status = 404, description = "unknown or expired upload", body = crate::docs::ErrorBody)
+    ),
+    security(("bearer_jwt" = []))
+)]
+pub async fn upload_chunk(
+    State(state): State<AppState>,
+    ctx: RequestContext,
+    Path(upload_id): Path<Uuid>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> AppResult<Json<ApiResponse<ChunkReceipt>>> {
+    ctx.require_user()?;
+    let range = headers
+        .get(axum::http::header::CONTENT_RANGE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(parse_content_range)
+        .ok_or_else(|| {
+            AppError::Validation("missing or malformed Content-Range header".into())
+        })?;
+
+    let mut redis = state.get_redis().await?;
+    let meta = load_meta(&mut redis, upload_id).await?;
+    let index = validate_chunk(range, meta.chunk_size, meta.total_size, body.len() as u64)?;
+
+    let _: () = redis
+        .set_ex(chunk_key(upload_id, index), body.as_ref(), UPLOAD_TTL_SECS)
+        .await
+        .map_err(|e| AppError::ServiceUnavailable(format!("redis: {e}")))?;
+    Ok(Json(ApiResponse::ok(ChunkReceipt { index })))
+}
+
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+pub struct CompleteUploadRequest {
+    pub crop_type: CropType,
+    pub user_query: Option<String>,
+    #[serde(default)]
+    pub urgent: bool,
+}
+
+/// `POST /api/v1/vision/upload/:upload_id/complete` — reassemble the
+/// chunks, validate the whole file, and queue it for analysis exactly like
+/// a direct upload.
+#[utoipa::path(
+    post,
+    path = "/api/v1/vision/upload/{upload_id}/complete",
+    operation_id = "completeChunkedUpload",
+    tag = "vision",
+    params(("upload_id" = Uuid, Path)),
+    request_body = CompleteUploadRequest,
+    responses(
+        (status = 200, body = ApiResponse<super::vision::AnalyzeOutcome>),
+        (status = 400, description = "missing chunks or invalid file", body = crate::docs::ErrorBody),
+        (status = 404, description = "unknown or expired upload", body = crate::docs::ErrorBody)
+    ),
+    security(("bearer_jwt" = []))
+)]
+pub async fn complete_upload(
+    State(state): State<AppState>,
+    ctx: RequestContext,
+    Path(upload_id): Path<Uuid>,
+    Json(request): Json<CompleteUploadRequest>,
+) -> AppResult<Json<ApiResponse<super::vision::AnalyzeOutcome>>> {
+    ctx.require_user()?;
+    let mut redis = state.get_redis().await?;
+    let meta = load_meta(&mut redis, upload_id).await?;
+
+    let count = chunk_count(meta.total_size, meta.chunk_size);
+    let mut parts = Vec::with_capacity(count as usize);
+    for index in 0..count {
+        let chunk: Option<Vec<u8>> = redis
+            .get(chunk_key(upload_id, index))
+            .await
+            .map_err(|e| AppError::ServiceUnavailable(format!("redis: {e}")))?;
+        parts.push(chunk.ok_or_else(|| {
+            AppError::Validation(format!("chunk {index} of {count} was never uploaded"))
+        })?);
+    }
+    let bytes = reassemble(&parts, meta.total_size)?;
+
+    let stored = state.file_storage.store_chunked(&bytes, &meta.extension).await?;
+    drop(bytes);
+    // Best-effort cleanup; anything missed expires with the TTL.
+    for index in 0..count {
+        let _: Result<(), _> = redis.del(chunk_key(upload_id, index)).await;
+    }
+    let _: Result<(), _> = redis.del(meta_key(upload_id)).await;
+    drop(redis);
+
+    let priority = super::vision::job_priority(request.urgent, ctx.user.as_ref());
+    let outcome = super::vision::enqueue_stored(
+        &state,
+        stored,
+        request.crop_type,
+        request.user_query,
+        priority,
+    )
+    .await?;
+    Ok(Json(ApiResponse::ok(outcome)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MB: u64 = 1024 * 1024;
+
+    #[test]
+    fn five_two_megabyte_chunks_reassemble_to_ten_megabytes() {
+        let parts: Vec<Vec<u8>> = (0..5u8).map(|i| vec![i; (2 * MB) as usize]).collect();
+        let bytes = reassemble(&parts, 10 * MB).unwrap();
+        assert_eq!(bytes.len() as u64, 10 * MB);
+        // Chunk boundaries land where the indices say they should.
+        assert_eq!(bytes[0], 0);
+        assert_eq!(bytes[(2 * MB) as usize], 1);
+        assert_eq!(bytes[(8 * MB) as usize], 4);
+    }
+
+    #[test]
+    fn a_short_reassembly_is_rejected() {
+        let parts = vec![vec![0u8; MB as usize]];
+        assert!(reassemble(&parts, 2 * MB).is_err());
+    }
+
+    #[test]
+    fn content_range_parses_the_bytes_form_only() {
+        assert_eq!(
+            parse_content_range("bytes 0-2097151/10485760"),
+            Some(ContentRange { start: 0, end: 2097151, total: 10485760 })
+        );
+        assert_eq!(parse_content_range("bytes 5-4/10"), None);
+        assert_eq!(parse_content_range("bytes 0-10/10"), None); // end past total
+        assert_eq!(parse_content_range("bytes 0-1/*"), None);
+        assert_eq!(parse_content_range("items 0-1/10"), None);
+    }
+
+    #[test]
+    fn chunks_must_align_to_the_chunk_grid() {
+        let total = 5 * MB;
+        // Chunk 1 of a 2 MB grid.
+        let range = ContentRange { start: 2 * MB, end: 4 * MB - 1, total };
+        assert_eq!(validate_chunk(range, 2 * MB, total, 2 * MB).unwrap(), 1);
+
+        // The final chunk is short and that's fine.
+        let last = ContentRange { start: 4 * MB, end: 5 * MB - 1, total };
+        assert_eq!(validate_chunk(last, 2 * MB, total, MB).unwrap(), 2);
+
+        // Misaligned start, wrong span, and short bodies are all rejected.
+        let misaligned = ContentRange { start: MB, end: 3 * MB - 1, total };
+        assert!(validate_chunk(misaligned, 2 * MB, total, 2 * MB).is_err());
+        let short_span = ContentRange { start: 2 * MB, end: 3 * MB - 1, total };
+        assert!(validate_chunk(short_span, 2 * MB, total, MB).is_err());
+        assert!(validate_chunk(range, 2 * MB, total, MB).is_err());
+        // Total mismatch with the declared upload size.
+        assert!(validate_chunk(range, 2 * MB, 6 * MB, 2 * MB).is_err());
+    }
+
+    #[test]
+    fn chunk_count_rounds_up() {
+        assert_eq!(chunk_count(10 * MB, 2 * MB), 5);
+        assert_eq!(chunk_count(10 * MB + 1, 2 * MB), 6);
+        assert_eq!(chunk_count(1, 2 * MB), 1);
+    }
+}
@@ -0,0 +1,60 @@
+//! Current quota consumption for the authenticated user.
+
+use axum::{extract::State, Json};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use shared::types::ApiResponse;
+
+use crate::{
+    errors::AppResult,
+    middleware::request_context::RequestContext,
+    services::quota::{self, UsageKind},
+    state::AppState,
+};
+
+/// Consumption of one metered kind. `limit: null` means unlimited, in
+/// which case `used` is informational only.
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct UsageLine {
+    pub used: u64,
+    pub limit: Option<u64>,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct UsageReport {
+    /// When every counter resets: the first of the next month, UTC.
+    pub resets_at: DateTime<Utc>,
+    pub chat_messages: UsageLine,
+    pub vision_analyses: UsageLine,
+}
+
+/// `GET /api/v1/usage` — this month's consumption against the caller's
+/// quota, so clients can warn before a submission bounces with a 429.
+#[utoipa::path(
+    get,
+    path = "/api/v1/usage",
+    operation_id = "getUsage",
+    tag = "usage",
+    responses(
+        (status = 200, body = ApiResponse<UsageReport>),
+        (status = 401, body = crate::docs::ErrorBody)
+    ),
+    security(("bearer_jwt" = []))
+)]
+pub async fn get_usage(
+    State(state): State<AppState>,
+    ctx: RequestContext,
+) -> AppResult<Json<ApiResponse<UsageReport>>> {
+    let user = ctx.require_user()?;
+    let line = |kind: UsageKind, used: u64| UsageLine {
+        used,
+        limit: quota::limit_for(state.quotas.config(), &user.roles, kind),
+    };
+    let chat_used = state.quotas.used(user.user_id, UsageKind::ChatMessage).await?;
+    let vision_used = state.quotas.used(user.user_id, UsageKind::VisionAnalysis).await?;
+    Ok(Json(ApiResponse::ok(UsageReport {
+        resets_at: quota::window_reset(Utc::now()),
+        chat_messages: line(UsageKind::ChatMessage, chat_used),
+        vision_analyses: line(UsageKind::VisionAnalysis, vision_used),
+    })))
+}
@@ -0,0 +1,26 @@
+//! Version negotiation endpoint consumed by the frontend banner.
+
+use axum::{extract::State, Json};
+use serde::Serialize;
+
+use crate::state::AppState;
+
+#[derive(Debug, Serialize)]
+pub struct VersionPayload {
+    pub api_version: String,
+    pub min_frontend_version: String,
+    pub latest_frontend_version: String,
+    pub incompatible_below: String,
+}
+
+/// `GET /api/v1/version` — deliberately unauthenticated and cheap: stale
+/// clients poll this to find out they should reload.
+pub async fn get_version(State(state): State<AppState>) -> Json<VersionPayload> {
+    let versions = state.versions.read().expect("version lock poisoned");
+    Json(VersionPayload {
+        api_version: versions.api_version.clone(),
+        min_frontend_version: versions.min_frontend_version.clone(),
+        latest_frontend_version: versions.latest_frontend_version.clone(),
+        incompatible_below: versions.incompatible_below.clone(),
+    })
+}
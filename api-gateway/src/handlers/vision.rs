@@ -0,0 +1,1790 @@
+//! Vision analysis endpoints: queue a job, stream or poll its status, list
+//! past jobs.
+
+use std::{convert::Infallible, time::Duration};
+
+use axum::{
+    extract::{
+        ws::{CloseFrame, Message as WsMessage, WebSocket, WebSocketUpgrade},
+        Path, Query, State,
+    },
+    http::StatusCode,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    Json,
+};
+use futures_util::{Stream, StreamExt};
+use chrono::{DateTime, Utc};
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+use shared::{
+    models::{CropType, JobStatus, Language},
+    queue::{JobPriority, VisionJobRequestV1 as QueuedJob, VISION_JOB_SCHEMA_VERSION},
+    types::ApiResponse,
+};
+use sqlx::QueryBuilder;
+use uuid::Uuid;
+
+use crate::{
+    errors::{AppError, AppResult},
+    shared::pagination::{Allowlist, Cursor, Page, PageParams, PageRequest},
+    state::AppState,
+};
+
+const JOBS_ALLOWLIST: Allowlist = Allowlist::new(
+    &[("created_at", "created_at"), ("crop_type", "crop_type")],
+    &[("crop_type", "crop_type"), ("status", "status")],
+);
+
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+pub struct AnalyzeRequest {
+    /// Base64-encoded image bytes.
+    pub image_data: String,
+    pub crop_type: CropType,
+    pub user_query: Option<String>,
+    /// Ask for priority handling; see [`job_priority`] for how far the
+    /// flag actually gets a caller.
+    #[serde(default)]
+    pub urgent: bool,
+    /// Field sensor paired with this plot; lets the advice stage fold the
+    /// device's latest readings into the prompt as growing conditions.
+    #[serde(default)]
+    pub device_id: Option<String>,
+    /// Language the generated advice should answer in; Thai when absent.
+    #[serde(default)]
+    pub language: Language,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct JobEnvelope {
+    pub job_id: Uuid,
+    pub status: JobStatus,
+}
+
+/// Publish priority for a new job. The `urgent` flag alone gets a job to
+/// high; the top tier is reserved for officers and admins (field staff
+/// triaging an outbreak), so a client that always sends `urgent=true`
+/// cannot starve everyone else's urgent work.
+pub fn job_priority(urgent: bool, user: Option<&crate::AuthUser>) -> JobPriority {
+    let privileged = user.is_some_and(|u| u.has_role("officer") || u.has_role("admin"));
+    match (urgent, privileged) {
+        (true, true) => JobPriority::Urgent,
+        (true, false) => JobPriority::High,
+        (false, _) => JobPriority::Normal,
+    }
+}
+
+/// Completed result cached under the image's content hash, together with
+/// the model version that produced it — a model upgrade invalidates every
+/// prior entry.
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedResult {
+    model_version: String,
+    result: shared::models::VisionResponse,
+}
+
+/// What an analyze call returns: a fresh job to poll, the cached result of
+/// an identical earlier upload, or — for an `Idempotency-Key` retry — the
+/// envelope of the job the first attempt already created.
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+#[serde(untagged)]
+pub enum AnalyzeOutcome {
+    Cached {
+        cached: bool,
+        result: shared::models::VisionResponse,
+    },
+    Replayed {
+        idempotent_replay: bool,
+        job_id: Uuid,
+        status: JobStatus,
+    },
+    Queued(JobEnvelope),
+}
+
+fn image_cache_key(crop_type: CropType, hash: &str) -> String {
+    format!("vision_cache:{}:{hash}", crop_type.as_str())
+}
+
+fn hash_image(bytes: &[u8]) -> String {
+    use sha2::Digest;
+    let digest = sha2::Sha256::digest(bytes);
+    format!("{digest:x}")
+}
+
+/// Look up a cached result for this hash, dropping entries produced by an
+/// older model version than the latest one seen.
+async fn cached_result(
+    redis: &mut redis::aio::MultiplexedConnection,
+    key: &str,
+) -> Option<shared::models::VisionResponse> {
+    let raw: Option<String> = redis.get(key).await.ok()?;
+    let entry: CachedResult = serde_json::from_str(&raw?).ok()?;
+    let current_version: Option<String> = redis.get("vision_model_version").await.ok()?;
+    if let Some(current) = current_version {
+        if current != entry.model_version {
+            let _: Result<(), _> = redis.del(key).await;
+            return None;
+        }
+    }
+    Some(entry.result)
+}
+
+/// Publish a stored image as a vision job and record its initial status.
+/// Shared by the JSON and multipart analyze paths so both return the same
+/// job envelope. Re-uploads of a byte-identical image (common after a
+/// client timeout) short-circuit to the cached result instead of burning
+/// another inference run.
+pub(super) async fn enqueue_stored(
+    state: &AppState,
+    mut stored: crate::services::file_storage::StoredFile,
+    crop_type: CropType,
+    user_query: Option<String>,
+    priority: JobPriority,
+) -> AppResult<AnalyzeOutcome> {
+    let (original_bytes, compressed_bytes) =
+        state.file_storage.preprocess_image(&mut stored).await?;
+
+    let bytes = tokio::fs::read(&stored.path)
+        .await
+        .map_err(|e| AppError::Internal(format!("read stored file: {e}")))?;
+    let cache_key = image_cache_key(crop_type, &hash_image(&bytes));
+    drop(bytes);
+    {
+        let mut redis = state.get_redis().await?;
+        if let Some(result) = cached_result(&mut redis, &cache_key).await {
+            state.bump_counter("vision_cache_hits");
+            crate::metrics::record_cache_access("vision_results", true);
+            let _ = state.file_storage.delete_file(&stored.path).await;
+            return Ok(AnalyzeOutcome::Cached { cached: true, result });
+        }
+    }
+    state.bump_counter("vision_cache_misses");
+    crate::metrics::record_cache_access("vision_results", false);
+    let job = QueuedJob {
+        schema_version: VISION_JOB_SCHEMA_VERSION,
+        job_id: stored.id,
+        crop_type,
+        image_path: stored.path.display().to_string(),
+        user_query,
+        queued_at: Utc::now(),
+        priority,
+    };
+    state.rabbitmq.publish(&job).await?;
+
+    sqlx::query(
+        "INSERT INTO vision_jobs (id, user_id, crop_type, status, created_at) \
+         VALUES ($1, NULL, $2, 'queued', $3)",
+    )
+    .bind(job.job_id)
+    .bind(job.crop_type.as_str())
+    .bind(job.queued_at)
+    .execute(&state.db)
+    .await?;
+
+    let mut redis = state.get_redis().await?;
+    let _: () = redis
+        .set(format!("job:{}:status", job.job_id), "queued")
+        .await
+        .map_err(|e| AppError::ServiceUnavailable(format!("redis: {e}")))?;
+    let _: Result<(), _> = redis
+        .zadd(
+            crate::metrics::PENDING_JOBS_KEY,
+            job.job_id.to_string(),
+            job.queued_at.timestamp(),
+        )
+        .await;
+    // Per-job and aggregate compression stats feed /vision/files/stats; a
+    // stats failure must not fail the queued job.
+    let _: Result<(), _> = redis
+        .hset_multiple(
+            format!("job:{}:file_stats", job.job_id),
+            &[("original_bytes", original_bytes), ("compressed_bytes", compressed_bytes)],
+        )
+        .await;
+    let _: Result<(), _> = redis.incr("file_stats:original_bytes", original_bytes).await;
+    let _: Result<(), _> = redis
+        .incr("file_stats:compressed_bytes", compressed_bytes)
+        .await;
+    // Remember which cache slot this job should fill once it completes.
+    let _: Result<(), _> = redis
+        .set_ex(
+            format!("job:{}:cache_key", job.job_id),
+            &cache_key,
+            state.config.vision_cache.ttl.as_secs(),
+        )
+        .await;
+    // The audit row written at completion wants the submitting request's
+    // id, and the advice stage runs long after this request scope is gone.
+    if let Some(request_id) = crate::middleware::correlation::current_correlation_id() {
+        let _: Result<(), _> = redis
+            .set_ex(format!("job:{}:request_id", job.job_id), request_id, 24 * 60 * 60)
+            .await;
+    }
+
+    Ok(AnalyzeOutcome::Queued(JobEnvelope {
+        job_id: job.job_id,
+        status: JobStatus::Queued,
+    }))
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct FileStatsResponse {
+    pub original_bytes: u64,
+    pub compressed_bytes: u64,
+    pub saved_bytes: u64,
+    /// Bytes not written because an identical upload was already on disk.
+    pub dedup_saved_bytes: u64,
+}
+
+/// `GET /api/v1/vision/files/stats` — aggregate space savings from the
+/// upload compression and dedup pipeline.
+#[utoipa::path(
+    get,
+    path = "/api/v1/vision/files/stats",
+    operation_id = "getVisionFileStats",
+    tag = "vision",
+    responses(
+        (status = 200, body = ApiResponse<FileStatsResponse>),
+        (status = 401, body = crate::docs::ErrorBody),
+        (status = 403, description = "caller is not an admin", body = crate::docs::ErrorBody)
+    ),
+    security(("bearer_jwt" = []))
+)]
+pub async fn get_file_stats(
+    State(state): State<AppState>,
+    // Fleet-wide storage numbers are operator material, not farmer-facing.
+    _admin: crate::middleware::auth::RequireRole<crate::middleware::auth::Admin>,
+) -> AppResult<Json<ApiResponse<FileStatsResponse>>> {
+    let mut redis = state.get_redis().await?;
+    let (original_bytes, compressed_bytes): (Option<u64>, Option<u64>) = redis::pipe()
+        .get("file_stats:original_bytes")
+        .get("file_stats:compressed_bytes")
+        .query_async(&mut redis)
+        .await
+        .map_err(|e| AppError::ServiceUnavailable(format!("redis: {e}")))?;
+    let original_bytes = original_bytes.unwrap_or(0);
+    let compressed_bytes = compressed_bytes.unwrap_or(0);
+    Ok(Json(ApiResponse::ok(FileStatsResponse {
+        original_bytes,
+        compressed_bytes,
+        saved_bytes: original_bytes.saturating_sub(compressed_bytes),
+        dedup_saved_bytes: state.file_storage.dedup_saved_bytes(),
+    })))
+}
+
+/// Rows shown per `jobs/failed` listing; `failed_jobs` should be
+/// near-empty in steady state, so a page this size covers incidents too.
+const MAX_FAILED_JOBS_PAGE: i64 = 50;
+
+/// One exhausted job as drained into `failed_jobs` by the DLQ consumer.
+#[derive(Debug, Serialize, sqlx::FromRow, utoipa::ToSchema)]
+pub struct FailedJobRow {
+    pub job_id: Uuid,
+    #[sqlx(json)]
+    pub payload: serde_json::Value,
+    pub retry_count: i32,
+    pub parked_at: DateTime<Utc>,
+}
+
+/// `GET /api/v1/vision/jobs/failed` — admin view of jobs that exhausted
+/// their retries, read from the `failed_jobs` table the DLQ consumer
+/// drains into.
+#[utoipa::path(
+    get,
+    path = "/api/v1/vision/jobs/failed",
+    operation_id = "listFailedVisionJobs",
+    tag = "vision",
+    responses(
+        (status = 200, description = "failed jobs, newest first", body = ApiResponse<Vec<FailedJobRow>>),
+        (status = 401, body = crate::docs::ErrorBody),
+        (status = 403, description = "caller is not an admin", body = crate::docs::ErrorBody)
+    ),
+    security(("bearer_jwt" = []))
+)]
+pub async fn list_failed_jobs(
+    State(state): State<AppState>,
+    ctx: crate::middleware::request_context::RequestContext,
+) -> AppResult<Json<ApiResponse<Vec<FailedJobRow>>>> {
+    crate::middleware::auth::require_role(ctx.require_user()?, "admin")?;
+    let jobs: Vec<FailedJobRow> = sqlx::query_as(
+        "SELECT job_id, payload, retry_count, parked_at FROM failed_jobs
+         ORDER BY parked_at DESC LIMIT $1",
+    )
+    .bind(MAX_FAILED_JOBS_PAGE)
+    .fetch_all(&state.db)
+    .await
+    .map_err(|e| AppError::Database(format!("list failed jobs: {e}")))?;
+    Ok(Json(ApiResponse::ok(jobs)))
+}
+
+/// `POST /api/v1/vision/jobs/failed/:job_id/retry` — republish one failed
+/// job onto the main queue with a fresh retry budget and drop its
+/// `failed_jobs` row.
+#[utoipa::path(
+    post,
+    path = "/api/v1/vision/jobs/failed/{job_id}/retry",
+    operation_id = "retryFailedVisionJob",
+    tag = "vision",
+    params(("job_id" = String, Path)),
+    responses(
+        (status = 200, body = ApiResponse<JobEnvelope>),
+        (status = 404, description = "job is not parked on the DLQ", body = crate::docs::ErrorBody),
+        (status = 403, body = crate::docs::ErrorBody)
+    ),
+    security(("bearer_jwt" = []))
+)]
+pub async fn retry_failed_job(
+    State(state): State<AppState>,
+    ctx: crate::middleware::request_context::RequestContext,
+    Path(job_id): Path<Uuid>,
+) -> AppResult<Json<ApiResponse<JobEnvelope>>> {
+    let user = ctx.require_user()?.clone();
+    crate::middleware::auth::require_role(&user, "admin")?;
+    let row: Option<(serde_json::Value,)> =
+        sqlx::query_as("SELECT payload FROM failed_jobs WHERE job_id = $1")
+            .bind(job_id)
+            .fetch_optional(&state.db)
+            .await
+            .map_err(|e| AppError::Database(format!("load failed job: {e}")))?;
+    let Some((payload,)) = row else {
+        return Err(AppError::NotFound(format!("job {job_id} is not failed")));
+    };
+    // The parked payload must still decode as a job this build speaks;
+    // retrying an unparseable payload would only cycle it through the DLQ.
+    let job = serde_json::to_vec(&payload)
+        .ok()
+        .and_then(|body| shared::queue::decode_job_request(&body).ok())
+        .ok_or_else(|| {
+            AppError::Validation(format!("failed job {job_id} payload is not a retryable job"))
+        })?;
+    // Publish before deleting: a crash in between leaves a stale row an
+    // admin can retry again, which beats losing the job.
+    state.rabbitmq.publish(&job).await?;
+    sqlx::query("DELETE FROM failed_jobs WHERE job_id = $1")
+        .bind(job_id)
+        .execute(&state.db)
+        .await
+        .map_err(|e| AppError::Database(format!("delete failed job: {e}")))?;
+    Ok(Json(ApiResponse::ok(JobEnvelope {
+        job_id,
+        status: JobStatus::Queued,
+    })))
+}
+
+/// Fingerprint of everything in a submission that affects its outcome.
+/// Detects an `Idempotency-Key` reused for a *different* request, which is
+/// a client bug rather than a retry.
+fn submission_fingerprint(request: &AnalyzeRequest) -> String {
+    hash_image(
+        format!(
+            "{}|{}|{}|{}|{}",
+            request.image_data,
+            request.crop_type.as_str(),
+            request.user_query.as_deref().unwrap_or(""),
+            request.device_id.as_deref().unwrap_or(""),
+            request.language.as_str()
+        )
+        .as_bytes(),
+    )
+}
+
+/// Build the envelope a keyed retry gets back: the original job id plus its
+/// *current* status — the client likely never saw the first response, so
+/// hand it something it can poll (or act on) immediately.
+async fn replayed_envelope(state: &AppState, job_id: Uuid) -> AnalyzeOutcome {
+    let status = match state.get_redis().await {
+        Ok(mut redis) => {
+            let raw: Option<String> = redis
+                .get(format!("job:{job_id}:status"))
+                .await
+                .ok()
+                .flatten();
+            raw.and_then(|s| serde_json::from_value(serde_json::Value::String(s)).ok())
+                .unwrap_or(JobStatus::Queued)
+        }
+        Err(_) => JobStatus::Queued,
+    };
+    AnalyzeOutcome::Replayed {
+        idempotent_replay: true,
+        job_id,
+        status,
+    }
+}
+
+/// `POST /api/v1/vision/analyze` — store the image, enqueue a job, and
+/// return its id for status polling. Sending an `Idempotency-Key` header
+/// makes retries safe: the first request through creates the job and
+/// retries of the same body replay its envelope instead of queueing again.
+#[utoipa::path(
+    post,
+    path = "/api/v1/vision/analyze",
+    operation_id = "queueVisionAnalysis",
+    tag = "vision",
+    request_body = AnalyzeRequest,
+    params(
+        ("idempotency-key" = Option<String>, Header,
+         description = "client-chosen retry key; a repeat submission with the same key and body replays the original job envelope")
+    ),
+    responses(
+        (status = 200, description = "a queued job envelope, the cached result of an identical earlier upload, or a replayed envelope (`idempotent_replay: true`) for a keyed retry", body = ApiResponse<AnalyzeOutcome>),
+        (status = 400, description = "bad base64, unsupported type, or oversized image", body = crate::docs::ErrorBody),
+        (status = 401, body = crate::docs::ErrorBody),
+        (status = 409, description = "a submission with this idempotency key is still in flight", body = crate::docs::ErrorBody),
+        (status = 422, description = "idempotency key reused with a different request body", body = crate::docs::ErrorBody)
+    ),
+    security(("bearer_jwt" = []))
+)]
+pub async fn queue_vision_analysis(
+    State(state): State<AppState>,
+    ctx: crate::middleware::request_context::RequestContext,
+    headers: axum::http::HeaderMap,
+    Json(request): Json<AnalyzeRequest>,
+) -> AppResult<Json<ApiResponse<AnalyzeOutcome>>> {
+    use crate::shared::idempotency::{self, IdempotencyStore};
+
+    let claim = match headers
+        .get(idempotency::IDEMPOTENCY_HEADER)
+        .and_then(|v| v.to_str().ok())
+    {
+        Some(key) => {
+            let store = idempotency::RedisIdempotencyStore {
+                client: state.redis_client.clone(),
+            };
+            let scoped = idempotency::scoped_key(ctx.user.as_ref(), key);
+            let fingerprint = submission_fingerprint(&request);
+            match idempotency::claim_or_replay(&store, &scoped, &fingerprint).await? {
+                idempotency::Claim::Replay { job_id } => {
+                    state.bump_counter("idempotent_replays");
+                    return Ok(Json(ApiResponse::ok(replayed_envelope(&state, job_id).await)));
+                }
+                idempotency::Claim::Won => Some((store, scoped, fingerprint)),
+            }
+        }
+        None => None,
+    };
+
+    // Streaming decode with pre-decode size estimation; never materializes
+    // the decoded image in memory.
+    let result = async {
+        // Metered inside the fallible block: a quota rejection must release
+        // the idempotency claim, and a replay (returned above) costs nothing.
+        state
+            .quotas
+            .admit(ctx.user.as_ref(), crate::services::quota::UsageKind::VisionAnalysis)
+            .await?;
+        let stored = state
+            .file_storage
+            .store_base64(&request.image_data, "jpg")
+            .await?;
+        let priority = job_priority(request.urgent, ctx.user.as_ref());
+        enqueue_stored(&state, stored, request.crop_type, request.user_query, priority).await
+    }
+    .await;
+
+    let outcome = match (result, &claim) {
+        (Ok(outcome), _) => outcome,
+        // A failed submission must not poison the key: drop the claim so
+        // the client's retry runs for real.
+        (Err(err), Some((store, scoped, _))) => {
+            store.release(scoped).await;
+            return Err(err);
+        }
+        (Err(err), None) => return Err(err),
+    };
+
+    // Pin per-job context for the advice stage, which runs long after this
+    // request: the response language, and the paired sensor (if any) whose
+    // latest reading it folds into the prompt.
+    if let AnalyzeOutcome::Queued(envelope) = &outcome {
+        if let Ok(mut redis) = state.get_redis().await {
+            let _: Result<(), _> = redis
+                .set_ex(
+                    format!("job:{}:language", envelope.job_id),
+                    request.language.as_str(),
+                    24 * 60 * 60,
+                )
+                .await;
+            if let Some(device_id) = request.device_id.as_deref() {
+                let _: Result<(), _> = redis
+                    .set_ex(format!("job:{}:device_id", envelope.job_id), device_id, 24 * 60 * 60)
+                    .await;
+            }
+        }
+    }
+
+    if let Some((store, scoped, fingerprint)) = claim {
+        match &outcome {
+            AnalyzeOutcome::Queued(envelope) => {
+                store
+                    .fulfill(
+                        &scoped,
+                        &idempotency::IdempotencyRecord {
+                            body_hash: fingerprint,
+                            job_id: Some(envelope.job_id),
+                        },
+                    )
+                    .await?;
+            }
+            // A cache hit made no job; drop the claim so retries take the
+            // same (cheap) cache path instead of waiting on a job id.
+            _ => store.release(&scoped).await,
+        }
+    }
+    Ok(Json(ApiResponse::ok(outcome)))
+}
+
+fn parse_crop_type(value: &str) -> AppResult<CropType> {
+    serde_json::from_value(serde_json::Value::String(value.to_string()))
+        .map_err(|_| AppError::Validation(format!("unknown crop_type '{value}'")))
+}
+
+/// `POST /api/v1/vision/analyze/upload` — multipart sibling of the JSON
+/// route for raw file uploads (avoids the ~33% base64 overhead and keeps
+/// curl testing simple). Expects an `image` file part plus `crop_type` and
+/// optional `user_query` fields. The file's magic bytes must match its
+/// declared extension — extensions are caller-controlled and lie.
+#[utoipa::path(
+    post,
+    path = "/api/v1/vision/analyze/upload",
+    operation_id = "queueVisionUpload",
+    tag = "vision",
+    request_body(content_type = "multipart/form-data", description = "an `image` file part plus `crop_type` and optional `user_query` fields"),
+    responses(
+        (status = 200, body = ApiResponse<AnalyzeOutcome>),
+        (status = 400, description = "missing part, mismatched magic bytes, or oversized image", body = crate::docs::ErrorBody),
+        (status = 401, body = crate::docs::ErrorBody)
+    ),
+    security(("bearer_jwt" = []))
+)]
+pub async fn queue_vision_upload(
+    State(state): State<AppState>,
+    ctx: crate::middleware::request_context::RequestContext,
+    mut multipart: axum::extract::Multipart,
+) -> AppResult<Json<ApiResponse<AnalyzeOutcome>>> {
+    let mut image: Option<(Vec<u8>, String)> = None;
+    let mut crop_type: Option<CropType> = None;
+    let mut user_query: Option<String> = None;
+    let mut urgent = false;
+
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(|e| AppError::Validation(format!("malformed multipart body: {e}")))?
+    {
+        match field.name().unwrap_or_default() {
+            "image" => {
+                let extension = field
+                    .file_name()
+                    .and_then(|n| n.rsplit('.').next())
+                    .unwrap_or("jpg")
+                    .to_ascii_lowercase();
+                state.file_storage.validate_format(&extension)?;
+                let bytes = field
+                    .bytes()
+                    .await
+                    .map_err(|e| AppError::Validation(format!("read image part: {e}")))?;
+                if bytes.len() > state.file_storage.config().max_file_size {
+                    return Err(AppError::Validation(format!(
+                        "file size {} exceeds limit {}",
+                        bytes.len(),
+                        state.file_storage.config().max_file_size
+                    )));
+                }
+                if !crate::utils::image_sniff::matches_extension(&bytes, &extension) {
+                    return Err(AppError::Validation(
+                        "file content does not match its declared image format".into(),
+                    ));
+                }
+                image = Some((bytes.to_vec(), extension));
+            }
+            "crop_type" => {
+                let value = field
+                    .text()
+                    .await
+                    .map_err(|e| AppError::Validation(format!("read crop_type: {e}")))?;
+                crop_type = Some(parse_crop_type(value.trim())?);
+            }
+            "user_query" => {
+                let value = field
+                    .text()
+                    .await
+                    .map_err(|e| AppError::Validation(format!("read user_query: {e}")))?;
+                if !value.trim().is_empty() {
+                    user_query = Some(value);
+                }
+            }
+            "urgent" => {
+                let value = field
+                    .text()
+                    .await
+                    .map_err(|e| AppError::Validation(format!("read urgent: {e}")))?;
+                urgent = value
+                    .trim()
+                    .parse()
+                    .map_err(|_| AppError::Validation("urgent must be true or false".into()))?;
+            }
+            _ => {}
+        }
+    }
+
+    let (bytes, extension) =
+        image.ok_or_else(|| AppError::Validation("missing 'image' file part".into()))?;
+    let crop_type =
+        crop_type.ok_or_else(|| AppError::Validation("missing 'crop_type' field".into()))?;
+
+    state
+        .quotas
+        .admit(ctx.user.as_ref(), crate::services::quota::UsageKind::VisionAnalysis)
+        .await?;
+    let stored = state.file_storage.store_file(&bytes, &extension).await?;
+    let priority = job_priority(urgent, ctx.user.as_ref());
+    let outcome = enqueue_stored(&state, stored, crop_type, user_query, priority).await?;
+    Ok(Json(ApiResponse::ok(outcome)))
+}
+
+/// Upper bound on images per batch request; bursts from a field survey fit
+/// well under this.
+pub const MAX_BATCH_SIZE: usize = 20;
+
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+pub struct BatchItem {
+    /// Base64-encoded image bytes.
+    pub image_data: String,
+    pub crop_type: CropType,
+}
+
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+pub struct BatchAnalyzeRequest {
+    pub images: Vec<BatchItem>,
+    pub user_query: Option<String>,
+    /// Applies to every job in the batch.
+    #[serde(default)]
+    pub urgent: bool,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct BatchJobResponse {
+    pub batch_id: Uuid,
+    pub job_ids: Vec<Uuid>,
+}
+
+/// Pair each stored file with its request item; job ids are the stored file
+/// ids, so they are distinct by construction.
+fn batch_jobs(
+    stored: &[crate::services::file_storage::StoredFile],
+    items: &[BatchItem],
+    user_query: Option<&str>,
+    queued_at: DateTime<Utc>,
+    priority: JobPriority,
+) -> Vec<QueuedJob> {
+    stored
+        .iter()
+        .zip(items)
+        .map(|(file, item)| QueuedJob {
+            schema_version: VISION_JOB_SCHEMA_VERSION,
+            job_id: file.id,
+            crop_type: item.crop_type,
+            image_path: file.path.display().to_string(),
+            user_query: user_query.map(str::to_string),
+            queued_at,
+            priority,
+        })
+        .collect()
+}
+
+fn batch_key(batch_id: Uuid) -> String {
+    format!("batch:{batch_id}:jobs")
+}
+
+fn batch_owner_key(batch_id: Uuid) -> String {
+    format!("batch:{batch_id}:owner")
+}
+
+/// `POST /api/v1/vision/analyze/batch` — queue several images of the same
+/// field in one call. Files are stored all-or-nothing; one job per image.
+#[utoipa::path(
+    post,
+    path = "/api/v1/vision/analyze/batch",
+    operation_id = "queueBatchAnalysis",
+    tag = "vision",
+    request_body = BatchAnalyzeRequest,
+    responses(
+        (status = 200, body = ApiResponse<BatchJobResponse>),
+        (status = 400, description = "empty or oversized batch, or a bad image", body = crate::docs::ErrorBody),
+        (status = 401, body = crate::docs::ErrorBody)
+    ),
+    security(("bearer_jwt" = []))
+)]
+pub async fn queue_batch_analysis(
+    State(state): State<AppState>,
+    ctx: crate::middleware::request_context::RequestContext,
+    Json(request): Json<BatchAnalyzeRequest>,
+) -> AppResult<Json<ApiResponse<BatchJobResponse>>> {
+    if request.images.is_empty() {
+        return Err(AppError::Validation("batch must contain at least one image".into()));
+    }
+    if request.images.len() > MAX_BATCH_SIZE {
+        return Err(AppError::Validation(format!(
+            "batch size {} exceeds limit {MAX_BATCH_SIZE}",
+            request.images.len()
+        )));
+    }
+    // A batch of N images spends N analyses in one atomic step, so it
+    // either fits in the remaining allowance or bounces whole.
+    state
+        .quotas
+        .admit_many(
+            ctx.user.as_ref(),
+            crate::services::quota::UsageKind::VisionAnalysis,
+            request.images.len() as u64,
+        )
+        .await?;
+
+    let payloads: Vec<(String, String)> = request
+        .images
+        .iter()
+        .map(|item| (item.image_data.clone(), "jpg".to_string()))
+        .collect();
+    let stored = state.file_storage.store_batch(&payloads).await?;
+
+    let jobs = batch_jobs(
+        &stored,
+        &request.images,
+        request.user_query.as_deref(),
+        Utc::now(),
+        job_priority(request.urgent, ctx.user.as_ref()),
+    );
+
+    let batch_id = Uuid::new_v4();
+    let mut redis = state.get_redis().await?;
+    // Batches are not rows, so ownership rides on a Redis key next to the
+    // membership set; `get_batch_status` checks it.
+    if let Some(user) = ctx.user.as_ref() {
+        let _: () = redis
+            .set(batch_owner_key(batch_id), user.user_id.to_string())
+            .await
+            .map_err(|e| AppError::ServiceUnavailable(format!("redis: {e}")))?;
+    }
+    for job in &jobs {
+        state.rabbitmq.publish(job).await?;
+        sqlx::query(
+            "INSERT INTO vision_jobs (id, user_id, crop_type, status, created_at) \
+             VALUES ($1, NULL, $2, 'queued', $3)",
+        )
+        .bind(job.job_id)
+        .bind(job.crop_type.as_str())
+        .bind(job.queued_at)
+        .execute(&state.db)
+        .await?;
+        let _: () = redis
+            .set(format!("job:{}:status", job.job_id), "queued")
+            .await
+            .map_err(|e| AppError::ServiceUnavailable(format!("redis: {e}")))?;
+        let _: Result<(), _> = redis
+            .zadd(
+                crate::metrics::PENDING_JOBS_KEY,
+                job.job_id.to_string(),
+                job.queued_at.timestamp(),
+            )
+            .await;
+        let _: () = redis
+            .sadd(batch_key(batch_id), job.job_id.to_string())
+            .await
+            .map_err(|e| AppError::ServiceUnavailable(format!("redis: {e}")))?;
+    }
+
+    Ok(Json(ApiResponse::ok(BatchJobResponse {
+        batch_id,
+        job_ids: jobs.iter().map(|j| j.job_id).collect(),
+    })))
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct BatchStatusResponse {
+    pub total: u32,
+    pub completed: u32,
+    pub failed: u32,
+    pub jobs: Vec<JobStatus>,
+}
+
+fn aggregate_batch(jobs: Vec<JobStatus>) -> BatchStatusResponse {
+    let completed = jobs.iter().filter(|s| **s == JobStatus::Completed).count() as u32;
+    let failed = jobs.iter().filter(|s| **s == JobStatus::Failed).count() as u32;
+    BatchStatusResponse {
+        total: jobs.len() as u32,
+        completed,
+        failed,
+        jobs,
+    }
+}
+
+/// `GET /api/v1/vision/batches/:batch_id` — aggregate per-job statuses from
+/// Redis into batch-level progress.
+#[utoipa::path(
+    get,
+    path = "/api/v1/vision/batches/{batch_id}",
+    operation_id = "getBatchStatus",
+    tag = "vision",
+    params(("batch_id" = String, Path)),
+    responses(
+        (status = 200, body = ApiResponse<BatchStatusResponse>),
+        (status = 404, body = crate::docs::ErrorBody),
+        (status = 401, body = crate::docs::ErrorBody)
+    ),
+    security(("bearer_jwt" = []))
+)]
+pub async fn get_batch_status(
+    State(state): State<AppState>,
+    ctx: crate::middleware::request_context::RequestContext,
+    Path(batch_id): Path<Uuid>,
+) -> AppResult<Json<ApiResponse<BatchStatusResponse>>> {
+    let user = ctx.require_user()?.clone();
+    let mut redis = state.get_redis().await?;
+    // Foreign batches read as not found, like foreign jobs; batches from
+    // before the owner key existed stay readable, like NULL-owner rows.
+    let owner: Option<String> = redis
+        .get(batch_owner_key(batch_id))
+        .await
+        .map_err(|e| AppError::ServiceUnavailable(format!("redis: {e}")))?;
+    if owner.is_some_and(|o| o != user.user_id.to_string()) {
+        return Err(AppError::NotFound(format!("batch {batch_id}")));
+    }
+    let job_ids: Vec<String> = redis
+        .smembers(batch_key(batch_id))
+        .await
+        .map_err(|e| AppError::ServiceUnavailable(format!("redis: {e}")))?;
+    if job_ids.is_empty() {
+        return Err(AppError::NotFound(format!("batch {batch_id}")));
+    }
+
+    let mut jobs = Vec::with_capacity(job_ids.len());
+    for job_id in job_ids {
+        let status: Option<String> = redis
+            .get(format!("job:{job_id}:status"))
+            .await
+            .map_err(|e| AppError::ServiceUnavailable(format!("redis: {e}")))?;
+        let status = status
+            .and_then(|s| serde_json::from_value(serde_json::Value::String(s)).ok())
+            .unwrap_or(JobStatus::Queued);
+        jobs.push(status);
+    }
+
+    Ok(Json(ApiResponse::ok(aggregate_batch(jobs))))
+}
+
+/// 404 unless the job exists and belongs to `user_id` (NULL owners are
+/// legacy pre-auth rows). Foreign jobs read as not found rather than 403,
+/// matching `delete_conversation`'s convention, so job ids don't leak.
+async fn require_job_owner(state: &AppState, job_id: Uuid, user_id: Uuid) -> AppResult<()> {
+    let owned: Option<i32> = sqlx::query_scalar(
+        "SELECT 1 FROM vision_jobs WHERE id = $1 AND (user_id = $2 OR user_id IS NULL)",
+    )
+    .bind(job_id)
+    .bind(user_id)
+    .fetch_optional(&state.db)
+    .await?;
+    owned
+        .map(|_| ())
+        .ok_or_else(|| AppError::NotFound(format!("job {job_id}")))
+}
+
+/// `GET /api/v1/vision/jobs/:job_id` — current job status from Redis.
+#[utoipa::path(
+    get,
+    path = "/api/v1/vision/jobs/{job_id}",
+    operation_id = "getJobStatus",
+    tag = "vision",
+    params(("job_id" = String, Path)),
+    responses(
+        (status = 200, body = ApiResponse<JobEnvelope>),
+        (status = 404, body = crate::docs::ErrorBody),
+        (status = 401, body = crate::docs::ErrorBody)
+    ),
+    security(("bearer_jwt" = []))
+)]
+pub async fn get_job_status(
+    State(state): State<AppState>,
+    ctx: crate::middleware::request_context::RequestContext,
+    Path(job_id): Path<Uuid>,
+) -> AppResult<Json<ApiResponse<JobEnvelope>>> {
+    let user = ctx.require_user()?.clone();
+    require_job_owner(&state, job_id, user.user_id).await?;
+    let mut redis = state.get_redis().await?;
+    let status: Option<String> = redis
+        .get(format!("job:{job_id}:status"))
+        .await
+        .map_err(|e| AppError::ServiceUnavailable(format!("redis: {e}")))?;
+    let status = status.ok_or_else(|| AppError::NotFound(format!("job {job_id}")))?;
+    let status: JobStatus = serde_json::from_value(serde_json::Value::String(status))
+        .map_err(|e| AppError::Internal(format!("stored job status: {e}")))?;
+
+    if status == JobStatus::Completed {
+        populate_result_cache(&state, &mut redis, job_id).await;
+    }
+    // A poll is also a nudge: if the advice stage hasn't started (listener
+    // missed the publish, or the gateway restarted), kick it now. The
+    // per-job lock makes this idempotent.
+    if status == JobStatus::Diagnosed {
+        crate::services::advice::spawn_stage(state.clone(), job_id);
+    }
+
+    Ok(Json(ApiResponse::ok(JobEnvelope { job_id, status })))
+}
+
+fn cancelled_key(job_id: Uuid) -> String {
+    format!("job:{job_id}:cancelled")
+}
+
+/// How long the cancellation marker lives: long enough for a queued job to
+/// surface from a deep backlog and still be discarded by the worker.
+const CANCEL_MARKER_TTL_SECS: u64 = 24 * 60 * 60;
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct CancelOutcome {
+    pub job_id: Uuid,
+    pub status: JobStatus,
+    /// Final result, present when cancellation lost the race to the
+    /// analysis finishing.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<shared::models::VisionResponse>,
+}
+
+/// `POST /api/v1/vision/jobs/:job_id/cancel` — stop a job that has not
+/// produced a result yet. The cancellation marker is checked by the worker
+/// before and during inference, so a cancelled job stops consuming worker
+/// time instead of just being relabelled. Jobs whose analysis already
+/// finished return 409 carrying the result — the work happened, the client
+/// should show it.
+#[utoipa::path(
+    post,
+    path = "/api/v1/vision/jobs/{job_id}/cancel",
+    operation_id = "cancelVisionJob",
+    tag = "vision",
+    params(("job_id" = String, Path)),
+    responses(
+        (status = 200, description = "job cancelled (idempotent)", body = ApiResponse<CancelOutcome>),
+        (status = 409, description = "the analysis already finished; the body carries the result", body = ApiResponse<CancelOutcome>),
+        (status = 404, body = crate::docs::ErrorBody),
+        (status = 401, body = crate::docs::ErrorBody)
+    ),
+    security(("bearer_jwt" = []))
+)]
+pub async fn cancel_job(
+    State(state): State<AppState>,
+    ctx: crate::middleware::request_context::RequestContext,
+    Path(job_id): Path<Uuid>,
+) -> AppResult<Response> {
+    let user = ctx.require_user()?.clone();
+    require_job_owner(&state, job_id, user.user_id).await?;
+    let mut redis = state.get_redis().await?;
+    let status: Option<String> = redis
+        .get(format!("job:{job_id}:status"))
+        .await
+        .map_err(|e| AppError::ServiceUnavailable(format!("redis: {e}")))?;
+    let status = status.ok_or_else(|| AppError::NotFound(format!("job {job_id}")))?;
+    let status: JobStatus = serde_json::from_value(serde_json::Value::String(status))
+        .map_err(|e| AppError::Internal(format!("stored job status: {e}")))?;
+
+    match status {
+        // Repeating a cancel is a no-op, not an error.
+        JobStatus::Cancelled => Ok(Json(ApiResponse::ok(CancelOutcome {
+            job_id,
+            status,
+            result: None,
+        }))
+        .into_response()),
+        JobStatus::Failed => Err(AppError::Conflict(format!("job {job_id} already failed"))),
+        // The vision result exists (advice may still be pending): too late
+        // to save any work, so hand the result over instead.
+        JobStatus::Completed | JobStatus::Diagnosed => {
+            let raw: Option<String> = redis
+                .get(format!("job:{job_id}:result"))
+                .await
+                .ok()
+                .flatten();
+            let result = raw.and_then(|r| serde_json::from_str(&r).ok());
+            Ok((
+                StatusCode::CONFLICT,
+                Json(ApiResponse::ok(CancelOutcome { job_id, status, result })),
+            )
+                .into_response())
+        }
+        JobStatus::Queued | JobStatus::Processing => {
+            let _: () = redis
+                .set_ex(cancelled_key(job_id), 1, CANCEL_MARKER_TTL_SECS)
+                .await
+                .map_err(|e| AppError::ServiceUnavailable(format!("redis: {e}")))?;
+            let _: () = redis
+                .set(format!("job:{job_id}:status"), "cancelled")
+                .await
+                .map_err(|e| AppError::ServiceUnavailable(format!("redis: {e}")))?;
+            // Terminal publish closes open SSE/WS status streams.
+            let payload = serde_json::json!({ "status": "cancelled" }).to_string();
+            let _: Result<(), _> = redis.publish(format!("job_status:{job_id}"), payload).await;
+            crate::middleware::response_cache::invalidate(
+                &mut redis,
+                None,
+                &format!("/api/v1/vision/jobs/{job_id}"),
+            )
+            .await;
+            let _: Result<(), _> = redis
+                .zrem(crate::metrics::PENDING_JOBS_KEY, job_id.to_string())
+                .await;
+            let _ = sqlx::query("UPDATE vision_jobs SET status = 'cancelled' WHERE id = $1")
+                .bind(job_id)
+                .execute(&state.db)
+                .await;
+            Ok(Json(ApiResponse::ok(CancelOutcome {
+                job_id,
+                status: JobStatus::Cancelled,
+                result: None,
+            }))
+            .into_response())
+        }
+    }
+}
+
+/// `POST /api/v1/vision/jobs/:job_id/advice/retry` — re-run just the
+/// advice stage of a diagnosed job, e.g. after the LLM service was down.
+/// The vision result is untouched; only the second stage repeats.
+#[utoipa::path(
+    post,
+    path = "/api/v1/vision/jobs/{job_id}/advice/retry",
+    operation_id = "retryJobAdvice",
+    tag = "vision",
+    params(("job_id" = String, Path)),
+    responses(
+        (status = 200, body = ApiResponse<JobEnvelope>),
+        (status = 404, description = "job unknown or has no vision result yet", body = crate::docs::ErrorBody),
+        (status = 401, body = crate::docs::ErrorBody)
+    ),
+    security(("bearer_jwt" = []))
+)]
+pub async fn retry_job_advice(
+    State(state): State<AppState>,
+    ctx: crate::middleware::request_context::RequestContext,
+    Path(job_id): Path<Uuid>,
+) -> AppResult<Json<ApiResponse<JobEnvelope>>> {
+    let user = ctx.require_user()?.clone();
+    require_job_owner(&state, job_id, user.user_id).await?;
+    let mut redis = state.get_redis().await?;
+    let status: Option<String> = redis
+        .get(format!("job:{job_id}:status"))
+        .await
+        .map_err(|e| AppError::ServiceUnavailable(format!("redis: {e}")))?;
+    let status = status.ok_or_else(|| AppError::NotFound(format!("job {job_id}")))?;
+    let status: JobStatus = serde_json::from_value(serde_json::Value::String(status))
+        .map_err(|e| AppError::Internal(format!("stored job status: {e}")))?;
+    if !status.has_vision_result() {
+        return Err(AppError::Validation(format!(
+            "job {job_id} has no diagnosis yet; advice cannot be generated"
+        )));
+    }
+    if status == JobStatus::Completed {
+        return Ok(Json(ApiResponse::ok(JobEnvelope { job_id, status })));
+    }
+
+    let status = crate::services::advice::run_stage(&state, job_id).await?;
+    Ok(Json(ApiResponse::ok(JobEnvelope { job_id, status })))
+}
+
+/// Copy a completed job's result into its content-hash cache slot so a
+/// re-upload of the same image can skip the queue. The gateway sees results
+/// only in Redis (`job:{id}:result`, written by the worker), so population
+/// is lazy: the first status poll after completion fills the cache. Best
+/// effort throughout — a cache problem must never break a status poll.
+async fn populate_result_cache(
+    state: &AppState,
+    redis: &mut redis::aio::MultiplexedConnection,
+    job_id: Uuid,
+) {
+    let cache_key: Option<String> = match redis.get_del(format!("job:{job_id}:cache_key")).await {
+        Ok(key) => key,
+        Err(_) => return,
+    };
+    let Some(cache_key) = cache_key else { return };
+    let raw: Option<String> = redis.get(format!("job:{job_id}:result")).await.ok().flatten();
+    let Some(raw) = raw else { return };
+    let Ok(result) = serde_json::from_str::<shared::models::VisionResponse>(&raw) else {
+        return;
+    };
+    let entry = CachedResult {
+        model_version: result.model_version.clone(),
+        result,
+    };
+    // Track the newest model version seen; reads use it to drop entries
+    // from before an upgrade.
+    let _: Result<(), _> = redis
+        .set("vision_model_version", &entry.model_version)
+        .await;
+    if let Ok(json) = serde_json::to_string(&entry) {
+        let _: Result<(), _> = redis
+            .set_ex(cache_key, json, state.config.vision_cache.ttl.as_secs())
+            .await;
+    }
+}
+
+/// True when a published status payload (`{"status": "completed", ...}`)
+/// describes a terminal job, meaning the stream can close.
+fn is_terminal_payload(payload: &str) -> bool {
+    serde_json::from_str::<serde_json::Value>(payload)
+        .ok()
+        .and_then(|v| serde_json::from_value::<JobStatus>(v["status"].clone()).ok())
+        .is_some_and(|status| status.is_terminal())
+}
+
+/// Adapt a stream of published status payloads into SSE events, ending the
+/// stream after forwarding the terminal update. Split out from the handler
+/// so it can be driven by a plain channel in tests.
+fn status_event_stream(
+    payloads: impl Stream<Item = String>,
+) -> impl Stream<Item = Result<Event, Infallible>> {
+    futures_util::stream::unfold(
+        (Box::pin(payloads), false),
+        |(mut payloads, done)| async move {
+            if done {
+                return None;
+            }
+            let payload = payloads.next().await?;
+            let terminal = is_terminal_payload(&payload);
+            let event = Event::default().event("status_update").data(payload);
+            Some((Ok(event), (payloads, terminal)))
+        },
+    )
+}
+
+/// `GET /api/v1/vision/jobs/:job_id/stream` — push status updates over SSE
+/// instead of making clients poll. The worker publishes to the
+/// `job_status:<job_id>` Redis channel; the stream closes once the job
+/// reaches a terminal state. Keep-alives every 15s stop proxies from
+/// timing the connection out while the job sits in the queue.
+#[utoipa::path(
+    get,
+    path = "/api/v1/vision/jobs/{job_id}/stream",
+    operation_id = "streamJobStatus",
+    tag = "vision",
+    params(("job_id" = String, Path)),
+    responses(
+        (status = 200, description = "SSE stream of status updates, closing after the terminal one", content_type = "text/event-stream"),
+        (status = 404, body = crate::docs::ErrorBody),
+        (status = 401, body = crate::docs::ErrorBody)
+    ),
+    security(("bearer_jwt" = []))
+)]
+pub async fn stream_job_status(
+    State(state): State<AppState>,
+    ctx: crate::middleware::request_context::RequestContext,
+    Path(job_id): Path<Uuid>,
+) -> AppResult<Sse<impl Stream<Item = Result<Event, Infallible>>>> {
+    let user = ctx.require_user()?.clone();
+    require_job_owner(&state, job_id, user.user_id).await?;
+    let mut pubsub = state
+        .redis_client
+        .get_async_pubsub()
+        .await
+        .map_err(|e| AppError::ServiceUnavailable(format!("redis: {e}")))?;
+    pubsub
+        .subscribe(format!("job_status:{job_id}"))
+        .await
+        .map_err(|e| AppError::ServiceUnavailable(format!("redis: {e}")))?;
+
+    let payloads = pubsub
+        .into_on_message()
+        .filter_map(|msg| async move { msg.get_payload::<String>().ok() });
+
+    Ok(Sse::new(status_event_stream(payloads))
+        .keep_alive(KeepAlive::new().interval(Duration::from_secs(15))))
+}
+
+/// Application close codes (WebSocket reserves 1000–2999).
+const CLOSE_NORMAL: u16 = 1000;
+const CLOSE_JOB_NOT_FOUND: u16 = 4404;
+
+/// `GET /api/v1/vision/jobs/:job_id/ws` — the WebSocket sibling of the SSE
+/// stream, for clients already holding a socket (e.g. the chat view).
+/// Authenticates via a `token` query parameter like `/api/v1/chat/ws`.
+#[utoipa::path(
+    get,
+    path = "/api/v1/vision/jobs/{job_id}/ws",
+    operation_id = "jobStatusWebSocket",
+    tag = "vision",
+    params(
+        ("job_id" = String, Path),
+        ("token" = String, Query, description = "JWT, since browsers cannot set WS headers")
+    ),
+    responses((status = 101, description = "switching protocols to the status socket"))
+)]
+pub async fn job_status_ws(
+    State(state): State<AppState>,
+    Path(job_id): Path<Uuid>,
+    Query(params): Query<crate::handlers::chat::WsAuthParams>,
+    ws: WebSocketUpgrade,
+) -> AppResult<Response> {
+    let user =
+        crate::middleware::auth::validate_token(&params.token, state.config.jwt_secret.as_bytes())?;
+    require_job_owner(&state, job_id, user.user_id).await?;
+    Ok(ws.on_upgrade(move |socket| handle_job_socket(socket, state, job_id)))
+}
+
+async fn close_with(mut socket: WebSocket, code: u16, reason: &'static str) {
+    let _ = socket
+        .send(WsMessage::Close(Some(CloseFrame {
+            code,
+            reason: reason.into(),
+        })))
+        .await;
+}
+
+async fn handle_job_socket(mut socket: WebSocket, state: AppState, job_id: Uuid) {
+    // Send the current status first so the client doesn't wait for the next
+    // transition; unknown jobs get a distinct close code instead of frames.
+    let current: Option<String> = match state.get_redis().await {
+        Ok(mut redis) => redis.get(format!("job:{job_id}:status")).await.ok().flatten(),
+        Err(_) => None,
+    };
+    let Some(current) = current else {
+        close_with(socket, CLOSE_JOB_NOT_FOUND, "job not found").await;
+        return;
+    };
+    let frame = serde_json::json!({ "status": current }).to_string();
+    let terminal = is_terminal_payload(&frame);
+    if socket.send(WsMessage::Text(frame)).await.is_err() {
+        return;
+    }
+    if terminal {
+        close_with(socket, CLOSE_NORMAL, "job finished").await;
+        return;
+    }
+
+    let Ok(mut pubsub) = state.redis_client.get_async_pubsub().await else {
+        close_with(socket, CLOSE_NORMAL, "status feed unavailable").await;
+        return;
+    };
+    if pubsub.subscribe(format!("job_status:{job_id}")).await.is_err() {
+        close_with(socket, CLOSE_NORMAL, "status feed unavailable").await;
+        return;
+    }
+    let mut updates = pubsub.into_on_message();
+    // Proxies kill silent connections; pings keep the socket warm while the
+    // job sits in the queue.
+    let mut heartbeat = tokio::time::interval(Duration::from_secs(30));
+    heartbeat.tick().await; // first tick fires immediately
+
+    loop {
+        tokio::select! {
+            update = updates.next() => {
+                let Some(update) = update else { break };
+                let Ok(payload) = update.get_payload::<String>() else { continue };
+                let terminal = is_terminal_payload(&payload);
+                if socket.send(WsMessage::Text(payload)).await.is_err() {
+                    return; // client went away
+                }
+                if terminal {
+                    close_with(socket, CLOSE_NORMAL, "job finished").await;
+                    return;
+                }
+            }
+            _ = heartbeat.tick() => {
+                if socket.send(WsMessage::Ping(Vec::new())).await.is_err() {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow, utoipa::ToSchema)]
+pub struct JobSummary {
+    pub id: Uuid,
+    pub crop_type: String,
+    pub status: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ListJobsParams {
+    /// Keep only jobs carrying this tag of the caller's.
+    pub tag: Option<String>,
+    #[serde(flatten)]
+    pub page: PageParams,
+}
+
+/// `GET /api/v1/vision/jobs` — paginated job history, filterable by
+/// `crop_type`, `status`, and `tag`, sortable by `created_at` /
+/// `crop_type`.
+#[utoipa::path(
+    get,
+    path = "/api/v1/vision/jobs",
+    operation_id = "listVisionJobs",
+    tag = "vision",
+    params(ListJobsParams),
+    responses(
+        (status = 200, body = ApiResponse<Page<JobSummary>>),
+        (status = 400, description = "unknown filter or sort value", body = crate::docs::ErrorBody),
+        (status = 401, body = crate::docs::ErrorBody)
+    ),
+    security(("bearer_jwt" = []))
+)]
+pub async fn list_jobs(
+    State(state): State<AppState>,
+    ctx: crate::middleware::request_context::RequestContext,
+    Query(params): Query<ListJobsParams>,
+) -> AppResult<Json<ApiResponse<Page<JobSummary>>>> {
+    let user = ctx.require_user()?.clone();
+    let page = PageRequest::from_params(params.page, &JOBS_ALLOWLIST)?;
+
+    let mut qb = QueryBuilder::new(
+        "SELECT id, crop_type, status, created_at FROM vision_jobs WHERE deleted_at IS NULL",
+    );
+    // Scope to the caller: cross-user listings are the admin-only
+    // `list_all_jobs`. NULL owners are legacy pre-auth rows.
+    qb.push(" AND (user_id = ");
+    qb.push_bind(user.user_id);
+    qb.push(" OR user_id IS NULL)");
+    if let Some(tag) = params.tag {
+        qb.push(
+            " AND EXISTS (SELECT 1 FROM taggings tg JOIN tags t ON t.id = tg.tag_id \
+             WHERE tg.target_type = 'job' AND tg.target_id = vision_jobs.id \
+             AND t.user_id = ",
+        );
+        qb.push_bind(user.user_id);
+        qb.push(" AND t.name = ").push_bind(tag).push(")");
+    }
+    page.apply(&mut qb, &JOBS_ALLOWLIST, true);
+    let rows: Vec<JobSummary> = qb.build_query_as().fetch_all(&state.db).await?;
+
+    let limit = page.limit;
+    Ok(Json(ApiResponse::ok(Page::from_rows(rows, limit, |job| {
+        Cursor {
+            created_at: job.created_at,
+            id: job.id,
+        }
+    }))))
+}
+
+/// Upper bound on job ids per bulk request, mirrored by the history view's
+/// selection cap.
+pub const MAX_BULK_JOBS: usize = 100;
+
+fn validate_bulk_ids(ids: &[Uuid]) -> AppResult<()> {
+    if ids.is_empty() {
+        return Err(AppError::Validation("job_ids must not be empty".into()));
+    }
+    if ids.len() > MAX_BULK_JOBS {
+        return Err(AppError::Validation(format!(
+            "at most {MAX_BULK_JOBS} jobs per bulk request"
+        )));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+pub struct BulkTagRequest {
+    pub job_ids: Vec<Uuid>,
+    pub tag_id: Uuid,
+}
+
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+pub struct BulkJobsRequest {
+    pub job_ids: Vec<Uuid>,
+}
+
+/// Chunk size for bulk mutations: each chunk runs in its own transaction
+/// so one bad row (or a dropped connection mid-batch) can't poison the
+/// whole request.
+const BULK_CHUNK: usize = 50;
+
+/// Per-id result of a bulk mutation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BulkJobOutcome {
+    Ok,
+    NotFound,
+    Forbidden,
+    Error,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct BulkJobResult {
+    pub job_id: Uuid,
+    pub outcome: BulkJobOutcome,
+}
+
+/// Split requested ids into per-id outcomes against the caller's ownership.
+/// `rows` are `(id, user_id)` pairs as stored; jobs queued before auth was
+/// wired carry a NULL user and stay mutable by anyone, since refusing them
+/// would strand every pre-auth job forever. Returns the outcome list in
+/// request order plus the ids the caller may actually mutate.
+fn classify_jobs(
+    requested: &[Uuid],
+    rows: &[(Uuid, Option<Uuid>)],
+    user_id: Uuid,
+) -> (Vec<BulkJobResult>, Vec<Uuid>) {
+    let mut results = Vec::with_capacity(requested.len());
+    let mut actionable = Vec::new();
+    for &job_id in requested {
+        let outcome = match rows.iter().find(|(id, _)| *id == job_id) {
+            None => BulkJobOutcome::NotFound,
+            Some((_, Some(owner))) if *owner != user_id => BulkJobOutcome::Forbidden,
+            Some(_) => {
+                actionable.push(job_id);
+                BulkJobOutcome::Ok
+            }
+        };
+        results.push(BulkJobResult { job_id, outcome });
+    }
+    (results, actionable)
+}
+
+/// Downgrade `Ok` outcomes to `Error` for ids whose chunk failed.
+fn mark_failed(results: &mut [BulkJobResult], failed: &[Uuid]) {
+    for result in results {
+        if failed.contains(&result.job_id) {
+            result.outcome = BulkJobOutcome::Error;
+        }
+    }
+}
+
+async fn fetch_job_owners(
+    state: &AppState,
+    ids: &[Uuid],
+) -> AppResult<Vec<(Uuid, Option<Uuid>)>> {
+    Ok(sqlx::query_as(
+        "SELECT id, user_id FROM vision_jobs WHERE id = ANY($1) AND deleted_at IS NULL",
+    )
+    .bind(ids)
+    .fetch_all(&state.db)
+    .await?)
+}
+
+/// `POST /api/v1/vision/jobs/batch/tag` — attach one of the caller's tags
+/// to up to [`MAX_BULK_JOBS`] jobs, reporting a per-id outcome instead of
+/// failing the batch. Already-tagged jobs still report `ok`.
+#[utoipa::path(
+    post,
+    path = "/api/v1/vision/jobs/batch/tag",
+    operation_id = "batchTagJobs",
+    tag = "vision",
+    request_body = BulkTagRequest,
+    responses(
+        (status = 200, body = ApiResponse<Vec<BulkJobResult>>),
+        (status = 400, description = "empty or oversized id list", body = crate::docs::ErrorBody),
+        (status = 404, description = "tag does not exist or is not the caller's", body = crate::docs::ErrorBody)
+    ),
+    security(("bearer_jwt" = []))
+)]
+pub async fn batch_tag_jobs(
+    State(state): State<AppState>,
+    ctx: crate::middleware::request_context::RequestContext,
+    Json(request): Json<BulkTagRequest>,
+) -> AppResult<Json<ApiResponse<Vec<BulkJobResult>>>> {
+    let user = ctx.require_user()?.clone();
+    validate_bulk_ids(&request.job_ids)?;
+    let tag_id = super::tags::owned_tag_id(&state, &user, request.tag_id).await?;
+    let rows = fetch_job_owners(&state, &request.job_ids).await?;
+    let (mut results, actionable) = classify_jobs(&request.job_ids, &rows, user.user_id);
+
+    for chunk in actionable.chunks(BULK_CHUNK) {
+        let outcome = sqlx::query(
+            "INSERT INTO taggings (tag_id, target_type, target_id) \
+             SELECT $1, 'job', unnest($2::uuid[]) ON CONFLICT DO NOTHING",
+        )
+        .bind(tag_id)
+        .bind(chunk)
+        .execute(&state.db)
+        .await;
+        if let Err(e) = outcome {
+            tracing::warn!(error = %e, "bulk tag chunk failed");
+            mark_failed(&mut results, chunk);
+        }
+    }
+    Ok(Json(ApiResponse::ok(results)))
+}
+
+/// Soft-delete or restore, per chunk, inside a transaction together with
+/// the audit rows so an audited action and its audit entry land atomically.
+async fn bulk_set_deleted(
+    state: &AppState,
+    user_id: Uuid,
+    results: &mut [BulkJobResult],
+    actionable: &[Uuid],
+    delete: bool,
+) -> AppResult<()> {
+    let (update_sql, action) = if delete {
+        (
+            "UPDATE vision_jobs SET deleted_at = now() WHERE id = ANY($1) AND deleted_at IS NULL",
+            "soft_delete",
+        )
+    } else {
+        (
+            "UPDATE vision_jobs SET deleted_at = NULL WHERE id = ANY($1)",
+            "restore",
+        )
+    };
+    for chunk in actionable.chunks(BULK_CHUNK) {
+        let attempt = async {
+            let mut tx = state.db.begin().await?;
+            sqlx::query(update_sql).bind(chunk).execute(&mut *tx).await?;
+            sqlx::query(
+                "INSERT INTO job_audit (id, job_id, user_id, action) \
+                 SELECT gen_random_uuid(), unnest($1::uuid[]), $2, $3",
+            )
+            .bind(chunk)
+            .bind(user_id)
+            .bind(action)
+            .execute(&mut *tx)
+            .await?;
+            tx.commit().await
+        };
+        if let Err(e) = attempt.await {
+            tracing::warn!(error = %e, action, "bulk job chunk failed");
+            mark_failed(results, chunk);
+        }
+    }
+    Ok(())
+}
+
+/// `POST /api/v1/vision/jobs/batch/delete` — per-id soft delete with audit
+/// entries; the client's undo toast reverses it via `batch/restore`.
+#[utoipa::path(
+    post,
+    path = "/api/v1/vision/jobs/batch/delete",
+    operation_id = "batchDeleteJobs",
+    tag = "vision",
+    request_body = BulkJobsRequest,
+    responses(
+        (status = 200, body = ApiResponse<Vec<BulkJobResult>>),
+        (status = 400, body = crate::docs::ErrorBody),
+        (status = 401, body = crate::docs::ErrorBody)
+    ),
+    security(("bearer_jwt" = []))
+)]
+pub async fn batch_delete_jobs(
+    State(state): State<AppState>,
+    ctx: crate::middleware::request_context::RequestContext,
+    Json(request): Json<BulkJobsRequest>,
+) -> AppResult<Json<ApiResponse<Vec<BulkJobResult>>>> {
+    let user = ctx.require_user()?.clone();
+    validate_bulk_ids(&request.job_ids)?;
+    let rows = fetch_job_owners(&state, &request.job_ids).await?;
+    let (mut results, actionable) = classify_jobs(&request.job_ids, &rows, user.user_id);
+    bulk_set_deleted(&state, user.user_id, &mut results, &actionable, true).await?;
+    Ok(Json(ApiResponse::ok(results)))
+}
+
+/// `POST /api/v1/vision/jobs/batch/restore` — undo a recent soft delete.
+#[utoipa::path(
+    post,
+    path = "/api/v1/vision/jobs/batch/restore",
+    operation_id = "batchRestoreJobs",
+    tag = "vision",
+    request_body = BulkJobsRequest,
+    responses(
+        (status = 200, body = ApiResponse<Vec<BulkJobResult>>),
+        (status = 400, body = crate::docs::ErrorBody),
+        (status = 401, body = crate::docs::ErrorBody)
+    ),
+    security(("bearer_jwt" = []))
+)]
+pub async fn batch_restore_jobs(
+    State(state): State<AppState>,
+    ctx: crate::middleware::request_context::RequestContext,
+    Json(request): Json<BulkJobsRequest>,
+) -> AppResult<Json<ApiResponse<Vec<BulkJobResult>>>> {
+    let user = ctx.require_user()?.clone();
+    validate_bulk_ids(&request.job_ids)?;
+    // Soft-deleted rows are invisible to `fetch_job_owners`; restore has to
+    // look at exactly the deleted ones.
+    let rows: Vec<(Uuid, Option<Uuid>)> = sqlx::query_as(
+        "SELECT id, user_id FROM vision_jobs WHERE id = ANY($1) AND deleted_at IS NOT NULL",
+    )
+    .bind(&request.job_ids)
+    .fetch_all(&state.db)
+    .await?;
+    let (mut results, actionable) = classify_jobs(&request.job_ids, &rows, user.user_id);
+    bulk_set_deleted(&state, user.user_id, &mut results, &actionable, false).await?;
+    Ok(Json(ApiResponse::ok(results)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn image_hashes_are_stable_and_scoped_by_crop() {
+        let a = hash_image(b"same bytes");
+        assert_eq!(a, hash_image(b"same bytes"));
+        assert_ne!(a, hash_image(b"other bytes"));
+        // The same photo of a different declared crop must not share a slot.
+        assert_ne!(
+            image_cache_key(CropType::Rice, &a),
+            image_cache_key(CropType::Durian, &a)
+        );
+    }
+
+    #[test]
+    fn urgent_flag_is_capped_below_the_officer_tier() {
+        let user = |roles: &[&str]| crate::AuthUser {
+            user_id: Uuid::new_v4(),
+            email: "a@example.com".into(),
+            roles: roles.iter().map(|r| r.to_string()).collect(),
+        };
+        assert_eq!(job_priority(false, None), JobPriority::Normal);
+        assert_eq!(job_priority(true, None), JobPriority::High);
+        assert_eq!(
+            job_priority(true, Some(&user(&["farmer"]))),
+            JobPriority::High
+        );
+        assert_eq!(
+            job_priority(true, Some(&user(&["officer"]))),
+            JobPriority::Urgent
+        );
+        assert_eq!(
+            job_priority(false, Some(&user(&["officer"]))),
+            JobPriority::Normal
+        );
+    }
+
+    #[test]
+    fn cached_outcomes_serialize_with_the_cached_flag() {
+        let outcome = AnalyzeOutcome::Queued(JobEnvelope {
+            job_id: Uuid::nil(),
+            status: JobStatus::Queued,
+        });
+        let json = serde_json::to_value(&outcome).unwrap();
+        assert!(json.get("cached").is_none());
+        assert_eq!(json["status"], "queued");
+    }
+
+    #[test]
+    fn bulk_outcomes_cover_owned_foreign_and_missing_ids() {
+        let me = Uuid::new_v4();
+        let someone_else = Uuid::new_v4();
+        let mine = Uuid::new_v4();
+        let foreign = Uuid::new_v4();
+        let missing = Uuid::new_v4();
+        let legacy = Uuid::new_v4();
+
+        let rows = vec![
+            (mine, Some(me)),
+            (foreign, Some(someone_else)),
+            (legacy, None),
+        ];
+        let requested = vec![mine, foreign, missing, legacy];
+        let (results, actionable) = classify_jobs(&requested, &rows, me);
+
+        let outcomes: Vec<BulkJobOutcome> = results.iter().map(|r| r.outcome).collect();
+        assert_eq!(
+            outcomes,
+            vec![
+                BulkJobOutcome::Ok,
+                BulkJobOutcome::Forbidden,
+                BulkJobOutcome::NotFound,
+                BulkJobOutcome::Ok,
+            ]
+        );
+        // Foreign jobs never reach the mutation list.
+        assert_eq!(actionable, vec![mine, legacy]);
+    }
+
+    #[test]
+    fn failed_chunks_downgrade_ok_to_error() {
+        let a = Uuid::new_v4();
+        let b = Uuid::new_v4();
+        let mut results = vec![
+            BulkJobResult { job_id: a, outcome: BulkJobOutcome::Ok },
+            BulkJobResult { job_id: b, outcome: BulkJobOutcome::Ok },
+        ];
+        mark_failed(&mut results, &[b]);
+        assert_eq!(results[0].outcome, BulkJobOutcome::Ok);
+        assert_eq!(results[1].outcome, BulkJobOutcome::Error);
+    }
+
+    #[test]
+    fn bulk_requests_are_bounded() {
+        assert!(validate_bulk_ids(&[]).is_err());
+        assert!(validate_bulk_ids(&[Uuid::new_v4()]).is_ok());
+        let too_many: Vec<Uuid> = (0..101).map(|_| Uuid::new_v4()).collect();
+        assert!(validate_bulk_ids(&too_many).is_err());
+        assert!(validate_bulk_ids(&too_many[..100]).is_ok());
+    }
+
+    #[test]
+    fn crop_types_parse_from_form_fields() {
+        assert_eq!(parse_crop_type("rice").unwrap(), CropType::Rice);
+        assert_eq!(parse_crop_type("durian").unwrap(), CropType::Durian);
+        assert!(parse_crop_type("wheat").is_err());
+    }
+
+    #[test]
+    fn terminal_payloads_are_recognised() {
+        assert!(is_terminal_payload(r#"{"status": "completed"}"#));
+        assert!(is_terminal_payload(r#"{"status": "failed"}"#));
+        assert!(!is_terminal_payload(r#"{"status": "processing"}"#));
+        assert!(!is_terminal_payload("not json"));
+    }
+
+    #[tokio::test]
+    async fn stream_forwards_updates_then_closes_after_terminal() {
+        // A mock publisher: three progress updates, the terminal one, and a
+        // late publish that must never reach the client.
+        let payloads = futures_util::stream::iter(
+            [
+                r#"{"status": "queued"}"#,
+                r#"{"status": "processing"}"#,
+                r#"{"status": "processing"}"#,
+                r#"{"status": "completed"}"#,
+                r#"{"status": "completed"}"#,
+            ]
+            .map(String::from),
+        );
+        let events: Vec<_> = status_event_stream(payloads).collect().await;
+        assert_eq!(events.len(), 4);
+    }
+
+    #[test]
+    fn a_batch_of_three_images_gets_three_distinct_job_ids() {
+        let stored: Vec<_> = (0..3)
+            .map(|i| crate::services::file_storage::StoredFile {
+                id: Uuid::new_v4(),
+                path: std::path::PathBuf::from(format!("/tmp/{i}.jpg")),
+                size_bytes: 1,
+            })
+            .collect();
+        let items: Vec<_> = (0..3)
+            .map(|_| BatchItem {
+                image_data: "aGk=".into(),
+                crop_type: CropType::Rice,
+            })
+            .collect();
+        let jobs = batch_jobs(&stored, &items, Some("what is this"), Utc::now());
+        assert_eq!(jobs.len(), 3);
+        let mut ids: Vec<_> = jobs.iter().map(|j| j.job_id).collect();
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), 3);
+    }
+
+    #[test]
+    fn batch_aggregation_counts_terminal_states() {
+        let response = aggregate_batch(vec![
+            JobStatus::Completed,
+            JobStatus::Completed,
+            JobStatus::Failed,
+            JobStatus::Processing,
+        ]);
+        assert_eq!(response.total, 4);
+        assert_eq!(response.completed, 2);
+        assert_eq!(response.failed, 1);
+    }
+
+    #[tokio::test]
+    async fn stream_ends_when_publisher_goes_away() {
+        let payloads = futures_util::stream::iter([r#"{"status": "queued"}"#.to_string()]);
+        let events: Vec<_> = status_event_stream(payloads).collect().await;
+        assert_eq!(events.len(), 1);
+    }
+}
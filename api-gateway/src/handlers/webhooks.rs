@@ -0,0 +1,145 @@
+//! Webhook subscription management.
+//!
+//! `POST /api/v1/webhooks` registers a callback URL for terminal-job
+//! events; `DELETE /api/v1/webhooks/:id` removes it. Delivery itself lives
+//! in `services::webhooks`. The shared secret is write-only: it is stored
+//! for signing and never echoed back in any response.
+
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use shared::types::ApiResponse;
+use uuid::Uuid;
+
+use crate::{
+    errors::{AppError, AppResult},
+    middleware::request_context::RequestContext,
+    services::webhooks::{EVENT_JOB_COMPLETED, EVENT_JOB_FAILED},
+    state::AppState,
+};
+
+/// Minimum secret length; anything shorter makes the HMAC guessable.
+const MIN_SECRET_LEN: usize = 16;
+
+#[derive(Debug, Deserialize)]
+pub struct RegisterWebhookRequest {
+    pub url: String,
+    pub events: Vec<String>,
+    pub secret: String,
+}
+
+/// The subscription as returned to the client — everything except the
+/// secret.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct WebhookResponse {
+    pub id: Uuid,
+    pub url: String,
+    pub events: Vec<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Validate a registration request: an absolute http(s) URL, at least one
+/// known event, and a secret long enough to sign with.
+pub fn validate_registration(request: &RegisterWebhookRequest) -> AppResult<()> {
+    if !request.url.starts_with("https://") && !request.url.starts_with("http://") {
+        return Err(AppError::Validation(
+            "url must be an absolute http(s) URL".into(),
+        ));
+    }
+    if request.events.is_empty() {
+        return Err(AppError::Validation("subscribe to at least one event".into()));
+    }
+    for event in &request.events {
+        if event != EVENT_JOB_COMPLETED && event != EVENT_JOB_FAILED {
+            return Err(AppError::Validation(format!(
+                "unknown event '{event}', expected '{EVENT_JOB_COMPLETED}' or '{EVENT_JOB_FAILED}'"
+            )));
+        }
+    }
+    if request.secret.len() < MIN_SECRET_LEN {
+        return Err(AppError::Validation(format!(
+            "secret must be at least {MIN_SECRET_LEN} characters"
+        )));
+    }
+    Ok(())
+}
+
+/// `POST /api/v1/webhooks`
+pub async fn register_webhook(
+    State(state): State<AppState>,
+    ctx: RequestContext,
+    Json(request): Json<RegisterWebhookRequest>,
+) -> AppResult<Json<ApiResponse<WebhookResponse>>> {
+    let user = ctx.require_user()?;
+    validate_registration(&request)?;
+
+    let webhook: WebhookResponse = sqlx::query_as(
+        "INSERT INTO webhooks (id, user_id, url, events, secret) \
+         VALUES ($1, $2, $3, $4, $5) RETURNING id, url, events, created_at",
+    )
+    .bind(Uuid::new_v4())
+    .bind(user.user_id)
+    .bind(&request.url)
+    .bind(&request.events)
+    .bind(&request.secret)
+    .fetch_one(&state.db)
+    .await?;
+    Ok(Json(ApiResponse::ok(webhook)))
+}
+
+/// `DELETE /api/v1/webhooks/:webhook_id` — owner-scoped, like job deletion:
+/// someone else's subscription id is indistinguishable from a missing one.
+pub async fn delete_webhook(
+    State(state): State<AppState>,
+    ctx: RequestContext,
+    Path(webhook_id): Path<Uuid>,
+) -> AppResult<Json<ApiResponse<()>>> {
+    let user = ctx.require_user()?;
+    let result = sqlx::query("DELETE FROM webhooks WHERE id = $1 AND user_id = $2")
+        .bind(webhook_id)
+        .bind(user.user_id)
+        .execute(&state.db)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound(format!("webhook {webhook_id}")));
+    }
+    Ok(Json(ApiResponse::ok(())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(url: &str, events: &[&str], secret: &str) -> RegisterWebhookRequest {
+        RegisterWebhookRequest {
+            url: url.into(),
+            events: events.iter().map(|e| e.to_string()).collect(),
+            secret: secret.into(),
+        }
+    }
+
+    #[test]
+    fn registrations_are_validated() {
+        let ok = request(
+            "https://example.com/hook",
+            &["job.completed", "job.failed"],
+            "0123456789abcdef",
+        );
+        assert!(validate_registration(&ok).is_ok());
+
+        let bad_url = request("ftp://example.com", &["job.completed"], "0123456789abcdef");
+        assert!(validate_registration(&bad_url).is_err());
+
+        let no_events = request("https://example.com", &[], "0123456789abcdef");
+        assert!(validate_registration(&no_events).is_err());
+
+        let bad_event = request("https://example.com", &["job.started"], "0123456789abcdef");
+        assert!(validate_registration(&bad_event).is_err());
+
+        let short_secret = request("https://example.com", &["job.completed"], "short");
+        assert!(validate_registration(&short_secret).is_err());
+    }
+}
@@ -0,0 +1,33 @@
+//! API gateway: HTTP surface in front of the vision and LLM services.
+
+pub mod app;
+pub mod config;
+pub mod docs;
+pub mod errors;
+pub mod handlers;
+pub mod logging;
+pub mod metrics;
+pub mod middleware;
+pub mod services;
+pub mod shared;
+pub mod shutdown;
+pub mod state;
+pub mod telemetry;
+pub mod utils;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Authenticated caller, populated from the JWT by the auth middleware.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuthUser {
+    pub user_id: Uuid,
+    pub email: String,
+    pub roles: Vec<String>,
+}
+
+impl AuthUser {
+    pub fn has_role(&self, role: &str) -> bool {
+        self.roles.iter().any(|r| r == role)
+    }
+}
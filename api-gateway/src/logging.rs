@@ -0,0 +1,417 @@
+//! Log pipeline: subscriber setup plus in-process broadcasting for the
+//! admin log-streaming endpoint.
+//!
+//! [`init`] assembles the subscriber from [`LoggingConfig`]: text or JSON
+//! lines, stdout or a daily-rolling file, and per-module level overrides on
+//! top of `RUST_LOG`. JSON output goes through [`JsonLayer`] below rather
+//! than `fmt`'s JSON mode so every line carries the `service` name and the
+//! request's correlation id, and redaction matches the admin stream.
+//!
+//! A [`BroadcastLayer`] hangs off the tracing subscriber and copies every
+//! event into a bounded `tokio::sync::broadcast` channel as structured JSON.
+//! Broadcast semantics are exactly what we want here: senders never block,
+//! and a subscriber that falls behind skips the events it missed instead of
+//! back-pressuring the application.
+
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+use tracing::{field::Visit, Level, Subscriber};
+use tracing_subscriber::layer::Context;
+
+use crate::config::{LogFormat, LogOutput, LoggingConfig};
+
+/// Service name stamped on every structured line so aggregated streams can
+/// tell the gateway apart from the worker and the model services.
+pub const SERVICE_NAME: &str = "api-gateway";
+
+/// How many events the broadcast buffer holds before lagging subscribers
+/// start skipping.
+pub const LOG_BUFFER_CAPACITY: usize = 1024;
+
+/// Field names whose values must never leave the process in logs.
+const SENSITIVE_FIELDS: &[&str] = &["token", "password", "authorization", "api_key", "secret"];
+
+pub fn redact_field(name: &str, value: &str) -> String {
+    let lowered = name.to_ascii_lowercase();
+    if SENSITIVE_FIELDS.iter().any(|s| lowered.contains(s)) {
+        "[redacted]".to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+/// One structured log event as streamed to admins.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogEvent {
+    pub timestamp: DateTime<Utc>,
+    pub level: String,
+    /// Module path the event came from (`tracing` target).
+    pub target: String,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub fields: BTreeMap<String, String>,
+}
+
+/// Subscriber-side filters, bound from the stream endpoint's query params.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct LogFilter {
+    /// Minimum level, e.g. `warn` shows warn and error.
+    pub level: Option<String>,
+    /// Keep only events whose target starts with this module path.
+    pub module: Option<String>,
+}
+
+impl LogFilter {
+    pub fn matches(&self, event: &LogEvent) -> bool {
+        if let Some(min) = self.level.as_deref().and_then(|l| l.parse::<Level>().ok()) {
+            match event.level.parse::<Level>() {
+                Ok(level) if level <= min => {}
+                _ => return false,
+            }
+        }
+        if let Some(module) = &self.module {
+            if !event.target.starts_with(module.as_str()) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Shared handle: the tracing layer publishes, SSE subscribers receive.
+#[derive(Clone)]
+pub struct LogBroadcaster {
+    sender: broadcast::Sender<LogEvent>,
+}
+
+impl Default for LogBroadcaster {
+    fn default() -> Self {
+        Self::new(LOG_BUFFER_CAPACITY)
+    }
+}
+
+impl LogBroadcaster {
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity);
+        Self { sender }
+    }
+
+    /// Never blocks; without subscribers the event is simply dropped.
+    pub fn publish(&self, event: LogEvent) {
+        let _ = self.sender.send(event);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<LogEvent> {
+        self.sender.subscribe()
+    }
+
+    pub fn layer(&self) -> BroadcastLayer {
+        BroadcastLayer {
+            broadcaster: self.clone(),
+        }
+    }
+}
+
+/// Tracing layer feeding the broadcaster.
+pub struct BroadcastLayer {
+    broadcaster: LogBroadcaster,
+}
+
+impl<S: Subscriber> tracing_subscriber::Layer<S> for BroadcastLayer {
+    fn on_event(&self, event: &tracing::Event<'_>, _ctx: Context<'_, S>) {
+        let mut visitor = FieldVisitor::default();
+        event.record(&mut visitor);
+        self.broadcaster.publish(LogEvent {
+            timestamp: Utc::now(),
+            level: event.metadata().level().to_string(),
+            target: event.metadata().target().to_string(),
+            message: visitor.message,
+            fields: visitor.fields,
+        });
+    }
+}
+
+/// Serialize one event as a single JSON log line. Extra fields are
+/// flattened to the top level so aggregators can index them directly.
+pub fn json_line(event: &LogEvent, correlation_id: Option<&str>) -> String {
+    #[derive(Serialize)]
+    struct Line<'a> {
+        service: &'static str,
+        timestamp: &'a DateTime<Utc>,
+        level: &'a str,
+        target: &'a str,
+        message: &'a str,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        correlation_id: Option<&'a str>,
+        #[serde(flatten)]
+        fields: &'a BTreeMap<String, String>,
+    }
+    serde_json::to_string(&Line {
+        service: SERVICE_NAME,
+        timestamp: &event.timestamp,
+        level: &event.level,
+        target: &event.target,
+        message: &event.message,
+        correlation_id,
+        fields: &event.fields,
+    })
+    .unwrap_or_default()
+}
+
+/// JSON formatter layer. The correlation id comes from the middleware's
+/// task-local, so every line emitted while handling a request carries the
+/// same id as the response header; background tasks simply omit it.
+pub struct JsonLayer<W> {
+    make_writer: W,
+}
+
+impl<W> JsonLayer<W> {
+    pub fn new(make_writer: W) -> Self {
+        Self { make_writer }
+    }
+}
+
+impl<S, W> tracing_subscriber::Layer<S> for JsonLayer<W>
+where
+    S: Subscriber,
+    W: for<'w> tracing_subscriber::fmt::MakeWriter<'w> + 'static,
+{
+    fn on_event(&self, event: &tracing::Event<'_>, _ctx: Context<'_, S>) {
+        let mut visitor = FieldVisitor::default();
+        event.record(&mut visitor);
+        let line = json_line(
+            &LogEvent {
+                timestamp: Utc::now(),
+                level: event.metadata().level().to_string(),
+                target: event.metadata().target().to_string(),
+                message: visitor.message,
+                fields: visitor.fields,
+            },
+            crate::middleware::correlation::current_correlation_id().as_deref(),
+        );
+        use std::io::Write;
+        let _ = writeln!(self.make_writer.make_writer(), "{line}");
+    }
+}
+
+/// `RUST_LOG` as the base, with the config's per-module overrides stacked
+/// on top. Unparseable directives are skipped with a note instead of
+/// failing the boot over a typo'd module name.
+pub fn env_filter(filters: &BTreeMap<String, String>) -> tracing_subscriber::EnvFilter {
+    let mut filter = tracing_subscriber::EnvFilter::from_default_env();
+    for (module, level) in filters {
+        match format!("{module}={level}").parse() {
+            Ok(directive) => filter = filter.add_directive(directive),
+            Err(e) => eprintln!("ignoring log filter {module}={level}: {e}"),
+        }
+    }
+    filter
+}
+
+/// Install the global subscriber. The returned guard owns the background
+/// writer thread; dropping it flushes and stops logging, so `main` must
+/// hold it for the life of the process. When `telemetry::init` produced a
+/// tracer, its layer rides the same subscriber so spans are exported.
+pub fn init(
+    config: &LoggingConfig,
+    broadcaster: &LogBroadcaster,
+    tracer: Option<opentelemetry_sdk::trace::Tracer>,
+) -> tracing_appender::non_blocking::WorkerGuard {
+    use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+
+    let (writer, guard) = match config.output {
+        LogOutput::Stdout => tracing_appender::non_blocking(std::io::stdout()),
+        LogOutput::File => tracing_appender::non_blocking(tracing_appender::rolling::daily(
+            &config.directory,
+            &config.file_prefix,
+        )),
+    };
+    let registry = tracing_subscriber::registry()
+        .with(env_filter(&config.filters))
+        .with(broadcaster.layer())
+        .with(tracer.map(tracing_opentelemetry::OpenTelemetryLayer::new));
+    match config.format {
+        LogFormat::Json => registry.with(JsonLayer::new(writer)).init(),
+        LogFormat::Text => registry
+            .with(tracing_subscriber::fmt::layer().with_writer(writer))
+            .init(),
+    }
+    guard
+}
+
+#[derive(Default)]
+struct FieldVisitor {
+    message: String,
+    fields: BTreeMap<String, String>,
+}
+
+impl FieldVisitor {
+    fn record(&mut self, field: &tracing::field::Field, value: String) {
+        if field.name() == "message" {
+            self.message = value;
+        } else {
+            self.fields
+                .insert(field.name().to_string(), redact_field(field.name(), &value));
+        }
+    }
+}
+
+impl Visit for FieldVisitor {
+    fn record_str(&mut self, field: &tracing::field::Field, value: &str) {
+        self.record(field, value.to_string());
+    }
+
+    fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+        self.record(field, format!("{value:?}"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(level: &str, target: &str) -> LogEvent {
+        LogEvent {
+            timestamp: Utc::now(),
+            level: level.to_string(),
+            target: target.to_string(),
+            message: "m".into(),
+            fields: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn level_filter_is_a_minimum() {
+        let filter = LogFilter {
+            level: Some("warn".into()),
+            module: None,
+        };
+        assert!(filter.matches(&event("ERROR", "api_gateway")));
+        assert!(filter.matches(&event("WARN", "api_gateway")));
+        assert!(!filter.matches(&event("INFO", "api_gateway")));
+    }
+
+    #[test]
+    fn module_filter_is_a_prefix() {
+        let filter = LogFilter {
+            level: None,
+            module: Some("api_gateway::handlers".into()),
+        };
+        assert!(filter.matches(&event("INFO", "api_gateway::handlers::chat")));
+        assert!(!filter.matches(&event("INFO", "sqlx::query")));
+    }
+
+    #[test]
+    fn sensitive_fields_are_redacted() {
+        assert_eq!(redact_field("api_key", "abc123"), "[redacted]");
+        assert_eq!(redact_field("Authorization", "Bearer x"), "[redacted]");
+        assert_eq!(redact_field("crop_type", "rice"), "rice");
+    }
+
+    #[tokio::test]
+    async fn publishing_past_capacity_drops_instead_of_blocking() {
+        let broadcaster = LogBroadcaster::new(4);
+        let mut receiver = broadcaster.subscribe();
+
+        // Overfill the buffer without the receiver draining; publish must
+        // stay non-blocking throughout.
+        for i in 0..10 {
+            broadcaster.publish(event("INFO", &format!("mod{i}")));
+        }
+
+        // The slow receiver learns it lagged, then picks up from the newest
+        // retained events rather than stalling the publisher.
+        assert!(matches!(
+            receiver.recv().await,
+            Err(broadcast::error::RecvError::Lagged(_))
+        ));
+        let next = receiver.recv().await.unwrap();
+        assert_eq!(next.target, "mod6");
+    }
+
+    /// `MakeWriter` collecting everything into a shared buffer.
+    #[derive(Clone, Default)]
+    struct CaptureWriter(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl std::io::Write for CaptureWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl<'w> tracing_subscriber::fmt::MakeWriter<'w> for CaptureWriter {
+        type Writer = CaptureWriter;
+        fn make_writer(&'w self) -> Self::Writer {
+            self.clone()
+        }
+    }
+
+    #[test]
+    fn json_layer_emits_parseable_lines_with_standard_fields() {
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let capture = CaptureWriter::default();
+        let subscriber =
+            tracing_subscriber::registry().with(JsonLayer::new(capture.clone()));
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!(crop_type = "rice", "job queued");
+        });
+
+        let output = String::from_utf8(capture.0.lock().unwrap().clone()).unwrap();
+        let line: serde_json::Value =
+            serde_json::from_str(output.lines().next().expect("one log line"))
+                .expect("line is valid JSON");
+        assert_eq!(line["service"], SERVICE_NAME);
+        assert_eq!(line["level"], "INFO");
+        assert!(line["timestamp"].is_string());
+        assert_eq!(line["message"], "job queued");
+        // Extra fields are flattened to the top level for indexing.
+        assert_eq!(line["crop_type"], "rice");
+    }
+
+    #[tokio::test]
+    async fn json_lines_carry_the_request_correlation_id() {
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let capture = CaptureWriter::default();
+        let subscriber =
+            tracing_subscriber::registry().with(JsonLayer::new(capture.clone()));
+        let _guard = tracing::subscriber::set_default(subscriber);
+        crate::middleware::correlation::scope_correlation_id("req-42", async {
+            tracing::warn!("slow upstream");
+        })
+        .await;
+
+        let output = String::from_utf8(capture.0.lock().unwrap().clone()).unwrap();
+        let line: serde_json::Value =
+            serde_json::from_str(output.lines().next().expect("one log line")).unwrap();
+        assert_eq!(line["correlation_id"], "req-42");
+    }
+
+    #[test]
+    fn module_overrides_stack_on_the_env_filter() {
+        let mut filters = BTreeMap::new();
+        filters.insert("sqlx".to_string(), "warn".to_string());
+        filters.insert("api_gateway::handlers".to_string(), "debug".to_string());
+        let filter = env_filter(&filters);
+        let rendered = filter.to_string();
+        assert!(rendered.contains("sqlx=warn"), "got {rendered}");
+        assert!(rendered.contains("api_gateway::handlers=debug"), "got {rendered}");
+    }
+
+    #[tokio::test]
+    async fn subscribers_receive_published_events() {
+        let broadcaster = LogBroadcaster::new(4);
+        let mut receiver = broadcaster.subscribe();
+        broadcaster.publish(event("INFO", "api_gateway"));
+        let received = receiver.recv().await.unwrap();
+        assert_eq!(received.target, "api_gateway");
+    }
+}
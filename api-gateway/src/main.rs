@@ -0,0 +1,54 @@
+use std::sync::Arc;
+
+use api_gateway::config::AppConfig;
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    // Config first: the log pipeline's shape (format, output, filters) comes
+    // from it. The guard must live until exit or file logging stops early.
+    let config = Arc::new(AppConfig::from_env()?);
+    let tracer = api_gateway::telemetry::init(&config.tracing);
+    let log_broadcaster = api_gateway::logging::LogBroadcaster::default();
+    let _log_guard = api_gateway::logging::init(&config.log, &log_broadcaster, tracer);
+    // Fail the boot on a bad CORS policy rather than serving broken
+    // preflights.
+    let cors = api_gateway::middleware::cors::layer(&config.cors)?;
+    let metrics_handle = api_gateway::metrics::install_recorder();
+
+    let state = api_gateway::app::build_state(config.clone(), log_broadcaster, metrics_handle).await?;
+    let scheduler = api_gateway::app::spawn_background_tasks(&state);
+
+    let addr = format!("{}:{}", config.server.host, config.server.port);
+    tracing::info!(%addr, "api-gateway listening");
+    let listener = tokio::net::TcpListener::bind(&addr).await?;
+
+    let shutdown = {
+        let state = state.clone();
+        async move {
+            api_gateway::shutdown::signal().await;
+            tracing::info!("shutdown signal received; draining in-flight requests");
+            state.begin_shutdown();
+        }
+    };
+    api_gateway::shutdown::serve_with_drain(
+        listener,
+        api_gateway::app::create_router(state.clone(), cors),
+        shutdown,
+        config.server.drain_timeout,
+    )
+    .await?;
+
+    // Connections are drained (or dropped at the deadline): stop the
+    // periodic sweeps so none starts mid-teardown, release the broker so
+    // unacked jobs are redelivered immediately, and flush one last cleanup
+    // pass before exiting.
+    scheduler.shutdown().await;
+    state.rabbitmq.close().await;
+    api_gateway::services::cleanup::run_once(&state).await;
+    // Last: the cleanup pass above still needs the pool. Closing waits for
+    // checked-out connections, so nothing is cut off mid-query.
+    state.db.close().await;
+    api_gateway::telemetry::shutdown();
+    tracing::info!("shutdown complete");
+    Ok(())
+}
@@ -0,0 +1,229 @@
+//! Prometheus metrics: recorder installation, HTTP request tracking, and
+//! dependency gauges.
+//!
+//! The exposition text is served by `GET /health/metrics` (see
+//! `handlers::health`), rendered from the [`PrometheusHandle`] kept on
+//! `AppState`.
+
+use std::time::{Duration, Instant};
+
+use axum::{
+    extract::{MatchedPath, Request},
+    middleware::Next,
+    response::Response,
+};
+use metrics_exporter_prometheus::{PrometheusBuilder, PrometheusHandle};
+
+use crate::state::AppState;
+
+/// Sorted set of pending job ids scored by enqueue time (unix seconds),
+/// kept so the age of the oldest waiting job is answerable without a
+/// broker query. The gateway sees the whole lifecycle: enqueue adds the
+/// member, and every terminal transition it performs (advice completion,
+/// cancellation) removes it. Jobs that die outside those paths are
+/// pruned by age when the set is read.
+pub const PENDING_JOBS_KEY: &str = "jobs:pending";
+
+/// How often the dependency gauges are refreshed.
+const GAUGE_POLL_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Install the global Prometheus recorder; the returned handle renders the
+/// exposition text for the metrics endpoint. Installing is once-per-process
+/// (the recorder is a global), so repeated calls — the e2e suite builds
+/// several app states in one test binary — share the first handle.
+pub fn install_recorder() -> PrometheusHandle {
+    static HANDLE: std::sync::OnceLock<PrometheusHandle> = std::sync::OnceLock::new();
+    HANDLE
+        .get_or_init(|| {
+            PrometheusBuilder::new()
+                .install_recorder()
+                .expect("install prometheus recorder")
+        })
+        .clone()
+}
+
+/// Record one finished request into the counter/histogram pair. `path` is
+/// the matched route template rather than the raw URI, so label cardinality
+/// stays bounded by the route table.
+pub fn record_request(method: &str, path: &str, status: u16, latency: Duration) {
+    metrics::counter!(
+        "http_requests_total",
+        "method" => method.to_string(),
+        "path" => path.to_string(),
+        "status" => status.to_string(),
+    )
+    .increment(1);
+    metrics::histogram!("http_request_duration_seconds", "path" => path.to_string())
+        .record(latency.as_secs_f64());
+}
+
+/// One message published to (or consumed from) a queue.
+pub fn record_queue_event(queue: &str, event: &'static str) {
+    metrics::counter!("rabbitmq_messages_total", "queue" => queue.to_string(), "event" => event)
+        .increment(1);
+}
+
+/// One result-cache lookup; `hit` distinguishes the outcome label.
+pub fn record_cache_access(cache: &'static str, hit: bool) {
+    metrics::counter!(
+        "cache_requests_total",
+        "cache" => cache,
+        "outcome" => if hit { "hit" } else { "miss" },
+    )
+    .increment(1);
+}
+
+/// One stampede-protected cache lookup (see `services::cache::get_or_compute`);
+/// outcomes are `hit`, `miss`, `stale_serve`, and `lock_contention`.
+pub fn record_swr_cache(outcome: &'static str) {
+    metrics::counter!("swr_cache_requests_total", "outcome" => outcome).increment(1);
+}
+
+/// One upstream call that came back as an error, labeled by service and
+/// HTTP status.
+pub fn record_upstream_error(upstream: &'static str, status: u16) {
+    metrics::counter!(
+        "upstream_errors_total",
+        "upstream" => upstream,
+        "status" => status.to_string(),
+    )
+    .increment(1);
+}
+
+/// Middleware timing every request; layered next to the `TraceLayer` in
+/// `create_router` so it sees the final status of the inner middleware too.
+pub async fn track_http_metrics(request: Request, next: Next) -> Response {
+    let method = request.method().to_string();
+    let path = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|p| p.as_str().to_string())
+        .unwrap_or_else(|| "unmatched".to_string());
+    let started = Instant::now();
+    let response = next.run(request).await;
+    record_request(&method, &path, response.status().as_u16(), started.elapsed());
+    response
+}
+
+/// Periodically refresh the dependency gauges: RabbitMQ queue depth, Redis
+/// connected clients, and idle DB pool connections. Failed polls leave the
+/// previous gauge value in place.
+pub fn spawn_gauge_poller(state: AppState) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(GAUGE_POLL_INTERVAL);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        let http = reqwest::Client::new();
+        loop {
+            ticker.tick().await;
+            metrics::gauge!("db_pool_idle_connections").set(state.db.num_idle() as f64);
+            metrics::gauge!("upstream_breaker_state", "upstream" => "vision")
+                .set(state.services.vision_breaker.state_code());
+            metrics::gauge!("upstream_breaker_state", "upstream" => "llm")
+                .set(state.services.llm_breaker.state_code());
+            // Keys are labeled by slot index, never by value — the key
+            // itself is a credential.
+            for usage in state.services.ai4thai_keys.usage() {
+                let key = usage.index.to_string();
+                metrics::gauge!("ai4thai_key_requests", "key" => key.clone())
+                    .set(usage.requests as f64);
+                metrics::gauge!("ai4thai_key_quota_hits", "key" => key.clone())
+                    .set(usage.quota_hits as f64);
+                metrics::gauge!("ai4thai_key_demoted", "key" => key)
+                    .set(if usage.demoted { 1.0 } else { 0.0 });
+            }
+            if let Some(clients) = redis_connected_clients(&state).await {
+                metrics::gauge!("redis_connected_clients").set(clients);
+            }
+            if let Some(depth) = rabbitmq_queue_depth(&http, &state).await {
+                metrics::gauge!("rabbitmq_queue_depth").set(depth);
+            }
+            // Per-queue depth and consumer gauges straight over AMQP, so
+            // they work without a management URL configured.
+            for queue in [
+                state.rabbitmq.vision_queue().to_string(),
+                state.rabbitmq.dlq().to_string(),
+            ] {
+                if let Ok(stats) = state.rabbitmq.queue_stats(&queue).await {
+                    metrics::gauge!("rabbitmq_queue_messages", "queue" => queue.clone())
+                        .set(stats.messages as f64);
+                    metrics::gauge!("rabbitmq_queue_consumers", "queue" => queue)
+                        .set(stats.consumers as f64);
+                }
+            }
+        }
+    })
+}
+
+async fn redis_connected_clients(state: &AppState) -> Option<f64> {
+    let mut conn = state.get_redis().await.ok()?;
+    let info: String = redis::cmd("INFO")
+        .arg("clients")
+        .query_async(&mut conn)
+        .await
+        .ok()?;
+    parse_connected_clients(&info)
+}
+
+/// Extract `connected_clients` from a Redis `INFO clients` section.
+fn parse_connected_clients(info: &str) -> Option<f64> {
+    info.lines()
+        .find_map(|line| line.strip_prefix("connected_clients:"))
+        .and_then(|value| value.trim().parse().ok())
+}
+
+/// Queue depth from the RabbitMQ management API (the AMQP protocol has no
+/// cheap depth query). `None` when `rabbitmq.management_url` is not
+/// configured or the call fails.
+async fn rabbitmq_queue_depth(http: &reqwest::Client, state: &AppState) -> Option<f64> {
+    let base = state.config.rabbitmq.management_url.as_deref()?;
+    let queue = &state.config.rabbitmq.vision_queue;
+    let url = format!("{}/api/queues/%2f/{queue}", base.trim_end_matches('/'));
+    let body: serde_json::Value = http.get(&url).send().await.ok()?.json().await.ok()?;
+    body.get("messages")?.as_f64()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recorded_requests_render_in_prometheus_format() {
+        let recorder = PrometheusBuilder::new().build_recorder();
+        let handle = recorder.handle();
+        metrics::with_local_recorder(&recorder, || {
+            record_request("GET", "/api/v1/version", 200, Duration::from_millis(12));
+            record_request("GET", "/api/v1/version", 200, Duration::from_millis(20));
+        });
+        let text = handle.render();
+        assert!(text.contains("http_requests_total"), "missing counter in:\n{text}");
+        assert!(text.contains("path=\"/api/v1/version\""));
+        assert!(text.contains("status=\"200\""));
+        assert!(text.contains("http_request_duration_seconds"));
+    }
+
+    #[test]
+    fn event_counters_render_with_their_labels() {
+        let recorder = PrometheusBuilder::new().build_recorder();
+        let handle = recorder.handle();
+        metrics::with_local_recorder(&recorder, || {
+            record_queue_event("vision_analysis_queue", "published");
+            record_cache_access("vision_results", true);
+            record_cache_access("vision_results", false);
+            record_upstream_error("vision", 503);
+        });
+        let text = handle.render();
+        assert!(text.contains("rabbitmq_messages_total"));
+        assert!(text.contains("event=\"published\""));
+        assert!(text.contains("outcome=\"hit\""));
+        assert!(text.contains("outcome=\"miss\""));
+        assert!(text.contains("upstream_errors_total"));
+        assert!(text.contains("status=\"503\""));
+    }
+
+    #[test]
+    fn connected_clients_parse_from_info_output() {
+        let info = "# Clients\r\nconnected_clients:7\r\nblocked_clients:0\r\n";
+        assert_eq!(parse_connected_clients(info), Some(7.0));
+        assert_eq!(parse_connected_clients("no such section"), None);
+    }
+}
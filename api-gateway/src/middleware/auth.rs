@@ -0,0 +1,270 @@
+//! JWT authentication: validates `Authorization: Bearer` tokens and makes
+//! [`AuthUser`] available to handlers as an extractor.
+
+use axum::{
+    extract::{FromRequestParts, Request, State},
+    http::request::Parts,
+    middleware::Next,
+    response::Response,
+};
+use jsonwebtoken::{decode, errors::ErrorKind, Algorithm, DecodingKey, Validation};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{errors::AppError, state::AppState, AuthUser};
+
+/// Route prefixes that never require a bearer header. The chat WebSocket is
+/// listed because browsers cannot set headers on upgrade requests; its
+/// handler validates a query-parameter token itself.
+const PUBLIC_PREFIXES: &[&str] = &[
+    "/health",
+    "/api/v1/version",
+    "/api/v1/auth",
+    "/api/v1/webhooks/line",
+    "/api/v1/chat/ws",
+    // API documentation: integrators read the contract before they have a
+    // token.
+    "/docs",
+    "/openapi.json",
+];
+
+/// JWT claims; field names map directly onto [`AuthUser`].
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Claims {
+    /// User id.
+    pub sub: Uuid,
+    pub email: String,
+    #[serde(default)]
+    pub roles: Vec<String>,
+    /// Expiry as unix seconds (validated by jsonwebtoken).
+    pub exp: i64,
+}
+
+pub fn is_public_route(path: &str) -> bool {
+    // The job-status WebSocket has an id in the middle of the path, so it
+    // can't be a prefix entry; like the chat socket it validates a query
+    // token itself.
+    if path.starts_with("/api/v1/vision/jobs/") && path.ends_with("/ws") {
+        return true;
+    }
+    PUBLIC_PREFIXES.iter().any(|prefix| path.starts_with(prefix))
+}
+
+/// Validate a bearer token and map its claims onto [`AuthUser`]. Expired
+/// tokens get a distinct message so clients can silently refresh instead of
+/// logging the user out.
+pub fn validate_token(token: &str, secret: &[u8]) -> Result<AuthUser, AppError> {
+    // HS256 only: the key is an HMAC secret, and jsonwebtoken rejects a
+    // validation whose algorithm list names a family the key can't verify.
+    let validation = Validation::new(Algorithm::HS256);
+    match decode::<Claims>(token, &DecodingKey::from_secret(secret), &validation) {
+        Ok(data) => Ok(AuthUser {
+            user_id: data.claims.sub,
+            email: data.claims.email,
+            roles: data.claims.roles,
+        }),
+        Err(e) if matches!(e.kind(), ErrorKind::ExpiredSignature) => {
+            Err(AppError::Auth("token expired".into()))
+        }
+        Err(_) => Err(AppError::Auth("invalid token".into())),
+    }
+}
+
+/// Guard for handlers that need a role beyond authentication. The caller is
+/// authenticated — we know who they are — so a missing role is a 403, not
+/// another 401.
+pub fn require_role(user: &AuthUser, role: &str) -> Result<(), AppError> {
+    if user.has_role(role) {
+        Ok(())
+    } else {
+        Err(AppError::Forbidden(format!("role '{role}' required")))
+    }
+}
+
+/// Names a role at the type level for the [`RequireRole`] extractor.
+pub trait RoleRequirement {
+    const ROLE: &'static str;
+}
+
+/// The `admin` role: destructive and fleet-wide read endpoints.
+pub struct Admin;
+
+impl RoleRequirement for Admin {
+    const ROLE: &'static str = "admin";
+}
+
+/// Declarative form of [`require_role`]: a handler taking
+/// `RequireRole<Admin>` rejects before its body runs — 401 when nobody is
+/// authenticated, 403 when the caller lacks the role. The checked
+/// [`AuthUser`] is inside for handlers that also need the identity.
+pub struct RequireRole<R>(pub AuthUser, std::marker::PhantomData<R>);
+
+#[axum::async_trait]
+impl<S, R> FromRequestParts<S> for RequireRole<R>
+where
+    S: Send + Sync,
+    R: RoleRequirement,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let user = AuthUser::from_request_parts(parts, state).await?;
+        require_role(&user, R::ROLE)?;
+        Ok(Self(user, std::marker::PhantomData))
+    }
+}
+
+/// Middleware: public routes pass through untouched; everything else needs a
+/// valid bearer token, whose claims are injected as an [`AuthUser`]
+/// extension for the extractor below.
+pub async fn auth_middleware(
+    State(state): State<AppState>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    if is_public_route(request.uri().path()) {
+        return Ok(next.run(request).await);
+    }
+
+    let token = request
+        .headers()
+        .get(axum::http::header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .ok_or_else(|| AppError::Auth("missing bearer token".into()))?;
+
+    let user = validate_token(token, state.config.jwt_secret.as_bytes())?;
+    request.extensions_mut().insert(user);
+    Ok(next.run(request).await)
+}
+
+#[axum::async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for AuthUser {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<AuthUser>()
+            .cloned()
+            .ok_or_else(|| AppError::Auth("not authenticated".into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use jsonwebtoken::{encode, EncodingKey, Header};
+
+    use super::*;
+
+    const SECRET: &[u8] = b"test-secret";
+
+    fn token(exp_offset_secs: i64) -> String {
+        let claims = Claims {
+            sub: Uuid::new_v4(),
+            email: "farmer@example.com".into(),
+            roles: vec!["farmer".into()],
+            exp: chrono::Utc::now().timestamp() + exp_offset_secs,
+        };
+        encode(&Header::default(), &claims, &EncodingKey::from_secret(SECRET)).unwrap()
+    }
+
+    #[test]
+    fn valid_token_maps_claims_onto_auth_user() {
+        let user = validate_token(&token(3600), SECRET).unwrap();
+        assert_eq!(user.email, "farmer@example.com");
+        assert!(user.has_role("farmer"));
+    }
+
+    #[test]
+    fn expired_token_gets_a_distinct_message() {
+        let err = validate_token(&token(-3600), SECRET).unwrap_err();
+        assert!(matches!(&err, AppError::Auth(m) if m == "token expired"));
+    }
+
+    #[test]
+    fn malformed_token_does_not_panic() {
+        let err = validate_token("not.a.jwt", SECRET).unwrap_err();
+        assert!(matches!(&err, AppError::Auth(m) if m == "invalid token"));
+        let err = validate_token("", SECRET).unwrap_err();
+        assert!(matches!(err, AppError::Auth(_)));
+    }
+
+    #[test]
+    fn wrong_secret_is_rejected() {
+        let err = validate_token(&token(3600), b"other-secret").unwrap_err();
+        assert!(matches!(err, AppError::Auth(_)));
+    }
+
+    #[test]
+    fn health_routes_are_public() {
+        assert!(is_public_route("/health"));
+        assert!(is_public_route("/health/ready"));
+        assert!(is_public_route("/api/v1/version"));
+        assert!(is_public_route("/api/v1/auth/login"));
+        assert!(is_public_route("/api/v1/chat/ws"));
+        assert!(is_public_route("/api/v1/vision/jobs/abc/ws"));
+        assert!(!is_public_route("/api/v1/vision/jobs/abc"));
+        assert!(!is_public_route("/api/v1/chat"));
+    }
+
+    fn user_with_roles(roles: &[&str]) -> AuthUser {
+        AuthUser {
+            user_id: Uuid::new_v4(),
+            email: "a@b.c".into(),
+            roles: roles.iter().map(|r| r.to_string()).collect(),
+        }
+    }
+
+    fn bare_parts() -> Parts {
+        axum::http::Request::builder()
+            .uri("/api/v1/vision/files/stats")
+            .body(())
+            .unwrap()
+            .into_parts()
+            .0
+    }
+
+    #[tokio::test]
+    async fn extractor_rejects_the_unauthenticated_with_401() {
+        let mut parts = bare_parts();
+        let err = RequireRole::<Admin>::from_request_parts(&mut parts, &())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AppError::Auth(_)));
+    }
+
+    #[tokio::test]
+    async fn extractor_rejects_a_missing_role_with_403() {
+        let mut parts = bare_parts();
+        parts.extensions.insert(user_with_roles(&["farmer"]));
+        let err = RequireRole::<Admin>::from_request_parts(&mut parts, &())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AppError::Forbidden(_)));
+    }
+
+    #[tokio::test]
+    async fn extractor_passes_the_authorized_user_through() {
+        let mut parts = bare_parts();
+        parts.extensions.insert(user_with_roles(&["farmer", "admin"]));
+        let RequireRole(user, _) = RequireRole::<Admin>::from_request_parts(&mut parts, &())
+            .await
+            .expect("admin passes");
+        assert!(user.has_role("admin"));
+    }
+
+    #[test]
+    fn require_role_guards() {
+        let user = AuthUser {
+            user_id: Uuid::new_v4(),
+            email: "a@b.c".into(),
+            roles: vec!["admin".into()],
+        };
+        assert!(require_role(&user, "admin").is_ok());
+        assert!(matches!(
+            require_role(&user, "reviewer"),
+            Err(AppError::Forbidden(_))
+        ));
+    }
+}
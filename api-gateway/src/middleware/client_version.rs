@@ -0,0 +1,121 @@
+//! Minimum-client enforcement.
+//!
+//! Reads `X-Client-Version` and, when the client is below
+//! `incompatible_below`, rejects mutating requests with `426 Upgrade
+//! Required` while still allowing reads (so an outdated tab can render the
+//! version banner that tells the user to reload). Requests per client
+//! version are counted for the metrics endpoint.
+
+use axum::{
+    extract::{Request, State},
+    http::{HeaderValue, Method},
+    middleware::Next,
+    response::Response,
+};
+
+use crate::{errors::AppError, state::AppState};
+
+pub const CLIENT_VERSION_HEADER: &str = "x-client-version";
+pub const MIN_VERSION_HEADER: &str = "x-min-frontend-version";
+
+/// `major.minor.patch` comparison; malformed versions compare as 0.0.0 so an
+/// unparseable header never locks a client out of reads but does gate writes
+/// only if `incompatible_below` is above zero and the header was present.
+fn parse(raw: &str) -> (u32, u32, u32) {
+    let core = raw.trim().trim_start_matches('v');
+    let core = core.split(['-', '+']).next().unwrap_or("");
+    let mut parts = core.split('.').map(|p| p.parse().unwrap_or(0));
+    (
+        parts.next().unwrap_or(0),
+        parts.next().unwrap_or(0),
+        parts.next().unwrap_or(0),
+    )
+}
+
+/// Decide whether a request must be rejected. Pure so it can be unit tested
+/// without spinning up the router.
+pub fn should_reject(method: &Method, client_version: Option<&str>, incompatible_below: &str) -> bool {
+    // Reads always pass; a stale tab must still be able to fetch /version.
+    if matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS) {
+        return false;
+    }
+    // No header means a client predating version negotiation or a non-browser
+    // caller; let it through rather than breaking curl and the worker.
+    let Some(version) = client_version else {
+        return false;
+    };
+    parse(version) < parse(incompatible_below)
+}
+
+/// Axum middleware applying [`should_reject`] and stamping the expected
+/// minimum version on every response so clients can self-check cheaply.
+pub async fn enforce_client_version(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    let client_version = request
+        .headers()
+        .get(CLIENT_VERSION_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_owned);
+
+    {
+        let versions = state.versions.read().expect("version lock poisoned");
+        if should_reject(
+            request.method(),
+            client_version.as_deref(),
+            &versions.incompatible_below,
+        ) {
+            return Err(AppError::ClientOutdated);
+        }
+    }
+
+    if let Some(version) = &client_version {
+        let mut counts = state
+            .client_version_counts
+            .lock()
+            .expect("version counter lock poisoned");
+        *counts.entry(version.clone()).or_insert(0) += 1;
+    }
+
+    let mut response = next.run(request).await;
+    let min = state
+        .versions
+        .read()
+        .expect("version lock poisoned")
+        .min_frontend_version
+        .clone();
+    if let Ok(value) = HeaderValue::from_str(&min) {
+        response.headers_mut().insert(MIN_VERSION_HEADER, value);
+    }
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_are_never_rejected() {
+        assert!(!should_reject(&Method::GET, Some("0.0.1"), "1.0.0"));
+        assert!(!should_reject(&Method::OPTIONS, Some("0.0.1"), "1.0.0"));
+    }
+
+    #[test]
+    fn outdated_writes_get_426() {
+        assert!(should_reject(&Method::POST, Some("0.9.0"), "1.0.0"));
+        assert!(should_reject(&Method::DELETE, Some("0.9.0"), "1.0.0"));
+    }
+
+    #[test]
+    fn compatible_writes_pass() {
+        assert!(!should_reject(&Method::POST, Some("1.0.0"), "1.0.0"));
+        assert!(!should_reject(&Method::POST, Some("1.2.3"), "1.0.0"));
+    }
+
+    #[test]
+    fn missing_header_passes() {
+        assert!(!should_reject(&Method::POST, None, "1.0.0"));
+    }
+}
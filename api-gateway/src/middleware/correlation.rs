@@ -0,0 +1,115 @@
+//! Request correlation IDs.
+//!
+//! Every request gets an id — the caller's `X-Correlation-ID` if supplied,
+//! otherwise a fresh UUID — carried three ways: a request extension for
+//! handlers, a task-local so [`crate::errors::AppError`] can stamp error
+//! bodies without threading the id everywhere, and a tracing span field so
+//! all log lines for one request share the identifier.
+
+use axum::{
+    extract::{Request, State},
+    http::{HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use tracing::Instrument;
+use uuid::Uuid;
+
+use crate::state::AppState;
+
+pub const CORRELATION_HEADER: HeaderName = HeaderName::from_static("x-correlation-id");
+
+/// Longest accepted caller-supplied id; anything longer is replaced so a
+/// hostile client can't stuff kilobytes into every log line.
+const MAX_ID_LENGTH: usize = 64;
+
+tokio::task_local! {
+    static CORRELATION_ID: String;
+}
+
+/// The id for the current request, if running inside the middleware's
+/// scope (background tasks are not).
+pub fn current_correlation_id() -> Option<String> {
+    CORRELATION_ID.try_with(|id| id.clone()).ok()
+}
+
+/// Run `future` with a fixed correlation id; what the middleware does for
+/// real requests, exposed for tests of id-dependent code paths.
+pub async fn scope_correlation_id<F: std::future::Future>(id: &str, future: F) -> F::Output {
+    CORRELATION_ID.scope(id.to_string(), future).await
+}
+
+/// Per-request id carried in request extensions.
+#[derive(Debug, Clone)]
+pub struct CorrelationId(pub String);
+
+/// Use the caller's id when it is sane, otherwise generate one.
+pub fn resolve_correlation_id(supplied: Option<&str>) -> String {
+    match supplied {
+        Some(id)
+            if !id.trim().is_empty()
+                && id.len() <= MAX_ID_LENGTH
+                && id.chars().all(|c| c.is_ascii_graphic()) =>
+        {
+            id.to_string()
+        }
+        _ => Uuid::new_v4().to_string(),
+    }
+}
+
+pub async fn correlation_id_middleware(
+    State(state): State<AppState>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let id = resolve_correlation_id(
+        request
+            .headers()
+            .get(&CORRELATION_HEADER)
+            .and_then(|v| v.to_str().ok()),
+    );
+    request.extensions_mut().insert(CorrelationId(id.clone()));
+    *state
+        .last_correlation_id
+        .lock()
+        .expect("correlation lock poisoned") = Some(id.clone());
+
+    let span = tracing::info_span!("request", correlation_id = %id);
+    let mut response = CORRELATION_ID
+        .scope(id.clone(), next.run(request).instrument(span))
+        .await;
+    if let Ok(value) = HeaderValue::from_str(&id) {
+        response.headers_mut().insert(CORRELATION_HEADER, value);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn supplied_ids_are_kept_when_sane() {
+        assert_eq!(resolve_correlation_id(Some("req-1234")), "req-1234");
+    }
+
+    #[test]
+    fn hostile_or_missing_ids_are_replaced() {
+        for supplied in [None, Some(""), Some("   "), Some("has spaces"), Some("คนไทย")] {
+            let id = resolve_correlation_id(supplied);
+            assert!(Uuid::parse_str(&id).is_ok(), "expected generated uuid, got {id}");
+        }
+        let long = "x".repeat(65);
+        assert!(Uuid::parse_str(&resolve_correlation_id(Some(&long))).is_ok());
+    }
+
+    #[tokio::test]
+    async fn task_local_id_is_visible_inside_the_scope() {
+        assert_eq!(current_correlation_id(), None);
+        CORRELATION_ID
+            .scope("req-42".to_string(), async {
+                assert_eq!(current_correlation_id().as_deref(), Some("req-42"));
+            })
+            .await;
+    }
+}
@@ -0,0 +1,170 @@
+//! CORS policy built from configuration.
+//!
+//! The hackathon-era `CorsLayer::permissive()` let any page on the internet
+//! call the API from a browser; behind a real domain the allowed origins
+//! have to be pinned down. The policy is validated once at startup — a typo
+//! in an origin or method is a [`ConfigError`] that stops the boot, not a
+//! silently broken preflight in production.
+
+use axum::http::{header, HeaderValue, Method};
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
+
+use crate::config::{ConfigError, CorsConfig};
+
+/// Headers the frontend sends on cross-origin requests; kept in one place
+/// so a new client header only needs adding here.
+const ALLOWED_HEADERS: [header::HeaderName; 5] = [
+    header::AUTHORIZATION,
+    header::CONTENT_TYPE,
+    header::ACCEPT,
+    header::HeaderName::from_static("x-client-version"),
+    header::HeaderName::from_static("x-correlation-id"),
+];
+
+/// Build the CORS layer, rejecting configurations that are invalid or that
+/// the CORS spec forbids (credentials with a wildcard origin).
+pub fn layer(config: &CorsConfig) -> Result<CorsLayer, ConfigError> {
+    let methods = config
+        .allowed_methods
+        .iter()
+        .map(|m| {
+            m.parse::<Method>()
+                .map_err(|_| ConfigError(format!("cors: invalid method {m:?}")))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let wildcard = config.allowed_origins.iter().any(|o| o == "*");
+    if wildcard && config.allow_credentials {
+        return Err(ConfigError(
+            "cors: allow_credentials cannot be combined with a wildcard origin; \
+             list the frontend origins explicitly"
+                .into(),
+        ));
+    }
+
+    let origin = if wildcard {
+        AllowOrigin::any()
+    } else {
+        let origins = config
+            .allowed_origins
+            .iter()
+            .map(|o| {
+                HeaderValue::from_str(o)
+                    .map_err(|_| ConfigError(format!("cors: invalid origin {o:?}")))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        AllowOrigin::list(origins)
+    };
+
+    let layer = CorsLayer::new()
+        .allow_origin(origin)
+        .allow_methods(methods)
+        .max_age(config.max_age);
+    // With a wildcard, headers can be wildcarded too; with credentials the
+    // spec requires explicit header names.
+    let layer = if wildcard {
+        layer.allow_headers(Any)
+    } else {
+        layer.allow_headers(ALLOWED_HEADERS)
+    };
+    Ok(if config.allow_credentials {
+        layer.allow_credentials(true)
+    } else {
+        layer
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use axum::{routing::get, Router};
+
+    use super::*;
+
+    fn config(origins: &[&str], credentials: bool) -> CorsConfig {
+        CorsConfig {
+            allowed_origins: origins.iter().map(|o| o.to_string()).collect(),
+            allowed_methods: vec!["GET".into(), "POST".into()],
+            allow_credentials: credentials,
+            max_age: Duration::from_secs(600),
+        }
+    }
+
+    async fn preflight(cors: CorsLayer, origin: &str) -> reqwest::Response {
+        let app = Router::new()
+            .route("/api/v1/version", get(|| async { "ok" }))
+            .layer(cors);
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        reqwest::Client::new()
+            .request(
+                reqwest::Method::OPTIONS,
+                format!("http://{addr}/api/v1/version"),
+            )
+            .header("Origin", origin)
+            .header("Access-Control-Request-Method", "GET")
+            .send()
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn preflight_from_an_allowed_origin_is_granted() {
+        let cors = layer(&config(&["https://app.example.com"], true)).unwrap();
+        let response = preflight(cors, "https://app.example.com").await;
+        assert_eq!(
+            response
+                .headers()
+                .get("access-control-allow-origin")
+                .and_then(|v| v.to_str().ok()),
+            Some("https://app.example.com")
+        );
+        assert_eq!(
+            response
+                .headers()
+                .get("access-control-allow-credentials")
+                .and_then(|v| v.to_str().ok()),
+            Some("true")
+        );
+    }
+
+    #[tokio::test]
+    async fn preflight_from_an_unknown_origin_gets_no_grant() {
+        let cors = layer(&config(&["https://app.example.com"], false)).unwrap();
+        let response = preflight(cors, "https://evil.example.net").await;
+        assert!(response.headers().get("access-control-allow-origin").is_none());
+    }
+
+    #[tokio::test]
+    async fn wildcard_origin_answers_every_preflight() {
+        let cors = layer(&config(&["*"], false)).unwrap();
+        let response = preflight(cors, "https://anywhere.example.org").await;
+        assert_eq!(
+            response
+                .headers()
+                .get("access-control-allow-origin")
+                .and_then(|v| v.to_str().ok()),
+            Some("*")
+        );
+    }
+
+    #[test]
+    fn credentials_with_a_wildcard_origin_is_rejected_at_startup() {
+        let error = layer(&config(&["*"], true)).unwrap_err();
+        assert!(error.to_string().contains("wildcard"), "got: {error}");
+    }
+
+    #[test]
+    fn malformed_origins_and_methods_are_named_in_the_error() {
+        let error = layer(&config(&["not an origin\u{7f}"], false)).unwrap_err();
+        assert!(error.to_string().contains("invalid origin"), "got: {error}");
+
+        let mut bad_method = config(&["https://app.example.com"], false);
+        bad_method.allowed_methods = vec!["GE T".into()];
+        let error = layer(&bad_method).unwrap_err();
+        assert!(error.to_string().contains("invalid method"), "got: {error}");
+    }
+}
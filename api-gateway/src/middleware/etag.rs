@@ -0,0 +1,150 @@
+//! ETag / conditional GET support for stable JSON endpoints.
+//!
+//! Clients re-fetch conversation history and job status on every page load;
+//! most of the time nothing changed. [`etag_middleware`] hashes the
+//! serialized response body, sets `ETag`, and answers `304 Not Modified`
+//! with an empty body when the incoming `If-None-Match` already names that
+//! hash. It buffers the response to hash it, so it is layered onto specific
+//! JSON routes in `app::create_router` — never onto streaming ones.
+
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{header, Method, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+use sha2::{Digest, Sha256};
+
+/// Strong ETag for a body: quoted hex SHA-256.
+pub fn etag_for(body: &[u8]) -> String {
+    let digest = Sha256::digest(body);
+    let mut tag = String::with_capacity(2 + digest.len() * 2);
+    tag.push('"');
+    for byte in digest {
+        tag.push_str(&format!("{byte:02x}"));
+    }
+    tag.push('"');
+    tag
+}
+
+/// Does an `If-None-Match` header value cover `etag`? Handles the `*`
+/// wildcard, comma-separated lists, and weak (`W/`) validators — a weak
+/// match is fine for cache revalidation.
+pub fn if_none_match_covers(header_value: &str, etag: &str) -> bool {
+    header_value
+        .split(',')
+        .map(str::trim)
+        .any(|candidate| candidate == "*" || candidate.trim_start_matches("W/") == etag)
+}
+
+pub async fn etag_middleware(request: Request, next: Next) -> Response {
+    if request.method() != Method::GET {
+        return next.run(request).await;
+    }
+    let if_none_match = request
+        .headers()
+        .get(header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+
+    let response = next.run(request).await;
+    // Only cacheable successes get validators; errors stay as they are.
+    if response.status() != StatusCode::OK {
+        return response;
+    }
+    let (mut parts, body) = response.into_parts();
+    let Ok(bytes) = axum::body::to_bytes(body, usize::MAX).await else {
+        // Body already failed mid-stream; nothing sensible left to send.
+        return Response::from_parts(parts, Body::empty());
+    };
+
+    let etag = etag_for(&bytes);
+    parts
+        .headers
+        .insert(header::ETAG, etag.parse().expect("hex etag is a valid header value"));
+    if if_none_match
+        .as_deref()
+        .is_some_and(|given| if_none_match_covers(given, &etag))
+    {
+        parts.status = StatusCode::NOT_MODIFIED;
+        parts.headers.remove(header::CONTENT_LENGTH);
+        return Response::from_parts(parts, Body::empty());
+    }
+    Response::from_parts(parts, Body::from(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{routing::get, Router};
+
+    use super::*;
+
+    #[test]
+    fn identical_bodies_hash_to_identical_tags() {
+        assert_eq!(etag_for(b"{\"a\":1}"), etag_for(b"{\"a\":1}"));
+        assert_ne!(etag_for(b"{\"a\":1}"), etag_for(b"{\"a\":2}"));
+        assert!(etag_for(b"x").starts_with('"') && etag_for(b"x").ends_with('"'));
+    }
+
+    #[test]
+    fn if_none_match_handles_lists_weak_tags_and_wildcard() {
+        let etag = "\"abc\"";
+        assert!(if_none_match_covers("\"abc\"", etag));
+        assert!(if_none_match_covers("\"xyz\", \"abc\"", etag));
+        assert!(if_none_match_covers("W/\"abc\"", etag));
+        assert!(if_none_match_covers("*", etag));
+        assert!(!if_none_match_covers("\"xyz\"", etag));
+    }
+
+    async fn serve() -> std::net::SocketAddr {
+        let app = Router::new()
+            .route("/history", get(|| async { "[{\"message\":\"hi\"}]" }))
+            .route("/missing", get(|| async { StatusCode::NOT_FOUND }))
+            .layer(axum::middleware::from_fn(etag_middleware));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        addr
+    }
+
+    #[tokio::test]
+    async fn revalidation_round_trip_returns_304_with_no_body() {
+        let addr = serve().await;
+        let client = reqwest::Client::new();
+
+        let first = client
+            .get(format!("http://{addr}/history"))
+            .send()
+            .await
+            .unwrap();
+        let etag = first.headers()[header::ETAG].to_str().unwrap().to_string();
+        assert_eq!(first.status(), 200);
+        assert!(!first.text().await.unwrap().is_empty());
+
+        let second = client
+            .get(format!("http://{addr}/history"))
+            .header(header::IF_NONE_MATCH, &etag)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(second.status(), 304);
+        // The tag rides along so caches can refresh their metadata.
+        assert_eq!(second.headers()[header::ETAG].to_str().unwrap(), etag);
+        assert!(second.text().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn identical_requests_get_identical_tags_and_errors_get_none() {
+        let addr = serve().await;
+        let client = reqwest::Client::new();
+
+        let a = client.get(format!("http://{addr}/history")).send().await.unwrap();
+        let b = client.get(format!("http://{addr}/history")).send().await.unwrap();
+        assert_eq!(a.headers()[header::ETAG], b.headers()[header::ETAG]);
+
+        let missing = client.get(format!("http://{addr}/missing")).send().await.unwrap();
+        assert_eq!(missing.status(), 404);
+        assert!(missing.headers().get(header::ETAG).is_none());
+    }
+}
@@ -0,0 +1,159 @@
+//! Request body size limits and the per-request timeout.
+//!
+//! `ServerConfig` has carried `request_timeout` and `max_upload_size` since
+//! the start, but neither was enforced — a client could stream an unbounded
+//! body or hold a handler forever. The limits are plain `tower_http` layers
+//! wired in `create_router`: vision upload routes get `max_upload_size`
+//! (images), everything else gets the much smaller `max_body_size` (JSON).
+//! Because those layers answer with bare text bodies, [`envelope_rejections`]
+//! sits outside them and rewrites their 413/408 responses into the standard
+//! `ApiResponse` error shape clients already parse.
+
+use axum::{
+    extract::Request,
+    http::{header, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Json, Response},
+};
+use shared::types::ApiResponse;
+use tower_http::{limit::RequestBodyLimitLayer, timeout::TimeoutLayer};
+
+use crate::config::ServerConfig;
+
+/// Body limit for JSON routes (chat, auth, tags, ...).
+pub fn default_body_limit(config: &ServerConfig) -> RequestBodyLimitLayer {
+    RequestBodyLimitLayer::new(config.max_body_size)
+}
+
+/// Body limit for the vision upload routes, which carry image payloads.
+pub fn upload_body_limit(config: &ServerConfig) -> RequestBodyLimitLayer {
+    RequestBodyLimitLayer::new(config.max_upload_size)
+}
+
+/// Overall per-request deadline.
+pub fn timeout(config: &ServerConfig) -> TimeoutLayer {
+    TimeoutLayer::new(config.request_timeout)
+}
+
+/// Rewrite the bare rejections produced by the limit and timeout layers
+/// into the `ApiResponse` error envelope. Responses that are already JSON
+/// (a handler returning its own 413, e.g. `ImageTooLarge`) pass through
+/// untouched.
+pub async fn envelope_rejections(request: Request, next: Next) -> Response {
+    let response = next.run(request).await;
+    let message = match response.status() {
+        StatusCode::PAYLOAD_TOO_LARGE => "request body too large",
+        StatusCode::REQUEST_TIMEOUT => "request timed out",
+        _ => return response,
+    };
+    let already_json = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("application/json"));
+    if already_json {
+        return response;
+    }
+    (
+        response.status(),
+        Json(ApiResponse::<()>::error(message.to_string())),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use axum::{
+        routing::{get, post},
+        Router,
+    };
+
+    use super::*;
+
+    fn config(max_body_size: usize) -> ServerConfig {
+        ServerConfig {
+            host: "127.0.0.1".into(),
+            port: 0,
+            request_timeout: Duration::from_millis(200),
+            drain_timeout: Duration::from_secs(1),
+            max_upload_size: 4 * max_body_size,
+            max_body_size,
+            metrics_auth_token: None,
+        }
+    }
+
+    /// Serve a router shaped like `create_router`'s layering: per-route
+    /// body limits inside, the timeout and envelope rewriter outside.
+    async fn serve(config: &ServerConfig) -> std::net::SocketAddr {
+        let uploads = Router::new()
+            .route("/upload", post(|body: String| async move { body.len().to_string() }))
+            .layer(upload_body_limit(config));
+        let app = Router::new()
+            .route("/chat", post(|body: String| async move { body.len().to_string() }))
+            .route(
+                "/slow",
+                get(|| async {
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                    "done"
+                }),
+            )
+            .layer(default_body_limit(config))
+            .merge(uploads)
+            .layer(axum::middleware::from_fn(envelope_rejections))
+            .layer(timeout(config));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        addr
+    }
+
+    async fn post_body(addr: std::net::SocketAddr, path: &str, size: usize) -> reqwest::Response {
+        reqwest::Client::new()
+            .post(format!("http://{addr}{path}"))
+            .body(vec![b'x'; size])
+            .send()
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn bodies_on_either_side_of_the_limit() {
+        let config = config(1024);
+        let addr = serve(&config).await;
+
+        let under = post_body(addr, "/chat", 1024).await;
+        assert_eq!(under.status(), 200);
+        assert_eq!(under.text().await.unwrap(), "1024");
+
+        let over = post_body(addr, "/chat", 1025).await;
+        assert_eq!(over.status(), 413);
+        let body: serde_json::Value = over.json().await.unwrap();
+        assert_eq!(body["success"], false, "413 must use the ApiResponse shape");
+    }
+
+    #[tokio::test]
+    async fn upload_routes_get_the_larger_limit() {
+        let config = config(1024);
+        let addr = serve(&config).await;
+
+        // Over the JSON limit but under the upload limit.
+        let upload = post_body(addr, "/upload", 2048).await;
+        assert_eq!(upload.status(), 200);
+
+        let over = post_body(addr, "/upload", 4097).await;
+        assert_eq!(over.status(), 413);
+    }
+
+    #[tokio::test]
+    async fn slow_handlers_hit_the_timeout_with_an_enveloped_408() {
+        let config = config(1024);
+        let addr = serve(&config).await;
+
+        let response = reqwest::get(format!("http://{addr}/slow")).await.unwrap();
+        assert_eq!(response.status(), 408);
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(body["success"], false);
+    }
+}
@@ -0,0 +1,10 @@
+pub mod auth;
+pub mod client_version;
+pub mod correlation;
+pub mod cors;
+pub mod etag;
+pub mod limits;
+pub mod rate_limit;
+pub mod request_context;
+pub mod response_cache;
+pub mod shadow;
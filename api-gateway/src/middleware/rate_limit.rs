@@ -0,0 +1,246 @@
+//! Redis sliding-window rate limiting.
+//!
+//! One sorted set per subject (authenticated user id, or client IP for
+//! anonymous traffic) and route group holds the timestamps of recent
+//! requests. A Lua script trims the window, counts, and conditionally
+//! records the new request in a single round-trip, so concurrent requests
+//! cannot race past the limit (no check-then-set gap).
+
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::HeaderValue,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::net::SocketAddr;
+
+use crate::{
+    config::{RateLimitConfig, WindowLimit},
+    errors::AppError,
+    state::AppState,
+    AuthUser,
+};
+
+/// KEYS[1] = window zset; ARGV = now_ms, window_ms, limit, member.
+/// Returns {allowed, remaining, reset_ms}.
+const SLIDING_WINDOW_SCRIPT: &str = r#"
+local key = KEYS[1]
+local now = tonumber(ARGV[1])
+local window = tonumber(ARGV[2])
+local limit = tonumber(ARGV[3])
+
+redis.call('ZREMRANGEBYSCORE', key, 0, now - window)
+local count = redis.call('ZCARD', key)
+local allowed = 0
+if count < limit then
+  redis.call('ZADD', key, now, ARGV[4])
+  redis.call('PEXPIRE', key, window)
+  allowed = 1
+  count = count + 1
+end
+
+local oldest = redis.call('ZRANGE', key, 0, 0, 'WITHSCORES')
+local reset = now + window
+if oldest[2] then
+  reset = tonumber(oldest[2]) + window
+end
+return {allowed, limit - count, reset}
+"#;
+
+/// Route groups with separate budgets; the group name is part of the Redis
+/// key so a burst of chat messages can't consume the vision budget.
+pub fn route_group(path: &str) -> &'static str {
+    if path.starts_with("/api/v1/vision/jobs/batch") {
+        // Bulk mutations touch up to 100 rows per call; budget them tighter.
+        "bulk"
+    } else if path.starts_with("/api/v1/vision") {
+        "vision"
+    } else if path.starts_with("/api/v1/chat") {
+        "chat"
+    } else {
+        "default"
+    }
+}
+
+pub fn limit_for(config: &RateLimitConfig, group: &str) -> WindowLimit {
+    match group {
+        "vision" => config.vision,
+        "chat" => config.chat,
+        "bulk" => config.bulk,
+        _ => config.default,
+    }
+}
+
+/// Prefer the authenticated user id so one farmer behind carrier-grade NAT
+/// can't exhaust the budget of everyone sharing that IP; fall back to the IP
+/// for anonymous routes.
+pub fn subject_key(user: Option<&AuthUser>, addr: &SocketAddr) -> String {
+    match user {
+        Some(user) => format!("user:{}", user.user_id),
+        None => format!("ip:{}", addr.ip()),
+    }
+}
+
+/// Outcome of one rate-limit check, also used to build the response headers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimitDecision {
+    pub allowed: bool,
+    pub remaining: u64,
+    /// Unix millis at which the oldest counted request leaves the window.
+    pub reset_ms: u64,
+}
+
+impl RateLimitDecision {
+    pub fn from_script_reply(reply: (i64, i64, i64)) -> Self {
+        Self {
+            allowed: reply.0 == 1,
+            remaining: reply.1.max(0) as u64,
+            reset_ms: reply.2.max(0) as u64,
+        }
+    }
+
+    /// Whole seconds until the oldest counted request leaves the window,
+    /// rounded up so "retry after 0" never lies.
+    pub fn retry_after_secs(&self, now_ms: u64) -> u64 {
+        self.reset_ms.saturating_sub(now_ms).div_ceil(1000)
+    }
+
+    fn apply_headers(&self, response: &mut Response, now_ms: u64) {
+        let headers = response.headers_mut();
+        headers.insert(
+            "x-ratelimit-remaining",
+            HeaderValue::from_str(&self.remaining.to_string()).expect("numeric header"),
+        );
+        headers.insert(
+            "x-ratelimit-reset",
+            HeaderValue::from_str(&(self.reset_ms / 1000).to_string()).expect("numeric header"),
+        );
+        if !self.allowed {
+            headers.insert(
+                axum::http::header::RETRY_AFTER,
+                HeaderValue::from_str(&self.retry_after_secs(now_ms).to_string())
+                    .expect("numeric header"),
+            );
+        }
+    }
+}
+
+async fn check(state: &AppState, key: &str, limit: WindowLimit) -> Option<RateLimitDecision> {
+    let mut conn = state.get_redis().await.ok()?;
+    let now_ms = chrono::Utc::now().timestamp_millis();
+    let member = format!("{now_ms}-{}", uuid::Uuid::new_v4());
+    let reply: Result<(i64, i64, i64), _> = redis::Script::new(SLIDING_WINDOW_SCRIPT)
+        .key(format!("rate:{key}"))
+        .arg(now_ms)
+        .arg(limit.window_seconds * 1000)
+        .arg(limit.requests_per_window)
+        .arg(member)
+        .invoke_async(&mut conn)
+        .await;
+    match reply {
+        Ok(reply) => Some(RateLimitDecision::from_script_reply(reply)),
+        Err(e) => {
+            tracing::warn!(error = %e, "rate limiter redis error, failing open");
+            None
+        }
+    }
+}
+
+/// Middleware enforcing the sliding window per subject and route group.
+/// Redis being unreachable fails open — blocking all traffic is worse than
+/// briefly not rate limiting.
+pub async fn rate_limit_middleware(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let group = route_group(request.uri().path());
+    let subject = subject_key(request.extensions().get::<AuthUser>(), &addr);
+    let limit = limit_for(&state.config.rate_limit, group);
+
+    let Some(decision) = check(&state, &format!("{group}:{subject}"), limit).await else {
+        return next.run(request).await;
+    };
+    let now_ms = chrono::Utc::now().timestamp_millis().max(0) as u64;
+
+    if !decision.allowed {
+        let mut response = AppError::RateLimit.into_response();
+        decision.apply_headers(&mut response, now_ms);
+        return response;
+    }
+
+    let mut response = next.run(request).await;
+    decision.apply_headers(&mut response, now_ms);
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn script_reply_maps_to_decision() {
+        let d = RateLimitDecision::from_script_reply((1, 9, 1_700_000_000_000));
+        assert!(d.allowed);
+        assert_eq!(d.remaining, 9);
+        assert_eq!(d.reset_ms, 1_700_000_000_000);
+    }
+
+    #[test]
+    fn denied_reply_clamps_remaining_to_zero() {
+        let d = RateLimitDecision::from_script_reply((0, -1, 1_700_000_000_000));
+        assert!(!d.allowed);
+        assert_eq!(d.remaining, 0);
+    }
+
+    #[test]
+    fn headers_are_set_on_responses() {
+        let decision = RateLimitDecision {
+            allowed: true,
+            remaining: 5,
+            reset_ms: 12_000,
+        };
+        let mut response = Response::new(axum::body::Body::empty());
+        decision.apply_headers(&mut response, 10_000);
+        assert_eq!(response.headers()["x-ratelimit-remaining"], "5");
+        assert_eq!(response.headers()["x-ratelimit-reset"], "12");
+        assert!(!response.headers().contains_key("retry-after"));
+    }
+
+    #[test]
+    fn denied_responses_carry_retry_after() {
+        let decision = RateLimitDecision {
+            allowed: false,
+            remaining: 0,
+            reset_ms: 12_500,
+        };
+        let mut response = Response::new(axum::body::Body::empty());
+        decision.apply_headers(&mut response, 10_000);
+        // 2.5s rounds up; never tell a client to retry immediately.
+        assert_eq!(response.headers()["retry-after"], "3");
+    }
+
+    #[test]
+    fn paths_map_to_route_groups() {
+        assert_eq!(route_group("/api/v1/vision/analyze"), "vision");
+        assert_eq!(route_group("/api/v1/vision/jobs/batch/delete"), "bulk");
+        assert_eq!(route_group("/api/v1/chat/history"), "chat");
+        assert_eq!(route_group("/api/v1/profile/preferences"), "default");
+    }
+
+    #[test]
+    fn authenticated_subjects_are_keyed_by_user_not_ip() {
+        let addr: SocketAddr = "10.0.0.1:1234".parse().unwrap();
+        let user = AuthUser {
+            user_id: uuid::Uuid::nil(),
+            email: "a@b.c".into(),
+            roles: vec![],
+        };
+        assert_eq!(
+            subject_key(Some(&user), &addr),
+            format!("user:{}", uuid::Uuid::nil())
+        );
+        assert_eq!(subject_key(None, &addr), "ip:10.0.0.1");
+    }
+}
@@ -0,0 +1,200 @@
+//! One extractor for the per-request context handlers keep re-deriving.
+//!
+//! The correlation, auth, and client-version middleware each stash their
+//! piece of context in request extensions or leave it in headers;
+//! [`RequestContext`] gathers all of it behind a single `FromRequestParts`
+//! impl so handlers take one argument instead of digging through
+//! extensions ad hoc. A missing correlation id means the middleware stack
+//! is miswired — that is reported as a 500 naming the absent layer, never
+//! papered over with a default.
+
+use std::time::{Duration, Instant};
+
+use axum::{
+    extract::FromRequestParts,
+    http::{header, request::Parts, Extensions, HeaderMap},
+};
+use shared::models::Language;
+
+use crate::{
+    errors::{AppError, AppResult},
+    middleware::{client_version::CLIENT_VERSION_HEADER, correlation::CorrelationId},
+    state::AppState,
+    AuthUser,
+};
+
+/// Resolved context for the current request.
+#[derive(Debug, Clone)]
+pub struct RequestContext {
+    /// Correlation id assigned by the correlation middleware.
+    pub correlation_id: String,
+    /// Authenticated identity; `None` on public routes.
+    pub user: Option<AuthUser>,
+    /// Preferred response language from `Accept-Language`; Thai by default.
+    pub language: Language,
+    /// Raw `X-Client-Version` header, when the client sent one.
+    pub client_version: Option<String>,
+    /// When this request's time budget runs out (arrival plus the
+    /// configured request timeout).
+    pub deadline: Instant,
+}
+
+impl RequestContext {
+    /// The authenticated user, or the 401 that the auth middleware would
+    /// have produced had the route not been public.
+    pub fn require_user(&self) -> AppResult<&AuthUser> {
+        self.user
+            .as_ref()
+            .ok_or_else(|| AppError::Auth("not authenticated".into()))
+    }
+
+    /// Time left before the deadline; zero once it has passed.
+    pub fn time_remaining(&self) -> Duration {
+        self.deadline.saturating_duration_since(Instant::now())
+    }
+}
+
+/// Pick a language from an `Accept-Language` value: first recognized tag in
+/// listed order wins, anything else (or no header) falls back to Thai —
+/// the audience is Thai farmers and a wrong guess in English helps nobody.
+pub fn resolve_language(accept_language: Option<&str>) -> Language {
+    let Some(raw) = accept_language else {
+        return Language::default();
+    };
+    raw.split(',')
+        .filter_map(|entry| {
+            let tag = entry.split(';').next()?.trim().to_ascii_lowercase();
+            if tag.starts_with("th") {
+                Some(Language::Thai)
+            } else if tag.starts_with("en") {
+                Some(Language::English)
+            } else {
+                None
+            }
+        })
+        .next()
+        .unwrap_or_default()
+}
+
+/// Assemble the context from already-extracted request pieces; pure apart
+/// from reading the clock, so the with/without cases are unit testable.
+pub(crate) fn context_from_parts(
+    extensions: &Extensions,
+    headers: &HeaderMap,
+    request_timeout: Duration,
+) -> AppResult<RequestContext> {
+    let correlation_id = extensions
+        .get::<CorrelationId>()
+        .map(|id| id.0.clone())
+        .ok_or_else(|| {
+            AppError::Internal(
+                "RequestContext used without the correlation middleware; \
+                 add correlation_id_middleware to this router"
+                    .into(),
+            )
+        })?;
+    let language = resolve_language(
+        headers
+            .get(header::ACCEPT_LANGUAGE)
+            .and_then(|v| v.to_str().ok()),
+    );
+    let client_version = headers
+        .get(CLIENT_VERSION_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_owned);
+    Ok(RequestContext {
+        correlation_id,
+        user: extensions.get::<AuthUser>().cloned(),
+        language,
+        client_version,
+        deadline: Instant::now() + request_timeout,
+    })
+}
+
+#[axum::async_trait]
+impl FromRequestParts<AppState> for RequestContext {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        context_from_parts(
+            &parts.extensions,
+            &parts.headers,
+            state.config.server.request_timeout,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use super::*;
+
+    const TIMEOUT: Duration = Duration::from_secs(30);
+
+    fn parts(correlation: bool, user: bool, headers: &[(&str, &str)]) -> (Extensions, HeaderMap) {
+        let mut extensions = Extensions::new();
+        if correlation {
+            extensions.insert(CorrelationId("req-1".into()));
+        }
+        if user {
+            extensions.insert(AuthUser {
+                user_id: Uuid::new_v4(),
+                email: "farmer@example.com".into(),
+                roles: vec!["farmer".into()],
+            });
+        }
+        let mut map = HeaderMap::new();
+        for (name, value) in headers {
+            map.insert(
+                axum::http::HeaderName::try_from(*name).unwrap(),
+                value.parse().unwrap(),
+            );
+        }
+        (extensions, map)
+    }
+
+    #[test]
+    fn a_fully_populated_request_resolves_every_field() {
+        let (extensions, headers) = parts(
+            true,
+            true,
+            &[("accept-language", "en-US,th;q=0.8"), ("x-client-version", "1.4.2")],
+        );
+        let ctx = context_from_parts(&extensions, &headers, TIMEOUT).unwrap();
+        assert_eq!(ctx.correlation_id, "req-1");
+        assert!(ctx.require_user().is_ok());
+        assert_eq!(ctx.language, Language::English);
+        assert_eq!(ctx.client_version.as_deref(), Some("1.4.2"));
+        assert!(ctx.time_remaining() <= TIMEOUT);
+    }
+
+    #[test]
+    fn missing_correlation_layer_is_a_loud_internal_error() {
+        let (extensions, headers) = parts(false, true, &[]);
+        let error = context_from_parts(&extensions, &headers, TIMEOUT).unwrap_err();
+        match error {
+            AppError::Internal(message) => {
+                assert!(message.contains("correlation middleware"), "got: {message}")
+            }
+            other => panic!("expected Internal, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn public_routes_have_no_user_and_require_user_rejects() {
+        let (extensions, headers) = parts(true, false, &[]);
+        let ctx = context_from_parts(&extensions, &headers, TIMEOUT).unwrap();
+        assert!(ctx.user.is_none());
+        assert!(matches!(ctx.require_user(), Err(AppError::Auth(_))));
+    }
+
+    #[test]
+    fn language_negotiation_prefers_the_first_recognized_tag() {
+        assert_eq!(resolve_language(None), Language::Thai);
+        assert_eq!(resolve_language(Some("th-TH,en;q=0.5")), Language::Thai);
+        assert_eq!(resolve_language(Some("en-GB")), Language::English);
+        assert_eq!(resolve_language(Some("fr-FR,de")), Language::Thai);
+        assert_eq!(resolve_language(Some("fr,en;q=0.3")), Language::English);
+    }
+}
@@ -0,0 +1,170 @@
+//! Short-TTL Redis cache for hammered idempotent GET endpoints.
+//!
+//! Polling clients ask for job status and conversation history far more
+//! often than either changes; this opt-in layer answers repeats straight
+//! from Redis. Entries are keyed by path + normalized query + the
+//! authenticated user (responses are per-user, so the key must be too)
+//! and only plain 200s are ever stored — errors and 304s stay fresh.
+//! Cache answers carry `X-Cache: HIT`, pass-throughs `X-Cache: MISS`.
+//!
+//! TTLs are per route and deliberately short: job status is polled every
+//! two seconds and transitions outside the gateway (the worker marks
+//! processing/completed), so its TTL is the staleness bound. Transitions
+//! the gateway itself performs — cancelling a job, completing advice,
+//! posting a chat message — call [`invalidate`] so their next read is
+//! exact rather than TTL-eventually.
+
+use std::time::Duration;
+
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::{header, Method, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+use redis::AsyncCommands;
+use uuid::Uuid;
+
+use crate::{state::AppState, AuthUser};
+
+/// Header naming whether the response came from the cache.
+pub const CACHE_HEADER: &str = "x-cache";
+
+const KEY_PREFIX: &str = "resp_cache";
+
+/// Per-route wiring for [`response_cache_middleware`]: the shared state
+/// plus how long this route's 200s may be replayed.
+#[derive(Clone)]
+pub struct RouteCache {
+    pub state: AppState,
+    pub ttl: Duration,
+}
+
+/// Sort the query parameters so equivalent requests share one entry
+/// (`?a=1&b=2` and `?b=2&a=1` are the same question).
+pub fn normalized_query(query: Option<&str>) -> String {
+    let mut params: Vec<&str> = query
+        .unwrap_or_default()
+        .split('&')
+        .filter(|p| !p.is_empty())
+        .collect();
+    params.sort_unstable();
+    params.join("&")
+}
+
+/// Cache key for one request. Unauthenticated requests (public routes)
+/// share an `anon` bucket.
+pub fn cache_key(user_id: Option<Uuid>, path: &str, query: Option<&str>) -> String {
+    let user = user_id.map_or_else(|| "anon".to_string(), |u| u.to_string());
+    format!("{KEY_PREFIX}:{user}:{path}?{}", normalized_query(query))
+}
+
+pub async fn response_cache_middleware(
+    State(route): State<RouteCache>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if request.method() != Method::GET {
+        return next.run(request).await;
+    }
+    let user_id = request.extensions().get::<AuthUser>().map(|u| u.user_id);
+    let key = cache_key(user_id, request.uri().path(), request.uri().query());
+
+    // Redis being down just means every request is a miss.
+    if let Ok(mut redis) = route.state.get_redis().await {
+        let cached: Option<String> = redis.get(&key).await.ok().flatten();
+        if let Some(body) = cached {
+            crate::metrics::record_cache_access("response_cache", true);
+            return Response::builder()
+                .status(StatusCode::OK)
+                .header(header::CONTENT_TYPE, "application/json")
+                .header(CACHE_HEADER, "HIT")
+                .body(Body::from(body))
+                .expect("static response parts are valid");
+        }
+    }
+    crate::metrics::record_cache_access("response_cache", false);
+
+    let response = next.run(request).await;
+    if response.status() != StatusCode::OK {
+        return response;
+    }
+    let (mut parts, body) = response.into_parts();
+    let Ok(bytes) = axum::body::to_bytes(body, usize::MAX).await else {
+        return Response::from_parts(parts, Body::empty());
+    };
+    if let Ok(mut redis) = route.state.get_redis().await {
+        let _: Result<(), _> = redis
+            .set_ex(&key, bytes.as_ref(), route.ttl.as_secs().max(1))
+            .await;
+    }
+    parts.headers.insert(
+        CACHE_HEADER,
+        "MISS".parse().expect("static header value is valid"),
+    );
+    Response::from_parts(parts, Body::from(bytes))
+}
+
+/// Drop cached entries for `path` — one user's when `user_id` is given,
+/// every user's otherwise (a job transition is visible to whoever may
+/// read it). Best-effort: a failure only means the entry lives out its
+/// short TTL.
+pub async fn invalidate(
+    redis: &mut redis::aio::MultiplexedConnection,
+    user_id: Option<Uuid>,
+    path: &str,
+) {
+    let user = user_id.map_or_else(|| "*".to_string(), |u| u.to_string());
+    let pattern = format!("{KEY_PREFIX}:{user}:{path}?*");
+    let mut keys = Vec::new();
+    {
+        let mut iter = match redis.scan_match::<_, String>(&pattern).await {
+            Ok(iter) => iter,
+            Err(e) => {
+                tracing::debug!(error = %e, pattern, "response cache scan failed");
+                return;
+            }
+        };
+        while let Some(key) = iter.next_item().await {
+            keys.push(key);
+        }
+    }
+    if !keys.is_empty() {
+        let _: Result<(), _> = redis.del(keys).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn equivalent_queries_normalize_to_one_key() {
+        assert_eq!(normalized_query(Some("b=2&a=1")), "a=1&b=2");
+        assert_eq!(normalized_query(Some("a=1&b=2")), "a=1&b=2");
+        assert_eq!(normalized_query(None), "");
+        assert_eq!(normalized_query(Some("")), "");
+    }
+
+    #[test]
+    fn keys_separate_users_and_queries() {
+        let a = Uuid::new_v4();
+        let b = Uuid::new_v4();
+        let path = "/api/v1/chat/history";
+        assert_ne!(
+            cache_key(Some(a), path, Some("page=1")),
+            cache_key(Some(b), path, Some("page=1")),
+            "one user's history must never answer another's"
+        );
+        assert_ne!(
+            cache_key(Some(a), path, Some("page=1")),
+            cache_key(Some(a), path, Some("page=2")),
+        );
+        assert_eq!(
+            cache_key(Some(a), path, Some("page=1&limit=20")),
+            cache_key(Some(a), path, Some("limit=20&page=1")),
+        );
+        assert!(cache_key(None, path, None).contains(":anon:"));
+    }
+}
@@ -0,0 +1,339 @@
+//! Request shadowing to a staging deployment.
+//!
+//! Before a contract cutover we mirror a configurable sample of production
+//! requests to staging and record how the two responses compare. The copy
+//! is sent from a spawned task *after* the production response is produced,
+//! so a slow or dead staging environment can never add latency or errors to
+//! the real request. Auth headers and cookies are stripped from the copy,
+//! and bodies are only forwarded for routes that opt in via `include_body`.
+
+use std::{
+    collections::VecDeque,
+    sync::Mutex,
+    time::{Duration, Instant, SystemTime},
+};
+
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::{header, HeaderMap, Method},
+    middleware::Next,
+    response::Response,
+};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::config::ShadowRoute;
+use crate::state::AppState;
+
+/// Comparison rows kept in memory; old rows roll off. Enough to summarize a
+/// cutover rehearsal without growing without bound.
+const LOG_CAPACITY: usize = 2048;
+
+/// Bodies larger than this are not mirrored even for `include_body` routes;
+/// buffering a full-size image upload twice isn't worth the comparison.
+const MAX_SHADOW_BODY: usize = 1024 * 1024;
+
+/// How long the shadow copy may take before it is recorded as failed.
+const SHADOW_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// One mirrored request and how staging answered it.
+#[derive(Debug, Clone, Serialize)]
+pub struct ShadowComparison {
+    pub path: String,
+    pub prod_status: u16,
+    /// `None` when the shadow request failed outright (connect error or
+    /// timeout) — itself a signal worth counting.
+    pub shadow_status: Option<u16>,
+    /// Shadow latency minus production latency, in milliseconds. Negative
+    /// means staging was faster.
+    pub latency_delta_ms: i64,
+    pub recorded_at: DateTime<Utc>,
+}
+
+impl ShadowComparison {
+    /// A row counts as a mismatch when staging answered with a different
+    /// status or not at all.
+    pub fn is_mismatch(&self) -> bool {
+        self.shadow_status != Some(self.prod_status)
+    }
+}
+
+/// Everything a mirrored copy needs, captured before the original request
+/// is consumed by the handler.
+struct ShadowSample {
+    method: Method,
+    target_url: String,
+    headers: HeaderMap,
+    body: Option<Vec<u8>>,
+}
+
+/// Owns the shadow routes, the HTTP client the copies are sent with, and
+/// the rolling comparison log the admin summary reads.
+pub struct ShadowMirror {
+    routes: Vec<ShadowRoute>,
+    http: reqwest::Client,
+    log: Mutex<VecDeque<ShadowComparison>>,
+}
+
+impl ShadowMirror {
+    pub fn new(routes: Vec<ShadowRoute>) -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(SHADOW_TIMEOUT)
+            .build()
+            .expect("shadow http client");
+        Self {
+            routes,
+            http,
+            log: Mutex::new(VecDeque::with_capacity(LOG_CAPACITY)),
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        !self.routes.is_empty()
+    }
+
+    fn matching_route(&self, path: &str) -> Option<&ShadowRoute> {
+        self.routes
+            .iter()
+            .find(|route| path.starts_with(&route.path_prefix))
+    }
+
+    /// All recorded comparisons, oldest first.
+    pub fn snapshot(&self) -> Vec<ShadowComparison> {
+        self.log
+            .lock()
+            .expect("shadow log poisoned")
+            .iter()
+            .cloned()
+            .collect()
+    }
+
+    fn record(&self, comparison: ShadowComparison) {
+        let mut log = self.log.lock().expect("shadow log poisoned");
+        if log.len() == LOG_CAPACITY {
+            log.pop_front();
+        }
+        log.push_back(comparison);
+    }
+
+    /// Send one shadow copy and record the comparison. Failures are
+    /// recorded, never propagated — by the time this runs the production
+    /// response has already been returned.
+    async fn mirror(&self, sample: ShadowSample, path: String, prod_status: u16, prod_latency: Duration) {
+        let started = Instant::now();
+        let mut request = self
+            .http
+            .request(sample.method, &sample.target_url)
+            .headers(sample.headers);
+        if let Some(body) = sample.body {
+            request = request.body(body);
+        }
+        let shadow_status = match request.send().await {
+            Ok(response) => Some(response.status().as_u16()),
+            Err(error) => {
+                tracing::debug!(%error, url = %sample.target_url, "shadow request failed");
+                None
+            }
+        };
+        let latency_delta_ms =
+            started.elapsed().as_millis() as i64 - prod_latency.as_millis() as i64;
+        self.record(ShadowComparison {
+            path,
+            prod_status,
+            shadow_status,
+            latency_delta_ms,
+            recorded_at: Utc::now(),
+        });
+    }
+}
+
+/// True when a request with this roll falls inside the sample. The roll is
+/// a uniform value in `[0, 100)`; keeping the comparison pure makes the
+/// sampling rate testable.
+pub fn should_sample(sample_percent: f64, roll: f64) -> bool {
+    roll < sample_percent.clamp(0.0, 100.0)
+}
+
+/// Uniform-ish roll in `[0, 100)` from the clock's sub-second noise; the
+/// same cheap source the retry jitter uses, and plenty for traffic sampling.
+fn sample_roll() -> f64 {
+    let nanos = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.subsec_nanos())
+        .unwrap_or(0);
+    f64::from(nanos % 100_000) / 1000.0
+}
+
+/// Copy of the request headers safe to send to staging: credentials and
+/// cookies never leave production.
+pub fn sanitized_headers(headers: &HeaderMap) -> HeaderMap {
+    let mut copy = headers.clone();
+    copy.remove(header::AUTHORIZATION);
+    copy.remove(header::COOKIE);
+    copy.remove(header::HOST);
+    copy
+}
+
+pub async fn shadow_middleware(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let path = request.uri().path().to_string();
+    let Some(route) = state.shadow.matching_route(&path) else {
+        return next.run(request).await;
+    };
+    if !should_sample(route.sample_percent, sample_roll()) {
+        return next.run(request).await;
+    }
+
+    let path_and_query = request
+        .uri()
+        .path_and_query()
+        .map(|pq| pq.as_str().to_string())
+        .unwrap_or_else(|| path.clone());
+    let mut sample = ShadowSample {
+        method: request.method().clone(),
+        target_url: format!(
+            "{}{}",
+            route.target_base_url.trim_end_matches('/'),
+            path_and_query
+        ),
+        headers: sanitized_headers(request.headers()),
+        body: None,
+    };
+
+    // Only `include_body` routes pay for buffering, and only when the
+    // declared length fits the cap — once a body stream is partially read
+    // it can't be handed back to the handler, so oversized or unsized
+    // bodies are mirrored as metadata only.
+    let declared_length = request
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<usize>().ok());
+    let request = match (route.include_body, declared_length) {
+        (true, Some(length)) if length <= MAX_SHADOW_BODY => {
+            let (parts, body) = request.into_parts();
+            match axum::body::to_bytes(body, MAX_SHADOW_BODY).await {
+                Ok(bytes) => {
+                    sample.body = Some(bytes.to_vec());
+                    Request::from_parts(parts, Body::from(bytes))
+                }
+                Err(error) => {
+                    // Body shorter than its Content-Length or the client
+                    // hung up; the handler will reject it anyway.
+                    tracing::debug!(%error, "request body unreadable, skipping shadow");
+                    return next.run(Request::from_parts(parts, Body::empty())).await;
+                }
+            }
+        }
+        _ => request,
+    };
+
+    let started = Instant::now();
+    let response = next.run(request).await;
+    let prod_latency = started.elapsed();
+    let prod_status = response.status().as_u16();
+
+    let mirror = state.shadow.clone();
+    tokio::spawn(async move {
+        mirror.mirror(sample, path, prod_status, prod_latency).await;
+    });
+
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn route(prefix: &str, percent: f64) -> ShadowRoute {
+        ShadowRoute {
+            path_prefix: prefix.into(),
+            target_base_url: "http://staging.invalid".into(),
+            sample_percent: percent,
+            include_body: false,
+        }
+    }
+
+    #[test]
+    fn sampling_rate_matches_the_configured_percentage() {
+        // Sweep the whole roll space: exactly `percent` of a uniform
+        // distribution must fall inside the sample.
+        for percent in [0.0, 10.0, 50.0, 100.0] {
+            let sampled = (0..10_000)
+                .filter(|i| should_sample(percent, f64::from(*i) / 100.0))
+                .count();
+            assert_eq!(sampled as f64 / 100.0, percent, "at {percent}%");
+        }
+        // Out-of-range configs clamp instead of over- or under-sampling.
+        assert!(should_sample(150.0, 99.9));
+        assert!(!should_sample(-5.0, 0.0));
+    }
+
+    #[test]
+    fn credentials_are_stripped_from_the_copy() {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::AUTHORIZATION, "Bearer secret".parse().unwrap());
+        headers.insert(header::COOKIE, "session=1".parse().unwrap());
+        headers.insert(header::CONTENT_TYPE, "application/json".parse().unwrap());
+        let copy = sanitized_headers(&headers);
+        assert!(!copy.contains_key(header::AUTHORIZATION));
+        assert!(!copy.contains_key(header::COOKIE));
+        assert_eq!(copy[header::CONTENT_TYPE], "application/json");
+    }
+
+    #[test]
+    fn longest_is_not_required_first_match_wins() {
+        let mirror = ShadowMirror::new(vec![route("/api/v1/vision", 100.0), route("/api", 100.0)]);
+        let matched = mirror.matching_route("/api/v1/vision/analyze").unwrap();
+        assert_eq!(matched.path_prefix, "/api/v1/vision");
+        assert!(mirror.matching_route("/health").is_none());
+    }
+
+    #[tokio::test]
+    async fn shadow_failure_is_recorded_not_propagated() {
+        // `.invalid` is reserved and never resolves, so the mirror call
+        // fails at connect time. It must return normally and leave a row
+        // with no shadow status — the production response already went out.
+        let mirror = ShadowMirror::new(vec![route("/api/v1/vision", 100.0)]);
+        mirror
+            .mirror(
+                ShadowSample {
+                    method: Method::POST,
+                    target_url: "http://staging.invalid/api/v1/vision/analyze".into(),
+                    headers: HeaderMap::new(),
+                    body: None,
+                },
+                "/api/v1/vision/analyze".into(),
+                200,
+                Duration::from_millis(40),
+            )
+            .await;
+        let rows = mirror.snapshot();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].prod_status, 200);
+        assert_eq!(rows[0].shadow_status, None);
+        assert!(rows[0].is_mismatch());
+    }
+
+    #[test]
+    fn the_log_is_bounded() {
+        let mirror = ShadowMirror::new(vec![]);
+        for i in 0..(LOG_CAPACITY + 10) {
+            mirror.record(ShadowComparison {
+                path: "/p".into(),
+                prod_status: 200,
+                shadow_status: Some(200),
+                latency_delta_ms: i as i64,
+                recorded_at: Utc::now(),
+            });
+        }
+        let rows = mirror.snapshot();
+        assert_eq!(rows.len(), LOG_CAPACITY);
+        // The oldest rows rolled off the front.
+        assert_eq!(rows[0].latency_delta_ms, 10);
+    }
+}
@@ -0,0 +1,773 @@
+//! Second-stage advice generation for vision jobs.
+//!
+//! The worker stops at `diagnosed`: detections are in Redis and the client
+//! already has them. This module runs the LLM stage afterwards — build a
+//! prompt from the detections, call the LLM service, merge the advice into
+//! the stored result, and flip the job to `completed` with a status
+//! publish so WS/SSE subscribers see it land. A failed LLM call leaves the
+//! job in `diagnosed` with the error recorded, and
+//! `POST /api/v1/vision/jobs/:id/advice/retry` re-runs just this stage.
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use redis::AsyncCommands;
+use shared::models::{JobStatus, Language, LLMResponse, VisionResponse};
+use uuid::Uuid;
+
+use crate::{
+    errors::{AppError, AppResult},
+    state::AppState,
+};
+
+/// How long one stage run may hold the per-job lock before a retry can
+/// claim it; covers the LLM timeout plus its retry budget.
+const STAGE_LOCK_TTL_SECS: u64 = 120;
+
+fn result_key(job_id: Uuid) -> String {
+    format!("job:{job_id}:result")
+}
+
+fn error_key(job_id: Uuid) -> String {
+    format!("job:{job_id}:advice_error")
+}
+
+fn lock_key(job_id: Uuid) -> String {
+    format!("job:{job_id}:advice_lock")
+}
+
+/// Build the LLM prompt from a vision result, folding in the paired
+/// sensor's latest reading as growing conditions when one exists. Kept
+/// pure so prompt shape is testable without an LLM in the loop.
+pub fn advice_prompt(
+    result: &VisionResponse,
+    environment: Option<&crate::handlers::sensors::ReadingSample>,
+) -> String {
+    let mut prompt = if result.detections.is_empty() {
+        format!(
+            "A {} plant photo was analyzed and no disease was detected.\n",
+            result.crop_type.as_str()
+        )
+    } else {
+        let mut prompt = format!(
+            "A {} plant photo was analyzed with these detections:\n",
+            result.crop_type.as_str()
+        );
+        for detection in &result.detections {
+            prompt.push_str(&format!(
+                "- {} (confidence {:.0}%)\n",
+                detection.disease_name,
+                detection.confidence * 100.0
+            ));
+        }
+        if let Some(severity) = result.severity_score {
+            prompt.push_str(&format!("Overall severity score: {severity:.2}\n"));
+        }
+        prompt
+    };
+    if let Some(env) = environment {
+        prompt.push_str(&format!(
+            "Field conditions from the farmer's sensor: temperature {:.1}C, \
+             soil moisture {:.0}%, humidity {:.0}%.\n",
+            env.temperature_c, env.soil_moisture_pct, env.humidity_pct
+        ));
+    }
+    if result.detections.is_empty() {
+        prompt.push_str("Give brief preventative care advice for this crop.");
+    } else {
+        prompt.push_str("Give practical treatment advice for a farmer.");
+    }
+    prompt
+}
+
+/// Top detection confidence, `None` when nothing was detected.
+fn top_confidence(result: &VisionResponse) -> Option<f32> {
+    result
+        .detections
+        .iter()
+        .map(|d| d.confidence)
+        .fold(None, |best, c| Some(best.map_or(c, |b: f32| b.max(c))))
+}
+
+/// Rewrite the stored result as an uncertain outcome: flip
+/// `diagnosis_status`, attach the top-3 candidate diseases, and add retake
+/// guidance in the requested language. Done at the JSON level for the same
+/// reason as [`merge_advice`].
+pub fn merge_uncertain(
+    result_json: &str,
+    result: &VisionResponse,
+    language: Language,
+) -> AppResult<String> {
+    let mut value: serde_json::Value = serde_json::from_str(result_json)
+        .map_err(|e| AppError::Internal(format!("stored vision result: {e}")))?;
+    let mut candidates: Vec<_> = result.detections.iter().collect();
+    candidates.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+    candidates.truncate(3);
+    value["diagnosis_status"] = "uncertain".into();
+    value["candidates"] = serde_json::to_value(&candidates)
+        .map_err(|e| AppError::Internal(format!("serialize candidates: {e}")))?;
+    value["guidance"] = shared::confidence::guidance(
+        shared::confidence::ConfidenceBand::Low,
+        language,
+    )
+    .into();
+    Ok(value.to_string())
+}
+
+/// Merge generated advice into the stored result JSON as an `advice` field.
+/// Done at the JSON level so `VisionResponse` keeps its schema — readers
+/// that only want detections deserialize the same as before.
+pub fn merge_advice(result_json: &str, advice: &LLMResponse) -> AppResult<String> {
+    let mut value: serde_json::Value = serde_json::from_str(result_json)
+        .map_err(|e| AppError::Internal(format!("stored vision result: {e}")))?;
+    let advice = serde_json::to_value(advice)
+        .map_err(|e| AppError::Internal(format!("serialize advice: {e}")))?;
+    value["advice"] = advice;
+    Ok(value.to_string())
+}
+
+/// Storage half of the stage, behind a trait so the progression can be
+/// tested against an in-memory double (same pattern as `cache::CacheStore`).
+#[async_trait]
+pub(crate) trait AdviceStore {
+    /// Claim the per-job lock; `false` means another run is in flight.
+    async fn try_lock(&mut self, job_id: Uuid) -> bool;
+    async fn unlock(&mut self, job_id: Uuid);
+    async fn vision_result(&mut self, job_id: Uuid) -> Option<String>;
+    /// Persist the merged result and flip the job to `completed`,
+    /// publishing the transition for WS/SSE subscribers.
+    async fn complete(&mut self, job_id: Uuid, merged_result: &str);
+    /// Record a failed stage; the job stays `diagnosed`.
+    async fn record_error(&mut self, job_id: Uuid, reason: &str);
+}
+
+#[async_trait]
+impl AdviceStore for redis::aio::MultiplexedConnection {
+    async fn try_lock(&mut self, job_id: Uuid) -> bool {
+        let claimed: Result<bool, _> = redis::cmd("SET")
+            .arg(lock_key(job_id))
+            .arg("1")
+            .arg("NX")
+            .arg("EX")
+            .arg(STAGE_LOCK_TTL_SECS)
+            .query_async(self)
+            .await;
+        claimed.unwrap_or(false)
+    }
+
+    async fn unlock(&mut self, job_id: Uuid) {
+        let _: Result<(), _> = self.del(lock_key(job_id)).await;
+    }
+
+    async fn vision_result(&mut self, job_id: Uuid) -> Option<String> {
+        self.get(result_key(job_id)).await.ok().flatten()
+    }
+
+    async fn complete(&mut self, job_id: Uuid, merged_result: &str) {
+        let _: Result<(), _> = self.set(result_key(job_id), merged_result).await;
+        let _: Result<(), _> = self.set(format!("job:{job_id}:status"), "completed").await;
+        let _: Result<(), _> = self.del(error_key(job_id)).await;
+        let payload = serde_json::json!({ "status": "completed" }).to_string();
+        let _: Result<(), _> = self.publish(format!("job_status:{job_id}"), payload).await;
+        crate::middleware::response_cache::invalidate(
+            self,
+            None,
+            &format!("/api/v1/vision/jobs/{job_id}"),
+        )
+        .await;
+        let _: Result<(), _> = self
+            .zrem(crate::metrics::PENDING_JOBS_KEY, job_id.to_string())
+            .await;
+    }
+
+    async fn record_error(&mut self, job_id: Uuid, reason: &str) {
+        let _: Result<(), _> = self.set(error_key(job_id), reason).await;
+    }
+}
+
+/// LLM half of the stage; implemented by the registry's client, mocked in
+/// tests.
+#[async_trait]
+pub(crate) trait AdviceLlm: Send + Sync {
+    async fn generate(&self, prompt: &str, language: Language) -> AppResult<LLMResponse>;
+}
+
+#[async_trait]
+impl AdviceLlm for crate::services::registry::LLMClient {
+    async fn generate(&self, prompt: &str, language: Language) -> AppResult<LLMResponse> {
+        self.completion(prompt, language).await
+    }
+}
+
+/// Run the advice stage once. Returns the status the job ends up in:
+/// `Completed` on success, `Diagnosed` when another run holds the lock,
+/// an error when the vision result is missing or the LLM call failed.
+pub(crate) async fn run_stage_with(
+    store: &mut dyn AdviceStore,
+    llm: &dyn AdviceLlm,
+    job_id: Uuid,
+    language: Language,
+    environment: Option<&crate::handlers::sensors::ReadingSample>,
+    confidence_threshold: f32,
+) -> AppResult<JobStatus> {
+    if !store.try_lock(job_id).await {
+        // A concurrent run owns the stage; its outcome will be published.
+        return Ok(JobStatus::Diagnosed);
+    }
+    let result = async {
+        let raw = store
+            .vision_result(job_id)
+            .await
+            .ok_or_else(|| AppError::NotFound(format!("job {job_id} has no vision result")))?;
+        let result: VisionResponse = serde_json::from_str(&raw)
+            .map_err(|e| AppError::Internal(format!("stored vision result: {e}")))?;
+        // Below the threshold the "diagnosis" is a guess: present the
+        // candidates with retake guidance instead, and spend no LLM call
+        // advising treatment for a disease the plant may not have.
+        if top_confidence(&result).is_some_and(|top| top < confidence_threshold) {
+            let merged = merge_uncertain(&raw, &result, language)?;
+            store.complete(job_id, &merged);
+            return Ok(JobStatus::Completed);
+        }
+        let advice = llm.generate(&advice_prompt(&result, environment), language).await?;
+        let merged = merge_advice(&raw, &advice)?;
+        store.complete(job_id, &merged).await;
+        Ok(JobStatus::Completed)
+    }
+    .await;
+    if let Err(error) = &result {
+        store.record_error(job_id, &error.to_string()).await;
+    }
+    store.unlock(job_id).await;
+    result
+}
+
+/// Run the advice stage for one job and reflect the transition in
+/// Postgres. The submission pinned the language it wants advice in; jobs
+/// without a pin (older jobs, the upload and batch paths) get the Thai
+/// default.
+pub async fn run_stage(state: &AppState, job_id: Uuid) -> AppResult<JobStatus> {
+    let mut redis = state.get_redis().await?;
+    // A cancel can land between the diagnosed publish and this stage
+    // starting; generating advice for a cancelled job would resurrect it
+    // as completed.
+    let cancelled: Option<String> = redis
+        .get(format!("job:{job_id}:cancelled"))
+        .await
+        .ok()
+        .flatten();
+    if cancelled.is_some() {
+        return Ok(JobStatus::Cancelled);
+    }
+    // Jobs submitted with a paired sensor get its latest reading folded
+    // into the prompt as growing conditions.
+    let device_id: Option<String> = redis
+        .get(format!("job:{job_id}:device_id"))
+        .await
+        .ok()
+        .flatten();
+    let environment = match device_id {
+        Some(device_id) => {
+            crate::handlers::sensors::latest_for_device(&state.db, &device_id).await
+        }
+        None => None,
+    };
+    let language: Option<String> = redis
+        .get(format!("job:{job_id}:language"))
+        .await
+        .ok()
+        .flatten();
+    let language = match language.as_deref() {
+        Some("english") => Language::English,
+        _ => Language::default(),
+    };
+    let status = run_stage_with(
+        &mut redis,
+        &state.services.llm,
+        job_id,
+        language,
+        environment.as_ref(),
+        state.config.diagnosis.confidence_threshold,
+    )
+    .await?;
+    if status == JobStatus::Completed {
+        let _ = sqlx::query("UPDATE vision_jobs SET status = 'completed' WHERE id = $1")
+            .bind(job_id)
+            .execute(&state.db)
+            .await;
+        let raw: Option<String> = redis.get(result_key(job_id)).await.ok().flatten();
+        let merged = raw
+            .and_then(|r| serde_json::from_str(&r).ok())
+            .unwrap_or(serde_json::Value::Null);
+        record_diagnosis(state, job_id, &merged).await;
+        record_audit(state, &mut redis, job_id, &merged).await;
+        notify_completed(state, job_id, merged).await;
+    }
+    Ok(status)
+}
+
+/// Persist the searchable summary row for the finished diagnosis (the
+/// `diagnoses` table feeds `GET /api/v1/diagnoses/search`). Best effort:
+/// the client already has its result, and anonymous jobs (no user) have no
+/// history to search.
+async fn record_diagnosis(state: &AppState, job_id: Uuid, merged: &serde_json::Value) {
+    let Ok(result) = serde_json::from_value::<VisionResponse>(merged.clone()) else {
+        return;
+    };
+    let user_id: Option<Option<Uuid>> =
+        sqlx::query_scalar("SELECT user_id FROM vision_jobs WHERE id = $1")
+            .bind(job_id)
+            .fetch_optional(&state.db)
+            .await
+            .unwrap_or_default();
+    let Some(Some(user_id)) = user_id else {
+        return;
+    };
+    let disease_name = if result.detections.is_empty() {
+        "healthy".to_string()
+    } else {
+        result
+            .detections
+            .iter()
+            .map(|d| d.disease_name.as_str())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let advice = merged["advice"]["advice"].as_str().unwrap_or_default();
+    let outcome = sqlx::query(
+        "INSERT INTO diagnoses (id, job_id, user_id, crop_type, disease_name, advice, severity_score) \
+         VALUES ($1, $2, $3, $4, $5, $6, $7) \
+         ON CONFLICT (job_id) DO NOTHING",
+    )
+    .bind(Uuid::new_v4())
+    .bind(job_id)
+    .bind(user_id)
+    .bind(result.crop_type.as_str())
+    .bind(&disease_name)
+    .bind(advice)
+    .bind(result.severity_score)
+    .execute(&state.db)
+    .await;
+    if let Err(error) = outcome {
+        tracing::warn!(%job_id, %error, "diagnosis summary insert failed");
+    }
+    // The dashboard aggregates this table; make the new row visible before
+    // its cache TTL would have expired.
+    crate::handlers::dashboard::invalidate_trends(state, user_id).await;
+}
+
+/// Write the `analysis_audit` row for a finished diagnosis (fed to
+/// `GET /api/v1/vision/history`). Unlike `diagnoses` this keeps anonymous
+/// jobs too — officers reviewing what was issued care about every
+/// diagnosis, not just the attributable ones. Best effort, same as the
+/// summary row.
+async fn record_audit(
+    state: &AppState,
+    redis: &mut redis::aio::MultiplexedConnection,
+    job_id: Uuid,
+    merged: &serde_json::Value,
+) {
+    let Ok(result) = serde_json::from_value::<VisionResponse>(merged.clone()) else {
+        return;
+    };
+    let job: Option<(Option<Uuid>, chrono::DateTime<chrono::Utc>)> =
+        sqlx::query_as("SELECT user_id, created_at FROM vision_jobs WHERE id = $1")
+            .bind(job_id)
+            .fetch_optional(&state.db)
+            .await
+            .unwrap_or_default();
+    let (user_id, requested_at) = job.map_or((None, None), |(u, at)| (u, Some(at)));
+    let request_id: Option<String> = redis
+        .get_del(format!("job:{job_id}:request_id"))
+        .await
+        .ok()
+        .flatten();
+    let disease_name = if result.detections.is_empty() {
+        "healthy".to_string()
+    } else {
+        result
+            .detections
+            .iter()
+            .map(|d| d.disease_name.as_str())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let confidence = result
+        .detections
+        .iter()
+        .map(|d| d.confidence)
+        .fold(None::<f32>, |best, c| Some(best.map_or(c, |b| b.max(c))));
+    let outcome = sqlx::query(
+        "INSERT INTO analysis_audit \
+         (id, job_id, user_id, request_id, crop_type, disease_name, confidence, severity_score, requested_at) \
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9) \
+         ON CONFLICT (job_id) DO NOTHING",
+    )
+    .bind(Uuid::new_v4())
+    .bind(job_id)
+    .bind(user_id)
+    .bind(&request_id)
+    .bind(result.crop_type.as_str())
+    .bind(&disease_name)
+    .bind(confidence)
+    .bind(result.severity_score)
+    .bind(requested_at)
+    .execute(&state.db)
+    .await;
+    if let Err(error) = outcome {
+        tracing::warn!(%job_id, %error, "analysis audit insert failed");
+    }
+}
+
+/// Tell webhook subscribers the job completed, with the merged result as
+/// the payload. Best effort: a broker problem must not fail the stage that
+/// already succeeded.
+async fn notify_completed(state: &AppState, job_id: Uuid, result: serde_json::Value) {
+    let event = crate::services::webhooks::WebhookEvent::completed(job_id, result);
+    if let Err(error) = state.rabbitmq.publish_webhook_event(&event).await {
+        tracing::warn!(%job_id, %error, "webhook event publish failed");
+    }
+}
+
+/// Kick the stage in the background; used where the caller must not wait
+/// (status polls, the pubsub listener). Failures are logged — the job
+/// stays `diagnosed` and retryable.
+pub fn spawn_stage(state: AppState, job_id: Uuid) {
+    tokio::spawn(async move {
+        if let Err(error) = run_stage(&state, job_id).await {
+            tracing::warn!(%job_id, %error, "advice stage failed; job stays diagnosed");
+        }
+    });
+}
+
+/// Background listener: subscribes to the worker's `job_status:*` channels
+/// and starts the advice stage whenever a job reports `diagnosed`. Runs
+/// for the life of the process, reconnecting on pubsub loss.
+pub fn spawn(state: AppState) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            let pubsub = match state.redis_client.get_async_pubsub().await {
+                Ok(pubsub) => pubsub,
+                Err(e) => {
+                    tracing::warn!(error = %e, "advice listener: redis unavailable, retrying");
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                    continue;
+                }
+            };
+            let mut pubsub = pubsub;
+            if let Err(e) = pubsub.psubscribe("job_status:*").await {
+                tracing::warn!(error = %e, "advice listener: subscribe failed, retrying");
+                tokio::time::sleep(Duration::from_secs(5)).await;
+                continue;
+            }
+            let mut messages = pubsub.into_on_message();
+            while let Some(message) = futures_util::StreamExt::next(&mut messages).await {
+                let Ok(payload) = message.get_payload::<String>() else { continue };
+                if !payload_is_diagnosed(&payload) {
+                    continue;
+                }
+                if let Some(job_id) = job_id_from_channel(message.get_channel_name()) {
+                    spawn_stage(state.clone(), job_id);
+                }
+            }
+            tracing::warn!("advice listener: pubsub stream ended, reconnecting");
+        }
+    })
+}
+
+/// True when a published status payload reports the `diagnosed` state.
+fn payload_is_diagnosed(payload: &str) -> bool {
+    serde_json::from_str::<serde_json::Value>(payload)
+        .ok()
+        .and_then(|v| serde_json::from_value::<JobStatus>(v["status"].clone()).ok())
+        == Some(JobStatus::Diagnosed)
+}
+
+fn job_id_from_channel(channel: &str) -> Option<Uuid> {
+    channel.strip_prefix("job_status:")?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use chrono::Utc;
+    use shared::confidence::LOW_BELOW;
+    use shared::models::CropType;
+
+    use super::*;
+
+    #[derive(Default)]
+    struct MemoryStore {
+        results: HashMap<Uuid, String>,
+        statuses: HashMap<Uuid, String>,
+        errors: HashMap<Uuid, String>,
+        locked: bool,
+        published: Vec<String>,
+    }
+
+    #[async_trait]
+    impl AdviceStore for MemoryStore {
+        async fn try_lock(&mut self, _job_id: Uuid) -> bool {
+            !std::mem::replace(&mut self.locked, true)
+        }
+        async fn unlock(&mut self, _job_id: Uuid) {
+            self.locked = false;
+        }
+        async fn vision_result(&mut self, job_id: Uuid) -> Option<String> {
+            self.results.get(&job_id).cloned()
+        }
+        async fn complete(&mut self, job_id: Uuid, merged_result: &str) {
+            self.results.insert(job_id, merged_result.to_string());
+            self.statuses.insert(job_id, "completed".into());
+            self.errors.remove(&job_id);
+            self.published.push(r#"{"status": "completed"}"#.into());
+        }
+        async fn record_error(&mut self, job_id: Uuid, reason: &str) {
+            self.errors.insert(job_id, reason.to_string());
+        }
+    }
+
+    /// Fails the first `failures` calls, then answers.
+    struct FlakyLlm {
+        failures: std::sync::atomic::AtomicU32,
+    }
+
+    #[async_trait]
+    impl AdviceLlm for FlakyLlm {
+        async fn generate(&self, _prompt: &str, language: Language) -> AppResult<LLMResponse> {
+            if self.failures.fetch_update(
+                std::sync::atomic::Ordering::SeqCst,
+                std::sync::atomic::Ordering::SeqCst,
+                |n| n.checked_sub(1),
+            ).is_ok() {
+                return Err(AppError::ServiceUnavailable("llm down".into()));
+            }
+            Ok(LLMResponse {
+                advice: "ใช้เชื้อราไตรโคเดอร์มา".into(),
+                language,
+                model: "test-model".into(),
+                generated_at: Utc::now(),
+            })
+        }
+    }
+
+    fn store_with_detections(job_id: Uuid, confidences: &[(&str, f32)]) -> MemoryStore {
+        let result = VisionResponse {
+            job_id,
+            crop_type: CropType::Rice,
+            detections: confidences
+                .iter()
+                .map(|(name, confidence)| shared::models::DiseaseDetection {
+                    disease_name: name.to_string(),
+                    confidence: *confidence,
+                    bounding_box: None,
+                })
+                .collect(),
+            severity_score: Some(0.7),
+            model_version: "v1".into(),
+            processed_at: Utc::now(),
+            diagnosis_status: shared::models::DiagnosisStatus::Confirmed,
+        };
+        let mut store = MemoryStore::default();
+        store
+            .results
+            .insert(job_id, serde_json::to_string(&result).unwrap());
+        store.statuses.insert(job_id, "diagnosed".into());
+        store
+    }
+
+    fn diagnosed_store(job_id: Uuid) -> MemoryStore {
+        store_with_detections(job_id, &[("rice blast", 0.9)])
+    }
+
+    #[tokio::test]
+    async fn successful_stage_moves_diagnosed_to_completed_with_merged_advice() {
+        let job_id = Uuid::new_v4();
+        let mut store = diagnosed_store(job_id);
+        let llm = FlakyLlm { failures: 0.into() };
+
+        let status = run_stage_with(&mut store, &llm, job_id, Language::Thai, None, LOW_BELOW)
+            .await
+            .unwrap();
+
+        assert_eq!(status, JobStatus::Completed);
+        assert_eq!(store.statuses[&job_id], "completed");
+        assert_eq!(store.published, vec![r#"{"status": "completed"}"#]);
+        // The merged result still deserializes as a plain VisionResponse
+        // and carries the advice alongside.
+        let merged: serde_json::Value = serde_json::from_str(&store.results[&job_id]).unwrap();
+        assert_eq!(merged["advice"]["advice"], "ใช้เชื้อราไตรโคเดอร์มา");
+        assert!(serde_json::from_value::<VisionResponse>(merged.clone()).is_ok());
+        assert!(!store.locked, "lock must be released");
+    }
+
+    #[tokio::test]
+    async fn requested_language_reaches_the_llm_and_is_echoed_in_the_advice() {
+        let job_id = Uuid::new_v4();
+        let mut store = diagnosed_store(job_id);
+        let llm = FlakyLlm { failures: 0.into() };
+
+        run_stage_with(&mut store, &llm, job_id, Language::English, None, LOW_BELOW)
+            .await
+            .unwrap();
+
+        let merged: serde_json::Value = serde_json::from_str(&store.results[&job_id]).unwrap();
+        assert_eq!(merged["advice"]["language"], "english");
+    }
+
+    /// Reaching the LLM at all is the failure.
+    struct PanickyLlm;
+
+    #[async_trait]
+    impl AdviceLlm for PanickyLlm {
+        async fn generate(&self, _prompt: &str, _language: Language) -> AppResult<LLMResponse> {
+            panic!("the LLM must not be called for an uncertain result");
+        }
+    }
+
+    #[tokio::test]
+    async fn low_confidence_completes_as_uncertain_without_an_llm_call() {
+        let job_id = Uuid::new_v4();
+        let mut store = store_with_detections(
+            job_id,
+            &[("rice blast", 0.35), ("brown spot", 0.30), ("tungro", 0.20), ("smut", 0.10)],
+        );
+
+        let status =
+            run_stage_with(&mut store, &PanickyLlm, job_id, Language::Thai, None, LOW_BELOW)
+                .await
+                .unwrap();
+
+        assert_eq!(status, JobStatus::Completed);
+        let merged: serde_json::Value = serde_json::from_str(&store.results[&job_id]).unwrap();
+        assert_eq!(merged["diagnosis_status"], "uncertain");
+        assert!(merged.get("advice").is_none(), "no treatment advice for a guess");
+        // Top-3 candidates, best first.
+        let candidates = merged["candidates"].as_array().unwrap();
+        assert_eq!(candidates.len(), 3);
+        assert_eq!(candidates[0]["disease_name"], "rice blast");
+        assert_eq!(candidates[2]["disease_name"], "tungro");
+        assert_eq!(
+            merged["guidance"],
+            shared::confidence::guidance(
+                shared::confidence::ConfidenceBand::Low,
+                Language::Thai
+            )
+        );
+        assert!(!store.locked, "lock must be released");
+    }
+
+    #[tokio::test]
+    async fn threshold_is_exclusive_and_healthy_results_are_never_uncertain() {
+        // Exactly at the threshold still counts as confident.
+        let job_id = Uuid::new_v4();
+        let mut store = store_with_detections(job_id, &[("rice blast", LOW_BELOW)]);
+        let llm = FlakyLlm { failures: 0.into() };
+        run_stage_with(&mut store, &llm, job_id, Language::Thai, None, LOW_BELOW)
+            .await
+            .unwrap();
+        let merged: serde_json::Value = serde_json::from_str(&store.results[&job_id]).unwrap();
+        assert!(merged.get("candidates").is_none());
+        assert_eq!(merged["advice"]["advice"], "ใช้เชื้อราไตรโคเดอร์มา");
+
+        // No detections means nothing to be uncertain about: preventative
+        // advice is generated as before.
+        let job_id = Uuid::new_v4();
+        let mut store = store_with_detections(job_id, &[]);
+        run_stage_with(&mut store, &llm, job_id, Language::Thai, None, LOW_BELOW)
+            .await
+            .unwrap();
+        let merged: serde_json::Value = serde_json::from_str(&store.results[&job_id]).unwrap();
+        assert!(merged.get("advice").is_some());
+    }
+
+    #[tokio::test]
+    async fn failed_llm_leaves_the_job_diagnosed_and_a_retry_completes_it() {
+        let job_id = Uuid::new_v4();
+        let mut store = diagnosed_store(job_id);
+        let llm = FlakyLlm { failures: 1.into() };
+
+        let error = run_stage_with(&mut store, &llm, job_id, Language::Thai, None, LOW_BELOW)
+            .await
+            .unwrap_err();
+        assert!(matches!(error, AppError::ServiceUnavailable(_)));
+        assert_eq!(store.statuses[&job_id], "diagnosed");
+        assert!(store.errors.contains_key(&job_id));
+        assert!(store.published.is_empty(), "no transition to publish yet");
+        assert!(!store.locked, "a failed run must release the lock");
+
+        // Retry: the LLM recovered.
+        let status = run_stage_with(&mut store, &llm, job_id, Language::Thai, None, LOW_BELOW)
+            .await
+            .unwrap();
+        assert_eq!(status, JobStatus::Completed);
+        assert!(!store.errors.contains_key(&job_id));
+    }
+
+    #[tokio::test]
+    async fn concurrent_runs_yield_to_the_lock_holder() {
+        let job_id = Uuid::new_v4();
+        let mut store = diagnosed_store(job_id);
+        store.locked = true;
+        let llm = FlakyLlm { failures: 0.into() };
+
+        let status = run_stage_with(&mut store, &llm, job_id, Language::Thai, None, LOW_BELOW)
+            .await
+            .unwrap();
+        assert_eq!(status, JobStatus::Diagnosed);
+        assert_eq!(store.statuses[&job_id], "diagnosed");
+    }
+
+    #[test]
+    fn prompts_cover_detections_and_the_healthy_case() {
+        let result = VisionResponse {
+            job_id: Uuid::new_v4(),
+            crop_type: CropType::Durian,
+            detections: vec![],
+            severity_score: None,
+            model_version: "v1".into(),
+            processed_at: Utc::now(),
+            diagnosis_status: shared::models::DiagnosisStatus::Confirmed,
+        };
+        assert!(advice_prompt(&result, None).contains("no disease was detected"));
+
+        let mut with_detections = result;
+        with_detections.detections.push(shared::models::DiseaseDetection {
+            disease_name: "anthracnose".into(),
+            confidence: 0.82,
+            bounding_box: None,
+        });
+        with_detections.severity_score = Some(0.5);
+        let prompt = advice_prompt(&with_detections, None);
+        assert!(prompt.contains("anthracnose"));
+        assert!(prompt.contains("82%"));
+        assert!(prompt.contains("severity"));
+        assert!(!prompt.contains("sensor"));
+
+        // A paired sensor's reading lands in the prompt as conditions.
+        let reading = crate::handlers::sensors::ReadingSample {
+            temperature_c: 33.4,
+            soil_moisture_pct: 62.0,
+            humidity_pct: 88.0,
+            recorded_at: Utc::now(),
+        };
+        let prompt = advice_prompt(&with_detections, Some(&reading));
+        assert!(prompt.contains("temperature 33.4C"));
+        assert!(prompt.contains("soil moisture 62%"));
+        assert!(prompt.contains("humidity 88%"));
+        assert!(prompt.ends_with("Give practical treatment advice for a farmer."));
+    }
+
+    #[test]
+    fn diagnosed_payloads_and_channels_are_recognized() {
+        assert!(payload_is_diagnosed(r#"{"status": "diagnosed"}"#));
+        assert!(!payload_is_diagnosed(r#"{"status": "completed"}"#));
+        assert!(!payload_is_diagnosed("not json"));
+
+        let id = Uuid::new_v4();
+        assert_eq!(job_id_from_channel(&format!("job_status:{id}")), Some(id));
+        assert_eq!(job_id_from_channel("job_status:nope"), None);
+        assert_eq!(job_id_from_channel("other"), None);
+    }
+}
@@ -0,0 +1,374 @@
+//! AI4Thai API key management: rotation, quota demotion, and error mapping.
+//!
+//! The upstream vision and LLM services proxy AI4Thai, whose per-key daily
+//! quotas are easy to exhaust during a demo. The gateway therefore accepts
+//! several keys (comma-separated in `external_apis.ai4thai_api_key`), hands
+//! them out round-robin, and when a key comes back quota-exceeded demotes
+//! it for a cooldown so the remaining keys carry the traffic.
+
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::errors::{AppError, AppResult};
+
+/// Header AI4Thai authenticates on.
+pub const KEY_HEADER: &str = "Apikey";
+
+/// How long a quota-exceeded key sits out before it is tried again. AI4Thai
+/// quotas reset on their own schedule; a re-probe after the cooldown either
+/// readmits the key or demotes it for another round.
+pub const QUOTA_COOLDOWN: Duration = Duration::from_secs(60);
+
+struct KeyEntry {
+    key: String,
+    /// `Some(deadline)` while the key is demoted for quota.
+    demoted_until: Mutex<Option<Instant>>,
+    requests: AtomicU64,
+    quota_hits: AtomicU64,
+}
+
+/// Usage snapshot for one key, rendered into the Prometheus gauges by the
+/// metrics poller. Keys are identified by index, never by value — the key
+/// itself is a credential.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyUsage {
+    pub index: usize,
+    pub requests: u64,
+    pub quota_hits: u64,
+    pub demoted: bool,
+}
+
+/// Round-robin pool of AI4Thai keys with per-key quota demotion.
+pub struct Ai4ThaiKeyPool {
+    keys: Vec<KeyEntry>,
+    next: AtomicUsize,
+}
+
+/// One checked-out key. Holds no lock; it only remembers which slot to
+/// report usage and quota hits against.
+pub struct KeyLease<'a> {
+    pool: &'a Ai4ThaiKeyPool,
+    pub index: usize,
+}
+
+impl KeyLease<'_> {
+    pub fn key(&self) -> &str {
+        &self.pool.keys[self.index].key
+    }
+
+    fn record_use(&self) {
+        self.pool.keys[self.index]
+            .requests
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Demote this key for [`QUOTA_COOLDOWN`] after a quota-exceeded answer.
+    fn report_quota_exceeded_at(&self, now: Instant) {
+        let entry = &self.pool.keys[self.index];
+        entry.quota_hits.fetch_add(1, Ordering::Relaxed);
+        *entry.demoted_until.lock().expect("key pool lock poisoned") =
+            Some(now + QUOTA_COOLDOWN);
+    }
+}
+
+impl Ai4ThaiKeyPool {
+    pub fn new(keys: Vec<String>) -> Self {
+        Self {
+            keys: keys
+                .into_iter()
+                .map(|key| KeyEntry {
+                    key,
+                    demoted_until: Mutex::new(None),
+                    requests: AtomicU64::new(0),
+                    quota_hits: AtomicU64::new(0),
+                })
+                .collect(),
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    /// Build from the raw config value: one or more keys, comma-separated.
+    pub fn from_comma_separated(raw: &str) -> Self {
+        Self::new(
+            raw.split(',')
+                .map(str::trim)
+                .filter(|key| !key.is_empty())
+                .map(str::to_string)
+                .collect(),
+        )
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Check out the next usable key, round-robin, skipping keys still in
+    /// their quota cooldown (an expired cooldown readmits the key).
+    pub fn checkout(&self) -> AppResult<KeyLease<'_>> {
+        self.checkout_at(Instant::now())
+    }
+
+    fn checkout_at(&self, now: Instant) -> AppResult<KeyLease<'_>> {
+        if self.keys.is_empty() {
+            return Err(AppError::ExternalApi(
+                "no AI4Thai API keys configured (external_apis.ai4thai_api_key)".into(),
+            ));
+        }
+        for _ in 0..self.keys.len() {
+            let index = self.next.fetch_add(1, Ordering::Relaxed) % self.keys.len();
+            let mut demoted = self.keys[index]
+                .demoted_until
+                .lock()
+                .expect("key pool lock poisoned");
+            match *demoted {
+                Some(deadline) if now < deadline => continue,
+                _ => {
+                    *demoted = None;
+                    return Ok(KeyLease { pool: self, index });
+                }
+            }
+        }
+        Err(AppError::ExternalApi(
+            "all AI4Thai API keys are over quota; add keys (comma-separated) to \
+             external_apis.ai4thai_api_key or wait for the quota window to reset"
+                .into(),
+        ))
+    }
+
+    /// Per-key counters for the metrics poller.
+    pub fn usage(&self) -> Vec<KeyUsage> {
+        let now = Instant::now();
+        self.keys
+            .iter()
+            .enumerate()
+            .map(|(index, entry)| KeyUsage {
+                index,
+                requests: entry.requests.load(Ordering::Relaxed),
+                quota_hits: entry.quota_hits.load(Ordering::Relaxed),
+                demoted: entry
+                    .demoted_until
+                    .lock()
+                    .expect("key pool lock poisoned")
+                    .is_some_and(|deadline| now < deadline),
+            })
+            .collect()
+    }
+}
+
+/// Pull the human-readable message out of an AI4Thai error body, which is
+/// sometimes `{"message": ...}`, sometimes `{"error": ...}`, and sometimes
+/// plain text.
+fn upstream_message(body: &str) -> String {
+    serde_json::from_str::<serde_json::Value>(body)
+        .ok()
+        .and_then(|v| {
+            v.get("message")
+                .or_else(|| v.get("error"))
+                .and_then(|m| m.as_str().map(str::to_string))
+        })
+        .unwrap_or_else(|| body.trim().to_string())
+}
+
+/// Map an AI4Thai error answer into an [`AppError`] whose message says what
+/// to do about it, not just what came back.
+pub fn map_error(status: reqwest::StatusCode, body: &str) -> AppError {
+    let message = upstream_message(body);
+    match status {
+        reqwest::StatusCode::UNAUTHORIZED | reqwest::StatusCode::FORBIDDEN => {
+            AppError::ExternalApi(format!(
+                "AI4Thai rejected the API key ({status}): {message} — check \
+                 external_apis.ai4thai_api_key"
+            ))
+        }
+        reqwest::StatusCode::TOO_MANY_REQUESTS => AppError::ExternalApi(format!(
+            "AI4Thai quota exceeded: {message} — key demoted for {}s",
+            QUOTA_COOLDOWN.as_secs()
+        )),
+        _ => AppError::ExternalApi(format!("upstream {status}: {body}")),
+    }
+}
+
+/// Send one upstream request with key rotation: check out a key, attach it,
+/// and on a quota-exceeded answer demote that key and retry with the next
+/// one. Gives up once every key has been tried or is already demoted.
+/// Connection failures and 502/503 surface as `ServiceUnavailable` so the
+/// caller's retry and breaker logic still see them.
+pub async fn send_with_rotation(
+    pool: &Ai4ThaiKeyPool,
+    upstream: &'static str,
+    build: impl Fn() -> reqwest::RequestBuilder,
+) -> AppResult<reqwest::Response> {
+    let mut quota_error = None;
+    for _ in 0..pool.len().max(1) {
+        let lease = match pool.checkout() {
+            Ok(lease) => lease,
+            // Prefer the concrete quota message from this call's own
+            // attempts over the generic all-demoted one.
+            Err(error) => return Err(quota_error.unwrap_or(error)),
+        };
+        let response = build()
+            .header(KEY_HEADER, lease.key())
+            .send()
+            .await
+            .map_err(|e| AppError::ServiceUnavailable(format!("{upstream} service: {e}")))?;
+        lease.record_use();
+        let status = response.status();
+        if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            crate::metrics::record_upstream_error(upstream, status.as_u16());
+            let body = response.text().await.unwrap_or_default();
+            tracing::warn!(upstream, key_index = lease.index, "AI4Thai key over quota, demoting");
+            lease.report_quota_exceeded_at(Instant::now());
+            quota_error = Some(map_error(status, &body));
+            continue;
+        }
+        if !status.is_success() {
+            crate::metrics::record_upstream_error(upstream, status.as_u16());
+            let body = response.text().await.unwrap_or_default();
+            // 502/503 mean the service (or its proxy) is mid-restart:
+            // transient, so map them where retry and breaker logic will see
+            // them.
+            if matches!(
+                status,
+                reqwest::StatusCode::BAD_GATEWAY | reqwest::StatusCode::SERVICE_UNAVAILABLE
+            ) {
+                return Err(AppError::ServiceUnavailable(format!("upstream {status}: {body}")));
+            }
+            return Err(map_error(status, &body));
+        }
+        return Ok(response);
+    }
+    Err(quota_error.expect("loop only exhausts after a quota error"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::{
+        matchers::{header, method},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    #[test]
+    fn config_value_splits_into_trimmed_keys() {
+        let pool = Ai4ThaiKeyPool::from_comma_separated(" key-a, key-b ,,key-c ");
+        assert_eq!(pool.len(), 3);
+        assert_eq!(pool.checkout().unwrap().key(), "key-a");
+        assert_eq!(pool.checkout().unwrap().key(), "key-b");
+        assert_eq!(pool.checkout().unwrap().key(), "key-c");
+        assert_eq!(pool.checkout().unwrap().key(), "key-a");
+    }
+
+    #[test]
+    fn demoted_keys_are_skipped_until_the_cooldown_expires() {
+        let pool = Ai4ThaiKeyPool::new(vec!["a".into(), "b".into()]);
+        let now = Instant::now();
+
+        let lease = pool.checkout_at(now).unwrap();
+        assert_eq!(lease.key(), "a");
+        lease.report_quota_exceeded_at(now);
+
+        // Round-robin would offer "b" then "a"; "a" stays benched.
+        assert_eq!(pool.checkout_at(now).unwrap().key(), "b");
+        assert_eq!(pool.checkout_at(now).unwrap().key(), "b");
+
+        // After the cooldown the demoted key rejoins the rotation.
+        let later = now + QUOTA_COOLDOWN + Duration::from_secs(1);
+        let keys: Vec<String> = (0..2)
+            .map(|_| pool.checkout_at(later).unwrap().key().to_string())
+            .collect();
+        assert!(keys.contains(&"a".to_string()));
+    }
+
+    #[test]
+    fn an_exhausted_pool_says_so_and_an_empty_pool_is_a_config_error() {
+        let pool = Ai4ThaiKeyPool::new(vec!["only".into()]);
+        let now = Instant::now();
+        pool.checkout_at(now).unwrap().report_quota_exceeded_at(now);
+        match pool.checkout_at(now).err() {
+            Some(AppError::ExternalApi(message)) => assert!(message.contains("over quota")),
+            other => panic!("expected ExternalApi, got {other:?}"),
+        }
+
+        let empty = Ai4ThaiKeyPool::from_comma_separated("");
+        match empty.checkout().err() {
+            Some(AppError::ExternalApi(message)) => assert!(message.contains("no AI4Thai")),
+            other => panic!("expected ExternalApi, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn error_payloads_map_to_actionable_messages() {
+        let error = map_error(
+            reqwest::StatusCode::UNAUTHORIZED,
+            r#"{"message": "Api Key not found"}"#,
+        );
+        match error {
+            AppError::ExternalApi(message) => {
+                assert!(message.contains("Api Key not found"));
+                assert!(message.contains("ai4thai_api_key"));
+            }
+            other => panic!("expected ExternalApi, got {other:?}"),
+        }
+
+        let error = map_error(
+            reqwest::StatusCode::TOO_MANY_REQUESTS,
+            r#"{"error": "quota exceeded"}"#,
+        );
+        match error {
+            AppError::ExternalApi(message) => assert!(message.contains("quota exceeded")),
+            other => panic!("expected ExternalApi, got {other:?}"),
+        }
+
+        // Unrecognized statuses keep the generic upstream format.
+        let error = map_error(reqwest::StatusCode::IM_A_TEAPOT, "plain text");
+        match error {
+            AppError::ExternalApi(message) => assert!(message.contains("418")),
+            other => panic!("expected ExternalApi, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn rotation_demotes_the_quota_exceeded_key_and_succeeds_with_the_next() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(header(KEY_HEADER, "exhausted"))
+            .respond_with(
+                ResponseTemplate::new(429).set_body_string(r#"{"message": "quota exceeded"}"#),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(header(KEY_HEADER, "fresh"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("ok"))
+            .mount(&server)
+            .await;
+
+        let pool = Ai4ThaiKeyPool::new(vec!["exhausted".into(), "fresh".into()]);
+        let http = reqwest::Client::new();
+
+        // One logical call: the 429 demotes "exhausted" and the rotation
+        // finishes the request with "fresh".
+        let response = send_with_rotation(&pool, "vision", || http.post(server.uri()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+
+        let usage = pool.usage();
+        assert_eq!(usage[0].quota_hits, 1);
+        assert!(usage[0].demoted);
+        assert_eq!(usage[1].requests, 1);
+        assert!(!usage[1].demoted);
+
+        // Follow-up calls go straight to the healthy key.
+        let response = send_with_rotation(&pool, "vision", || http.post(server.uri()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+        assert_eq!(pool.usage()[1].requests, 2);
+    }
+}
@@ -0,0 +1,181 @@
+//! Webhook alerts to a Slack/Discord channel for critical events.
+//!
+//! Used by the DLQ monitor, readiness watchers, and panic handlers in
+//! background tasks. Sending is strictly best-effort: a down webhook must
+//! never slow down or fail request handling, and repeated alerts for the
+//! same event type are suppressed inside a configurable window so a flapping
+//! dependency doesn't page every few seconds.
+
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use serde::Deserialize;
+
+use crate::config::AlertsConfig;
+
+/// Alert severity; alerts below the configured minimum are dropped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Info,
+    #[default]
+    Warning,
+    Critical,
+}
+
+/// Webhook payload dialect.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertStyle {
+    Slack,
+    Discord,
+    #[default]
+    Generic,
+}
+
+#[derive(Debug, Clone)]
+pub struct Alert {
+    pub severity: Severity,
+    /// Stable key for suppression, e.g. `dlq_growth` or `readiness_failed`.
+    pub event_type: &'static str,
+    pub message: String,
+    /// Admin endpoint with the details, appended to the message.
+    pub link: Option<String>,
+}
+
+fn severity_tag(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Info => "INFO",
+        Severity::Warning => "WARN",
+        Severity::Critical => "CRIT",
+    }
+}
+
+/// Compact one-line text shared by all dialects.
+fn format_text(alert: &Alert) -> String {
+    let mut text = format!("[{}] {}", severity_tag(alert.severity), alert.message);
+    if let Some(link) = &alert.link {
+        text.push_str(&format!(" — {link}"));
+    }
+    text
+}
+
+/// Body for the configured channel style. Slack reads `text`, Discord reads
+/// `content`, and generic consumers get the structured fields.
+pub fn format_payload(style: AlertStyle, alert: &Alert) -> serde_json::Value {
+    let text = format_text(alert);
+    match style {
+        AlertStyle::Slack => serde_json::json!({ "text": text }),
+        AlertStyle::Discord => serde_json::json!({ "content": text }),
+        AlertStyle::Generic => serde_json::json!({
+            "severity": severity_tag(alert.severity),
+            "event_type": alert.event_type,
+            "message": alert.message,
+            "link": alert.link,
+        }),
+    }
+}
+
+pub struct AlertSink {
+    config: AlertsConfig,
+    http: reqwest::Client,
+    last_sent: Mutex<HashMap<&'static str, Instant>>,
+}
+
+impl AlertSink {
+    pub fn new(config: AlertsConfig) -> Self {
+        Self {
+            config,
+            http: reqwest::Client::new(),
+            last_sent: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// True when an alert of this event type already fired inside the
+    /// suppression window; records the send otherwise.
+    fn suppress(&self, event_type: &'static str, now: Instant, window: Duration) -> bool {
+        let mut last_sent = self.last_sent.lock().expect("alert lock poisoned");
+        match last_sent.get(event_type) {
+            Some(last) if now.duration_since(*last) < window => true,
+            _ => {
+                last_sent.insert(event_type, now);
+                false
+            }
+        }
+    }
+
+    /// Fire-and-forget: failures are logged, never propagated. Callers on a
+    /// request path should `tokio::spawn` this.
+    pub async fn send(&self, alert: Alert) {
+        let Some(url) = self.config.webhook_url.clone() else {
+            return; // alerts not configured
+        };
+        if alert.severity < self.config.min_severity {
+            return;
+        }
+        let window = Duration::from_secs(self.config.suppress_seconds);
+        if self.suppress(alert.event_type, Instant::now(), window) {
+            return;
+        }
+
+        let payload = format_payload(self.config.style, &alert);
+        if let Err(e) = self.http.post(&url).json(&payload).send().await {
+            tracing::warn!(error = %e, event_type = alert.event_type, "alert webhook failed");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn alert() -> Alert {
+        Alert {
+            severity: Severity::Critical,
+            event_type: "dlq_growth",
+            message: "DLQ depth 42".into(),
+            link: Some("/api/v1/admin/logs/stream".into()),
+        }
+    }
+
+    #[test]
+    fn slack_and_discord_use_their_text_fields() {
+        let slack = format_payload(AlertStyle::Slack, &alert());
+        assert_eq!(
+            slack["text"],
+            "[CRIT] DLQ depth 42 — /api/v1/admin/logs/stream"
+        );
+        let discord = format_payload(AlertStyle::Discord, &alert());
+        assert_eq!(discord["content"], slack["text"]);
+    }
+
+    #[test]
+    fn generic_payload_keeps_structured_fields() {
+        let payload = format_payload(AlertStyle::Generic, &alert());
+        assert_eq!(payload["severity"], "CRIT");
+        assert_eq!(payload["event_type"], "dlq_growth");
+        assert_eq!(payload["message"], "DLQ depth 42");
+    }
+
+    #[test]
+    fn duplicate_alerts_inside_the_window_are_suppressed() {
+        let sink = AlertSink::new(AlertsConfig::default());
+        let window = Duration::from_secs(60);
+        let start = Instant::now();
+        assert!(!sink.suppress("dlq_growth", start, window));
+        assert!(sink.suppress("dlq_growth", start + Duration::from_secs(30), window));
+        // A different event type has its own window.
+        assert!(!sink.suppress("readiness_failed", start, window));
+        // And the original fires again once the window has passed.
+        assert!(!sink.suppress("dlq_growth", start + Duration::from_secs(61), window));
+    }
+
+    #[test]
+    fn severities_order_for_minimum_filtering() {
+        assert!(Severity::Info < Severity::Warning);
+        assert!(Severity::Warning < Severity::Critical);
+    }
+}
@@ -0,0 +1,523 @@
+//! Redis-backed query cache for read-heavy endpoints.
+//!
+//! Wraps a computation in a get-or-set against Redis: on a hit the stored
+//! JSON is returned without touching Postgres, on a miss the computation
+//! runs and its result is stored with a TTL. The cache is strictly an
+//! optimization — any Redis failure falls through to the computation, so a
+//! cache outage degrades to the uncached behavior instead of erroring.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{config::CacheConfig, errors::AppResult};
+
+pub struct CacheService {
+    redis: redis::Client,
+    enabled: bool,
+    pub default_ttl: Duration,
+    flights: FlightMap,
+}
+
+impl CacheService {
+    pub fn new(redis: redis::Client, config: &CacheConfig) -> Self {
+        Self {
+            redis,
+            enabled: config.enabled,
+            default_ttl: Duration::from_secs(config.default_ttl_secs),
+            flights: FlightMap::default(),
+        }
+    }
+
+    /// Return the cached value under `key`, or run `compute`, cache its
+    /// result for `ttl`, and return it. Compute errors are never cached.
+    pub async fn get_or_set<T, F>(&self, key: &str, ttl: Duration, compute: F) -> AppResult<T>
+    where
+        T: Serialize + DeserializeOwned,
+        F: std::future::Future<Output = AppResult<T>>,
+    {
+        if !self.enabled {
+            return compute.await;
+        }
+        match self.redis.get_multiplexed_async_connection().await {
+            Ok(mut conn) => get_or_set_in(&mut conn, key, ttl, compute).await,
+            // Redis down: serve from Postgres as if the cache weren't there.
+            Err(e) => {
+                tracing::debug!(error = %e, "cache unavailable; computing directly");
+                compute.await
+            }
+        }
+    }
+
+    /// Stampede-protected variant of [`get_or_set`](Self::get_or_set) for
+    /// hot reference data that every app launch reads. On top of plain
+    /// get-or-set it adds: single-flight per key (a burst of identical
+    /// misses runs `compute` once and the rest wait), TTL jitter so entries
+    /// written together don't expire together, and stale-while-revalidate
+    /// (an expired entry is served as-is to callers arriving while one
+    /// caller refreshes it).
+    pub async fn get_or_compute<T, F>(
+        &self,
+        key: &str,
+        ttl: Duration,
+        jitter: Duration,
+        compute: F,
+    ) -> AppResult<T>
+    where
+        T: Serialize + DeserializeOwned,
+        F: std::future::Future<Output = AppResult<T>>,
+    {
+        if !self.enabled {
+            return compute.await;
+        }
+        match self.redis.get_multiplexed_async_connection().await {
+            Ok(mut conn) => {
+                get_or_compute_in(&mut conn, &self.flights, key, ttl, jitter, compute).await
+            }
+            Err(e) => {
+                tracing::debug!(error = %e, "cache unavailable; computing directly");
+                compute.await
+            }
+        }
+    }
+
+    /// Drop `key` so the next read recomputes. Best-effort: an unreachable
+    /// cache only means a stale entry lives out its TTL.
+    pub async fn invalidate(&self, key: &str) {
+        if !self.enabled {
+            return;
+        }
+        if let Ok(mut conn) = self.redis.get_multiplexed_async_connection().await {
+            conn.del(key).await;
+        }
+    }
+}
+
+/// Storage operations the get-or-set core needs, kept behind a trait so the
+/// logic is testable without a Redis instance. All methods are best-effort;
+/// implementations swallow their own errors.
+#[async_trait]
+pub(crate) trait CacheStore {
+    async fn get(&mut self, key: &str) -> Option<String>;
+    async fn set(&mut self, key: &str, value: &str, ttl: Duration);
+    async fn del(&mut self, key: &str);
+}
+
+#[async_trait]
+impl CacheStore for redis::aio::MultiplexedConnection {
+    async fn get(&mut self, key: &str) -> Option<String> {
+        redis::cmd("GET").arg(key).query_async(self).await.ok()
+    }
+
+    async fn set(&mut self, key: &str, value: &str, ttl: Duration) {
+        let result: Result<(), _> = redis::cmd("SET")
+            .arg(key)
+            .arg(value)
+            .arg("EX")
+            .arg(ttl.as_secs().max(1))
+            .query_async(self)
+            .await;
+        if let Err(e) = result {
+            tracing::debug!(error = %e, key, "cache set failed");
+        }
+    }
+
+    async fn del(&mut self, key: &str) {
+        let result: Result<(), _> = redis::cmd("DEL").arg(key).query_async(self).await;
+        if let Err(e) = result {
+            tracing::debug!(error = %e, key, "cache del failed");
+        }
+    }
+}
+
+/// Get-or-set against any store. A stored value that no longer deserializes
+/// (e.g. the type changed across a deploy) counts as a miss and is
+/// overwritten by the recomputed value.
+pub(crate) async fn get_or_set_in<S, T, F>(
+    store: &mut S,
+    key: &str,
+    ttl: Duration,
+    compute: F,
+) -> AppResult<T>
+where
+    S: CacheStore + Send,
+    T: Serialize + DeserializeOwned,
+    F: std::future::Future<Output = AppResult<T>>,
+{
+    if let Some(cached) = store.get(key).await {
+        if let Ok(value) = serde_json::from_str(&cached) {
+            crate::metrics::record_cache_access("query_cache", true);
+            return Ok(value);
+        }
+    }
+    crate::metrics::record_cache_access("query_cache", false);
+    let value = compute.await?;
+    if let Ok(serialized) = serde_json::to_string(&value) {
+        store.set(key, &serialized, ttl).await;
+    }
+    Ok(value)
+}
+
+/// Per-key in-process locks backing the single-flight behavior. One lock
+/// per gateway instance is the point: a burst of identical requests landing
+/// on one process collapses to one computation, and the handful of
+/// cross-instance duplicates is noise Postgres can absorb.
+#[derive(Default)]
+pub(crate) struct FlightMap {
+    locks: std::sync::Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>,
+}
+
+impl FlightMap {
+    fn lock_for(&self, key: &str) -> Arc<tokio::sync::Mutex<()>> {
+        self.locks
+            .lock()
+            .expect("flight lock poisoned")
+            .entry(key.to_string())
+            .or_default()
+            .clone()
+    }
+
+    /// Drop the key's lock once nobody holds a handle to it, so the map
+    /// doesn't accumulate an entry per key ever requested.
+    fn release(&self, key: &str) {
+        let mut locks = self.locks.lock().expect("flight lock poisoned");
+        if locks.get(key).is_some_and(|lock| Arc::strong_count(lock) == 1) {
+            locks.remove(key);
+        }
+    }
+}
+
+/// Stored shape for `get_or_compute` entries: the value plus its logical
+/// freshness horizon. The physical Redis TTL extends past `fresh_until` by
+/// one `ttl`, which is the stale-while-revalidate window.
+#[derive(Deserialize)]
+struct Envelope<T> {
+    value: T,
+    fresh_until: DateTime<Utc>,
+}
+
+#[derive(Serialize)]
+struct EnvelopeRef<'a, T> {
+    value: &'a T,
+    fresh_until: DateTime<Utc>,
+}
+
+enum Cached<T> {
+    Fresh(T),
+    Stale(T),
+    Missing,
+}
+
+async fn read_envelope<S, T>(store: &mut S, key: &str, now: DateTime<Utc>) -> Cached<T>
+where
+    S: CacheStore + Send,
+    T: DeserializeOwned,
+{
+    let Some(raw) = store.get(key).await else {
+        return Cached::Missing;
+    };
+    match serde_json::from_str::<Envelope<T>>(&raw) {
+        Ok(envelope) if envelope.fresh_until > now => Cached::Fresh(envelope.value),
+        Ok(envelope) => Cached::Stale(envelope.value),
+        // Shape changed across a deploy: treat as a miss.
+        Err(_) => Cached::Missing,
+    }
+}
+
+/// `ttl` plus up to `jitter` extra, scaled by the seed. Entries written in
+/// the same burst then expire spread out instead of all at once.
+pub(crate) fn jittered(ttl: Duration, jitter: Duration, seed: u64) -> Duration {
+    if jitter.is_zero() {
+        return ttl;
+    }
+    ttl + Duration::from_millis(seed % (jitter.as_millis().max(1) as u64))
+}
+
+/// Single-flight, jittered, stale-while-revalidate get-or-compute core.
+/// Compute errors are never cached, and when a stale copy exists it is
+/// served in place of a failed refresh.
+pub(crate) async fn get_or_compute_in<S, T, F>(
+    store: &mut S,
+    flights: &FlightMap,
+    key: &str,
+    ttl: Duration,
+    jitter: Duration,
+    compute: F,
+) -> AppResult<T>
+where
+    S: CacheStore + Send,
+    T: Serialize + DeserializeOwned,
+    F: std::future::Future<Output = AppResult<T>>,
+{
+    // Fast path: a fresh entry needs no lock at all.
+    if let Cached::Fresh(value) = read_envelope(store, key, Utc::now()).await {
+        crate::metrics::record_swr_cache("hit");
+        return Ok(value);
+    }
+
+    let lock = flights.lock_for(key);
+    let guard = match lock.try_lock() {
+        Ok(guard) => guard,
+        Err(_) => {
+            crate::metrics::record_swr_cache("lock_contention");
+            // Someone else is computing. A stale copy is good enough to
+            // answer with right now; without one we wait for their result.
+            match read_envelope(store, key, Utc::now()).await {
+                Cached::Fresh(value) | Cached::Stale(value) => {
+                    crate::metrics::record_swr_cache("stale_serve");
+                    return Ok(value);
+                }
+                Cached::Missing => {}
+            }
+            let guard = lock.lock().await;
+            match read_envelope(store, key, Utc::now()).await {
+                Cached::Fresh(value) | Cached::Stale(value) => {
+                    crate::metrics::record_swr_cache("hit");
+                    drop(guard);
+                    drop(lock);
+                    flights.release(key);
+                    return Ok(value);
+                }
+                // Their compute failed and cached nothing: ours runs.
+                Cached::Missing => guard,
+            }
+        }
+    };
+
+    crate::metrics::record_swr_cache("miss");
+    let result = compute.await;
+    let result = match result {
+        Ok(value) => {
+            let seed = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.subsec_nanos() as u64)
+                .unwrap_or(0);
+            let fresh = jittered(ttl, jitter, seed);
+            let envelope = EnvelopeRef {
+                value: &value,
+                fresh_until: Utc::now()
+                    + chrono::Duration::from_std(fresh).unwrap_or(chrono::Duration::zero()),
+            };
+            if let Ok(serialized) = serde_json::to_string(&envelope) {
+                store.set(key, &serialized, fresh + ttl).await;
+            }
+            Ok(value)
+        }
+        Err(error) => {
+            // A failed refresh of a stale entry keeps serving the old value;
+            // the next expiry attempt gets another shot at Postgres.
+            match read_envelope(store, key, Utc::now()).await {
+                Cached::Fresh(value) | Cached::Stale(value) => {
+                    crate::metrics::record_swr_cache("stale_serve");
+                    Ok(value)
+                }
+                Cached::Missing => Err(error),
+            }
+        }
+    };
+    drop(guard);
+    drop(lock);
+    flights.release(key);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct MemoryStore {
+        entries: HashMap<String, String>,
+    }
+
+    #[async_trait]
+    impl CacheStore for MemoryStore {
+        async fn get(&mut self, key: &str) -> Option<String> {
+            self.entries.get(key).cloned()
+        }
+
+        async fn set(&mut self, key: &str, value: &str, _ttl: Duration) {
+            self.entries.insert(key.to_string(), value.to_string());
+        }
+
+        async fn del(&mut self, key: &str) {
+            self.entries.remove(key);
+        }
+    }
+
+    const TTL: Duration = Duration::from_secs(60);
+
+    #[tokio::test]
+    async fn second_read_is_served_from_the_cache() {
+        let mut store = MemoryStore::default();
+        let first: Vec<String> = get_or_set_in(&mut store, "k", TTL, async {
+            Ok(vec!["row".to_string()])
+        })
+        .await
+        .unwrap();
+        assert_eq!(first, vec!["row"]);
+
+        // The computation must not run again; reaching it is the failure.
+        let second: Vec<String> = get_or_set_in(&mut store, "k", TTL, async {
+            panic!("computed despite a warm cache")
+        })
+        .await
+        .unwrap();
+        assert_eq!(second, first);
+    }
+
+    #[tokio::test]
+    async fn compute_errors_are_not_cached() {
+        let mut store = MemoryStore::default();
+        let failed: AppResult<Vec<String>> = get_or_set_in(&mut store, "k", TTL, async {
+            Err(crate::errors::AppError::Internal("db down".into()))
+        })
+        .await;
+        assert!(failed.is_err());
+
+        let recovered: Vec<String> = get_or_set_in(&mut store, "k", TTL, async {
+            Ok(vec!["row".to_string()])
+        })
+        .await
+        .unwrap();
+        assert_eq!(recovered, vec!["row"]);
+    }
+
+    #[tokio::test]
+    async fn undeserializable_entries_are_recomputed() {
+        let mut store = MemoryStore::default();
+        store.set("k", "{not json", TTL).await;
+        let value: u32 = get_or_set_in(&mut store, "k", TTL, async { Ok(7) })
+            .await
+            .unwrap();
+        assert_eq!(value, 7);
+        assert_eq!(store.get("k").await.as_deref(), Some("7"));
+    }
+
+    /// Cloneable store for the concurrency tests below.
+    #[derive(Clone, Default)]
+    struct SharedStore(Arc<tokio::sync::Mutex<MemoryStore>>);
+
+    #[async_trait]
+    impl CacheStore for SharedStore {
+        async fn get(&mut self, key: &str) -> Option<String> {
+            self.0.lock().await.get(key).await
+        }
+
+        async fn set(&mut self, key: &str, value: &str, ttl: Duration) {
+            self.0.lock().await.set(key, value, ttl).await;
+        }
+
+        async fn del(&mut self, key: &str) {
+            self.0.lock().await.del(key).await;
+        }
+    }
+
+    async fn seed_envelope(store: &SharedStore, key: &str, value: u32, fresh_for: chrono::Duration) {
+        let envelope = EnvelopeRef { value: &value, fresh_until: Utc::now() + fresh_for };
+        store
+            .clone()
+            .set(key, &serde_json::to_string(&envelope).unwrap(), TTL)
+            .await;
+    }
+
+    #[tokio::test]
+    async fn a_burst_of_identical_misses_computes_once() {
+        let store = SharedStore::default();
+        let flights = Arc::new(FlightMap::default());
+        let computations = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+
+        let mut handles = Vec::new();
+        for _ in 0..8 {
+            let mut store = store.clone();
+            let flights = flights.clone();
+            let computations = computations.clone();
+            handles.push(tokio::spawn(async move {
+                get_or_compute_in(&mut store, &flights, "k", TTL, Duration::ZERO, async move {
+                    computations.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(50)).await;
+                    Ok(7u32)
+                })
+                .await
+            }));
+        }
+        for handle in handles {
+            assert_eq!(handle.await.unwrap().unwrap(), 7);
+        }
+        assert_eq!(
+            computations.load(std::sync::atomic::Ordering::SeqCst),
+            1,
+            "the herd must collapse to one computation"
+        );
+    }
+
+    #[tokio::test]
+    async fn stale_entry_is_served_while_one_caller_refreshes() {
+        let store = SharedStore::default();
+        let flights = Arc::new(FlightMap::default());
+        seed_envelope(&store, "k", 1, chrono::Duration::seconds(-1)).await;
+
+        // The refresher grabs the flight lock and recomputes slowly.
+        let refresher = {
+            let mut store = store.clone();
+            let flights = flights.clone();
+            tokio::spawn(async move {
+                get_or_compute_in(&mut store, &flights, "k", TTL, Duration::ZERO, async {
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    Ok(2u32)
+                })
+                .await
+            })
+        };
+        tokio::time::sleep(Duration::from_millis(10)).await;
+
+        // A caller arriving mid-refresh gets the stale value immediately
+        // instead of waiting out the recompute.
+        let stale: u32 = get_or_compute_in(
+            &mut store.clone(),
+            &flights,
+            "k",
+            TTL,
+            Duration::ZERO,
+            async { panic!("the waiter must not compute") },
+        )
+        .await
+        .unwrap();
+        assert_eq!(stale, 1);
+        assert_eq!(refresher.await.unwrap().unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn failed_refresh_keeps_serving_the_stale_value() {
+        let store = SharedStore::default();
+        let flights = FlightMap::default();
+        seed_envelope(&store, "k", 9, chrono::Duration::seconds(-1)).await;
+
+        let value: u32 = get_or_compute_in(
+            &mut store.clone(),
+            &flights,
+            "k",
+            TTL,
+            Duration::ZERO,
+            async { Err(crate::errors::AppError::Internal("db down".into())) },
+        )
+        .await
+        .unwrap();
+        assert_eq!(value, 9);
+    }
+
+    #[test]
+    fn jitter_extends_the_ttl_within_bounds() {
+        let ttl = Duration::from_secs(60);
+        let jitter = Duration::from_secs(10);
+        assert_eq!(jittered(ttl, Duration::ZERO, 42), ttl);
+        for seed in [0, 1, 9_999, u64::MAX] {
+            let extended = jittered(ttl, jitter, seed);
+            assert!(extended >= ttl && extended < ttl + jitter, "seed {seed}: {extended:?}");
+        }
+    }
+}
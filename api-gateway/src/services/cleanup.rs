@@ -0,0 +1,104 @@
+//! Periodic housekeeping passes: expired uploads and stale cache entries.
+//!
+//! `FileStorageConfig` has carried `cleanup_interval` and `file_ttl` since
+//! the start, but nothing ran on a schedule — disks filled until someone
+//! noticed. The file pass scans on the interval, protects files that
+//! still back queued or processing jobs, and feeds the freed counts into
+//! the metrics counters. Scan errors are logged and the next tick retries.
+//! The [`BackgroundScheduler`](crate::services::scheduler::BackgroundScheduler)
+//! owns the tickers; this module only provides the single-pass bodies.
+
+use std::collections::HashSet;
+
+use redis::AsyncCommands;
+
+use crate::state::AppState;
+
+/// File stems (job ids) that must not be cleaned up yet. `None` means the
+/// lookup failed and this tick should be skipped — deleting a pending
+/// job's image is worse than a late cleanup.
+async fn pending_job_ids(state: &AppState) -> Option<HashSet<String>> {
+    let rows: Result<Vec<(uuid::Uuid,)>, _> = sqlx::query_as(
+        "SELECT id FROM vision_jobs WHERE status IN ('queued', 'processing')",
+    )
+    .fetch_all(&state.db)
+    .await;
+    match rows {
+        Ok(rows) => Some(rows.into_iter().map(|(id,)| id.to_string()).collect()),
+        Err(e) => {
+            tracing::warn!(error = %e, "cleanup: pending-job lookup failed, skipping tick");
+            None
+        }
+    }
+}
+
+/// One cleanup pass. Shared by the periodic loop and the shutdown path,
+/// which flushes a final pass so a deploy doesn't strand expired files
+/// until the next process gets around to them.
+pub async fn run_once(state: &AppState) {
+    let Some(keep) = pending_job_ids(state).await else {
+        return;
+    };
+    match state.file_storage.cleanup_expired(&keep).await {
+        Ok(outcome) if outcome.files_removed > 0 => {
+            tracing::info!(
+                files = outcome.files_removed,
+                bytes = outcome.bytes_freed,
+                "cleanup: removed expired uploads"
+            );
+            state.add_to_counter("cleanup_files_removed", outcome.files_removed);
+            state.add_to_counter("cleanup_bytes_freed", outcome.bytes_freed);
+        }
+        Ok(_) => {}
+        Err(e) => {
+            tracing::warn!(error = %e, "cleanup: scan failed, will retry next tick");
+        }
+    }
+}
+
+/// One pass over the vision result cache. Reads already drop an entry
+/// lazily when its model version went stale, but an entry that is never
+/// read again after a model upgrade would sit out its full TTL — this
+/// sweep deletes those eagerly so the cache only holds answers the
+/// current model would give. Unparseable entries count as stale.
+pub async fn prune_stale_cache(state: &AppState) {
+    let Ok(mut redis) = state.redis_client.get_multiplexed_async_connection().await else {
+        tracing::debug!("cache prune: redis unavailable, skipping tick");
+        return;
+    };
+    // No version recorded means no completion has run yet: nothing to
+    // judge staleness against.
+    let Ok(Some(current)) = redis.get::<_, Option<String>>("vision_model_version").await else {
+        return;
+    };
+
+    let keys = {
+        let Ok(mut iter) = redis.scan_match::<_, String>("vision_cache:*").await else {
+            return;
+        };
+        let mut keys = Vec::new();
+        while let Some(key) = iter.next_item().await {
+            keys.push(key);
+        }
+        keys
+    };
+
+    let mut pruned = 0u64;
+    for key in keys {
+        let Ok(Some(raw)) = redis.get::<_, Option<String>>(&key).await else {
+            continue;
+        };
+        let fresh = serde_json::from_str::<serde_json::Value>(&raw)
+            .ok()
+            .and_then(|entry| entry.get("model_version")?.as_str().map(|v| v == current))
+            .unwrap_or(false);
+        if !fresh {
+            let _: Result<(), _> = redis.del(&key).await;
+            pruned += 1;
+        }
+    }
+    if pruned > 0 {
+        tracing::info!(entries = pruned, "cache prune: dropped stale vision results");
+        state.add_to_counter("cache_entries_pruned", pruned);
+    }
+}
@@ -0,0 +1,223 @@
+//! Postgres-backed conversation storage behind one repository type, so chat
+//! handlers stop hand-rolling SQL and the queries have a single home.
+
+use chrono::{DateTime, Utc};
+use shared::models::{ChatMessage, MessageRole};
+use sqlx::{PgPool, QueryBuilder};
+use uuid::Uuid;
+
+use crate::errors::{AppError, AppResult};
+use crate::shared::pagination::{Cursor, SortDirection};
+
+pub(crate) fn role_str(role: MessageRole) -> &'static str {
+    match role {
+        MessageRole::User => "user",
+        MessageRole::Assistant => "assistant",
+        MessageRole::System => "system",
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct MessageRow {
+    id: Uuid,
+    conversation_id: Uuid,
+    role: String,
+    content: String,
+    image_url: Option<String>,
+    created_at: DateTime<Utc>,
+}
+
+impl From<MessageRow> for ChatMessage {
+    fn from(row: MessageRow) -> Self {
+        let role = serde_json::from_value(serde_json::Value::String(row.role))
+            .unwrap_or(MessageRole::System);
+        ChatMessage {
+            id: Some(row.id),
+            conversation_id: Some(row.conversation_id),
+            role,
+            content: row.content,
+            image_url: row.image_url,
+            created_at: row.created_at,
+        }
+    }
+}
+
+pub struct ConversationRepository {
+    db: PgPool,
+}
+
+impl ConversationRepository {
+    pub fn new(db: PgPool) -> Self {
+        Self { db }
+    }
+
+    /// Idempotent: a second create for the same id is a no-op, so callers
+    /// can create lazily on first message.
+    pub async fn create_conversation(&self, conversation_id: Uuid, user_id: Uuid) -> AppResult<()> {
+        sqlx::query(
+            "INSERT INTO conversations (id, user_id) VALUES ($1, $2) ON CONFLICT (id) DO NOTHING",
+        )
+        .bind(conversation_id)
+        .bind(user_id)
+        .execute(&self.db)
+        .await?;
+        Ok(())
+    }
+
+    /// Insert one message, assigning its persisted id in place.
+    pub async fn add_message(
+        &self,
+        conversation_id: Uuid,
+        message: &mut ChatMessage,
+    ) -> AppResult<()> {
+        let id = Uuid::new_v4();
+        sqlx::query(
+            "INSERT INTO messages (id, conversation_id, role, content, image_url, created_at) \
+             VALUES ($1, $2, $3, $4, $5, $6)",
+        )
+        .bind(id)
+        .bind(conversation_id)
+        .bind(role_str(message.role))
+        .bind(&message.content)
+        .bind(&message.image_url)
+        .bind(message.created_at)
+        .execute(&self.db)
+        .await?;
+        message.id = Some(id);
+        message.conversation_id = Some(conversation_id);
+        Ok(())
+    }
+
+    /// Create-if-missing plus a batch of messages in one transaction, so a
+    /// crash can't persist the user's question without the reply.
+    pub async fn add_exchange(
+        &self,
+        conversation_id: Uuid,
+        user_id: Uuid,
+        messages: &mut [ChatMessage],
+    ) -> AppResult<()> {
+        let mut tx = self.db.begin().await?;
+        sqlx::query(
+            "INSERT INTO conversations (id, user_id) VALUES ($1, $2) ON CONFLICT (id) DO NOTHING",
+        )
+        .bind(conversation_id)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+        for message in messages.iter_mut() {
+            let id = Uuid::new_v4();
+            sqlx::query(
+                "INSERT INTO messages (id, conversation_id, role, content, image_url, created_at) \
+                 VALUES ($1, $2, $3, $4, $5, $6)",
+            )
+            .bind(id)
+            .bind(conversation_id)
+            .bind(role_str(message.role))
+            .bind(&message.content)
+            .bind(&message.image_url)
+            .bind(message.created_at)
+            .execute(&mut *tx)
+            .await?;
+            message.id = Some(id);
+            message.conversation_id = Some(conversation_id);
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Resolve a message id into its keyset cursor, for legacy `before`
+    /// paging that anchors on a raw id.
+    pub async fn message_cursor(&self, message_id: Uuid) -> AppResult<Cursor> {
+        let (created_at, id): (DateTime<Utc>, Uuid) =
+            sqlx::query_as("SELECT created_at, id FROM messages WHERE id = $1")
+                .bind(message_id)
+                .fetch_optional(&self.db)
+                .await?
+                .ok_or_else(|| AppError::NotFound(format!("message {message_id}")))?;
+        Ok(Cursor { created_at, id })
+    }
+
+    /// Page through a conversation, scoped to its owner. Keyset on
+    /// `(created_at, id)` — offsets would skip or duplicate rows while new
+    /// messages arrive. Fetches `limit + 1` rows so the caller
+    /// (`Page::from_rows`) can tell whether another page exists.
+    pub async fn get_messages_page(
+        &self,
+        conversation_id: Uuid,
+        user_id: Uuid,
+        limit: i64,
+        cursor: Option<&Cursor>,
+        order: SortDirection,
+        role: Option<MessageRole>,
+    ) -> AppResult<Vec<ChatMessage>> {
+        let mut qb = QueryBuilder::new(
+            "SELECT m.id, m.conversation_id, m.role, m.content, m.image_url, m.created_at \
+             FROM messages m JOIN conversations c ON c.id = m.conversation_id \
+             WHERE m.conversation_id = ",
+        );
+        qb.push_bind(conversation_id);
+        qb.push(" AND c.user_id = ").push_bind(user_id);
+        if let Some(role) = role {
+            qb.push(" AND m.role = ").push_bind(role_str(role));
+        }
+        if let Some(cursor) = cursor {
+            // The comparison follows the sort order: paging ascending walks
+            // forwards in time, descending walks backwards.
+            qb.push(" AND (m.created_at, m.id) ")
+                .push(match order {
+                    SortDirection::Asc => "> (",
+                    SortDirection::Desc => "< (",
+                })
+                .push_bind(cursor.created_at)
+                .push(", ")
+                .push_bind(cursor.id)
+                .push(")");
+        }
+        qb.push(match order {
+            SortDirection::Asc => " ORDER BY m.created_at ASC, m.id ASC LIMIT ",
+            SortDirection::Desc => " ORDER BY m.created_at DESC, m.id DESC LIMIT ",
+        });
+        qb.push_bind(limit + 1);
+
+        let rows: Vec<MessageRow> = qb.build_query_as().fetch_all(&self.db).await?;
+        Ok(rows.into_iter().map(ChatMessage::from).collect())
+    }
+
+    /// Delete a conversation the user owns; messages cascade. Returns false
+    /// when nothing matched (unknown id or someone else's conversation).
+    pub async fn delete_conversation(&self, conversation_id: Uuid, user_id: Uuid) -> AppResult<bool> {
+        let result = sqlx::query("DELETE FROM conversations WHERE id = $1 AND user_id = $2")
+            .bind(conversation_id)
+            .bind(user_id)
+            .execute(&self.db)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roles_round_trip_through_their_column_values() {
+        for role in [MessageRole::User, MessageRole::Assistant, MessageRole::System] {
+            let parsed: MessageRole =
+                serde_json::from_value(serde_json::Value::String(role_str(role).into())).unwrap();
+            assert_eq!(parsed, role);
+        }
+    }
+
+    #[test]
+    fn unknown_stored_role_degrades_to_system() {
+        let row = MessageRow {
+            id: Uuid::new_v4(),
+            conversation_id: Uuid::new_v4(),
+            role: "moderator".into(),
+            content: "c".into(),
+            image_url: None,
+            created_at: Utc::now(),
+        };
+        assert_eq!(ChatMessage::from(row).role, MessageRole::System);
+    }
+}
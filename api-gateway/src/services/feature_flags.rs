@@ -0,0 +1,213 @@
+//! Runtime feature flags backed by the Redis hash `feature_flags`.
+//!
+//! Shipping a new model used to mean a config change and a restart; a flag
+//! flips at runtime through the admin API instead. Each hash field holds
+//! one [`FlagDefinition`] as JSON, so flags survive gateway restarts and
+//! apply to every instance at once. Lookups fail closed: if Redis is down,
+//! every flag reads as off and the stable code path runs.
+
+use std::collections::BTreeMap;
+
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::errors::{AppError, AppResult};
+
+/// The Redis hash holding every flag.
+const FLAGS_KEY: &str = "feature_flags";
+
+/// Routes vision analysis to the v2 model endpoint.
+pub const USE_NEW_VISION_MODEL_V2: &str = "use_new_vision_model_v2";
+
+/// One flag as stored in the hash. All mechanisms compose; see
+/// [`flag_applies`] for the precedence.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct FlagDefinition {
+    /// On for everyone; overrides the rollout percentage.
+    #[serde(default)]
+    pub enabled: bool,
+    /// On for this percentage of users (0–100). Users land in stable
+    /// buckets by `user_id` modulo 100, so a user already in the rollout
+    /// stays in it as the percentage grows.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rollout_percent: Option<u8>,
+    /// Forced on for these users regardless of the rollout — pilot farmers
+    /// and our own test accounts.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub enabled_users: Vec<Uuid>,
+    /// Forced off for these users; wins over everything else, so a user
+    /// hitting a bug can be pulled out without ending the rollout.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub disabled_users: Vec<Uuid>,
+}
+
+/// Stable rollout bucket for a user: 0–99.
+fn rollout_bucket(user_id: Uuid) -> u8 {
+    (user_id.as_u128() % 100) as u8
+}
+
+/// Whether `definition` turns the flag on for `user_id`. Precedence:
+/// per-user off list, per-user on list, the global switch, then the
+/// percentage rollout. Anonymous callers only see globally enabled flags —
+/// there is no stable bucket to put them in.
+pub fn flag_applies(definition: &FlagDefinition, user_id: Option<Uuid>) -> bool {
+    if let Some(user_id) = user_id {
+        if definition.disabled_users.contains(&user_id) {
+            return false;
+        }
+        if definition.enabled_users.contains(&user_id) {
+            return true;
+        }
+    }
+    if definition.enabled {
+        return true;
+    }
+    match (definition.rollout_percent, user_id) {
+        (Some(percent), Some(user_id)) => rollout_bucket(user_id) < percent.min(100),
+        _ => false,
+    }
+}
+
+pub struct FeatureFlagService {
+    client: redis::Client,
+}
+
+impl FeatureFlagService {
+    pub fn new(client: redis::Client) -> Self {
+        Self { client }
+    }
+
+    async fn conn(&self) -> AppResult<redis::aio::MultiplexedConnection> {
+        self.client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(|e| AppError::ServiceUnavailable(format!("redis: {e}")))
+    }
+
+    /// Whether `flag` is on for `user_id`. Unknown flags, undecodable
+    /// definitions, and Redis failures all read as off.
+    pub async fn is_enabled(&self, flag: &str, user_id: Option<Uuid>) -> bool {
+        let Ok(mut conn) = self.conn().await else {
+            return false;
+        };
+        let raw: Option<String> = match conn.hget(FLAGS_KEY, flag).await {
+            Ok(raw) => raw,
+            Err(_) => return false,
+        };
+        raw.and_then(|r| serde_json::from_str::<FlagDefinition>(&r).ok())
+            .is_some_and(|definition| flag_applies(&definition, user_id))
+    }
+
+    /// Every flag currently defined, for the admin listing. Entries that no
+    /// longer decode (a hand-edited hash field) are skipped rather than
+    /// failing the whole listing.
+    pub async fn all(&self) -> AppResult<BTreeMap<String, FlagDefinition>> {
+        let mut conn = self.conn().await?;
+        let raw: std::collections::HashMap<String, String> = conn
+            .hgetall(FLAGS_KEY)
+            .await
+            .map_err(|e| AppError::ServiceUnavailable(format!("redis: {e}")))?;
+        Ok(raw
+            .into_iter()
+            .filter_map(|(name, value)| {
+                serde_json::from_str(&value).ok().map(|def| (name, def))
+            })
+            .collect())
+    }
+
+    /// Create or replace one flag.
+    pub async fn set(&self, name: &str, definition: &FlagDefinition) -> AppResult<()> {
+        if definition.rollout_percent.is_some_and(|p| p > 100) {
+            return Err(AppError::Validation(
+                "rollout_percent must be between 0 and 100".into(),
+            ));
+        }
+        let payload = serde_json::to_string(definition)
+            .map_err(|e| AppError::Internal(format!("serialize flag: {e}")))?;
+        let mut conn = self.conn().await?;
+        let _: () = conn
+            .hset(FLAGS_KEY, name, payload)
+            .await
+            .map_err(|e| AppError::ServiceUnavailable(format!("redis: {e}")))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rollout(percent: u8) -> FlagDefinition {
+        FlagDefinition {
+            rollout_percent: Some(percent),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn percentage_rollouts_bucket_users_deterministically() {
+        // Buckets come from the uuid value modulo 100, so these land at
+        // exactly 7, 42, and 99.
+        let low = Uuid::from_u128(7);
+        let mid = Uuid::from_u128(42);
+        let high = Uuid::from_u128(199);
+
+        assert!(flag_applies(&rollout(50), Some(low)));
+        assert!(flag_applies(&rollout(50), Some(mid)));
+        assert!(!flag_applies(&rollout(50), Some(high)));
+
+        // The boundary is exclusive: bucket 42 needs at least 43 percent.
+        assert!(!flag_applies(&rollout(42), Some(mid)));
+        assert!(flag_applies(&rollout(43), Some(mid)));
+
+        for user in [low, mid, high] {
+            assert!(!flag_applies(&rollout(0), Some(user)));
+            assert!(flag_applies(&rollout(100), Some(user)));
+        }
+    }
+
+    #[test]
+    fn a_user_keeps_their_bucket_as_the_rollout_grows() {
+        let user = Uuid::from_u128(17);
+        assert!(flag_applies(&rollout(18), Some(user)));
+        for percent in 18..=100 {
+            assert!(flag_applies(&rollout(percent), Some(user)));
+        }
+    }
+
+    #[test]
+    fn overrides_beat_the_rollout_in_both_directions() {
+        let pinned_on = Uuid::from_u128(99);
+        let pinned_off = Uuid::from_u128(1);
+        let definition = FlagDefinition {
+            enabled: false,
+            rollout_percent: Some(50),
+            enabled_users: vec![pinned_on],
+            disabled_users: vec![pinned_off],
+        };
+        assert!(flag_applies(&definition, Some(pinned_on)));
+        assert!(!flag_applies(&definition, Some(pinned_off)));
+    }
+
+    #[test]
+    fn the_off_list_wins_even_over_a_global_enable() {
+        let user = Uuid::from_u128(5);
+        let definition = FlagDefinition {
+            enabled: true,
+            disabled_users: vec![user],
+            ..Default::default()
+        };
+        assert!(!flag_applies(&definition, Some(user)));
+        assert!(flag_applies(&definition, Some(Uuid::from_u128(6))));
+    }
+
+    #[test]
+    fn anonymous_callers_only_see_globally_enabled_flags() {
+        assert!(!flag_applies(&rollout(100), None));
+        assert!(flag_applies(
+            &FlagDefinition { enabled: true, ..Default::default() },
+            None
+        ));
+    }
+}
@@ -0,0 +1,985 @@
+//! Local temp-dir storage for uploaded images awaiting analysis.
+//!
+//! Storage is content-deduplicated: bytes live once under
+//! `temp_dir/objects/<sha256>.<ext>` and each stored file is a hard link to
+//! its object. The filesystem's link count is the reference count, so
+//! deleting one reference never touches the bytes other references share;
+//! unreferenced objects are swept by the cleanup scan. (Hard links require
+//! the POSIX filesystems we deploy on.)
+//!
+//! Configuring `file_storage.backend = "s3"` (or, legacy, just setting
+//! `s3_bucket`) switches to an S3-compatible backend (AWS or MinIO via
+//! `s3_endpoint`): objects live under the same content-addressed keys,
+//! reads go through pre-signed URLs that expire with `file_ttl`, and
+//! expiry is delegated to the bucket's lifecycle rule. Local disk remains
+//! the default. The object key travels in the job payload as
+//! `image_path`, so the queue worker fetches the image from the same
+//! backend that stored it.
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use image::GenericImageView;
+use sha2::Digest;
+use tokio::{fs, io::AsyncWriteExt};
+use uuid::Uuid;
+
+use crate::{
+    config::FileStorageConfig,
+    errors::{AppError, AppResult},
+    utils::base64_image,
+};
+
+pub struct FileStorageService {
+    config: FileStorageConfig,
+    backend: StorageBackend,
+    /// Bytes not written because an identical object already existed.
+    dedup_saved_bytes: AtomicU64,
+}
+
+/// Where stored bytes actually live. For S3, `StoredFile::path` holds the
+/// object key instead of a filesystem path.
+pub enum StorageBackend {
+    Local,
+    S3(S3Backend),
+}
+
+pub struct S3Backend {
+    client: aws_sdk_s3::Client,
+    bucket: String,
+}
+
+impl S3Backend {
+    /// Build a client from explicit config. Only called when a bucket is
+    /// set; region defaults so MinIO setups don't have to invent one, and
+    /// a custom endpoint forces path-style addressing (MinIO serves
+    /// buckets as path prefixes, not subdomains).
+    fn from_config(config: &FileStorageConfig) -> Option<Self> {
+        let bucket = config.s3_bucket.clone()?;
+        let mut builder = aws_sdk_s3::config::Builder::new()
+            .behavior_version(aws_sdk_s3::config::BehaviorVersion::latest())
+            .region(aws_sdk_s3::config::Region::new(
+                config.s3_region.clone().unwrap_or_else(|| "us-east-1".into()),
+            ));
+        if let Some(endpoint) = &config.s3_endpoint {
+            builder = builder.endpoint_url(endpoint).force_path_style(true);
+        }
+        if let (Some(access), Some(secret)) = (&config.s3_access_key, &config.s3_secret_key) {
+            builder = builder.credentials_provider(aws_sdk_s3::config::Credentials::new(
+                access.clone(),
+                secret.clone(),
+                None,
+                None,
+                "file_storage_config",
+            ));
+        }
+        Some(Self {
+            client: aws_sdk_s3::Client::from_conf(builder.build()),
+            bucket,
+        })
+    }
+}
+
+/// Handle returned for a stored file; the path travels with the queued job.
+#[derive(Debug, Clone)]
+pub struct StoredFile {
+    pub id: Uuid,
+    pub path: PathBuf,
+    pub size_bytes: u64,
+}
+
+/// What one cleanup scan freed; feeds the metrics counters.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CleanupOutcome {
+    pub files_removed: u64,
+    pub bytes_freed: u64,
+}
+
+impl FileStorageService {
+    /// Build the service on the configured backend. Misconfiguration
+    /// (`backend = "s3"` without a bucket, or an unknown name) panics:
+    /// this runs once at startup, and a gateway silently writing uploads
+    /// to local disk when S3 was asked for would lose them across
+    /// replicas.
+    pub fn new(config: FileStorageConfig) -> Self {
+        let backend = match config.backend.as_deref() {
+            Some("local") => StorageBackend::Local,
+            Some("s3") => StorageBackend::S3(
+                S3Backend::from_config(&config)
+                    .expect("file_storage.backend = \"s3\" requires file_storage.s3_bucket"),
+            ),
+            Some(other) => panic!(
+                "unknown file_storage.backend {other:?} (expected \"local\" or \"s3\")"
+            ),
+            // Legacy inference: a configured bucket means S3.
+            None => match S3Backend::from_config(&config) {
+                Some(s3) => StorageBackend::S3(s3),
+                None => StorageBackend::Local,
+            },
+        };
+        Self {
+            config,
+            backend,
+            dedup_saved_bytes: AtomicU64::new(0),
+        }
+    }
+
+    pub fn config(&self) -> &FileStorageConfig {
+        &self.config
+    }
+
+    /// Bytes saved so far by dedup, for the stats endpoint.
+    pub fn dedup_saved_bytes(&self) -> u64 {
+        self.dedup_saved_bytes.load(Ordering::Relaxed)
+    }
+
+    fn path_for(&self, id: Uuid, extension: &str) -> PathBuf {
+        PathBuf::from(&self.config.temp_dir).join(format!("{id}.{extension}"))
+    }
+
+    fn objects_dir(&self) -> PathBuf {
+        PathBuf::from(&self.config.temp_dir).join("objects")
+    }
+
+    fn object_path(&self, hash: &str, extension: &str) -> PathBuf {
+        self.objects_dir().join(format!("{hash}.{extension}"))
+    }
+
+    /// Promote `tmp` (fully written content with the given hash) to its
+    /// content-addressed object, then hard-link the object as `link`.
+    /// Concurrent uploads of the same content race on the rename; whichever
+    /// lands first wins and the loser's bytes are discarded — either way
+    /// every caller ends up linked to one shared object.
+    async fn link_as_object(
+        &self,
+        tmp: &Path,
+        hash: &str,
+        extension: &str,
+        link: &Path,
+        size_bytes: u64,
+    ) -> AppResult<()> {
+        let object = self.object_path(hash, extension);
+        if fs::try_exists(&object).await.unwrap_or(false) {
+            let _ = fs::remove_file(tmp).await;
+            self.dedup_saved_bytes.fetch_add(size_bytes, Ordering::Relaxed);
+        } else {
+            fs::rename(tmp, &object)
+                .await
+                .map_err(|e| AppError::Internal(format!("promote object: {e}")))?;
+        }
+        fs::hard_link(&object, link)
+            .await
+            .map_err(|e| AppError::Internal(format!("link object: {e}")))
+    }
+
+    async fn ensure_dirs(&self) -> AppResult<()> {
+        fs::create_dir_all(self.objects_dir())
+            .await
+            .map_err(|e| AppError::Internal(format!("create temp dirs: {e}")))
+    }
+
+    /// Validate the format against `supported_formats` before accepting.
+    pub fn validate_format(&self, extension: &str) -> AppResult<()> {
+        let ext = extension.to_ascii_lowercase();
+        if self.config.supported_formats.iter().any(|f| *f == ext) {
+            Ok(())
+        } else {
+            Err(AppError::Validation(format!(
+                "unsupported image format '{ext}', supported: {}",
+                self.config.supported_formats.join(", ")
+            )))
+        }
+    }
+
+    /// Write image bytes to the temp dir, enforcing the size limit.
+    /// Identical bytes already on disk become a second reference, not a
+    /// second copy.
+    pub async fn store_file(&self, bytes: &[u8], extension: &str) -> AppResult<StoredFile> {
+        self.validate_format(extension)?;
+        if bytes.len() > self.config.max_file_size {
+            return Err(AppError::Validation(format!(
+                "file size {} exceeds limit {}",
+                bytes.len(),
+                self.config.max_file_size
+            )));
+        }
+        self.write_validated(bytes, extension).await
+    }
+
+    /// Store a reassembled chunked upload. Same pipeline as [`store_file`]
+    /// but bounded by `max_chunked_file_size` — the whole point of chunking
+    /// is accepting camera files above the per-request limit.
+    pub async fn store_chunked(&self, bytes: &[u8], extension: &str) -> AppResult<StoredFile> {
+        self.validate_format(extension)?;
+        if bytes.len() > self.config.max_chunked_file_size {
+            return Err(AppError::Validation(format!(
+                "file size {} exceeds the chunked upload limit {}",
+                bytes.len(),
+                self.config.max_chunked_file_size
+            )));
+        }
+        self.write_validated(bytes, extension).await
+    }
+
+    /// Write already-validated bytes to the configured backend.
+    async fn write_validated(&self, bytes: &[u8], extension: &str) -> AppResult<StoredFile> {
+        if let StorageBackend::S3(s3) = &self.backend {
+            return self.s3_store(s3, bytes, extension).await;
+        }
+        self.ensure_dirs().await?;
+        let id = Uuid::new_v4();
+        let path = self.path_for(id, extension);
+        let hash = format!("{:x}", sha2::Sha256::digest(bytes));
+
+        let tmp = self.objects_dir().join(format!("{id}.tmp"));
+        let mut file = fs::File::create(&tmp)
+            .await
+            .map_err(|e| AppError::Internal(format!("create file: {e}")))?;
+        file.write_all(bytes)
+            .await
+            .map_err(|e| AppError::Internal(format!("write file: {e}")))?;
+        file.flush()
+            .await
+            .map_err(|e| AppError::Internal(format!("flush file: {e}")))?;
+        drop(file);
+        self.link_as_object(&tmp, &hash, extension, &path, bytes.len() as u64)
+            .await?;
+        Ok(StoredFile {
+            id,
+            path,
+            size_bytes: bytes.len() as u64,
+        })
+    }
+
+    /// Stream-decode a base64 payload straight into a temp file, hashing as
+    /// it goes, and reject over-limit payloads before any decoding
+    /// allocates. Accepts an optional `data:image/...;base64,` prefix, which
+    /// also overrides the extension.
+    pub async fn store_base64(&self, payload: &str, extension: &str) -> AppResult<StoredFile> {
+        let (payload, prefix_format) = base64_image::strip_data_url_prefix(payload);
+        let extension = prefix_format.unwrap_or(extension);
+        self.validate_format(extension)?;
+
+        if let StorageBackend::S3(s3) = &self.backend {
+            // No local staging file to stream into; decode to memory (the
+            // size limit bounds the allocation) and upload in one shot.
+            let mut buffer = Vec::new();
+            base64_image::decode_to_writer(payload, self.config.max_file_size, &mut buffer)
+                .await?;
+            return self.s3_store(s3, &buffer, extension).await;
+        }
+
+        self.ensure_dirs().await?;
+        let id = Uuid::new_v4();
+        let path = self.path_for(id, extension);
+        let tmp = self.objects_dir().join(format!("{id}.tmp"));
+        let file = fs::File::create(&tmp)
+            .await
+            .map_err(|e| AppError::Internal(format!("create file: {e}")))?;
+        let mut writer = HashingWriter::new(file);
+        let size_bytes =
+            match base64_image::decode_to_writer(payload, self.config.max_file_size, &mut writer)
+                .await
+            {
+                Ok(size) => size,
+                Err(e) => {
+                    // Don't leave partial files behind on validation failure.
+                    drop(writer);
+                    let _ = fs::remove_file(&tmp).await;
+                    return Err(e);
+                }
+            };
+        let hash = writer.finish();
+        self.link_as_object(&tmp, &hash, extension, &path, size_bytes)
+            .await?;
+        Ok(StoredFile {
+            id,
+            path,
+            size_bytes,
+        })
+    }
+
+    /// Store a batch of base64 payloads all-or-nothing: if any image fails
+    /// validation or decoding, every file already written is removed so a
+    /// half-stored batch can never be queued.
+    pub async fn store_batch(
+        &self,
+        payloads: &[(String, String)],
+    ) -> AppResult<Vec<StoredFile>> {
+        let mut stored = Vec::with_capacity(payloads.len());
+        for (payload, extension) in payloads {
+            match self.store_base64(payload, extension).await {
+                Ok(file) => stored.push(file),
+                Err(e) => {
+                    for file in &stored {
+                        let _ = self.delete_file(&file.path).await;
+                    }
+                    return Err(e);
+                }
+            }
+        }
+        Ok(stored)
+    }
+
+    /// Preprocess a stored upload in place: bake in the EXIF orientation,
+    /// downscale to fit `max_dimension`, and re-encode as JPEG (which also
+    /// strips metadata, including GPS). Farmer photos are routinely 8–12 MB
+    /// 12MP shots taken sideways; the vision service needs neither the
+    /// pixels nor the rotation surprise. Formats outside the preprocessable
+    /// set pass through untouched; corrupt or truncated images fail with
+    /// `Validation`. Returns `(original_bytes, processed_bytes)` for the
+    /// savings stats.
+    pub async fn preprocess_image(&self, stored: &mut StoredFile) -> AppResult<(u64, u64)> {
+        let original_bytes = stored.size_bytes;
+        // Preprocessing rewrites local files in place; S3-stored uploads go
+        // to the vision service as uploaded rather than paying a
+        // download/re-upload round trip here.
+        if let StorageBackend::S3(_) = &self.backend {
+            return Ok((original_bytes, original_bytes));
+        }
+        let extension = stored
+            .path
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        if !matches!(extension.as_str(), "jpg" | "jpeg" | "png") {
+            return Ok((original_bytes, original_bytes));
+        }
+
+        let bytes = fs::read(&stored.path)
+            .await
+            .map_err(|e| AppError::Internal(format!("read for preprocessing: {e}")))?;
+        let max_dimension = self.config.max_dimension;
+        let quality = self.config.jpeg_quality;
+        // Decode + resize is CPU-bound; keep it off the async workers.
+        let processed = tokio::task::spawn_blocking(move || {
+            preprocess_bytes(&bytes, max_dimension, quality)
+        })
+        .await
+        .map_err(|e| AppError::Internal(format!("preprocessing task: {e}")))?;
+        let processed = match processed {
+            Ok(processed) => processed,
+            Err(e) => {
+                // The upload passed magic-byte checks but won't decode:
+                // reject it now rather than queueing a job doomed to fail.
+                let _ = fs::remove_file(&stored.path).await;
+                return Err(AppError::Validation(format!(
+                    "image is corrupt or truncated: {e}"
+                )));
+            }
+        };
+
+        // The processed output is itself deduplicated: two identical uploads
+        // compress to identical JPEGs, so they converge on one object. Drop
+        // the original link first — writing through it would clobber the
+        // shared object for every other reference.
+        let new_path = stored.path.with_extension("jpg");
+        let hash = format!("{:x}", sha2::Sha256::digest(&processed));
+        let tmp = self.objects_dir().join(format!("{}.tmp", stored.id));
+        fs::write(&tmp, &processed)
+            .await
+            .map_err(|e| AppError::Internal(format!("write processed file: {e}")))?;
+        let _ = fs::remove_file(&stored.path).await;
+        self.link_as_object(&tmp, &hash, "jpg", &new_path, processed.len() as u64)
+            .await?;
+        stored.path = new_path;
+        stored.size_bytes = processed.len() as u64;
+        Ok((original_bytes, stored.size_bytes))
+    }
+
+    /// Remove temp-dir references older than `file_ttl`, skipping any whose
+    /// file stem (the job id) appears in `keep` — those still back pending
+    /// jobs. Removing a reference frees no bytes on its own; a second pass
+    /// over the object store drops objects whose link count has fallen to
+    /// one (no references left), and only that pass counts freed bytes.
+    /// Per-file errors are logged and skipped so one bad entry can't abort
+    /// the scan.
+    pub async fn cleanup_expired(
+        &self,
+        keep: &std::collections::HashSet<String>,
+    ) -> AppResult<CleanupOutcome> {
+        use std::os::unix::fs::MetadataExt;
+
+        let mut outcome = CleanupOutcome::default();
+        // On S3, expiry belongs to the bucket lifecycle rule, not our scan.
+        if let StorageBackend::S3(_) = &self.backend {
+            return Ok(outcome);
+        }
+        let mut entries = match fs::read_dir(&self.config.temp_dir).await {
+            Ok(entries) => entries,
+            // Nothing uploaded yet: the dir is created lazily on first store.
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(outcome),
+            Err(e) => return Err(AppError::Internal(format!("scan temp dir: {e}"))),
+        };
+        loop {
+            let entry = match entries.next_entry().await {
+                Ok(Some(entry)) => entry,
+                Ok(None) => break,
+                Err(e) => {
+                    tracing::warn!(error = %e, "cleanup: read_dir entry failed");
+                    break;
+                }
+            };
+            let path = entry.path();
+            let stem = path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or_default()
+                .to_string();
+            if keep.contains(&stem) {
+                continue;
+            }
+            let metadata = match entry.metadata().await {
+                Ok(m) => m,
+                Err(e) => {
+                    tracing::warn!(path = %path.display(), error = %e, "cleanup: stat failed");
+                    continue;
+                }
+            };
+            if metadata.is_dir() {
+                // The object store itself; swept below.
+                continue;
+            }
+            let age = metadata
+                .modified()
+                .ok()
+                .and_then(|m| m.elapsed().ok())
+                .unwrap_or_default();
+            if age < self.config.file_ttl {
+                continue;
+            }
+            match fs::remove_file(&path).await {
+                Ok(()) => {
+                    outcome.files_removed += 1;
+                    // Legacy standalone files (no backing object) free their
+                    // bytes here; shared objects are accounted for below.
+                    if metadata.nlink() == 1 {
+                        outcome.bytes_freed += metadata.len();
+                    }
+                }
+                Err(e) => {
+                    tracing::warn!(path = %path.display(), error = %e, "cleanup: remove failed");
+                }
+            }
+        }
+
+        // Sweep the object store: an object whose link count is one is no
+        // longer referenced by any stored file and its retention window has
+        // its own mtime clock.
+        let mut objects = match fs::read_dir(self.objects_dir()).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(outcome),
+            Err(e) => return Err(AppError::Internal(format!("scan object store: {e}"))),
+        };
+        loop {
+            let entry = match objects.next_entry().await {
+                Ok(Some(entry)) => entry,
+                Ok(None) => break,
+                Err(e) => {
+                    tracing::warn!(error = %e, "cleanup: object read_dir entry failed");
+                    break;
+                }
+            };
+            let path = entry.path();
+            let metadata = match entry.metadata().await {
+                Ok(m) => m,
+                Err(e) => {
+                    tracing::warn!(path = %path.display(), error = %e, "cleanup: stat failed");
+                    continue;
+                }
+            };
+            if metadata.nlink() > 1 {
+                continue;
+            }
+            let age = metadata
+                .modified()
+                .ok()
+                .and_then(|m| m.elapsed().ok())
+                .unwrap_or_default();
+            if age < self.config.file_ttl {
+                continue;
+            }
+            match fs::remove_file(&path).await {
+                Ok(()) => {
+                    outcome.files_removed += 1;
+                    outcome.bytes_freed += metadata.len();
+                }
+                Err(e) => {
+                    tracing::warn!(path = %path.display(), error = %e, "cleanup: remove failed");
+                }
+            }
+        }
+        Ok(outcome)
+    }
+
+    /// Fetch stored bytes from whichever backend holds them, given the
+    /// path (local) or object key (S3) that travels in the job payload.
+    pub async fn read_file(&self, path: &Path) -> AppResult<Vec<u8>> {
+        if let StorageBackend::S3(s3) = &self.backend {
+            let object = s3
+                .client
+                .get_object()
+                .bucket(&s3.bucket)
+                .key(path.to_string_lossy().as_ref())
+                .send()
+                .await
+                .map_err(|e| AppError::Internal(format!("s3 get: {e}")))?;
+            let bytes = object
+                .body
+                .collect()
+                .await
+                .map_err(|e| AppError::Internal(format!("s3 read body: {e}")))?;
+            return Ok(bytes.into_bytes().to_vec());
+        }
+        fs::read(path)
+            .await
+            .map_err(|e| AppError::Internal(format!("read file: {e}")))
+    }
+
+    pub async fn delete_file(&self, path: &PathBuf) -> AppResult<()> {
+        // S3 has no link counts: deleting drops the object itself, taking
+        // any deduped sibling reference with it. Uploads are short-lived,
+        // so that trade is acceptable there.
+        if let StorageBackend::S3(s3) = &self.backend {
+            s3.client
+                .delete_object()
+                .bucket(&s3.bucket)
+                .key(path.to_string_lossy().as_ref())
+                .send()
+                .await
+                .map_err(|e| AppError::Internal(format!("s3 delete: {e}")))?;
+            return Ok(());
+        }
+        match fs::remove_file(path).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(AppError::Internal(format!("delete file: {e}"))),
+        }
+    }
+
+    /// A URL clients can fetch the stored file from. Local files are read
+    /// off disk by the gateway and have no URL; S3 objects get a
+    /// pre-signed GET whose expiry matches `file_ttl`.
+    pub async fn get_file_url(&self, stored: &StoredFile) -> AppResult<Option<String>> {
+        let StorageBackend::S3(s3) = &self.backend else {
+            return Ok(None);
+        };
+        let presigning =
+            aws_sdk_s3::presigning::PresigningConfig::expires_in(self.config.file_ttl)
+                .map_err(|e| AppError::Internal(format!("presign config: {e}")))?;
+        let request = s3
+            .client
+            .get_object()
+            .bucket(&s3.bucket)
+            .key(stored.path.to_string_lossy().as_ref())
+            .presigned(presigning)
+            .await
+            .map_err(|e| AppError::Internal(format!("s3 presign: {e}")))?;
+        Ok(Some(request.uri().to_string()))
+    }
+
+    /// Upload to the content-addressed key, skipping the transfer when an
+    /// identical object already exists — the same dedup the local backend
+    /// gets from hard links, via `HeadObject` instead of link counts.
+    async fn s3_store(
+        &self,
+        s3: &S3Backend,
+        bytes: &[u8],
+        extension: &str,
+    ) -> AppResult<StoredFile> {
+        let hash = format!("{:x}", sha2::Sha256::digest(bytes));
+        let key = format!("objects/{hash}.{extension}");
+        let exists = s3
+            .client
+            .head_object()
+            .bucket(&s3.bucket)
+            .key(&key)
+            .send()
+            .await
+            .is_ok();
+        if exists {
+            self.dedup_saved_bytes
+                .fetch_add(bytes.len() as u64, Ordering::Relaxed);
+        } else {
+            s3.client
+                .put_object()
+                .bucket(&s3.bucket)
+                .key(&key)
+                .body(aws_sdk_s3::primitives::ByteStream::from(bytes.to_vec()))
+                .send()
+                .await
+                .map_err(|e| AppError::Internal(format!("s3 put: {e}")))?;
+        }
+        Ok(StoredFile {
+            id: Uuid::new_v4(),
+            path: PathBuf::from(key),
+            size_bytes: bytes.len() as u64,
+        })
+    }
+}
+
+/// `AsyncWrite` wrapper that feeds every written byte into a SHA-256
+/// hasher, so streamed uploads are hashed in the same pass that writes
+/// them to disk.
+struct HashingWriter<W> {
+    inner: W,
+    hasher: sha2::Sha256,
+}
+
+impl<W> HashingWriter<W> {
+    fn new(inner: W) -> Self {
+        Self { inner, hasher: sha2::Sha256::new() }
+    }
+
+    /// Finish the hash, returning its lowercase hex digest.
+    fn finish(self) -> String {
+        format!("{:x}", self.hasher.finalize())
+    }
+}
+
+impl<W: tokio::io::AsyncWrite + Unpin> tokio::io::AsyncWrite for HashingWriter<W> {
+    fn poll_write(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        buf: &[u8],
+    ) -> std::task::Poll<std::io::Result<usize>> {
+        let poll = std::pin::Pin::new(&mut self.inner).poll_write(cx, buf);
+        if let std::task::Poll::Ready(Ok(written)) = &poll {
+            self.hasher.update(&buf[..*written]);
+        }
+        poll
+    }
+
+    fn poll_flush(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        std::pin::Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        std::pin::Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+/// Apply the EXIF orientation (if any), resize to fit within
+/// `max_dimension` on the longest side (never upscales), and re-encode as
+/// JPEG at the given quality. The re-encode writes no EXIF, so metadata is
+/// stripped as a side effect.
+fn preprocess_bytes(
+    bytes: &[u8],
+    max_dimension: u32,
+    quality: u8,
+) -> Result<Vec<u8>, image::ImageError> {
+    let orientation = crate::utils::image_sniff::jpeg_orientation(bytes).unwrap_or(1);
+    let decoded = apply_orientation(image::load_from_memory(bytes)?, orientation);
+    let (width, height) = decoded.dimensions();
+    let resized = if width.max(height) > max_dimension {
+        decoded.resize(max_dimension, max_dimension, image::imageops::FilterType::Triangle)
+    } else {
+        decoded
+    };
+    let mut out = Vec::new();
+    let mut encoder =
+        image::codecs::jpeg::JpegEncoder::new_with_quality(std::io::Cursor::new(&mut out), quality);
+    encoder.encode_image(&resized.to_rgb8())?;
+    Ok(out)
+}
+
+/// Transform pixels so an orientation-ignorant viewer sees the image
+/// upright (EXIF orientation values 1–8).
+fn apply_orientation(img: image::DynamicImage, orientation: u16) -> image::DynamicImage {
+    match orientation {
+        2 => img.fliph(),
+        3 => img.rotate180(),
+        4 => img.flipv(),
+        5 => img.rotate90().fliph(),
+        6 => img.rotate90(),
+        7 => img.rotate270().fliph(),
+        8 => img.rotate270(),
+        _ => img,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dslr_sized_png_compresses_under_500kb() {
+        // Gradient fill so the PNG isn't trivially compressible to nothing.
+        let img = image::RgbImage::from_fn(4000, 3000, |x, y| {
+            image::Rgb([(x % 256) as u8, (y % 256) as u8, ((x + y) % 256) as u8])
+        });
+        let mut png = Vec::new();
+        image::DynamicImage::ImageRgb8(img)
+            .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+
+        let jpeg = preprocess_bytes(&png, 1024, 85).unwrap();
+        assert!(jpeg.len() < 500 * 1024, "compressed to {} bytes", jpeg.len());
+
+        let decoded = image::load_from_memory(&jpeg).unwrap();
+        let (w, h) = decoded.dimensions();
+        assert!(w <= 1024 && h <= 1024);
+        // Aspect ratio preserved: 4000x3000 -> 1024x768.
+        assert_eq!((w, h), (1024, 768));
+    }
+
+    #[test]
+    fn small_images_are_not_upscaled() {
+        let img = image::RgbImage::from_pixel(200, 100, image::Rgb([30, 120, 60]));
+        let mut png = Vec::new();
+        image::DynamicImage::ImageRgb8(img)
+            .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+
+        let jpeg = preprocess_bytes(&png, 1024, 85).unwrap();
+        let decoded = image::load_from_memory(&jpeg).unwrap();
+        assert_eq!(decoded.dimensions(), (200, 100));
+    }
+
+    #[test]
+    fn orientation_six_swaps_the_axes() {
+        // A camera writing orientation 6 stored the sensor image rotated
+        // 90° CCW; applying it must rotate CW, swapping width and height.
+        let img = image::DynamicImage::ImageRgb8(image::RgbImage::new(300, 200));
+        let upright = apply_orientation(img, 6);
+        assert_eq!(upright.dimensions(), (200, 300));
+
+        let img = image::DynamicImage::ImageRgb8(image::RgbImage::new(300, 200));
+        assert_eq!(apply_orientation(img, 1).dimensions(), (300, 200));
+    }
+
+    #[test]
+    fn garbage_bytes_fail_to_decode() {
+        assert!(preprocess_bytes(b"definitely not an image", 1024, 85).is_err());
+    }
+
+    pub(super) fn test_config(temp_dir: &std::path::Path, file_ttl: std::time::Duration) -> FileStorageConfig {
+        FileStorageConfig {
+            temp_dir: temp_dir.display().to_string(),
+            backend: None,
+            s3_bucket: None,
+            s3_region: None,
+            s3_endpoint: None,
+            s3_access_key: None,
+            s3_secret_key: None,
+            max_file_size: 10 * 1024 * 1024,
+            max_chunked_file_size: 30 * 1024 * 1024,
+            chunk_size: 2 * 1024 * 1024,
+            supported_formats: vec!["jpg".into(), "png".into()],
+            file_ttl,
+            cleanup_interval: std::time::Duration::from_secs(60),
+            max_dimension: 1024,
+            jpeg_quality: 85,
+        }
+    }
+
+    #[tokio::test]
+    async fn cleanup_removes_only_expired_unreferenced_files() {
+        let dir = std::env::temp_dir().join(format!("cleanup-test-{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).await.unwrap();
+        fs::write(dir.join("expired-job.jpg"), b"old").await.unwrap();
+        fs::write(dir.join("pending-job.jpg"), b"in use").await.unwrap();
+
+        // TTL zero: everything on disk counts as expired, so only the keep
+        // set protects the pending job's file.
+        let service = FileStorageService::new(test_config(&dir, std::time::Duration::ZERO));
+        let keep = std::collections::HashSet::from(["pending-job".to_string()]);
+        let outcome = service.cleanup_expired(&keep).await.unwrap();
+        assert_eq!(outcome.files_removed, 1);
+        assert_eq!(outcome.bytes_freed, 3);
+        assert!(!dir.join("expired-job.jpg").exists());
+        assert!(dir.join("pending-job.jpg").exists());
+
+        // Generous TTL: freshly written files stay.
+        let service = FileStorageService::new(test_config(&dir, std::time::Duration::from_secs(3600)));
+        let outcome = service.cleanup_expired(&Default::default()).await.unwrap();
+        assert_eq!(outcome.files_removed, 0);
+        assert!(dir.join("pending-job.jpg").exists());
+
+        let _ = fs::remove_dir_all(&dir).await;
+    }
+
+    #[tokio::test]
+    async fn concurrent_identical_uploads_share_one_object() {
+        let dir = std::env::temp_dir().join(format!("dedup-concurrent-{}", Uuid::new_v4()));
+        let service =
+            FileStorageService::new(test_config(&dir, std::time::Duration::from_secs(3600)));
+        let bytes = b"identical farmer photo bytes";
+
+        let (a, b, c) = tokio::join!(
+            service.store_file(bytes, "jpg"),
+            service.store_file(bytes, "jpg"),
+            service.store_file(bytes, "jpg"),
+        );
+        let stored = [a.unwrap(), b.unwrap(), c.unwrap()];
+        for file in &stored {
+            assert_eq!(fs::read(&file.path).await.unwrap(), bytes);
+        }
+
+        // All three references resolve to a single object, no stray temp
+        // files left behind by the losers of the promote race.
+        let mut entries = fs::read_dir(dir.join("objects")).await.unwrap();
+        let mut objects = 0;
+        while let Some(entry) = entries.next_entry().await.unwrap() {
+            let extension = entry.path().extension().and_then(|e| e.to_str()).map(String::from);
+            assert_ne!(extension.as_deref(), Some("tmp"));
+            objects += 1;
+        }
+        assert_eq!(objects, 1);
+
+        let _ = fs::remove_dir_all(&dir).await;
+    }
+
+    #[tokio::test]
+    async fn deleting_a_reference_keeps_shared_bytes_until_the_last_one_goes() {
+        use base64::Engine;
+
+        let dir = std::env::temp_dir().join(format!("dedup-refcount-{}", Uuid::new_v4()));
+        let service =
+            FileStorageService::new(test_config(&dir, std::time::Duration::from_secs(3600)));
+        let bytes = b"shared upload bytes";
+
+        let first = service.store_file(bytes, "jpg").await.unwrap();
+        // The base64 path hashes while streaming and must land on the same
+        // object as the raw path.
+        let encoded = base64::engine::general_purpose::STANDARD.encode(bytes);
+        let second = service.store_base64(&encoded, "jpg").await.unwrap();
+        assert_eq!(service.dedup_saved_bytes(), bytes.len() as u64);
+
+        service.delete_file(&first.path).await.unwrap();
+        assert_eq!(fs::read(&second.path).await.unwrap(), bytes);
+        let outcome = service.cleanup_expired(&Default::default()).await.unwrap();
+        assert_eq!(outcome.bytes_freed, 0, "object still referenced");
+
+        // Last reference gone: a TTL-zero sweep now reclaims the bytes.
+        service.delete_file(&second.path).await.unwrap();
+        let service = FileStorageService::new(test_config(&dir, std::time::Duration::ZERO));
+        let outcome = service.cleanup_expired(&Default::default()).await.unwrap();
+        assert_eq!(outcome.bytes_freed, bytes.len() as u64);
+        let mut entries = fs::read_dir(dir.join("objects")).await.unwrap();
+        assert!(entries.next_entry().await.unwrap().is_none());
+
+        let _ = fs::remove_dir_all(&dir).await;
+    }
+
+    #[test]
+    fn explicit_local_backend_wins_over_a_configured_bucket() {
+        let mut config = test_config(&std::env::temp_dir(), std::time::Duration::ZERO);
+        config.s3_bucket = Some("uploads".into());
+        config.backend = Some("local".into());
+        let service = FileStorageService::new(config);
+        assert!(matches!(service.backend, StorageBackend::Local));
+    }
+
+    #[test]
+    #[should_panic(expected = "requires file_storage.s3_bucket")]
+    fn s3_backend_without_a_bucket_refuses_to_start() {
+        let mut config = test_config(&std::env::temp_dir(), std::time::Duration::ZERO);
+        config.backend = Some("s3".into());
+        FileStorageService::new(config);
+    }
+
+    #[test]
+    #[should_panic(expected = "unknown file_storage.backend")]
+    fn unknown_backend_names_refuse_to_start() {
+        let mut config = test_config(&std::env::temp_dir(), std::time::Duration::ZERO);
+        config.backend = Some("gcs".into());
+        FileStorageService::new(config);
+    }
+
+    #[tokio::test]
+    async fn read_file_round_trips_local_bytes() {
+        let dir = std::env::temp_dir().join(format!("read-file-{}", Uuid::new_v4()));
+        let service =
+            FileStorageService::new(test_config(&dir, std::time::Duration::from_secs(3600)));
+        let stored = service.store_file(b"bytes for the worker", "jpg").await.unwrap();
+        assert_eq!(
+            service.read_file(&stored.path).await.unwrap(),
+            b"bytes for the worker"
+        );
+        let _ = fs::remove_dir_all(&dir).await;
+    }
+
+    #[tokio::test]
+    async fn cleanup_of_a_missing_temp_dir_is_a_no_op() {
+        let dir = std::env::temp_dir().join(format!("cleanup-missing-{}", Uuid::new_v4()));
+        let service = FileStorageService::new(test_config(&dir, std::time::Duration::ZERO));
+        let outcome = service.cleanup_expired(&Default::default()).await.unwrap();
+        assert_eq!(outcome.files_removed, 0);
+    }
+}
+
+/// End-to-end checks against real S3 semantics via a MinIO container.
+/// Excluded from the default run: `cargo test -- --ignored` with a Docker
+/// daemon available.
+#[cfg(test)]
+mod s3_tests {
+    use super::*;
+    use testcontainers::{clients, core::WaitFor, GenericImage, RunnableImage};
+
+    async fn minio_service(docker: &clients::Cli) -> (FileStorageService, testcontainers::Container<'_, GenericImage>) {
+        let image = RunnableImage::from(
+            GenericImage::new("minio/minio", "latest")
+                .with_env_var("MINIO_ROOT_USER", "minioadmin")
+                .with_env_var("MINIO_ROOT_PASSWORD", "minioadmin")
+                .with_wait_for(WaitFor::message_on_stderr("API:")),
+        )
+        .with_args(vec!["server".into(), "/data".into()]);
+        let container = docker.run(image);
+        let endpoint = format!("http://127.0.0.1:{}", container.get_host_port_ipv4(9000));
+
+        let temp = std::env::temp_dir();
+        let mut config = tests::test_config(&temp, std::time::Duration::from_secs(300));
+        config.backend = Some("s3".into());
+        config.s3_bucket = Some("uploads".into());
+        config.s3_endpoint = Some(endpoint);
+        config.s3_access_key = Some("minioadmin".into());
+        config.s3_secret_key = Some("minioadmin".into());
+        let service = FileStorageService::new(config);
+
+        // Create the bucket through the same client the service built.
+        let StorageBackend::S3(s3) = &service.backend else {
+            panic!("expected the S3 backend to be selected");
+        };
+        s3.client
+            .create_bucket()
+            .bucket("uploads")
+            .send()
+            .await
+            .expect("create bucket");
+        (service, container)
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a docker daemon"]
+    async fn s3_backend_round_trips_store_url_and_delete() {
+        let docker = clients::Cli::default();
+        let (service, _container) = minio_service(&docker).await;
+
+        let bytes = b"\xff\xd8\xff\xe0 not a real jpeg but good enough".to_vec();
+        let stored = service.store_file(&bytes, "jpg").await.unwrap();
+
+        // The pre-signed URL serves the bytes without credentials.
+        let url = service.get_file_url(&stored).await.unwrap().expect("s3 files have urls");
+        let fetched = reqwest::get(&url).await.unwrap().bytes().await.unwrap();
+        assert_eq!(fetched.as_ref(), bytes.as_slice());
+
+        // An identical second upload dedups onto the same key.
+        let again = service.store_file(&bytes, "jpg").await.unwrap();
+        assert_eq!(again.path, stored.path);
+        assert_eq!(service.dedup_saved_bytes(), bytes.len() as u64);
+
+        // The worker-side fetch reads through the same backend and key.
+        assert_eq!(service.read_file(&stored.path).await.unwrap(), bytes);
+
+        service.delete_file(&stored.path).await.unwrap();
+        assert_eq!(reqwest::get(&url).await.unwrap().status(), 404);
+    }
+}
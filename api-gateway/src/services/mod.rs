@@ -0,0 +1,13 @@
+pub mod advice;
+pub mod ai4thai;
+pub mod alerts;
+pub mod cache;
+pub mod cleanup;
+pub mod conversations;
+pub mod feature_flags;
+pub mod file_storage;
+pub mod quota;
+pub mod rabbitmq;
+pub mod registry;
+pub mod scheduler;
+pub mod webhooks;
@@ -0,0 +1,230 @@
+//! Monthly per-user usage quotas for the free tier.
+//!
+//! Distinct from the sliding-window rate limiter: that protects the
+//! service from bursts, this caps how much a role may consume per
+//! calendar month. Counters live in Redis under
+//! `usage:{user_id}:{yyyymm}:{kind}` and are advanced with `INCRBY`, so
+//! concurrent requests serialize on the increment — the last admitted
+//! request is the one that reaches the limit, and everything after it is
+//! rejected. A rejected attempt still bumped the counter, so the raw
+//! number can sit above the limit by the rejected request's size;
+//! admitted work never exceeds it.
+
+use chrono::{DateTime, Datelike, TimeZone, Utc};
+use redis::AsyncCommands;
+use uuid::Uuid;
+
+use crate::{
+    config::QuotaConfig,
+    errors::{AppError, AppResult},
+    AuthUser,
+};
+
+/// Counters outlive their month by a few days for the usage endpoint,
+/// then expire on their own.
+const COUNTER_TTL_SECS: u64 = 40 * 24 * 60 * 60;
+
+/// What a quota-metered request consumes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UsageKind {
+    ChatMessage,
+    VisionAnalysis,
+}
+
+impl UsageKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UsageKind::ChatMessage => "chat_messages",
+            UsageKind::VisionAnalysis => "vision_analyses",
+        }
+    }
+}
+
+fn month_stamp(now: DateTime<Utc>) -> String {
+    format!("{:04}{:02}", now.year(), now.month())
+}
+
+pub(crate) fn usage_key(user_id: Uuid, kind: UsageKind, now: DateTime<Utc>) -> String {
+    format!("usage:{user_id}:{}:{}", month_stamp(now), kind.as_str())
+}
+
+/// First instant of the next calendar month — when every counter resets.
+pub fn window_reset(now: DateTime<Utc>) -> DateTime<Utc> {
+    let (year, month) = if now.month() == 12 {
+        (now.year() + 1, 1)
+    } else {
+        (now.year(), now.month() + 1)
+    };
+    Utc.with_ymd_and_hms(year, month, 1, 0, 0, 0)
+        .single()
+        .expect("first of month is always valid")
+}
+
+/// The monthly allowance that applies to `roles`, or `None` for
+/// unlimited. Any role missing from the config lifts the cap entirely;
+/// among listed roles the most generous wins.
+pub fn limit_for(config: &QuotaConfig, roles: &[String], kind: UsageKind) -> Option<u64> {
+    if !config.enabled || roles.is_empty() {
+        return None;
+    }
+    let mut best = 0;
+    for role in roles {
+        let quota = config.monthly.get(role)?;
+        best = best.max(match kind {
+            UsageKind::ChatMessage => quota.chat_messages,
+            UsageKind::VisionAnalysis => quota.vision_analyses,
+        });
+    }
+    Some(best)
+}
+
+pub struct QuotaService {
+    client: redis::Client,
+    config: QuotaConfig,
+}
+
+impl QuotaService {
+    pub fn new(client: redis::Client, config: QuotaConfig) -> Self {
+        Self { client, config }
+    }
+
+    pub fn config(&self) -> &QuotaConfig {
+        &self.config
+    }
+
+    async fn conn(&self) -> AppResult<redis::aio::MultiplexedConnection> {
+        self.client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(|e| AppError::ServiceUnavailable(format!("redis: {e}")))
+    }
+
+    /// Count `amount` units against the caller's monthly allowance,
+    /// rejecting with a 429 (and the reset instant) once it is spent.
+    /// Anonymous callers and unlimited roles pass through untouched.
+    pub async fn admit_many(
+        &self,
+        user: Option<&AuthUser>,
+        kind: UsageKind,
+        amount: u64,
+    ) -> AppResult<()> {
+        let Some(user) = user else { return Ok(()) };
+        let Some(limit) = limit_for(&self.config, &user.roles, kind) else {
+            return Ok(());
+        };
+        let now = Utc::now();
+        let key = usage_key(user.user_id, kind, now);
+        let mut conn = self.conn().await?;
+        let (count,): (u64,) = redis::pipe()
+            .atomic()
+            .incr(&key, amount)
+            .expire(&key, COUNTER_TTL_SECS as i64)
+            .ignore()
+            .query_async(&mut conn)
+            .await
+            .map_err(|e| AppError::ServiceUnavailable(format!("redis: {e}")))?;
+        if count > limit {
+            return Err(AppError::QuotaExceeded {
+                resets_at: window_reset(now),
+            });
+        }
+        Ok(())
+    }
+
+    pub async fn admit(&self, user: Option<&AuthUser>, kind: UsageKind) -> AppResult<()> {
+        self.admit_many(user, kind, 1).await
+    }
+
+    /// This month's consumption of one kind, for the usage endpoint.
+    pub async fn used(&self, user_id: Uuid, kind: UsageKind) -> AppResult<u64> {
+        let mut conn = self.conn().await?;
+        let count: Option<u64> = conn
+            .get(usage_key(user_id, kind, Utc::now()))
+            .await
+            .map_err(|e| AppError::ServiceUnavailable(format!("redis: {e}")))?;
+        Ok(count.unwrap_or(0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::config::MonthlyQuota;
+
+    use super::*;
+
+    fn config(entries: &[(&str, u64, u64)]) -> QuotaConfig {
+        QuotaConfig {
+            enabled: true,
+            monthly: entries
+                .iter()
+                .map(|(role, chat, vision)| {
+                    (
+                        role.to_string(),
+                        MonthlyQuota { chat_messages: *chat, vision_analyses: *vision },
+                    )
+                })
+                .collect::<HashMap<_, _>>(),
+        }
+    }
+
+    fn roles(names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    #[test]
+    fn limits_pick_the_most_generous_listed_role() {
+        let config = config(&[("farmer", 100, 20), ("reviewer", 500, 50)]);
+        let both = roles(&["farmer", "reviewer"]);
+        assert_eq!(limit_for(&config, &both, UsageKind::ChatMessage), Some(500));
+        assert_eq!(limit_for(&config, &both, UsageKind::VisionAnalysis), Some(50));
+        assert_eq!(
+            limit_for(&config, &roles(&["farmer"]), UsageKind::VisionAnalysis),
+            Some(20)
+        );
+    }
+
+    #[test]
+    fn unlisted_roles_and_disabled_config_mean_unlimited() {
+        let config = config(&[("farmer", 100, 20)]);
+        // An officer is not in the table, so farmer+officer is uncapped.
+        assert_eq!(
+            limit_for(&config, &roles(&["farmer", "officer"]), UsageKind::ChatMessage),
+            None
+        );
+
+        let disabled = QuotaConfig { enabled: false, ..config };
+        assert_eq!(
+            limit_for(&disabled, &roles(&["farmer"]), UsageKind::ChatMessage),
+            None
+        );
+    }
+
+    #[test]
+    fn counters_reset_on_the_first_of_the_next_month() {
+        let mid_month = Utc.with_ymd_and_hms(2026, 8, 30, 17, 4, 5).unwrap();
+        assert_eq!(
+            window_reset(mid_month),
+            Utc.with_ymd_and_hms(2026, 9, 1, 0, 0, 0).unwrap()
+        );
+        // December rolls the year.
+        let december = Utc.with_ymd_and_hms(2026, 12, 31, 23, 59, 59).unwrap();
+        assert_eq!(
+            window_reset(december),
+            Utc.with_ymd_and_hms(2027, 1, 1, 0, 0, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn usage_keys_separate_users_months_and_kinds() {
+        let user = Uuid::new_v4();
+        let august = Utc.with_ymd_and_hms(2026, 8, 15, 0, 0, 0).unwrap();
+        let september = Utc.with_ymd_and_hms(2026, 9, 1, 0, 0, 0).unwrap();
+
+        let key = usage_key(user, UsageKind::ChatMessage, august);
+        assert_eq!(key, format!("usage:{user}:202608:chat_messages"));
+        assert_ne!(key, usage_key(user, UsageKind::ChatMessage, september));
+        assert_ne!(key, usage_key(user, UsageKind::VisionAnalysis, august));
+    }
+}
@@ -0,0 +1,477 @@
+//! Thin wrapper around the lapin channel used for job publishing.
+//!
+//! Topology: rejected messages on the vision queue dead-letter into a retry
+//! queue, which TTLs them back onto the vision queue after `retry_delay`
+//! (each cycle increments the broker's `x-death` count); the worker parks
+//! messages that exhaust their retries on the DLQ with their retry count
+//! in an `x-retry-count` header. The gateway runs a DLQ consumer that
+//! routes each parked message: remaining budget means requeue onto the
+//! main queue, exhausted budget means a row in the `failed_jobs` table,
+//! which is what the admin endpoints read and requeue from.
+
+use std::sync::Arc;
+
+use futures_util::StreamExt;
+use lapin::{
+    options::{
+        BasicAckOptions, BasicConsumeOptions, BasicNackOptions, BasicPublishOptions,
+        QueueDeclareOptions,
+    },
+    types::{AMQPValue, FieldTable},
+    BasicProperties, Channel, Connection, ConnectionProperties,
+};
+use serde::Serialize;
+use shared::queue::{JobPriority, MAX_PRIORITY};
+use uuid::Uuid;
+
+use crate::{
+    config::RabbitMQConfig,
+    errors::{AppError, AppResult},
+};
+
+/// One queue's broker-side counters, from a passive declare.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct QueueStats {
+    pub messages: u32,
+    pub consumers: u32,
+}
+
+pub struct RabbitMQService {
+    connection: Connection,
+    channel: Channel,
+    config: RabbitMQConfig,
+}
+
+impl RabbitMQService {
+    pub async fn new(config: RabbitMQConfig) -> AppResult<Self> {
+        let connection = Connection::connect(&config.url, ConnectionProperties::default())
+            .await
+            .map_err(|e| AppError::ServiceUnavailable(format!("rabbitmq connect: {e}")))?;
+        let channel = connection
+            .create_channel()
+            .await
+            .map_err(|e| AppError::ServiceUnavailable(format!("rabbitmq channel: {e}")))?;
+        let durable = QueueDeclareOptions {
+            durable: true,
+            ..Default::default()
+        };
+        // Main queue: priority-ordered, rejections dead-letter into the
+        // retry queue.
+        let mut main_args = FieldTable::default();
+        main_args.insert("x-max-priority".into(), AMQPValue::ShortShortUInt(MAX_PRIORITY));
+        main_args.insert("x-dead-letter-exchange".into(), AMQPValue::LongString("".into()));
+        main_args.insert(
+            "x-dead-letter-routing-key".into(),
+            AMQPValue::LongString(config.retry_queue.as_str().into()),
+        );
+        channel
+            .queue_declare(&config.vision_queue, durable, main_args)
+            .await
+            .map_err(|e| AppError::ServiceUnavailable(format!("queue declare: {e}")))?;
+        // Retry queue: no consumers; messages expire back onto the main
+        // queue after the retry delay.
+        let mut retry_args = FieldTable::default();
+        retry_args.insert(
+            "x-message-ttl".into(),
+            AMQPValue::LongInt(config.retry_delay.as_millis() as i32),
+        );
+        retry_args.insert("x-dead-letter-exchange".into(), AMQPValue::LongString("".into()));
+        retry_args.insert(
+            "x-dead-letter-routing-key".into(),
+            AMQPValue::LongString(config.vision_queue.as_str().into()),
+        );
+        channel
+            .queue_declare(&config.retry_queue, durable, retry_args)
+            .await
+            .map_err(|e| AppError::ServiceUnavailable(format!("retry queue declare: {e}")))?;
+        channel
+            .queue_declare(&config.dlq, durable, FieldTable::default())
+            .await
+            .map_err(|e| AppError::ServiceUnavailable(format!("dlq declare: {e}")))?;
+        // Terminal-job events for the webhook dispatcher.
+        channel
+            .queue_declare(&config.webhook_queue, durable, FieldTable::default())
+            .await
+            .map_err(|e| AppError::ServiceUnavailable(format!("webhook queue declare: {e}")))?;
+        Ok(Self {
+            connection,
+            channel,
+            config,
+        })
+    }
+
+    /// Publish a vision job at its declared priority. Typed on purpose:
+    /// every job on the queue is a [`shared::queue::VisionJobRequestV1`],
+    /// so the gateway and worker cannot drift apart field by field.
+    pub async fn publish(&self, job: &shared::queue::VisionJobRequestV1) -> AppResult<()> {
+        self.publish_raw(job, job.priority).await
+    }
+
+    /// Untyped publish onto the vision queue. Kept for payloads that predate
+    /// the versioned contract (the LINE relay) and for requeue paths that
+    /// must never drop a message over a shape problem.
+    pub(crate) async fn publish_raw<T: Serialize>(
+        &self,
+        payload: &T,
+        priority: JobPriority,
+    ) -> AppResult<()> {
+        let body = serde_json::to_vec(payload)
+            .map_err(|e| AppError::Internal(format!("serialize queue message: {e}")))?;
+        self.channel
+            .basic_publish(
+                "",
+                &self.config.vision_queue,
+                BasicPublishOptions::default(),
+                &body,
+                BasicProperties::default()
+                    .with_content_type("application/json".into())
+                    .with_delivery_mode(2) // persistent
+                    .with_priority(priority.amqp_value()),
+            )
+            .await
+            .map_err(|e| AppError::ServiceUnavailable(format!("rabbitmq publish: {e}")))?
+            .await
+            .map_err(|e| AppError::ServiceUnavailable(format!("rabbitmq confirm: {e}")))?;
+        crate::metrics::record_queue_event(&self.config.vision_queue, "published");
+        Ok(())
+    }
+
+    /// Publish a terminal-job event onto the webhook queue. Best-effort at
+    /// the call sites — a broker hiccup must not fail the job transition
+    /// that triggered it.
+    pub async fn publish_webhook_event<T: Serialize>(&self, payload: &T) -> AppResult<()> {
+        let body = serde_json::to_vec(payload)
+            .map_err(|e| AppError::Internal(format!("serialize webhook event: {e}")))?;
+        self.channel
+            .basic_publish(
+                "",
+                &self.config.webhook_queue,
+                BasicPublishOptions::default(),
+                &body,
+                BasicProperties::default()
+                    .with_content_type("application/json".into())
+                    .with_delivery_mode(2),
+            )
+            .await
+            .map_err(|e| AppError::ServiceUnavailable(format!("rabbitmq publish: {e}")))?
+            .await
+            .map_err(|e| AppError::ServiceUnavailable(format!("rabbitmq confirm: {e}")))?;
+        crate::metrics::record_queue_event(&self.config.webhook_queue, "published");
+        Ok(())
+    }
+
+    /// Open a dedicated consumer on `queue`; used by the long-running
+    /// dispatcher tasks so they don't share the publish channel.
+    pub(crate) async fn create_consumer(
+        &self,
+        queue: &str,
+        consumer_tag: &str,
+    ) -> AppResult<lapin::Consumer> {
+        let channel = self
+            .connection
+            .create_channel()
+            .await
+            .map_err(|e| AppError::ServiceUnavailable(format!("rabbitmq channel: {e}")))?;
+        channel
+            .basic_consume(
+                queue,
+                consumer_tag,
+                BasicConsumeOptions::default(),
+                FieldTable::default(),
+            )
+            .await
+            .map_err(|e| AppError::ServiceUnavailable(format!("rabbitmq consume: {e}")))
+    }
+
+    pub fn vision_queue(&self) -> &str {
+        &self.config.vision_queue
+    }
+
+    pub fn dlq(&self) -> &str {
+        &self.config.dlq
+    }
+
+    /// Broker-side message and consumer counts for `queue`, via a passive
+    /// declare — the one depth query plain AMQP offers. Passive means the
+    /// queue is never created or reconfigured by asking; all our queues
+    /// exist from startup, so a failure here means the broker (or this
+    /// channel) is down, not a missing queue.
+    pub async fn queue_stats(&self, queue: &str) -> AppResult<QueueStats> {
+        let passive = QueueDeclareOptions {
+            passive: true,
+            ..Default::default()
+        };
+        let declared = self
+            .channel
+            .queue_declare(queue, passive, FieldTable::default())
+            .await
+            .map_err(|e| AppError::ServiceUnavailable(format!("queue stats: {e}")))?;
+        Ok(QueueStats {
+            messages: declared.message_count(),
+            consumers: declared.consumer_count(),
+        })
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.connection.status().connected() && self.channel.status().connected()
+    }
+
+    /// Close the channel and connection cleanly so the broker redelivers
+    /// anything unacked immediately instead of waiting for a heartbeat
+    /// timeout. Called on shutdown; errors are logged, not propagated —
+    /// we're exiting either way.
+    pub async fn close(&self) {
+        if let Err(e) = self.channel.close(0, "shutting down").await {
+            tracing::debug!(error = %e, "rabbitmq channel close failed");
+        }
+        if let Err(e) = self.connection.close(0, "shutting down").await {
+            tracing::debug!(error = %e, "rabbitmq connection close failed");
+        }
+    }
+
+    /// Republish a dead-lettered payload onto the main queue with its
+    /// incremented retry count, so the next failure routes correctly.
+    /// Retries keep the priority the job was originally published with.
+    async fn requeue_dead_letter(&self, body: &[u8], retry_count: u32) -> AppResult<()> {
+        let mut headers = FieldTable::default();
+        headers.insert(RETRY_COUNT_HEADER.into(), AMQPValue::LongInt(retry_count as i32));
+        let priority = serde_json::from_slice(body)
+            .map(|payload| priority_of(&payload))
+            .unwrap_or_default();
+        self.channel
+            .basic_publish(
+                "",
+                &self.config.vision_queue,
+                BasicPublishOptions::default(),
+                body,
+                BasicProperties::default()
+                    .with_content_type("application/json".into())
+                    .with_delivery_mode(2)
+                    .with_priority(priority.amqp_value())
+                    .with_headers(headers),
+            )
+            .await
+            .map_err(|e| AppError::ServiceUnavailable(format!("rabbitmq publish: {e}")))?
+            .await
+            .map_err(|e| AppError::ServiceUnavailable(format!("rabbitmq confirm: {e}")))?;
+        crate::metrics::record_queue_event(&self.config.vision_queue, "requeued");
+        Ok(())
+    }
+
+    /// Consume the parking DLQ, routing each message by its remaining retry
+    /// budget: requeue onto the main queue, or persist into `failed_jobs`.
+    /// Runs for the life of the process on its own channel; a dropped
+    /// consumer leaves messages parked (unacked work is redelivered), never
+    /// lost.
+    pub fn start_dlq_consumer(self: Arc<Self>, db: sqlx::PgPool) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let channel = match self.connection.create_channel().await {
+                Ok(channel) => channel,
+                Err(e) => {
+                    tracing::error!(error = %e, "dlq consumer: channel create failed");
+                    return;
+                }
+            };
+            let mut consumer = match channel
+                .basic_consume(
+                    &self.config.dlq,
+                    "gateway-dlq-consumer",
+                    BasicConsumeOptions::default(),
+                    FieldTable::default(),
+                )
+                .await
+            {
+                Ok(consumer) => consumer,
+                Err(e) => {
+                    tracing::error!(error = %e, "dlq consumer: consume failed");
+                    return;
+                }
+            };
+            while let Some(delivery) = consumer.next().await {
+                let Ok(delivery) = delivery else { continue };
+                crate::metrics::record_queue_event(&self.config.dlq, "consumed");
+                let retry_count = retry_count_header(delivery.properties.headers().as_ref());
+                match route_dead_letter(retry_count, self.config.max_retries) {
+                    DlqAction::Requeue(next_count) => {
+                        match self.requeue_dead_letter(&delivery.data, next_count).await {
+                            Ok(()) => {
+                                let _ = delivery.acker.ack(BasicAckOptions::default()).await;
+                            }
+                            Err(e) => {
+                                tracing::warn!(error = %e, "dlq consumer: requeue failed");
+                                let _ = delivery
+                                    .acker
+                                    .nack(BasicNackOptions { requeue: true, ..Default::default() })
+                                    .await;
+                            }
+                        }
+                    }
+                    DlqAction::Persist => {
+                        match persist_failed_job(&db, &delivery.data, retry_count).await {
+                            Ok(()) => {
+                                // A persisted job is terminal: notify
+                                // webhook subscribers, best effort.
+                                if let Some(job_id) = job_id_from_payload(&delivery.data) {
+                                    let event = crate::services::webhooks::WebhookEvent::failed(
+                                        job_id,
+                                        "retries exhausted".into(),
+                                    );
+                                    if let Err(e) = self.publish_webhook_event(&event).await {
+                                        tracing::warn!(error = %e, "webhook event publish failed");
+                                    }
+                                }
+                                let _ = delivery.acker.ack(BasicAckOptions::default()).await;
+                            }
+                            Err(e) => {
+                                // Leave it parked rather than lose it; the
+                                // redelivery will retry the insert.
+                                tracing::warn!(error = %e, "dlq consumer: persist failed");
+                                let _ = delivery
+                                    .acker
+                                    .nack(BasicNackOptions { requeue: true, ..Default::default() })
+                                    .await;
+                            }
+                        }
+                    }
+                }
+            }
+            tracing::warn!("dlq consumer stream ended");
+        })
+    }
+}
+
+/// Header carrying how many times a message has been through the retry
+/// routing. The worker stamps it when parking; requeues increment it.
+pub const RETRY_COUNT_HEADER: &str = "x-retry-count";
+
+/// Where a dead-lettered message goes next.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DlqAction {
+    /// Back onto the main queue, carrying the new retry count.
+    Requeue(u32),
+    /// Into the `failed_jobs` table; the budget is spent.
+    Persist,
+}
+
+/// Route one dead-lettered message by its recorded retry count. A missing
+/// header means the budget is spent: the worker only parks exhausted jobs,
+/// so an unstamped message has no retries left to claim.
+pub fn route_dead_letter(retry_count: Option<u32>, max_retries: u32) -> DlqAction {
+    match retry_count {
+        Some(count) if count < max_retries => DlqAction::Requeue(count + 1),
+        _ => DlqAction::Persist,
+    }
+}
+
+/// Read the retry count header, if present.
+pub fn retry_count_header(headers: Option<&FieldTable>) -> Option<u32> {
+    let value = headers?.inner().get(RETRY_COUNT_HEADER)?;
+    match value {
+        AMQPValue::LongInt(count) => u32::try_from(*count).ok(),
+        AMQPValue::LongLongInt(count) => u32::try_from(*count).ok(),
+        AMQPValue::ShortShortInt(count) => u32::try_from(*count).ok(),
+        _ => None,
+    }
+}
+
+/// Read the `priority` field out of a queued payload; anything missing or
+/// unrecognized publishes at normal priority rather than being rejected —
+/// requeue paths must never drop a job over a malformed hint.
+pub fn priority_of(payload: &serde_json::Value) -> JobPriority {
+    payload
+        .get("priority")
+        .cloned()
+        .and_then(|value| serde_json::from_value(value).ok())
+        .unwrap_or_default()
+}
+
+/// Read the `job_id` field out of a queued payload, if it has one.
+fn job_id_from_payload(body: &[u8]) -> Option<Uuid> {
+    let payload: serde_json::Value = serde_json::from_slice(body).ok()?;
+    payload
+        .get("job_id")
+        .and_then(|id| id.as_str())
+        .and_then(|id| Uuid::parse_str(id).ok())
+}
+
+/// Upsert one exhausted job into `failed_jobs`. Jobs without a parseable
+/// `job_id` get a fresh id so a malformed payload is still kept.
+async fn persist_failed_job(
+    db: &sqlx::PgPool,
+    body: &[u8],
+    retry_count: Option<u32>,
+) -> AppResult<()> {
+    let payload: serde_json::Value = serde_json::from_slice(body)
+        .unwrap_or_else(|_| serde_json::json!({ "raw": String::from_utf8_lossy(body) }));
+    let job_id = payload
+        .get("job_id")
+        .and_then(|id| id.as_str())
+        .and_then(|id| Uuid::parse_str(id).ok())
+        .unwrap_or_else(Uuid::new_v4);
+    sqlx::query(
+        "INSERT INTO failed_jobs (job_id, payload, retry_count) VALUES ($1, $2, $3)
+         ON CONFLICT (job_id) DO UPDATE SET payload = $2, retry_count = $3, parked_at = now()",
+    )
+    .bind(job_id)
+    .bind(&payload)
+    .bind(retry_count.unwrap_or(0) as i32)
+    .execute(db)
+    .await
+    .map_err(|e| AppError::Database(format!("persist failed job: {e}")))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_job_that_keeps_failing_ends_up_persisted() {
+        // Simulate the full lifecycle of one message with a budget of 3:
+        // each failure routes it through the DLQ, where the consumer
+        // requeues it with an incremented count until the budget is spent.
+        let max_retries = 3;
+        let mut retry_count = Some(0);
+        let mut requeues = 0;
+        let final_action = loop {
+            match route_dead_letter(retry_count, max_retries) {
+                DlqAction::Requeue(next) => {
+                    requeues += 1;
+                    retry_count = Some(next);
+                }
+                DlqAction::Persist => break DlqAction::Persist,
+            }
+        };
+        assert_eq!(final_action, DlqAction::Persist);
+        assert_eq!(requeues, max_retries);
+    }
+
+    #[test]
+    fn unstamped_messages_are_persisted_immediately() {
+        // The worker only parks jobs that already spent their budget, so a
+        // message with no retry header claims no retries.
+        assert_eq!(route_dead_letter(None, 3), DlqAction::Persist);
+    }
+
+    #[test]
+    fn payload_priority_falls_back_to_normal() {
+        let payload = serde_json::json!({ "job_id": "x", "priority": "urgent" });
+        assert_eq!(priority_of(&payload), JobPriority::Urgent);
+        let payload = serde_json::json!({ "job_id": "x" });
+        assert_eq!(priority_of(&payload), JobPriority::Normal);
+        let payload = serde_json::json!({ "priority": "blazing" });
+        assert_eq!(priority_of(&payload), JobPriority::Normal);
+    }
+
+    #[test]
+    fn retry_header_parses_the_integer_encodings() {
+        let mut headers = FieldTable::default();
+        headers.insert(RETRY_COUNT_HEADER.into(), AMQPValue::LongInt(2));
+        assert_eq!(retry_count_header(Some(&headers)), Some(2));
+
+        let mut headers = FieldTable::default();
+        headers.insert(RETRY_COUNT_HEADER.into(), AMQPValue::LongString("2".into()));
+        assert_eq!(retry_count_header(Some(&headers)), None);
+        assert_eq!(retry_count_header(None), None);
+    }
+}
@@ -0,0 +1,541 @@
+//! HTTP clients for the vision and LLM services.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use futures_util::{Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use shared::models::{CropType, Language, LLMResponse, VisionResponse};
+
+use crate::{
+    config::ExternalApisConfig,
+    errors::{AppError, AppResult},
+    services::ai4thai::{self, Ai4ThaiKeyPool},
+};
+
+/// Consecutive connection failures before the breaker opens.
+const FAILURE_THRESHOLD: u32 = 5;
+/// How long the breaker stays open before allowing a probe request.
+const RESET_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Copy)]
+enum BreakerState {
+    /// Normal operation, counting consecutive failures.
+    Closed { failures: u32 },
+    /// Tripped: requests are rejected without touching the network.
+    Open { since: Instant },
+    /// One probe request is in flight; its outcome decides the next state.
+    HalfOpen,
+}
+
+/// Per-upstream circuit breaker. When a service is down, letting every
+/// request run into the HTTP timeout ties up the pool; after
+/// `FAILURE_THRESHOLD` consecutive failures the breaker opens and calls
+/// fail fast with `ServiceUnavailable`. After `RESET_TIMEOUT` a single
+/// probe is let through — success closes the breaker, failure re-opens it.
+///
+/// Only transient failures count (connection errors, timeouts, 502/503) —
+/// and each counts after the retry budget is spent, so one blip behind a
+/// successful retry never moves the breaker. A service answering with other
+/// error statuses is up, and tripping on those would mask real responses.
+pub struct CircuitBreaker {
+    failure_threshold: u32,
+    reset_timeout: Duration,
+    state: Mutex<BreakerState>,
+}
+
+impl Default for CircuitBreaker {
+    fn default() -> Self {
+        Self::new(FAILURE_THRESHOLD, RESET_TIMEOUT)
+    }
+}
+
+impl CircuitBreaker {
+    pub fn new(failure_threshold: u32, reset_timeout: Duration) -> Self {
+        Self {
+            failure_threshold,
+            reset_timeout,
+            state: Mutex::new(BreakerState::Closed { failures: 0 }),
+        }
+    }
+
+    /// Current state for the readiness endpoint.
+    pub fn state_name(&self) -> &'static str {
+        match *self.state.lock().expect("breaker lock poisoned") {
+            BreakerState::Closed { .. } => "closed",
+            BreakerState::Open { .. } => "open",
+            BreakerState::HalfOpen => "half_open",
+        }
+    }
+
+    /// Numeric encoding of the state for the Prometheus gauge
+    /// (0 = closed, 1 = half-open, 2 = open).
+    pub fn state_code(&self) -> f64 {
+        match *self.state.lock().expect("breaker lock poisoned") {
+            BreakerState::Closed { .. } => 0.0,
+            BreakerState::HalfOpen => 1.0,
+            BreakerState::Open { .. } => 2.0,
+        }
+    }
+
+    fn admit_at(&self, now: Instant) -> AppResult<()> {
+        let mut state = self.state.lock().expect("breaker lock poisoned");
+        match *state {
+            BreakerState::Closed { .. } => Ok(()),
+            BreakerState::Open { since } if now.duration_since(since) >= self.reset_timeout => {
+                *state = BreakerState::HalfOpen;
+                Ok(())
+            }
+            BreakerState::Open { .. } | BreakerState::HalfOpen => Err(
+                AppError::ServiceUnavailable("upstream circuit open, failing fast".into()),
+            ),
+        }
+    }
+
+    fn record_success(&self) {
+        *self.state.lock().expect("breaker lock poisoned") = BreakerState::Closed { failures: 0 };
+    }
+
+    fn record_failure_at(&self, now: Instant) {
+        let mut state = self.state.lock().expect("breaker lock poisoned");
+        *state = match *state {
+            BreakerState::Closed { failures } if failures + 1 < self.failure_threshold => {
+                BreakerState::Closed { failures: failures + 1 }
+            }
+            _ => BreakerState::Open { since: now },
+        };
+    }
+
+    /// Run `future` under the breaker. Rejects immediately when open;
+    /// otherwise records the outcome, counting only `ServiceUnavailable`
+    /// (connection-level) errors as breaker failures.
+    async fn guard<T, F>(&self, future: F) -> AppResult<T>
+    where
+        F: std::future::Future<Output = AppResult<T>>,
+    {
+        self.admit_at(Instant::now())?;
+        let result = future.await;
+        match &result {
+            Err(AppError::ServiceUnavailable(_)) => self.record_failure_at(Instant::now()),
+            _ => self.record_success(),
+        }
+        result
+    }
+}
+
+/// Attempt budget and backoff base for retrying transient upstream errors
+/// (connect refused, timeouts, 502/503). Non-transient errors — validation
+/// failures, 4xx, decode errors — are never retried.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+}
+
+/// Exponential backoff with full jitter: the delay before retry `attempt`
+/// (1-based) doubles each time and is then scaled into `[50%, 100%]` by the
+/// seed, so a thundering herd of retries spreads out.
+fn backoff_delay(base: Duration, attempt: u32, jitter_seed: u64) -> Duration {
+    let exp = base.saturating_mul(1u32 << (attempt - 1).min(16));
+    let scale = 0.5 + (jitter_seed % 1000) as f64 / 2000.0;
+    exp.mul_f64(scale)
+}
+
+/// Run `op` up to `policy.max_attempts` times, sleeping with exponential
+/// backoff between attempts. Only `ServiceUnavailable` results are
+/// considered transient and retried.
+async fn retry_transient<T, F, Fut>(policy: RetryPolicy, mut op: F) -> AppResult<T>
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = AppResult<T>>,
+{
+    let mut attempt = 1;
+    loop {
+        match op().await {
+            Err(AppError::ServiceUnavailable(reason)) if attempt < policy.max_attempts.max(1) => {
+                let seed = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map(|d| d.subsec_nanos() as u64)
+                    .unwrap_or(0);
+                let delay = backoff_delay(policy.base_delay, attempt, seed);
+                tracing::warn!(%reason, attempt, delay_ms = delay.as_millis() as u64, "retrying transient upstream failure");
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Holds the upstream clients so handlers get them from one place. Each
+/// upstream has its own breaker: a down vision service must not fail chat,
+/// and vice versa.
+pub struct ServiceRegistry {
+    pub vision: VisionClient,
+    pub llm: LLMClient,
+    pub vision_breaker: Arc<CircuitBreaker>,
+    pub llm_breaker: Arc<CircuitBreaker>,
+    /// Shared by both clients; exposed for the metrics poller.
+    pub ai4thai_keys: Arc<Ai4ThaiKeyPool>,
+}
+
+impl ServiceRegistry {
+    pub fn new(
+        config: &ExternalApisConfig,
+        feature_flags: Arc<crate::services::feature_flags::FeatureFlagService>,
+    ) -> AppResult<Self> {
+        let http = reqwest::Client::builder()
+            .timeout(config.timeout)
+            .connect_timeout(Duration::from_secs(5))
+            .build()
+            .map_err(|e| AppError::Internal(format!("build http client: {e}")))?;
+        let retry = RetryPolicy {
+            max_attempts: config.retry_max_attempts,
+            base_delay: config.retry_base_delay,
+        };
+        let vision_breaker = Arc::new(CircuitBreaker::default());
+        let llm_breaker = Arc::new(CircuitBreaker::default());
+        let ai4thai_keys = Arc::new(Ai4ThaiKeyPool::from_comma_separated(
+            &config.ai4thai_api_key,
+        ));
+        Ok(Self {
+            vision: VisionClient {
+                http: http.clone(),
+                base_url: config.vision_service_url.clone(),
+                breaker: vision_breaker.clone(),
+                retry,
+                keys: ai4thai_keys.clone(),
+                feature_flags,
+            },
+            llm: LLMClient {
+                http,
+                base_url: config.llm_service_url.clone(),
+                breaker: llm_breaker.clone(),
+                retry,
+                keys: ai4thai_keys.clone(),
+                thai_prompt_template: config.thai_prompt_template.clone(),
+                english_prompt_template: config.english_prompt_template.clone(),
+            },
+            vision_breaker,
+            llm_breaker,
+            ai4thai_keys,
+        })
+    }
+}
+
+pub struct VisionClient {
+    http: reqwest::Client,
+    base_url: String,
+    breaker: Arc<CircuitBreaker>,
+    retry: RetryPolicy,
+    keys: Arc<Ai4ThaiKeyPool>,
+    feature_flags: Arc<crate::services::feature_flags::FeatureFlagService>,
+}
+
+#[derive(Serialize)]
+struct AnalyzeRequest<'a> {
+    image_path: &'a str,
+    crop_type: CropType,
+}
+
+impl VisionClient {
+    pub async fn analyze(&self, image_path: &str, crop_type: CropType) -> AppResult<VisionResponse> {
+        // The v2 model rolls out behind a runtime flag instead of a deploy;
+        // checked per call so flipping the flag takes effect immediately.
+        let endpoint = if self
+            .feature_flags
+            .is_enabled(crate::services::feature_flags::USE_NEW_VISION_MODEL_V2, None)
+            .await
+        {
+            "analyze/v2"
+        } else {
+            "analyze"
+        };
+        self.breaker
+            .guard(retry_transient(self.retry, || async {
+                let response = ai4thai::send_with_rotation(&self.keys, "vision", || {
+                    with_correlation(self.http.post(format!("{}/{endpoint}", self.base_url)))
+                        .json(&AnalyzeRequest { image_path, crop_type })
+                })
+                .await?;
+                parse_upstream("vision", response).await
+            }))
+            .await
+    }
+}
+
+pub struct LLMClient {
+    http: reqwest::Client,
+    base_url: String,
+    breaker: Arc<CircuitBreaker>,
+    retry: RetryPolicy,
+    keys: Arc<Ai4ThaiKeyPool>,
+    thai_prompt_template: String,
+    english_prompt_template: String,
+}
+
+#[derive(Serialize)]
+struct CompletionRequest<'a> {
+    prompt: &'a str,
+    language: Language,
+}
+
+impl LLMClient {
+    /// Assemble the final prompt: detect the language the user actually
+    /// wrote in (the stored request preference breaks ties for letterless
+    /// text), normalize Thai input, and prepend the matching system prompt
+    /// template.
+    fn build_prompt(&self, prompt: &str, stored: Language) -> (String, Language) {
+        let language = crate::utils::thai_text::effective_language(prompt, stored);
+        let (template, body) = match language {
+            Language::Thai => (
+                &self.thai_prompt_template,
+                crate::utils::thai_text::ThaiTextProcessor::normalize_thai(prompt),
+            ),
+            Language::English => (&self.english_prompt_template, prompt.to_string()),
+        };
+        (format!("{template}\n\n{body}"), language)
+    }
+
+    pub async fn completion(&self, prompt: &str, language: Language) -> AppResult<LLMResponse> {
+        let (prompt, language) = self.build_prompt(prompt, language);
+        let prompt = prompt.as_str();
+        self.breaker
+            .guard(retry_transient(self.retry, || async {
+                let response = ai4thai::send_with_rotation(&self.keys, "llm", || {
+                    with_correlation(self.http.post(format!("{}/completion", self.base_url)))
+                        .json(&CompletionRequest { prompt, language })
+                })
+                .await?;
+                parse_upstream("llm", response).await
+            }))
+            .await
+    }
+
+    /// Stream completion tokens as they arrive instead of waiting for the
+    /// full answer. The LLM service emits plain-text chunks on
+    /// `/completion/stream`; each non-empty chunk is yielded as-is. A chunk
+    /// error mid-stream ends the stream rather than erroring — the caller
+    /// has already forwarded earlier tokens and can only stop cleanly.
+    pub async fn stream_completion(
+        &self,
+        prompt: &str,
+        language: Language,
+    ) -> AppResult<impl Stream<Item = String>> {
+        let (prompt, language) = self.build_prompt(prompt, language);
+        let prompt = prompt.as_str();
+        let response = self
+            .breaker
+            .guard(retry_transient(self.retry, || async {
+                ai4thai::send_with_rotation(&self.keys, "llm", || {
+                    with_correlation(
+                        self.http.post(format!("{}/completion/stream", self.base_url)),
+                    )
+                    .json(&CompletionRequest { prompt, language })
+                })
+                .await
+            }))
+            .await?;
+        Ok(response.bytes_stream().filter_map(|chunk| async move {
+            match chunk {
+                Ok(bytes) if !bytes.is_empty() => {
+                    Some(String::from_utf8_lossy(&bytes).into_owned())
+                }
+                Ok(_) => None,
+                Err(e) => {
+                    tracing::warn!(error = %e, "llm stream interrupted");
+                    None
+                }
+            }
+        }))
+    }
+}
+
+/// Attach the current request's correlation id — so gateway and upstream
+/// log lines join on one identifier — plus the W3C trace context so the
+/// upstream's spans land in the same distributed trace.
+fn with_correlation(builder: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+    let builder = crate::telemetry::inject_trace_context(builder);
+    match crate::middleware::correlation::current_correlation_id() {
+        Some(id) => builder.header("X-Correlation-ID", id),
+        None => builder,
+    }
+}
+
+/// Decode a successful upstream body. Error statuses never reach here:
+/// `ai4thai::send_with_rotation` maps them (and records the error metric)
+/// before handing the response back.
+async fn parse_upstream<T: for<'de> Deserialize<'de>>(
+    upstream: &'static str,
+    response: reqwest::Response,
+) -> AppResult<T> {
+    response
+        .json()
+        .await
+        .map_err(|e| AppError::ExternalApi(format!("decode {upstream} response: {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fail(breaker: &CircuitBreaker, now: Instant) {
+        breaker.admit_at(now).unwrap();
+        breaker.record_failure_at(now);
+    }
+
+    #[test]
+    fn breaker_opens_after_consecutive_failures() {
+        let breaker = CircuitBreaker::new(5, Duration::from_secs(30));
+        let now = Instant::now();
+        for _ in 0..5 {
+            fail(&breaker, now);
+        }
+        assert_eq!(breaker.state_name(), "open");
+        // Rejected without a network attempt.
+        assert!(matches!(
+            breaker.admit_at(now),
+            Err(AppError::ServiceUnavailable(_))
+        ));
+    }
+
+    #[test]
+    fn success_resets_the_failure_count() {
+        let breaker = CircuitBreaker::new(5, Duration::from_secs(30));
+        let now = Instant::now();
+        for _ in 0..4 {
+            fail(&breaker, now);
+        }
+        breaker.record_success();
+        for _ in 0..4 {
+            fail(&breaker, now);
+        }
+        assert_eq!(breaker.state_name(), "closed");
+    }
+
+    #[test]
+    fn half_open_allows_one_probe_then_closes_on_success() {
+        let breaker = CircuitBreaker::new(1, Duration::from_secs(30));
+        let now = Instant::now();
+        fail(&breaker, now);
+        assert_eq!(breaker.state_name(), "open");
+
+        let later = now + Duration::from_secs(31);
+        breaker.admit_at(later).unwrap();
+        assert_eq!(breaker.state_name(), "half_open");
+        // Second caller is rejected while the probe is in flight.
+        assert!(breaker.admit_at(later).is_err());
+
+        breaker.record_success();
+        assert_eq!(breaker.state_name(), "closed");
+    }
+
+    #[test]
+    fn backoff_doubles_and_jitter_stays_within_bounds() {
+        let base = Duration::from_millis(200);
+        for attempt in 1..=4u32 {
+            let full = base * (1 << (attempt - 1));
+            for seed in [0, 499, 999] {
+                let delay = backoff_delay(base, attempt, seed);
+                assert!(delay >= full / 2 && delay <= full, "attempt {attempt} seed {seed}: {delay:?}");
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn transient_errors_are_retried_until_the_budget_runs_out() {
+        let policy = RetryPolicy { max_attempts: 3, base_delay: Duration::from_millis(1) };
+        let attempts = std::sync::atomic::AtomicU32::new(0);
+        let result: AppResult<()> = retry_transient(policy, || {
+            attempts.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            async { Err(AppError::ServiceUnavailable("connect refused".into())) }
+        })
+        .await;
+        assert!(matches!(result, Err(AppError::ServiceUnavailable(_))));
+        assert_eq!(attempts.load(std::sync::atomic::Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn a_retry_can_recover_and_non_transient_errors_never_retry() {
+        let policy = RetryPolicy { max_attempts: 3, base_delay: Duration::from_millis(1) };
+        let attempts = std::sync::atomic::AtomicU32::new(0);
+        let result = retry_transient(policy, || {
+            let n = attempts.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            async move {
+                if n == 0 {
+                    Err(AppError::ServiceUnavailable("blip".into()))
+                } else {
+                    Ok(42)
+                }
+            }
+        })
+        .await;
+        assert_eq!(result.unwrap(), 42);
+        assert_eq!(attempts.load(std::sync::atomic::Ordering::SeqCst), 2);
+
+        // A 4xx-style upstream error surfaces immediately.
+        let attempts = std::sync::atomic::AtomicU32::new(0);
+        let result: AppResult<()> = retry_transient(policy, || {
+            attempts.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            async { Err(AppError::ExternalApi("upstream 400".into())) }
+        })
+        .await;
+        assert!(matches!(result, Err(AppError::ExternalApi(_))));
+        assert_eq!(attempts.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn prompt_template_follows_the_detected_language() {
+        let llm = LLMClient {
+            http: reqwest::Client::new(),
+            base_url: String::new(),
+            breaker: Arc::new(CircuitBreaker::default()),
+            retry: RetryPolicy { max_attempts: 1, base_delay: Duration::from_millis(1) },
+            keys: Arc::new(Ai4ThaiKeyPool::new(vec!["test-key".into()])),
+            thai_prompt_template: "TH".into(),
+            english_prompt_template: "EN".into(),
+        };
+
+        // Stored English preference loses to Thai text, and the zero-width
+        // character is normalized away.
+        let (prompt, language) = llm.build_prompt("ใบ\u{200C}เหลือง", Language::English);
+        assert_eq!(language, Language::Thai);
+        assert_eq!(prompt, "TH\n\nใบเหลือง");
+
+        let (prompt, language) = llm.build_prompt("yellow leaves", Language::Thai);
+        assert_eq!(language, Language::English);
+        assert_eq!(prompt, "EN\n\nyellow leaves");
+    }
+
+    #[test]
+    fn built_prompt_carries_the_language_instruction() {
+        let llm = LLMClient {
+            http: reqwest::Client::new(),
+            base_url: String::new(),
+            breaker: Arc::new(CircuitBreaker::default()),
+            retry: RetryPolicy { max_attempts: 1, base_delay: Duration::from_millis(1) },
+            keys: Arc::new(Ai4ThaiKeyPool::new(vec!["test-key".into()])),
+            thai_prompt_template: "ตอบเป็นภาษาไทยที่เข้าใจง่าย".into(),
+            english_prompt_template: "Answer in clear, practical English.".into(),
+        };
+
+        let (prompt, _) = llm.build_prompt("ใบข้าวมีจุดสีน้ำตาล", Language::Thai);
+        assert!(prompt.contains("ตอบเป็นภาษาไทย"));
+
+        let (prompt, _) = llm.build_prompt("brown spots on rice leaves", Language::English);
+        assert!(prompt.contains("Answer in clear, practical English."));
+    }
+
+    #[test]
+    fn failed_probe_reopens_the_breaker() {
+        let breaker = CircuitBreaker::new(1, Duration::from_secs(30));
+        let now = Instant::now();
+        fail(&breaker, now);
+
+        let later = now + Duration::from_secs(31);
+        breaker.admit_at(later).unwrap();
+        breaker.record_failure_at(later);
+        assert_eq!(breaker.state_name(), "open");
+        assert!(breaker.admit_at(later + Duration::from_secs(1)).is_err());
+    }
+}
@@ -0,0 +1,117 @@
+//! Periodic background task supervisor.
+//!
+//! The cleanup loop used to be a bare `tokio::spawn` that lived until the
+//! process died; every new periodic job would have copied that pattern and
+//! its shutdown blind spot. The scheduler owns the tickers and a shared
+//! stop signal, so `main` can cancel every loop at one point during drain
+//! — between runs, never mid-run, because half a cleanup pass is worse
+//! than a late one.
+
+use std::{future::Future, sync::Mutex, time::Duration};
+
+use tokio::sync::watch;
+
+pub struct BackgroundScheduler {
+    stop: watch::Sender<bool>,
+    handles: Mutex<Vec<tokio::task::JoinHandle<()>>>,
+}
+
+impl Default for BackgroundScheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BackgroundScheduler {
+    pub fn new() -> Self {
+        let (stop, _) = watch::channel(false);
+        Self {
+            stop,
+            handles: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Run `task` on every tick of `interval` until shutdown. The first
+    /// tick fires immediately — a process that boots with overdue work
+    /// should not wait a full interval to notice. `task` builds the
+    /// future for one run; a missed tick (a run overran the interval) is
+    /// delayed, not bursted.
+    pub fn start<F, Fut>(&self, name: &'static str, interval: Duration, mut task: F)
+    where
+        F: FnMut() -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send,
+    {
+        let mut stop = self.stop.subscribe();
+        let handle = tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                tokio::select! {
+                    _ = stop.changed() => break,
+                    _ = ticker.tick() => task().await,
+                }
+            }
+            tracing::debug!(task = name, "background task stopped");
+        });
+        self.handles.lock().expect("scheduler lock poisoned").push(handle);
+    }
+
+    /// Cancel every task and wait for in-flight runs to finish.
+    pub async fn shutdown(&self) {
+        let _ = self.stop.send(true);
+        let handles: Vec<_> = self
+            .handles
+            .lock()
+            .expect("scheduler lock poisoned")
+            .drain(..)
+            .collect();
+        for handle in handles {
+            let _ = handle.await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use super::*;
+
+    fn counting_task(runs: Arc<AtomicUsize>) -> impl FnMut() -> std::future::Ready<()> + Send {
+        move || {
+            runs.fetch_add(1, Ordering::SeqCst);
+            std::future::ready(())
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn tasks_run_once_per_interval() {
+        let scheduler = BackgroundScheduler::new();
+        let runs = Arc::new(AtomicUsize::new(0));
+        scheduler.start("counter", Duration::from_secs(60), counting_task(runs.clone()));
+
+        // Immediate first run, then one per interval: two intervals in.
+        tokio::time::sleep(Duration::from_secs(125)).await;
+        assert_eq!(runs.load(Ordering::SeqCst), 3);
+        scheduler.shutdown().await;
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn shutdown_stops_future_runs() {
+        let scheduler = BackgroundScheduler::new();
+        let runs = Arc::new(AtomicUsize::new(0));
+        scheduler.start("counter", Duration::from_secs(60), counting_task(runs.clone()));
+
+        tokio::time::sleep(Duration::from_secs(65)).await;
+        scheduler.shutdown().await;
+        let settled = runs.load(Ordering::SeqCst);
+        assert_eq!(settled, 2);
+
+        // Time marching on after shutdown produces no more runs.
+        tokio::time::sleep(Duration::from_secs(600)).await;
+        assert_eq!(runs.load(Ordering::SeqCst), settled);
+    }
+}
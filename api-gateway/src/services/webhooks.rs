@@ -0,0 +1,340 @@
+//! Webhook delivery: consume terminal-job events and POST them to
+//! subscribed callback URLs.
+//!
+//! Registration lives in `handlers::webhooks`; this module is the
+//! dispatcher side. Events arrive on the `webhook_events` queue (published
+//! from the advice stage on completion and the DLQ consumer on failure),
+//! each matching subscription gets the JSON payload POSTed with an
+//! `X-Signature-256: sha256=<hex>` HMAC header, and transient delivery
+//! failures are retried with backoff. After the retry budget a delivery is
+//! dropped with a warning — webhooks are a notification channel, not a
+//! ledger; clients can always poll the job.
+
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use futures_util::StreamExt;
+use lapin::options::{BasicAckOptions, BasicNackOptions};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::state::AppState;
+
+/// Signature header name, shaped like GitHub's so receivers can reuse
+/// existing verification code.
+pub const SIGNATURE_HEADER: &str = "X-Signature-256";
+
+/// Delivery attempts per subscription per event.
+const MAX_DELIVERY_ATTEMPTS: u32 = 3;
+/// Backoff base between attempts (doubles each retry).
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(1);
+/// Per-request timeout; a slow receiver must not stall the queue.
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Event names subscriptions can ask for.
+pub const EVENT_JOB_COMPLETED: &str = "job.completed";
+pub const EVENT_JOB_FAILED: &str = "job.failed";
+
+/// The message published onto the webhook queue and, serialized, the body
+/// POSTed to receivers. `result` carries the stored vision result with the
+/// merged advice for completions; `error` the failure reason for failures.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WebhookEvent {
+    pub event: String,
+    pub job_id: Uuid,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub occurred_at: DateTime<Utc>,
+}
+
+impl WebhookEvent {
+    pub fn completed(job_id: Uuid, result: serde_json::Value) -> Self {
+        Self {
+            event: EVENT_JOB_COMPLETED.into(),
+            job_id,
+            result: Some(result),
+            error: None,
+            occurred_at: Utc::now(),
+        }
+    }
+
+    pub fn failed(job_id: Uuid, reason: String) -> Self {
+        Self {
+            event: EVENT_JOB_FAILED.into(),
+            job_id,
+            result: None,
+            error: Some(reason),
+            occurred_at: Utc::now(),
+        }
+    }
+}
+
+/// HMAC-SHA256 over `message`, written out by hand over the `sha2` digest
+/// we already depend on (RFC 2104: H(key ⊕ opad ‖ H(key ⊕ ipad ‖ message))).
+pub fn hmac_sha256(secret: &[u8], message: &[u8]) -> [u8; 32] {
+    const BLOCK_SIZE: usize = 64;
+    let mut key = [0u8; BLOCK_SIZE];
+    if secret.len() > BLOCK_SIZE {
+        key[..32].copy_from_slice(&Sha256::digest(secret));
+    } else {
+        key[..secret.len()].copy_from_slice(secret);
+    }
+
+    let mut inner = Sha256::new();
+    inner.update(key.map(|b| b ^ 0x36));
+    inner.update(message);
+    let inner = inner.finalize();
+
+    let mut outer = Sha256::new();
+    outer.update(key.map(|b| b ^ 0x5c));
+    outer.update(inner);
+    outer.finalize().into()
+}
+
+/// Signature header value for a payload: `sha256=<hex>`.
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mac = hmac_sha256(secret.as_bytes(), body);
+    let mut out = String::with_capacity(7 + 64);
+    out.push_str("sha256=");
+    for byte in mac {
+        out.push_str(&format!("{byte:02x}"));
+    }
+    out
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct Subscription {
+    id: Uuid,
+    url: String,
+    secret: String,
+}
+
+/// POST one payload to one receiver, retrying transient failures (connect
+/// errors, 5xx) with exponential backoff. 4xx responses are not retried —
+/// the receiver saw the delivery and rejected it.
+pub async fn deliver(
+    http: &reqwest::Client,
+    url: &str,
+    secret: &str,
+    body: &[u8],
+) -> Result<(), String> {
+    let mut attempt = 1;
+    loop {
+        let result = http
+            .post(url)
+            .header("Content-Type", "application/json")
+            .header(SIGNATURE_HEADER, sign(secret, body))
+            .body(body.to_vec())
+            .send()
+            .await;
+        let retryable = match result {
+            Ok(response) if response.status().is_success() => return Ok(()),
+            Ok(response) if response.status().is_server_error() => {
+                format!("receiver answered {}", response.status())
+            }
+            Ok(response) => return Err(format!("receiver answered {}", response.status())),
+            Err(e) => format!("delivery failed: {e}"),
+        };
+        if attempt >= MAX_DELIVERY_ATTEMPTS {
+            return Err(retryable);
+        }
+        tokio::time::sleep(RETRY_BASE_DELAY * 2u32.pow(attempt - 1)).await;
+        attempt += 1;
+    }
+}
+
+/// Fan one event out to every subscription listening for it.
+async fn dispatch_event(state: &AppState, http: &reqwest::Client, body: &[u8]) {
+    let event: WebhookEvent = match serde_json::from_slice(body) {
+        Ok(event) => event,
+        Err(e) => {
+            tracing::error!(error = %e, "webhook dispatcher: undecodable event, dropping");
+            return;
+        }
+    };
+    // Subscriptions are per-user: resolve the job to its owner and deliver
+    // only to that user's receivers — a webhook must never carry another
+    // user's diagnosis. Jobs without an owner (or unknown ids) go nowhere.
+    let owner: Option<Uuid> = match sqlx::query_scalar::<_, Option<Uuid>>(
+        "SELECT user_id FROM vision_jobs WHERE id = $1",
+    )
+    .bind(event.job_id)
+    .fetch_optional(&state.db)
+    .await
+    {
+        Ok(row) => row.flatten(),
+        Err(e) => {
+            tracing::warn!(error = %e, job_id = %event.job_id, "webhook dispatcher: owner lookup failed");
+            return;
+        }
+    };
+    let Some(owner) = owner else {
+        tracing::warn!(job_id = %event.job_id, "webhook dispatcher: job has no owner, dropping event");
+        return;
+    };
+    let subscriptions: Vec<Subscription> = match sqlx::query_as(
+        "SELECT id, url, secret FROM webhooks WHERE $1 = ANY(events) AND user_id = $2",
+    )
+    .bind(&event.event)
+    .bind(owner)
+    .fetch_all(&state.db)
+    .await
+    {
+        Ok(subscriptions) => subscriptions,
+        Err(e) => {
+            tracing::warn!(error = %e, "webhook dispatcher: subscription lookup failed");
+            return;
+        }
+    };
+    for subscription in subscriptions {
+        if let Err(reason) = deliver(http, &subscription.url, &subscription.secret, body).await {
+            tracing::warn!(
+                webhook_id = %subscription.id,
+                job_id = %event.job_id,
+                %reason,
+                "webhook delivery gave up"
+            );
+        }
+    }
+}
+
+/// Consume the webhook queue for the life of the process. An event is
+/// acked once every matching subscription has been attempted; a crashed
+/// dispatcher leaves unacked events to be redelivered.
+pub fn spawn(state: AppState) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let queue = state.config.rabbitmq.webhook_queue.clone();
+        let mut consumer = match state
+            .rabbitmq
+            .create_consumer(&queue, "gateway-webhook-dispatcher")
+            .await
+        {
+            Ok(consumer) => consumer,
+            Err(e) => {
+                tracing::error!(error = %e, "webhook dispatcher: consume failed");
+                return;
+            }
+        };
+        let http = match reqwest::Client::builder().timeout(DELIVERY_TIMEOUT).build() {
+            Ok(http) => http,
+            Err(e) => {
+                tracing::error!(error = %e, "webhook dispatcher: http client build failed");
+                return;
+            }
+        };
+        while let Some(delivery) = consumer.next().await {
+            let Ok(delivery) = delivery else { continue };
+            crate::metrics::record_queue_event(&queue, "consumed");
+            dispatch_event(&state, &http, &delivery.data).await;
+            if delivery.acker.ack(BasicAckOptions::default()).await.is_err() {
+                let _ = delivery
+                    .acker
+                    .nack(BasicNackOptions { requeue: true, ..Default::default() })
+                    .await;
+            }
+        }
+        tracing::warn!("webhook dispatcher stream ended");
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use axum::{extract::State, http::HeaderMap, routing::post, Router};
+
+    use super::*;
+
+    #[test]
+    fn hmac_matches_the_rfc_4231_test_vector() {
+        // RFC 4231 test case 2: key "Jefe", data "what do ya want for nothing?".
+        let mac = hmac_sha256(b"Jefe", b"what do ya want for nothing?");
+        let hex: String = mac.iter().map(|b| format!("{b:02x}")).collect();
+        assert_eq!(
+            hex,
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn long_keys_are_hashed_down_to_block_size() {
+        let long_key = [0xaa_u8; 131];
+        // Must not panic and must differ from the truncated-key MAC.
+        let a = hmac_sha256(&long_key, b"payload");
+        let b = hmac_sha256(&long_key[..64], b"payload");
+        assert_ne!(a, b);
+    }
+
+    #[tokio::test]
+    async fn deliveries_carry_a_verifiable_signature() {
+        type Captured = Arc<Mutex<Vec<(Option<String>, Vec<u8>)>>>;
+        let captured: Captured = Arc::default();
+
+        let app = Router::new()
+            .route(
+                "/hook",
+                post(
+                    |State(captured): State<Captured>, headers: HeaderMap, body: axum::body::Bytes| async move {
+                        let signature = headers
+                            .get(SIGNATURE_HEADER)
+                            .and_then(|v| v.to_str().ok())
+                            .map(String::from);
+                        captured.lock().unwrap().push((signature, body.to_vec()));
+                        "ok"
+                    },
+                ),
+            )
+            .with_state(captured.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let event = WebhookEvent::completed(
+            Uuid::new_v4(),
+            serde_json::json!({ "model_version": "v1", "detections": [] }),
+        );
+        let body = serde_json::to_vec(&event).unwrap();
+        deliver(
+            &reqwest::Client::new(),
+            &format!("http://{addr}/hook"),
+            "shared-secret",
+            &body,
+        )
+        .await
+        .unwrap();
+
+        let captured = captured.lock().unwrap();
+        let (signature, received) = &captured[0];
+        assert_eq!(received, &body, "body must arrive byte-identical");
+        assert_eq!(
+            signature.as_deref(),
+            Some(sign("shared-secret", &body).as_str()),
+            "receiver must be able to recompute the signature over the raw body"
+        );
+        assert!(signature.as_deref().unwrap().starts_with("sha256="));
+    }
+
+    #[tokio::test]
+    async fn a_rejecting_receiver_is_not_retried() {
+        let app = Router::new().route(
+            "/hook",
+            post(|| async { (axum::http::StatusCode::BAD_REQUEST, "no") }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let error = deliver(
+            &reqwest::Client::new(),
+            &format!("http://{addr}/hook"),
+            "s",
+            b"{}",
+        )
+        .await
+        .unwrap_err();
+        assert!(error.contains("400"), "got: {error}");
+    }
+}
@@ -0,0 +1,281 @@
+//! Idempotency keys for job-creating endpoints.
+//!
+//! Mobile clients on rural networks retry POSTs after timeouts, and every
+//! retry used to become a fresh GPU job. A caller sending an
+//! `Idempotency-Key` header gets a claim stored in Redis (`SET NX`, scoped
+//! per user so tenants can't collide); the first request through wins and
+//! records its job id, retries of the same body replay that envelope, and
+//! the same key with a *different* body is rejected — that's a client bug,
+//! not a retry.
+
+use async_trait::async_trait;
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::errors::{AppError, AppResult};
+
+/// Request header carrying the client-chosen key.
+pub const IDEMPOTENCY_HEADER: &str = "idempotency-key";
+
+/// How long a claim blocks duplicate submissions. Long enough to cover any
+/// realistic retry storm; short enough that keys can be reused across days.
+pub const IDEMPOTENCY_TTL_SECS: u64 = 24 * 60 * 60;
+
+/// How long a loser of the concurrent double-submit race waits for the
+/// winner to record its job id before giving up.
+const IN_FLIGHT_RETRIES: usize = 5;
+const IN_FLIGHT_RETRY_DELAY_MS: u64 = 100;
+
+/// What one key maps to. `job_id` is `None` between the claim and the
+/// enqueue completing (the winner fills it in as a second step).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IdempotencyRecord {
+    /// Hash of the submitted body; detects key reuse with different content.
+    pub body_hash: String,
+    pub job_id: Option<Uuid>,
+}
+
+/// How a keyed submission should proceed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Claim {
+    /// First time through: run the real submission, then call `fulfill`.
+    Won,
+    /// Same key, same body, job already created: return its envelope.
+    Replay { job_id: Uuid },
+}
+
+/// Storage for claims; Redis in production, a map in tests.
+#[async_trait]
+pub trait IdempotencyStore: Send + Sync {
+    /// Atomically store `record` under `key` if absent. Returns true when
+    /// this caller won the claim.
+    async fn claim(&self, key: &str, record: &IdempotencyRecord) -> AppResult<bool>;
+    async fn get(&self, key: &str) -> AppResult<Option<IdempotencyRecord>>;
+    /// Overwrite the claim with the final record (job id filled in).
+    async fn fulfill(&self, key: &str, record: &IdempotencyRecord) -> AppResult<()>;
+    /// Drop a claim: the submission failed (the client must be able to
+    /// retry) or short-circuited without creating a job.
+    async fn release(&self, key: &str);
+}
+
+pub struct RedisIdempotencyStore {
+    pub client: redis::Client,
+}
+
+impl RedisIdempotencyStore {
+    async fn conn(&self) -> AppResult<redis::aio::MultiplexedConnection> {
+        self.client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(|e| AppError::ServiceUnavailable(format!("redis: {e}")))
+    }
+}
+
+#[async_trait]
+impl IdempotencyStore for RedisIdempotencyStore {
+    async fn claim(&self, key: &str, record: &IdempotencyRecord) -> AppResult<bool> {
+        let mut conn = self.conn().await?;
+        let payload = serde_json::to_string(record)
+            .map_err(|e| AppError::Internal(format!("serialize idempotency record: {e}")))?;
+        let outcome: Option<String> = redis::cmd("SET")
+            .arg(key)
+            .arg(payload)
+            .arg("NX")
+            .arg("EX")
+            .arg(IDEMPOTENCY_TTL_SECS)
+            .query_async(&mut conn)
+            .await
+            .map_err(|e| AppError::ServiceUnavailable(format!("redis: {e}")))?;
+        Ok(outcome.is_some())
+    }
+
+    async fn get(&self, key: &str) -> AppResult<Option<IdempotencyRecord>> {
+        let mut conn = self.conn().await?;
+        let raw: Option<String> = conn
+            .get(key)
+            .await
+            .map_err(|e| AppError::ServiceUnavailable(format!("redis: {e}")))?;
+        Ok(raw.and_then(|r| serde_json::from_str(&r).ok()))
+    }
+
+    async fn fulfill(&self, key: &str, record: &IdempotencyRecord) -> AppResult<()> {
+        let mut conn = self.conn().await?;
+        let payload = serde_json::to_string(record)
+            .map_err(|e| AppError::Internal(format!("serialize idempotency record: {e}")))?;
+        let _: () = conn
+            .set_ex(key, payload, IDEMPOTENCY_TTL_SECS)
+            .await
+            .map_err(|e| AppError::ServiceUnavailable(format!("redis: {e}")))?;
+        Ok(())
+    }
+
+    async fn release(&self, key: &str) {
+        if let Ok(mut conn) = self.conn().await {
+            let _: Result<(), _> = conn.del(key).await;
+        }
+    }
+}
+
+/// Redis key for one user's idempotency key. Unauthenticated callers share
+/// one scope — they also share a rate limit bucket, so that's consistent.
+pub fn scoped_key(user: Option<&crate::AuthUser>, key: &str) -> String {
+    match user {
+        Some(user) => format!("idem:{}:{key}", user.user_id),
+        None => format!("idem:anon:{key}"),
+    }
+}
+
+/// Claim `key` or resolve what an earlier claim means for this request.
+///
+/// * first submission → [`Claim::Won`]
+/// * retry of the same body → [`Claim::Replay`]
+/// * same key, different body → 422
+/// * the concurrent-race loser waits briefly for the winner's job id, then
+///   gives up with a 409 rather than hanging the request
+pub async fn claim_or_replay(
+    store: &dyn IdempotencyStore,
+    key: &str,
+    body_hash: &str,
+) -> AppResult<Claim> {
+    let fresh = IdempotencyRecord {
+        body_hash: body_hash.to_string(),
+        job_id: None,
+    };
+    if store.claim(key, &fresh).await? {
+        return Ok(Claim::Won);
+    }
+
+    for attempt in 0..=IN_FLIGHT_RETRIES {
+        let Some(existing) = store.get(key).await? else {
+            // Claim expired between our SETNX and this read; extremely
+            // rare, and retrying the claim here could loop. Treat as won.
+            return Ok(Claim::Won);
+        };
+        if existing.body_hash != body_hash {
+            return Err(AppError::Unprocessable(
+                "idempotency key was already used with a different request body".into(),
+            ));
+        }
+        if let Some(job_id) = existing.job_id {
+            return Ok(Claim::Replay { job_id });
+        }
+        // The winner is still enqueueing; give it a moment.
+        if attempt < IN_FLIGHT_RETRIES {
+            tokio::time::sleep(std::time::Duration::from_millis(IN_FLIGHT_RETRY_DELAY_MS)).await;
+        }
+    }
+    Err(AppError::Conflict(
+        "a submission with this idempotency key is still in flight".into(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        sync::{Arc, Mutex},
+    };
+
+    use super::*;
+
+    #[derive(Default)]
+    struct MemoryStore {
+        entries: Mutex<HashMap<String, IdempotencyRecord>>,
+        claims: std::sync::atomic::AtomicUsize,
+    }
+
+    #[async_trait]
+    impl IdempotencyStore for MemoryStore {
+        async fn claim(&self, key: &str, record: &IdempotencyRecord) -> AppResult<bool> {
+            self.claims.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            let mut entries = self.entries.lock().unwrap();
+            if entries.contains_key(key) {
+                Ok(false)
+            } else {
+                entries.insert(key.to_string(), record.clone());
+                Ok(true)
+            }
+        }
+
+        async fn get(&self, key: &str) -> AppResult<Option<IdempotencyRecord>> {
+            Ok(self.entries.lock().unwrap().get(key).cloned())
+        }
+
+        async fn fulfill(&self, key: &str, record: &IdempotencyRecord) -> AppResult<()> {
+            self.entries.lock().unwrap().insert(key.to_string(), record.clone());
+            Ok(())
+        }
+
+        async fn release(&self, key: &str) {
+            self.entries.lock().unwrap().remove(key);
+        }
+    }
+
+    fn fulfilled(store: &MemoryStore, key: &str, body_hash: &str, job_id: Uuid) {
+        store.entries.lock().unwrap().insert(
+            key.to_string(),
+            IdempotencyRecord {
+                body_hash: body_hash.to_string(),
+                job_id: Some(job_id),
+            },
+        );
+    }
+
+    #[tokio::test]
+    async fn first_submission_wins_and_retry_replays() {
+        let store = MemoryStore::default();
+        assert_eq!(claim_or_replay(&store, "idem:u:k1", "h1").await.unwrap(), Claim::Won);
+
+        let job_id = Uuid::new_v4();
+        fulfilled(&store, "idem:u:k1", "h1", job_id);
+        assert_eq!(
+            claim_or_replay(&store, "idem:u:k1", "h1").await.unwrap(),
+            Claim::Replay { job_id }
+        );
+    }
+
+    #[tokio::test]
+    async fn same_key_with_a_different_body_is_rejected() {
+        let store = MemoryStore::default();
+        claim_or_replay(&store, "idem:u:k1", "h1").await.unwrap();
+        fulfilled(&store, "idem:u:k1", "h1", Uuid::new_v4());
+
+        let err = claim_or_replay(&store, "idem:u:k1", "other-hash").await.unwrap_err();
+        assert!(matches!(err, AppError::Unprocessable(_)));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn concurrent_double_submit_has_exactly_one_winner() {
+        let store = Arc::new(MemoryStore::default());
+        // Both copies of the retry storm race the same SETNX.
+        let a = tokio::spawn({
+            let store = store.clone();
+            async move { claim_or_replay(store.as_ref(), "idem:u:race", "h1").await }
+        });
+        let b = tokio::spawn({
+            let store = store.clone();
+            async move { claim_or_replay(store.as_ref(), "idem:u:race", "h1").await }
+        });
+        let (a, b) = (a.await.unwrap(), b.await.unwrap());
+
+        let won = |r: &AppResult<Claim>| matches!(r, Ok(Claim::Won));
+        assert_eq!(usize::from(won(&a)) + usize::from(won(&b)), 1, "exactly one winner");
+        // The loser either saw the winner's record land or timed out with a
+        // 409 — it must not have created a second claim.
+        let loser = if won(&a) { b } else { a };
+        assert!(matches!(loser, Err(AppError::Conflict(_)) | Ok(Claim::Replay { .. })));
+    }
+
+    #[test]
+    fn keys_are_scoped_per_user() {
+        let user = crate::AuthUser {
+            user_id: Uuid::new_v4(),
+            email: "a@b.c".into(),
+            roles: vec![],
+        };
+        let scoped = scoped_key(Some(&user), "retry-1");
+        assert!(scoped.contains(&user.user_id.to_string()));
+        assert_ne!(scoped, scoped_key(None, "retry-1"));
+    }
+}
@@ -0,0 +1,4 @@
+//! Cross-cutting helpers shared by multiple handler modules.
+
+pub mod idempotency;
+pub mod pagination;
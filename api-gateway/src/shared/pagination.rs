@@ -0,0 +1,410 @@
+//! Typed pagination, sorting, and filtering shared across list endpoints.
+//!
+//! Every list endpoint accepts the same query parameters:
+//! `?limit=20&cursor=...&sort=-created_at&<filter>=<value>`. Each endpoint
+//! declares an [`Allowlist`] naming which fields may be sorted or filtered
+//! on and how logical names map to SQL columns, so nothing user-supplied is
+//! ever interpolated into a query.
+
+use std::collections::BTreeMap;
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{Postgres, QueryBuilder};
+use uuid::Uuid;
+
+use crate::errors::{AppError, AppResult};
+
+pub const DEFAULT_LIMIT: u32 = 20;
+pub const MAX_LIMIT: u32 = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortDirection {
+    Asc,
+    Desc,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SortField {
+    pub field: String,
+    pub direction: SortDirection,
+}
+
+/// Raw query parameters as they arrive; turned into a [`PageRequest`] by
+/// validating against the endpoint's allowlist. Unknown keys are treated as
+/// candidate filters.
+#[derive(Debug, Default, Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PageParams {
+    pub cursor: Option<String>,
+    pub limit: Option<u32>,
+    /// Comma-separated, `-` prefix for descending: `sort=-created_at,crop_type`.
+    pub sort: Option<String>,
+    #[serde(flatten)]
+    pub filters: BTreeMap<String, String>,
+}
+
+/// Per-endpoint declaration of what may be sorted and filtered, mapping
+/// logical field names to SQL column expressions.
+pub struct Allowlist {
+    sortable: &'static [(&'static str, &'static str)],
+    filterable: &'static [(&'static str, &'static str)],
+}
+
+impl Allowlist {
+    pub const fn new(
+        sortable: &'static [(&'static str, &'static str)],
+        filterable: &'static [(&'static str, &'static str)],
+    ) -> Self {
+        Self { sortable, filterable }
+    }
+
+    fn sort_column(&self, field: &str) -> Option<&'static str> {
+        self.sortable.iter().find(|(f, _)| *f == field).map(|(_, c)| *c)
+    }
+
+    fn filter_column(&self, field: &str) -> Option<&'static str> {
+        self.filterable.iter().find(|(f, _)| *f == field).map(|(_, c)| *c)
+    }
+}
+
+/// A validated page request ready to apply to a query.
+#[derive(Debug)]
+pub struct PageRequest {
+    pub cursor: Option<Cursor>,
+    pub limit: u32,
+    pub sort: Vec<SortField>,
+    /// Validated (column, value) pairs; columns come from the allowlist.
+    pub filters: Vec<(&'static str, String)>,
+}
+
+impl PageRequest {
+    /// Validate raw params against `allowlist`. Limits are capped rather than
+    /// rejected; unknown sort or filter fields are a 400.
+    pub fn from_params(params: PageParams, allowlist: &Allowlist) -> AppResult<Self> {
+        let limit = params.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+
+        let mut sort = Vec::new();
+        if let Some(raw) = params.sort.as_deref() {
+            for part in raw.split(',').filter(|p| !p.is_empty()) {
+                let (field, direction) = match part.strip_prefix('-') {
+                    Some(field) => (field, SortDirection::Desc),
+                    None => (part, SortDirection::Asc),
+                };
+                if allowlist.sort_column(field).is_none() {
+                    return Err(AppError::Validation(format!(
+                        "cannot sort by '{field}'"
+                    )));
+                }
+                sort.push(SortField {
+                    field: field.to_string(),
+                    direction,
+                });
+            }
+        }
+
+        let mut filters = Vec::new();
+        for (key, value) in &params.filters {
+            match allowlist.filter_column(key) {
+                Some(column) => filters.push((column, value.clone())),
+                None => {
+                    return Err(AppError::Validation(format!(
+                        "cannot filter by '{key}'"
+                    )))
+                }
+            }
+        }
+
+        let cursor = params
+            .cursor
+            .as_deref()
+            .map(Cursor::decode)
+            .transpose()?;
+
+        Ok(Self {
+            cursor,
+            limit,
+            sort,
+            filters,
+        })
+    }
+
+    /// Append `WHERE`/`ORDER BY`/`LIMIT` clauses. `created_at`/`id` keyset
+    /// pagination keeps cursors stable under concurrent inserts: new rows
+    /// change only the pages before the cursor, never the rows after it.
+    ///
+    /// `base_has_where` says whether the builder already contains a WHERE
+    /// clause (filters are then appended with AND).
+    pub fn apply(&self, qb: &mut QueryBuilder<'_, Postgres>, allowlist: &Allowlist, base_has_where: bool) {
+        let mut has_where = base_has_where;
+        let mut push_clause = |qb: &mut QueryBuilder<'_, Postgres>| {
+            qb.push(if has_where { " AND " } else { " WHERE " });
+            has_where = true;
+        };
+
+        for (column, value) in &self.filters {
+            push_clause(qb);
+            qb.push(*column).push(" = ").push_bind(value.clone());
+        }
+
+        if let Some(cursor) = &self.cursor {
+            push_clause(qb);
+            qb.push("(created_at, id) < (")
+                .push_bind(cursor.created_at)
+                .push(", ")
+                .push_bind(cursor.id)
+                .push(")");
+        }
+
+        qb.push(" ORDER BY ");
+        if self.sort.is_empty() {
+            qb.push("created_at DESC, id DESC");
+        } else {
+            for (i, sort) in self.sort.iter().enumerate() {
+                if i > 0 {
+                    qb.push(", ");
+                }
+                // Validated in from_params, so the lookup cannot fail.
+                let column = allowlist
+                    .sort_column(&sort.field)
+                    .expect("sort field validated against allowlist");
+                qb.push(column).push(match sort.direction {
+                    SortDirection::Asc => " ASC",
+                    SortDirection::Desc => " DESC",
+                });
+            }
+            qb.push(", id DESC");
+        }
+
+        // Fetch one extra row to know whether a next page exists.
+        qb.push(" LIMIT ").push_bind(i64::from(self.limit) + 1);
+    }
+}
+
+/// Opaque keyset cursor: base64 of `<created_at_micros>:<id>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cursor {
+    pub created_at: DateTime<Utc>,
+    pub id: Uuid,
+}
+
+impl Cursor {
+    pub fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(format!(
+            "{}:{}",
+            self.created_at.timestamp_micros(),
+            self.id
+        ))
+    }
+
+    pub fn decode(raw: &str) -> AppResult<Self> {
+        let invalid = || AppError::Validation("invalid cursor".to_string());
+        let bytes = URL_SAFE_NO_PAD.decode(raw).map_err(|_| invalid())?;
+        let text = String::from_utf8(bytes).map_err(|_| invalid())?;
+        let (micros, id) = text.split_once(':').ok_or_else(invalid)?;
+        let micros: i64 = micros.parse().map_err(|_| invalid())?;
+        Ok(Self {
+            created_at: DateTime::from_timestamp_micros(micros).ok_or_else(invalid)?,
+            id: id.parse().map_err(|_| invalid())?,
+        })
+    }
+}
+
+/// One page of results plus the cursor for the next page.
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<u64>,
+}
+
+impl<T> Page<T> {
+    /// Build a page from `limit + 1` fetched rows, deriving the next cursor
+    /// from the last row kept via `cursor_of`.
+    pub fn from_rows(
+        mut items: Vec<T>,
+        limit: u32,
+        cursor_of: impl Fn(&T) -> Cursor,
+    ) -> Self {
+        let next_cursor = if items.len() > limit as usize {
+            items.truncate(limit as usize);
+            items.last().map(|item| cursor_of(item).encode())
+        } else {
+            None
+        };
+        Self {
+            items,
+            next_cursor,
+            total: None,
+        }
+    }
+}
+
+/// Classic page-numbered envelope for search-style endpoints. Ranked
+/// results have no stable sort key, so keyset cursors don't apply there;
+/// offset pagination with an explicit total is the honest contract.
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct PaginatedResponse<T> {
+    pub items: Vec<T>,
+    pub total: u64,
+    pub page: u32,
+    pub limit: u32,
+    pub total_pages: u32,
+}
+
+impl<T> PaginatedResponse<T> {
+    pub fn new(items: Vec<T>, total: u64, page: u32, limit: u32) -> Self {
+        let total_pages = if limit == 0 {
+            0
+        } else {
+            total.div_ceil(u64::from(limit)) as u32
+        };
+        Self {
+            items,
+            total,
+            page,
+            limit,
+            total_pages,
+        }
+    }
+}
+
+/// Page/limit query parameters for the offset-paginated endpoints that
+/// produce a [`PaginatedResponse`]. Flatten into a handler's params struct
+/// so every offset endpoint shares one clamp and one cap.
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+pub struct OffsetParams {
+    pub page: Option<i64>,
+    pub limit: Option<i64>,
+}
+
+impl OffsetParams {
+    /// Normalize into `(page, limit, offset)`: page at least 1, limit
+    /// defaulted to [`DEFAULT_LIMIT`] and clamped to `1..=MAX_LIMIT`.
+    pub fn window(self) -> (i64, i64, i64) {
+        self.window_or(i64::from(DEFAULT_LIMIT))
+    }
+
+    /// [`Self::window`] with an endpoint-specific default limit; the cap
+    /// still comes from [`MAX_LIMIT`].
+    pub fn window_or(self, default_limit: i64) -> (i64, i64, i64) {
+        let limit = self.limit.unwrap_or(default_limit).clamp(1, i64::from(MAX_LIMIT));
+        let page = self.page.unwrap_or(1).max(1);
+        (page, limit, (page - 1) * limit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALLOWLIST: Allowlist = Allowlist::new(
+        &[("created_at", "created_at"), ("crop_type", "crop_type")],
+        &[("crop_type", "crop_type"), ("status", "status")],
+    );
+
+    fn params(limit: Option<u32>, sort: Option<&str>) -> PageParams {
+        PageParams {
+            cursor: None,
+            limit,
+            sort: sort.map(str::to_string),
+            filters: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn limit_is_capped_at_max() {
+        let req = PageRequest::from_params(params(Some(10_000), None), &ALLOWLIST).unwrap();
+        assert_eq!(req.limit, MAX_LIMIT);
+        let req = PageRequest::from_params(params(Some(0), None), &ALLOWLIST).unwrap();
+        assert_eq!(req.limit, 1);
+        let req = PageRequest::from_params(params(None, None), &ALLOWLIST).unwrap();
+        assert_eq!(req.limit, DEFAULT_LIMIT);
+    }
+
+    #[test]
+    fn invalid_sort_field_is_rejected() {
+        let err = PageRequest::from_params(params(None, Some("password")), &ALLOWLIST).unwrap_err();
+        assert!(matches!(err, AppError::Validation(_)));
+    }
+
+    #[test]
+    fn sort_prefix_sets_direction() {
+        let req =
+            PageRequest::from_params(params(None, Some("-created_at,crop_type")), &ALLOWLIST)
+                .unwrap();
+        assert_eq!(req.sort[0].direction, SortDirection::Desc);
+        assert_eq!(req.sort[1].direction, SortDirection::Asc);
+    }
+
+    #[test]
+    fn unknown_filter_is_rejected() {
+        let mut raw = params(None, None);
+        raw.filters.insert("role".into(), "admin".into());
+        let err = PageRequest::from_params(raw, &ALLOWLIST).unwrap_err();
+        assert!(matches!(err, AppError::Validation(_)));
+    }
+
+    #[test]
+    fn cursor_round_trips() {
+        let cursor = Cursor {
+            created_at: DateTime::from_timestamp_micros(1_722_000_000_123_456).unwrap(),
+            id: Uuid::new_v4(),
+        };
+        assert_eq!(Cursor::decode(&cursor.encode()).unwrap(), cursor);
+    }
+
+    #[test]
+    fn garbage_cursor_is_a_validation_error() {
+        assert!(matches!(
+            Cursor::decode("not-a-cursor!"),
+            Err(AppError::Validation(_))
+        ));
+    }
+
+    #[test]
+    fn page_from_rows_detects_next_page() {
+        let rows: Vec<(DateTime<Utc>, Uuid)> = (0..4)
+            .map(|i| (DateTime::from_timestamp_micros(1_000 - i).unwrap(), Uuid::new_v4()))
+            .collect();
+        let page = Page::from_rows(rows.clone(), 3, |(ts, id)| Cursor {
+            created_at: *ts,
+            id: *id,
+        });
+        assert_eq!(page.items.len(), 3);
+        // Cursor points at the last *kept* row, so rows inserted after the
+        // fetch (which sort before it) cannot shift the next page.
+        let cursor = Cursor::decode(page.next_cursor.as_deref().unwrap()).unwrap();
+        assert_eq!(cursor.id, rows[2].1);
+
+        let page = Page::from_rows(rows[..2].to_vec(), 3, |(ts, id)| Cursor {
+            created_at: *ts,
+            id: *id,
+        });
+        assert!(page.next_cursor.is_none());
+    }
+
+    #[test]
+    fn offset_windows_clamp_to_sane_bounds() {
+        let window = |page, limit| OffsetParams { page, limit }.window();
+        assert_eq!(window(None, None), (1, 20, 0));
+        assert_eq!(window(Some(3), Some(10)), (3, 10, 20));
+        assert_eq!(window(Some(0), Some(1000)), (1, 100, 0));
+        assert_eq!(window(Some(-2), Some(-5)), (1, 1, 0));
+        // An endpoint-specific default changes the default, never the cap.
+        let admin = OffsetParams { page: None, limit: None };
+        assert_eq!(admin.window_or(50), (1, 50, 0));
+        let admin = OffsetParams { page: None, limit: Some(1000) };
+        assert_eq!(admin.window_or(50), (1, 100, 0));
+    }
+
+    #[test]
+    fn total_pages_rounds_up() {
+        assert_eq!(PaginatedResponse::new(Vec::<()>::new(), 0, 1, 20).total_pages, 0);
+        assert_eq!(PaginatedResponse::new(Vec::<()>::new(), 20, 1, 20).total_pages, 1);
+        assert_eq!(PaginatedResponse::new(Vec::<()>::new(), 21, 2, 20).total_pages, 2);
+    }
+}
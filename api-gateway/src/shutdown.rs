@@ -0,0 +1,143 @@
+//! Coordinated graceful shutdown.
+//!
+//! On SIGTERM or SIGINT the listener stops accepting connections, readiness
+//! flips to 503 so the load balancer drains traffic away, and in-flight
+//! requests get up to `server.drain_timeout` to finish before the remaining
+//! connections are dropped. `main` then closes RabbitMQ cleanly (so unacked
+//! jobs are redelivered immediately rather than after a heartbeat timeout),
+//! flushes one last file-cleanup pass, and drains the Postgres pool.
+
+use std::{net::SocketAddr, time::Duration};
+
+use axum::Router;
+use tokio::net::TcpListener;
+
+/// Resolves when the process receives SIGTERM or SIGINT. SIGTERM is what
+/// the orchestrator sends on deploy; SIGINT keeps Ctrl-C working locally.
+pub async fn signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("install SIGINT handler");
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("install SIGTERM handler")
+            .recv()
+            .await;
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
+
+/// Serve `app` until `shutdown` resolves, then drain: no new connections
+/// are accepted, and in-flight requests get up to `drain_timeout` to
+/// complete before the server gives up on them.
+pub async fn serve_with_drain(
+    listener: TcpListener,
+    app: Router,
+    shutdown: impl std::future::Future<Output = ()> + Send + 'static,
+    drain_timeout: Duration,
+) -> std::io::Result<()> {
+    let (draining_tx, draining_rx) = tokio::sync::oneshot::channel::<()>();
+    let server = axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(async move {
+        shutdown.await;
+        let _ = draining_tx.send(());
+    });
+
+    tokio::select! {
+        result = server => result,
+        _ = async {
+            // The deadline starts when draining starts, not at boot.
+            let _ = draining_rx.await;
+            tokio::time::sleep(drain_timeout).await;
+        } => {
+            tracing::warn!(
+                ?drain_timeout,
+                "drain timeout elapsed; dropping remaining connections"
+            );
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::routing::get;
+
+    async fn slow(
+        axum::extract::Query(params): axum::extract::Query<
+            std::collections::HashMap<String, u64>,
+        >,
+    ) -> &'static str {
+        let ms = params.get("ms").copied().unwrap_or(200);
+        tokio::time::sleep(Duration::from_millis(ms)).await;
+        "done"
+    }
+
+    async fn start(
+        drain_timeout: Duration,
+    ) -> (String, std::sync::Arc<tokio::sync::Notify>, tokio::task::JoinHandle<std::io::Result<()>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let notify = std::sync::Arc::new(tokio::sync::Notify::new());
+        let app = Router::new().route("/slow", get(slow));
+        let server = {
+            let notify = notify.clone();
+            tokio::spawn(serve_with_drain(
+                listener,
+                app,
+                async move { notify.notified().await },
+                drain_timeout,
+            ))
+        };
+        (url, notify, server)
+    }
+
+    #[tokio::test]
+    async fn in_flight_requests_complete_after_shutdown_begins() {
+        let (url, notify, server) = start(Duration::from_secs(5)).await;
+
+        // Request in flight when the "signal" arrives mid-handler.
+        let request = tokio::spawn(reqwest::get(format!("{url}/slow?ms=200")));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        notify.notify_one();
+
+        let response = request.await.unwrap().expect("in-flight request completes");
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.text().await.unwrap(), "done");
+
+        // The server has stopped accepting; a new connection is refused.
+        server.await.unwrap().unwrap();
+        assert!(reqwest::get(format!("{url}/slow?ms=0")).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn drain_timeout_bounds_how_long_stragglers_hold_the_process() {
+        let (url, notify, server) = start(Duration::from_millis(100)).await;
+
+        // A handler that would outlive any reasonable drain.
+        let request = tokio::spawn(reqwest::get(format!("{url}/slow?ms=10000")));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        notify.notify_one();
+
+        // The server must give up well before the handler finishes.
+        tokio::time::timeout(Duration::from_secs(2), server)
+            .await
+            .expect("server exits at the drain deadline")
+            .unwrap()
+            .unwrap();
+        request.abort();
+    }
+}
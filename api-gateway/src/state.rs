@@ -0,0 +1,97 @@
+//! Shared application state threaded through every handler.
+
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, RwLock,
+    },
+};
+
+use sqlx::PgPool;
+
+use crate::{
+    config::{AppConfig, VersionConfig},
+    errors::{AppError, AppResult},
+    logging::LogBroadcaster,
+    middleware::shadow::ShadowMirror,
+    services::{
+        alerts::AlertSink, cache::CacheService, conversations::ConversationRepository,
+        feature_flags::FeatureFlagService, file_storage::FileStorageService,
+        quota::QuotaService, rabbitmq::RabbitMQService, registry::ServiceRegistry,
+    },
+};
+
+#[derive(Clone)]
+pub struct AppState {
+    pub config: Arc<AppConfig>,
+    pub db: PgPool,
+    pub redis_client: redis::Client,
+    pub rabbitmq: Arc<RabbitMQService>,
+    pub services: Arc<ServiceRegistry>,
+    pub file_storage: Arc<FileStorageService>,
+    /// Client-version expectations; behind a lock so admin-triggered config
+    /// reloads apply without a restart.
+    pub versions: Arc<RwLock<VersionConfig>>,
+    /// Requests seen per `X-Client-Version` value, reported by the metrics
+    /// endpoint so we know when the old bundles have drained.
+    pub client_version_counts: Arc<Mutex<HashMap<String, u64>>>,
+    /// Ad-hoc named counters kept alongside the Prometheus recorder; cheap
+    /// to read in-process where a full exposition parse would be overkill.
+    pub counters: Arc<Mutex<HashMap<&'static str, u64>>>,
+    /// Feeds the admin log-streaming endpoint; the matching tracing layer is
+    /// installed in `main`.
+    pub log_broadcaster: LogBroadcaster,
+    /// Ops webhook for critical events; no-op unless configured.
+    pub alerts: Arc<AlertSink>,
+    /// Renders the Prometheus exposition text for the metrics endpoint;
+    /// recording happens through the globally installed recorder.
+    pub metrics: metrics_exporter_prometheus::PrometheusHandle,
+    /// Most recent correlation id, surfaced by the metrics endpoint as a
+    /// debugging breadcrumb.
+    pub last_correlation_id: Arc<Mutex<Option<String>>>,
+    /// Mirrors sampled requests to staging and keeps the comparison log;
+    /// inert when no shadow routes are configured.
+    pub shadow: Arc<ShadowMirror>,
+    /// Set when a shutdown signal arrives; readiness reports 503 from then
+    /// on so the load balancer drains traffic away while in-flight
+    /// requests finish.
+    pub shutting_down: Arc<AtomicBool>,
+    pub conversations: Arc<ConversationRepository>,
+    /// Redis-backed query cache; falls through to the computation whenever
+    /// Redis is unavailable or caching is disabled.
+    pub cache: Arc<CacheService>,
+    /// Runtime feature flags; lookups fail closed when Redis is down.
+    pub feature_flags: Arc<FeatureFlagService>,
+    /// Monthly free-tier usage quotas; inert unless configured.
+    pub quotas: Arc<QuotaService>,
+}
+
+impl AppState {
+    pub fn bump_counter(&self, name: &'static str) {
+        self.add_to_counter(name, 1);
+    }
+
+    pub fn add_to_counter(&self, name: &'static str, amount: u64) {
+        let mut counters = self.counters.lock().expect("counter lock poisoned");
+        *counters.entry(name).or_insert(0) += amount;
+    }
+
+    pub fn begin_shutdown(&self) {
+        self.shutting_down.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_shutting_down(&self) -> bool {
+        self.shutting_down.load(Ordering::SeqCst)
+    }
+}
+
+impl AppState {
+    /// Checked-out async Redis connection; callers should hold it briefly.
+    pub async fn get_redis(&self) -> AppResult<redis::aio::MultiplexedConnection> {
+        self.redis_client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(|e| AppError::ServiceUnavailable(format!("redis: {e}")))
+    }
+}
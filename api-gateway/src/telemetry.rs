@@ -0,0 +1,227 @@
+//! Distributed tracing: OTLP span export plus the per-request HTTP span.
+//!
+//! [`init`] stands up an OTLP/gRPC exporter (Jaeger, Tempo and the OTel
+//! collector all speak it) with parent-based head sampling; the returned
+//! tracer is threaded into `logging::init` so `tracing` spans become OTel
+//! spans. [`trace_http`] replaces the stock `TraceLayer` with a span that
+//! carries the semconv `http.method` / `http.route` / `http.status_code`
+//! attributes, and [`inject_trace_context`] stamps outbound requests to the
+//! vision and LLM services with W3C `traceparent` headers so their spans
+//! join the same trace.
+
+use axum::{
+    extract::{MatchedPath, Request},
+    middleware::Next,
+    response::Response,
+};
+use opentelemetry::KeyValue;
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::trace::Sampler;
+use tracing::Instrument;
+
+use crate::config::TracingConfig;
+
+/// Start the OTLP export pipeline, or `None` when tracing is disabled or
+/// the exporter can't be built. Runs before the log subscriber exists, so
+/// failures go to stderr like `logging::env_filter`'s do.
+pub fn init(config: &TracingConfig) -> Option<opentelemetry_sdk::trace::Tracer> {
+    if !config.enabled {
+        return None;
+    }
+    // W3C TraceContext is what `inject_trace_context` emits and what the
+    // Python services' instrumentation expects.
+    opentelemetry::global::set_text_map_propagator(
+        opentelemetry_sdk::propagation::TraceContextPropagator::new(),
+    );
+    let pipeline = opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(
+            opentelemetry_otlp::new_exporter()
+                .tonic()
+                .with_endpoint(&config.otlp_endpoint),
+        )
+        .with_trace_config(
+            opentelemetry_sdk::trace::Config::default()
+                // Parent-based: a sampled upstream decision wins, so traces
+                // aren't torn apart by our local ratio.
+                .with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(
+                    clamped_sample_rate(config.sample_rate),
+                ))))
+                .with_resource(opentelemetry_sdk::Resource::new([KeyValue::new(
+                    "service.name",
+                    crate::logging::SERVICE_NAME,
+                )])),
+        )
+        .install_batch(opentelemetry_sdk::runtime::Tokio);
+    match pipeline {
+        Ok(tracer) => Some(tracer),
+        Err(e) => {
+            eprintln!("tracing disabled: failed to start OTLP exporter: {e}");
+            None
+        }
+    }
+}
+
+/// Flush buffered spans and stop the exporter. Called once on the SIGTERM
+/// path after the request drain, so the final requests' spans still go out.
+pub fn shutdown() {
+    opentelemetry::global::shutdown_tracer_provider();
+}
+
+/// Ratio samplers only accept `0.0..=1.0`; a typo'd rate should degrade to
+/// all-or-nothing, not panic the boot.
+pub(crate) fn clamped_sample_rate(rate: f64) -> f64 {
+    if rate.is_nan() {
+        return 1.0;
+    }
+    rate.clamp(0.0, 1.0)
+}
+
+/// The span wrapping one HTTP request. `otel.name` gives the exported span
+/// the semconv `METHOD /route` name; the status is recorded by [`trace_http`]
+/// once the response exists.
+pub fn request_span(method: &str, route: &str) -> tracing::Span {
+    tracing::info_span!(
+        "http_request",
+        otel.name = %format!("{method} {route}"),
+        http.method = %method,
+        http.route = %route,
+        http.status_code = tracing::field::Empty,
+    )
+}
+
+/// Middleware creating the request span; replaces `TraceLayer` in
+/// `create_router`. The route label is the matched pattern
+/// (`/api/v1/vision/jobs/:job_id`), not the concrete path, so traces group
+/// by endpoint instead of by job id.
+pub async fn trace_http(request: Request, next: Next) -> Response {
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|p| p.as_str().to_string())
+        .unwrap_or_else(|| request.uri().path().to_string());
+    let span = request_span(request.method().as_str(), &route);
+    let response = next.run(request).instrument(span.clone()).await;
+    span.record("http.status_code", response.status().as_u16());
+    response
+}
+
+/// Stamp an outbound request with the current span's W3C `traceparent` /
+/// `tracestate` headers so upstream spans parent onto this trace. A no-op
+/// when tracing is disabled (the propagator injects nothing).
+pub fn inject_trace_context(mut builder: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+    use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+    let context = tracing::Span::current().context();
+    let mut carrier = std::collections::HashMap::new();
+    opentelemetry::global::get_text_map_propagator(|propagator| {
+        propagator.inject_context(&context, &mut carrier);
+    });
+    for (name, value) in carrier {
+        builder = builder.header(name, value);
+    }
+    builder
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::BTreeMap,
+        sync::{Arc, Mutex},
+    };
+
+    use tracing::field::Visit;
+    use tracing_subscriber::{layer::SubscriberExt, registry::LookupSpan};
+
+    use super::*;
+
+    /// Layer capturing span attributes as strings, both at creation and
+    /// from later `record` calls.
+    #[derive(Clone, Default)]
+    struct SpanCapture {
+        fields: Arc<Mutex<BTreeMap<String, String>>>,
+    }
+
+    #[derive(Default)]
+    struct FieldCollector(BTreeMap<String, String>);
+
+    impl Visit for FieldCollector {
+        fn record_str(&mut self, field: &tracing::field::Field, value: &str) {
+            self.0.insert(field.name().to_string(), value.to_string());
+        }
+        fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+            self.0.insert(field.name().to_string(), format!("{value:?}"));
+        }
+    }
+
+    impl<S> tracing_subscriber::Layer<S> for SpanCapture
+    where
+        S: tracing::Subscriber + for<'a> LookupSpan<'a>,
+    {
+        fn on_new_span(
+            &self,
+            attrs: &tracing::span::Attributes<'_>,
+            _id: &tracing::span::Id,
+            _ctx: tracing_subscriber::layer::Context<'_, S>,
+        ) {
+            let mut collector = FieldCollector::default();
+            attrs.record(&mut collector);
+            self.fields.lock().unwrap().extend(collector.0);
+        }
+
+        fn on_record(
+            &self,
+            _id: &tracing::span::Id,
+            values: &tracing::span::Record<'_>,
+            _ctx: tracing_subscriber::layer::Context<'_, S>,
+        ) {
+            let mut collector = FieldCollector::default();
+            values.record(&mut collector);
+            self.fields.lock().unwrap().extend(collector.0);
+        }
+    }
+
+    #[test]
+    fn request_span_carries_the_route_attributes() {
+        let capture = SpanCapture::default();
+        let subscriber = tracing_subscriber::registry().with(capture.clone());
+        tracing::subscriber::with_default(subscriber, || {
+            let _span = request_span("GET", "/api/v1/vision/jobs/:job_id");
+        });
+
+        let fields = capture.fields.lock().unwrap();
+        assert_eq!(
+            fields.get("http.route").map(String::as_str),
+            Some("/api/v1/vision/jobs/:job_id")
+        );
+        assert_eq!(fields.get("http.method").map(String::as_str), Some("GET"));
+        assert_eq!(
+            fields.get("otel.name").map(String::as_str),
+            Some("GET /api/v1/vision/jobs/:job_id")
+        );
+    }
+
+    #[test]
+    fn status_code_is_recorded_after_the_response() {
+        let capture = SpanCapture::default();
+        let subscriber = tracing_subscriber::registry().with(capture.clone());
+        tracing::subscriber::with_default(subscriber, || {
+            let span = request_span("POST", "/api/v1/vision/analyze");
+            span.record("http.status_code", 202u16);
+        });
+
+        let fields = capture.fields.lock().unwrap();
+        assert_eq!(
+            fields.get("http.status_code").map(String::as_str),
+            Some("202")
+        );
+    }
+
+    #[test]
+    fn sample_rate_is_clamped_to_the_unit_interval() {
+        assert_eq!(clamped_sample_rate(0.25), 0.25);
+        assert_eq!(clamped_sample_rate(-1.0), 0.0);
+        assert_eq!(clamped_sample_rate(7.0), 1.0);
+        assert_eq!(clamped_sample_rate(f64::NAN), 1.0);
+    }
+}
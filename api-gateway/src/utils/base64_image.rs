@@ -0,0 +1,158 @@
+//! Streaming validation and decoding of base64 image payloads.
+//!
+//! A 60 MB base64 string used to be fully decoded into a `Vec` before any
+//! size check ran. Here the decoded size is estimated from the string length
+//! before touching any allocation, the alphabet is validated incrementally,
+//! and decoding happens in fixed-size chunks straight into the storage
+//! writer, so peak memory stays at a few KB regardless of payload size.
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+use crate::errors::{AppError, AppResult};
+
+/// Chunk of base64 characters decoded at a time (must be divisible by 4).
+const CHUNK_CHARS: usize = 16 * 1024;
+
+/// Strip an optional `data:image/<fmt>;base64,` prefix, returning the bare
+/// payload and the format from the MIME type when present.
+pub fn strip_data_url_prefix(input: &str) -> (&str, Option<&str>) {
+    let Some(rest) = input.strip_prefix("data:image/") else {
+        return (input, None);
+    };
+    let Some((format, payload)) = rest.split_once(";base64,") else {
+        return (input, None);
+    };
+    (payload, Some(format))
+}
+
+/// Decoded size implied by the encoded length, computed without decoding.
+/// Exact for well-formed input; an upper bound otherwise.
+pub fn estimated_decoded_len(payload: &str) -> usize {
+    let len = payload.len();
+    if len == 0 {
+        return 0;
+    }
+    let padding = payload.bytes().rev().take_while(|b| *b == b'=').count();
+    (len / 4) * 3 - padding.min(2)
+}
+
+fn is_base64_char(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'+' || b == b'/'
+}
+
+/// Validate and stream-decode `payload` into `writer`, enforcing
+/// `max_decoded_len` *before* any decoding happens.
+pub async fn decode_to_writer<W: AsyncWrite + Unpin>(
+    payload: &str,
+    max_decoded_len: usize,
+    writer: &mut W,
+) -> AppResult<u64> {
+    if payload.is_empty() {
+        return Err(AppError::InvalidEncoding("empty image payload".into()));
+    }
+    if payload.len() % 4 != 0 {
+        return Err(AppError::InvalidEncoding(
+            "base64 payload length must be a multiple of 4".into(),
+        ));
+    }
+    if estimated_decoded_len(payload) > max_decoded_len {
+        return Err(AppError::ImageTooLarge(format!(
+            "decoded image would be ~{} bytes, limit is {}",
+            estimated_decoded_len(payload),
+            max_decoded_len
+        )));
+    }
+
+    let bytes = payload.as_bytes();
+    let mut written: u64 = 0;
+    let mut buf = Vec::with_capacity(CHUNK_CHARS / 4 * 3);
+    for (i, chunk) in bytes.chunks(CHUNK_CHARS).enumerate() {
+        let is_last = (i + 1) * CHUNK_CHARS >= bytes.len();
+        // Incremental alphabet check: padding may only appear at the very
+        // end of the final chunk.
+        for (j, &b) in chunk.iter().enumerate() {
+            let in_padding_tail =
+                is_last && j >= chunk.len().saturating_sub(2) && b == b'=';
+            if !is_base64_char(b) && !in_padding_tail {
+                return Err(AppError::InvalidEncoding(format!(
+                    "invalid base64 character {:?}",
+                    b as char
+                )));
+            }
+        }
+        buf.clear();
+        STANDARD
+            .decode_vec(chunk, &mut buf)
+            .map_err(|e| AppError::InvalidEncoding(format!("base64 decode: {e}")))?;
+        writer
+            .write_all(&buf)
+            .await
+            .map_err(|e| AppError::Internal(format!("write image: {e}")))?;
+        written += buf.len() as u64;
+    }
+    writer
+        .flush()
+        .await
+        .map_err(|e| AppError::Internal(format!("flush image: {e}")))?;
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn decode(payload: &str, max: usize) -> AppResult<Vec<u8>> {
+        let mut out = Vec::new();
+        decode_to_writer(payload, max, &mut out).await?;
+        Ok(out)
+    }
+
+    #[tokio::test]
+    async fn padding_edge_cases_decode_exactly() {
+        assert_eq!(decode("QQ==", 1024).await.unwrap(), b"A");
+        assert_eq!(decode("QUI=", 1024).await.unwrap(), b"AB");
+        assert_eq!(decode("QUJD", 1024).await.unwrap(), b"ABC");
+    }
+
+    #[test]
+    fn size_estimate_accounts_for_padding() {
+        assert_eq!(estimated_decoded_len("QQ=="), 1);
+        assert_eq!(estimated_decoded_len("QUI="), 2);
+        assert_eq!(estimated_decoded_len("QUJD"), 3);
+    }
+
+    #[tokio::test]
+    async fn over_limit_payload_is_rejected_before_decoding() {
+        // 8 chars -> 6 decoded bytes, limit 5.
+        let err = decode("QUJDQUJD", 5).await.unwrap_err();
+        assert!(matches!(err, AppError::ImageTooLarge(_)));
+    }
+
+    #[tokio::test]
+    async fn data_url_prefix_is_stripped_with_format() {
+        let (payload, format) = strip_data_url_prefix("data:image/png;base64,QUJD");
+        assert_eq!(payload, "QUJD");
+        assert_eq!(format, Some("png"));
+        let (payload, format) = strip_data_url_prefix("QUJD");
+        assert_eq!(payload, "QUJD");
+        assert_eq!(format, None);
+    }
+
+    #[tokio::test]
+    async fn invalid_characters_are_rejected() {
+        let err = decode("QUJ!", 1024).await.unwrap_err();
+        assert!(matches!(err, AppError::InvalidEncoding(_)));
+        // Padding in the middle is not valid either.
+        let err = decode("QQ==QUJD", 1024).await.unwrap_err();
+        assert!(matches!(err, AppError::InvalidEncoding(_)));
+    }
+
+    #[tokio::test]
+    async fn large_payload_round_trips_across_chunks() {
+        let original = vec![0xAB_u8; CHUNK_CHARS]; // forces multiple chunks
+        let encoded = STANDARD.encode(&original);
+        let decoded = decode(&encoded, original.len()).await.unwrap();
+        assert_eq!(decoded, original);
+    }
+}
@@ -0,0 +1,167 @@
+//! Magic-byte detection for uploaded images.
+//!
+//! File extensions and declared content types are caller-controlled, so
+//! uploads are sniffed against the leading bytes before they reach storage.
+
+/// Detect the image format from magic bytes, returning the canonical
+/// extension used by `FileStorageConfig::supported_formats`. Unknown or
+/// truncated payloads return `None`.
+pub fn detected_format(bytes: &[u8]) -> Option<&'static str> {
+    if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        Some("jpg")
+    } else if bytes.starts_with(&[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A]) {
+        Some("png")
+    } else if bytes.len() >= 12 && &bytes[0..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+        Some("webp")
+    } else if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        Some("gif")
+    } else {
+        None
+    }
+}
+
+/// True when the sniffed format matches the declared extension, treating
+/// `jpeg` and `jpg` as the same format.
+pub fn matches_extension(bytes: &[u8], extension: &str) -> bool {
+    let ext = extension.to_ascii_lowercase();
+    let ext = if ext == "jpeg" { "jpg".to_string() } else { ext };
+    detected_format(bytes) == Some(ext.as_str())
+}
+
+/// Read the EXIF orientation (tag 0x0112, values 1–8) from a JPEG, if
+/// present and well-formed. Phone cameras store the sensor image unrotated
+/// and set this tag instead, so preprocessing must bake it in before the
+/// re-encode strips metadata.
+pub fn jpeg_orientation(bytes: &[u8]) -> Option<u16> {
+    if !bytes.starts_with(&[0xFF, 0xD8]) {
+        return None;
+    }
+    let mut pos = 2;
+    // Walk JPEG segments looking for APP1/Exif.
+    while pos + 4 <= bytes.len() {
+        if bytes[pos] != 0xFF {
+            return None;
+        }
+        let marker = bytes[pos + 1];
+        if marker == 0xDA {
+            return None; // start of scan: no EXIF before image data
+        }
+        let len = u16::from_be_bytes([bytes[pos + 2], bytes[pos + 3]]) as usize;
+        if len < 2 || pos + 2 + len > bytes.len() {
+            return None;
+        }
+        let body = &bytes[pos + 4..pos + 2 + len];
+        if marker == 0xE1 && body.starts_with(b"Exif\0\0") {
+            return tiff_orientation(&body[6..]);
+        }
+        pos += 2 + len;
+    }
+    None
+}
+
+fn tiff_orientation(tiff: &[u8]) -> Option<u16> {
+    let read_u16 = |at: usize, le: bool| -> Option<u16> {
+        let b: [u8; 2] = tiff.get(at..at + 2)?.try_into().ok()?;
+        Some(if le { u16::from_le_bytes(b) } else { u16::from_be_bytes(b) })
+    };
+    let read_u32 = |at: usize, le: bool| -> Option<u32> {
+        let b: [u8; 4] = tiff.get(at..at + 4)?.try_into().ok()?;
+        Some(if le { u32::from_le_bytes(b) } else { u32::from_be_bytes(b) })
+    };
+
+    let le = match tiff.get(0..2)? {
+        b"II" => true,
+        b"MM" => false,
+        _ => return None,
+    };
+    if read_u16(2, le)? != 42 {
+        return None;
+    }
+    let ifd0 = read_u32(4, le)? as usize;
+    let entries = read_u16(ifd0, le)? as usize;
+    for i in 0..entries {
+        let entry = ifd0 + 2 + i * 12;
+        if read_u16(entry, le)? == 0x0112 {
+            let value = read_u16(entry + 8, le)?;
+            return (1..=8).contains(&value).then_some(value);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Minimal JPEG prefix: SOI + APP1/Exif with a little-endian IFD0
+    /// containing only the orientation tag.
+    fn jpeg_with_orientation(orientation: u16) -> Vec<u8> {
+        let mut tiff = Vec::new();
+        tiff.extend_from_slice(b"II");
+        tiff.extend_from_slice(&42u16.to_le_bytes());
+        tiff.extend_from_slice(&8u32.to_le_bytes()); // IFD0 offset
+        tiff.extend_from_slice(&1u16.to_le_bytes()); // one entry
+        tiff.extend_from_slice(&0x0112u16.to_le_bytes());
+        tiff.extend_from_slice(&3u16.to_le_bytes()); // SHORT
+        tiff.extend_from_slice(&1u32.to_le_bytes());
+        tiff.extend_from_slice(&orientation.to_le_bytes());
+        tiff.extend_from_slice(&[0, 0]); // value padding
+        tiff.extend_from_slice(&0u32.to_le_bytes()); // no next IFD
+
+        let mut body = b"Exif\0\0".to_vec();
+        body.extend_from_slice(&tiff);
+        let mut jpeg = vec![0xFF, 0xD8, 0xFF, 0xE1];
+        jpeg.extend_from_slice(&((body.len() + 2) as u16).to_be_bytes());
+        jpeg.extend_from_slice(&body);
+        jpeg
+    }
+
+    #[test]
+    fn detects_common_formats() {
+        assert_eq!(detected_format(&[0xFF, 0xD8, 0xFF, 0xE0]), Some("jpg"));
+        assert_eq!(
+            detected_format(&[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, 0]),
+            Some("png")
+        );
+        let mut webp = b"RIFF\x00\x00\x00\x00WEBP".to_vec();
+        webp.extend_from_slice(b"VP8 ");
+        assert_eq!(detected_format(&webp), Some("webp"));
+        assert_eq!(detected_format(b"GIF89a"), Some("gif"));
+    }
+
+    #[test]
+    fn rejects_unknown_and_truncated_payloads() {
+        assert_eq!(detected_format(b"not an image"), None);
+        assert_eq!(detected_format(&[0xFF, 0xD8]), None);
+        assert_eq!(detected_format(b"RIFF1234"), None); // RIFF but not WEBP
+    }
+
+    #[test]
+    fn jpeg_and_jpg_extensions_are_interchangeable() {
+        let jpeg = [0xFF, 0xD8, 0xFF, 0xE1];
+        assert!(matches_extension(&jpeg, "jpg"));
+        assert!(matches_extension(&jpeg, "JPEG"));
+        assert!(!matches_extension(&jpeg, "png"));
+    }
+
+    #[test]
+    fn reads_exif_orientation_from_app1() {
+        for orientation in [1u16, 3, 6, 8] {
+            assert_eq!(
+                jpeg_orientation(&jpeg_with_orientation(orientation)),
+                Some(orientation)
+            );
+        }
+    }
+
+    #[test]
+    fn missing_or_invalid_orientation_yields_none() {
+        assert_eq!(jpeg_orientation(&[0xFF, 0xD8, 0xFF, 0xDB, 0x00, 0x02]), None);
+        assert_eq!(jpeg_orientation(b"not a jpeg"), None);
+        assert_eq!(jpeg_orientation(&jpeg_with_orientation(9)), None); // out of range
+        // Truncated APP1 segment must not panic.
+        let mut truncated = jpeg_with_orientation(6);
+        truncated.truncate(12);
+        assert_eq!(jpeg_orientation(&truncated), None);
+    }
+}
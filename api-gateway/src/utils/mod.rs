@@ -0,0 +1,3 @@
+pub mod base64_image;
+pub mod image_sniff;
+pub mod thai_text;
@@ -0,0 +1,113 @@
+//! Thai-aware text preparation for LLM prompts.
+//!
+//! User messages arrive from phone keyboards and copy-paste, which means
+//! compatibility forms (fullwidth digits, ligatures) and invisible
+//! zero-width characters that confuse the LLM's tokenizer. Thai text is
+//! normalized before it reaches a prompt template, and the template itself
+//! is chosen from the language the user actually wrote in rather than only
+//! their stored preference.
+
+use shared::models::Language;
+use unicode_normalization::UnicodeNormalization;
+
+/// Unicode block for Thai (`U+0E00`–`U+0E7F`), covering consonants, vowels,
+/// tone marks, and Thai digits.
+const THAI_BLOCK: std::ops::RangeInclusive<char> = '\u{0E00}'..='\u{0E7F}';
+
+/// Zero-width and invisible formatting characters stripped during
+/// normalization: ZWSP, ZWNJ, ZWJ, word joiner, and the BOM when it shows
+/// up mid-text after a paste.
+const ZERO_WIDTH: [char; 5] = ['\u{200B}', '\u{200C}', '\u{200D}', '\u{2060}', '\u{FEFF}'];
+
+pub struct ThaiTextProcessor;
+
+impl ThaiTextProcessor {
+    /// NFKC-normalize the text and drop zero-width characters. Safe for
+    /// mixed Thai/English input; Thai combining marks are untouched by NFKC.
+    pub fn normalize_thai(text: &str) -> String {
+        text.nfkc().filter(|c| !ZERO_WIDTH.contains(c)).collect()
+    }
+
+    /// Guess the language from codepoint ranges: Thai wins when Thai
+    /// characters are at least as common as ASCII letters, so a Thai
+    /// question quoting an English disease name still reads as Thai. Text
+    /// with no letters at all falls back to [`Language::default`]; callers
+    /// with a stored preference should use [`effective_language`] instead.
+    pub fn detect_language(text: &str) -> Language {
+        let thai = text.chars().filter(|c| THAI_BLOCK.contains(c)).count();
+        let ascii = text.chars().filter(char::is_ascii_alphabetic).count();
+        if thai == 0 && ascii == 0 {
+            Language::default()
+        } else if thai >= ascii {
+            Language::Thai
+        } else {
+            Language::English
+        }
+    }
+}
+
+/// Language to build the prompt in: what the user wrote wins, and the stored
+/// request preference decides only when the text carries no letters (bare
+/// numbers, emoji, punctuation).
+pub fn effective_language(text: &str, stored: Language) -> Language {
+    let has_signal = text
+        .chars()
+        .any(|c| THAI_BLOCK.contains(&c) || c.is_ascii_alphabetic());
+    if has_signal {
+        ThaiTextProcessor::detect_language(text)
+    } else {
+        stored
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_strips_zero_width_non_joiners() {
+        assert_eq!(
+            ThaiTextProcessor::normalize_thai("ใบ\u{200C}มัน\u{200B}เหลือง"),
+            "ใบมันเหลือง"
+        );
+        assert_eq!(
+            ThaiTextProcessor::normalize_thai("\u{FEFF}ทุเรียน\u{2060}"),
+            "ทุเรียน"
+        );
+    }
+
+    #[test]
+    fn normalize_folds_compatibility_forms() {
+        // Fullwidth digits from some keyboards become plain ASCII.
+        assert_eq!(ThaiTextProcessor::normalize_thai("５０％"), "50%");
+        // Thai text itself is preserved byte-for-byte.
+        assert_eq!(
+            ThaiTextProcessor::normalize_thai("น้ำหมักชีวภาพ"),
+            "น้ำหมักชีวภาพ"
+        );
+    }
+
+    #[test]
+    fn detection_follows_the_dominant_script() {
+        assert_eq!(
+            ThaiTextProcessor::detect_language("ใบมันเหลือง ทำไงดี"),
+            Language::Thai
+        );
+        assert_eq!(
+            ThaiTextProcessor::detect_language("why are my durian leaves yellow"),
+            Language::English
+        );
+        // Thai question quoting an English disease name stays Thai.
+        assert_eq!(
+            ThaiTextProcessor::detect_language("โรค leaf blight รักษายังไง"),
+            Language::Thai
+        );
+    }
+
+    #[test]
+    fn letterless_text_defers_to_the_stored_preference() {
+        assert_eq!(effective_language("123 🙏", Language::English), Language::English);
+        assert_eq!(effective_language("123 🙏", Language::Thai), Language::Thai);
+        assert_eq!(effective_language("hello ครับ", Language::English), Language::Thai);
+    }
+}
@@ -0,0 +1,313 @@
+//! Expert annotation editor overlay (reviewer role only).
+//!
+//! Boxes are drawn on an SVG layer over the diagnosis image with pointer
+//! events. All geometry is normalized 0..1 so it survives image resizes; the
+//! math lives in pure functions below so it can be unit tested.
+
+use gloo_net::http::Request;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen_futures::spawn_local;
+use web_sys::KeyboardEvent;
+use yew::prelude::*;
+
+/// Normalized rectangle, mirroring the gateway's `AnnotationBox`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EditorBox {
+    pub label: String,
+    pub bounding_box: [f32; 4],
+}
+
+/// Which resize handle is being dragged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Handle {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+/// Step used for arrow-key nudging (0.5% of the image per press).
+pub const NUDGE_STEP: f32 = 0.005;
+
+/// Build a normalized rect from two drag corners, in any order, clamped to
+/// the image bounds.
+pub fn rect_from_drag(x0: f32, y0: f32, x1: f32, y1: f32) -> [f32; 4] {
+    let left = x0.min(x1).clamp(0.0, 1.0);
+    let top = y0.min(y1).clamp(0.0, 1.0);
+    let right = x0.max(x1).clamp(0.0, 1.0);
+    let bottom = y0.max(y1).clamp(0.0, 1.0);
+    [left, top, right - left, bottom - top]
+}
+
+/// Move a rect by (dx, dy), keeping it fully inside the image.
+pub fn translate_rect(rect: [f32; 4], dx: f32, dy: f32) -> [f32; 4] {
+    let [x, y, w, h] = rect;
+    [
+        (x + dx).clamp(0.0, 1.0 - w),
+        (y + dy).clamp(0.0, 1.0 - h),
+        w,
+        h,
+    ]
+}
+
+/// Resize by dragging `handle` to (px, py). The opposite corner stays fixed;
+/// dragging across it flips the rect instead of producing negative sizes.
+pub fn resize_rect(rect: [f32; 4], handle: Handle, px: f32, py: f32) -> [f32; 4] {
+    let [x, y, w, h] = rect;
+    let (anchor_x, anchor_y) = match handle {
+        Handle::TopLeft => (x + w, y + h),
+        Handle::TopRight => (x, y + h),
+        Handle::BottomLeft => (x + w, y),
+        Handle::BottomRight => (x, y),
+    };
+    rect_from_drag(anchor_x, anchor_y, px, py)
+}
+
+/// Outcome of a save attempt, derived from the gateway response.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SaveOutcome {
+    Saved { new_version: i32 },
+    /// Someone saved a newer version while we were editing: prompt
+    /// reload-and-merge instead of overwriting their work.
+    Conflict { head_version: i32 },
+    Failed(String),
+}
+
+/// Classify the gateway response for a save. A 400 mentioning a version
+/// conflict becomes [`SaveOutcome::Conflict`].
+pub fn classify_save_response(status: u16, body: &str) -> SaveOutcome {
+    if (200..300).contains(&status) {
+        #[derive(Deserialize)]
+        struct Envelope {
+            data: Option<Version>,
+        }
+        #[derive(Deserialize)]
+        struct Version {
+            version: i32,
+        }
+        match serde_json::from_str::<Envelope>(body) {
+            Ok(Envelope { data: Some(v) }) => SaveOutcome::Saved { new_version: v.version },
+            _ => SaveOutcome::Failed("unexpected save response".into()),
+        }
+    } else if status == 400 && body.contains("version conflict") {
+        let head_version = body
+            .split("head is ")
+            .nth(1)
+            .and_then(|rest| rest.split(|c: char| !c.is_ascii_digit()).next())
+            .and_then(|digits| digits.parse().ok())
+            .unwrap_or(0);
+        SaveOutcome::Conflict { head_version }
+    } else {
+        SaveOutcome::Failed(format!("save failed with status {status}"))
+    }
+}
+
+pub fn generate_annotation_editor_css() -> String {
+    r#"
+.annotation-layer { position: absolute; inset: 0; touch-action: none; }
+.annotation-box { fill: rgba(37, 99, 235, 0.15); stroke: var(--electric-blue); stroke-width: 2; }
+.annotation-box.selected { stroke: var(--accent-lime-green); }
+.annotation-handle { fill: #fff; stroke: var(--electric-blue); cursor: nwse-resize; }
+.annotation-toolbar { display: flex; gap: 8px; padding: 8px 0; align-items: center; }
+.annotation-conflict {
+  background: var(--warning-amber);
+  color: var(--ink);
+  padding: 8px 12px;
+  border-radius: 8px;
+}
+"#
+    .to_string()
+}
+
+#[derive(Properties, PartialEq)]
+pub struct AnnotationEditorProps {
+    pub job_id: String,
+    pub labels: Vec<String>,
+    pub initial_boxes: Vec<EditorBox>,
+    pub initial_version: i32,
+}
+
+#[derive(Serialize)]
+struct SaveRequest<'a> {
+    boxes: &'a [EditorBox],
+    base_version: i32,
+}
+
+/// Edit-mode overlay: draw, move, resize, relabel, and delete boxes, then
+/// save optimistically with conflict detection.
+#[function_component(AnnotationEditor)]
+pub fn annotation_editor(props: &AnnotationEditorProps) -> Html {
+    let boxes = use_state(|| props.initial_boxes.clone());
+    let version = use_state(|| props.initial_version);
+    let selected = use_state(|| None::<usize>);
+    let conflict = use_state(|| None::<i32>);
+    let saving = use_state(|| false);
+
+    let on_keydown = {
+        let boxes = boxes.clone();
+        let selected = selected.clone();
+        Callback::from(move |event: KeyboardEvent| {
+            let Some(index) = *selected else { return };
+            let mut next = (*boxes).clone();
+            let Some(b) = next.get_mut(index) else { return };
+            match event.key().as_str() {
+                "ArrowLeft" => b.bounding_box = translate_rect(b.bounding_box, -NUDGE_STEP, 0.0),
+                "ArrowRight" => b.bounding_box = translate_rect(b.bounding_box, NUDGE_STEP, 0.0),
+                "ArrowUp" => b.bounding_box = translate_rect(b.bounding_box, 0.0, -NUDGE_STEP),
+                "ArrowDown" => b.bounding_box = translate_rect(b.bounding_box, 0.0, NUDGE_STEP),
+                "Delete" | "Backspace" => {
+                    next.remove(index);
+                    selected.set(None);
+                }
+                _ => return,
+            }
+            event.prevent_default();
+            boxes.set(next);
+        })
+    };
+
+    let on_save = {
+        let boxes = boxes.clone();
+        let version = version.clone();
+        let conflict = conflict.clone();
+        let saving = saving.clone();
+        let job_id = props.job_id.clone();
+        Callback::from(move |_: MouseEvent| {
+            let boxes = boxes.clone();
+            let version = version.clone();
+            let conflict = conflict.clone();
+            let saving = saving.clone();
+            let job_id = job_id.clone();
+            saving.set(true);
+            spawn_local(async move {
+                let payload = SaveRequest {
+                    boxes: &boxes,
+                    base_version: *version,
+                };
+                let outcome = match Request::put(&format!(
+                    "/api/v1/vision/jobs/{job_id}/annotations"
+                ))
+                .json(&payload)
+                .expect("serialize annotations")
+                .send()
+                .await
+                {
+                    Ok(response) => {
+                        let status = response.status();
+                        let body = response.text().await.unwrap_or_default();
+                        classify_save_response(status, &body)
+                    }
+                    Err(e) => SaveOutcome::Failed(e.to_string()),
+                };
+                match outcome {
+                    SaveOutcome::Saved { new_version } => {
+                        version.set(new_version);
+                        conflict.set(None);
+                    }
+                    SaveOutcome::Conflict { head_version } => conflict.set(Some(head_version)),
+                    SaveOutcome::Failed(message) => {
+                        gloo_console::error!("annotation save failed:", message)
+                    }
+                }
+                saving.set(false);
+            });
+        })
+    };
+
+    html! {
+        <div class="annotation-editor" onkeydown={on_keydown} tabindex="0">
+            if let Some(head) = *conflict {
+                <div class="annotation-conflict" role="alert">
+                    { format!("มีการแก้ไขใหม่กว่า (เวอร์ชัน {head}) กรุณาโหลดใหม่ · A newer version exists. Reload and merge.") }
+                </div>
+            }
+            <svg class="annotation-layer" viewBox="0 0 1 1" preserveAspectRatio="none">
+                { for boxes.iter().enumerate().map(|(i, b)| {
+                    let class = if *selected == Some(i) { "annotation-box selected" } else { "annotation-box" };
+                    let selected = selected.clone();
+                    html! {
+                        <rect
+                            class={class}
+                            x={b.bounding_box[0].to_string()}
+                            y={b.bounding_box[1].to_string()}
+                            width={b.bounding_box[2].to_string()}
+                            height={b.bounding_box[3].to_string()}
+                            onclick={Callback::from(move |_| selected.set(Some(i)))}
+                        />
+                    }
+                }) }
+            </svg>
+            <div class="annotation-toolbar">
+                <button class="btn-primary" onclick={on_save} disabled={*saving}>
+                    { if *saving { "กำลังบันทึก…" } else { "บันทึก · Save" } }
+                </button>
+            </div>
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drag_in_any_direction_yields_positive_size() {
+        assert_eq!(rect_from_drag(0.6, 0.7, 0.2, 0.3), [0.2, 0.3, 0.4, 0.4]);
+        assert_eq!(rect_from_drag(0.2, 0.3, 0.6, 0.7), [0.2, 0.3, 0.4, 0.4]);
+    }
+
+    #[test]
+    fn drag_is_clamped_to_image_bounds() {
+        let [x, y, w, h] = rect_from_drag(-0.5, 0.5, 0.5, 1.5);
+        assert_eq!((x, y), (0.0, 0.5));
+        assert_eq!((w, h), (0.5, 0.5));
+    }
+
+    #[test]
+    fn translate_keeps_rect_inside_image() {
+        let rect = [0.8, 0.8, 0.15, 0.15];
+        assert_eq!(translate_rect(rect, 0.5, 0.5), [0.85, 0.85, 0.15, 0.15]);
+        assert_eq!(translate_rect(rect, -2.0, 0.0), [0.0, 0.8, 0.15, 0.15]);
+    }
+
+    #[test]
+    fn resize_keeps_opposite_corner_anchored() {
+        let rect = [0.2, 0.2, 0.4, 0.4];
+        let resized = resize_rect(rect, Handle::BottomRight, 0.9, 0.9);
+        assert_eq!(resized, [0.2, 0.2, 0.7, 0.7]);
+    }
+
+    #[test]
+    fn resize_across_anchor_flips_instead_of_negative_size() {
+        let rect = [0.4, 0.4, 0.2, 0.2];
+        let resized = resize_rect(rect, Handle::BottomRight, 0.1, 0.1);
+        assert!((resized[0] - 0.1).abs() < 1e-6);
+        assert!(resized[2] > 0.0 && resized[3] > 0.0);
+    }
+
+    #[test]
+    fn version_mismatch_is_classified_as_conflict() {
+        let body = r#"{"success":false,"error":"validation failed: annotation version conflict: head is 7, edit was based on 5"}"#;
+        assert_eq!(
+            classify_save_response(400, body),
+            SaveOutcome::Conflict { head_version: 7 }
+        );
+    }
+
+    #[test]
+    fn successful_save_reports_new_version() {
+        let body = r#"{"success":true,"data":{"job_id":"x","version":3,"boxes":[]}}"#;
+        assert_eq!(
+            classify_save_response(200, body),
+            SaveOutcome::Saved { new_version: 3 }
+        );
+    }
+
+    #[test]
+    fn other_errors_are_plain_failures() {
+        assert!(matches!(
+            classify_save_response(503, "oops"),
+            SaveOutcome::Failed(_)
+        ));
+    }
+}
@@ -0,0 +1,379 @@
+//! Camera capture frame with framing aids.
+//!
+//! The vision team wants consistently framed photos, so the capture frame
+//! offers a rule-of-thirds grid and a device-orientation level indicator
+//! that turns green when the phone is within [`LEVEL_TOLERANCE_DEGREES`]
+//! of level. Both toggles persist in local storage. Devices that never
+//! deliver orientation events simply don't show the indicator, and iOS
+//! 13+ gets a one-tap permission prompt. All overlays are
+//! `pointer-events: none` so they never steal taps from the shutter.
+
+use gloo_events::EventListener;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::JsCast;
+use yew::prelude::*;
+
+/// How far from level (in degrees of roll) still counts as level.
+pub const LEVEL_TOLERANCE_DEGREES: f64 = 3.0;
+
+/// Source rectangle of the raw video frame that `object-fit: cover` leaves
+/// visible in the viewfinder. Captures must draw exactly this region —
+/// drawing the full frame is what made portrait captures come out
+/// stretched relative to the preview.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CropRect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+/// Compute the covered region: the largest centered rectangle of the video
+/// with the viewfinder's aspect ratio. Degenerate dimensions fall back to
+/// the full frame rather than a zero-size capture.
+pub fn cover_crop(video_width: f64, video_height: f64, view_width: f64, view_height: f64) -> CropRect {
+    let full = CropRect { x: 0.0, y: 0.0, width: video_width, height: video_height };
+    if video_width <= 0.0 || video_height <= 0.0 || view_width <= 0.0 || view_height <= 0.0 {
+        return full;
+    }
+    let video_ratio = video_width / video_height;
+    let view_ratio = view_width / view_height;
+    if video_ratio > view_ratio {
+        // Video is wider than the viewfinder: the sides are cropped away.
+        let width = video_height * view_ratio;
+        CropRect { x: (video_width - width) / 2.0, y: 0.0, width, height: video_height }
+    } else {
+        // Video is taller: the top and bottom are cropped away.
+        let height = video_width / view_ratio;
+        CropRect { x: 0.0, y: (video_height - height) / 2.0, width: video_width, height }
+    }
+}
+
+/// What a capture actually produced; sent along with the upload so
+/// stretched-photo reports can be checked against real numbers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImageMetadata {
+    pub width: u32,
+    pub height: u32,
+    /// The preview was mirrored (front camera); the capture itself is
+    /// already un-mirrored.
+    pub mirrored_preview: bool,
+}
+
+/// Draw the visible region of the live video onto a fresh canvas, so the
+/// capture matches the viewfinder pixel for pixel. Front-camera captures
+/// are flipped back: the preview mirrors for the user's benefit, but the
+/// stored image should show the plant the way it really is.
+#[cfg(target_arch = "wasm32")]
+pub fn capture_frame(
+    video: &web_sys::HtmlVideoElement,
+    front_camera: bool,
+) -> Option<(web_sys::HtmlCanvasElement, ImageMetadata)> {
+    let crop = cover_crop(
+        f64::from(video.video_width()),
+        f64::from(video.video_height()),
+        f64::from(video.client_width()),
+        f64::from(video.client_height()),
+    );
+    let document = web_sys::window()?.document()?;
+    let canvas: web_sys::HtmlCanvasElement =
+        document.create_element("canvas").ok()?.dyn_into().ok()?;
+    let width = crop.width.round() as u32;
+    let height = crop.height.round() as u32;
+    canvas.set_width(width);
+    canvas.set_height(height);
+    let context: web_sys::CanvasRenderingContext2d =
+        canvas.get_context("2d").ok()??.dyn_into().ok()?;
+    if front_camera {
+        let _ = context.translate(crop.width, 0.0);
+        let _ = context.scale(-1.0, 1.0);
+    }
+    context
+        .draw_image_with_html_video_element_and_sx_and_sy_and_sw_and_sh_and_dx_and_dy_and_dw_and_dh(
+            video, crop.x, crop.y, crop.width, crop.height, 0.0, 0.0, crop.width, crop.height,
+        )
+        .ok()?;
+    Some((
+        canvas,
+        ImageMetadata { width, height, mirrored_preview: front_camera },
+    ))
+}
+
+const PREFS_KEY: &str = "camera_overlay_prefs";
+
+pub fn generate_camera_capture_css() -> String {
+    r#"
+.camera-frame { position: relative; overflow: hidden; border-radius: 8px; }
+.camera-frame video { display: block; width: 100%; }
+.camera-frame.mirrored video { transform: scaleX(-1); }
+.camera-overlay { position: absolute; inset: 0; pointer-events: none; }
+.camera-grid-line { position: absolute; background: rgba(255, 255, 255, 0.5); }
+.camera-grid-line.v { top: 0; bottom: 0; width: 1px; }
+.camera-grid-line.h { left: 0; right: 0; height: 1px; }
+.camera-level {
+  position: absolute;
+  top: 12px; left: 50%;
+  transform: translateX(-50%);
+  padding: 2px 10px;
+  border-radius: 999px;
+  font-size: 0.8rem;
+  background: rgba(31, 41, 55, 0.7);
+  color: #fff;
+}
+.camera-level.level { background: rgba(22, 163, 74, 0.85); }
+.camera-toggles { display: flex; gap: 8px; margin-top: 8px; }
+"#
+    .to_string()
+}
+
+/// Grid / level toggle state, remembered across sessions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OverlayPrefs {
+    pub grid: bool,
+    pub level: bool,
+}
+
+impl Default for OverlayPrefs {
+    fn default() -> Self {
+        Self { grid: true, level: true }
+    }
+}
+
+pub fn load_prefs() -> OverlayPrefs {
+    use gloo_storage::{LocalStorage, Storage};
+    LocalStorage::get(PREFS_KEY).unwrap_or_default()
+}
+
+pub fn save_prefs(prefs: &OverlayPrefs) {
+    use gloo_storage::{LocalStorage, Storage};
+    let _ = LocalStorage::set(PREFS_KEY, prefs);
+}
+
+/// Degrees of roll away from the nearest level orientation. Portrait and
+/// both landscape grips all count as level, so the distance is taken to
+/// the nearest multiple of 90°.
+pub fn tilt_from_level(roll_degrees: f64) -> f64 {
+    let r = roll_degrees.rem_euclid(90.0);
+    r.min(90.0 - r)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LevelState {
+    Level,
+    Tilted,
+}
+
+pub fn level_state(roll_degrees: f64) -> LevelState {
+    if tilt_from_level(roll_degrees) <= LEVEL_TOLERANCE_DEGREES {
+        LevelState::Level
+    } else {
+        LevelState::Tilted
+    }
+}
+
+/// iOS 13+ gates orientation events behind an explicit permission call
+/// that must come from a user gesture; detect whether that call exists.
+fn needs_orientation_permission() -> bool {
+    let event_ctor = js_sys::Reflect::get(
+        &js_sys::global(),
+        &wasm_bindgen::JsValue::from_str("DeviceOrientationEvent"),
+    );
+    match event_ctor {
+        Ok(ctor) if !ctor.is_undefined() => js_sys::Reflect::get(
+            &ctor,
+            &wasm_bindgen::JsValue::from_str("requestPermission"),
+        )
+        .map(|p| p.is_function())
+        .unwrap_or(false),
+        _ => false,
+    }
+}
+
+fn request_orientation_permission() {
+    let event_ctor = js_sys::Reflect::get(
+        &js_sys::global(),
+        &wasm_bindgen::JsValue::from_str("DeviceOrientationEvent"),
+    );
+    if let Ok(ctor) = event_ctor {
+        if let Ok(request) =
+            js_sys::Reflect::get(&ctor, &wasm_bindgen::JsValue::from_str("requestPermission"))
+        {
+            if let Some(request) = request.dyn_ref::<js_sys::Function>() {
+                let _ = request.call0(&ctor);
+            }
+        }
+    }
+}
+
+#[derive(Properties, PartialEq)]
+pub struct CameraCaptureProps {
+    /// The live preview (a `<video>`) or a still frame.
+    pub children: Children,
+    /// Mirror the preview, as users expect from a front camera. Only the
+    /// preview flips — [`capture_frame`] un-mirrors the stored image.
+    #[prop_or(false)]
+    pub mirror_preview: bool,
+}
+
+#[function_component(CameraCapture)]
+pub fn camera_capture(props: &CameraCaptureProps) -> Html {
+    let prefs = use_state(load_prefs);
+    // None until the first orientation event; unsupported devices stay None
+    // forever and the indicator never renders.
+    let roll = use_state(|| Option::<f64>::None);
+    let permission_needed = use_state(needs_orientation_permission);
+
+    {
+        let roll = roll.clone();
+        use_effect_with((), move |_| {
+            let window = web_sys::window().expect("window");
+            let listener = EventListener::new(&window, "deviceorientation", move |event| {
+                if let Some(event) = event.dyn_ref::<web_sys::DeviceOrientationEvent>() {
+                    if let Some(gamma) = event.gamma() {
+                        roll.set(Some(gamma));
+                    }
+                }
+            });
+            move || drop(listener)
+        });
+    }
+
+    let toggle = |field: fn(&mut OverlayPrefs) -> &mut bool| {
+        let prefs = prefs.clone();
+        Callback::from(move |_| {
+            let mut next = *prefs;
+            let flag = field(&mut next);
+            *flag = !*flag;
+            save_prefs(&next);
+            prefs.set(next);
+        })
+    };
+    let on_grid = toggle(|p| &mut p.grid);
+    let on_level = toggle(|p| &mut p.level);
+
+    let on_request_permission = {
+        let permission_needed = permission_needed.clone();
+        Callback::from(move |_| {
+            request_orientation_permission();
+            permission_needed.set(false);
+        })
+    };
+
+    let level_badge = (*roll).filter(|_| prefs.level).map(|roll| {
+        let state = level_state(roll);
+        let class = classes!(
+            "camera-level",
+            (state == LevelState::Level).then_some("level")
+        );
+        let text = match state {
+            LevelState::Level => "ระดับแล้ว · Level",
+            LevelState::Tilted => "เอียงอยู่ · Tilted",
+        };
+        html! { <span {class} role="status">{ text }</span> }
+    });
+
+    html! {
+        <div>
+            <div class={classes!("camera-frame", props.mirror_preview.then_some("mirrored"))}>
+                { props.children.clone() }
+                <div class="camera-overlay" aria-hidden="true">
+                    if prefs.grid {
+                        <span class="camera-grid-line v" style="left: 33.33%;"></span>
+                        <span class="camera-grid-line v" style="left: 66.66%;"></span>
+                        <span class="camera-grid-line h" style="top: 33.33%;"></span>
+                        <span class="camera-grid-line h" style="top: 66.66%;"></span>
+                    }
+                    { level_badge }
+                </div>
+            </div>
+            <div class="camera-toggles">
+                <button type="button" aria-pressed={prefs.grid.to_string()} onclick={on_grid}>
+                    { "เส้นตาราง · Grid" }
+                </button>
+                <button type="button" aria-pressed={prefs.level.to_string()} onclick={on_level}>
+                    { "ระดับน้ำ · Level" }
+                </button>
+                if *permission_needed && prefs.level {
+                    <button type="button" onclick={on_request_permission}>
+                        { "อนุญาตเซ็นเซอร์ · Allow sensor" }
+                    </button>
+                }
+            </div>
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tilt_is_measured_to_the_nearest_level_grip() {
+        assert_eq!(tilt_from_level(0.0), 0.0);
+        assert_eq!(tilt_from_level(92.0), 2.0);
+        assert_eq!(tilt_from_level(-3.0), 3.0);
+        assert_eq!(tilt_from_level(45.0), 45.0);
+        assert_eq!(tilt_from_level(178.0), 2.0);
+        assert_eq!(tilt_from_level(-268.0), 2.0);
+    }
+
+    #[test]
+    fn level_state_uses_the_tolerance_band() {
+        assert_eq!(level_state(0.0), LevelState::Level);
+        assert_eq!(level_state(-2.9), LevelState::Level);
+        assert_eq!(level_state(3.0), LevelState::Level);
+        assert_eq!(level_state(3.1), LevelState::Tilted);
+        assert_eq!(level_state(88.0), LevelState::Level); // landscape grip
+    }
+
+    #[test]
+    fn landscape_video_in_a_portrait_viewfinder_crops_the_sides() {
+        // 16:9 sensor shown in a 3:4 viewfinder: full height, centered slice.
+        let crop = cover_crop(1920.0, 1080.0, 300.0, 400.0);
+        assert_eq!(crop.height, 1080.0);
+        assert_eq!(crop.width, 810.0);
+        assert_eq!(crop.x, 555.0);
+        assert_eq!(crop.y, 0.0);
+    }
+
+    #[test]
+    fn portrait_video_in_a_landscape_viewfinder_crops_top_and_bottom() {
+        let crop = cover_crop(1080.0, 1920.0, 400.0, 300.0);
+        assert_eq!(crop.width, 1080.0);
+        assert_eq!(crop.height, 810.0);
+        assert_eq!(crop.x, 0.0);
+        assert_eq!(crop.y, 555.0);
+    }
+
+    #[test]
+    fn matching_aspect_ratios_capture_the_full_frame() {
+        let crop = cover_crop(1280.0, 720.0, 640.0, 360.0);
+        assert_eq!(crop, CropRect { x: 0.0, y: 0.0, width: 1280.0, height: 720.0 });
+    }
+
+    #[test]
+    fn same_orientation_but_narrower_viewfinder_still_crops() {
+        // Both landscape, but the viewfinder is squarer than the sensor.
+        let crop = cover_crop(1920.0, 1080.0, 500.0, 400.0);
+        assert_eq!(crop.height, 1080.0);
+        assert_eq!(crop.width, 1350.0);
+        assert_eq!(crop.x, 285.0);
+    }
+
+    #[test]
+    fn degenerate_dimensions_fall_back_to_the_full_frame() {
+        // A hidden video element reports zero client size; capturing the
+        // full frame beats a zero-pixel canvas.
+        let crop = cover_crop(1920.0, 1080.0, 0.0, 0.0);
+        assert_eq!(crop, CropRect { x: 0.0, y: 0.0, width: 1920.0, height: 1080.0 });
+    }
+
+    #[test]
+    fn prefs_round_trip_through_serde() {
+        let prefs = OverlayPrefs { grid: false, level: true };
+        let json = serde_json::to_string(&prefs).unwrap();
+        assert_eq!(serde_json::from_str::<OverlayPrefs>(&json).unwrap(), prefs);
+        // Defaults favour the framing aids being on.
+        assert_eq!(OverlayPrefs::default(), OverlayPrefs { grid: true, level: true });
+    }
+}
@@ -0,0 +1,312 @@
+//! Streaming chat window over the `/api/v1/chat/ws` WebSocket.
+//!
+//! Each send opens a fresh socket, writes the request as one JSON frame, and
+//! renders tokens as they arrive instead of waiting 10–30s for the complete
+//! answer. A fresh socket per message is deliberate: mobile connections drop
+//! often, and reconnect logic for a single long-lived socket buys nothing
+//! when the server treats every message independently anyway.
+
+use chrono::Utc;
+use gloo_storage::{LocalStorage, Storage};
+use shared::models::{ChatMessage, MessageRole};
+use yew::prelude::*;
+
+use crate::components::job_card::JobCard;
+use crate::components::voice_recorder::{VoiceRecorder, VOICE_MIME};
+use crate::services::api::ApiService;
+use crate::simple_app::{AppAction, AppContext, TimelineEntry};
+
+/// LocalStorage key holding the bearer token after login.
+const AUTH_TOKEN_KEY: &str = "auth_token";
+
+pub fn generate_chat_window_css() -> String {
+    r#"
+.chat-window { display: flex; flex-direction: column; gap: 8px; }
+.chat-messages { display: flex; flex-direction: column; gap: 8px; }
+.chat-bubble {
+  max-width: 85%;
+  padding: 8px 12px;
+  border-radius: 12px;
+  white-space: pre-wrap;
+  overflow-wrap: anywhere;
+}
+.chat-bubble.user { align-self: flex-end; background: var(--electric-blue); color: #fff; }
+.chat-bubble.assistant { align-self: flex-start; background: var(--surface); }
+.chat-bubble.system {
+  align-self: center;
+  font-size: 0.8rem;
+  color: var(--ink);
+  opacity: 0.7;
+}
+.chat-bubble.streaming::after { content: "▌"; animation: spin 1s step-end infinite; }
+.chat-input-row { display: flex; gap: 8px; }
+.chat-input-row input { flex: 1; padding: 8px 12px; border-radius: 8px; border: 1px solid #d1d5db; }
+"#
+    .to_string()
+}
+
+/// One frame received from the chat socket. Token frames are plain text;
+/// control frames are JSON objects with a `done` or `error` key. An LLM
+/// token that happens to parse as JSON but has neither key is still a token.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WsFrame {
+    Token(String),
+    Done { conversation_id: String },
+    Error(String),
+}
+
+pub fn parse_ws_frame(text: &str) -> WsFrame {
+    if let Ok(value) = serde_json::from_str::<serde_json::Value>(text) {
+        if value.get("done").and_then(|d| d.as_bool()) == Some(true) {
+            if let Some(id) = value.get("conversation_id").and_then(|v| v.as_str()) {
+                return WsFrame::Done {
+                    conversation_id: id.to_string(),
+                };
+            }
+        }
+        if let Some(error) = value.get("error").and_then(|v| v.as_str()) {
+            return WsFrame::Error(error.to_string());
+        }
+    }
+    WsFrame::Token(text.to_string())
+}
+
+/// Derive the socket URL from the page origin so the same bundle works on
+/// localhost and behind TLS.
+pub fn ws_url(origin: &str, token: &str) -> String {
+    let base = if let Some(rest) = origin.strip_prefix("https://") {
+        format!("wss://{rest}")
+    } else if let Some(rest) = origin.strip_prefix("http://") {
+        format!("ws://{rest}")
+    } else {
+        origin.to_string()
+    };
+    format!("{base}/api/v1/chat/ws?token={token}")
+}
+
+#[function_component(ChatWindow)]
+pub fn chat_window() -> Html {
+    let app = use_context::<AppContext>().expect("ChatWindow must be inside the app context");
+    let input = use_state(String::new);
+    let streaming = use_state(|| Option::<String>::None);
+    let conversation_id = use_state(|| Option::<String>::None);
+
+    let oninput = {
+        let input = input.clone();
+        Callback::from(move |e: InputEvent| {
+            let target: web_sys::HtmlInputElement = e.target_unchecked_into();
+            input.set(target.value());
+        })
+    };
+
+    let send = {
+        let app = app.clone();
+        let input = input.clone();
+        let streaming = streaming.clone();
+        let conversation_id = conversation_id.clone();
+        Callback::from(move |_| {
+            let message = (*input).trim().to_string();
+            if message.is_empty() || streaming.is_some() {
+                return;
+            }
+            input.set(String::new());
+            app.dispatch(AppAction::PushMessage(ChatMessage {
+                id: None,
+                conversation_id: None,
+                role: MessageRole::User,
+                content: message.clone(),
+                image_url: None,
+                created_at: Utc::now(),
+            }));
+            streaming.set(Some(String::new()));
+
+            let app = app.clone();
+            let streaming = streaming.clone();
+            let conversation_id = conversation_id.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                use futures_util::{SinkExt, StreamExt};
+                use gloo_net::websocket::{futures::WebSocket, Message};
+
+                let token: String = LocalStorage::get(AUTH_TOKEN_KEY).unwrap_or_default();
+                let origin = web_sys::window()
+                    .and_then(|w| w.location().origin().ok())
+                    .unwrap_or_default();
+                let request = serde_json::json!({
+                    "conversation_id": *conversation_id,
+                    "message": message,
+                    "crop_context": app.pinned_crop,
+                });
+
+                let finish = |streaming: &UseStateHandle<Option<String>>, app: &AppContext| {
+                    if let Some(text) = (**streaming).clone() {
+                        if !text.is_empty() {
+                            app.dispatch(AppAction::PushMessage(ChatMessage {
+                                id: None,
+                                conversation_id: None,
+                                role: MessageRole::Assistant,
+                                content: text,
+                                image_url: None,
+                                created_at: Utc::now(),
+                            }));
+                        }
+                    }
+                    streaming.set(None);
+                };
+
+                let Ok(mut socket) = WebSocket::open(&ws_url(&origin, &token)) else {
+                    app.dispatch(AppAction::SetError(Some(
+                        "เชื่อมต่อไม่ได้ · Could not connect".into(),
+                    )));
+                    streaming.set(None);
+                    return;
+                };
+                if socket.send(Message::Text(request.to_string())).await.is_err() {
+                    app.dispatch(AppAction::SetError(Some(
+                        "เชื่อมต่อไม่ได้ · Could not connect".into(),
+                    )));
+                    streaming.set(None);
+                    return;
+                }
+
+                while let Some(Ok(Message::Text(text))) = socket.next().await {
+                    match parse_ws_frame(&text) {
+                        WsFrame::Token(token) => {
+                            let mut buffer = (*streaming).clone().unwrap_or_default();
+                            buffer.push_str(&token);
+                            streaming.set(Some(buffer));
+                        }
+                        WsFrame::Done { conversation_id: id } => {
+                            conversation_id.set(Some(id));
+                            break;
+                        }
+                        WsFrame::Error(error) => {
+                            app.dispatch(AppAction::SetError(Some(error)));
+                            break;
+                        }
+                    }
+                }
+                // Either done, errored, or the connection dropped: keep what
+                // we already streamed rather than discarding a partial answer.
+                finish(&streaming, &app);
+            });
+        })
+    };
+
+    // A finished recording goes up as a voice note; the transcript comes
+    // back through the conversation like any other assistant turn.
+    let on_voice = {
+        let app = app.clone();
+        let conversation_id = conversation_id.clone();
+        Callback::from(move |audio_base64: String| {
+            app.dispatch(AppAction::PushMessage(ChatMessage {
+                id: None,
+                conversation_id: None,
+                role: MessageRole::User,
+                content: "🎤 ข้อความเสียง · voice note".into(),
+                image_url: None,
+                created_at: Utc::now(),
+            }));
+            let app = app.clone();
+            let conversation_id = conversation_id.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                let api = ApiService::new();
+                if let Err(error) = api
+                    .send_voice_note(&audio_base64, VOICE_MIME, (*conversation_id).as_deref())
+                    .await
+                {
+                    app.dispatch(AppAction::SetError(Some(error.to_string())));
+                }
+            });
+        })
+    };
+
+    let onkeydown = {
+        let send = send.clone();
+        Callback::from(move |e: KeyboardEvent| {
+            if e.key() == "Enter" && !e.shift_key() {
+                e.prevent_default();
+                send.emit(());
+            }
+        })
+    };
+
+    html! {
+        <div class="chat-window">
+            <div class="chat-messages">
+                { for app.messages.iter().map(|entry| match entry {
+                    TimelineEntry::Message(m) => {
+                        let class = match m.role {
+                            MessageRole::User => "chat-bubble user",
+                            MessageRole::Assistant => "chat-bubble assistant",
+                            MessageRole::System => "chat-bubble system",
+                        };
+                        html! { <div class={class}>{ &m.content }</div> }
+                    }
+                    TimelineEntry::JobCard { job_id } => html! {
+                        <JobCard key={job_id.clone()} job_id={job_id.clone()} />
+                    },
+                }) }
+                if let Some(partial) = &*streaming {
+                    <div class="chat-bubble assistant streaming">{ partial }</div>
+                }
+            </div>
+            <div class="chat-input-row">
+                <input
+                    type="text"
+                    value={(*input).clone()}
+                    placeholder="ถามเรื่องพืชของคุณ · Ask about your crop"
+                    {oninput}
+                    {onkeydown}
+                />
+                <VoiceRecorder on_recording_complete={on_voice} />
+                <button
+                    class="btn-primary"
+                    disabled={streaming.is_some()}
+                    onclick={send.reform(|_| ())}
+                >
+                    { "ส่ง · Send" }
+                </button>
+            </div>
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_text_is_a_token() {
+        assert_eq!(parse_ws_frame("ใบ"), WsFrame::Token("ใบ".into()));
+    }
+
+    #[test]
+    fn done_frame_is_recognised() {
+        let frame = parse_ws_frame(r#"{"done": true, "conversation_id": "abc"}"#);
+        assert_eq!(frame, WsFrame::Done { conversation_id: "abc".into() });
+    }
+
+    #[test]
+    fn error_frame_is_recognised() {
+        let frame = parse_ws_frame(r#"{"error": "token expired"}"#);
+        assert_eq!(frame, WsFrame::Error("token expired".into()));
+    }
+
+    #[test]
+    fn json_looking_token_without_control_keys_stays_a_token() {
+        let frame = parse_ws_frame(r#"{"nitrogen": 12}"#);
+        assert_eq!(frame, WsFrame::Token(r#"{"nitrogen": 12}"#.into()));
+    }
+
+    #[test]
+    fn ws_url_maps_scheme_and_appends_token() {
+        assert_eq!(
+            ws_url("https://farm.example.com", "t0k"),
+            "wss://farm.example.com/api/v1/chat/ws?token=t0k"
+        );
+        assert_eq!(
+            ws_url("http://localhost:8080", "t0k"),
+            "ws://localhost:8080/api/v1/chat/ws?token=t0k"
+        );
+    }
+}
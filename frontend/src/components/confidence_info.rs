@@ -0,0 +1,120 @@
+//! Confidence bar with an inline "what does this mean?" popover.
+//!
+//! Rendered next to every confidence value (diagnosis results, chat message
+//! bubbles) so a 62% never reads as near-certain. Copy comes from
+//! `shared::confidence`, the same table the gateway report uses.
+
+use shared::confidence::{band_for, explanation, guidance, ConfidenceBand};
+use shared::models::Language;
+use yew::prelude::*;
+
+pub fn generate_confidence_info_css() -> String {
+    r#"
+.confidence-info { position: relative; display: flex; align-items: center; gap: 8px; }
+.confidence-bar { flex: 1; height: 8px; border-radius: 4px; background: var(--surface); overflow: hidden; }
+.confidence-bar > span { display: block; height: 100%; border-radius: 4px; }
+.confidence-bar.low > span { background: var(--danger-red); }
+.confidence-bar.medium > span { background: var(--warning-amber); }
+.confidence-bar.high > span { background: var(--leaf-green); }
+.confidence-info-button {
+  border: none;
+  background: var(--surface);
+  border-radius: 50%;
+  width: 24px;
+  height: 24px;
+  cursor: pointer;
+}
+.confidence-popover {
+  position: absolute;
+  top: calc(100% + 4px);
+  right: 0;
+  z-index: 100;
+  width: 260px;
+  padding: 12px;
+  border-radius: 8px;
+  background: #fff;
+  box-shadow: 0 4px 12px rgba(31, 41, 55, 0.2);
+  font-size: 0.85rem;
+}
+.confidence-popover p { margin: 0 0 8px; }
+.confidence-popover p:last-child { margin-bottom: 0; }
+"#
+    .to_string()
+}
+
+fn band_class(band: ConfidenceBand) -> &'static str {
+    match band {
+        ConfidenceBand::Low => "low",
+        ConfidenceBand::Medium => "medium",
+        ConfidenceBand::High => "high",
+    }
+}
+
+#[derive(Properties, PartialEq)]
+pub struct ConfidenceInfoProps {
+    /// Confidence in `0.0..=1.0`.
+    pub confidence: f32,
+}
+
+#[function_component(ConfidenceInfo)]
+pub fn confidence_info(props: &ConfidenceInfoProps) -> Html {
+    let open = use_state(|| false);
+    let band = band_for(props.confidence);
+    let percent = (props.confidence * 100.0).round() as i32;
+
+    let toggle = {
+        let open = open.clone();
+        Callback::from(move |_| open.set(!*open))
+    };
+    // Escape closes from anywhere inside the widget, keeping it reachable
+    // without a pointer.
+    let onkeydown = {
+        let open = open.clone();
+        Callback::from(move |e: KeyboardEvent| {
+            if e.key() == "Escape" {
+                open.set(false);
+            }
+        })
+    };
+
+    html! {
+        <div class="confidence-info" {onkeydown}>
+            <div class={classes!("confidence-bar", band_class(band))} aria-hidden="true">
+                <span style={format!("width: {percent}%;")} />
+            </div>
+            <span>{ format!("{percent}%") }</span>
+            <button
+                type="button"
+                class="confidence-info-button"
+                aria-expanded={open.to_string()}
+                aria-label="อธิบายค่าความมั่นใจ · Explain confidence"
+                onclick={toggle.clone()}
+            >
+                { "ℹ" }
+            </button>
+            if *open {
+                <div class="confidence-popover" role="dialog" aria-label="Confidence explanation">
+                    <p>{ explanation(Language::Thai) }</p>
+                    <p>{ explanation(Language::English) }</p>
+                    <p><strong>{ guidance(band, Language::Thai) }</strong></p>
+                    <p>{ guidance(band, Language::English) }</p>
+                    <button type="button" class="btn-primary" onclick={toggle}>
+                        { "ปิด · Close" }
+                    </button>
+                </div>
+            }
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn band_classes_cover_all_bands() {
+        assert_eq!(band_class(band_for(0.3)), "low");
+        assert_eq!(band_class(band_for(0.6)), "medium");
+        assert_eq!(band_class(band_for(0.9)), "high");
+    }
+}
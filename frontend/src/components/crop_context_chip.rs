@@ -0,0 +1,107 @@
+//! Pinned crop context chip shown at the top of a conversation.
+
+use shared::models::CropType;
+use yew::prelude::*;
+
+use crate::simple_app::{AppAction, AppContext};
+
+pub fn generate_crop_context_chip_css() -> String {
+    r#"
+.crop-chip {
+  display: inline-flex;
+  align-items: center;
+  gap: 6px;
+  background: var(--leaf-green);
+  color: #fff;
+  border-radius: 999px;
+  padding: 4px 12px;
+  font-size: 0.85rem;
+}
+.crop-chip select {
+  background: transparent;
+  color: inherit;
+  border: none;
+  font: inherit;
+}
+"#
+    .to_string()
+}
+
+fn crop_label(crop: CropType) -> &'static str {
+    match crop {
+        CropType::Rice => "ข้าว · Rice",
+        CropType::Cassava => "มันสำปะหลัง · Cassava",
+        CropType::Durian => "ทุเรียน · Durian",
+        CropType::Mango => "มะม่วง · Mango",
+        CropType::Rubber => "ยางพารา · Rubber",
+        CropType::Corn => "ข้าวโพด · Corn",
+        CropType::Sugarcane => "อ้อย · Sugarcane",
+        CropType::Soybean => "ถั่วเหลือง · Soybean",
+        CropType::Pineapple => "สับปะรด · Pineapple",
+    }
+}
+
+/// Chip showing the active crop with a picker to change it. Changing the pin
+/// dispatches a manual [`AppAction::PinCrop`] (which records a system
+/// message) and persists server-side via the conversation context endpoint.
+#[function_component(CropContextChip)]
+pub fn crop_context_chip() -> Html {
+    let app = use_context::<AppContext>().expect("AppContext provided");
+
+    let onchange = {
+        let app = app.clone();
+        Callback::from(move |event: Event| {
+            let value = event
+                .target_dyn_into::<web_sys::HtmlSelectElement>()
+                .map(|select| select.value())
+                .unwrap_or_default();
+            if let Ok(crop) =
+                serde_json::from_value::<CropType>(serde_json::Value::String(value))
+            {
+                app.dispatch(AppAction::PinCrop { crop, manual: true });
+            }
+        })
+    };
+
+    html! {
+        <span class="crop-chip">
+            { "📌" }
+            <select aria-label="พืชที่ปักหมุด · Pinned crop" {onchange}>
+                <option value="" selected={app.pinned_crop.is_none()} disabled=true>
+                    { "เลือกพืช · Pick a crop" }
+                </option>
+                { for CropType::ALL.iter().map(|crop| html! {
+                    <option
+                        value={crop.as_str()}
+                        selected={app.pinned_crop == Some(*crop)}
+                    >
+                        { crop_label(*crop) }
+                    </option>
+                }) }
+            </select>
+        </span>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_crop_has_a_bilingual_label() {
+        for crop in CropType::ALL {
+            let label = crop_label(*crop);
+            let (thai, english) = label
+                .split_once(" · ")
+                .unwrap_or_else(|| panic!("label '{label}' is not 'thai · english'"));
+            assert!(
+                thai.chars().any(|c| ('\u{0E00}'..='\u{0E7F}').contains(&c)),
+                "label '{label}' lacks a Thai name"
+            );
+            assert!(
+                english.chars().any(|c| c.is_ascii_alphabetic()),
+                "label '{label}' lacks an English name"
+            );
+        }
+    }
+}
@@ -0,0 +1,207 @@
+//! Dashboard: weekly severity and volume trends for one crop.
+//!
+//! Two [`TrendChart`]s over `GET /api/v1/dashboard/trends` — average
+//! severity score and analysis count per week for the selected crop, last
+//! 12 weeks. Weeks with no analyses are omitted by the gateway, and a
+//! week that arrives with `count: 0` renders as a gap: a farmer who
+//! uploaded nothing in week 3 did not have a zero-severity week 3.
+
+use gloo_net::http::Request;
+use gloo_storage::{LocalStorage, Storage};
+use serde::Deserialize;
+use shared::models::CropType;
+use yew::prelude::*;
+
+use crate::components::trend_chart::{TrendChart, TrendPoint};
+
+/// LocalStorage key holding the bearer token after login.
+const AUTH_TOKEN_KEY: &str = "auth_token";
+/// Trend window requested from the gateway.
+const TREND_WEEKS: u32 = 12;
+
+/// One week in the trends response. `avg_severity` is null when the week
+/// had no analyses (or none with a severity score).
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct TrendWeek {
+    /// ISO date of the week's Monday.
+    pub week_start: String,
+    pub avg_severity: Option<f64>,
+    pub count: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct TrendsData {
+    pub crop_type: String,
+    pub weeks: Vec<TrendWeek>,
+}
+
+#[derive(Deserialize)]
+struct TrendsEnvelope {
+    data: Option<TrendsData>,
+}
+
+/// Severity series: empty weeks are gaps, not zeros.
+pub fn severity_points(weeks: &[TrendWeek]) -> Vec<TrendPoint> {
+    weeks
+        .iter()
+        .map(|week| TrendPoint {
+            label: week.week_start.clone(),
+            value: week.avg_severity,
+        })
+        .collect()
+}
+
+/// Count series: a week with zero analyses is also a gap — the line shows
+/// usage, and "no uploads" is absence of usage, not a measured zero.
+pub fn count_points(weeks: &[TrendWeek]) -> Vec<TrendPoint> {
+    weeks
+        .iter()
+        .map(|week| TrendPoint {
+            label: week.week_start.clone(),
+            value: (week.count > 0).then_some(week.count as f64),
+        })
+        .collect()
+}
+
+pub fn generate_dashboard_css() -> String {
+    r#"
+.dashboard { display: flex; flex-direction: column; gap: 8px; }
+.dashboard-crop-picker { display: flex; gap: 6px; flex-wrap: wrap; margin-bottom: 8px; }
+.dashboard-crop-picker button {
+  padding: 4px 12px;
+  border-radius: 16px;
+  border: 1px solid var(--leaf-green);
+  background: transparent;
+  cursor: pointer;
+}
+.dashboard-crop-picker button.selected { background: var(--leaf-green); color: #fff; }
+.dashboard-error { color: var(--danger-red); font-size: 0.9rem; }
+"#
+    .to_string()
+}
+
+async fn fetch_trends(crop_type: CropType) -> Result<TrendsData, String> {
+    let token: String = LocalStorage::get(AUTH_TOKEN_KEY).unwrap_or_default();
+    let url = format!(
+        "/api/v1/dashboard/trends?crop_type={}&weeks={TREND_WEEKS}",
+        crop_type.as_str()
+    );
+    let response = Request::get(&url)
+        .header("Authorization", &format!("Bearer {token}"))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !response.ok() {
+        return Err(format!("trends request failed: {}", response.status()));
+    }
+    let envelope: TrendsEnvelope = response.json().await.map_err(|e| e.to_string())?;
+    envelope.data.ok_or_else(|| "empty trends response".into())
+}
+
+#[function_component(Dashboard)]
+pub fn dashboard() -> Html {
+    let crop = use_state(|| CropType::Rice);
+    let trends = use_state(|| Option::<TrendsData>::None);
+    let error = use_state(|| Option::<String>::None);
+
+    {
+        let trends = trends.clone();
+        let error = error.clone();
+        use_effect_with(*crop, move |crop| {
+            let crop = *crop;
+            wasm_bindgen_futures::spawn_local(async move {
+                match fetch_trends(crop).await {
+                    Ok(data) => {
+                        error.set(None);
+                        trends.set(Some(data));
+                    }
+                    Err(reason) => error.set(Some(reason)),
+                }
+            });
+        });
+    }
+
+    let picker = CropType::ALL.iter().map(|candidate| {
+        let selected = *crop == *candidate;
+        let class = if selected { "selected" } else { "" };
+        let onclick = {
+            let crop = crop.clone();
+            let candidate = *candidate;
+            Callback::from(move |_| crop.set(candidate))
+        };
+        html! {
+            <button class={class} {onclick}>{ candidate.as_str() }</button>
+        }
+    });
+
+    html! {
+        <div class="dashboard">
+            <div class="dashboard-crop-picker" role="tablist">
+                { for picker }
+            </div>
+            if let Some(reason) = &*error {
+                <p class="dashboard-error" role="alert">
+                    { format!("โหลดข้อมูลไม่ได้ · Could not load trends: {reason}") }
+                </p>
+            }
+            if let Some(trends) = &*trends {
+                <TrendChart
+                    title={"ความรุนแรงเฉลี่ยต่อสัปดาห์ · Average severity per week".to_string()}
+                    points={severity_points(&trends.weeks)}
+                />
+                <TrendChart
+                    title={"จำนวนการวิเคราะห์ต่อสัปดาห์ · Analyses per week".to_string()}
+                    points={count_points(&trends.weeks)}
+                    integer_values={true}
+                />
+            }
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn week(start: &str, avg: Option<f64>, count: u64) -> TrendWeek {
+        TrendWeek {
+            week_start: start.into(),
+            avg_severity: avg,
+            count,
+        }
+    }
+
+    #[test]
+    fn empty_weeks_become_gaps_in_both_series() {
+        let weeks = vec![
+            week("2026-06-01", Some(0.4), 7),
+            week("2026-06-08", None, 0),
+            week("2026-06-15", Some(0.6), 3),
+        ];
+        let severity = severity_points(&weeks);
+        assert_eq!(severity[1].value, None);
+        assert_eq!(severity[2].value, Some(0.6));
+
+        let counts = count_points(&weeks);
+        assert_eq!(counts[0].value, Some(7.0));
+        assert_eq!(counts[1].value, None, "zero analyses is a gap, not a zero");
+    }
+
+    #[test]
+    fn trends_envelope_deserializes_with_null_severity() {
+        let json = r#"{
+            "success": true,
+            "data": {
+                "crop_type": "rice",
+                "weeks": [
+                    {"week_start": "2026-06-01", "avg_severity": 0.42, "count": 17},
+                    {"week_start": "2026-06-08", "avg_severity": null, "count": 0}
+                ]
+            }
+        }"#;
+        let envelope: TrendsEnvelope = serde_json::from_str(json).unwrap();
+        let data = envelope.data.unwrap();
+        assert_eq!(data.weeks.len(), 2);
+        assert_eq!(data.weeks[1].avg_severity, None);
+    }
+}
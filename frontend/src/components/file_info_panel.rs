@@ -0,0 +1,80 @@
+//! File details shown under the image picker: name, size, and — when the
+//! EXIF parser finds one — the capture date, with a warning for old photos.
+
+use chrono::{DateTime, Utc};
+use yew::prelude::*;
+
+use crate::services::exif::{is_stale, DEFAULT_STALE_DAYS};
+
+pub fn generate_file_info_panel_css() -> String {
+    r#"
+.file-info-panel { font-size: 0.85rem; color: var(--ink); }
+.file-info-panel dl { display: grid; grid-template-columns: auto 1fr; gap: 4px 12px; margin: 8px 0; }
+.file-info-panel dt { opacity: 0.7; }
+.file-info-panel dd { margin: 0; }
+.stale-photo-warning {
+  background: var(--warning-amber);
+  color: var(--ink);
+  padding: 8px 12px;
+  border-radius: 8px;
+  margin-top: 8px;
+}
+"#
+    .to_string()
+}
+
+pub fn format_size(bytes: u64) -> String {
+    if bytes >= 1024 * 1024 {
+        format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+    } else {
+        format!("{:.0} KB", (bytes as f64 / 1024.0).max(1.0))
+    }
+}
+
+#[derive(Properties, PartialEq)]
+pub struct FileInfoPanelProps {
+    pub file_name: String,
+    pub size_bytes: u64,
+    /// From EXIF `DateTimeOriginal`; `None` hides the row and the warning.
+    pub captured: Option<DateTime<Utc>>,
+}
+
+#[function_component(FileInfoPanel)]
+pub fn file_info_panel(props: &FileInfoPanelProps) -> Html {
+    let stale = props
+        .captured
+        .is_some_and(|captured| is_stale(captured, Utc::now(), DEFAULT_STALE_DAYS));
+
+    html! {
+        <div class="file-info-panel card">
+            <dl>
+                <dt>{ "ไฟล์ · File" }</dt>
+                <dd>{ &props.file_name }</dd>
+                <dt>{ "ขนาด · Size" }</dt>
+                <dd>{ format_size(props.size_bytes) }</dd>
+                if let Some(captured) = props.captured {
+                    <dt>{ "ถ่ายเมื่อ · Taken" }</dt>
+                    <dd>{ captured.format("%Y-%m-%d").to_string() }</dd>
+                }
+            </dl>
+            if stale {
+                <div class="stale-photo-warning" role="status">
+                    { "รูปนี้ถ่ายนานแล้ว อาการอาจเปลี่ยนไป แนะนำให้ถ่ายรูปใหม่ · \
+                       This photo is old; symptoms may have changed. Consider taking a fresh one." }
+                </div>
+            }
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sizes_format_in_sensible_units() {
+        assert_eq!(format_size(500), "1 KB");
+        assert_eq!(format_size(200 * 1024), "200 KB");
+        assert_eq!(format_size(3 * 1024 * 1024 + 150 * 1024), "3.1 MB");
+    }
+}
@@ -0,0 +1,402 @@
+//! Job history list with multi-select bulk actions.
+//!
+//! Selection mode is entered from a checkbox toggle (or long-press on
+//! touch); a bottom action bar then offers tag / export CSV / delete on the
+//! selected jobs. Delete is optimistic with an undo toast backed by the
+//! gateway's soft delete. Selection and undo state live in plain structs so
+//! the interaction rules are testable without a DOM.
+
+use std::collections::BTreeSet;
+
+use gloo_net::http::Request;
+use serde::{Deserialize, Serialize};
+use yew::prelude::*;
+
+/// Mirrors the gateway's `MAX_BULK_JOBS` cap.
+pub const MAX_SELECTED: usize = 100;
+
+pub fn generate_history_list_css() -> String {
+    r#"
+.history-list { display: flex; flex-direction: column; gap: 8px; padding-bottom: 72px; }
+.history-row { display: flex; align-items: center; gap: 10px; padding: 10px; border-radius: 8px; background: var(--surface); }
+.history-row.selected { outline: 2px solid var(--primary); }
+.history-action-bar {
+  position: fixed;
+  bottom: 0; left: 0; right: 0;
+  display: flex;
+  gap: 8px;
+  align-items: center;
+  padding: 12px 16px;
+  background: #fff;
+  box-shadow: 0 -2px 8px rgba(31, 41, 55, 0.15);
+}
+.history-action-bar .count { flex: 1; font-size: 0.9rem; }
+.history-cap-warning { color: var(--danger, #b91c1c); font-size: 0.85rem; }
+.undo-toast {
+  position: fixed;
+  bottom: 72px; left: 50%;
+  transform: translateX(-50%);
+  display: flex;
+  gap: 12px;
+  align-items: center;
+  padding: 10px 16px;
+  border-radius: 8px;
+  background: #1f2937;
+  color: #fff;
+}
+"#
+    .to_string()
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JobItem {
+    pub id: String,
+    pub crop_type: String,
+    pub status: String,
+    pub created_at: String,
+}
+
+/// Multi-select state. Indices refer to positions in the visible list;
+/// shift-selection extends from the last plainly-toggled row (the anchor),
+/// matching desktop file-manager conventions.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Selection {
+    pub active: bool,
+    pub selected: BTreeSet<usize>,
+    anchor: Option<usize>,
+    /// Set when a toggle would exceed [`MAX_SELECTED`]; the UI surfaces it
+    /// until the next successful change.
+    pub cap_hit: bool,
+}
+
+impl Selection {
+    pub fn enter(&mut self) {
+        self.active = true;
+    }
+
+    pub fn clear(&mut self) {
+        *self = Selection::default();
+    }
+
+    /// Space / tap: toggle one row, moving the anchor there.
+    pub fn toggle(&mut self, index: usize) {
+        self.active = true;
+        if self.selected.contains(&index) {
+            self.selected.remove(&index);
+            self.cap_hit = false;
+        } else if self.selected.len() < MAX_SELECTED {
+            self.selected.insert(index);
+            self.cap_hit = false;
+        } else {
+            self.cap_hit = true;
+            return;
+        }
+        self.anchor = Some(index);
+    }
+
+    /// Shift+space / shift+click: select the whole range from the anchor.
+    /// Rows beyond the cap stay unselected and the cap warning shows.
+    pub fn extend_to(&mut self, index: usize) {
+        let anchor = match self.anchor {
+            Some(a) => a,
+            None => return self.toggle(index),
+        };
+        self.active = true;
+        let (from, to) = if anchor <= index { (anchor, index) } else { (index, anchor) };
+        self.cap_hit = false;
+        for i in from..=to {
+            if self.selected.len() >= MAX_SELECTED && !self.selected.contains(&i) {
+                self.cap_hit = true;
+                break;
+            }
+            self.selected.insert(i);
+        }
+    }
+
+    pub fn ids<'a>(&self, items: &'a [JobItem]) -> Vec<String> {
+        self.selected
+            .iter()
+            .filter_map(|&i| items.get(i))
+            .map(|item| item.id.clone())
+            .collect()
+    }
+}
+
+/// Snapshot held while the undo toast is visible: the full pre-delete list
+/// so undo restores both the rows and their order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PendingDelete {
+    pub deleted_ids: Vec<String>,
+    pub prior_items: Vec<JobItem>,
+}
+
+/// Optimistically remove the selected rows, returning the snapshot the undo
+/// toast needs.
+pub fn apply_delete(items: &mut Vec<JobItem>, ids: &[String]) -> PendingDelete {
+    let snapshot = PendingDelete {
+        deleted_ids: ids.to_vec(),
+        prior_items: items.clone(),
+    };
+    items.retain(|item| !ids.contains(&item.id));
+    snapshot
+}
+
+/// Undo: put the prior list back.
+pub fn apply_undo(items: &mut Vec<JobItem>, pending: PendingDelete) -> Vec<String> {
+    *items = pending.prior_items;
+    pending.deleted_ids
+}
+
+/// CSV export of the selected rows; quotes fields that need it.
+pub fn to_csv(items: &[JobItem], ids: &[String]) -> String {
+    let mut out = String::from("id,crop_type,status,created_at\n");
+    for item in items.iter().filter(|i| ids.contains(&i.id)) {
+        out.push_str(&format!(
+            "{},{},{},{}\n",
+            item.id, item.crop_type, item.status, item.created_at
+        ));
+    }
+    out
+}
+
+#[derive(Serialize)]
+struct BulkJobsBody<'a> {
+    job_ids: &'a [String],
+}
+
+async fn post_bulk(path: &str, job_ids: &[String]) {
+    let result = Request::post(path)
+        .json(&BulkJobsBody { job_ids })
+        .expect("serialize bulk body")
+        .send()
+        .await;
+    if let Err(e) = result {
+        gloo_console::warn!("bulk action failed:", e.to_string());
+    }
+}
+
+#[derive(Properties, PartialEq)]
+pub struct HistoryListProps {
+    pub jobs: Vec<JobItem>,
+    /// Invoked with the CSV text when the user exports.
+    pub on_export: Callback<String>,
+}
+
+#[function_component(HistoryList)]
+pub fn history_list(props: &HistoryListProps) -> Html {
+    let items = use_state(|| props.jobs.clone());
+    let selection = use_state(Selection::default);
+    let pending_delete = use_state(|| Option::<PendingDelete>::None);
+
+    {
+        let items = items.clone();
+        use_effect_with(props.jobs.clone(), move |jobs| {
+            items.set(jobs.clone());
+        });
+    }
+
+    let on_row_key = {
+        let selection = selection.clone();
+        Callback::from(move |(e, index): (KeyboardEvent, usize)| {
+            if e.key() == " " {
+                e.prevent_default();
+                let mut next = (*selection).clone();
+                if e.shift_key() {
+                    next.extend_to(index);
+                } else {
+                    next.toggle(index);
+                }
+                selection.set(next);
+            }
+        })
+    };
+
+    let on_delete = {
+        let items = items.clone();
+        let selection = selection.clone();
+        let pending_delete = pending_delete.clone();
+        Callback::from(move |_| {
+            let ids = selection.ids(&items);
+            if ids.is_empty() {
+                return;
+            }
+            let mut next_items = (*items).clone();
+            let snapshot = apply_delete(&mut next_items, &ids);
+            items.set(next_items);
+            pending_delete.set(Some(snapshot));
+            selection.set(Selection::default());
+            wasm_bindgen_futures::spawn_local(async move {
+                post_bulk("/api/v1/vision/jobs/batch/delete", &ids).await;
+            });
+        })
+    };
+
+    let on_undo = {
+        let items = items.clone();
+        let pending_delete = pending_delete.clone();
+        Callback::from(move |_| {
+            if let Some(pending) = (*pending_delete).clone() {
+                let mut next_items = (*items).clone();
+                let ids = apply_undo(&mut next_items, pending);
+                items.set(next_items);
+                pending_delete.set(None);
+                wasm_bindgen_futures::spawn_local(async move {
+                    post_bulk("/api/v1/vision/jobs/batch/restore", &ids).await;
+                });
+            }
+        })
+    };
+
+    let on_export = {
+        let items = items.clone();
+        let selection = selection.clone();
+        let export = props.on_export.clone();
+        Callback::from(move |_| {
+            let ids = selection.ids(&items);
+            if !ids.is_empty() {
+                export.emit(to_csv(&items, &ids));
+            }
+        })
+    };
+
+    let toggle_mode = {
+        let selection = selection.clone();
+        Callback::from(move |_| {
+            let mut next = (*selection).clone();
+            if next.active {
+                next.clear();
+            } else {
+                next.enter();
+            }
+            selection.set(next);
+        })
+    };
+
+    html! {
+        <div class="history-list">
+            <button type="button" class="btn-secondary" onclick={toggle_mode}>
+                { if selection.active { "ยกเลิกการเลือก · Cancel selection" } else { "เลือกหลายรายการ · Select multiple" } }
+            </button>
+            { for items.iter().enumerate().map(|(index, job)| {
+                let selected = selection.selected.contains(&index);
+                let on_key = on_row_key.clone();
+                let selection = selection.clone();
+                html! {
+                    <div
+                        class={classes!("history-row", selected.then_some("selected"))}
+                        tabindex="0"
+                        role="checkbox"
+                        aria-checked={selected.to_string()}
+                        onkeydown={Callback::from(move |e: KeyboardEvent| on_key.emit((e, index)))}
+                        onclick={Callback::from(move |e: MouseEvent| {
+                            let mut next = (*selection).clone();
+                            if !next.active { return; }
+                            if e.shift_key() { next.extend_to(index); } else { next.toggle(index); }
+                            selection.set(next);
+                        })}
+                    >
+                        <span>{ &job.crop_type }</span>
+                        <span>{ &job.status }</span>
+                        <span>{ &job.created_at }</span>
+                    </div>
+                }
+            }) }
+            if selection.active && !selection.selected.is_empty() {
+                <div class="history-action-bar">
+                    <span class="count">
+                        { format!("เลือกแล้ว {} รายการ · {} selected", selection.selected.len(), selection.selected.len()) }
+                    </span>
+                    if selection.cap_hit {
+                        <span class="history-cap-warning">
+                            { format!("เลือกได้สูงสุด {MAX_SELECTED} รายการ · At most {MAX_SELECTED} items") }
+                        </span>
+                    }
+                    <button type="button" onclick={on_export}>{ "ส่งออก CSV · Export CSV" }</button>
+                    <button type="button" onclick={on_delete}>{ "ลบ · Delete" }</button>
+                </div>
+            }
+            if pending_delete.is_some() {
+                <div class="undo-toast" role="status">
+                    <span>{ "ลบรายการแล้ว · Items deleted" }</span>
+                    <button type="button" onclick={on_undo}>{ "เลิกทำ · Undo" }</button>
+                </div>
+            }
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn jobs(n: usize) -> Vec<JobItem> {
+        (0..n)
+            .map(|i| JobItem {
+                id: format!("job-{i}"),
+                crop_type: "rice".into(),
+                status: "completed".into(),
+                created_at: "2026-08-30T00:00:00Z".into(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn toggle_selects_and_deselects() {
+        let mut sel = Selection::default();
+        sel.toggle(3);
+        assert!(sel.active);
+        assert!(sel.selected.contains(&3));
+        sel.toggle(3);
+        assert!(sel.selected.is_empty());
+    }
+
+    #[test]
+    fn shift_extends_from_the_anchor_in_both_directions() {
+        let mut sel = Selection::default();
+        sel.toggle(5);
+        sel.extend_to(8);
+        assert_eq!(sel.selected.iter().copied().collect::<Vec<_>>(), vec![5, 6, 7, 8]);
+        sel.toggle(3);
+        sel.extend_to(1);
+        assert!(sel.selected.contains(&1) && sel.selected.contains(&2) && sel.selected.contains(&3));
+    }
+
+    #[test]
+    fn selection_is_capped_with_a_visible_warning() {
+        let mut sel = Selection::default();
+        for i in 0..MAX_SELECTED {
+            sel.toggle(i);
+        }
+        assert!(!sel.cap_hit);
+        sel.toggle(MAX_SELECTED);
+        assert!(sel.cap_hit);
+        assert_eq!(sel.selected.len(), MAX_SELECTED);
+        // Deselecting clears the warning.
+        sel.toggle(0);
+        assert!(!sel.cap_hit);
+    }
+
+    #[test]
+    fn delete_is_optimistic_and_undo_restores_order() {
+        let mut items = jobs(5);
+        let ids = vec!["job-1".to_string(), "job-3".to_string()];
+        let pending = apply_delete(&mut items, &ids);
+        assert_eq!(items.len(), 3);
+        assert!(items.iter().all(|i| !ids.contains(&i.id)));
+
+        let restored_ids = apply_undo(&mut items, pending);
+        assert_eq!(restored_ids, ids);
+        assert_eq!(items, jobs(5));
+    }
+
+    #[test]
+    fn csv_export_covers_only_selected_rows() {
+        let items = jobs(3);
+        let csv = to_csv(&items, &["job-0".to_string(), "job-2".to_string()]);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "id,crop_type,status,created_at");
+        assert_eq!(lines.len(), 3);
+        assert!(lines[1].starts_with("job-0,"));
+        assert!(lines[2].starts_with("job-2,"));
+    }
+}
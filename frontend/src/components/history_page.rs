@@ -0,0 +1,504 @@
+//! Diagnosis history page: a filterable, infinitely scrolling feed.
+//!
+//! Pages come from `GET /api/v1/diagnoses/search` in browse mode (empty
+//! `q`), newest first. The paging and filter rules live in plain structs
+//! ([`HistoryQuery`], [`HistoryFeed`]) so they are testable without a DOM;
+//! the component binds them to an `IntersectionObserver` on a sentinel
+//! element after the last card, which is what turns "scrolled to the
+//! bottom" into a next-page fetch.
+
+use shared::models::CropType;
+use yew::prelude::*;
+
+use crate::services::api::{ApiService, DiagnosisSummary, HistoryPage};
+use crate::simple_app::{AppAction, AppContext};
+
+pub fn generate_history_page_css() -> String {
+    r#"
+.history-page { display: flex; flex-direction: column; gap: 12px; }
+.history-filters { display: flex; flex-wrap: wrap; gap: 8px; align-items: center; }
+.history-filters select, .history-filters input {
+  padding: 6px 8px;
+  border: 1px solid var(--border, #d1d5db);
+  border-radius: 8px;
+  font-size: 0.9rem;
+}
+.history-feed { display: flex; flex-direction: column; gap: 8px; }
+.history-card { padding: 10px 12px; border-radius: 8px; background: var(--surface); }
+.history-card .history-card-head { display: flex; gap: 8px; align-items: baseline; }
+.history-card .history-card-disease { font-weight: 600; }
+.history-card .history-card-crop { font-size: 0.85rem; color: var(--muted, #6b7280); }
+.history-card .history-card-date { margin-left: auto; font-size: 0.8rem; color: var(--muted, #6b7280); }
+.history-card .history-card-advice {
+  margin: 4px 0 0;
+  font-size: 0.9rem;
+  display: -webkit-box;
+  -webkit-line-clamp: 2;
+  -webkit-box-orient: vertical;
+  overflow: hidden;
+}
+.history-feed-status { text-align: center; font-size: 0.9rem; color: var(--muted, #6b7280); padding: 8px; }
+"#
+    .to_string()
+}
+
+/// The feed's filter controls, mapped onto the search endpoint's query
+/// parameters. Dates come straight from the `<input type="date">` values
+/// (`YYYY-MM-DD`) and are widened to whole-day UTC bounds.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HistoryQuery {
+    pub crop_type: Option<CropType>,
+    pub from: String,
+    pub to: String,
+}
+
+impl HistoryQuery {
+    /// Query string for one page. `q=` (empty) selects the gateway's
+    /// browse mode: every diagnosis, newest first.
+    pub fn query_string(&self, page: u32) -> String {
+        let mut query = format!("q=&page={page}&limit=20");
+        if let Some(crop) = self.crop_type {
+            query.push_str("&crop_type=");
+            query.push_str(crop.as_str());
+        }
+        if !self.from.is_empty() {
+            query.push_str("&from=");
+            query.push_str(&self.from);
+            query.push_str("T00:00:00Z");
+        }
+        if !self.to.is_empty() {
+            query.push_str("&to=");
+            query.push_str(&self.to);
+            query.push_str("T23:59:59Z");
+        }
+        query
+    }
+}
+
+/// Accumulated feed state: every card fetched so far plus the paging
+/// cursor. `start_loading` is the single gate for fetches, so the
+/// sentinel can fire as often as the browser likes without duplicate or
+/// overlapping requests.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistoryFeed {
+    pub items: Vec<DiagnosisSummary>,
+    /// 1-based page the next fetch should ask for.
+    pub next_page: u32,
+    pub has_more: bool,
+    pub loading: bool,
+}
+
+impl Default for HistoryFeed {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HistoryFeed {
+    pub fn new() -> Self {
+        Self { items: Vec::new(), next_page: 1, has_more: true, loading: false }
+    }
+
+    /// Claim the next fetch; `false` means one is already in flight or
+    /// the gateway has no more pages.
+    pub fn start_loading(&mut self) -> bool {
+        if self.loading || !self.has_more {
+            return false;
+        }
+        self.loading = true;
+        true
+    }
+
+    /// Fold a fetched page into the feed and advance the cursor.
+    pub fn apply_page(&mut self, page: HistoryPage) {
+        self.items.extend(page.items);
+        self.next_page += 1;
+        self.has_more = page.has_more;
+        self.loading = false;
+    }
+
+    /// A failed fetch releases the loading gate without advancing the
+    /// cursor, so the same page can be retried.
+    pub fn fail(&mut self) {
+        self.loading = false;
+    }
+}
+
+/// One condensed card in the feed.
+fn summary_card(item: &DiagnosisSummary) -> Html {
+    // Dates arrive as RFC 3339; the date part alone is enough for a feed.
+    let date = item.created_at.split('T').next().unwrap_or_default().to_string();
+    html! {
+        <article class="history-card" key={item.id.clone()}>
+            <div class="history-card-head">
+                <span class="history-card-disease">{ item.disease_name.clone() }</span>
+                <span class="history-card-crop">{ item.crop_type.clone() }</span>
+                <span class="history-card-date">{ date }</span>
+            </div>
+            <p class="history-card-advice">{ item.advice.clone() }</p>
+        </article>
+    }
+}
+
+/// Render the feed region: cards, then a status line for the loading /
+/// exhausted / empty states. The scenario tests mount this directly.
+pub fn feed_view(feed: &HistoryFeed) -> Html {
+    html! {
+        <div class="history-feed">
+            { for feed.items.iter().map(summary_card) }
+            if feed.loading {
+                <p class="history-feed-status">{ "กำลังโหลด..." }</p>
+            } else if feed.items.is_empty() {
+                <p class="history-feed-status">{ "ยังไม่มีประวัติการวินิจฉัย" }</p>
+            } else if !feed.has_more {
+                <p class="history-feed-status">{ "สุดรายการแล้ว" }</p>
+            }
+        </div>
+    }
+}
+
+#[function_component(DiagnosisHistoryPage)]
+pub fn diagnosis_history_page() -> Html {
+    let app = use_context::<AppContext>()
+        .expect("DiagnosisHistoryPage must be inside the app context");
+    let query = use_state(HistoryQuery::default);
+    let feed = use_state(HistoryFeed::new);
+    let sentinel = use_node_ref();
+
+    // Fetch the feed's next page. Safe to fire eagerly: the feed guards
+    // against overlapping loads and exhausted pagination itself.
+    let load_more = {
+        let app = app.clone();
+        let query = query.clone();
+        let feed = feed.clone();
+        Callback::from(move |_: ()| {
+            let mut next = (*feed).clone();
+            if !next.start_loading() {
+                return;
+            }
+            feed.set(next.clone());
+
+            let app = app.clone();
+            let query = (*query).clone();
+            let feed = feed.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                let api = ApiService::new();
+                match api.diagnosis_history(&query.query_string(next.next_page)).await {
+                    Ok(page) => {
+                        next.apply_page(page);
+                        feed.set(next);
+                    }
+                    Err(e) => {
+                        next.fail();
+                        feed.set(next);
+                        app.dispatch(AppAction::SetError(Some(e.to_string())));
+                    }
+                }
+            });
+        })
+    };
+
+    // Changing any filter restarts the feed from page 1.
+    let refilter = {
+        let query = query.clone();
+        let feed = feed.clone();
+        let load_more = load_more.clone();
+        Callback::from(move |next: HistoryQuery| {
+            query.set(next);
+            feed.set(HistoryFeed::new());
+            load_more.emit(());
+        })
+    };
+
+    let on_crop = {
+        let query = query.clone();
+        let refilter = refilter.clone();
+        Callback::from(move |e: Event| {
+            let value = e.target_unchecked_into::<web_sys::HtmlSelectElement>().value();
+            let mut next = (*query).clone();
+            next.crop_type = CropType::ALL.iter().copied().find(|c| c.as_str() == value);
+            refilter.emit(next);
+        })
+    };
+    let on_from = {
+        let query = query.clone();
+        let refilter = refilter.clone();
+        Callback::from(move |e: Event| {
+            let mut next = (*query).clone();
+            next.from = e.target_unchecked_into::<web_sys::HtmlInputElement>().value();
+            refilter.emit(next);
+        })
+    };
+    let on_to = {
+        let query = query.clone();
+        let refilter = refilter.clone();
+        Callback::from(move |e: Event| {
+            let mut next = (*query).clone();
+            next.to = e.target_unchecked_into::<web_sys::HtmlInputElement>().value();
+            refilter.emit(next);
+        })
+    };
+
+    // First page on mount, then further pages whenever the sentinel below
+    // the last card scrolls into view.
+    {
+        let load_more = load_more.clone();
+        let sentinel = sentinel.clone();
+        use_effect_with((), move |_| {
+            use wasm_bindgen::{closure::Closure, JsCast};
+
+            load_more.emit(());
+
+            let on_intersect = Closure::<dyn FnMut(js_sys::Array)>::new(move |entries: js_sys::Array| {
+                let visible = entries.iter().any(|entry| {
+                    entry
+                        .dyn_into::<web_sys::IntersectionObserverEntry>()
+                        .map(|e| e.is_intersecting())
+                        .unwrap_or(false)
+                });
+                if visible {
+                    load_more.emit(());
+                }
+            });
+            let observer =
+                web_sys::IntersectionObserver::new(on_intersect.as_ref().unchecked_ref())
+                    .expect("IntersectionObserver unavailable");
+            if let Some(element) = sentinel.cast::<web_sys::Element>() {
+                observer.observe(&element);
+            }
+            move || {
+                observer.disconnect();
+                drop(on_intersect);
+            }
+        });
+    }
+
+    html! {
+        <div class="history-page">
+            <div class="history-filters">
+                <select onchange={on_crop} aria-label="crop filter">
+                    <option value="" selected={query.crop_type.is_none()}>
+                        { "ทุกพืช · All crops" }
+                    </option>
+                    { for CropType::ALL.iter().map(|crop| html! {
+                        <option
+                            value={crop.as_str()}
+                            selected={query.crop_type == Some(*crop)}
+                        >
+                            { crop.as_str() }
+                        </option>
+                    }) }
+                </select>
+                <input type="date" value={query.from.clone()} onchange={on_from} aria-label="from date" />
+                <input type="date" value={query.to.clone()} onchange={on_to} aria-label="to date" />
+            </div>
+            { feed_view(&feed) }
+            <div ref={sentinel} class="history-sentinel"></div>
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn query_string_carries_only_the_set_filters() {
+        let query = HistoryQuery::default();
+        assert_eq!(query.query_string(1), "q=&page=1&limit=20");
+
+        let query = HistoryQuery {
+            crop_type: Some(CropType::Rice),
+            from: "2026-08-01".into(),
+            to: "2026-08-31".into(),
+        };
+        assert_eq!(
+            query.query_string(3),
+            "q=&page=3&limit=20&crop_type=rice\
+             &from=2026-08-01T00:00:00Z&to=2026-08-31T23:59:59Z"
+        );
+    }
+
+    #[test]
+    fn feed_pages_accumulate_until_the_gateway_runs_out() {
+        let item = |id: &str| DiagnosisSummary {
+            id: id.into(),
+            job_id: "j".into(),
+            crop_type: "rice".into(),
+            disease_name: "blast".into(),
+            advice: "drain".into(),
+            severity_score: None,
+            created_at: "2026-08-30T10:00:00Z".into(),
+        };
+
+        let mut feed = HistoryFeed::new();
+        assert!(feed.start_loading());
+        // A second load while one is in flight is refused.
+        assert!(!feed.start_loading());
+        feed.apply_page(HistoryPage {
+            items: vec![item("a"), item("b")],
+            has_more: true,
+        });
+        assert_eq!(feed.items.len(), 2);
+        assert_eq!(feed.next_page, 2);
+
+        assert!(feed.start_loading());
+        feed.apply_page(HistoryPage {
+            items: vec![item("c")],
+            has_more: false,
+        });
+        assert_eq!(feed.items.len(), 3);
+        assert!(!feed.has_more);
+        // Exhausted: the sentinel can fire all it wants.
+        assert!(!feed.start_loading());
+    }
+
+    #[test]
+    fn failed_load_clears_the_loading_flag_but_keeps_the_page_cursor() {
+        let mut feed = HistoryFeed::new();
+        assert!(feed.start_loading());
+        feed.fail();
+        assert!(!feed.loading);
+        assert_eq!(feed.next_page, 1);
+        // The same page can be retried.
+        assert!(feed.start_loading());
+    }
+}
+
+#[cfg(test)]
+#[cfg(target_arch = "wasm32")]
+mod wasm_tests {
+    use std::{cell::RefCell, collections::VecDeque, rc::Rc};
+
+    use serde_json::Value;
+    use wasm_bindgen_test::*;
+    use yew::prelude::*;
+
+    use super::*;
+    use crate::services::api::{
+        ApiError, ApiRequest, ApiResponse, ApiTransport, TransportError, TransportFuture,
+    };
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    /// Scripted gateway, as in the `services::api` scenario tests.
+    #[derive(Default)]
+    struct MockTransport {
+        script: RefCell<VecDeque<Result<ApiResponse, TransportError>>>,
+        seen: RefCell<Vec<String>>,
+    }
+
+    impl MockTransport {
+        fn push_data(&self, data: Value) {
+            self.script.borrow_mut().push_back(Ok(ApiResponse {
+                status: 200,
+                body: serde_json::json!({ "success": true, "data": data }),
+            }));
+        }
+    }
+
+    impl ApiTransport for MockTransport {
+        fn send(&self, request: ApiRequest) -> TransportFuture<'_> {
+            self.seen.borrow_mut().push(request.path);
+            let next = self
+                .script
+                .borrow_mut()
+                .pop_front()
+                .expect("scenario script exhausted");
+            Box::pin(async move { next })
+        }
+    }
+
+    fn row(id: &str) -> Value {
+        serde_json::json!({
+            "id": id,
+            "job_id": "job-1",
+            "crop_type": "rice",
+            "disease_name": "โรคไหม้ข้าว",
+            "advice": "ระบายน้ำออกจากแปลง",
+            "severity_score": 0.6,
+            "created_at": "2026-08-30T10:00:00Z",
+        })
+    }
+
+    /// Mount the feed view and count the rendered cards.
+    fn rendered_card_count(feed: &HistoryFeed) -> u32 {
+        #[derive(Properties, PartialEq)]
+        struct Props {
+            feed: HistoryFeed,
+        }
+        #[function_component(Harness)]
+        fn harness(props: &Props) -> Html {
+            feed_view(&props.feed)
+        }
+
+        let document = web_sys::window().unwrap().document().unwrap();
+        let root = document.create_element("div").unwrap();
+        document.body().unwrap().append_child(&root).unwrap();
+        let handle = yew::Renderer::<Harness>::with_root_and_props(
+            root.clone(),
+            Props { feed: feed.clone() },
+        )
+        .render();
+        let count = root.query_selector_all(".history-card").unwrap().length();
+        handle.destroy();
+        root.remove();
+        count
+    }
+
+    #[wasm_bindgen_test]
+    async fn two_pages_render_as_one_accumulated_card_list() {
+        let mock = Rc::new(MockTransport::default());
+        mock.push_data(serde_json::json!({
+            "items": [row("a"), row("b")],
+            "total": 3, "page": 1, "limit": 2, "total_pages": 2,
+        }));
+        mock.push_data(serde_json::json!({
+            "items": [row("c")],
+            "total": 3, "page": 2, "limit": 2, "total_pages": 2,
+        }));
+        let api = ApiService::with_transport(mock.clone());
+        let query = HistoryQuery::default();
+
+        let mut feed = HistoryFeed::new();
+        assert!(feed.start_loading());
+        let page = api.diagnosis_history(&query.query_string(feed.next_page)).await.unwrap();
+        feed.apply_page(page);
+        assert_eq!(rendered_card_count(&feed), 2);
+        assert!(feed.has_more);
+
+        assert!(feed.start_loading());
+        let page = api.diagnosis_history(&query.query_string(feed.next_page)).await.unwrap();
+        feed.apply_page(page);
+        assert_eq!(rendered_card_count(&feed), 3);
+        assert!(!feed.has_more);
+
+        let seen = mock.seen.borrow();
+        assert_eq!(seen[0], "/api/v1/diagnoses/search?q=&page=1&limit=20");
+        assert_eq!(seen[1], "/api/v1/diagnoses/search?q=&page=2&limit=20");
+    }
+
+    #[wasm_bindgen_test]
+    async fn failed_page_surfaces_the_error_and_keeps_earlier_cards() {
+        let mock = Rc::new(MockTransport::default());
+        mock.push_data(serde_json::json!({
+            "items": [row("a")],
+            "total": 2, "page": 1, "limit": 1, "total_pages": 2,
+        }));
+        mock.push_data(serde_json::json!({ "wrong": "shape" }));
+        let api = ApiService::with_transport(mock);
+        let query = HistoryQuery::default();
+
+        let mut feed = HistoryFeed::new();
+        feed.start_loading();
+        feed.apply_page(api.diagnosis_history(&query.query_string(1)).await.unwrap());
+
+        feed.start_loading();
+        let error = api.diagnosis_history(&query.query_string(feed.next_page)).await;
+        assert!(matches!(error, Err(ApiError::Decode(_))));
+        feed.fail();
+
+        assert_eq!(rendered_card_count(&feed), 1);
+        assert_eq!(feed.next_page, 2);
+    }
+}
@@ -0,0 +1,237 @@
+//! Drag-and-drop image intake.
+//!
+//! Tablet users drag screenshots straight from their gallery instead of
+//! going through the camera, so the drop zone accepts them with the same
+//! validation the rest of the pipeline assumes: gateway-supported image
+//! types only, capped at the gateway's upload limit. Only the first
+//! dropped file is used; extras are reported rather than silently
+//! dropped.
+
+use yew::prelude::*;
+
+/// Mirrors the gateway's `file_storage.max_file_size` default, so the
+/// rejection happens before the upload round-trips.
+pub const MAX_IMAGE_BYTES: u64 = 10 * 1024 * 1024;
+
+/// The image types the vision pipeline accepts.
+const ACCEPTED_TYPES: [&str; 3] = ["image/jpeg", "image/png", "image/webp"];
+
+pub fn generate_drop_zone_css() -> String {
+    r#"
+.drop-zone {
+  border: 2px dashed rgba(31, 41, 55, 0.25);
+  border-radius: var(--radius-card);
+  padding: 24px;
+  text-align: center;
+  transition: border-color 0.15s, background 0.15s;
+}
+.drop-zone.active {
+  border-color: var(--accent-lime-green);
+  background: rgba(132, 204, 22, 0.08);
+}
+.drop-zone-error { color: var(--danger-red); font-size: 0.85rem; margin-top: 8px; }
+"#
+    .to_string()
+}
+
+/// The same checks the upload path relies on, front-loaded: accepted image
+/// type and size cap. The message names the file so multi-file warnings
+/// stay readable.
+pub fn validate_image_file(name: &str, mime: &str, size_bytes: u64) -> Result<(), String> {
+    if !ACCEPTED_TYPES.contains(&mime) {
+        return Err(format!("{name} ไม่ใช่ไฟล์รูปภาพที่รองรับ · not a supported image file"));
+    }
+    if size_bytes > MAX_IMAGE_BYTES {
+        return Err(format!("{name} ใหญ่เกิน 10 MB · exceeds the 10 MB limit"));
+    }
+    Ok(())
+}
+
+/// Decide what a drop does: which file (by index) to hand to the upload
+/// path, and what to put in the validation banner. Only the first file is
+/// used; a valid first file with company produces a warning, an invalid
+/// first file produces its validation error and no selection.
+pub fn triage_drop(files: &[(String, String, u64)]) -> (Option<usize>, Option<String>) {
+    let Some((name, mime, size)) = files.first() else {
+        return (None, None);
+    };
+    match validate_image_file(name, mime, *size) {
+        Err(message) => (None, Some(message)),
+        Ok(()) if files.len() > 1 => {
+            let skipped = files.len() - 1;
+            (
+                Some(0),
+                Some(format!(
+                    "ใช้รูปแรกเท่านั้น ข้ามอีก {skipped} ไฟล์ · only the first image is used, {skipped} more skipped"
+                )),
+            )
+        }
+        Ok(()) => (Some(0), None),
+    }
+}
+
+#[derive(Properties, PartialEq)]
+pub struct DropZoneProps {
+    /// Fired with the accepted file; the parent owns reading and encoding
+    /// it, same as it would for a file input.
+    pub on_file_select: Callback<web_sys::File>,
+    #[prop_or_default]
+    pub children: Children,
+}
+
+#[function_component(DropZone)]
+pub fn drop_zone(props: &DropZoneProps) -> Html {
+    let active = use_state(|| false);
+    let validation_error = use_state(|| Option::<String>::None);
+
+    // Without cancelling dragover the browser never delivers the drop —
+    // it navigates to the dragged file instead.
+    let ondragover = Callback::from(|e: DragEvent| e.prevent_default());
+    let ondragenter = {
+        let active = active.clone();
+        Callback::from(move |e: DragEvent| {
+            e.prevent_default();
+            active.set(true);
+        })
+    };
+    let ondragleave = {
+        let active = active.clone();
+        Callback::from(move |_: DragEvent| active.set(false))
+    };
+    let ondrop = {
+        let active = active.clone();
+        let validation_error = validation_error.clone();
+        let on_file_select = props.on_file_select.clone();
+        Callback::from(move |e: DragEvent| {
+            e.prevent_default();
+            active.set(false);
+            let Some(files) = e.data_transfer().and_then(|dt| dt.files()) else {
+                return;
+            };
+            let dropped: Vec<web_sys::File> = (0..files.length())
+                .filter_map(|i| files.item(i))
+                .collect();
+            let described: Vec<(String, String, u64)> = dropped
+                .iter()
+                .map(|f| (f.name(), f.type_(), f.size() as u64))
+                .collect();
+            let (selected, message) = triage_drop(&described);
+            validation_error.set(message);
+            if let Some(index) = selected {
+                on_file_select.emit(dropped[index].clone());
+            }
+        })
+    };
+
+    html! {
+        <div
+            class={classes!("drop-zone", active.then_some("active"))}
+            {ondragover}
+            {ondragenter}
+            {ondragleave}
+            {ondrop}
+        >
+            { props.children.clone() }
+            <p>{ "ลากรูปมาวางที่นี่ · drag an image here" }</p>
+            if let Some(message) = validation_error.as_ref() {
+                <p class="drop-zone-error" role="alert">{ message }</p>
+            }
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn jpeg(name: &str, size: u64) -> (String, String, u64) {
+        (name.to_string(), "image/jpeg".to_string(), size)
+    }
+
+    #[test]
+    fn accepted_types_within_the_cap_validate() {
+        assert!(validate_image_file("leaf.jpg", "image/jpeg", 1024).is_ok());
+        assert!(validate_image_file("leaf.png", "image/png", MAX_IMAGE_BYTES).is_ok());
+        assert!(validate_image_file("leaf.webp", "image/webp", 0).is_ok());
+    }
+
+    #[test]
+    fn non_images_and_oversized_files_are_named_in_the_error() {
+        let err = validate_image_file("notes.pdf", "application/pdf", 10).unwrap_err();
+        assert!(err.contains("notes.pdf"), "{err}");
+        let err = validate_image_file("huge.jpg", "image/jpeg", MAX_IMAGE_BYTES + 1).unwrap_err();
+        assert!(err.contains("huge.jpg"), "{err}");
+        assert!(err.contains("10 MB"), "{err}");
+    }
+
+    #[test]
+    fn an_oversized_first_file_sets_the_error_and_selects_nothing() {
+        let (selected, message) = triage_drop(&[jpeg("huge.jpg", MAX_IMAGE_BYTES + 1)]);
+        assert_eq!(selected, None);
+        let message = message.expect("oversized drop must explain itself");
+        assert!(message.contains("10 MB"), "{message}");
+    }
+
+    #[test]
+    fn a_multi_file_drop_uses_the_first_and_warns_about_the_rest() {
+        let (selected, message) =
+            triage_drop(&[jpeg("a.jpg", 100), jpeg("b.jpg", 100), jpeg("c.jpg", 100)]);
+        assert_eq!(selected, Some(0));
+        assert!(message.expect("extras warrant a warning").contains('2'));
+    }
+
+    #[test]
+    fn an_empty_or_clean_single_drop_stays_silent() {
+        assert_eq!(triage_drop(&[]), (None, None));
+        assert_eq!(triage_drop(&[jpeg("a.jpg", 100)]), (Some(0), None));
+    }
+}
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod wasm_tests {
+    use super::*;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    async fn drag_enter_highlights_and_drag_leave_reverts() {
+        let document = gloo_utils::document();
+        let host = document.create_element("div").unwrap();
+        document.body().unwrap().append_child(&host).unwrap();
+
+        let handle = yew::Renderer::<DropZone>::with_root_and_props(
+            host.clone(),
+            DropZoneProps {
+                on_file_select: Callback::noop(),
+                children: Children::default(),
+            },
+        )
+        .render();
+        gloo_timers::future::TimeoutFuture::new(50).await;
+
+        // Yew delegates listeners at the mount root, so synthetic events
+        // must bubble to be seen at all.
+        let drag_event = |kind: &str| {
+            let init = web_sys::DragEventInit::new();
+            init.set_bubbles(true);
+            web_sys::DragEvent::new_with_event_init_dict(kind, &init).unwrap()
+        };
+
+        let zone = host.query_selector(".drop-zone").unwrap().unwrap();
+        assert!(!zone.class_list().contains("active"));
+
+        zone.dispatch_event(&drag_event("dragenter")).unwrap();
+        gloo_timers::future::TimeoutFuture::new(50).await;
+        let zone = host.query_selector(".drop-zone").unwrap().unwrap();
+        assert!(zone.class_list().contains("active"), "dragenter must highlight");
+
+        zone.dispatch_event(&drag_event("dragleave")).unwrap();
+        gloo_timers::future::TimeoutFuture::new(50).await;
+        let zone = host.query_selector(".drop-zone").unwrap().unwrap();
+        assert!(!zone.class_list().contains("active"), "dragleave must revert");
+
+        handle.destroy();
+        host.remove();
+    }
+}
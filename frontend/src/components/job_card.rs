@@ -0,0 +1,364 @@
+//! Inline analysis card for the chat timeline.
+//!
+//! One card is inserted when a job is queued and then mutates in place: a
+//! stepper while the job runs, a compact diagnosis card on completion, and
+//! a retry affordance on failure. Updates arrive over the job status
+//! WebSocket (`/api/v1/vision/jobs/:id/ws`) keyed by job id, so status
+//! changes never append new timeline entries.
+
+use gloo_storage::{LocalStorage, Storage};
+use shared::confidence::{guidance, ConfidenceBand};
+use shared::models::{DiagnosisStatus, Language, VisionResponse};
+use yew::prelude::*;
+
+use crate::simple_app::{AppAction, AppContext};
+
+/// LocalStorage key holding the bearer token after login (shared with the
+/// chat window).
+const AUTH_TOKEN_KEY: &str = "auth_token";
+
+/// Live state of one analysis card, keyed by job id in the app state. A
+/// completed status may arrive without the full result (the poll endpoint
+/// reports status only); the card then shows a generic done state.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CardStatus {
+    Queued,
+    Processing,
+    /// Detections arrived; treatment advice is still being generated.
+    Diagnosed(Option<VisionResponse>),
+    Completed(Option<VisionResponse>),
+    Failed(String),
+}
+
+impl CardStatus {
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, CardStatus::Completed(_) | CardStatus::Failed(_))
+    }
+}
+
+/// Map one status payload onto a card status. Accepts both the WS frames
+/// published by the worker (`{"status": ..., "result": ...}`) and the poll
+/// endpoint's envelope (`{"data": {"status": ...}}`). `None` means the
+/// payload carried no recognisable status.
+pub fn status_from_payload(payload: &str) -> Option<CardStatus> {
+    let value: serde_json::Value = serde_json::from_str(payload).ok()?;
+    let data = value.get("data").unwrap_or(&value);
+    match data.get("status")?.as_str()? {
+        "queued" => Some(CardStatus::Queued),
+        "processing" => Some(CardStatus::Processing),
+        "diagnosed" => Some(CardStatus::Diagnosed(
+            data.get("result")
+                .and_then(|result| serde_json::from_value(result.clone()).ok()),
+        )),
+        "completed" => Some(CardStatus::Completed(
+            data.get("result")
+                .and_then(|result| serde_json::from_value(result.clone()).ok()),
+        )),
+        "failed" => Some(CardStatus::Failed(
+            data.get("error")
+                .and_then(|error| error.as_str())
+                .unwrap_or("การวิเคราะห์ล้มเหลว · analysis failed")
+                .to_string(),
+        )),
+        _ => None,
+    }
+}
+
+pub fn generate_job_card_css() -> String {
+    r#"
+.job-card {
+  align-self: stretch;
+  border: 1px solid #d1d5db;
+  border-radius: 12px;
+  padding: 12px;
+  background: var(--surface);
+}
+.job-card-stepper { display: flex; align-items: center; gap: 8px; font-size: 0.85rem; }
+.job-card-step { opacity: 0.45; }
+.job-card-step.active { opacity: 1; font-weight: 600; color: var(--leaf-green); }
+.job-card-step.done { opacity: 1; }
+.job-card-result h3 { margin: 0 0 4px; font-size: 1rem; }
+.job-card-detection { display: flex; justify-content: space-between; font-size: 0.9rem; }
+.job-card-failed { color: var(--danger-red); font-size: 0.9rem; }
+.job-card-uncertain { border-left: 3px solid #f59e0b; padding-left: 8px; }
+.job-card-uncertain .job-card-guidance { font-size: 0.85rem; opacity: 0.8; }
+.job-card-retry { margin-top: 8px; }
+"#
+    .to_string()
+}
+
+/// Subscribe to the job's status socket, dispatching card updates until a
+/// terminal status arrives or the socket closes.
+fn subscribe(app: AppContext, job_id: String) {
+    wasm_bindgen_futures::spawn_local(async move {
+        use futures_util::StreamExt;
+        use gloo_net::websocket::{futures::WebSocket, Message};
+
+        let token: String = LocalStorage::get(AUTH_TOKEN_KEY).unwrap_or_default();
+        let origin = web_sys::window()
+            .and_then(|w| w.location().origin().ok())
+            .unwrap_or_default();
+        let base = if let Some(rest) = origin.strip_prefix("https://") {
+            format!("wss://{rest}")
+        } else if let Some(rest) = origin.strip_prefix("http://") {
+            format!("ws://{rest}")
+        } else {
+            origin
+        };
+        let url = format!("{base}/api/v1/vision/jobs/{job_id}/ws?token={token}");
+        let Ok(mut socket) = WebSocket::open(&url) else {
+            app.dispatch(AppAction::UpdateJobCard {
+                job_id,
+                status: CardStatus::Failed("เชื่อมต่อไม่ได้ · could not connect".into()),
+            });
+            return;
+        };
+        while let Some(Ok(Message::Text(text))) = socket.next().await {
+            let Some(status) = status_from_payload(&text) else { continue };
+            let terminal = status.is_terminal();
+            app.dispatch(AppAction::UpdateJobCard { job_id: job_id.clone(), status });
+            if terminal {
+                break;
+            }
+        }
+    });
+}
+
+/// Render the diagnosis block shared by the `diagnosed` and `completed`
+/// states; `fallback` is shown when the payload carried no result.
+/// Render an uncertain result: candidate diseases (best first, at most
+/// three) plus the shared low-confidence retake guidance — deliberately not
+/// styled like a diagnosis, since that is the complaint it fixes.
+fn uncertain_view(result: &VisionResponse) -> Html {
+    let mut candidates: Vec<_> = result.detections.iter().collect();
+    candidates.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+    candidates.truncate(3);
+    html! {
+        <div class="job-card-result job-card-uncertain">
+            <h3>{ "ยังไม่แน่ใจ · Uncertain" }</h3>
+            { for candidates.into_iter().map(|d| html! {
+                <div class="job-card-detection">
+                    <span>{ format!("{}?", d.disease_name) }</span>
+                    <span>{ format!("{:.0}%", d.confidence * 100.0) }</span>
+                </div>
+            }) }
+            <p class="job-card-guidance">{ guidance(ConfidenceBand::Low, Language::Thai) }</p>
+            <p class="job-card-guidance">{ guidance(ConfidenceBand::Low, Language::English) }</p>
+        </div>
+    }
+}
+
+fn diagnosis_view(result: &Option<VisionResponse>, fallback: &str) -> Html {
+    if let Some(result) = result {
+        if result.diagnosis_status == DiagnosisStatus::Uncertain {
+            return uncertain_view(result);
+        }
+    }
+    html! {
+        <div class="job-card-result">
+            <h3>{ "ผลการวินิจฉัย · Diagnosis" }</h3>
+            {
+                match result {
+                    Some(result) if !result.detections.is_empty() => html! {
+                        <>
+                            { for result.detections.iter().map(|d| html! {
+                                <div class="job-card-detection">
+                                    <span>{ &d.disease_name }</span>
+                                    <span>{ format!("{:.0}%", d.confidence * 100.0) }</span>
+                                </div>
+                            }) }
+                        </>
+                    },
+                    Some(_) => html! {
+                        <p>{ "ไม่พบโรค · No disease detected" }</p>
+                    },
+                    None => html! {
+                        <p>{ fallback.to_string() }</p>
+                    },
+                }
+            }
+        </div>
+    }
+}
+
+#[derive(Properties, PartialEq)]
+pub struct JobCardProps {
+    pub job_id: String,
+}
+
+#[function_component(JobCard)]
+pub fn job_card(props: &JobCardProps) -> Html {
+    let app = use_context::<AppContext>().expect("JobCard must be inside the app context");
+    let status = app
+        .job_cards
+        .get(&props.job_id)
+        .cloned()
+        .unwrap_or(CardStatus::Queued);
+
+    {
+        let app = app.clone();
+        use_effect_with(props.job_id.clone(), move |job_id| {
+            subscribe(app, job_id.clone());
+        });
+    }
+
+    let retry = {
+        let app = app.clone();
+        let job_id = props.job_id.clone();
+        Callback::from(move |_| {
+            app.dispatch(AppAction::UpdateJobCard {
+                job_id: job_id.clone(),
+                status: CardStatus::Queued,
+            });
+            subscribe(app.clone(), job_id.clone());
+        })
+    };
+
+    let stepper = |active: usize| {
+        let steps = [
+            "รอคิว · Queued",
+            "กำลังวิเคราะห์ · Analyzing",
+            "สร้างคำแนะนำ · Advising",
+            "เสร็จสิ้น · Done",
+        ];
+        html! {
+            <div class="job-card-stepper">
+                { for steps.iter().enumerate().map(|(i, label)| {
+                    let class = if i < active {
+                        "job-card-step done"
+                    } else if i == active {
+                        "job-card-step active"
+                    } else {
+                        "job-card-step"
+                    };
+                    html! { <span class={class}>{ label }</span> }
+                }) }
+            </div>
+        }
+    };
+
+    html! {
+        <div class="job-card" data-job-id={props.job_id.clone()}>
+            {
+                match &status {
+                    CardStatus::Queued => stepper(0),
+                    CardStatus::Processing => stepper(1),
+                    // Show the diagnosis as soon as the vision stage lands;
+                    // the stepper keeps running while advice is generated.
+                    CardStatus::Diagnosed(result) => html! {
+                        <>
+                            { stepper(2) }
+                            { diagnosis_view(result, "กำลังสร้างคำแนะนำ · Generating advice") }
+                        </>
+                    },
+                    CardStatus::Completed(result) => html! {
+                        { diagnosis_view(result, "วิเคราะห์เสร็จแล้ว · Analysis complete") }
+                    },
+                    CardStatus::Failed(reason) => html! {
+                        <>
+                            <div class="job-card-failed">
+                                { format!("ล้มเหลว · Failed: {reason}") }
+                            </div>
+                            <button class="btn-primary job-card-retry" onclick={retry.clone()}>
+                                { "ลองใหม่ · Retry" }
+                            </button>
+                        </>
+                    },
+                }
+            }
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn worker_frames_map_onto_card_statuses() {
+        assert_eq!(status_from_payload(r#"{"status": "queued"}"#), Some(CardStatus::Queued));
+        assert_eq!(
+            status_from_payload(r#"{"status": "processing"}"#),
+            Some(CardStatus::Processing)
+        );
+        assert_eq!(
+            status_from_payload(r#"{"status": "diagnosed"}"#),
+            Some(CardStatus::Diagnosed(None))
+        );
+        assert!(!CardStatus::Diagnosed(None).is_terminal(), "advice is still pending");
+        assert_eq!(
+            status_from_payload(r#"{"status": "failed", "error": "boom"}"#),
+            Some(CardStatus::Failed("boom".into()))
+        );
+        assert_eq!(status_from_payload("not json"), None);
+        assert_eq!(status_from_payload(r#"{"other": 1}"#), None);
+    }
+
+    #[test]
+    fn poll_envelopes_and_resultless_completions_are_handled() {
+        // Poll endpoint wraps the status in an ApiResponse envelope and
+        // carries no result.
+        let status =
+            status_from_payload(r#"{"success": true, "data": {"status": "completed"}}"#);
+        assert_eq!(status, Some(CardStatus::Completed(None)));
+
+        let with_result = format!(
+            r#"{{"status": "completed", "result": {{
+                "job_id": "{}",
+                "crop_type": "rice",
+                "detections": [],
+                "severity_score": null,
+                "model_version": "v1",
+                "processed_at": "2026-01-01T00:00:00Z"
+            }}}}"#,
+            uuid::Uuid::new_v4()
+        );
+        match status_from_payload(&with_result) {
+            Some(CardStatus::Completed(Some(result))) => {
+                assert_eq!(result.model_version, "v1");
+            }
+            other => panic!("expected a completed card with a result, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn uncertain_results_keep_their_flag_and_older_payloads_read_confirmed() {
+        let uncertain = format!(
+            r#"{{"status": "completed", "result": {{
+                "job_id": "{}",
+                "crop_type": "rice",
+                "detections": [{{"disease_name": "rice blast", "confidence": 0.35, "bounding_box": null}}],
+                "severity_score": null,
+                "model_version": "v1",
+                "processed_at": "2026-01-01T00:00:00Z",
+                "diagnosis_status": "uncertain"
+            }}}}"#,
+            uuid::Uuid::new_v4()
+        );
+        match status_from_payload(&uncertain) {
+            Some(CardStatus::Completed(Some(result))) => {
+                assert_eq!(result.diagnosis_status, DiagnosisStatus::Uncertain);
+            }
+            other => panic!("expected an uncertain completed card, got {other:?}"),
+        }
+
+        // Payloads from before the field existed deserialize as confirmed.
+        let legacy = format!(
+            r#"{{"status": "completed", "result": {{
+                "job_id": "{}",
+                "crop_type": "rice",
+                "detections": [],
+                "severity_score": null,
+                "model_version": "v1",
+                "processed_at": "2026-01-01T00:00:00Z"
+            }}}}"#,
+            uuid::Uuid::new_v4()
+        );
+        match status_from_payload(&legacy) {
+            Some(CardStatus::Completed(Some(result))) => {
+                assert_eq!(result.diagnosis_status, DiagnosisStatus::Confirmed);
+            }
+            other => panic!("expected a completed card, got {other:?}"),
+        }
+    }
+}
@@ -0,0 +1,405 @@
+//! Full-screen image lightbox for attachments and diagnosis photos.
+//!
+//! One shared viewer for chat attachments, the history detail view, and the
+//! comparison view. The thumbnail is shown immediately while the
+//! full-resolution signed URL loads behind it; zoom (wheel, pinch via the
+//! browser's wheel synthesis, double-tap, `+`/`-`), drag-to-pan, and
+//! arrow-key panning all run through the pure clamp math below so the image
+//! can never be zoomed or panned out of reach. While open, focus is trapped
+//! on the dialog and the page behind it cannot scroll.
+
+use wasm_bindgen::JsCast;
+use web_sys::{KeyboardEvent, MouseEvent, PointerEvent, WheelEvent};
+use yew::prelude::*;
+
+/// Zoom bounds. 1.0 is "fit the viewport"; beyond [`MAX_ZOOM`] the image is
+/// just blurry pixels.
+pub const MIN_ZOOM: f64 = 1.0;
+pub const MAX_ZOOM: f64 = 8.0;
+
+/// Zoom applied by one `+`/`-` key press or a double-tap.
+pub const KEY_ZOOM_FACTOR: f64 = 1.5;
+
+/// Viewport pixels panned per arrow-key press.
+pub const KEY_PAN_STEP: f64 = 48.0;
+
+/// Clamp a requested zoom level into the supported range.
+pub fn clamp_zoom(zoom: f64) -> f64 {
+    if zoom.is_nan() {
+        return MIN_ZOOM;
+    }
+    zoom.clamp(MIN_ZOOM, MAX_ZOOM)
+}
+
+/// Apply one wheel notch: exponential so each notch feels the same at every
+/// zoom level, negative `delta_y` (scroll up) zooming in.
+pub fn apply_wheel(zoom: f64, delta_y: f64) -> f64 {
+    clamp_zoom(zoom * (-delta_y / 500.0).exp())
+}
+
+/// Clamp a pan offset (in viewport pixels) so the image edge never crosses
+/// the viewport centre. At zoom 1 the image fits exactly and the only valid
+/// pan is (0, 0).
+pub fn clamp_pan(pan: (f64, f64), zoom: f64, viewport: (f64, f64)) -> (f64, f64) {
+    let max_x = (zoom - 1.0).max(0.0) * viewport.0 / 2.0;
+    let max_y = (zoom - 1.0).max(0.0) * viewport.1 / 2.0;
+    (pan.0.clamp(-max_x, max_x), pan.1.clamp(-max_y, max_y))
+}
+
+/// Scroll-lock bookkeeping for the page behind the lightbox. Locks nest —
+/// the comparison view can open a second lightbox over the first — and the
+/// body's original `overflow` value is restored only when the last lock is
+/// released. Pure so the lifecycle is testable without a DOM.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ScrollLock {
+    depth: usize,
+    saved_overflow: Option<String>,
+}
+
+impl ScrollLock {
+    /// Acquire the lock. Returns the `overflow` value to apply to the body
+    /// (`Some("hidden")` on the first acquisition, `None` when already held).
+    pub fn lock(&mut self, current_overflow: &str) -> Option<&'static str> {
+        self.depth += 1;
+        if self.depth == 1 {
+            self.saved_overflow = Some(current_overflow.to_string());
+            Some("hidden")
+        } else {
+            None
+        }
+    }
+
+    /// Release the lock. Returns the original `overflow` value to restore
+    /// once no lightbox remains open. Unbalanced releases are ignored.
+    pub fn unlock(&mut self) -> Option<String> {
+        match self.depth {
+            0 => None,
+            1 => {
+                self.depth = 0;
+                self.saved_overflow.take()
+            }
+            _ => {
+                self.depth -= 1;
+                None
+            }
+        }
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.depth > 0
+    }
+}
+
+thread_local! {
+    /// One shared lock for the whole page; components touch it only through
+    /// [`lock_body_scroll`] / [`unlock_body_scroll`].
+    static BODY_SCROLL_LOCK: std::cell::RefCell<ScrollLock> =
+        std::cell::RefCell::new(ScrollLock::default());
+}
+
+fn lock_body_scroll() {
+    let Some(body) = web_sys::window().and_then(|w| w.document()).and_then(|d| d.body()) else {
+        return;
+    };
+    let current = body.style().get_property_value("overflow").unwrap_or_default();
+    if let Some(value) = BODY_SCROLL_LOCK.with(|lock| lock.borrow_mut().lock(&current)) {
+        let _ = body.style().set_property("overflow", value);
+    }
+}
+
+fn unlock_body_scroll() {
+    let Some(body) = web_sys::window().and_then(|w| w.document()).and_then(|d| d.body()) else {
+        return;
+    };
+    if let Some(value) = BODY_SCROLL_LOCK.with(|lock| lock.borrow_mut().unlock()) {
+        if value.is_empty() {
+            let _ = body.style().remove_property("overflow");
+        } else {
+            let _ = body.style().set_property("overflow", &value);
+        }
+    }
+}
+
+pub fn generate_lightbox_css() -> String {
+    r#"
+.lightbox-backdrop {
+  position: fixed;
+  inset: 0;
+  z-index: 1000;
+  display: flex;
+  align-items: center;
+  justify-content: center;
+  background: rgba(17, 24, 39, 0.9);
+  touch-action: none;
+}
+.lightbox-stage { position: relative; overflow: hidden; width: 100%; height: 100%; cursor: grab; }
+.lightbox-stage.dragging { cursor: grabbing; }
+.lightbox-stage img {
+  position: absolute;
+  inset: 0;
+  margin: auto;
+  max-width: 100%;
+  max-height: 100%;
+  user-select: none;
+  -webkit-user-drag: none;
+}
+.lightbox-close {
+  position: absolute;
+  top: 12px; right: 12px;
+  z-index: 1;
+  border: none;
+  border-radius: 999px;
+  width: 40px; height: 40px;
+  background: rgba(31, 41, 55, 0.7);
+  color: #fff;
+  font-size: 1.2rem;
+}
+"#
+    .to_string()
+}
+
+#[derive(Properties, PartialEq)]
+pub struct LightboxProps {
+    /// Small image already on screen; shown instantly.
+    pub thumbnail_url: String,
+    /// Full-resolution signed URL, swapped in once it finishes loading.
+    pub full_url: String,
+    pub alt: String,
+    pub on_close: Callback<()>,
+}
+
+/// Full-screen zoomable viewer. Esc closes, `+`/`-` zoom, arrows pan,
+/// double-tap toggles between fit and 2x, wheel zooms, dragging pans.
+#[function_component(Lightbox)]
+pub fn lightbox(props: &LightboxProps) -> Html {
+    let zoom = use_state(|| MIN_ZOOM);
+    let pan = use_state(|| (0.0_f64, 0.0_f64));
+    let full_loaded = use_state(|| false);
+    let drag_origin = use_state(|| None::<(f64, f64, f64, f64)>);
+    let stage = use_node_ref();
+
+    // Lock the page scroll for the lifetime of the dialog and put focus on
+    // it so the key bindings work without a click first.
+    {
+        let stage = stage.clone();
+        use_effect_with((), move |_| {
+            lock_body_scroll();
+            if let Some(element) = stage.cast::<web_sys::HtmlElement>() {
+                let _ = element.focus();
+            }
+            unlock_body_scroll
+        });
+    }
+
+    let viewport = {
+        let stage = stage.clone();
+        move || -> (f64, f64) {
+            stage
+                .cast::<web_sys::HtmlElement>()
+                .map(|e| (f64::from(e.client_width()), f64::from(e.client_height())))
+                .unwrap_or((1.0, 1.0))
+        }
+    };
+
+    let set_zoom = {
+        let zoom = zoom.clone();
+        let pan = pan.clone();
+        let viewport = viewport.clone();
+        move |next: f64| {
+            let next = clamp_zoom(next);
+            pan.set(clamp_pan(*pan, next, viewport()));
+            zoom.set(next);
+        }
+    };
+
+    let on_wheel = {
+        let zoom = zoom.clone();
+        let set_zoom = set_zoom.clone();
+        Callback::from(move |event: WheelEvent| {
+            event.prevent_default();
+            set_zoom(apply_wheel(*zoom, event.delta_y()));
+        })
+    };
+
+    let on_dblclick = {
+        let zoom = zoom.clone();
+        let set_zoom = set_zoom.clone();
+        Callback::from(move |_: MouseEvent| {
+            set_zoom(if *zoom > MIN_ZOOM { MIN_ZOOM } else { 2.0 });
+        })
+    };
+
+    let on_pointer_down = {
+        let pan = pan.clone();
+        let drag_origin = drag_origin.clone();
+        Callback::from(move |event: PointerEvent| {
+            if let Some(target) = event
+                .target()
+                .and_then(|t| t.dyn_into::<web_sys::Element>().ok())
+            {
+                let _ = target.set_pointer_capture(event.pointer_id());
+            }
+            let (pan_x, pan_y) = *pan;
+            drag_origin.set(Some((event.client_x().into(), event.client_y().into(), pan_x, pan_y)));
+        })
+    };
+
+    let on_pointer_move = {
+        let zoom = zoom.clone();
+        let pan = pan.clone();
+        let drag_origin = drag_origin.clone();
+        let viewport = viewport.clone();
+        Callback::from(move |event: PointerEvent| {
+            let Some((start_x, start_y, pan_x, pan_y)) = *drag_origin else { return };
+            let next = (
+                pan_x + f64::from(event.client_x()) - start_x,
+                pan_y + f64::from(event.client_y()) - start_y,
+            );
+            pan.set(clamp_pan(next, *zoom, viewport()));
+        })
+    };
+
+    let on_pointer_up = {
+        let drag_origin = drag_origin.clone();
+        Callback::from(move |_: PointerEvent| drag_origin.set(None))
+    };
+
+    let on_keydown = {
+        let zoom = zoom.clone();
+        let pan = pan.clone();
+        let set_zoom = set_zoom.clone();
+        let viewport = viewport.clone();
+        let on_close = props.on_close.clone();
+        Callback::from(move |event: KeyboardEvent| {
+            let (pan_x, pan_y) = *pan;
+            let step = |dx: f64, dy: f64| clamp_pan((pan_x + dx, pan_y + dy), *zoom, viewport());
+            match event.key().as_str() {
+                "Escape" => on_close.emit(()),
+                "+" | "=" => set_zoom(*zoom * KEY_ZOOM_FACTOR),
+                "-" => set_zoom(*zoom / KEY_ZOOM_FACTOR),
+                "ArrowLeft" => pan.set(step(KEY_PAN_STEP, 0.0)),
+                "ArrowRight" => pan.set(step(-KEY_PAN_STEP, 0.0)),
+                "ArrowUp" => pan.set(step(0.0, KEY_PAN_STEP)),
+                "ArrowDown" => pan.set(step(0.0, -KEY_PAN_STEP)),
+                // The close button and the stage are the only focusables;
+                // swallowing Tab keeps focus inside the dialog.
+                "Tab" => {}
+                _ => return,
+            }
+            event.prevent_default();
+        })
+    };
+
+    let on_full_loaded = {
+        let full_loaded = full_loaded.clone();
+        Callback::from(move |_: Event| full_loaded.set(true))
+    };
+
+    let on_close = {
+        let on_close = props.on_close.clone();
+        Callback::from(move |_: MouseEvent| on_close.emit(()))
+    };
+
+    let (pan_x, pan_y) = *pan;
+    let transform = format!("translate({pan_x}px, {pan_y}px) scale({})", *zoom);
+    let stage_class = if drag_origin.is_some() {
+        "lightbox-stage dragging"
+    } else {
+        "lightbox-stage"
+    };
+
+    html! {
+        <div class="lightbox-backdrop" role="dialog" aria-modal="true" aria-label={props.alt.clone()}>
+            <button class="lightbox-close" onclick={on_close} aria-label="ปิด · Close">{ "✕" }</button>
+            <div
+                class={stage_class}
+                ref={stage}
+                tabindex="0"
+                onwheel={on_wheel}
+                ondblclick={on_dblclick}
+                onpointerdown={on_pointer_down}
+                onpointermove={on_pointer_move}
+                onpointerup={on_pointer_up}
+                onkeydown={on_keydown}
+            >
+                // The thumbnail stays underneath until the full image fires
+                // its load event, so opening never shows a blank frame.
+                if !*full_loaded {
+                    <img src={props.thumbnail_url.clone()} alt={props.alt.clone()}
+                        style={format!("transform: {transform};")} />
+                }
+                <img
+                    src={props.full_url.clone()}
+                    alt={props.alt.clone()}
+                    loading="lazy"
+                    onload={on_full_loaded}
+                    style={format!(
+                        "transform: {transform};{}",
+                        if *full_loaded { "" } else { " visibility: hidden;" }
+                    )}
+                />
+            </div>
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zoom_is_clamped_to_the_supported_range() {
+        assert_eq!(clamp_zoom(0.2), MIN_ZOOM);
+        assert_eq!(clamp_zoom(3.0), 3.0);
+        assert_eq!(clamp_zoom(50.0), MAX_ZOOM);
+        assert_eq!(clamp_zoom(f64::NAN), MIN_ZOOM);
+    }
+
+    #[test]
+    fn wheel_zoom_is_symmetric_and_clamped() {
+        let zoomed_in = apply_wheel(2.0, -500.0);
+        let back = apply_wheel(zoomed_in, 500.0);
+        assert!((back - 2.0).abs() < 1e-9, "in then out returns to start");
+        assert_eq!(apply_wheel(MAX_ZOOM, -10_000.0), MAX_ZOOM);
+        assert_eq!(apply_wheel(MIN_ZOOM, 10_000.0), MIN_ZOOM);
+    }
+
+    #[test]
+    fn pan_is_clamped_to_the_overflowing_region() {
+        let viewport = (800.0, 600.0);
+        // At zoom 1 the image fits: no panning at all.
+        assert_eq!(clamp_pan((100.0, -50.0), 1.0, viewport), (0.0, 0.0));
+        // At zoom 2 half the image overflows on each axis.
+        assert_eq!(clamp_pan((1_000.0, -1_000.0), 2.0, viewport), (400.0, -300.0));
+        assert_eq!(clamp_pan((10.0, 20.0), 2.0, viewport), (10.0, 20.0));
+    }
+
+    #[test]
+    fn scroll_lock_restores_the_original_overflow() {
+        let mut lock = ScrollLock::default();
+        assert_eq!(lock.lock("auto"), Some("hidden"));
+        assert!(lock.is_locked());
+        assert_eq!(lock.unlock(), Some("auto".to_string()));
+        assert!(!lock.is_locked());
+    }
+
+    #[test]
+    fn nested_locks_release_only_at_the_last_unlock() {
+        let mut lock = ScrollLock::default();
+        assert_eq!(lock.lock("scroll"), Some("hidden"));
+        // A second lightbox over the first changes nothing.
+        assert_eq!(lock.lock("hidden"), None);
+        assert_eq!(lock.unlock(), None);
+        assert!(lock.is_locked());
+        assert_eq!(lock.unlock(), Some("scroll".to_string()));
+    }
+
+    #[test]
+    fn unbalanced_unlock_is_ignored() {
+        let mut lock = ScrollLock::default();
+        assert_eq!(lock.unlock(), None);
+        assert_eq!(lock.lock(""), Some("hidden"));
+        assert_eq!(lock.unlock(), Some(String::new()));
+        assert_eq!(lock.unlock(), None);
+    }
+}
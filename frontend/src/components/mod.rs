@@ -0,0 +1,18 @@
+pub mod annotation_editor;
+pub mod camera_capture;
+pub mod chat_window;
+pub mod confidence_info;
+pub mod crop_context_chip;
+pub mod dashboard;
+pub mod file_info_panel;
+pub mod history_list;
+pub mod history_page;
+pub mod image;
+pub mod job_card;
+pub mod lightbox;
+pub mod network_status_banner;
+pub mod profile_page;
+pub mod tag_chips;
+pub mod trend_chart;
+pub mod version_banner;
+pub mod voice_recorder;
@@ -0,0 +1,297 @@
+//! Farmer profile form.
+//!
+//! Renders against `GET`/`PUT /api/v1/profile`. The form state is a plain
+//! struct of strings ([`ProfileForm`]) so validation and the request body
+//! are testable without a DOM; the component pre-fills from
+//! `AppState::user_profile`, falls back to fetching on first mount, and
+//! dispatches `AppAction::SetUserProfile` after a successful save.
+
+use shared::models::{CropType, FarmerProfile};
+use yew::prelude::*;
+
+use crate::services::api::ApiService;
+use crate::simple_app::{AppAction, AppContext};
+
+pub fn generate_profile_page_css() -> String {
+    r#"
+.profile-page { display: flex; flex-direction: column; gap: 12px; }
+.profile-field { display: flex; flex-direction: column; gap: 4px; }
+.profile-field label { font-size: 0.85rem; }
+.profile-field input { padding: 8px 12px; border-radius: 8px; border: 1px solid #d1d5db; }
+.profile-crops { display: flex; flex-wrap: wrap; gap: 8px; }
+.profile-crops label { display: inline-flex; align-items: center; gap: 4px; font-size: 0.85rem; }
+.profile-saved { color: var(--leaf-green); font-size: 0.85rem; margin: 0; }
+"#
+    .to_string()
+}
+
+/// Form state: everything as entered, before parsing. Numbers stay
+/// strings until save so half-typed input never fights the user.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ProfileForm {
+    pub name: String,
+    pub province: String,
+    pub farm_size_rai: String,
+    pub primary_crops: Vec<CropType>,
+    pub phone_number: String,
+}
+
+impl ProfileForm {
+    /// Pre-fill from a saved profile.
+    pub fn from_profile(profile: &FarmerProfile) -> Self {
+        Self {
+            name: profile.name.clone(),
+            province: profile.province.clone().unwrap_or_default(),
+            farm_size_rai: profile
+                .farm_size_rai
+                .map(|s| s.to_string())
+                .unwrap_or_default(),
+            primary_crops: profile.primary_crops.clone(),
+            phone_number: profile.phone_number.clone().unwrap_or_default(),
+        }
+    }
+
+    /// Add or remove a crop from the selection.
+    pub fn toggle_crop(&mut self, crop: CropType) {
+        if let Some(index) = self.primary_crops.iter().position(|c| *c == crop) {
+            self.primary_crops.remove(index);
+        } else {
+            self.primary_crops.push(crop);
+        }
+    }
+
+    /// Client-side check mirroring the gateway's rules, so obvious
+    /// mistakes don't round-trip. Returns the `PUT` body on success.
+    pub fn to_request_body(&self) -> Result<serde_json::Value, String> {
+        let name = self.name.trim();
+        if name.is_empty() {
+            return Err("กรุณากรอกชื่อ · Name is required".into());
+        }
+        let farm_size_rai = match self.farm_size_rai.trim() {
+            "" => None,
+            raw => match raw.parse::<f64>() {
+                Ok(size) if size > 0.0 => Some(size),
+                _ => return Err("ขนาดไร่ต้องเป็นตัวเลขบวก · Farm size must be a positive number".into()),
+            },
+        };
+        let optional = |value: &str| {
+            let trimmed = value.trim();
+            (!trimmed.is_empty()).then(|| trimmed.to_string())
+        };
+        Ok(serde_json::json!({
+            "name": name,
+            "province": optional(&self.province),
+            "farm_size_rai": farm_size_rai,
+            "primary_crops": self.primary_crops,
+            "phone_number": optional(&self.phone_number),
+        }))
+    }
+}
+
+#[function_component(FarmerProfilePage)]
+pub fn farmer_profile_page() -> Html {
+    let app = use_context::<AppContext>().expect("FarmerProfilePage must be inside the app context");
+    let form = use_state(|| {
+        app.user_profile
+            .as_ref()
+            .map(ProfileForm::from_profile)
+            .unwrap_or_default()
+    });
+    let saving = use_state(|| false);
+    let saved = use_state(|| false);
+
+    // Nothing in the reducer yet: fetch the saved profile once and
+    // pre-fill. A 404 just means a blank form.
+    {
+        let app = app.clone();
+        let form = form.clone();
+        use_effect_with((), move |_| {
+            if app.user_profile.is_none() {
+                wasm_bindgen_futures::spawn_local(async move {
+                    let api = ApiService::new();
+                    match api.get_profile().await {
+                        Ok(Some(profile)) => {
+                            form.set(ProfileForm::from_profile(&profile));
+                            app.dispatch(AppAction::SetUserProfile(profile));
+                        }
+                        Ok(None) => {}
+                        Err(error) => {
+                            app.dispatch(AppAction::SetError(Some(error.to_string())))
+                        }
+                    }
+                });
+            }
+            || ()
+        });
+    }
+
+    let text_field = |label: &str,
+                      value: String,
+                      set: fn(&mut ProfileForm, String)| {
+        let form = form.clone();
+        let saved = saved.clone();
+        let oninput = Callback::from(move |e: InputEvent| {
+            let target: web_sys::HtmlInputElement = e.target_unchecked_into();
+            let mut next = (*form).clone();
+            set(&mut next, target.value());
+            form.set(next);
+            saved.set(false);
+        });
+        html! {
+            <div class="profile-field">
+                <label>{ label }</label>
+                <input type="text" {value} {oninput} />
+            </div>
+        }
+    };
+
+    let crop_checkbox = |crop: CropType| {
+        let form = form.clone();
+        let saved = saved.clone();
+        let checked = form.primary_crops.contains(&crop);
+        let onchange = Callback::from(move |_| {
+            let mut next = (*form).clone();
+            next.toggle_crop(crop);
+            form.set(next);
+            saved.set(false);
+        });
+        html! {
+            <label key={crop.as_str()}>
+                <input type="checkbox" {checked} {onchange} />
+                { crop.as_str() }
+            </label>
+        }
+    };
+
+    let on_save = {
+        let app = app.clone();
+        let form = form.clone();
+        let saving = saving.clone();
+        let saved = saved.clone();
+        Callback::from(move |_| {
+            if *saving {
+                return;
+            }
+            let body = match form.to_request_body() {
+                Ok(body) => body,
+                Err(message) => {
+                    app.dispatch(AppAction::SetError(Some(message)));
+                    return;
+                }
+            };
+            saving.set(true);
+            let app = app.clone();
+            let saving = saving.clone();
+            let saved = saved.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                let api = ApiService::new();
+                match api.save_profile(body).await {
+                    Ok(profile) => {
+                        app.dispatch(AppAction::SetUserProfile(profile));
+                        saved.set(true);
+                    }
+                    Err(error) => app.dispatch(AppAction::SetError(Some(error.to_string()))),
+                }
+                saving.set(false);
+            });
+        })
+    };
+
+    html! {
+        <div class="profile-page card">
+            { text_field("ชื่อ · Name", form.name.clone(), |f, v| f.name = v) }
+            { text_field("จังหวัด · Province", form.province.clone(), |f, v| f.province = v) }
+            { text_field(
+                "ขนาดไร่ (ไร่) · Farm size (rai)",
+                form.farm_size_rai.clone(),
+                |f, v| f.farm_size_rai = v,
+            ) }
+            <div class="profile-field">
+                <label>{ "พืชหลัก · Primary crops" }</label>
+                <div class="profile-crops">
+                    { for CropType::ALL.iter().map(|crop| crop_checkbox(*crop)) }
+                </div>
+            </div>
+            { text_field(
+                "เบอร์โทร · Phone number",
+                form.phone_number.clone(),
+                |f, v| f.phone_number = v,
+            ) }
+            <button class="btn-primary" disabled={*saving} onclick={on_save}>
+                { "บันทึก · Save" }
+            </button>
+            if *saved {
+                <p class="profile-saved" role="status">{ "บันทึกแล้ว · Saved" }</p>
+            }
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use uuid::Uuid;
+
+    fn profile() -> FarmerProfile {
+        FarmerProfile {
+            user_id: Uuid::nil(),
+            name: "สมหญิง".into(),
+            province: Some("Chiang Mai".into()),
+            farm_size_rai: Some(8.0),
+            primary_crops: vec![CropType::Durian],
+            phone_number: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn the_form_prefills_from_a_saved_profile() {
+        let form = ProfileForm::from_profile(&profile());
+        assert_eq!(form.name, "สมหญิง");
+        assert_eq!(form.province, "Chiang Mai");
+        assert_eq!(form.farm_size_rai, "8");
+        assert_eq!(form.primary_crops, vec![CropType::Durian]);
+        assert_eq!(form.phone_number, "");
+    }
+
+    #[test]
+    fn toggling_a_crop_adds_then_removes_it() {
+        let mut form = ProfileForm::default();
+        form.toggle_crop(CropType::Rice);
+        assert_eq!(form.primary_crops, vec![CropType::Rice]);
+        form.toggle_crop(CropType::Mango);
+        form.toggle_crop(CropType::Rice);
+        assert_eq!(form.primary_crops, vec![CropType::Mango]);
+    }
+
+    #[test]
+    fn the_request_body_trims_and_nulls_empty_optionals() {
+        let form = ProfileForm {
+            name: "  สมชาย  ".into(),
+            province: "   ".into(),
+            farm_size_rai: "12.5".into(),
+            primary_crops: vec![CropType::Rice],
+            phone_number: String::new(),
+        };
+        let body = form.to_request_body().unwrap();
+        assert_eq!(body["name"], "สมชาย");
+        assert_eq!(body["province"], serde_json::Value::Null);
+        assert_eq!(body["farm_size_rai"], 12.5);
+        assert_eq!(body["primary_crops"][0], "rice");
+        assert_eq!(body["phone_number"], serde_json::Value::Null);
+    }
+
+    #[test]
+    fn missing_name_and_garbage_farm_size_are_rejected() {
+        let mut form = ProfileForm { name: " ".into(), ..ProfileForm::default() };
+        assert!(form.to_request_body().is_err());
+        form.name = "สมชาย".into();
+        form.farm_size_rai = "many".into();
+        assert!(form.to_request_body().is_err());
+        form.farm_size_rai = "-3".into();
+        assert!(form.to_request_body().is_err());
+        form.farm_size_rai = String::new();
+        assert!(form.to_request_body().is_ok());
+    }
+}
@@ -0,0 +1,122 @@
+//! Tag chips shown on conversation and job cards, with a small add-tag
+//! popover. Validation mirrors the gateway's 30-char limit so users get
+//! feedback before the request round-trips.
+
+use yew::prelude::*;
+
+pub const MAX_TAG_LENGTH: usize = 30;
+
+pub fn generate_tag_chips_css() -> String {
+    r#"
+.tag-chips { display: flex; flex-wrap: wrap; gap: 6px; align-items: center; }
+.tag-chip {
+  display: inline-flex;
+  align-items: center;
+  gap: 4px;
+  padding: 2px 10px;
+  border-radius: 999px;
+  background: var(--surface);
+  font-size: 0.8rem;
+}
+.tag-chip button { border: none; background: none; cursor: pointer; padding: 0; }
+.tag-add { position: relative; }
+.tag-add-popover {
+  position: absolute;
+  top: calc(100% + 4px);
+  left: 0;
+  z-index: 100;
+  display: flex;
+  gap: 6px;
+  padding: 8px;
+  border-radius: 8px;
+  background: #fff;
+  box-shadow: 0 4px 12px rgba(31, 41, 55, 0.2);
+}
+.tag-add-popover input { width: 140px; }
+"#
+    .to_string()
+}
+
+#[derive(Properties, PartialEq)]
+pub struct TagChipsProps {
+    pub tags: Vec<String>,
+    pub on_add: Callback<String>,
+    pub on_remove: Callback<String>,
+}
+
+#[function_component(TagChips)]
+pub fn tag_chips(props: &TagChipsProps) -> Html {
+    let open = use_state(|| false);
+    let draft = use_state(String::new);
+
+    let toggle = {
+        let open = open.clone();
+        Callback::from(move |_| open.set(!*open))
+    };
+    let oninput = {
+        let draft = draft.clone();
+        Callback::from(move |e: InputEvent| {
+            let target: web_sys::HtmlInputElement = e.target_unchecked_into();
+            draft.set(target.value());
+        })
+    };
+    let submit = {
+        let open = open.clone();
+        let draft = draft.clone();
+        let on_add = props.on_add.clone();
+        Callback::from(move |_| {
+            let name = draft.trim().to_string();
+            if name.is_empty() || name.chars().count() > MAX_TAG_LENGTH {
+                return;
+            }
+            on_add.emit(name);
+            draft.set(String::new());
+            open.set(false);
+        })
+    };
+
+    html! {
+        <div class="tag-chips">
+            { for props.tags.iter().map(|tag| {
+                let on_remove = props.on_remove.clone();
+                let name = tag.clone();
+                html! {
+                    <span class="tag-chip">
+                        { tag }
+                        <button
+                            type="button"
+                            aria-label={format!("ลบแท็ก {tag} · Remove tag {tag}")}
+                            onclick={Callback::from(move |_| on_remove.emit(name.clone()))}
+                        >
+                            { "×" }
+                        </button>
+                    </span>
+                }
+            }) }
+            <div class="tag-add">
+                <button
+                    type="button"
+                    class="tag-chip"
+                    aria-expanded={open.to_string()}
+                    onclick={toggle}
+                >
+                    { "+ แท็ก · tag" }
+                </button>
+                if *open {
+                    <div class="tag-add-popover">
+                        <input
+                            type="text"
+                            value={(*draft).clone()}
+                            maxlength={MAX_TAG_LENGTH.to_string()}
+                            placeholder="ชื่อแท็ก · tag name"
+                            {oninput}
+                        />
+                        <button type="button" class="btn-primary" onclick={submit}>
+                            { "เพิ่ม · Add" }
+                        </button>
+                    </div>
+                }
+            </div>
+        </div>
+    }
+}
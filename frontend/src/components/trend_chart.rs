@@ -0,0 +1,323 @@
+//! Dependency-free SVG line/area chart.
+//!
+//! Built for the dashboard trends: a dozen weekly points, one series per
+//! chart. The path math is pure string-building over `Option<f64>` values —
+//! `None` means "no data that week" and breaks the line into separate
+//! segments instead of plotting a misleading zero. Tooltips are native
+//! `<title>` elements on the hover markers, and the draw-in transition is
+//! disabled for users who prefer reduced motion.
+
+use yew::prelude::*;
+
+use crate::hooks::breakpoint::{use_breakpoint, Breakpoint};
+
+/// Fixed chart height; width follows the breakpoint.
+const CHART_HEIGHT: f64 = 160.0;
+/// Margins for the axis labels.
+const PAD_LEFT: f64 = 36.0;
+const PAD_RIGHT: f64 = 8.0;
+const PAD_TOP: f64 = 8.0;
+const PAD_BOTTOM: f64 = 22.0;
+/// Horizontal y-axis gridlines (including the baseline).
+const Y_TICKS: usize = 4;
+
+/// One point on the x axis. `value: None` renders as a gap.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrendPoint {
+    /// Short x-axis label (e.g. an ISO week start date).
+    pub label: String,
+    pub value: Option<f64>,
+}
+
+/// Round the y-axis maximum up to a tidy value so gridline labels don't
+/// come out as 0.37-style fractions. Zero-or-empty data gets a unit axis.
+pub fn nice_max(values: &[TrendPoint]) -> f64 {
+    let max = values
+        .iter()
+        .filter_map(|p| p.value)
+        .fold(0.0_f64, f64::max);
+    if max <= 0.0 {
+        return 1.0;
+    }
+    // Scale into [1, 10), round the mantissa up to 1/2/5/10 steps.
+    let magnitude = 10.0_f64.powf(max.log10().floor());
+    let mantissa = max / magnitude;
+    let nice = if mantissa <= 1.0 {
+        1.0
+    } else if mantissa <= 2.0 {
+        2.0
+    } else if mantissa <= 5.0 {
+        5.0
+    } else {
+        10.0
+    };
+    nice * magnitude
+}
+
+/// Map the series onto pixel coordinates; gaps stay `None`.
+pub fn scale_points(
+    points: &[TrendPoint],
+    width: f64,
+    y_max: f64,
+) -> Vec<Option<(f64, f64)>> {
+    let inner_width = (width - PAD_LEFT - PAD_RIGHT).max(1.0);
+    let inner_height = CHART_HEIGHT - PAD_TOP - PAD_BOTTOM;
+    let step = if points.len() > 1 {
+        inner_width / (points.len() - 1) as f64
+    } else {
+        0.0
+    };
+    points
+        .iter()
+        .enumerate()
+        .map(|(i, point)| {
+            point.value.map(|value| {
+                let x = PAD_LEFT + step * i as f64;
+                let y = PAD_TOP + inner_height * (1.0 - (value / y_max).clamp(0.0, 1.0));
+                (round2(x), round2(y))
+            })
+        })
+        .collect()
+}
+
+/// Two-decimal rounding keeps the path strings stable and readable.
+fn round2(v: f64) -> f64 {
+    (v * 100.0).round() / 100.0
+}
+
+/// Build the polyline `d` attribute. Each run of consecutive values becomes
+/// one `M ... L ...` segment; a gap starts a fresh `M`, so missing weeks
+/// show as breaks in the line.
+pub fn line_path(scaled: &[Option<(f64, f64)>]) -> String {
+    let mut path = String::new();
+    let mut in_segment = false;
+    for point in scaled {
+        match point {
+            Some((x, y)) => {
+                let command = if in_segment { 'L' } else { 'M' };
+                path.push_str(&format!("{command}{x} {y} "));
+                in_segment = true;
+            }
+            None => in_segment = false,
+        }
+    }
+    path.trim_end().to_string()
+}
+
+/// Build the area fill: every contiguous segment is closed down to the
+/// baseline. Single isolated points produce no area (they still get a
+/// marker from the component).
+pub fn area_path(scaled: &[Option<(f64, f64)>]) -> String {
+    let baseline = round2(CHART_HEIGHT - PAD_BOTTOM);
+    let mut path = String::new();
+    let mut segment: Vec<(f64, f64)> = Vec::new();
+    let mut close = |segment: &mut Vec<(f64, f64)>, path: &mut String| {
+        if segment.len() < 2 {
+            segment.clear();
+            return;
+        }
+        let (first_x, _) = segment[0];
+        let (last_x, _) = segment[segment.len() - 1];
+        path.push_str(&format!("M{first_x} {baseline} "));
+        for (x, y) in segment.iter() {
+            path.push_str(&format!("L{x} {y} "));
+        }
+        path.push_str(&format!("L{last_x} {baseline} Z "));
+        segment.clear();
+    };
+    for point in scaled {
+        match point {
+            Some(xy) => segment.push(*xy),
+            None => close(&mut segment, &mut path),
+        }
+    }
+    close(&mut segment, &mut path);
+    path.trim_end().to_string()
+}
+
+pub fn generate_trend_chart_css() -> String {
+    r#"
+.trend-chart { margin-bottom: 16px; }
+.trend-chart h4 { margin: 0 0 4px; font-size: 0.95rem; }
+.trend-chart-empty { font-size: 0.85rem; opacity: 0.7; }
+.trend-chart-grid { stroke: #e5e7eb; stroke-width: 1; }
+.trend-chart-axis-label { font-size: 9px; fill: #6b7280; }
+.trend-chart-area { fill: var(--leaf-green); opacity: 0.15; }
+.trend-chart-line {
+  fill: none;
+  stroke: var(--leaf-green);
+  stroke-width: 2;
+  transition: d 0.3s ease;
+}
+.trend-chart-marker { fill: var(--leaf-green); }
+.trend-chart-marker:hover { r: 5; }
+@media (prefers-reduced-motion: reduce) {
+  .trend-chart-line { transition: none; }
+}
+"#
+    .to_string()
+}
+
+#[derive(Properties, PartialEq)]
+pub struct TrendChartProps {
+    pub title: String,
+    pub points: Vec<TrendPoint>,
+    /// Formats a value for tooltips and the y axis (e.g. `{:.2}` for
+    /// severity, `{:.0}` for counts).
+    #[prop_or_default]
+    pub integer_values: bool,
+}
+
+#[function_component(TrendChart)]
+pub fn trend_chart(props: &TrendChartProps) -> Html {
+    let width = match use_breakpoint() {
+        Breakpoint::Narrow => 320.0,
+        Breakpoint::Medium => 440.0,
+        Breakpoint::Wide => 640.0,
+    };
+
+    if props.points.iter().all(|p| p.value.is_none()) {
+        return html! {
+            <div class="trend-chart">
+                <h4>{ &props.title }</h4>
+                <p class="trend-chart-empty">{ "ยังไม่มีข้อมูล · No data yet" }</p>
+            </div>
+        };
+    }
+
+    let y_max = nice_max(&props.points);
+    let scaled = scale_points(&props.points, width, y_max);
+    let line = line_path(&scaled);
+    let area = area_path(&scaled);
+    let format_value = |value: f64| {
+        if props.integer_values {
+            format!("{value:.0}")
+        } else {
+            format!("{value:.2}")
+        }
+    };
+
+    let gridlines = (0..=Y_TICKS).map(|i| {
+        let fraction = i as f64 / Y_TICKS as f64;
+        let y = round2(PAD_TOP + (CHART_HEIGHT - PAD_TOP - PAD_BOTTOM) * fraction);
+        let label = format_value(y_max * (1.0 - fraction));
+        html! {
+            <>
+                <line class="trend-chart-grid" x1={PAD_LEFT.to_string()} y1={y.to_string()}
+                      x2={(width - PAD_RIGHT).to_string()} y2={y.to_string()} />
+                <text class="trend-chart-axis-label" x="0" y={(y + 3.0).to_string()}>
+                    { label }
+                </text>
+            </>
+        }
+    });
+
+    // Label first, middle, and last weeks; a dozen labels would collide.
+    let x_labels = props.points.iter().enumerate().filter_map(|(i, point)| {
+        let last = props.points.len().saturating_sub(1);
+        if i != 0 && i != last && i != last / 2 {
+            return None;
+        }
+        let x = scaled
+            .get(i)
+            .copied()
+            .flatten()
+            .map(|(x, _)| x)
+            .unwrap_or(PAD_LEFT + (width - PAD_LEFT - PAD_RIGHT) * i as f64 / last.max(1) as f64);
+        Some(html! {
+            <text class="trend-chart-axis-label" text-anchor="middle"
+                  x={x.to_string()} y={(CHART_HEIGHT - 6.0).to_string()}>
+                { point.label.clone() }
+            </text>
+        })
+    });
+
+    let markers = props.points.iter().zip(&scaled).filter_map(|(point, xy)| {
+        let (x, y) = (*xy)?;
+        let value = point.value?;
+        Some(html! {
+            <circle class="trend-chart-marker" cx={x.to_string()} cy={y.to_string()} r="3">
+                <title>{ format!("{}: {}", point.label, format_value(value)) }</title>
+            </circle>
+        })
+    });
+
+    html! {
+        <div class="trend-chart">
+            <h4>{ &props.title }</h4>
+            <svg viewBox={format!("0 0 {width} {CHART_HEIGHT}")}
+                 width={width.to_string()} height={CHART_HEIGHT.to_string()}
+                 role="img" aria-label={props.title.clone()}>
+                { for gridlines }
+                <path class="trend-chart-area" d={area} />
+                <path class="trend-chart-line" d={line} />
+                { for markers }
+                { for x_labels }
+            </svg>
+        </div>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn points(values: &[Option<f64>]) -> Vec<TrendPoint> {
+        values
+            .iter()
+            .enumerate()
+            .map(|(i, value)| TrendPoint {
+                label: format!("w{i}"),
+                value: *value,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn a_full_series_becomes_one_segment() {
+        let points = points(&[Some(0.0), Some(0.5), Some(1.0)]);
+        let scaled = scale_points(&points, 320.0, 1.0);
+        let path = line_path(&scaled);
+        assert!(path.starts_with('M'));
+        assert_eq!(path.matches('M').count(), 1);
+        assert_eq!(path.matches('L').count(), 2);
+        // y=1.0 at y_max=1.0 maps to the top padding; 0.0 to the baseline.
+        assert_eq!(scaled[2].unwrap().1, PAD_TOP);
+        assert_eq!(scaled[0].unwrap().1, CHART_HEIGHT - PAD_BOTTOM);
+    }
+
+    #[test]
+    fn gaps_split_the_line_instead_of_plotting_zero() {
+        let points = points(&[Some(1.0), Some(2.0), None, Some(3.0), Some(1.0)]);
+        let scaled = scale_points(&points, 320.0, 5.0);
+        let path = line_path(&scaled);
+        // Two segments: before and after the empty week.
+        assert_eq!(path.matches('M').count(), 2);
+        assert_eq!(path.matches('L').count(), 2);
+        assert!(!path.contains(&format!("{}", CHART_HEIGHT - PAD_BOTTOM)),
+            "a gap must not be drawn at the baseline: {path}");
+    }
+
+    #[test]
+    fn area_closes_each_segment_to_the_baseline() {
+        let points = points(&[Some(1.0), Some(2.0), None, Some(3.0), Some(1.0)]);
+        let scaled = scale_points(&points, 320.0, 5.0);
+        let area = area_path(&scaled);
+        assert_eq!(area.matches('Z').count(), 2);
+
+        // An isolated point has no area to fill.
+        let lone = scale_points(&self::points(&[None, Some(2.0), None]), 320.0, 5.0);
+        assert_eq!(area_path(&lone), "");
+        // ...but still draws a marker-able M in the line path.
+        assert_eq!(line_path(&lone).matches('M').count(), 1);
+    }
+
+    #[test]
+    fn nice_max_rounds_up_to_tidy_steps() {
+        assert_eq!(nice_max(&points(&[Some(0.37)])), 0.5);
+        assert_eq!(nice_max(&points(&[Some(1.2)])), 2.0);
+        assert_eq!(nice_max(&points(&[Some(17.0)])), 20.0);
+        assert_eq!(nice_max(&points(&[Some(73.0)])), 100.0);
+        assert_eq!(nice_max(&points(&[None, None])), 1.0, "empty data gets a unit axis");
+    }
+}
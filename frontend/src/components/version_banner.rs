@@ -0,0 +1,83 @@
+//! Banner / modal telling users on a stale bundle to reload.
+
+use gloo_net::http::Request;
+use yew::prelude::*;
+
+use crate::services::version::{classify, VersionInfo, VersionStatus, BUILD_VERSION};
+
+pub fn generate_version_banner_css() -> String {
+    r#"
+.version-banner {
+  display: flex;
+  align-items: center;
+  justify-content: space-between;
+  gap: 12px;
+  padding: 8px 16px;
+  background: var(--warning-amber);
+  color: var(--ink);
+}
+.version-banner button { white-space: nowrap; }
+.version-modal-backdrop {
+  position: fixed;
+  inset: 0;
+  background: rgba(31, 41, 55, 0.6);
+  display: flex;
+  align-items: center;
+  justify-content: center;
+  z-index: 1000;
+}
+.version-modal { max-width: 360px; text-align: center; }
+"#
+    .to_string()
+}
+
+fn reload() {
+    if let Some(window) = web_sys::window() {
+        // Bypass the HTTP cache so the new bundle is actually fetched.
+        let _ = window.location().reload();
+    }
+}
+
+/// Fetches `/api/v1/version` once on mount and renders nothing, a reload
+/// banner, or a blocking modal depending on how stale this bundle is.
+#[function_component(VersionBanner)]
+pub fn version_banner() -> Html {
+    let status = use_state(|| VersionStatus::Current);
+
+    {
+        let status = status.clone();
+        use_effect_with((), move |_| {
+            wasm_bindgen_futures::spawn_local(async move {
+                let Ok(response) = Request::get("/api/v1/version").send().await else {
+                    return; // offline or old gateway: stay quiet
+                };
+                if let Ok(info) = response.json::<VersionInfo>().await {
+                    status.set(classify(BUILD_VERSION, &info));
+                }
+            });
+        });
+    }
+
+    match *status {
+        VersionStatus::Current => html! {},
+        VersionStatus::Outdated => html! {
+            <div class="version-banner" role="status">
+                <span>{ "มีเวอร์ชันใหม่ · A new version is available." }</span>
+                <button class="btn-primary" onclick={Callback::from(|_| reload())}>
+                    { "รีโหลด · Reload" }
+                </button>
+            </div>
+        },
+        VersionStatus::Incompatible => html! {
+            <div class="version-modal-backdrop" role="dialog" aria-modal="true">
+                <div class="version-modal card">
+                    <h2>{ "จำเป็นต้องอัปเดต · Update required" }</h2>
+                    <p>{ "เวอร์ชันนี้ใช้งานไม่ได้แล้ว กรุณารีโหลดหน้า · This version can no longer talk to the server. Please reload." }</p>
+                    <button class="btn-primary" onclick={Callback::from(|_| reload())}>
+                        { "รีโหลด · Reload" }
+                    </button>
+                </div>
+            </div>
+        },
+    }
+}
@@ -0,0 +1,351 @@
+//! Voice note recording over the `MediaRecorder` API.
+//!
+//! Farmers describe symptoms faster by talking than by typing Thai on a
+//! phone keyboard. The recorder captures `audio/webm` from the microphone,
+//! concatenates the recorded chunks, and hands the result to the parent as
+//! one base64 string — the same shape the upload paths already use for
+//! images. Recording is a three-phase machine (idle, recording,
+//! processing) so the button can never start a second recorder while one
+//! is still encoding.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::{JsCast, JsValue};
+use yew::prelude::*;
+
+/// Container/codec requested from the recorder; what the gateway's voice
+/// endpoint expects to receive.
+pub const VOICE_MIME: &str = "audio/webm";
+
+/// How often the recorder flushes a chunk while recording, so a tab
+/// closed mid-recording loses at most this much audio.
+const CHUNK_INTERVAL_MS: i32 = 250;
+
+pub fn generate_voice_recorder_css() -> String {
+    r#"
+.voice-recorder { display: inline-flex; flex-direction: column; gap: 4px; }
+.voice-recorder button.recording { background: var(--danger-red); }
+.voice-error { color: var(--danger-red); font-size: 0.8rem; margin: 0; }
+"#
+    .to_string()
+}
+
+/// Where the recorder is in its lifecycle. Transitions go through
+/// [`transition`] so the legal moves are testable without a microphone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecorderPhase {
+    /// Nothing running; the button starts a recording.
+    Idle,
+    /// The microphone is live and chunks are accumulating.
+    Recording,
+    /// Recording stopped; chunks are being concatenated and encoded.
+    Processing,
+}
+
+/// The events that move the recorder between phases.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecorderEvent {
+    /// `getUserMedia` resolved and the recorder started.
+    StartGranted,
+    /// The user pressed stop.
+    StopPressed,
+    /// The chunks were concatenated and emitted.
+    Encoded,
+    /// Permission denied, recorder error, or encoding failure.
+    Failed,
+}
+
+/// Advance the phase machine; illegal moves (a stop while idle, a late
+/// data event after a failure) leave the phase unchanged rather than
+/// panicking, because DOM event ordering is not under our control.
+pub fn transition(phase: RecorderPhase, event: RecorderEvent) -> RecorderPhase {
+    match (phase, event) {
+        (RecorderPhase::Idle, RecorderEvent::StartGranted) => RecorderPhase::Recording,
+        (RecorderPhase::Recording, RecorderEvent::StopPressed) => RecorderPhase::Processing,
+        (RecorderPhase::Processing, RecorderEvent::Encoded) => RecorderPhase::Idle,
+        (_, RecorderEvent::Failed) => RecorderPhase::Idle,
+        (phase, _) => phase,
+    }
+}
+
+/// Concatenate recorded chunks in arrival order.
+pub fn concat_chunks(chunks: &[Vec<u8>]) -> Vec<u8> {
+    let mut joined = Vec::with_capacity(chunks.iter().map(Vec::len).sum());
+    for chunk in chunks {
+        joined.extend_from_slice(chunk);
+    }
+    joined
+}
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Standard base64 with padding. Small enough to keep here rather than
+/// pulling a crate into the wasm bundle for one call site.
+pub fn encode_base64(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for group in bytes.chunks(3) {
+        let b = [group[0], *group.get(1).unwrap_or(&0), *group.get(2).unwrap_or(&0)];
+        let indices = [
+            b[0] >> 2,
+            ((b[0] & 0x03) << 4) | (b[1] >> 4),
+            ((b[1] & 0x0f) << 2) | (b[2] >> 6),
+            b[2] & 0x3f,
+        ];
+        for (slot, index) in indices.into_iter().enumerate() {
+            if slot <= group.len() {
+                out.push(BASE64_ALPHABET[index as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// Everything that must stay alive while a recording runs: the recorder,
+/// the stream whose tracks we stop afterwards, and the event closures
+/// (dropping a closure while the recorder still references it would
+/// invalidate the handler).
+struct RecorderHandle {
+    recorder: web_sys::MediaRecorder,
+    stream: web_sys::MediaStream,
+    _ondata: Closure<dyn FnMut(web_sys::BlobEvent)>,
+    _onstop: Closure<dyn FnMut(web_sys::Event)>,
+}
+
+impl RecorderHandle {
+    /// Release the microphone; the recording light should go out the
+    /// moment the user stops, not when GC gets around to it.
+    fn stop_tracks(&self) {
+        for track in self.stream.get_tracks().iter() {
+            if let Ok(track) = track.dyn_into::<web_sys::MediaStreamTrack>() {
+                track.stop();
+            }
+        }
+    }
+}
+
+#[derive(Properties, PartialEq)]
+pub struct VoiceRecorderProps {
+    /// Fired with the complete recording as base64-encoded `audio/webm`.
+    pub on_recording_complete: Callback<String>,
+}
+
+#[function_component(VoiceRecorder)]
+pub fn voice_recorder(props: &VoiceRecorderProps) -> Html {
+    let phase = use_state(|| RecorderPhase::Idle);
+    let error = use_state(|| Option::<String>::None);
+    let handle = use_mut_ref(|| Option::<RecorderHandle>::None);
+
+    let start = {
+        let phase = phase.clone();
+        let error = error.clone();
+        let handle = handle.clone();
+        let on_complete = props.on_recording_complete.clone();
+        Callback::from(move |_| {
+            if *phase != RecorderPhase::Idle {
+                return;
+            }
+            error.set(None);
+            let phase = phase.clone();
+            let error = error.clone();
+            let handle = handle.clone();
+            let on_complete = on_complete.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                match start_recorder(phase.clone(), on_complete).await {
+                    Ok(started) => {
+                        *handle.borrow_mut() = Some(started);
+                        phase.set(transition(*phase, RecorderEvent::StartGranted));
+                    }
+                    Err(_) => {
+                        error.set(Some(
+                            "ใช้ไมโครโฟนไม่ได้ · Microphone unavailable".into(),
+                        ));
+                        phase.set(transition(*phase, RecorderEvent::Failed));
+                    }
+                }
+            });
+        })
+    };
+
+    let stop = {
+        let phase = phase.clone();
+        let handle = handle.clone();
+        Callback::from(move |_| {
+            if *phase != RecorderPhase::Recording {
+                return;
+            }
+            if let Some(running) = handle.borrow_mut().take() {
+                let _ = running.recorder.stop();
+                running.stop_tracks();
+                // The closures in the handle must survive until onstop has
+                // fired (it comes after the final dataavailable event), so
+                // park it there instead of dropping it now.
+                STOPPING.with(|s| *s.borrow_mut() = Some(running));
+            }
+            phase.set(transition(*phase, RecorderEvent::StopPressed));
+        })
+    };
+
+    let (label, onclick, busy) = match *phase {
+        RecorderPhase::Idle => ("🎤 อัดเสียง · Record", start, false),
+        RecorderPhase::Recording => ("⏹ หยุด · Stop", stop, false),
+        RecorderPhase::Processing => ("กำลังประมวลผล… · Processing…", stop, true),
+    };
+
+    html! {
+        <div class="voice-recorder">
+            <button
+                type="button"
+                class={classes!(
+                    "btn-primary",
+                    (*phase == RecorderPhase::Recording).then_some("recording")
+                )}
+                disabled={busy}
+                {onclick}
+            >
+                { label }
+            </button>
+            if let Some(message) = error.as_ref() {
+                <p class="voice-error" role="alert">{ message }</p>
+            }
+        </div>
+    }
+}
+
+thread_local! {
+    /// A stopped recorder parked until its `onstop` callback has run; the
+    /// closures it owns must stay valid through the final data events.
+    static STOPPING: RefCell<Option<RecorderHandle>> = const { RefCell::new(None) };
+}
+
+/// Ask for the microphone, start a chunked `audio/webm` recording, and
+/// wire the data/stop handlers that concatenate, encode, and emit.
+async fn start_recorder(
+    phase: UseStateHandle<RecorderPhase>,
+    on_complete: Callback<String>,
+) -> Result<RecorderHandle, JsValue> {
+    let devices = web_sys::window()
+        .ok_or_else(|| JsValue::from_str("no window"))?
+        .navigator()
+        .media_devices()?;
+    let constraints = web_sys::MediaStreamConstraints::new();
+    constraints.set_audio(&JsValue::TRUE);
+    let stream: web_sys::MediaStream =
+        wasm_bindgen_futures::JsFuture::from(devices.get_user_media_with_constraints(&constraints)?)
+            .await?
+            .dyn_into()?;
+
+    let options = web_sys::MediaRecorderOptions::new();
+    options.set_mime_type(VOICE_MIME);
+    let recorder = web_sys::MediaRecorder::new_with_media_stream_and_media_recorder_options(
+        &stream, &options,
+    )?;
+
+    let chunks: Rc<RefCell<Vec<web_sys::Blob>>> = Rc::new(RefCell::new(Vec::new()));
+    let ondata = {
+        let chunks = chunks.clone();
+        Closure::<dyn FnMut(web_sys::BlobEvent)>::new(move |event: web_sys::BlobEvent| {
+            if let Some(blob) = event.data() {
+                chunks.borrow_mut().push(blob);
+            }
+        })
+    };
+    recorder.set_ondataavailable(Some(ondata.as_ref().unchecked_ref()));
+
+    let onstop = Closure::<dyn FnMut(web_sys::Event)>::new(move |_: web_sys::Event| {
+        let chunks = chunks.clone();
+        let phase = phase.clone();
+        let on_complete = on_complete.clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            let blobs: Vec<web_sys::Blob> = chunks.borrow_mut().drain(..).collect();
+            let mut parts = Vec::with_capacity(blobs.len());
+            for blob in &blobs {
+                match wasm_bindgen_futures::JsFuture::from(blob.array_buffer()).await {
+                    Ok(buffer) => parts.push(js_sys::Uint8Array::new(&buffer).to_vec()),
+                    Err(_) => {
+                        phase.set(transition(*phase, RecorderEvent::Failed));
+                        STOPPING.with(|s| s.borrow_mut().take());
+                        return;
+                    }
+                }
+            }
+            on_complete.emit(encode_base64(&concat_chunks(&parts)));
+            phase.set(transition(*phase, RecorderEvent::Encoded));
+            // The onstop callback has run; the parked handle (and its
+            // closures) can finally be released.
+            STOPPING.with(|s| s.borrow_mut().take());
+        });
+    });
+    recorder.set_onstop(Some(onstop.as_ref().unchecked_ref()));
+
+    recorder.start_with_time_slice(CHUNK_INTERVAL_MS)?;
+    Ok(RecorderHandle {
+        recorder,
+        stream,
+        _ondata: ondata,
+        _onstop: onstop,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_happy_path_walks_idle_recording_processing_idle() {
+        let mut phase = RecorderPhase::Idle;
+        phase = transition(phase, RecorderEvent::StartGranted);
+        assert_eq!(phase, RecorderPhase::Recording);
+        phase = transition(phase, RecorderEvent::StopPressed);
+        assert_eq!(phase, RecorderPhase::Processing);
+        phase = transition(phase, RecorderEvent::Encoded);
+        assert_eq!(phase, RecorderPhase::Idle);
+    }
+
+    #[test]
+    fn illegal_events_leave_the_phase_unchanged() {
+        assert_eq!(
+            transition(RecorderPhase::Idle, RecorderEvent::StopPressed),
+            RecorderPhase::Idle
+        );
+        assert_eq!(
+            transition(RecorderPhase::Recording, RecorderEvent::StartGranted),
+            RecorderPhase::Recording
+        );
+        assert_eq!(
+            transition(RecorderPhase::Recording, RecorderEvent::Encoded),
+            RecorderPhase::Recording
+        );
+    }
+
+    #[test]
+    fn failure_resets_every_phase_to_idle() {
+        for phase in [
+            RecorderPhase::Idle,
+            RecorderPhase::Recording,
+            RecorderPhase::Processing,
+        ] {
+            assert_eq!(transition(phase, RecorderEvent::Failed), RecorderPhase::Idle);
+        }
+    }
+
+    #[test]
+    fn chunks_concatenate_in_arrival_order() {
+        let joined = concat_chunks(&[vec![1, 2], vec![], vec![3]]);
+        assert_eq!(joined, vec![1, 2, 3]);
+        assert!(concat_chunks(&[]).is_empty());
+    }
+
+    #[test]
+    fn base64_matches_the_known_vectors() {
+        assert_eq!(encode_base64(b""), "");
+        assert_eq!(encode_base64(b"f"), "Zg==");
+        assert_eq!(encode_base64(b"fo"), "Zm8=");
+        assert_eq!(encode_base64(b"foo"), "Zm9v");
+        assert_eq!(encode_base64(b"foobar"), "Zm9vYmFy");
+    }
+}
@@ -0,0 +1,80 @@
+//! Viewport breakpoint tracking.
+//!
+//! The classification is a pure function over the width so the thresholds
+//! are testable; `use_breakpoint` binds it to the window's resize events.
+//! Components use this where CSS alone is not enough — e.g. an SVG chart
+//! that needs a concrete pixel width for its coordinate math.
+
+use yew::prelude::*;
+
+/// Phone-first thresholds, matching the `max-width` values used in the
+/// component stylesheets.
+const MEDIUM_MIN_PX: f64 = 480.0;
+const WIDE_MIN_PX: f64 = 720.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Breakpoint {
+    /// Small phones.
+    Narrow,
+    /// Large phones and small tablets.
+    Medium,
+    /// Anything at or beyond the app shell's max content width.
+    Wide,
+}
+
+pub fn breakpoint_for(width: f64) -> Breakpoint {
+    if width >= WIDE_MIN_PX {
+        Breakpoint::Wide
+    } else if width >= MEDIUM_MIN_PX {
+        Breakpoint::Medium
+    } else {
+        Breakpoint::Narrow
+    }
+}
+
+fn window_width() -> f64 {
+    web_sys::window()
+        .and_then(|w| w.inner_width().ok())
+        .and_then(|v| v.as_f64())
+        .unwrap_or(WIDE_MIN_PX)
+}
+
+/// Current breakpoint, re-rendering the component on resize crossings.
+#[hook]
+pub fn use_breakpoint() -> Breakpoint {
+    let breakpoint = use_state(|| breakpoint_for(window_width()));
+
+    {
+        let breakpoint = breakpoint.clone();
+        use_effect_with((), move |_| {
+            let listener = gloo_events::EventListener::new(
+                &web_sys::window().expect("no window"),
+                "resize",
+                move |_| {
+                    let next = breakpoint_for(window_width());
+                    if next != *breakpoint {
+                        breakpoint.set(next);
+                    }
+                },
+            );
+            move || drop(listener)
+        });
+    }
+
+    *breakpoint
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn widths_classify_onto_the_expected_breakpoints() {
+        assert_eq!(breakpoint_for(320.0), Breakpoint::Narrow);
+        assert_eq!(breakpoint_for(479.9), Breakpoint::Narrow);
+        assert_eq!(breakpoint_for(480.0), Breakpoint::Medium);
+        assert_eq!(breakpoint_for(719.9), Breakpoint::Medium);
+        assert_eq!(breakpoint_for(720.0), Breakpoint::Wide);
+        assert_eq!(breakpoint_for(1920.0), Breakpoint::Wide);
+    }
+}
@@ -0,0 +1,2 @@
+pub mod breakpoint;
+pub mod swipe;
@@ -0,0 +1,236 @@
+//! Touch swipe gesture detection with direction locking.
+//!
+//! The state machine is pure Rust over (x, y, time) samples so it can be unit
+//! tested with synthetic sequences; `use_swipe` binds it to touch events.
+//! Swipes never replace buttons — every swipe action must also be reachable
+//! through a visible control for accessibility.
+
+use yew::prelude::*;
+
+/// Minimum horizontal travel in px before a swipe counts.
+const DISTANCE_THRESHOLD: f64 = 48.0;
+/// Or: shorter but fast flicks, px/ms.
+const VELOCITY_THRESHOLD: f64 = 0.5;
+/// Once vertical travel exceeds horizontal by this factor the gesture is
+/// locked to scrolling and we stop tracking.
+const SCROLL_LOCK_RATIO: f64 = 1.2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SwipeDirection {
+    Left,
+    Right,
+}
+
+/// Tracking state across one touch interaction.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SwipeState {
+    Idle,
+    /// Touch down, direction not yet determined.
+    Tracking { start_x: f64, start_y: f64, start_ms: f64 },
+    /// Horizontal intent confirmed; vertical scroll suppressed.
+    Swiping { start_x: f64, start_ms: f64, delta_x: f64 },
+    /// Vertical scroll won; ignore until touch end.
+    ScrollLocked,
+}
+
+#[derive(Debug, Default)]
+pub struct SwipeMachine {
+    state: SwipeState,
+}
+
+impl Default for SwipeState {
+    fn default() -> Self {
+        SwipeState::Idle
+    }
+}
+
+impl SwipeMachine {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn state(&self) -> SwipeState {
+        self.state
+    }
+
+    pub fn touch_start(&mut self, x: f64, y: f64, time_ms: f64) {
+        self.state = SwipeState::Tracking {
+            start_x: x,
+            start_y: y,
+            start_ms: time_ms,
+        };
+    }
+
+    /// Feed a move sample. Returns the live horizontal delta while swiping so
+    /// the UI can translate the card under the finger.
+    pub fn touch_move(&mut self, x: f64, y: f64) -> Option<f64> {
+        match self.state {
+            SwipeState::Tracking {
+                start_x,
+                start_y,
+                start_ms,
+            } => {
+                let dx = x - start_x;
+                let dy = y - start_y;
+                if dy.abs() > SCROLL_LOCK_RATIO * dx.abs() && dy.abs() > 8.0 {
+                    self.state = SwipeState::ScrollLocked;
+                    None
+                } else if dx.abs() > 8.0 {
+                    self.state = SwipeState::Swiping {
+                        start_x,
+                        start_ms,
+                        delta_x: dx,
+                    };
+                    Some(dx)
+                } else {
+                    None
+                }
+            }
+            SwipeState::Swiping { start_x, start_ms, .. } => {
+                let dx = x - start_x;
+                self.state = SwipeState::Swiping {
+                    start_x,
+                    start_ms,
+                    delta_x: dx,
+                };
+                Some(dx)
+            }
+            _ => None,
+        }
+    }
+
+    /// Touch lifted: resolve to a completed swipe or nothing.
+    pub fn touch_end(&mut self, time_ms: f64) -> Option<SwipeDirection> {
+        let result = match self.state {
+            SwipeState::Swiping {
+                start_ms, delta_x, ..
+            } => {
+                let elapsed = (time_ms - start_ms).max(1.0);
+                let velocity = delta_x.abs() / elapsed;
+                if delta_x.abs() >= DISTANCE_THRESHOLD || velocity >= VELOCITY_THRESHOLD {
+                    Some(if delta_x < 0.0 {
+                        SwipeDirection::Left
+                    } else {
+                        SwipeDirection::Right
+                    })
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        };
+        self.state = SwipeState::Idle;
+        result
+    }
+}
+
+/// Callbacks returned by [`use_swipe`]; attach to the swipeable element.
+#[derive(Clone, PartialEq)]
+pub struct SwipeHandlers {
+    pub ontouchstart: Callback<TouchEvent>,
+    pub ontouchmove: Callback<TouchEvent>,
+    pub ontouchend: Callback<TouchEvent>,
+    /// Live horizontal offset for translate animations.
+    pub delta_x: f64,
+}
+
+fn first_touch(event: &TouchEvent) -> Option<(f64, f64)> {
+    let touch = event.touches().get(0).or_else(|| event.changed_touches().get(0))?;
+    Some((touch.client_x() as f64, touch.client_y() as f64))
+}
+
+/// Hook wiring [`SwipeMachine`] to touch events. `on_swipe` fires once per
+/// completed gesture with the resolved direction.
+#[hook]
+pub fn use_swipe(on_swipe: Callback<SwipeDirection>) -> SwipeHandlers {
+    let machine = use_mut_ref(SwipeMachine::new);
+    let delta = use_state(|| 0.0f64);
+
+    let ontouchstart = {
+        let machine = machine.clone();
+        Callback::from(move |event: TouchEvent| {
+            if let Some((x, y)) = first_touch(&event) {
+                machine.borrow_mut().touch_start(x, y, js_sys::Date::now());
+            }
+        })
+    };
+    let ontouchmove = {
+        let machine = machine.clone();
+        let delta = delta.clone();
+        Callback::from(move |event: TouchEvent| {
+            if let Some((x, y)) = first_touch(&event) {
+                if let Some(dx) = machine.borrow_mut().touch_move(x, y) {
+                    delta.set(dx);
+                }
+            }
+        })
+    };
+    let ontouchend = {
+        let machine = machine.clone();
+        let delta = delta.clone();
+        Callback::from(move |_event: TouchEvent| {
+            if let Some(direction) = machine.borrow_mut().touch_end(js_sys::Date::now()) {
+                on_swipe.emit(direction);
+            }
+            delta.set(0.0);
+        })
+    };
+
+    SwipeHandlers {
+        ontouchstart,
+        ontouchmove,
+        ontouchend,
+        delta_x: *delta,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn long_slow_swipe_left_resolves() {
+        let mut m = SwipeMachine::new();
+        m.touch_start(200.0, 100.0, 0.0);
+        m.touch_move(150.0, 102.0);
+        m.touch_move(140.0, 103.0);
+        assert_eq!(m.touch_end(400.0), Some(SwipeDirection::Left));
+        assert_eq!(m.state(), SwipeState::Idle);
+    }
+
+    #[test]
+    fn fast_short_flick_right_resolves_by_velocity() {
+        let mut m = SwipeMachine::new();
+        m.touch_start(100.0, 100.0, 0.0);
+        m.touch_move(130.0, 100.0);
+        assert_eq!(m.touch_end(40.0), Some(SwipeDirection::Right));
+    }
+
+    #[test]
+    fn short_slow_drag_is_not_a_swipe() {
+        let mut m = SwipeMachine::new();
+        m.touch_start(100.0, 100.0, 0.0);
+        m.touch_move(115.0, 100.0);
+        assert_eq!(m.touch_end(900.0), None);
+    }
+
+    #[test]
+    fn vertical_movement_locks_to_scroll() {
+        let mut m = SwipeMachine::new();
+        m.touch_start(100.0, 100.0, 0.0);
+        m.touch_move(105.0, 160.0);
+        assert_eq!(m.state(), SwipeState::ScrollLocked);
+        // Even a later horizontal move must not resurrect the gesture.
+        m.touch_move(180.0, 160.0);
+        assert_eq!(m.touch_end(300.0), None);
+    }
+
+    #[test]
+    fn horizontal_lock_keeps_tracking_despite_later_drift() {
+        let mut m = SwipeMachine::new();
+        m.touch_start(200.0, 100.0, 0.0);
+        m.touch_move(150.0, 101.0); // locks horizontal
+        m.touch_move(120.0, 140.0); // vertical drift after lock is fine
+        assert_eq!(m.touch_end(300.0), Some(SwipeDirection::Left));
+    }
+}
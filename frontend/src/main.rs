@@ -0,0 +1,33 @@
+mod components;
+mod hooks;
+mod services;
+mod simple_app;
+mod splash;
+mod styles;
+
+use simple_app::SimpleApp;
+use styles::registry::StyleRegistry;
+
+/// Mounts the Yew app. Styles are collected and injected once here rather
+/// than concatenated per component.
+pub fn run_app() {
+    let mut registry = StyleRegistry::new();
+    simple_app::collect_styles(&mut registry);
+    #[cfg(target_arch = "wasm32")]
+    {
+        web_sys::console::log_1(&registry.size_report().into());
+        styles::registry::inject(&registry);
+    }
+    #[cfg(target_arch = "wasm32")]
+    services::pwa::init();
+    #[cfg(target_arch = "wasm32")]
+    services::watchdog::start();
+    yew::Renderer::<SimpleApp>::new().render();
+    // First render scheduled: drop the static splash from index.html.
+    #[cfg(target_arch = "wasm32")]
+    splash::remove_splash();
+}
+
+fn main() {
+    run_app();
+}
@@ -0,0 +1,204 @@
+//! Minimal EXIF reader: just enough JPEG/TIFF walking to pull out the
+//! capture time (`DateTimeOriginal`) client-side.
+//!
+//! Users sometimes upload month-old photos and expect current advice, so the
+//! upload flow shows the capture date and warns when it is stale. Anything
+//! unexpected in the bytes — not a JPEG, no APP1 segment, truncated IFDs —
+//! yields `None` and the UI silently skips the warning.
+
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+
+/// Photos older than this many days trigger the stale warning.
+pub const DEFAULT_STALE_DAYS: i64 = 14;
+
+const TAG_EXIF_IFD: u16 = 0x8769;
+const TAG_DATE_TIME_ORIGINAL: u16 = 0x9003;
+const TAG_DATE_TIME: u16 = 0x0132;
+
+/// Bounds-checked reader over the TIFF section, in its declared byte order.
+struct Tiff<'a> {
+    bytes: &'a [u8],
+    little_endian: bool,
+}
+
+impl<'a> Tiff<'a> {
+    fn u16_at(&self, offset: usize) -> Option<u16> {
+        let b = self.bytes.get(offset..offset + 2)?;
+        Some(if self.little_endian {
+            u16::from_le_bytes([b[0], b[1]])
+        } else {
+            u16::from_be_bytes([b[0], b[1]])
+        })
+    }
+
+    fn u32_at(&self, offset: usize) -> Option<u32> {
+        let b = self.bytes.get(offset..offset + 4)?;
+        let b = [b[0], b[1], b[2], b[3]];
+        Some(if self.little_endian {
+            u32::from_le_bytes(b)
+        } else {
+            u32::from_be_bytes(b)
+        })
+    }
+
+    /// Value of an ASCII tag in the IFD at `ifd_offset`, if present.
+    fn ascii_tag(&self, ifd_offset: usize, tag: u16) -> Option<String> {
+        let entry = self.find_entry(ifd_offset, tag)?;
+        let count = self.u32_at(entry + 4)? as usize;
+        // ASCII values longer than 4 bytes live at an offset; capture
+        // timestamps always are ("YYYY:MM:DD HH:MM:SS\0" is 20 bytes).
+        let value_offset = self.u32_at(entry + 8)? as usize;
+        let raw = self.bytes.get(value_offset..value_offset + count)?;
+        let text: String = raw
+            .iter()
+            .take_while(|b| **b != 0)
+            .map(|b| *b as char)
+            .collect();
+        Some(text)
+    }
+
+    fn u32_tag(&self, ifd_offset: usize, tag: u16) -> Option<u32> {
+        let entry = self.find_entry(ifd_offset, tag)?;
+        self.u32_at(entry + 8)
+    }
+
+    /// Offset of the 12-byte entry for `tag` in the IFD at `ifd_offset`.
+    fn find_entry(&self, ifd_offset: usize, tag: u16) -> Option<usize> {
+        let count = self.u16_at(ifd_offset)? as usize;
+        for i in 0..count {
+            let entry = ifd_offset + 2 + i * 12;
+            if self.u16_at(entry)? == tag {
+                return Some(entry);
+            }
+        }
+        None
+    }
+}
+
+/// Locate the TIFF section inside a JPEG's APP1 (Exif) segment.
+fn tiff_section(bytes: &[u8]) -> Option<Tiff<'_>> {
+    // SOI marker, or it isn't a JPEG at all.
+    if bytes.get(..2)? != [0xFF, 0xD8] {
+        return None;
+    }
+    let mut offset = 2;
+    loop {
+        if *bytes.get(offset)? != 0xFF {
+            return None;
+        }
+        let marker = *bytes.get(offset + 1)?;
+        let length = u16::from_be_bytes([*bytes.get(offset + 2)?, *bytes.get(offset + 3)?]) as usize;
+        if marker == 0xE1 && bytes.get(offset + 4..offset + 10)? == b"Exif\0\0" {
+            let tiff = bytes.get(offset + 10..offset + 2 + length)?;
+            let little_endian = match tiff.get(..2)? {
+                b"II" => true,
+                b"MM" => false,
+                _ => return None,
+            };
+            return Some(Tiff {
+                bytes: tiff,
+                little_endian,
+            });
+        }
+        if marker == 0xDA {
+            return None; // image data starts; no EXIF found
+        }
+        offset += 2 + length;
+    }
+}
+
+/// Capture time from EXIF `DateTimeOriginal` (falling back to `DateTime`).
+/// EXIF timestamps carry no zone; they are treated as UTC, which is close
+/// enough for a staleness check measured in days.
+pub fn capture_time(bytes: &[u8]) -> Option<DateTime<Utc>> {
+    let tiff = tiff_section(bytes)?;
+    let ifd0 = tiff.u32_at(4)? as usize;
+    let timestamp = tiff
+        .u32_tag(ifd0, TAG_EXIF_IFD)
+        .and_then(|exif_ifd| tiff.ascii_tag(exif_ifd as usize, TAG_DATE_TIME_ORIGINAL))
+        .or_else(|| tiff.ascii_tag(ifd0, TAG_DATE_TIME))?;
+    NaiveDateTime::parse_from_str(&timestamp, "%Y:%m:%d %H:%M:%S")
+        .ok()
+        .map(|naive| naive.and_utc())
+}
+
+pub fn is_stale(captured: DateTime<Utc>, now: DateTime<Utc>, threshold_days: i64) -> bool {
+    now.signed_duration_since(captured) > Duration::days(threshold_days)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Build a minimal little-endian EXIF JPEG whose DateTimeOriginal is the
+    /// given string.
+    fn exif_jpeg(timestamp: &str) -> Vec<u8> {
+        let mut tiff = Vec::new();
+        tiff.extend_from_slice(b"II");
+        tiff.extend_from_slice(&42u16.to_le_bytes());
+        tiff.extend_from_slice(&8u32.to_le_bytes()); // IFD0 at 8
+
+        // IFD0: one entry pointing at the Exif sub-IFD (at 26).
+        tiff.extend_from_slice(&1u16.to_le_bytes());
+        tiff.extend_from_slice(&TAG_EXIF_IFD.to_le_bytes());
+        tiff.extend_from_slice(&4u16.to_le_bytes()); // LONG
+        tiff.extend_from_slice(&1u32.to_le_bytes());
+        tiff.extend_from_slice(&26u32.to_le_bytes());
+        tiff.extend_from_slice(&0u32.to_le_bytes()); // next IFD
+
+        // Exif sub-IFD at 26: one ASCII entry whose value sits at 44.
+        tiff.extend_from_slice(&1u16.to_le_bytes());
+        tiff.extend_from_slice(&TAG_DATE_TIME_ORIGINAL.to_le_bytes());
+        tiff.extend_from_slice(&2u16.to_le_bytes()); // ASCII
+        tiff.extend_from_slice(&20u32.to_le_bytes());
+        tiff.extend_from_slice(&44u32.to_le_bytes());
+        tiff.extend_from_slice(&0u32.to_le_bytes());
+
+        tiff.extend_from_slice(timestamp.as_bytes());
+        tiff.push(0);
+
+        let mut jpeg = vec![0xFF, 0xD8, 0xFF, 0xE1];
+        let length = (2 + 6 + tiff.len()) as u16;
+        jpeg.extend_from_slice(&length.to_be_bytes());
+        jpeg.extend_from_slice(b"Exif\0\0");
+        jpeg.extend_from_slice(&tiff);
+        jpeg
+    }
+
+    #[test]
+    fn parses_date_time_original() {
+        let jpeg = exif_jpeg("2026:08:01 10:30:00");
+        let captured = capture_time(&jpeg).unwrap();
+        assert_eq!(captured.to_rfc3339(), "2026-08-01T10:30:00+00:00");
+    }
+
+    #[test]
+    fn corrupted_exif_yields_none() {
+        let mut jpeg = exif_jpeg("2026:08:01 10:30:00");
+        let truncated = &jpeg[..20];
+        assert_eq!(capture_time(truncated), None);
+        // Garble the TIFF byte-order marker.
+        jpeg[12] = 0xFF;
+        jpeg[13] = 0xFF;
+        assert_eq!(capture_time(&jpeg), None);
+    }
+
+    #[test]
+    fn non_jpeg_and_exifless_bytes_yield_none() {
+        assert_eq!(capture_time(b"PNG not jpeg"), None);
+        assert_eq!(capture_time(&[0xFF, 0xD8, 0xFF, 0xDA, 0x00, 0x02]), None);
+    }
+
+    #[test]
+    fn garbled_timestamp_yields_none() {
+        let jpeg = exif_jpeg("yesterday, probably");
+        assert_eq!(capture_time(&jpeg), None);
+    }
+
+    #[test]
+    fn staleness_uses_the_threshold() {
+        let now = Utc::now();
+        assert!(is_stale(now - Duration::days(15), now, DEFAULT_STALE_DAYS));
+        assert!(!is_stale(now - Duration::days(13), now, DEFAULT_STALE_DAYS));
+    }
+}
@@ -0,0 +1,63 @@
+//! SSE subscription for vision job status, replacing the old polling loop.
+//!
+//! The gateway streams `status_update` events from
+//! `/api/v1/vision/jobs/:job_id/stream` and closes the connection once the
+//! job is terminal, so the browser's `EventSource` needs no retry loop of
+//! its own.
+
+use wasm_bindgen::{closure::Closure, JsCast};
+use web_sys::{EventSource, MessageEvent};
+use yew::Callback;
+
+pub fn stream_url(job_id: &str) -> String {
+    format!("/api/v1/vision/jobs/{job_id}/stream")
+}
+
+/// Keeps the `EventSource` and its handlers alive; dropping it closes the
+/// stream.
+pub struct JobStatusSubscription {
+    source: EventSource,
+    _on_update: Closure<dyn FnMut(MessageEvent)>,
+    _watchdog: crate::services::watchdog::ListenerGuard,
+}
+
+impl Drop for JobStatusSubscription {
+    fn drop(&mut self) {
+        self.source.close();
+    }
+}
+
+/// Subscribe to a job's status stream; `on_update` receives each raw
+/// `status_update` payload (JSON, as published by the worker).
+pub fn subscribe_job_status(
+    job_id: &str,
+    on_update: Callback<String>,
+) -> Result<JobStatusSubscription, String> {
+    let source =
+        EventSource::new(&stream_url(job_id)).map_err(|e| format!("open event source: {e:?}"))?;
+
+    let handler = Closure::<dyn FnMut(MessageEvent)>::new(move |event: MessageEvent| {
+        if let Some(data) = event.data().as_string() {
+            on_update.emit(data);
+        }
+    });
+    source
+        .add_event_listener_with_callback("status_update", handler.as_ref().unchecked_ref())
+        .map_err(|e| format!("attach listener: {e:?}"))?;
+
+    Ok(JobStatusSubscription {
+        source,
+        _on_update: handler,
+        _watchdog: crate::services::watchdog::track("job_status_sse"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stream_url_targets_the_job() {
+        assert_eq!(stream_url("abc"), "/api/v1/vision/jobs/abc/stream");
+    }
+}
@@ -0,0 +1,8 @@
+pub mod api;
+pub mod exif;
+pub mod job_stream;
+pub mod offline_store;
+pub mod preferences;
+pub mod pwa;
+pub mod version;
+pub mod watchdog;
@@ -0,0 +1,271 @@
+//! Offline analysis queue backed by IndexedDB.
+//!
+//! A farmer in a field often has no signal at exactly the moment they
+//! photograph a diseased leaf. Instead of a dead error screen, a submit
+//! that fails while the browser reports itself offline is parked here
+//! (image bytes and crop type, keyed by a fresh id) and replayed by the
+//! `NetworkStatusBanner` once connectivity returns. IndexedDB rather than
+//! local storage because the payloads are whole base64 images, well past
+//! local storage's practical size limits.
+//!
+//! Values are stored as JSON strings — the same serde models as the wire
+//! format — so the schema needs no IndexedDB-side migration story beyond
+//! the object store itself.
+
+use indexed_db_futures::prelude::*;
+use serde::{Deserialize, Serialize};
+use shared::models::CropType;
+use wasm_bindgen::JsValue;
+
+use crate::services::api::{ApiService, FlowPhase};
+
+const DB_NAME: &str = "plant_ai_offline";
+const DB_VERSION: u32 = 1;
+const STORE_NAME: &str = "pending_analyses";
+
+/// One analysis waiting for connectivity.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PendingAnalysis {
+    pub id: String,
+    pub image_base64: String,
+    pub crop_type: CropType,
+    /// RFC 3339; shown in the banner so the farmer knows what's queued.
+    pub queued_at: String,
+}
+
+/// Does the browser believe it has connectivity? `onLine == false` is
+/// trustworthy (definitely offline); `true` only means "not provably
+/// offline", which is why submits still run and only fall back here.
+pub fn is_online() -> bool {
+    web_sys::window()
+        .map(|w| w.navigator().on_line())
+        .unwrap_or(true)
+}
+
+fn db_error(context: &str, e: impl std::fmt::Debug) -> String {
+    format!("{context}: {e:?}")
+}
+
+async fn open_db() -> Result<IdbDatabase, String> {
+    let mut request = IdbDatabase::open_u32(DB_NAME, DB_VERSION)
+        .map_err(|e| db_error("open offline db", e))?;
+    request.set_on_upgrade_needed(Some(
+        |event: &IdbVersionChangeEvent| -> Result<(), JsValue> {
+            if !event.db().object_store_names().any(|name| name == STORE_NAME) {
+                event.db().create_object_store(STORE_NAME)?;
+            }
+            Ok(())
+        },
+    ));
+    request.await.map_err(|e| db_error("open offline db", e))
+}
+
+/// Queue one analysis for later submission; returns its id.
+pub async fn save_pending_analysis(
+    image_base64: String,
+    crop_type: CropType,
+) -> Result<String, String> {
+    let pending = PendingAnalysis {
+        id: uuid::Uuid::new_v4().to_string(),
+        image_base64,
+        crop_type,
+        queued_at: chrono::Utc::now().to_rfc3339(),
+    };
+    let json = serde_json::to_string(&pending).map_err(|e| db_error("encode pending", e))?;
+
+    let db = open_db().await?;
+    let tx = db
+        .transaction_on_one_with_mode(STORE_NAME, IdbTransactionMode::Readwrite)
+        .map_err(|e| db_error("open tx", e))?;
+    tx.object_store(STORE_NAME)
+        .map_err(|e| db_error("open store", e))?
+        .put_key_val_owned(JsValue::from_str(&pending.id), &JsValue::from_str(&json))
+        .map_err(|e| db_error("put pending", e))?;
+    tx.await.into_result().map_err(|e| db_error("commit", e))?;
+    Ok(pending.id)
+}
+
+/// Everything currently queued, oldest first.
+pub async fn get_pending_analyses() -> Result<Vec<PendingAnalysis>, String> {
+    let db = open_db().await?;
+    let tx = db
+        .transaction_on_one(STORE_NAME)
+        .map_err(|e| db_error("open tx", e))?;
+    let values = tx
+        .object_store(STORE_NAME)
+        .map_err(|e| db_error("open store", e))?
+        .get_all()
+        .map_err(|e| db_error("read pending", e))?
+        .await
+        .map_err(|e| db_error("read pending", e))?;
+
+    let mut pending: Vec<PendingAnalysis> = values
+        .into_iter()
+        .filter_map(|value| value.as_string())
+        .filter_map(|json| serde_json::from_str(&json).ok())
+        .collect();
+    pending.sort_by(|a, b| a.queued_at.cmp(&b.queued_at));
+    Ok(pending)
+}
+
+/// Drop one queued analysis (after a successful replay).
+pub async fn remove_pending_analysis(id: &str) -> Result<(), String> {
+    let db = open_db().await?;
+    let tx = db
+        .transaction_on_one_with_mode(STORE_NAME, IdbTransactionMode::Readwrite)
+        .map_err(|e| db_error("open tx", e))?;
+    tx.object_store(STORE_NAME)
+        .map_err(|e| db_error("open store", e))?
+        .delete_owned(JsValue::from_str(id))
+        .map_err(|e| db_error("delete pending", e))?;
+    tx.await.into_result().map_err(|e| db_error("commit", e))
+}
+
+/// Submit the flow, or park it in the offline queue. `online` is the
+/// caller's `is_online()` reading, threaded as a parameter so scenarios
+/// can script both sides. A provably-offline device skips the doomed
+/// request entirely; an "online" device that still fails keeps the
+/// normal `Failed` phase — that's a server problem, not a signal problem.
+pub async fn submit_or_queue(
+    flow: &mut crate::services::api::AnalysisFlow,
+    api: &ApiService,
+    online: bool,
+) {
+    if online {
+        flow.submit(api).await;
+        return;
+    }
+    let (Some(crop), Some(image)) = (flow.crop_type.clone(), flow.image_base64.clone()) else {
+        return;
+    };
+    let crop_type = CropType::ALL
+        .iter()
+        .copied()
+        .find(|c| c.as_str() == crop)
+        .unwrap_or(CropType::Rice);
+    match save_pending_analysis(image, crop_type).await {
+        Ok(_) => flow.phase = FlowPhase::QueuedOffline,
+        Err(message) => flow.phase = FlowPhase::Failed { message },
+    }
+}
+
+/// Replay every queued analysis through the gateway, removing each on
+/// success. Stops at the first failure (likely still offline) and
+/// reports how many were flushed either way.
+pub async fn flush_pending(api: &ApiService) -> Result<usize, String> {
+    let pending = get_pending_analyses().await?;
+    let mut flushed = 0;
+    for analysis in pending {
+        if api
+            .submit_analysis(analysis.crop_type.as_str(), &analysis.image_base64)
+            .await
+            .is_err()
+        {
+            break;
+        }
+        remove_pending_analysis(&analysis.id).await?;
+        flushed += 1;
+    }
+    Ok(flushed)
+}
+
+#[cfg(test)]
+#[cfg(target_arch = "wasm32")]
+mod wasm_tests {
+    use std::{cell::RefCell, collections::VecDeque, rc::Rc};
+
+    use wasm_bindgen_test::*;
+
+    use super::*;
+    use crate::services::api::{
+        AnalysisFlow, ApiRequest, ApiResponse, ApiTransport, TransportError, TransportFuture,
+    };
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[derive(Default)]
+    struct MockTransport {
+        script: RefCell<VecDeque<Result<ApiResponse, TransportError>>>,
+        seen: RefCell<Vec<String>>,
+    }
+
+    impl ApiTransport for MockTransport {
+        fn send(&self, request: ApiRequest) -> TransportFuture<'_> {
+            self.seen.borrow_mut().push(request.path);
+            let next = self
+                .script
+                .borrow_mut()
+                .pop_front()
+                .expect("scenario script exhausted");
+            Box::pin(async move { next })
+        }
+    }
+
+    async fn clear_store() {
+        for pending in get_pending_analyses().await.unwrap() {
+            remove_pending_analysis(&pending.id).await.unwrap();
+        }
+    }
+
+    #[wasm_bindgen_test]
+    async fn pending_analyses_round_trip_through_indexeddb() {
+        clear_store().await;
+
+        let id = save_pending_analysis("img-bytes".into(), CropType::Durian)
+            .await
+            .unwrap();
+        let pending = get_pending_analyses().await.unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].id, id);
+        assert_eq!(pending[0].image_base64, "img-bytes");
+        assert_eq!(pending[0].crop_type, CropType::Durian);
+
+        remove_pending_analysis(&id).await.unwrap();
+        assert!(get_pending_analyses().await.unwrap().is_empty());
+    }
+
+    #[wasm_bindgen_test]
+    async fn offline_submit_parks_the_analysis_instead_of_failing() {
+        clear_store().await;
+
+        // An empty script: any request would panic, proving none is made.
+        let api = ApiService::with_transport(Rc::new(MockTransport::default()));
+        let mut flow = AnalysisFlow::new();
+        flow.select_image("offline-image");
+        flow.set_crop_type("rice");
+
+        submit_or_queue(&mut flow, &api, false).await;
+        assert_eq!(flow.phase, FlowPhase::QueuedOffline);
+        let pending = get_pending_analyses().await.unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].crop_type, CropType::Rice);
+
+        clear_store().await;
+    }
+
+    #[wasm_bindgen_test]
+    async fn flush_replays_queued_analyses_and_stops_on_failure() {
+        clear_store().await;
+        save_pending_analysis("first".into(), CropType::Rice).await.unwrap();
+        save_pending_analysis("second".into(), CropType::Mango).await.unwrap();
+
+        // First replay succeeds, second hits a dead network: one flushed,
+        // one still queued for the next attempt.
+        let mock = Rc::new(MockTransport::default());
+        mock.script.borrow_mut().push_back(Ok(ApiResponse {
+            status: 200,
+            body: serde_json::json!({ "success": true, "data": { "job_id": "j1" } }),
+        }));
+        mock.script
+            .borrow_mut()
+            .push_back(Err(TransportError::Network("still offline".into())));
+        let api = ApiService::with_transport(mock);
+
+        assert_eq!(flush_pending(&api).await.unwrap(), 1);
+        let pending = get_pending_analyses().await.unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].image_base64, "second");
+
+        clear_store().await;
+    }
+}
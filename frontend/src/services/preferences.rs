@@ -0,0 +1,123 @@
+//! Per-user analyze-form preferences: last used crop and recent queries.
+//!
+//! Stored in local storage for everyone; authenticated users also sync the
+//! same document through the profile API, and the server copy wins so
+//! preferences follow the user across devices. Clearing site data resets the
+//! anonymous state.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use shared::models::CropType;
+
+/// How many distinct recent queries are kept per crop.
+pub const RECENT_QUERIES_PER_CROP: usize = 3;
+
+const STORAGE_KEY: &str = "analyze_preferences";
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AnalyzePreferences {
+    /// Pre-selected on the analyze form.
+    pub last_crop: Option<CropType>,
+    /// Most-recent-first distinct queries, keyed by crop (as `as_str`).
+    #[serde(default)]
+    pub recent_queries: BTreeMap<String, Vec<String>>,
+}
+
+impl AnalyzePreferences {
+    /// Record a submitted query: moves duplicates to the front (LRU) and
+    /// truncates to [`RECENT_QUERIES_PER_CROP`]. Blank queries are ignored.
+    pub fn record_query(&mut self, crop: CropType, query: &str) {
+        let query = query.trim();
+        if query.is_empty() {
+            return;
+        }
+        self.last_crop = Some(crop);
+        let entries = self.recent_queries.entry(crop.as_str().to_string()).or_default();
+        entries.retain(|q| q != query);
+        entries.insert(0, query.to_string());
+        entries.truncate(RECENT_QUERIES_PER_CROP);
+    }
+
+    pub fn suggestions_for(&self, crop: CropType) -> &[String] {
+        self.recent_queries
+            .get(crop.as_str())
+            .map(Vec::as_slice)
+            .unwrap_or(&[])
+    }
+
+    /// Resolve local vs server copies: the server document wins wholesale
+    /// when present (it is the cross-device source of truth); otherwise keep
+    /// local.
+    pub fn resolve(server: Option<AnalyzePreferences>, local: AnalyzePreferences) -> Self {
+        server.unwrap_or(local)
+    }
+}
+
+/// Load from local storage; missing or corrupt data yields defaults.
+pub fn load_local() -> AnalyzePreferences {
+    use gloo_storage::{LocalStorage, Storage};
+    LocalStorage::get(STORAGE_KEY).unwrap_or_default()
+}
+
+pub fn save_local(prefs: &AnalyzePreferences) {
+    use gloo_storage::{LocalStorage, Storage};
+    if let Err(e) = LocalStorage::set(STORAGE_KEY, prefs) {
+        gloo_console::warn!("failed to persist preferences:", e.to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recent_queries_are_distinct_lru_capped_at_three() {
+        let mut prefs = AnalyzePreferences::default();
+        prefs.record_query(CropType::Rice, "ใบจุด");
+        prefs.record_query(CropType::Rice, "ใบไหม้");
+        prefs.record_query(CropType::Rice, "ราน้ำค้าง");
+        prefs.record_query(CropType::Rice, "ใบจุด"); // dedupe: moves to front
+        assert_eq!(
+            prefs.suggestions_for(CropType::Rice),
+            ["ใบจุด", "ราน้ำค้าง", "ใบไหม้"]
+        );
+        prefs.record_query(CropType::Rice, "หนอนกอ"); // evicts the oldest
+        assert_eq!(
+            prefs.suggestions_for(CropType::Rice),
+            ["หนอนกอ", "ใบจุด", "ราน้ำค้าง"]
+        );
+    }
+
+    #[test]
+    fn queries_are_scoped_per_crop() {
+        let mut prefs = AnalyzePreferences::default();
+        prefs.record_query(CropType::Rice, "ใบไหม้");
+        prefs.record_query(CropType::Durian, "รากเน่า");
+        assert_eq!(prefs.suggestions_for(CropType::Rice), ["ใบไหม้"]);
+        assert_eq!(prefs.suggestions_for(CropType::Durian), ["รากเน่า"]);
+        assert_eq!(prefs.last_crop, Some(CropType::Durian));
+    }
+
+    #[test]
+    fn blank_queries_are_ignored() {
+        let mut prefs = AnalyzePreferences::default();
+        prefs.record_query(CropType::Rice, "   ");
+        assert!(prefs.suggestions_for(CropType::Rice).is_empty());
+        assert_eq!(prefs.last_crop, None);
+    }
+
+    #[test]
+    fn server_copy_takes_precedence_when_present() {
+        let mut local = AnalyzePreferences::default();
+        local.record_query(CropType::Rice, "local query");
+        let mut server = AnalyzePreferences::default();
+        server.record_query(CropType::Mango, "server query");
+
+        let resolved = AnalyzePreferences::resolve(Some(server.clone()), local.clone());
+        assert_eq!(resolved, server);
+
+        let resolved = AnalyzePreferences::resolve(None, local.clone());
+        assert_eq!(resolved, local);
+    }
+}
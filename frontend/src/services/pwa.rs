@@ -0,0 +1,174 @@
+//! Progressive web app plumbing: manifest injection, service worker
+//! registration, and the install prompt.
+//!
+//! The manifest and worker live as static files (`static/manifest.json`,
+//! `static/sw.js`, copied to the site root by Trunk); this module wires
+//! them up at startup and brokers the `beforeinstallprompt` event, which
+//! the browser fires once and which must be stashed until the user taps
+//! the "Add to Home Screen" button.
+
+use yew::Callback;
+
+/// The manifest as shipped, so tests validate the real file rather than a
+/// copy of it.
+pub const MANIFEST_JSON: &str = include_str!("../../static/manifest.json");
+
+#[cfg(target_arch = "wasm32")]
+mod install {
+    use std::cell::RefCell;
+
+    use wasm_bindgen::{prelude::*, JsCast};
+    use yew::Callback;
+
+    use crate::services::watchdog;
+
+    /// The stashed `beforeinstallprompt` event plus the listener keeping it
+    /// coming. The listener is intentionally app-lifetime — parked here
+    /// rather than forgotten, with a watchdog guard so it still shows up in
+    /// the live-listener count.
+    struct InstallState {
+        prompt: Option<web_sys::Event>,
+        on_available: Option<Callback<()>>,
+        _listener: Option<(Closure<dyn FnMut(web_sys::Event)>, watchdog::ListenerGuard)>,
+    }
+
+    thread_local! {
+        static INSTALL: RefCell<InstallState> = const {
+            RefCell::new(InstallState { prompt: None, on_available: None, _listener: None })
+        };
+    }
+
+    /// Inject `<link rel="manifest">` into the document head. Done from the
+    /// app rather than hardcoded in `index.html` so the tag exists exactly
+    /// when the app that can answer the install prompt does.
+    pub(super) fn inject_manifest_link() {
+        let Some(document) = web_sys::window().and_then(|w| w.document()) else {
+            return;
+        };
+        let Some(head) = document.head() else { return };
+        let Ok(link) = document.create_element("link") else {
+            return;
+        };
+        let _ = link.set_attribute("rel", "manifest");
+        let _ = link.set_attribute("href", "/manifest.json");
+        let _ = head.append_child(&link);
+    }
+
+    /// Register the service worker. Fire-and-forget: an unsupported browser
+    /// or a failed registration just means no offline cache.
+    pub(super) fn register_service_worker() {
+        let Some(window) = web_sys::window() else { return };
+        let _ = window.navigator().service_worker().register("/sw.js");
+    }
+
+    /// Listen for `beforeinstallprompt`, suppress the browser's own banner,
+    /// and stash the event for [`show_install_prompt`].
+    pub(super) fn listen_for_install_prompt() {
+        let Some(window) = web_sys::window() else { return };
+        let listener = Closure::<dyn FnMut(web_sys::Event)>::new(move |event: web_sys::Event| {
+            event.prevent_default();
+            INSTALL.with(|state| {
+                let mut state = state.borrow_mut();
+                state.prompt = Some(event);
+                if let Some(callback) = &state.on_available {
+                    callback.emit(());
+                }
+            });
+        });
+        if window
+            .add_event_listener_with_callback("beforeinstallprompt", listener.as_ref().unchecked_ref())
+            .is_ok()
+        {
+            INSTALL.with(|state| {
+                state.borrow_mut()._listener =
+                    Some((listener, watchdog::track("beforeinstallprompt")));
+            });
+        }
+    }
+
+    pub(super) fn on_install_available(callback: Callback<()>) {
+        INSTALL.with(|state| {
+            let mut state = state.borrow_mut();
+            // The event may have fired before the app mounted.
+            if state.prompt.is_some() {
+                callback.emit(());
+            }
+            state.on_available = Some(callback);
+        });
+    }
+
+    pub(super) fn show_install_prompt() {
+        let Some(event) = INSTALL.with(|state| state.borrow_mut().prompt.take()) else {
+            return;
+        };
+        // `prompt()` has no typed binding — `BeforeInstallPromptEvent` is
+        // not in web-sys — so it goes through Reflect.
+        if let Ok(prompt) = js_sys::Reflect::get(&event, &"prompt".into()) {
+            if let Ok(prompt) = prompt.dyn_into::<js_sys::Function>() {
+                let _ = prompt.call0(&event);
+            }
+        }
+    }
+}
+
+/// Wire up the PWA at startup: manifest link, service worker, and the
+/// install-prompt listener. Called once from `run_app`.
+#[cfg(target_arch = "wasm32")]
+pub fn init() {
+    install::inject_manifest_link();
+    install::register_service_worker();
+    install::listen_for_install_prompt();
+}
+
+/// Run `callback` when a deferred install prompt is (or already was)
+/// available, so the shell can show its "Add to Home Screen" button.
+pub fn on_install_available(callback: Callback<()>) {
+    #[cfg(target_arch = "wasm32")]
+    install::on_install_available(callback);
+    #[cfg(not(target_arch = "wasm32"))]
+    let _ = callback;
+}
+
+/// Show the browser's install prompt, consuming the stashed event — the
+/// browser only honours it once.
+pub fn show_install_prompt() {
+    #[cfg(target_arch = "wasm32")]
+    install::show_install_prompt();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::styles::PrimaryColors;
+
+    #[test]
+    fn the_manifest_is_valid_and_installable() {
+        let manifest: serde_json::Value =
+            serde_json::from_str(MANIFEST_JSON).expect("manifest.json must parse");
+        assert!(!manifest["name"].as_str().unwrap().is_empty());
+        assert!(!manifest["short_name"].as_str().unwrap().is_empty());
+        assert_eq!(manifest["start_url"], "/");
+        assert_eq!(manifest["display"], "standalone");
+        assert!(manifest["background_color"].is_string());
+    }
+
+    #[test]
+    fn the_theme_color_matches_the_app_palette() {
+        let manifest: serde_json::Value = serde_json::from_str(MANIFEST_JSON).unwrap();
+        assert_eq!(manifest["theme_color"], PrimaryColors::ELECTRIC_BLUE);
+    }
+
+    #[test]
+    fn both_required_icon_sizes_are_declared_as_png() {
+        let manifest: serde_json::Value = serde_json::from_str(MANIFEST_JSON).unwrap();
+        let icons = manifest["icons"].as_array().unwrap();
+        for size in ["192x192", "512x512"] {
+            let icon = icons
+                .iter()
+                .find(|icon| icon["sizes"] == size)
+                .unwrap_or_else(|| panic!("manifest is missing the {size} icon"));
+            assert_eq!(icon["type"], "image/png");
+            assert!(icon["src"].as_str().unwrap().ends_with(".png"));
+        }
+    }
+}
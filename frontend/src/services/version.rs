@@ -0,0 +1,115 @@
+//! Build-version awareness: detect when the running wasm bundle is older
+//! than what the gateway expects and decide how loudly to tell the user.
+
+use serde::Deserialize;
+
+/// Version of the bundle currently running, embedded at compile time.
+pub const BUILD_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Payload of `GET /api/v1/version`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct VersionInfo {
+    pub api_version: String,
+    pub min_frontend_version: String,
+    pub latest_frontend_version: String,
+    /// Bundles older than this cannot talk to the API at all.
+    pub incompatible_below: String,
+}
+
+/// How the running bundle relates to what the server expects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VersionStatus {
+    /// Running at least `min_frontend_version`: no UI.
+    Current,
+    /// Older than `min_frontend_version` but still works: reload banner.
+    Outdated,
+    /// Below `incompatible_below`: blocking modal.
+    Incompatible,
+}
+
+/// `major.minor.patch` triple; anything after a `-` or `+` is ignored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SemVer(pub u32, pub u32, pub u32);
+
+impl SemVer {
+    pub fn parse(raw: &str) -> Option<Self> {
+        let core = raw
+            .trim()
+            .trim_start_matches('v')
+            .split(['-', '+'])
+            .next()?;
+        let mut parts = core.split('.');
+        let major = parts.next()?.parse().ok()?;
+        let minor = parts.next().unwrap_or("0").parse().ok()?;
+        let patch = parts.next().unwrap_or("0").parse().ok()?;
+        Some(Self(major, minor, patch))
+    }
+}
+
+/// Classify `running` against the server's expectations. Unparseable
+/// versions are treated as current so a malformed server response can never
+/// lock users out.
+pub fn classify(running: &str, info: &VersionInfo) -> VersionStatus {
+    let (Some(running), Some(min), Some(incompatible)) = (
+        SemVer::parse(running),
+        SemVer::parse(&info.min_frontend_version),
+        SemVer::parse(&info.incompatible_below),
+    ) else {
+        return VersionStatus::Current;
+    };
+    if running < incompatible {
+        VersionStatus::Incompatible
+    } else if running < min {
+        VersionStatus::Outdated
+    } else {
+        VersionStatus::Current
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(min: &str, incompatible: &str) -> VersionInfo {
+        VersionInfo {
+            api_version: "1".into(),
+            min_frontend_version: min.into(),
+            latest_frontend_version: min.into(),
+            incompatible_below: incompatible.into(),
+        }
+    }
+
+    #[test]
+    fn compatible_version_is_current() {
+        assert_eq!(classify("1.4.0", &info("1.4.0", "1.0.0")), VersionStatus::Current);
+        assert_eq!(classify("2.0.0", &info("1.4.0", "1.0.0")), VersionStatus::Current);
+    }
+
+    #[test]
+    fn older_than_min_is_outdated() {
+        assert_eq!(classify("1.3.9", &info("1.4.0", "1.0.0")), VersionStatus::Outdated);
+    }
+
+    #[test]
+    fn below_incompatible_floor_is_incompatible() {
+        assert_eq!(
+            classify("0.9.2", &info("1.4.0", "1.0.0")),
+            VersionStatus::Incompatible
+        );
+    }
+
+    #[test]
+    fn comparison_is_numeric_not_lexicographic() {
+        assert_eq!(classify("1.10.0", &info("1.9.0", "1.2.0")), VersionStatus::Current);
+    }
+
+    #[test]
+    fn prerelease_and_build_metadata_are_ignored() {
+        assert_eq!(SemVer::parse("1.4.0-rc.1+build5"), Some(SemVer(1, 4, 0)));
+    }
+
+    #[test]
+    fn unparseable_server_versions_fail_open() {
+        assert_eq!(classify("1.0.0", &info("garbage", "1.0.0")), VersionStatus::Current);
+    }
+}
@@ -0,0 +1,246 @@
+//! Dev-mode memory and listener watchdog for long-lived kiosk sessions.
+//!
+//! Demo kiosks run the app for days, so a leaked `Closure` or event
+//! listener that would be invisible in a quick demo slowly degrades them.
+//! The rule in this codebase is: never `Closure::forget` — closures live
+//! in a struct that removes them on `Drop` (see
+//! [`crate::services::job_stream::JobStatusSubscription`]) or in a
+//! `use_effect` cleanup. This module makes violations visible:
+//!
+//! * every long-lived listener registers a [`ListenerGuard`], so the live
+//!   count is observable at runtime and in tests;
+//! * in debug builds [`start`] samples `performance.memory` (Chrome only —
+//!   it's non-standard) and the listener count every minute and warns when
+//!   both grow across consecutive samples.
+//!
+//! Leak test procedure: build with `trunk serve` (debug), open the kiosk
+//! flow, leave it cycling for 30+ minutes, and watch the console — the
+//! watchdog logs one line per sample and a warning on sustained growth.
+//! For a targeted check, `wasm-pack test --headless --chrome` runs the
+//! mount/unmount test below, which fails if a component stops cleaning up.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Live long-lived listeners/subscriptions. Guards increment on creation
+/// and decrement on drop, so a stable UI has a stable count.
+static LIVE_LISTENERS: AtomicUsize = AtomicUsize::new(0);
+
+/// How often the dev watchdog samples, in milliseconds.
+const SAMPLE_INTERVAL_MS: u32 = 60_000;
+
+/// Consecutive growing samples before the watchdog warns; one minute of
+/// growth is normal churn, five in a row is a trend.
+const GROWTH_ALARM_SAMPLES: usize = 5;
+
+pub fn live_listener_count() -> usize {
+    LIVE_LISTENERS.load(Ordering::Relaxed)
+}
+
+/// RAII registration for a long-lived listener or subscription. Hold it
+/// next to the closure it describes; dropping both is the cleanup.
+#[derive(Debug)]
+pub struct ListenerGuard {
+    label: &'static str,
+}
+
+pub fn track(label: &'static str) -> ListenerGuard {
+    LIVE_LISTENERS.fetch_add(1, Ordering::Relaxed);
+    ListenerGuard { label }
+}
+
+impl Drop for ListenerGuard {
+    fn drop(&mut self) {
+        LIVE_LISTENERS.fetch_sub(1, Ordering::Relaxed);
+        let _ = self.label;
+    }
+}
+
+/// One watchdog sample. Heap size is `None` outside Chrome.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sample {
+    pub used_heap_bytes: Option<f64>,
+    pub live_listeners: usize,
+}
+
+/// Sliding growth detector over successive samples; pure so it can be unit
+/// tested with synthetic sequences.
+#[derive(Debug, Default)]
+pub struct GrowthTracker {
+    growing_streak: usize,
+    last: Option<Sample>,
+}
+
+impl GrowthTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed one sample; returns a warning description once the heap (where
+    /// measurable) and the listener count have both grown for
+    /// [`GROWTH_ALARM_SAMPLES`] consecutive samples.
+    pub fn push(&mut self, sample: Sample) -> Option<String> {
+        let growing = match self.last {
+            Some(last) => {
+                let heap_grew = match (last.used_heap_bytes, sample.used_heap_bytes) {
+                    (Some(before), Some(now)) => now > before,
+                    // No heap numbers: fall back to listeners alone.
+                    _ => true,
+                };
+                heap_grew && sample.live_listeners > last.live_listeners
+            }
+            None => false,
+        };
+        self.growing_streak = if growing { self.growing_streak + 1 } else { 0 };
+        self.last = Some(sample);
+        if self.growing_streak >= GROWTH_ALARM_SAMPLES {
+            self.growing_streak = 0;
+            Some(format!(
+                "possible leak: {} live listeners and heap growing for {} consecutive samples",
+                sample.live_listeners, GROWTH_ALARM_SAMPLES
+            ))
+        } else {
+            None
+        }
+    }
+}
+
+/// Read `performance.memory.usedJSHeapSize` where the browser exposes it.
+/// It's a non-standard Chrome API, so this goes through `Reflect` rather
+/// than a typed binding.
+#[cfg(target_arch = "wasm32")]
+fn used_heap_bytes() -> Option<f64> {
+    let performance = web_sys::window()?.performance()?;
+    let memory = js_sys::Reflect::get(&performance, &"memory".into()).ok()?;
+    if memory.is_undefined() {
+        return None;
+    }
+    js_sys::Reflect::get(&memory, &"usedJSHeapSize".into())
+        .ok()?
+        .as_f64()
+}
+
+/// Start the sampling loop. Debug builds only — the kiosk problem is
+/// diagnosed in dev, and production shouldn't pay for a per-minute wakeup.
+#[cfg(target_arch = "wasm32")]
+pub fn start() {
+    if !cfg!(debug_assertions) {
+        return;
+    }
+    wasm_bindgen_futures::spawn_local(async {
+        let mut tracker = GrowthTracker::new();
+        loop {
+            gloo_timers::future::TimeoutFuture::new(SAMPLE_INTERVAL_MS).await;
+            let sample = Sample {
+                used_heap_bytes: used_heap_bytes(),
+                live_listeners: live_listener_count(),
+            };
+            gloo_console::debug!(
+                "watchdog:",
+                sample.used_heap_bytes.unwrap_or(0.0),
+                "heap bytes,",
+                sample.live_listeners,
+                "listeners"
+            );
+            if let Some(warning) = tracker.push(sample) {
+                gloo_console::warn!(warning);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(heap: f64, listeners: usize) -> Sample {
+        Sample {
+            used_heap_bytes: Some(heap),
+            live_listeners: listeners,
+        }
+    }
+
+    #[test]
+    fn guards_track_the_live_count() {
+        let before = live_listener_count();
+        let guard = track("test");
+        let second = track("test");
+        assert_eq!(live_listener_count(), before + 2);
+        drop(guard);
+        drop(second);
+        assert_eq!(live_listener_count(), before);
+    }
+
+    #[test]
+    fn sustained_growth_in_both_signals_raises_the_alarm() {
+        let mut tracker = GrowthTracker::new();
+        assert!(tracker.push(sample(100.0, 1)).is_none());
+        for i in 0..4u32 {
+            assert!(tracker.push(sample(200.0 + f64::from(i), 2 + i as usize)).is_none());
+        }
+        assert!(tracker.push(sample(300.0, 10)).is_some());
+    }
+
+    #[test]
+    fn stable_listeners_never_alarm_despite_heap_noise() {
+        // GC churn grows and shrinks the heap constantly; without listener
+        // growth it means nothing.
+        let mut tracker = GrowthTracker::new();
+        for i in 0..20u32 {
+            assert!(tracker.push(sample(100.0 * f64::from(i + 1), 3)).is_none());
+        }
+    }
+
+    #[test]
+    fn a_flat_sample_resets_the_streak() {
+        let mut tracker = GrowthTracker::new();
+        tracker.push(sample(100.0, 1));
+        for i in 0..4u32 {
+            tracker.push(sample(200.0 + f64::from(i), 2 + i as usize));
+        }
+        // One stable minute breaks the trend; the next growth starts over.
+        assert!(tracker.push(sample(150.0, 6)).is_none());
+        assert!(tracker.push(sample(200.0, 7)).is_none());
+    }
+}
+
+#[cfg(test)]
+#[cfg(target_arch = "wasm32")]
+mod wasm_tests {
+    use wasm_bindgen_test::*;
+    use yew::prelude::*;
+
+    use super::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    /// Minimal stand-in for the upload flow's listener usage: registers a
+    /// guard on mount and relies on the effect cleanup to release it, like
+    /// every long-lived listener in the app should.
+    #[function_component(Probe)]
+    fn probe() -> Html {
+        use_effect_with((), |_| {
+            let guard = track("probe");
+            move || drop(guard)
+        });
+        html! { <div /> }
+    }
+
+    #[wasm_bindgen_test]
+    fn one_hundred_mount_unmount_cycles_do_not_accumulate_listeners() {
+        let document = web_sys::window().unwrap().document().unwrap();
+        let root = document.create_element("div").unwrap();
+        document.body().unwrap().append_child(&root).unwrap();
+
+        let baseline = live_listener_count();
+        for _ in 0..100 {
+            let handle = yew::Renderer::<Probe>::with_root(root.clone()).render();
+            handle.destroy();
+        }
+        assert_eq!(
+            live_listener_count(),
+            baseline,
+            "unmounted components must release their listeners"
+        );
+        root.remove();
+    }
+}
@@ -0,0 +1,558 @@
+//! Application shell: top-level state, reducer, and the root component.
+
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use chrono::Utc;
+use shared::models::{ChatMessage, CropType, FarmerProfile, MessageRole, VisionResponse};
+use yew::prelude::*;
+
+use crate::components::chat_window::ChatWindow;
+use crate::components::crop_context_chip::CropContextChip;
+use crate::components::history_page::DiagnosisHistoryPage;
+use crate::components::job_card::CardStatus;
+use crate::components::network_status_banner::NetworkStatusBanner;
+use crate::components::profile_page::FarmerProfilePage;
+use crate::components::version_banner::VersionBanner;
+use crate::styles::{registry::StyleLayer, registry::StyleRegistry};
+
+/// Connectivity state mirrored into the UI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionStatus {
+    Connected,
+    Disconnected,
+}
+
+/// The app's top-level views. There is no URL router; navigation is a
+/// plain state switch driven from the header, which keeps the chat
+/// timeline alive while the user browses their history.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Route {
+    #[default]
+    Chat,
+    History,
+    Profile,
+}
+
+/// One entry in the conversation timeline: a chat message, or a live
+/// analysis card that updates in place (its state lives in
+/// [`AppState::job_cards`], so status changes mutate the card instead of
+/// appending messages).
+#[derive(Debug, Clone, PartialEq)]
+pub enum TimelineEntry {
+    Message(ChatMessage),
+    JobCard { job_id: String },
+}
+
+/// Top-level application state shared through a reducer context.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AppState {
+    pub connection: ConnectionStatus,
+    pub error: Option<String>,
+    /// Which top-level view the shell renders.
+    pub route: Route,
+    /// Crop pinned to the active conversation; sent as `crop_context` with
+    /// every chat request so follow-up questions keep their referent.
+    pub pinned_crop: Option<CropType>,
+    pub messages: Vec<TimelineEntry>,
+    /// Live status per analysis card, keyed by job id.
+    pub job_cards: HashMap<String, CardStatus>,
+    /// Latest vision analysis, updated live by the job status stream.
+    pub diagnosis: Option<VisionResponse>,
+    /// The saved farmer profile; `None` until loaded or first saved.
+    pub user_profile: Option<FarmerProfile>,
+    /// Whether a deferred browser install prompt is waiting (see
+    /// `services::pwa`); shows the "Add to Home Screen" button.
+    pub can_install: bool,
+}
+
+impl Default for AppState {
+    fn default() -> Self {
+        Self {
+            connection: ConnectionStatus::Connected,
+            error: None,
+            route: Route::default(),
+            pinned_crop: None,
+            messages: Vec::new(),
+            job_cards: HashMap::new(),
+            diagnosis: None,
+            user_profile: None,
+            can_install: false,
+        }
+    }
+}
+
+/// Actions dispatched by components against [`AppState`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum AppAction {
+    SetConnectionStatus(ConnectionStatus),
+    SetError(Option<String>),
+    /// Switch the top-level view; also clears any stale error banner so a
+    /// failure on one page doesn't follow the user to the next.
+    Navigate(Route),
+    /// Pin a crop. `manual` pins come from the picker and always apply
+    /// (inserting a system message noting the switch); automatic pins from a
+    /// completed analysis only apply when nothing is pinned yet, so they
+    /// never silently override the user's choice.
+    PinCrop { crop: CropType, manual: bool },
+    PushMessage(ChatMessage),
+    /// Prepend an older page of persisted history ahead of the live
+    /// timeline. The page must already be oldest-first (reverse a
+    /// `order=desc` fetch before dispatching).
+    LoadOlderMessages(Vec<ChatMessage>),
+    /// Insert one analysis card for a freshly queued job. Idempotent: a
+    /// second push for the same job id never adds a second card.
+    PushJobCard { job_id: String },
+    /// Update a card's live status in place. A completed result also becomes
+    /// the app-wide diagnosis.
+    UpdateJobCard { job_id: String, status: CardStatus },
+    SetDiagnosis(VisionResponse),
+    /// Remember the loaded or just-saved farmer profile.
+    SetUserProfile(FarmerProfile),
+    /// A deferred install prompt became available (or was consumed).
+    SetInstallPromptAvailable(bool),
+}
+
+impl Reducible for AppState {
+    type Action = AppAction;
+
+    fn reduce(self: Rc<Self>, action: Self::Action) -> Rc<Self> {
+        let mut next = (*self).clone();
+        match action {
+            AppAction::SetConnectionStatus(status) => next.connection = status,
+            AppAction::SetError(error) => next.error = error,
+            AppAction::Navigate(route) => {
+                next.route = route;
+                next.error = None;
+            }
+            AppAction::PinCrop { crop, manual } => {
+                if manual {
+                    if next.pinned_crop != Some(crop) && next.pinned_crop.is_some() {
+                        next.messages.push(TimelineEntry::Message(ChatMessage {
+                            id: None,
+                            conversation_id: None,
+                            role: MessageRole::System,
+                            content: format!("crop context switched to {}", crop.as_str()),
+                            image_url: None,
+                            created_at: Utc::now(),
+                        }));
+                    }
+                    next.pinned_crop = Some(crop);
+                } else if next.pinned_crop.is_none() {
+                    next.pinned_crop = Some(crop);
+                }
+            }
+            AppAction::PushMessage(message) => {
+                next.messages.push(TimelineEntry::Message(message))
+            }
+            AppAction::LoadOlderMessages(page) => {
+                let mut merged: Vec<TimelineEntry> =
+                    page.into_iter().map(TimelineEntry::Message).collect();
+                merged.append(&mut next.messages);
+                next.messages = merged;
+            }
+            AppAction::PushJobCard { job_id } => {
+                if !next.job_cards.contains_key(&job_id) {
+                    next.job_cards.insert(job_id.clone(), CardStatus::Queued);
+                    next.messages.push(TimelineEntry::JobCard { job_id });
+                }
+            }
+            AppAction::UpdateJobCard { job_id, status } => {
+                if let CardStatus::Completed(Some(result)) = &status {
+                    next.diagnosis = Some(result.clone());
+                }
+                next.job_cards.insert(job_id, status);
+            }
+            AppAction::SetDiagnosis(diagnosis) => next.diagnosis = Some(diagnosis),
+            AppAction::SetUserProfile(profile) => next.user_profile = Some(profile),
+            AppAction::SetInstallPromptAvailable(available) => next.can_install = available,
+        }
+        next.into()
+    }
+}
+
+pub type AppContext = UseReducerHandle<AppState>;
+
+/// CSS for the shell itself; registered into the style registry at startup.
+pub fn generate_app_css() -> String {
+    r#"
+.app-shell { min-height: 100vh; display: flex; flex-direction: column; }
+.app-header {
+  display: flex;
+  align-items: center;
+  gap: 12px;
+  padding: 12px 16px;
+  background: var(--leaf-green);
+  color: #fff;
+}
+.app-header h1 { font-size: 1.1rem; margin: 0; flex: 1; }
+.app-nav { display: flex; gap: 4px; }
+.app-nav button {
+  border: none;
+  border-radius: 8px;
+  padding: 6px 10px;
+  font-size: 0.85rem;
+  background: transparent;
+  color: #fff;
+  cursor: pointer;
+}
+.app-nav button.active { background: rgba(255, 255, 255, 0.2); }
+.app-install {
+  border: none;
+  border-radius: 8px;
+  padding: 6px 10px;
+  font-size: 0.85rem;
+  background: var(--electric-blue);
+  color: #fff;
+  cursor: pointer;
+}
+.app-main { flex: 1; padding: 16px; max-width: 720px; width: 100%; margin: 0 auto; }
+.app-error-banner {
+  background: var(--danger-red);
+  color: #fff;
+  padding: 8px 16px;
+  border-radius: 8px;
+  margin-bottom: 12px;
+}
+"#
+    .to_string()
+}
+
+/// Register every component stylesheet exactly once. New components add their
+/// `generate_*_css()` here instead of concatenating in `run_app`.
+pub fn collect_styles(registry: &mut StyleRegistry) {
+    registry.register(
+        StyleLayer::Variables,
+        "variables",
+        crate::styles::generate_variables_css(),
+    );
+    registry.register(StyleLayer::Base, "base", crate::styles::generate_base_css());
+    registry.register(StyleLayer::Component, "app", generate_app_css());
+    registry.register(
+        StyleLayer::Component,
+        "version_banner",
+        crate::components::version_banner::generate_version_banner_css(),
+    );
+    registry.register(
+        StyleLayer::Component,
+        "annotation_editor",
+        crate::components::annotation_editor::generate_annotation_editor_css(),
+    );
+    registry.register(
+        StyleLayer::Component,
+        "chat_window",
+        crate::components::chat_window::generate_chat_window_css(),
+    );
+    registry.register(
+        StyleLayer::Component,
+        "confidence_info",
+        crate::components::confidence_info::generate_confidence_info_css(),
+    );
+    registry.register(
+        StyleLayer::Component,
+        "file_info_panel",
+        crate::components::file_info_panel::generate_file_info_panel_css(),
+    );
+    registry.register(
+        StyleLayer::Component,
+        "crop_context_chip",
+        crate::components::crop_context_chip::generate_crop_context_chip_css(),
+    );
+    registry.register(
+        StyleLayer::Component,
+        "camera_capture",
+        crate::components::camera_capture::generate_camera_capture_css(),
+    );
+    registry.register(
+        StyleLayer::Component,
+        "history_list",
+        crate::components::history_list::generate_history_list_css(),
+    );
+    registry.register(
+        StyleLayer::Component,
+        "history_page",
+        crate::components::history_page::generate_history_page_css(),
+    );
+    registry.register(
+        StyleLayer::Component,
+        "profile_page",
+        crate::components::profile_page::generate_profile_page_css(),
+    );
+    registry.register(
+        StyleLayer::Component,
+        "network_status_banner",
+        crate::components::network_status_banner::generate_network_status_banner_css(),
+    );
+    registry.register(
+        StyleLayer::Component,
+        "drop_zone",
+        crate::components::image::generate_drop_zone_css(),
+    );
+    registry.register(
+        StyleLayer::Component,
+        "lightbox",
+        crate::components::lightbox::generate_lightbox_css(),
+    );
+    registry.register(
+        StyleLayer::Component,
+        "tag_chips",
+        crate::components::tag_chips::generate_tag_chips_css(),
+    );
+    registry.register(
+        StyleLayer::Component,
+        "voice_recorder",
+        crate::components::voice_recorder::generate_voice_recorder_css(),
+    );
+    registry.register(
+        StyleLayer::Component,
+        "job_card",
+        crate::components::job_card::generate_job_card_css(),
+    );
+    registry.register(
+        StyleLayer::Component,
+        "trend_chart",
+        crate::components::trend_chart::generate_trend_chart_css(),
+    );
+    registry.register(
+        StyleLayer::Component,
+        "dashboard",
+        crate::components::dashboard::generate_dashboard_css(),
+    );
+}
+
+#[function_component(SimpleApp)]
+pub fn simple_app() -> Html {
+    let state = use_reducer(AppState::default);
+
+    // Surface the browser's deferred install prompt as an explicit button;
+    // the event may fire before or after mount, `pwa` handles both.
+    {
+        let state = state.clone();
+        use_effect_with((), move |_| {
+            crate::services::pwa::on_install_available(Callback::from(move |_| {
+                state.dispatch(AppAction::SetInstallPromptAvailable(true));
+            }));
+            || ()
+        });
+    }
+
+    let on_install = {
+        let state = state.clone();
+        Callback::from(move |_| {
+            crate::services::pwa::show_install_prompt();
+            state.dispatch(AppAction::SetInstallPromptAvailable(false));
+        })
+    };
+
+    let nav_button = |route: Route, label: &str| {
+        let state = state.clone();
+        let onclick = Callback::from(move |_| state.dispatch(AppAction::Navigate(route)));
+        let class = if state.route == route { "active" } else { "" };
+        html! { <button {class} {onclick}>{ label }</button> }
+    };
+
+    html! {
+        <ContextProvider<AppContext> context={state.clone()}>
+            <div class="app-shell">
+                <VersionBanner />
+                <NetworkStatusBanner />
+                <header class="app-header">
+                    <h1>{ "AI วินิจฉัยโรคพืช · Plant Disease AI" }</h1>
+                    if state.can_install {
+                        <button class="app-install" onclick={on_install}>
+                            { "ติดตั้งแอป · Add to Home Screen" }
+                        </button>
+                    }
+                    <nav class="app-nav">
+                        { nav_button(Route::Chat, "แชท · Chat") }
+                        { nav_button(Route::History, "ประวัติ · History") }
+                        { nav_button(Route::Profile, "โปรไฟล์ · Profile") }
+                    </nav>
+                </header>
+                <main class="app-main">
+                    if let Some(error) = &state.error {
+                        <div class="app-error-banner" role="alert">{ error }</div>
+                    }
+                    { match state.route {
+                        Route::Chat => html! {
+                            <>
+                                <CropContextChip />
+                                <ChatWindow />
+                            </>
+                        },
+                        Route::History => html! { <DiagnosisHistoryPage /> },
+                        Route::Profile => html! { <FarmerProfilePage /> },
+                    } }
+                </main>
+            </div>
+        </ContextProvider<AppContext>>
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reduce(state: AppState, action: AppAction) -> AppState {
+        (*Rc::new(state).reduce(action)).clone()
+    }
+
+    fn message_at(state: &AppState, index: usize) -> &ChatMessage {
+        match &state.messages[index] {
+            TimelineEntry::Message(message) => message,
+            other => panic!("expected a message at {index}, got {other:?}"),
+        }
+    }
+
+    fn chat(content: &str) -> ChatMessage {
+        ChatMessage {
+            id: None,
+            conversation_id: None,
+            role: MessageRole::User,
+            content: content.into(),
+            image_url: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn older_pages_prepend_ahead_of_the_live_timeline() {
+        let state = reduce(AppState::default(), AppAction::PushMessage(chat("live")));
+        let state = reduce(
+            state,
+            AppAction::LoadOlderMessages(vec![chat("older-1"), chat("older-2")]),
+        );
+        assert_eq!(state.messages.len(), 3);
+        assert_eq!(message_at(&state, 0).content, "older-1");
+        assert_eq!(message_at(&state, 1).content, "older-2");
+        assert_eq!(message_at(&state, 2).content, "live");
+    }
+
+    #[test]
+    fn the_install_button_follows_the_deferred_prompt() {
+        let state = reduce(
+            AppState::default(),
+            AppAction::SetInstallPromptAvailable(true),
+        );
+        assert!(state.can_install);
+        // Consumed: the browser honours the prompt only once.
+        let state = reduce(state, AppAction::SetInstallPromptAvailable(false));
+        assert!(!state.can_install);
+    }
+
+    #[test]
+    fn navigation_switches_the_route_and_drops_stale_errors() {
+        let state = reduce(
+            AppState::default(),
+            AppAction::SetError(Some("upload failed".into())),
+        );
+        let state = reduce(state, AppAction::Navigate(Route::History));
+        assert_eq!(state.route, Route::History);
+        assert_eq!(state.error, None);
+
+        let state = reduce(state, AppAction::Navigate(Route::Chat));
+        assert_eq!(state.route, Route::Chat);
+    }
+
+    #[test]
+    fn auto_pin_sets_crop_only_when_unset() {
+        let state = reduce(
+            AppState::default(),
+            AppAction::PinCrop { crop: CropType::Durian, manual: false },
+        );
+        assert_eq!(state.pinned_crop, Some(CropType::Durian));
+
+        // A later analysis of another crop must not steal the pin.
+        let state = reduce(
+            state,
+            AppAction::PinCrop { crop: CropType::Rice, manual: false },
+        );
+        assert_eq!(state.pinned_crop, Some(CropType::Durian));
+        assert!(state.messages.is_empty());
+    }
+
+    #[test]
+    fn manual_pin_change_inserts_system_message() {
+        let state = reduce(
+            AppState::default(),
+            AppAction::PinCrop { crop: CropType::Durian, manual: false },
+        );
+        let state = reduce(
+            state,
+            AppAction::PinCrop { crop: CropType::Rice, manual: true },
+        );
+        assert_eq!(state.pinned_crop, Some(CropType::Rice));
+        assert_eq!(state.messages.len(), 1);
+        assert_eq!(message_at(&state, 0).role, MessageRole::System);
+        assert!(message_at(&state, 0).content.contains("rice"));
+    }
+
+    #[test]
+    fn first_manual_pin_does_not_announce_a_switch() {
+        let state = reduce(
+            AppState::default(),
+            AppAction::PinCrop { crop: CropType::Mango, manual: true },
+        );
+        assert_eq!(state.pinned_crop, Some(CropType::Mango));
+        assert!(state.messages.is_empty());
+    }
+
+    #[test]
+    fn status_changes_mutate_one_card_instead_of_appending_messages() {
+        let job_id = "job-1".to_string();
+        let state = reduce(
+            AppState::default(),
+            AppAction::PushJobCard { job_id: job_id.clone() },
+        );
+        assert_eq!(state.messages.len(), 1);
+        assert_eq!(state.job_cards[&job_id], CardStatus::Queued);
+
+        // A duplicate push (e.g. the submit handler and the first status
+        // event racing) never adds a second card.
+        let state = reduce(state, AppAction::PushJobCard { job_id: job_id.clone() });
+        assert_eq!(state.messages.len(), 1);
+
+        let state = reduce(
+            state,
+            AppAction::UpdateJobCard { job_id: job_id.clone(), status: CardStatus::Processing },
+        );
+        let state = reduce(
+            state,
+            AppAction::UpdateJobCard {
+                job_id: job_id.clone(),
+                status: CardStatus::Failed("model exploded".into()),
+            },
+        );
+        // The whole lifecycle happened inside the one timeline entry.
+        assert_eq!(state.messages.len(), 1);
+        assert!(matches!(state.messages[0], TimelineEntry::JobCard { .. }));
+        assert_eq!(state.job_cards[&job_id], CardStatus::Failed("model exploded".into()));
+    }
+
+    #[test]
+    fn completed_card_result_becomes_the_app_diagnosis() {
+        let job_id = uuid::Uuid::new_v4();
+        let result = VisionResponse {
+            job_id,
+            crop_type: CropType::Rice,
+            detections: Vec::new(),
+            severity_score: None,
+            model_version: "v1".into(),
+            processed_at: Utc::now(),
+            diagnosis_status: shared::models::DiagnosisStatus::Confirmed,
+        };
+        let state = reduce(
+            AppState::default(),
+            AppAction::PushJobCard { job_id: job_id.to_string() },
+        );
+        let state = reduce(
+            state,
+            AppAction::UpdateJobCard {
+                job_id: job_id.to_string(),
+                status: CardStatus::Completed(Some(result.clone())),
+            },
+        );
+        assert_eq!(state.diagnosis, Some(result));
+        assert_eq!(state.messages.len(), 1);
+    }
+}
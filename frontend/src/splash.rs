@@ -0,0 +1,59 @@
+//! Removal of the static splash screen once Yew has rendered.
+//!
+//! The splash itself lives inline in `index.html` so it paints before the
+//! wasm module is even fetched; its timeout/panic fallback is wired there in
+//! plain JS because Rust cannot be trusted to run if startup failed.
+
+/// Mark the app as mounted (disarming the JS failure timer) and remove the
+/// splash element. Safe to call more than once.
+pub fn remove_splash() {
+    let Some(window) = web_sys::window() else { return };
+    // Disarm the timeout fallback in index.html first.
+    let _ = js_sys::Reflect::set(
+        &window,
+        &"__app_mounted".into(),
+        &wasm_bindgen::JsValue::TRUE,
+    );
+    if let Some(splash) = window
+        .document()
+        .and_then(|d| d.get_element_by_id("splash"))
+    {
+        splash.remove();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use wasm_bindgen_test::*;
+
+    use super::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    fn insert_splash() {
+        let document = web_sys::window().unwrap().document().unwrap();
+        let div = document.create_element("div").unwrap();
+        div.set_id("splash");
+        document.body().unwrap().append_child(&div).unwrap();
+    }
+
+    #[wasm_bindgen_test]
+    fn removes_splash_and_sets_mounted_flag() {
+        insert_splash();
+        remove_splash();
+        let window = web_sys::window().unwrap();
+        assert!(window
+            .document()
+            .unwrap()
+            .get_element_by_id("splash")
+            .is_none());
+        let mounted = js_sys::Reflect::get(&window, &"__app_mounted".into()).unwrap();
+        assert_eq!(mounted.as_bool(), Some(true), "timeout fallback must be disarmed");
+    }
+
+    #[wasm_bindgen_test]
+    fn is_idempotent_without_a_splash_element() {
+        remove_splash();
+        remove_splash();
+    }
+}
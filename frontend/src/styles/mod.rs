@@ -0,0 +1,85 @@
+//! Design tokens and the stylesheet registry.
+//!
+//! All component CSS is registered into [`registry::StyleRegistry`] during
+//! startup and injected as a single deduplicated `<style>` tag; see
+//! `collect_styles` in `main.rs`.
+
+pub mod registry;
+
+/// Brand palette used across components. Values are duplicated into the CSS
+/// custom properties below so Rust-side canvas/SVG code and CSS stay in sync.
+pub struct PrimaryColors;
+
+impl PrimaryColors {
+    pub const ELECTRIC_BLUE: &'static str = "#2563eb";
+    pub const LEAF_GREEN: &'static str = "#16a34a";
+    pub const ACCENT_LIME_GREEN: &'static str = "#84cc16";
+    pub const WARNING_AMBER: &'static str = "#f59e0b";
+    pub const DANGER_RED: &'static str = "#dc2626";
+    pub const INK: &'static str = "#1f2937";
+    pub const SURFACE: &'static str = "#f8fafc";
+}
+
+/// `:root` custom properties shared by every component stylesheet.
+pub fn generate_variables_css() -> String {
+    format!(
+        r#"
+:root {{
+  --electric-blue: {blue};
+  --leaf-green: {green};
+  --accent-lime-green: {lime};
+  --warning-amber: {amber};
+  --danger-red: {red};
+  --ink: {ink};
+  --surface: {surface};
+  --radius-card: 16px;
+  --shadow-card: 0 2px 8px rgba(31, 41, 55, 0.08);
+  --font-thai: 'Noto Sans Thai', 'Sarabun', sans-serif;
+}}
+"#,
+        blue = PrimaryColors::ELECTRIC_BLUE,
+        green = PrimaryColors::LEAF_GREEN,
+        lime = PrimaryColors::ACCENT_LIME_GREEN,
+        amber = PrimaryColors::WARNING_AMBER,
+        red = PrimaryColors::DANGER_RED,
+        ink = PrimaryColors::INK,
+        surface = PrimaryColors::SURFACE,
+    )
+}
+
+/// Shared primitives: resets, buttons, cards, and the spinner keyframe that
+/// several components previously each carried their own copy of.
+pub fn generate_base_css() -> String {
+    r#"
+* { box-sizing: border-box; }
+body {
+  margin: 0;
+  font-family: var(--font-thai);
+  color: var(--ink);
+  background: var(--surface);
+}
+.card {
+  background: #fff;
+  border-radius: var(--radius-card);
+  box-shadow: var(--shadow-card);
+  padding: 16px;
+}
+.btn-primary {
+  background: var(--electric-blue);
+  color: #fff;
+  border: none;
+  border-radius: 8px;
+  padding: 10px 20px;
+  cursor: pointer;
+}
+.btn-primary:disabled { opacity: 0.5; cursor: not-allowed; }
+@keyframes spin {
+  from { transform: rotate(0deg); }
+  to { transform: rotate(360deg); }
+}
+@media (prefers-reduced-motion: reduce) {
+  * { animation-duration: 0.01ms !important; transition-duration: 0.01ms !important; }
+}
+"#
+    .to_string()
+}
@@ -0,0 +1,179 @@
+//! Central registry for component CSS.
+//!
+//! Components used to expose `generate_*_css()` strings that were concatenated
+//! ad hoc in `run_app`, which shipped duplicated keyframes and rules that never
+//! matched anything. The registry collects every block once, deduplicates
+//! identical blocks, and emits a single stylesheet with variables first so
+//! component rules can rely on the custom properties being defined.
+
+use std::collections::HashSet;
+
+/// Where a block sits in the final stylesheet. Variables always come first so
+/// `var(--...)` lookups in component rules resolve.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum StyleLayer {
+    /// `:root` custom properties and font-face declarations.
+    Variables,
+    /// Shared primitives (buttons, cards, animations).
+    Base,
+    /// Per-component rules.
+    Component,
+}
+
+struct StyleBlock {
+    layer: StyleLayer,
+    source: &'static str,
+    css: String,
+}
+
+/// Collects CSS blocks from components and produces one deduplicated
+/// stylesheet. Registration order is preserved within a layer so the output is
+/// stable across builds.
+#[derive(Default)]
+pub struct StyleRegistry {
+    blocks: Vec<StyleBlock>,
+    seen: HashSet<String>,
+    duplicate_bytes: usize,
+}
+
+impl StyleRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a CSS block under `source` (the component name, used in the
+    /// size report). Identical blocks are dropped regardless of source.
+    pub fn register(&mut self, layer: StyleLayer, source: &'static str, css: impl Into<String>) {
+        let css = css.into();
+        let normalized = normalize(&css);
+        if normalized.is_empty() {
+            return;
+        }
+        if !self.seen.insert(normalized) {
+            self.duplicate_bytes += css.len();
+            return;
+        }
+        self.blocks.push(StyleBlock { layer, source, css });
+    }
+
+    /// Total bytes that registration skipped as duplicates, for the size
+    /// report logged at startup.
+    pub fn deduplicated_bytes(&self) -> usize {
+        self.duplicate_bytes
+    }
+
+    /// Build the final stylesheet: variables, then base, then components, each
+    /// layer in registration order.
+    pub fn build(&self) -> String {
+        let mut out = String::new();
+        for layer in [StyleLayer::Variables, StyleLayer::Base, StyleLayer::Component] {
+            for block in self.blocks.iter().filter(|b| b.layer == layer) {
+                out.push_str(block.css.trim());
+                out.push('\n');
+            }
+        }
+        out
+    }
+
+    /// Human-readable before/after size report.
+    pub fn size_report(&self) -> String {
+        let after = self.build().len();
+        let before = after + self.duplicate_bytes;
+        format!(
+            "stylesheet: {} blocks, {} bytes ({} bytes of duplicates removed, was {})",
+            self.blocks.len(),
+            after,
+            self.duplicate_bytes,
+            before
+        )
+    }
+
+    /// Sources that contributed at least one block, for debugging.
+    pub fn sources(&self) -> Vec<&'static str> {
+        self.blocks.iter().map(|b| b.source).collect()
+    }
+}
+
+/// Whitespace-insensitive key used for dedupe so formatting differences
+/// between two copies of the same keyframe still collapse.
+fn normalize(css: &str) -> String {
+    css.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Inject the built stylesheet as a single `<style>` tag in `<head>`.
+///
+/// With the `emit-css` feature (used by the trunk release build) the CSS is
+/// instead logged to the console for the build script to capture and serve as
+/// a static file.
+#[cfg(target_arch = "wasm32")]
+pub fn inject(registry: &StyleRegistry) {
+    let css = registry.build();
+    if cfg!(feature = "emit-css") {
+        web_sys::console::log_1(&format!("__EMIT_CSS__{css}").into());
+        return;
+    }
+    let document = web_sys::window()
+        .and_then(|w| w.document())
+        .expect("document available");
+    let head = document.head().expect("document has <head>");
+    let style = document
+        .create_element("style")
+        .expect("create <style> element");
+    style.set_attribute("data-generated", "registry").ok();
+    style.set_text_content(Some(&css));
+    head.append_child(&style).expect("append <style> to <head>");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SPIN_KEYFRAME: &str = "@keyframes spin { from { transform: rotate(0deg); } to { transform: rotate(360deg); } }";
+
+    #[test]
+    fn deduplicates_identical_keyframes() {
+        let mut registry = StyleRegistry::new();
+        registry.register(StyleLayer::Base, "spinner", SPIN_KEYFRAME);
+        registry.register(StyleLayer::Base, "camera", SPIN_KEYFRAME);
+        let css = registry.build();
+        assert_eq!(css.matches("@keyframes spin").count(), 1);
+        assert_eq!(registry.deduplicated_bytes(), SPIN_KEYFRAME.len());
+    }
+
+    #[test]
+    fn dedupe_ignores_whitespace_differences() {
+        let mut registry = StyleRegistry::new();
+        registry.register(StyleLayer::Base, "a", "@keyframes spin {\n  from { opacity: 0; }\n}");
+        registry.register(StyleLayer::Base, "b", "@keyframes spin { from { opacity: 0; } }");
+        assert_eq!(registry.build().matches("@keyframes spin").count(), 1);
+    }
+
+    #[test]
+    fn variables_precede_components_regardless_of_registration_order() {
+        let mut registry = StyleRegistry::new();
+        registry.register(StyleLayer::Component, "chat", ".chat { color: var(--ink); }");
+        registry.register(StyleLayer::Variables, "colors", ":root { --ink: #111; }");
+        let css = registry.build();
+        let root = css.find(":root").unwrap();
+        let chat = css.find(".chat").unwrap();
+        assert!(root < chat);
+    }
+
+    #[test]
+    fn ordering_is_stable_within_a_layer() {
+        let mut registry = StyleRegistry::new();
+        registry.register(StyleLayer::Component, "first", ".first { top: 0; }");
+        registry.register(StyleLayer::Component, "second", ".second { top: 0; }");
+        let css = registry.build();
+        assert!(css.find(".first").unwrap() < css.find(".second").unwrap());
+    }
+
+    #[test]
+    fn size_report_counts_removed_bytes() {
+        let mut registry = StyleRegistry::new();
+        registry.register(StyleLayer::Base, "a", SPIN_KEYFRAME);
+        registry.register(StyleLayer::Base, "b", SPIN_KEYFRAME);
+        let report = registry.size_report();
+        assert!(report.contains(&format!("{} bytes of duplicates", SPIN_KEYFRAME.len())));
+    }
+}
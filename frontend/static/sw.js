@@ -0,0 +1,84 @@
+// Plant Disease AI service worker.
+//
+// Strategy: network-first for /api/ (farmers must never see a stale
+// diagnosis when they are online, but a cached answer beats no answer in
+// the field), cache-first for everything else — the shell HTML, injected
+// CSS, and the wasm binary carry content hashes in their names, so a
+// cached copy is always the right copy.
+//
+// Bump CACHE_VERSION whenever the precached shell list changes.
+var CACHE_VERSION = 'plant-disease-ai-v1';
+var SHELL = ['/', '/index.html', '/manifest.json', '/icons/icon-192.png', '/icons/icon-512.png'];
+
+self.addEventListener('install', function (event) {
+  event.waitUntil(
+    caches.open(CACHE_VERSION).then(function (cache) {
+      return cache.addAll(SHELL);
+    }).then(function () {
+      return self.skipWaiting();
+    })
+  );
+});
+
+self.addEventListener('activate', function (event) {
+  event.waitUntil(
+    caches.keys().then(function (keys) {
+      return Promise.all(keys.filter(function (key) {
+        return key !== CACHE_VERSION;
+      }).map(function (key) {
+        return caches.delete(key);
+      }));
+    }).then(function () {
+      return self.clients.claim();
+    })
+  );
+});
+
+function networkFirst(request) {
+  return fetch(request).then(function (response) {
+    if (response.ok) {
+      var copy = response.clone();
+      caches.open(CACHE_VERSION).then(function (cache) {
+        cache.put(request, copy);
+      });
+    }
+    return response;
+  }).catch(function () {
+    return caches.match(request).then(function (cached) {
+      if (cached) return cached;
+      return new Response(
+        JSON.stringify({ success: false, data: null, error: 'offline' }),
+        { status: 503, headers: { 'content-type': 'application/json' } }
+      );
+    });
+  });
+}
+
+function cacheFirst(request) {
+  return caches.match(request).then(function (cached) {
+    if (cached) return cached;
+    return fetch(request).then(function (response) {
+      if (response.ok) {
+        var copy = response.clone();
+        caches.open(CACHE_VERSION).then(function (cache) {
+          cache.put(request, copy);
+        });
+      }
+      return response;
+    });
+  });
+}
+
+self.addEventListener('fetch', function (event) {
+  var url = new URL(event.request.url);
+  // Only GETs from our own origin; uploads, SSE, and websockets pass
+  // straight through.
+  if (event.request.method !== 'GET' || url.origin !== self.location.origin) {
+    return;
+  }
+  if (url.pathname.startsWith('/api/')) {
+    event.respondWith(networkFirst(event.request));
+  } else {
+    event.respondWith(cacheFirst(event.request));
+  }
+});
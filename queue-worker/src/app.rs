@@ -0,0 +1,327 @@
+//! Worker assembly: settings, the production sink implementations, and the
+//! consume loop.
+//!
+//! Split out of `main.rs` so the gateway's end-to-end suite can run the
+//! real consume loop in-process against containerized Redis and RabbitMQ.
+//! `main` keeps only process concerns: the subscriber, signals, and env
+//! parsing.
+
+use std::{
+    sync::{atomic::AtomicUsize, Arc},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use futures_util::StreamExt;
+use lapin::{
+    options::{BasicConsumeOptions, QueueDeclareOptions},
+    types::FieldTable,
+    Connection, ConnectionProperties,
+};
+use redis::AsyncCommands;
+use uuid::Uuid;
+
+use crate::{
+    shutdown::ShutdownController,
+    worker::{self, CancelProbe, DeadLetterSink, JobProcessor, TimelineSink, VisionJob},
+};
+
+/// Everything the consume loop needs, parsed from the environment in
+/// production and constructed directly by tests.
+#[derive(Debug, Clone)]
+pub struct WorkerSettings {
+    pub amqp_url: String,
+    pub redis_url: String,
+    pub vision_url: String,
+    pub queue: String,
+    pub retry_queue: String,
+    pub dlq: String,
+    pub retry_delay_ms: u64,
+    pub max_retries: u64,
+    pub drain_deadline: Duration,
+}
+
+impl WorkerSettings {
+    pub fn from_env() -> Self {
+        Self {
+            amqp_url: std::env::var("AMQP_URL").unwrap_or_else(|_| "amqp://localhost:5672".into()),
+            redis_url: std::env::var("REDIS_URL")
+                .unwrap_or_else(|_| "redis://localhost:6379".into()),
+            vision_url: std::env::var("VISION_SERVICE_URL")
+                .unwrap_or_else(|_| "http://localhost:8001".into()),
+            queue: std::env::var("VISION_QUEUE")
+                .unwrap_or_else(|_| "vision_analysis_queue".into()),
+            retry_queue: std::env::var("VISION_RETRY_QUEUE")
+                .unwrap_or_else(|_| "vision_analysis_retry_queue".into()),
+            dlq: std::env::var("VISION_DLQ").unwrap_or_else(|_| "vision_analysis_dlq".into()),
+            retry_delay_ms: std::env::var("VISION_RETRY_DELAY_MS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(10_000),
+            max_retries: std::env::var("WORKER_MAX_RETRIES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(3),
+            // Must stay below Kubernetes terminationGracePeriodSeconds
+            // (default 30s): the preStop hook sends SIGTERM, we drain for
+            // WORKER_DRAIN_SECONDS, and still need a moment to nack + close
+            // the connection before SIGKILL.
+            drain_deadline: Duration::from_secs(
+                std::env::var("WORKER_DRAIN_SECONDS")
+                    .ok()
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(20),
+            ),
+        }
+    }
+}
+
+/// Redis-backed job timeline.
+struct RedisTimeline {
+    client: redis::Client,
+}
+
+#[async_trait]
+impl TimelineSink for RedisTimeline {
+    async fn record(&self, job_id: Uuid, event: &str, reason: &str) {
+        let Ok(mut conn) = self.client.get_multiplexed_async_connection().await else {
+            tracing::error!(%job_id, "timeline: redis unavailable");
+            return;
+        };
+        let entry = serde_json::json!({
+            "event": event,
+            "reason": reason,
+            "at": chrono::Utc::now(),
+        })
+        .to_string();
+        let _: Result<(), _> = conn.rpush(format!("job:{job_id}:timeline"), entry).await;
+    }
+}
+
+/// Reads the `job:{id}:cancelled` marker the gateway writes when a user
+/// cancels. Errors read as "not cancelled": a Redis blip must never make
+/// the worker throw away someone's job.
+struct RedisCancelProbe {
+    client: redis::Client,
+}
+
+#[async_trait]
+impl CancelProbe for RedisCancelProbe {
+    async fn is_cancelled(&self, job_id: Uuid) -> bool {
+        let Ok(mut conn) = self.client.get_multiplexed_async_connection().await else {
+            return false;
+        };
+        matches!(
+            conn.get::<_, Option<String>>(format!("job:{job_id}:cancelled")).await,
+            Ok(Some(_))
+        )
+    }
+}
+
+/// Publishes exhausted jobs onto the parking DLQ via the consume channel.
+struct ChannelDlq {
+    channel: lapin::Channel,
+    dlq: String,
+}
+
+#[async_trait]
+impl DeadLetterSink for ChannelDlq {
+    async fn park(&self, job: &VisionJob, reason: &str, retries_so_far: u64) {
+        let Ok(body) = serde_json::to_vec(job) else { return };
+        // The gateway's DLQ consumer routes by this header; stamping the
+        // spent budget keeps it from granting the job a second one.
+        let mut headers = lapin::types::FieldTable::default();
+        headers.insert(
+            "x-retry-count".into(),
+            lapin::types::AMQPValue::LongInt(retries_so_far as i32),
+        );
+        let result = self
+            .channel
+            .basic_publish(
+                "",
+                &self.dlq,
+                lapin::options::BasicPublishOptions::default(),
+                &body,
+                lapin::BasicProperties::default()
+                    .with_content_type("application/json".into())
+                    .with_delivery_mode(2)
+                    .with_headers(headers),
+            )
+            .await;
+        match result {
+            Ok(confirm) => {
+                if let Err(e) = confirm.await {
+                    tracing::error!(job_id = %job.job_id, error = %e, "dlq publish unconfirmed");
+                }
+            }
+            Err(e) => tracing::error!(job_id = %job.job_id, error = %e, "dlq publish failed"),
+        }
+        tracing::warn!(job_id = %job.job_id, %reason, "job parked on dlq");
+    }
+}
+
+/// Calls the vision service for one job. Placeholder pipeline for now; the
+/// interesting part of this binary is the consume/shutdown plumbing.
+struct VisionProcessor {
+    http: reqwest::Client,
+    vision_url: String,
+}
+
+#[async_trait]
+impl JobProcessor for VisionProcessor {
+    async fn process(&self, job: &VisionJob) -> Result<(), String> {
+        self.http
+            .post(format!("{}/analyze", self.vision_url))
+            .json(job)
+            .send()
+            .await
+            .map_err(|e| format!("vision service: {e}"))?
+            .error_for_status()
+            .map_err(|e| format!("vision service: {e}"))?;
+        Ok(())
+    }
+}
+
+/// Park a payload that failed contract decoding on the DLQ as-is, stamped
+/// with the decode error so whoever inspects `failed_jobs` sees why.
+async fn park_undecodable(channel: &lapin::Channel, dlq: &str, body: &[u8], reason: &str) {
+    let mut headers = lapin::types::FieldTable::default();
+    headers.insert(
+        "x-decode-error".into(),
+        lapin::types::AMQPValue::LongString(reason.into()),
+    );
+    let result = channel
+        .basic_publish(
+            "",
+            dlq,
+            lapin::options::BasicPublishOptions::default(),
+            body,
+            lapin::BasicProperties::default()
+                .with_content_type("application/json".into())
+                .with_delivery_mode(2)
+                .with_headers(headers),
+        )
+        .await;
+    if let Err(e) = result {
+        tracing::error!(error = %e, "failed to park undecodable message");
+    }
+}
+
+/// Connect, declare topology, and consume until `shutdown` triggers; then
+/// drain in-flight work and close the connection.
+pub async fn run(settings: WorkerSettings, shutdown: ShutdownController) -> anyhow::Result<()> {
+    let connection =
+        Connection::connect(&settings.amqp_url, ConnectionProperties::default()).await?;
+    let channel = connection.create_channel().await?;
+    channel
+        .basic_qos(1, lapin::options::BasicQosOptions::default())
+        .await?;
+    // Topology mirrors the gateway's declaration (both sides declare so
+    // either can start first): rejections dead-letter into the retry queue,
+    // which TTLs back onto the main queue; the DLQ parks exhausted jobs.
+    let durable = QueueDeclareOptions {
+        durable: true,
+        ..Default::default()
+    };
+    let mut main_args = FieldTable::default();
+    main_args.insert(
+        "x-max-priority".into(),
+        lapin::types::AMQPValue::ShortShortUInt(shared::queue::MAX_PRIORITY),
+    );
+    main_args.insert(
+        "x-dead-letter-exchange".into(),
+        lapin::types::AMQPValue::LongString("".into()),
+    );
+    main_args.insert(
+        "x-dead-letter-routing-key".into(),
+        lapin::types::AMQPValue::LongString(settings.retry_queue.as_str().into()),
+    );
+    channel.queue_declare(&settings.queue, durable, main_args).await?;
+    let mut retry_args = FieldTable::default();
+    retry_args.insert(
+        "x-message-ttl".into(),
+        lapin::types::AMQPValue::LongInt(settings.retry_delay_ms as i32),
+    );
+    retry_args.insert(
+        "x-dead-letter-exchange".into(),
+        lapin::types::AMQPValue::LongString("".into()),
+    );
+    retry_args.insert(
+        "x-dead-letter-routing-key".into(),
+        lapin::types::AMQPValue::LongString(settings.queue.as_str().into()),
+    );
+    channel.queue_declare(&settings.retry_queue, durable, retry_args).await?;
+    channel.queue_declare(&settings.dlq, durable, FieldTable::default()).await?;
+
+    let mut consumer = channel
+        .basic_consume(
+            &settings.queue,
+            "queue-worker",
+            BasicConsumeOptions::default(),
+            FieldTable::default(),
+        )
+        .await?;
+
+    let processor = Arc::new(VisionProcessor {
+        http: reqwest::Client::new(),
+        vision_url: settings.vision_url.clone(),
+    });
+    let redis_client = redis::Client::open(settings.redis_url.as_str())?;
+    let timeline = Arc::new(RedisTimeline {
+        client: redis_client.clone(),
+    });
+    let cancel = Arc::new(RedisCancelProbe {
+        client: redis_client,
+    });
+    let dead_letters = Arc::new(ChannelDlq {
+        channel: channel.clone(),
+        dlq: settings.dlq.clone(),
+    });
+    let in_flight = Arc::new(AtomicUsize::new(0));
+
+    let mut stop = shutdown.signal();
+    loop {
+        let delivery = tokio::select! {
+            delivery = consumer.next() => delivery,
+            _ = stop.wait() => break,
+        };
+        let Some(Ok(delivery)) = delivery else { break };
+
+        let job: VisionJob = match shared::queue::decode_job_request(&delivery.data) {
+            Ok(job) => job,
+            Err(e) => {
+                // A version we don't speak (deploy skew) or junk: park it on
+                // the DLQ with the reason instead of silently dropping it.
+                tracing::error!(error = %e, "rejecting undecodable message to the dlq");
+                park_undecodable(&channel, &dead_letters.dlq, &delivery.data, &e.to_string())
+                    .await;
+                worker::DeliveryAck::ack(&delivery.acker).await;
+                continue;
+            }
+        };
+
+        let retries_so_far = worker::death_count(delivery.properties.headers().as_ref());
+        in_flight.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        let outcome = worker::process_delivery(
+            &job,
+            processor.as_ref(),
+            &delivery.acker,
+            timeline.as_ref(),
+            dead_letters.as_ref(),
+            cancel.as_ref(),
+            &shutdown,
+            retries_so_far,
+            settings.max_retries,
+        )
+        .await;
+        in_flight.fetch_sub(1, std::sync::atomic::Ordering::SeqCst);
+        tracing::info!(job_id = %job.job_id, ?outcome, "delivery resolved");
+    }
+
+    // Stopped consuming; give in-flight work the drain budget, then leave.
+    worker::drain(&in_flight, settings.drain_deadline).await;
+    tracing::info!("flushing and closing connections");
+    let _ = channel.close(200, "worker shutdown").await;
+    let _ = connection.close(200, "worker shutdown").await;
+    Ok(())
+}
@@ -0,0 +1,5 @@
+//! Queue worker: consumes vision analysis jobs published by the gateway.
+
+pub mod app;
+pub mod shutdown;
+pub mod worker;
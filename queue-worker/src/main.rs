@@ -0,0 +1,25 @@
+use queue_worker::{app, shutdown::ShutdownController};
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    tracing_subscriber::fmt::init();
+
+    let settings = app::WorkerSettings::from_env();
+    let shutdown = ShutdownController::new(settings.drain_deadline);
+    {
+        let shutdown = shutdown.clone();
+        tokio::spawn(async move {
+            let mut sigterm =
+                tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+                    .expect("install SIGTERM handler");
+            tokio::select! {
+                _ = sigterm.recv() => {}
+                _ = tokio::signal::ctrl_c() => {}
+            }
+            tracing::info!("shutdown signal received, draining");
+            shutdown.trigger();
+        });
+    }
+
+    app::run(settings, shutdown).await
+}
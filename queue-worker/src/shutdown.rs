@@ -0,0 +1,88 @@
+//! Graceful shutdown coordination.
+//!
+//! On SIGTERM the consumer stops taking new deliveries and in-flight jobs get
+//! up to `drain_deadline` to finish; whatever cannot finish is nacked with
+//! requeue so another replica picks it up. The deadline must be configured
+//! below Kubernetes' `terminationGracePeriodSeconds` (see config comments in
+//! `main.rs`), otherwise the kubelet SIGKILLs us mid-nack.
+
+use std::time::Duration;
+
+use tokio::sync::watch;
+
+/// Shared flag flipped once shutdown begins, plus the drain budget.
+#[derive(Clone)]
+pub struct ShutdownController {
+    sender: watch::Sender<bool>,
+    pub drain_deadline: Duration,
+}
+
+impl ShutdownController {
+    pub fn new(drain_deadline: Duration) -> Self {
+        let (sender, _) = watch::channel(false);
+        Self {
+            sender,
+            drain_deadline,
+        }
+    }
+
+    /// Begin shutdown: consumers observing [`ShutdownSignal`] stop pulling
+    /// new work.
+    pub fn trigger(&self) {
+        let _ = self.sender.send(true);
+    }
+
+    pub fn is_shutting_down(&self) -> bool {
+        *self.sender.borrow()
+    }
+
+    pub fn signal(&self) -> ShutdownSignal {
+        ShutdownSignal {
+            receiver: self.sender.subscribe(),
+        }
+    }
+}
+
+/// Per-task handle awaiting the shutdown flag.
+pub struct ShutdownSignal {
+    receiver: watch::Receiver<bool>,
+}
+
+impl ShutdownSignal {
+    /// Resolves when shutdown is triggered (immediately if it already was).
+    pub async fn wait(&mut self) {
+        if *self.receiver.borrow() {
+            return;
+        }
+        // The sender side lives as long as the controller; a closed channel
+        // also means shutdown.
+        let _ = self.receiver.wait_for(|v| *v).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn signal_resolves_after_trigger() {
+        let controller = ShutdownController::new(Duration::from_secs(5));
+        let mut signal = controller.signal();
+        assert!(!controller.is_shutting_down());
+        controller.trigger();
+        tokio::time::timeout(Duration::from_millis(100), signal.wait())
+            .await
+            .expect("signal should resolve after trigger");
+        assert!(controller.is_shutting_down());
+    }
+
+    #[tokio::test]
+    async fn signal_resolves_immediately_when_already_triggered() {
+        let controller = ShutdownController::new(Duration::from_secs(5));
+        controller.trigger();
+        let mut signal = controller.signal();
+        tokio::time::timeout(Duration::from_millis(10), signal.wait())
+            .await
+            .expect("already-triggered signal resolves at once");
+    }
+}
@@ -0,0 +1,541 @@
+//! Delivery processing with drain-aware ack/nack semantics.
+//!
+//! Processing and acknowledgement sit behind small traits so the shutdown
+//! behavior can be tested with a slow mock instead of a live broker.
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use crate::shutdown::ShutdownController;
+
+/// Message consumed from the vision queue: the same versioned struct the
+/// gateway publishes, so the two binaries cannot drift apart field by
+/// field. Decoding (and version rejection) happens in `main` via
+/// [`shared::queue::decode_job_request`] before a delivery reaches
+/// [`process_delivery`].
+pub type VisionJob = shared::queue::VisionJobRequestV1;
+
+/// Runs the actual inference pipeline for one job.
+#[async_trait]
+pub trait JobProcessor: Send + Sync {
+    async fn process(&self, job: &VisionJob) -> Result<(), String>;
+}
+
+/// Acknowledgement half of a delivery (implemented for lapin's `Acker`,
+/// mocked in tests).
+#[async_trait]
+pub trait DeliveryAck: Send + Sync {
+    async fn ack(&self);
+    /// Negative-acknowledge with requeue so another consumer retries.
+    async fn nack_requeue(&self);
+    /// Reject without requeue: the broker dead-letters the message into the
+    /// retry queue, which TTLs it back onto the main queue with an
+    /// incremented `x-death` count.
+    async fn nack_to_retry(&self);
+}
+
+#[async_trait]
+impl DeliveryAck for lapin::acker::Acker {
+    async fn ack(&self) {
+        if let Err(e) = self.ack(lapin::options::BasicAckOptions::default()).await {
+            tracing::error!(error = %e, "failed to ack delivery");
+        }
+    }
+
+    async fn nack_requeue(&self) {
+        let options = lapin::options::BasicNackOptions {
+            requeue: true,
+            ..Default::default()
+        };
+        if let Err(e) = self.nack(options).await {
+            tracing::error!(error = %e, "failed to nack delivery");
+        }
+    }
+
+    async fn nack_to_retry(&self) {
+        let options = lapin::options::BasicNackOptions {
+            requeue: false,
+            ..Default::default()
+        };
+        if let Err(e) = self.nack(options).await {
+            tracing::error!(error = %e, "failed to nack delivery");
+        }
+    }
+}
+
+/// Parks a message that exhausted its retries on the dead-letter queue
+/// (publishes to `vision_analysis_dlq` in production).
+#[async_trait]
+pub trait DeadLetterSink: Send + Sync {
+    /// `retries_so_far` is stamped onto the parked message so the
+    /// gateway's DLQ consumer can see the budget was spent.
+    async fn park(&self, job: &VisionJob, reason: &str, retries_so_far: u64);
+}
+
+/// Total times this message has been dead-lettered, from the
+/// broker-maintained `x-death` header. Zero on first delivery; each failed
+/// attempt cycles the message through the retry queue and bumps the count.
+pub fn death_count(headers: Option<&lapin::types::FieldTable>) -> u64 {
+    use lapin::types::AMQPValue;
+
+    let Some(headers) = headers else { return 0 };
+    let Some(AMQPValue::FieldArray(deaths)) = headers.inner().get("x-death") else {
+        return 0;
+    };
+    deaths
+        .as_slice()
+        .iter()
+        .filter_map(|entry| match entry {
+            AMQPValue::FieldTable(table) => match table.inner().get("count") {
+                Some(AMQPValue::LongLongInt(count)) => Some(*count as u64),
+                _ => None,
+            },
+            _ => None,
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+/// Records job timeline entries (Redis in production).
+#[async_trait]
+pub trait TimelineSink: Send + Sync {
+    async fn record(&self, job_id: Uuid, event: &str, reason: &str);
+}
+
+/// Answers "has this job been cancelled?" — backed by the Redis marker the
+/// gateway writes, mocked in tests.
+#[async_trait]
+pub trait CancelProbe: Send + Sync {
+    async fn is_cancelled(&self, job_id: Uuid) -> bool;
+}
+
+/// How often a running job checks for cancellation. Inference runs tens of
+/// seconds; a couple of seconds of wasted work after a cancel is fine.
+pub const CANCEL_CHECK_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Resolves once the job's cancellation marker appears; the checkpoint
+/// loop raced against the actual processing.
+async fn cancelled(probe: &dyn CancelProbe, job_id: Uuid) {
+    loop {
+        tokio::time::sleep(CANCEL_CHECK_INTERVAL).await;
+        if probe.is_cancelled(job_id).await {
+            return;
+        }
+    }
+}
+
+/// How a delivery was resolved; surfaced for metrics and tests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Acked,
+    /// Requeued because the drain deadline expired mid-job.
+    RequeuedForShutdown,
+    /// Sent through the retry queue because processing failed.
+    RequeuedAfterError,
+    /// Retries exhausted; parked on the dead-letter queue.
+    DeadLettered,
+    /// Discarded because the user cancelled the job; acked without a result.
+    Cancelled,
+}
+
+/// Process one delivery under the drain policy: if shutdown begins, the job
+/// gets until `drain_deadline` to finish; past that it is nacked (requeued)
+/// with a `shutdown` reason on its timeline. Unfinished work is never acked.
+///
+/// Failures are retried through the broker's retry queue until
+/// `retries_so_far` (the message's `x-death` count) reaches `max_retries`;
+/// after that the job is parked on the DLQ and acked so it stops cycling.
+///
+/// Cancelled jobs are acked and discarded: checked once before inference
+/// starts, then at [`CANCEL_CHECK_INTERVAL`] checkpoints while it runs.
+#[allow(clippy::too_many_arguments)]
+pub async fn process_delivery(
+    job: &VisionJob,
+    processor: &dyn JobProcessor,
+    ack: &dyn DeliveryAck,
+    timeline: &dyn TimelineSink,
+    dead_letters: &dyn DeadLetterSink,
+    cancel: &dyn CancelProbe,
+    shutdown: &ShutdownController,
+    retries_so_far: u64,
+    max_retries: u64,
+) -> Outcome {
+    // Cancelled while it sat in the queue: discard before spending any
+    // inference time on it.
+    if cancel.is_cancelled(job.job_id).await {
+        timeline.record(job.job_id, "cancelled", "cancelled before start").await;
+        ack.ack().await;
+        return Outcome::Cancelled;
+    }
+
+    let work = processor.process(job);
+
+    let result = if shutdown.is_shutting_down() {
+        // Already draining: the job only gets the remaining budget. The
+        // drain window is short, so no cancellation checkpoints here.
+        match tokio::time::timeout(shutdown.drain_deadline, work).await {
+            Ok(result) => Some(result),
+            Err(_) => None,
+        }
+    } else {
+        let mut signal = shutdown.signal();
+        tokio::select! {
+            result = work => Some(result),
+            // Shutdown arrived mid-job: grant the drain budget, then give up.
+            _ = async {
+                signal.wait().await;
+                tokio::time::sleep(shutdown.drain_deadline).await;
+            } => None,
+            // Cancelled mid-job: stop charging worker time to it.
+            _ = cancelled(cancel, job.job_id) => {
+                timeline
+                    .record(job.job_id, "cancelled", "cancelled mid-processing")
+                    .await;
+                ack.ack().await;
+                return Outcome::Cancelled;
+            }
+        }
+    };
+
+    match result {
+        Some(Ok(())) => {
+            ack.ack().await;
+            Outcome::Acked
+        }
+        Some(Err(error)) if retries_so_far >= max_retries => {
+            timeline.record(job.job_id, "dead_lettered", &error).await;
+            dead_letters.park(job, &error, retries_so_far).await;
+            // Acked only after the park: losing a message matters more than
+            // processing it twice.
+            ack.ack().await;
+            Outcome::DeadLettered
+        }
+        Some(Err(error)) => {
+            timeline.record(job.job_id, "requeued", &error).await;
+            ack.nack_to_retry().await;
+            Outcome::RequeuedAfterError
+        }
+        None => {
+            timeline.record(job.job_id, "requeued", "shutdown").await;
+            ack.nack_requeue().await;
+            Outcome::RequeuedForShutdown
+        }
+    }
+}
+
+/// Sleep-based drain helper used by `main` after the consume loop stops:
+/// waits for `in_flight` to drop to zero or the deadline to pass.
+pub async fn drain(in_flight: &std::sync::atomic::AtomicUsize, deadline: Duration) {
+    let start = tokio::time::Instant::now();
+    while in_flight.load(std::sync::atomic::Ordering::SeqCst) > 0 {
+        if start.elapsed() >= deadline {
+            tracing::warn!("drain deadline reached with jobs still in flight");
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    };
+
+    use super::*;
+
+    struct SlowProcessor {
+        duration: Duration,
+    }
+
+    #[async_trait]
+    impl JobProcessor for SlowProcessor {
+        async fn process(&self, _job: &VisionJob) -> Result<(), String> {
+            tokio::time::sleep(self.duration).await;
+            Ok(())
+        }
+    }
+
+    #[derive(Default)]
+    struct MockAck {
+        acks: AtomicUsize,
+        nacks: AtomicUsize,
+        retry_nacks: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl DeliveryAck for MockAck {
+        async fn ack(&self) {
+            self.acks.fetch_add(1, Ordering::SeqCst);
+        }
+        async fn nack_requeue(&self) {
+            self.nacks.fetch_add(1, Ordering::SeqCst);
+        }
+        async fn nack_to_retry(&self) {
+            self.retry_nacks.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[derive(Default)]
+    struct MockDlq {
+        parked: Mutex<Vec<(Uuid, String)>>,
+    }
+
+    #[async_trait]
+    impl DeadLetterSink for MockDlq {
+        async fn park(&self, job: &VisionJob, reason: &str, _retries_so_far: u64) {
+            self.parked.lock().unwrap().push((job.job_id, reason.into()));
+        }
+    }
+
+    #[derive(Default)]
+    struct MockTimeline {
+        entries: Mutex<Vec<(Uuid, String, String)>>,
+    }
+
+    #[async_trait]
+    impl TimelineSink for MockTimeline {
+        async fn record(&self, job_id: Uuid, event: &str, reason: &str) {
+            self.entries
+                .lock()
+                .unwrap()
+                .push((job_id, event.into(), reason.into()));
+        }
+    }
+
+    /// Probe for jobs nobody cancels.
+    struct NoCancel;
+
+    #[async_trait]
+    impl CancelProbe for NoCancel {
+        async fn is_cancelled(&self, _job_id: Uuid) -> bool {
+            false
+        }
+    }
+
+    /// Reports cancelled from the `after`-th check onwards, modelling a
+    /// cancel request that lands while the job is already running.
+    struct CancelAfter {
+        after: usize,
+        checks: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl CancelProbe for CancelAfter {
+        async fn is_cancelled(&self, _job_id: Uuid) -> bool {
+            self.checks.fetch_add(1, Ordering::SeqCst) >= self.after
+        }
+    }
+
+    fn job() -> VisionJob {
+        VisionJob {
+            schema_version: shared::queue::VISION_JOB_SCHEMA_VERSION,
+            job_id: Uuid::new_v4(),
+            crop_type: shared::models::CropType::Rice,
+            image_path: "/tmp/x.jpg".into(),
+            user_query: None,
+            queued_at: chrono::Utc::now(),
+            priority: shared::queue::JobPriority::default(),
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn sigterm_mid_job_nacks_after_drain_deadline() {
+        let shutdown = ShutdownController::new(Duration::from_millis(100));
+        let processor = SlowProcessor {
+            duration: Duration::from_secs(10),
+        };
+        let ack = MockAck::default();
+        let timeline = MockTimeline::default();
+        let dlq = MockDlq::default();
+        let job = job();
+
+        let controller = shutdown.clone();
+        let trigger = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            controller.trigger();
+        });
+
+        let outcome =
+            process_delivery(&job, &processor, &ack, &timeline, &dlq, &NoCancel, &shutdown, 0, 3)
+                .await;
+        trigger.await.unwrap();
+
+        assert_eq!(outcome, Outcome::RequeuedForShutdown);
+        assert_eq!(ack.acks.load(Ordering::SeqCst), 0, "unfinished work must not be acked");
+        assert_eq!(ack.nacks.load(Ordering::SeqCst), 1);
+        let entries = timeline.entries.lock().unwrap();
+        assert_eq!(entries[0].2, "shutdown");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn job_finishing_within_deadline_is_acked() {
+        let shutdown = ShutdownController::new(Duration::from_secs(5));
+        shutdown.trigger();
+        let processor = SlowProcessor {
+            duration: Duration::from_millis(50),
+        };
+        let ack = MockAck::default();
+        let timeline = MockTimeline::default();
+        let dlq = MockDlq::default();
+
+        let outcome =
+            process_delivery(&job(), &processor, &ack, &timeline, &dlq, &NoCancel, &shutdown, 0, 3)
+                .await;
+
+        assert_eq!(outcome, Outcome::Acked);
+        assert_eq!(ack.acks.load(Ordering::SeqCst), 1);
+        assert_eq!(ack.nacks.load(Ordering::SeqCst), 0);
+    }
+
+    struct FailingProcessor;
+
+    #[async_trait]
+    impl JobProcessor for FailingProcessor {
+        async fn process(&self, _job: &VisionJob) -> Result<(), String> {
+            Err("model exploded".into())
+        }
+    }
+
+    #[tokio::test]
+    async fn failed_job_goes_through_the_retry_queue_with_reason() {
+        let shutdown = ShutdownController::new(Duration::from_secs(5));
+        let ack = MockAck::default();
+        let timeline = MockTimeline::default();
+        let dlq = MockDlq::default();
+
+        let outcome =
+            process_delivery(
+            &job(),
+            &FailingProcessor,
+            &ack,
+            &timeline,
+            &dlq,
+            &NoCancel,
+            &shutdown,
+            0,
+            3,
+        )
+        .await;
+
+        assert_eq!(outcome, Outcome::RequeuedAfterError);
+        assert_eq!(ack.retry_nacks.load(Ordering::SeqCst), 1);
+        assert!(dlq.parked.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn exhausted_retries_park_the_job_on_the_dlq() {
+        let shutdown = ShutdownController::new(Duration::from_secs(5));
+        let ack = MockAck::default();
+        let timeline = MockTimeline::default();
+        let dlq = MockDlq::default();
+        let job = job();
+
+        let outcome =
+            process_delivery(&job, &FailingProcessor, &ack, &timeline, &dlq, &NoCancel, &shutdown, 3, 3)
+                .await;
+
+        assert_eq!(outcome, Outcome::DeadLettered);
+        // Parked and acked, so the message stops cycling.
+        assert_eq!(ack.acks.load(Ordering::SeqCst), 1);
+        assert_eq!(ack.retry_nacks.load(Ordering::SeqCst), 0);
+        let parked = dlq.parked.lock().unwrap();
+        assert_eq!(parked.len(), 1);
+        assert_eq!(parked[0].0, job.job_id);
+        let entries = timeline.entries.lock().unwrap();
+        assert_eq!(entries[0].1, "dead_lettered");
+    }
+
+    #[tokio::test]
+    async fn job_cancelled_before_start_is_acked_without_processing() {
+        let shutdown = ShutdownController::new(Duration::from_secs(5));
+        let ack = MockAck::default();
+        let timeline = MockTimeline::default();
+        let dlq = MockDlq::default();
+        let cancel = CancelAfter {
+            after: 0,
+            checks: AtomicUsize::new(0),
+        };
+        let job = job();
+
+        let outcome = process_delivery(
+            &job,
+            &FailingProcessor,
+            &ack,
+            &timeline,
+            &dlq,
+            &cancel,
+            &shutdown,
+            0,
+            3,
+        )
+        .await;
+
+        // FailingProcessor never ran: no retry nack, just a discard.
+        assert_eq!(outcome, Outcome::Cancelled);
+        assert_eq!(ack.acks.load(Ordering::SeqCst), 1);
+        assert_eq!(ack.retry_nacks.load(Ordering::SeqCst), 0);
+        let entries = timeline.entries.lock().unwrap();
+        assert_eq!(entries[0].2, "cancelled before start");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn cancellation_arriving_mid_processing_discards_the_job() {
+        let shutdown = ShutdownController::new(Duration::from_secs(5));
+        let processor = SlowProcessor {
+            duration: Duration::from_secs(60),
+        };
+        let ack = MockAck::default();
+        let timeline = MockTimeline::default();
+        let dlq = MockDlq::default();
+        // Passes the pre-start check, then reports cancelled at the second
+        // in-flight checkpoint — the cancel landed while inference ran.
+        let cancel = CancelAfter {
+            after: 3,
+            checks: AtomicUsize::new(0),
+        };
+        let job = job();
+
+        let outcome = process_delivery(
+            &job,
+            &processor,
+            &ack,
+            &timeline,
+            &dlq,
+            &cancel,
+            &shutdown,
+            0,
+            3,
+        )
+        .await;
+
+        assert_eq!(outcome, Outcome::Cancelled);
+        assert_eq!(ack.acks.load(Ordering::SeqCst), 1);
+        assert_eq!(ack.nacks.load(Ordering::SeqCst), 0);
+        assert_eq!(ack.retry_nacks.load(Ordering::SeqCst), 0);
+        assert!(dlq.parked.lock().unwrap().is_empty());
+        let entries = timeline.entries.lock().unwrap();
+        assert_eq!(entries[0].1, "cancelled");
+        assert_eq!(entries[0].2, "cancelled mid-processing");
+    }
+
+    #[test]
+    fn death_count_reads_the_broker_header() {
+        use lapin::types::{AMQPValue, FieldArray, FieldTable};
+
+        assert_eq!(death_count(None), 0);
+        assert_eq!(death_count(Some(&FieldTable::default())), 0);
+
+        let mut death = FieldTable::default();
+        death.insert("count".into(), AMQPValue::LongLongInt(2));
+        death.insert("queue".into(), AMQPValue::LongString("vision_analysis_queue".into()));
+        let mut deaths = FieldArray::default();
+        deaths.push(AMQPValue::FieldTable(death));
+        let mut headers = FieldTable::default();
+        headers.insert("x-death".into(), AMQPValue::FieldArray(deaths));
+        assert_eq!(death_count(Some(&headers)), 2);
+    }
+}
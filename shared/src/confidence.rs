@@ -0,0 +1,88 @@
+//! Confidence bands and calibrated guidance copy.
+//!
+//! Users read "62% confidence" as near-certain, so every surface that shows
+//! a confidence value (frontend result cards, gateway reports) pairs it with
+//! band-specific guidance. The bands and wording live here so the frontend
+//! and gateway can never drift apart.
+
+use crate::models::Language;
+
+/// Band boundaries: `< 0.50` is low, `0.50..=0.75` is medium, `> 0.75` is
+/// high.
+pub const LOW_BELOW: f32 = 0.50;
+pub const HIGH_ABOVE: f32 = 0.75;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfidenceBand {
+    Low,
+    Medium,
+    High,
+}
+
+pub fn band_for(confidence: f32) -> ConfidenceBand {
+    if confidence < LOW_BELOW {
+        ConfidenceBand::Low
+    } else if confidence <= HIGH_ABOVE {
+        ConfidenceBand::Medium
+    } else {
+        ConfidenceBand::High
+    }
+}
+
+/// Calibrated guidance for a band, in the requested language.
+pub fn guidance(band: ConfidenceBand, language: Language) -> &'static str {
+    match (band, language) {
+        (ConfidenceBand::Low, Language::Thai) => {
+            "ความมั่นใจต่ำ ควรถ่ายรูปเพิ่มในมุมอื่นหรือแสงที่ดีกว่า"
+        }
+        (ConfidenceBand::Low, Language::English) => {
+            "Low confidence — take more photos from other angles or in better light."
+        }
+        (ConfidenceBand::Medium, Language::Thai) => {
+            "น่าจะใช่ แต่ตรวจสอบอาการอื่นประกอบก่อนรักษา"
+        }
+        (ConfidenceBand::Medium, Language::English) => {
+            "Likely correct, but check for other symptoms before treating."
+        }
+        (ConfidenceBand::High, Language::Thai) => "มั่นใจสูง สามารถใช้คำแนะนำนี้ได้",
+        (ConfidenceBand::High, Language::English) => {
+            "High confidence — this advice can be followed."
+        }
+    }
+}
+
+/// What confidence means in general, shown in the explanation popover.
+pub fn explanation(language: Language) -> &'static str {
+    match language {
+        Language::Thai => {
+            "ค่าความมั่นใจคือการประเมินของโมเดล ไม่ใช่ความน่าจะเป็นที่วินิจฉัยถูกต้อง"
+        }
+        Language::English => {
+            "Confidence is the model's own estimate, not the probability the diagnosis is correct."
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn band_boundaries_are_inclusive_on_the_medium_side() {
+        assert_eq!(band_for(0.4999), ConfidenceBand::Low);
+        assert_eq!(band_for(0.50), ConfidenceBand::Medium);
+        assert_eq!(band_for(0.75), ConfidenceBand::Medium);
+        assert_eq!(band_for(0.7501), ConfidenceBand::High);
+    }
+
+    #[test]
+    fn every_band_has_copy_in_both_languages() {
+        for band in [ConfidenceBand::Low, ConfidenceBand::Medium, ConfidenceBand::High] {
+            for language in [Language::Thai, Language::English] {
+                assert!(!guidance(band, language).is_empty());
+            }
+        }
+        assert!(!explanation(Language::Thai).is_empty());
+        assert!(!explanation(Language::English).is_empty());
+    }
+}
@@ -0,0 +1,108 @@
+//! Canonical machine-readable error codes.
+//!
+//! Every error body the gateway emits carries one of these codes, so
+//! clients branch on the code instead of pattern-matching English prose.
+//! The wire form is SCREAMING_SNAKE_CASE and must stay stable: the
+//! frontend maps codes to Thai copy, and older clients already match on
+//! `CLIENT_OUTDATED`, `QUOTA_EXCEEDED`, `IMAGE_TOO_LARGE`, and
+//! `INVALID_ENCODING`.
+
+use serde::{Deserialize, Serialize};
+
+/// One code per gateway `AppError` variant. Adding a gateway variant
+/// without a code fails to compile — the mapping match is exhaustive on
+/// both sides.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    AuthFailed,
+    Forbidden,
+    ValidationFailed,
+    ImageTooLarge,
+    InvalidEncoding,
+    NotFound,
+    Conflict,
+    Unprocessable,
+    RateLimited,
+    QuotaExceeded,
+    ClientOutdated,
+    ServiceUnavailable,
+    ExternalApi,
+    Internal,
+}
+
+impl ErrorCode {
+    pub const ALL: &'static [ErrorCode] = &[
+        ErrorCode::AuthFailed,
+        ErrorCode::Forbidden,
+        ErrorCode::ValidationFailed,
+        ErrorCode::ImageTooLarge,
+        ErrorCode::InvalidEncoding,
+        ErrorCode::NotFound,
+        ErrorCode::Conflict,
+        ErrorCode::Unprocessable,
+        ErrorCode::RateLimited,
+        ErrorCode::QuotaExceeded,
+        ErrorCode::ClientOutdated,
+        ErrorCode::ServiceUnavailable,
+        ErrorCode::ExternalApi,
+        ErrorCode::Internal,
+    ];
+
+    /// The wire form, for callers building JSON by hand.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorCode::AuthFailed => "AUTH_FAILED",
+            ErrorCode::Forbidden => "FORBIDDEN",
+            ErrorCode::ValidationFailed => "VALIDATION_FAILED",
+            ErrorCode::ImageTooLarge => "IMAGE_TOO_LARGE",
+            ErrorCode::InvalidEncoding => "INVALID_ENCODING",
+            ErrorCode::NotFound => "NOT_FOUND",
+            ErrorCode::Conflict => "CONFLICT",
+            ErrorCode::Unprocessable => "UNPROCESSABLE",
+            ErrorCode::RateLimited => "RATE_LIMITED",
+            ErrorCode::QuotaExceeded => "QUOTA_EXCEEDED",
+            ErrorCode::ClientOutdated => "CLIENT_OUTDATED",
+            ErrorCode::ServiceUnavailable => "SERVICE_UNAVAILABLE",
+            ErrorCode::ExternalApi => "EXTERNAL_API",
+            ErrorCode::Internal => "INTERNAL",
+        }
+    }
+
+    /// Parse the wire form; unknown codes (from a newer gateway) are
+    /// `None` so old clients fall back to the prose message.
+    pub fn from_code(code: &str) -> Option<ErrorCode> {
+        ErrorCode::ALL.iter().copied().find(|c| c.as_str() == code)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wire_form_round_trips_through_as_str_and_from_code() {
+        for code in ErrorCode::ALL {
+            assert_eq!(ErrorCode::from_code(code.as_str()), Some(*code));
+        }
+        assert_eq!(ErrorCode::from_code("SOMETHING_NEW"), None);
+    }
+
+    #[test]
+    fn serde_matches_the_as_str_wire_form() {
+        for code in ErrorCode::ALL {
+            let json = serde_json::to_string(code).unwrap();
+            assert_eq!(json, format!("\"{}\"", code.as_str()));
+            assert_eq!(serde_json::from_str::<ErrorCode>(&json).unwrap(), *code);
+        }
+    }
+
+    #[test]
+    fn legacy_codes_clients_already_match_on_are_unchanged() {
+        assert_eq!(ErrorCode::ClientOutdated.as_str(), "CLIENT_OUTDATED");
+        assert_eq!(ErrorCode::QuotaExceeded.as_str(), "QUOTA_EXCEEDED");
+        assert_eq!(ErrorCode::ImageTooLarge.as_str(), "IMAGE_TOO_LARGE");
+        assert_eq!(ErrorCode::InvalidEncoding.as_str(), "INVALID_ENCODING");
+    }
+}
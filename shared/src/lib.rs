@@ -0,0 +1,8 @@
+//! Types shared between the API gateway, the queue worker, and the frontend.
+
+pub mod confidence;
+pub mod errors;
+pub mod models;
+pub mod preferences;
+pub mod queue;
+pub mod types;
@@ -0,0 +1,232 @@
+//! Core domain models.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+// `ToSchema` derives are gated behind the gateway-only `openapi` feature so
+// the wasm frontend never pulls in utoipa.
+
+/// Crops supported by the vision model.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum CropType {
+    Rice,
+    Cassava,
+    Durian,
+    Mango,
+    Rubber,
+    Corn,
+    Sugarcane,
+    Soybean,
+    Pineapple,
+}
+
+impl CropType {
+    pub const ALL: &'static [CropType] = &[
+        CropType::Rice,
+        CropType::Cassava,
+        CropType::Durian,
+        CropType::Mango,
+        CropType::Rubber,
+        CropType::Corn,
+        CropType::Sugarcane,
+        CropType::Soybean,
+        CropType::Pineapple,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CropType::Rice => "rice",
+            CropType::Cassava => "cassava",
+            CropType::Durian => "durian",
+            CropType::Mango => "mango",
+            CropType::Rubber => "rubber",
+            CropType::Corn => "corn",
+            CropType::Sugarcane => "sugarcane",
+            CropType::Soybean => "soybean",
+            CropType::Pineapple => "pineapple",
+        }
+    }
+}
+
+/// UI / advice language.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum Language {
+    #[default]
+    Thai,
+    English,
+}
+
+impl Language {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Language::Thai => "thai",
+            Language::English => "english",
+        }
+    }
+}
+
+/// Lifecycle of a queued vision analysis job.
+///
+/// Analysis runs in two stages: the vision model produces detections
+/// (`Diagnosed`), then treatment advice is generated asynchronously and the
+/// job moves to `Completed`. Clients get the diagnosis as soon as it exists
+/// instead of waiting out the LLM latency on top of it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Queued,
+    Processing,
+    /// Vision result available; advice generation still pending.
+    Diagnosed,
+    Completed,
+    Failed,
+    /// Cancelled by the user before a result was produced.
+    Cancelled,
+}
+
+impl JobStatus {
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            JobStatus::Completed | JobStatus::Failed | JobStatus::Cancelled
+        )
+    }
+
+    /// True once the vision result exists (advice may still be pending).
+    pub fn has_vision_result(&self) -> bool {
+        matches!(self, JobStatus::Diagnosed | JobStatus::Completed)
+    }
+}
+
+/// A single disease detection from the vision model.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct DiseaseDetection {
+    pub disease_name: String,
+    pub confidence: f32,
+    /// Normalized [x, y, width, height] in 0..1 image coordinates.
+    pub bounding_box: Option<[f32; 4]>,
+}
+
+/// How firmly a vision result should be presented.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum DiagnosisStatus {
+    /// Confidence cleared the gateway's threshold; shown as a diagnosis.
+    #[default]
+    Confirmed,
+    /// Top confidence fell below the threshold; shown as candidate
+    /// diseases with retake guidance instead of a firm diagnosis.
+    Uncertain,
+}
+
+/// Result of a vision analysis.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct VisionResponse {
+    pub job_id: Uuid,
+    pub crop_type: CropType,
+    pub detections: Vec<DiseaseDetection>,
+    pub severity_score: Option<f32>,
+    pub model_version: String,
+    pub processed_at: DateTime<Utc>,
+    /// Defaults to `Confirmed` for results produced before the gateway's
+    /// confidence threshold existed.
+    #[serde(default)]
+    pub diagnosis_status: DiagnosisStatus,
+}
+
+/// LLM-generated treatment advice.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct LLMResponse {
+    pub advice: String,
+    pub language: Language,
+    pub model: String,
+    pub generated_at: DateTime<Utc>,
+}
+
+/// One reading from a farmer's field sensor, as submitted to
+/// `POST /api/v1/sensors/readings`. `recorded_at` is the sensor's own
+/// clock — cheap devices buffer readings offline and upload in bursts, so
+/// it can lag arrival by hours.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SensorReading {
+    pub device_id: String,
+    pub crop_type: CropType,
+    pub temperature_c: f32,
+    pub soil_moisture_pct: f32,
+    pub humidity_pct: f32,
+    pub recorded_at: DateTime<Utc>,
+}
+
+/// A farmer's profile: who they are, where they farm, and what they grow.
+/// Pre-fills the profile form and will eventually feed location-aware
+/// advice. `user_id` and `created_at` are server-assigned on first save.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct FarmerProfile {
+    pub user_id: Uuid,
+    pub name: String,
+    pub province: Option<String>,
+    /// Farm area in rai (1 rai = 1,600 m²).
+    pub farm_size_rai: Option<f64>,
+    pub primary_crops: Vec<CropType>,
+    pub phone_number: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Who authored a chat message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum MessageRole {
+    User,
+    Assistant,
+    System,
+}
+
+/// One message in a conversation. `id` and `conversation_id` are assigned
+/// when the gateway persists the message; messages composed client-side
+/// carry `None` until the server echoes them back.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ChatMessage {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<Uuid>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub conversation_id: Option<Uuid>,
+    pub role: MessageRole,
+    pub content: String,
+    pub image_url: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn farmer_profile_round_trips_through_serde() {
+        let profile = FarmerProfile {
+            user_id: Uuid::nil(),
+            name: "สมชาย ใจดี".into(),
+            province: Some("Khon Kaen".into()),
+            farm_size_rai: Some(12.5),
+            primary_crops: vec![CropType::Rice, CropType::Cassava],
+            phone_number: None,
+            created_at: Utc::now(),
+        };
+        let json = serde_json::to_string(&profile).unwrap();
+        assert!(json.contains("\"rice\""), "crops use the snake_case wire form: {json}");
+        assert_eq!(serde_json::from_str::<FarmerProfile>(&json).unwrap(), profile);
+    }
+}
@@ -0,0 +1,114 @@
+//! Versioned schema for the synced UI preferences document.
+//!
+//! The gateway validates PATCHes against this schema and the frontend builds
+//! its settings store from the same definitions, so the two cannot drift.
+
+use serde_json::Value;
+
+/// Bump when adding keys; the gateway stores this alongside the document.
+pub const PREFERENCES_SCHEMA_VERSION: u32 = 1;
+
+/// Maximum serialized size of the stored document.
+pub const MAX_DOCUMENT_BYTES: usize = 8 * 1024;
+
+/// Allowed top-level keys. Anything else is rejected so typos don't grow
+/// unbounded junk that every device then syncs forever.
+pub const ALLOWED_KEYS: &[&str] = &[
+    "theme",
+    "reduced_data_mode",
+    "last_crop",
+    "notification_toggles",
+    "recent_queries",
+];
+
+/// Validate a preferences document (or patch): object-shaped, known keys
+/// only, under the size cap.
+pub fn validate_document(doc: &Value) -> Result<(), String> {
+    let object = doc
+        .as_object()
+        .ok_or_else(|| "preferences must be a JSON object".to_string())?;
+    for key in object.keys() {
+        if !ALLOWED_KEYS.contains(&key.as_str()) {
+            return Err(format!("unknown preference key '{key}'"));
+        }
+    }
+    let size = doc.to_string().len();
+    if size > MAX_DOCUMENT_BYTES {
+        return Err(format!(
+            "preferences document is {size} bytes, cap is {MAX_DOCUMENT_BYTES}"
+        ));
+    }
+    Ok(())
+}
+
+/// Merge a patch into the stored document: top-level keys from the patch
+/// replace stored values, `null` deletes a key. Last write wins — conflict
+/// resolution across devices uses the returned document plus `updated_at`.
+pub fn merge_patch(stored: &Value, patch: &Value) -> Value {
+    let mut result = stored
+        .as_object()
+        .cloned()
+        .unwrap_or_default();
+    if let Some(patch) = patch.as_object() {
+        for (key, value) in patch {
+            if value.is_null() {
+                result.remove(key);
+            } else {
+                result.insert(key.clone(), value.clone());
+            }
+        }
+    }
+    Value::Object(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn unknown_top_level_key_is_rejected() {
+        let err = validate_document(&json!({ "theme": "dark", "evil": 1 })).unwrap_err();
+        assert!(err.contains("evil"));
+    }
+
+    #[test]
+    fn known_keys_pass() {
+        validate_document(&json!({
+            "theme": "dark",
+            "reduced_data_mode": true,
+            "last_crop": "rice",
+        }))
+        .unwrap();
+    }
+
+    #[test]
+    fn non_object_documents_are_rejected() {
+        assert!(validate_document(&json!([1, 2, 3])).is_err());
+        assert!(validate_document(&json!("theme")).is_err());
+    }
+
+    #[test]
+    fn oversized_documents_are_rejected() {
+        let doc = json!({ "recent_queries": "x".repeat(MAX_DOCUMENT_BYTES) });
+        assert!(validate_document(&doc).unwrap_err().contains("cap"));
+    }
+
+    #[test]
+    fn merge_replaces_and_null_deletes() {
+        let stored = json!({ "theme": "light", "reduced_data_mode": true });
+        let patch = json!({ "theme": "dark", "reduced_data_mode": null, "last_crop": "rice" });
+        let merged = merge_patch(&stored, &patch);
+        assert_eq!(
+            merged,
+            json!({ "theme": "dark", "last_crop": "rice" })
+        );
+    }
+
+    #[test]
+    fn merge_into_empty_store_keeps_patch() {
+        let merged = merge_patch(&Value::Null, &json!({ "theme": "dark" }));
+        assert_eq!(merged, json!({ "theme": "dark" }));
+    }
+}
@@ -0,0 +1,264 @@
+//! Queue contracts shared between the gateway (publisher) and the worker
+//! (consumer).
+//!
+//! Vision jobs are published with a broker-level priority so an urgent
+//! outbreak photo does not wait behind a backlog of routine scans. The
+//! queue is declared with `x-max-priority`, and with the worker's prefetch
+//! of 1 the broker hands over the highest-priority ready message each time.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::models::{CropType, JobStatus, VisionResponse};
+
+/// `x-max-priority` of the vision queue. Kept small deliberately: RabbitMQ
+/// maintains one internal sub-queue per priority level, and three coarse
+/// tiers are all the product distinguishes.
+pub const MAX_PRIORITY: u8 = 9;
+
+/// Priority tier of a queued vision job. The wire form is the snake_case
+/// name, so adding a tier later does not renumber existing messages.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum JobPriority {
+    #[default]
+    Normal,
+    High,
+    Urgent,
+}
+
+impl JobPriority {
+    /// The AMQP priority value published with the message. Spread across
+    /// the declared range so a tier can be inserted without reshuffling.
+    pub fn amqp_value(self) -> u8 {
+        match self {
+            JobPriority::Normal => 0,
+            JobPriority::High => 5,
+            JobPriority::Urgent => MAX_PRIORITY,
+        }
+    }
+}
+
+/// Schema version this build publishes. Bumped only for breaking shape
+/// changes; additive optional fields stay within a version.
+pub const VISION_JOB_SCHEMA_VERSION: u32 = 1;
+
+fn current_schema_version() -> u32 {
+    VISION_JOB_SCHEMA_VERSION
+}
+
+/// A vision job as published by the gateway and consumed by the worker.
+/// Both sides build against this one struct, so a field added on one side
+/// cannot silently drift out of sync with the other — which is exactly
+/// what happened when the two binaries were deployed at different commits.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VisionJobRequestV1 {
+    /// Messages published before versioning existed decode as version 1.
+    #[serde(default = "current_schema_version")]
+    pub schema_version: u32,
+    pub job_id: Uuid,
+    pub crop_type: CropType,
+    pub image_path: String,
+    #[serde(default)]
+    pub user_query: Option<String>,
+    #[serde(default = "Utc::now")]
+    pub queued_at: DateTime<Utc>,
+    #[serde(default)]
+    pub priority: JobPriority,
+}
+
+/// Terminal outcome of a vision job as reported by the worker.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VisionJobResultV1 {
+    #[serde(default = "current_schema_version")]
+    pub schema_version: u32,
+    pub job_id: Uuid,
+    pub status: JobStatus,
+    #[serde(default)]
+    pub result: Option<VisionResponse>,
+    #[serde(default)]
+    pub error: Option<String>,
+    #[serde(default = "Utc::now")]
+    pub completed_at: DateTime<Utc>,
+}
+
+/// Why a queue payload could not be decoded. Consumers route either case to
+/// the dead-letter queue instead of dropping the message, so a version skew
+/// between deployments shows up in `failed_jobs` rather than as silent loss.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ContractError {
+    /// The payload declares a schema version this build does not speak.
+    UnsupportedVersion { found: u32, supported: u32 },
+    /// The payload is not valid JSON for the declared version.
+    Malformed(String),
+}
+
+impl std::fmt::Display for ContractError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ContractError::UnsupportedVersion { found, supported } => write!(
+                f,
+                "unsupported schema_version {found} (this build supports {supported})"
+            ),
+            ContractError::Malformed(reason) => write!(f, "malformed queue payload: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for ContractError {}
+
+/// Version-check the payload, then decode it. The version is inspected
+/// before the full decode so a message from a newer deployment is reported
+/// as a version mismatch, not as a confusing missing-field error.
+pub fn decode_job_request(body: &[u8]) -> Result<VisionJobRequestV1, ContractError> {
+    decode_versioned(body)
+}
+
+/// Decode a worker result, with the same version handling as
+/// [`decode_job_request`].
+pub fn decode_job_result(body: &[u8]) -> Result<VisionJobResultV1, ContractError> {
+    decode_versioned(body)
+}
+
+fn decode_versioned<T: serde::de::DeserializeOwned>(body: &[u8]) -> Result<T, ContractError> {
+    let value: serde_json::Value =
+        serde_json::from_slice(body).map_err(|e| ContractError::Malformed(e.to_string()))?;
+    let found = value
+        .get("schema_version")
+        .and_then(serde_json::Value::as_u64)
+        .unwrap_or(u64::from(VISION_JOB_SCHEMA_VERSION)) as u32;
+    if found != VISION_JOB_SCHEMA_VERSION {
+        return Err(ContractError::UnsupportedVersion {
+            found,
+            supported: VISION_JOB_SCHEMA_VERSION,
+        });
+    }
+    serde_json::from_value(value).map_err(|e| ContractError::Malformed(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn amqp_values_preserve_tier_order_within_the_declared_range() {
+        let tiers = [JobPriority::Normal, JobPriority::High, JobPriority::Urgent];
+        for pair in tiers.windows(2) {
+            assert!(pair[0] < pair[1]);
+            assert!(pair[0].amqp_value() < pair[1].amqp_value());
+        }
+        assert!(tiers.iter().all(|t| t.amqp_value() <= MAX_PRIORITY));
+    }
+
+    #[test]
+    fn broker_ordering_drains_urgent_before_normal() {
+        // With prefetch 1 the broker delivers the highest-priority ready
+        // message first; model that as a sort over the published values.
+        let mut published = vec![
+            JobPriority::Normal,
+            JobPriority::Urgent,
+            JobPriority::Normal,
+            JobPriority::High,
+        ];
+        published.sort_by_key(|p| std::cmp::Reverse(p.amqp_value()));
+        assert_eq!(
+            published,
+            vec![
+                JobPriority::Urgent,
+                JobPriority::High,
+                JobPriority::Normal,
+                JobPriority::Normal,
+            ]
+        );
+    }
+
+    fn request() -> VisionJobRequestV1 {
+        VisionJobRequestV1 {
+            schema_version: VISION_JOB_SCHEMA_VERSION,
+            job_id: Uuid::new_v4(),
+            crop_type: CropType::Rice,
+            image_path: "/data/uploads/x.jpg".into(),
+            user_query: Some("ใบเหลือง".into()),
+            queued_at: Utc::now(),
+            priority: JobPriority::High,
+        }
+    }
+
+    #[test]
+    fn job_request_round_trips() {
+        let job = request();
+        let decoded = decode_job_request(&serde_json::to_vec(&job).unwrap()).unwrap();
+        assert_eq!(decoded.job_id, job.job_id);
+        assert_eq!(decoded.crop_type, job.crop_type);
+        assert_eq!(decoded.user_query, job.user_query);
+        assert_eq!(decoded.priority, job.priority);
+    }
+
+    #[test]
+    fn job_result_round_trips() {
+        let result = VisionJobResultV1 {
+            schema_version: VISION_JOB_SCHEMA_VERSION,
+            job_id: Uuid::new_v4(),
+            status: JobStatus::Failed,
+            result: None,
+            error: Some("model exploded".into()),
+            completed_at: Utc::now(),
+        };
+        let decoded = decode_job_result(&serde_json::to_vec(&result).unwrap()).unwrap();
+        assert_eq!(decoded.job_id, result.job_id);
+        assert_eq!(decoded.status, JobStatus::Failed);
+        assert_eq!(decoded.error.as_deref(), Some("model exploded"));
+    }
+
+    #[test]
+    fn pre_versioning_messages_decode_as_version_one() {
+        let legacy = serde_json::json!({
+            "job_id": Uuid::new_v4(),
+            "crop_type": "rice",
+            "image_path": "/data/uploads/x.jpg",
+            "user_query": null,
+        });
+        let decoded = decode_job_request(&serde_json::to_vec(&legacy).unwrap()).unwrap();
+        assert_eq!(decoded.schema_version, VISION_JOB_SCHEMA_VERSION);
+        assert_eq!(decoded.priority, JobPriority::Normal);
+    }
+
+    #[test]
+    fn unknown_versions_are_rejected_as_such() {
+        let future = serde_json::json!({
+            "schema_version": 2,
+            "job_id": Uuid::new_v4(),
+            "crop_type": "rice",
+            "image_path": "/data/uploads/x.jpg",
+        });
+        // A version error, not a field error: the payload may well be valid
+        // for a schema this build has never heard of.
+        assert!(matches!(
+            decode_job_request(&serde_json::to_vec(&future).unwrap()),
+            Err(ContractError::UnsupportedVersion { found: 2, supported: 1 })
+        ));
+        assert!(matches!(
+            decode_job_request(b"{not json"),
+            Err(ContractError::Malformed(_))
+        ));
+    }
+
+    #[test]
+    fn missing_priority_field_deserializes_as_normal() {
+        // Messages published before the field existed must stay readable.
+        #[derive(Deserialize)]
+        struct Probe {
+            #[serde(default)]
+            priority: JobPriority,
+        }
+        let probe: Probe = serde_json::from_str("{}").unwrap();
+        assert_eq!(probe.priority, JobPriority::Normal);
+        assert_eq!(
+            serde_json::to_string(&JobPriority::Urgent).unwrap(),
+            "\"urgent\""
+        );
+    }
+}